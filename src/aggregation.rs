@@ -0,0 +1,98 @@
+use std::collections::BTreeMap;
+
+use crate::batch::{total_value, BatchTrade};
+use crate::error::McError;
+use crate::result::PricingResult;
+
+/// Level of the hierarchy trades are aggregated along
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GroupingLevel {
+    /// Book (desk) of the trade
+    Book,
+    /// Counterparty of the trade, i.e. its netting set
+    Counterparty,
+    /// Trading strategy of the trade
+    Strategy,
+}
+
+/// Total value of a group of trades on one node of an aggregation hierarchy
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GroupTotal {
+    /// Names of the groups from the top level down to the node, `None` for the trades
+    /// without a group on a level; empty for the total of all trades
+    pub path: Vec<Option<String>>,
+    /// Names of the trades in the group, in the order of the trades
+    pub trades: Vec<String>,
+    /// Total value of the trades, long and short positions netted, with the sum of their
+    /// standard errors; or the error of the first rejected trade
+    pub total: Result<PricingResult, McError>,
+}
+
+impl GroupTotal {
+    /// Returns the depth of the node: 0 for all trades, 1 for the groups of the top level
+    pub fn depth(&self) -> usize {
+        self.path.len()
+    }
+}
+
+/// Aggregates the values of trades along a hierarchy of grouping levels, e.g. books, then
+/// counterparties within each book
+///
+/// Every node of the hierarchy gets the total of its trades: the root all trades, the nodes
+/// of the first level the trades of each group on it, the nodes of the second level the
+/// trades of each group on it within their parent, and so on. The values of long and short
+/// positions net within each group, so the totals of counterparties are the values of their
+/// netting sets. Trades without a group on a level are aggregated in a node of their own,
+/// named `None`, apart from any group named by an empty string.
+///
+/// # Arguments
+/// * `trades` - Trades with their grouping metadata
+/// * `results` - Value of each trade in the order of the trades, e.g. of `price_batch`
+/// * `levels` - Levels of the hierarchy from the top down
+///
+/// # Returns
+/// The total of every node, the root first and each node followed by its children, with
+/// the groups of a level sorted by name after the trades without a group
+///
+/// # Errors
+/// Returns `McError::InvalidProduct` if there is not one result per trade.
+pub fn aggregate_results(
+    trades: &[BatchTrade],
+    results: &[Result<PricingResult, McError>],
+    levels: &[GroupingLevel],
+) -> Result<Vec<GroupTotal>, McError> {
+    if trades.len() != results.len() {
+        return Err(McError::InvalidProduct(format!(
+            "expected one result per trade, got {} results for {} trades",
+            results.len(),
+            trades.len()
+        )));
+    }
+    // Sorting the paths puts every node before its children
+    let mut groups = BTreeMap::from([(Vec::new(), Vec::new())]);
+    for (index, trade) in trades.iter().enumerate() {
+        let path: Vec<Option<String>> = levels
+            .iter()
+            .map(|&level| trade.group(level).map(str::to_string))
+            .collect();
+        for depth in 0..=path.len() {
+            groups
+                .entry(path[..depth].to_vec())
+                .or_default()
+                .push(index);
+        }
+    }
+    Ok(groups
+        .into_iter()
+        .map(|(path, indices)| {
+            let group_results: Vec<_> = indices.iter().map(|&i| results[i].clone()).collect();
+            GroupTotal {
+                path,
+                trades: indices.iter().map(|&i| trades[i].name.clone()).collect(),
+                total: total_value(&group_results),
+            }
+        })
+        .collect())
+}
@@ -0,0 +1,1162 @@
+use nalgebra::{DMatrix, DVector};
+
+use crate::bounds;
+use crate::closed_form;
+use crate::config::SimulationConfig;
+use crate::engine::{self, PathEngine, PathState, ShockGenerator};
+use crate::error::McError;
+use crate::math;
+use crate::payoff::{OptionType, Payoff};
+use crate::{intrinsic_value, with_effective_volatility, with_start_values};
+use crate::rates::RateCurve;
+use crate::result::PricingResult;
+use crate::statistics::{ChunkedStatistics, SampleStatistics};
+use crate::underlying::Underlying;
+use crate::validation;
+
+/// Offset of the seed of the independent paths valuing the fitted exercise policy out of
+/// sample
+const OUT_OF_SAMPLE_SEED_OFFSET: u64 = 0x5851_f42d_4c95_7f2d;
+
+/// Offsets of the seeds of the outer and inner paths of the dual upper bound
+const DUAL_OUTER_SEED_OFFSET: u64 = 0x2545_f491_4f6c_dd1d;
+const DUAL_INNER_SEED_OFFSET: u64 = 0x9fb2_1c65_1e98_df25;
+
+/// Family of functions of each spot-normalized price `x` in a regression basis
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BasisFamily {
+    /// Powers `x`, `x²`, ..., `x^order`
+    #[default]
+    Polynomial,
+    /// Weighted Laguerre polynomials `e^(-x/2) L_n(x)` for `n` in `0..order`, as in
+    /// Longstaff and Schwartz (2001)
+    Laguerre,
+}
+
+/// Basis functions of the Longstaff-Schwartz regression of the continuation value on the
+/// prices at an exercise date
+///
+/// The basis holds a constant, `order` functions of the spot-normalized price of each
+/// underlying, and optionally the powers 1..=`order` of the exercise value (normalized by the
+/// initial price of the first underlying) and the products of the normalized prices of each
+/// pair of underlyings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RegressionBasis {
+    /// Functions of each normalized price
+    pub family: BasisFamily,
+    /// Number of functions of each normalized price (and of the exercise value)
+    pub order: usize,
+    /// `true` to add the powers of the exercise value, a payoff-specific basis
+    pub exercise_value: bool,
+    /// `true` to add the products of the normalized prices of each pair of underlyings
+    pub cross_terms: bool,
+}
+
+impl RegressionBasis {
+    /// Creates a basis of the powers 1..=`order` of each normalized price, the basis of
+    /// `price_american`
+    pub fn polynomial(order: usize) -> Self {
+        Self {
+            family: BasisFamily::Polynomial,
+            order,
+            exercise_value: false,
+            cross_terms: false,
+        }
+    }
+
+    /// Creates a basis of the first `order` weighted Laguerre polynomials of each normalized
+    /// price
+    pub fn laguerre(order: usize) -> Self {
+        Self {
+            family: BasisFamily::Laguerre,
+            ..Self::polynomial(order)
+        }
+    }
+
+    /// Adds the powers 1..=`order` of the exercise value to the basis
+    pub fn with_exercise_value(mut self) -> Self {
+        self.exercise_value = true;
+        self
+    }
+
+    /// Adds the products of the normalized prices of each pair of underlyings to the basis
+    pub fn with_cross_terms(mut self) -> Self {
+        self.cross_terms = true;
+        self
+    }
+
+    /// Returns the number of basis functions for the given number of underlyings
+    pub fn num_functions(&self, num_underlyings: usize) -> usize {
+        let exercise_value_terms = if self.exercise_value { self.order } else { 0 };
+        let cross_terms = if self.cross_terms {
+            num_underlyings * num_underlyings.saturating_sub(1) / 2
+        } else {
+            0
+        };
+        1 + num_underlyings * self.order + exercise_value_terms + cross_terms
+    }
+
+    /// Appends the values of the basis functions on the prices of a path to `values`
+    fn evaluate(&self, prices: &[f64], initial_prices: &[f64], exercise_value: f64, values: &mut Vec<f64>) {
+        values.push(1.0);
+        for (price, initial_price) in prices.iter().zip(initial_prices) {
+            let x = price / initial_price;
+            match self.family {
+                BasisFamily::Polynomial => {
+                    values.extend((1..=self.order).map(|power| math::powi(x, power as i32)))
+                }
+                BasisFamily::Laguerre => {
+                    // L_0 = 1, L_1 = 1 - x, (n + 1) L_(n+1) = (2n + 1 - x) L_n - n L_(n-1)
+                    let weight = math::exp(-0.5 * x);
+                    let (mut previous, mut current) = (0.0, 1.0);
+                    for n in 0..self.order {
+                        values.push(weight * current);
+                        let n = n as f64;
+                        let next = ((2.0 * n + 1.0 - x) * current - n * previous) / (n + 1.0);
+                        (previous, current) = (current, next);
+                    }
+                }
+            }
+        }
+        if self.exercise_value {
+            let x = exercise_value / initial_prices[0];
+            values.extend((1..=self.order).map(|power| math::powi(x, power as i32)));
+        }
+        if self.cross_terms {
+            for i in 0..prices.len() {
+                for j in i + 1..prices.len() {
+                    values.push(prices[i] / initial_prices[i] * prices[j] / initial_prices[j]);
+                }
+            }
+        }
+    }
+}
+
+/// Fit of the continuation value regression on one exercise date
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RegressionFit {
+    /// Exercise date, counted from today
+    pub day: u32,
+    /// Number of in-the-money paths in the regression
+    pub num_paths: usize,
+    /// Coefficient of determination of the regression: the share of the variance of the
+    /// discounted future cashflows explained by the basis (1 if they do not vary)
+    pub r_squared: f64,
+}
+
+/// Result of pricing an American or Bermudan option with regression diagnostics
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AmericanResult {
+    /// Estimated price of the option
+    pub pricing: PricingResult,
+    /// Fit of the regression on each early exercise date with in-the-money paths, in
+    /// ascending order of the dates
+    pub regressions: Vec<RegressionFit>,
+    /// Mean discounted cashflow on the paths the exercise policy is fitted on, without
+    /// control variate
+    pub in_sample_value: f64,
+    /// Mean discounted cashflow of the fitted exercise policy on as many independent paths
+    pub out_of_sample_value: f64,
+    /// Standard error of the out-of-sample value
+    pub out_of_sample_std_error: f64,
+    /// Upper bound on the price from the duality, if estimated
+    pub upper_bound: Option<DualBound>,
+    /// Exercise policy fitted by the regressions, to price again under bumped markets
+    /// (see `price_american_with_policy`)
+    pub exercise_policy: ExercisePolicy,
+}
+
+impl AmericanResult {
+    /// Returns the in-sample value less the out-of-sample value
+    ///
+    /// The in-sample value is biased high by fitting the exercise policy to the paths it is
+    /// valued on, the out-of-sample value low by the suboptimal policy, so a gap well beyond
+    /// the standard errors points to a poor fit of the continuation value.
+    pub fn value_gap(&self) -> f64 {
+        self.in_sample_value - self.out_of_sample_value
+    }
+
+    /// Returns the upper bound less the out-of-sample value, the low-biased estimate of the
+    /// price, if the upper bound is estimated
+    ///
+    /// The true price lies in between up to the standard errors, so a small gap confirms the
+    /// exercise policy is close to optimal.
+    pub fn duality_gap(&self) -> Option<f64> {
+        self.upper_bound
+            .map(|upper_bound| upper_bound.value - self.out_of_sample_value)
+    }
+}
+
+/// Number of paths of the nested simulation of the dual upper bound
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DualSettings {
+    /// Number of outer paths, on which the upper bound is averaged
+    pub outer_paths: u64,
+    /// Number of inner paths estimating the continuation value of the exercise policy on
+    /// each exercise date of an outer path
+    pub inner_paths: u64,
+}
+
+impl DualSettings {
+    /// Creates the settings of a nested simulation with the given numbers of outer and inner
+    /// paths
+    pub fn new(outer_paths: u64, inner_paths: u64) -> Self {
+        Self {
+            outer_paths,
+            inner_paths,
+        }
+    }
+}
+
+/// Upper bound on the price of an American or Bermudan option from the duality (Andersen and
+/// Broadie, 2004)
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DualBound {
+    /// Estimated upper bound
+    pub value: f64,
+    /// Standard error of the upper bound over the outer paths
+    pub std_error: f64,
+    /// Settings of the nested simulation
+    pub settings: DualSettings,
+}
+
+/// Prices an American or Bermudan option (Call or Put) using the Longstaff-Schwartz
+/// least-squares Monte Carlo method.
+///
+/// Paths are simulated with daily steps. At every exercise date (processed backwards from
+/// expiry), the continuation value of in-the-money paths is estimated by regressing the
+/// discounted future cashflows on polynomial basis functions of the simulated prices.
+/// The option is exercised where the intrinsic value exceeds the estimated continuation value.
+///
+/// # Arguments
+/// * `underlyings` - List of underlying assets
+/// * `correlation_matrix` - Correlation matrix (n x n) where n is the number of underlyings.
+///   Must be symmetric, positive semi-definite, with 1.0 on the diagonal.
+/// * `time_horizon_days` - Time to expiration in days
+/// * `strike_price` - Strike price of the option
+/// * `option_type` - Call or Put option
+/// * `risk_free_rate` - Risk-free rate curve or flat annual rate (as a decimal, e.g., 0.05 for 5%)
+/// * `exercise_dates` - Days (from today, at most `time_horizon_days`) on which early exercise
+///   is allowed. Expiry is always an exercise date. Pass every day for an American option.
+/// * `basis_order` - Highest power of the (spot-normalized) prices used as regression basis
+/// * `config` - Number of paths and variance reduction settings. The control variate uses the
+///   European option with the same strike and expiry.
+///
+/// # Returns
+/// The estimated option price together with its standard error
+///
+/// # Errors
+/// Returns an error if an exercise date is zero or beyond `time_horizon_days`, if
+/// `basis_order` is zero, if the paths do not fit into memory, or for invalid inputs (see
+/// `price_option`).
+#[allow(clippy::too_many_arguments)]
+pub fn price_american(
+    underlyings: &[Underlying],
+    correlation_matrix: &DMatrix<f64>,
+    time_horizon_days: u32,
+    strike_price: f64,
+    option_type: OptionType,
+    risk_free_rate: impl Into<RateCurve>,
+    exercise_dates: &[u32],
+    basis_order: usize,
+    config: &SimulationConfig,
+) -> Result<PricingResult, McError> {
+    longstaff_schwartz(
+        underlyings,
+        correlation_matrix,
+        time_horizon_days,
+        strike_price,
+        option_type,
+        risk_free_rate.into(),
+        exercise_dates,
+        &RegressionBasis::polynomial(basis_order),
+        false,
+        None,
+        config,
+    )
+    .map(|result| result.pricing)
+}
+
+/// Prices an American or Bermudan option (Call or Put) like `price_american`, regressing on
+/// the given basis and reporting diagnostics of the fit
+///
+/// Besides the fit of each regression, the exercise policy fitted on the simulated paths is
+/// valued on as many independent paths, so the price costs about twice the simulation of
+/// `price_american`.
+///
+/// # Arguments
+/// * `underlyings` - List of underlying assets
+/// * `correlation_matrix` - Correlation matrix (n x n) where n is the number of underlyings.
+///   Must be symmetric, positive semi-definite, with 1.0 on the diagonal.
+/// * `time_horizon_days` - Time to expiration in days
+/// * `strike_price` - Strike price of the option
+/// * `option_type` - Call or Put option
+/// * `risk_free_rate` - Risk-free rate curve or flat annual rate (as a decimal, e.g., 0.05 for 5%)
+/// * `exercise_dates` - Days (from today, at most `time_horizon_days`) on which early exercise
+///   is allowed. Expiry is always an exercise date.
+/// * `basis` - Basis functions of the regression
+/// * `config` - Number of paths and variance reduction settings (see `price_american`)
+///
+/// # Returns
+/// The estimated option price with the diagnostics of the regressions
+///
+/// # Errors
+/// Returns an error under the same conditions as `price_american`, with the order of the
+/// basis in place of `basis_order`.
+#[allow(clippy::too_many_arguments)]
+pub fn price_american_with_basis(
+    underlyings: &[Underlying],
+    correlation_matrix: &DMatrix<f64>,
+    time_horizon_days: u32,
+    strike_price: f64,
+    option_type: OptionType,
+    risk_free_rate: impl Into<RateCurve>,
+    exercise_dates: &[u32],
+    basis: &RegressionBasis,
+    config: &SimulationConfig,
+) -> Result<AmericanResult, McError> {
+    longstaff_schwartz(
+        underlyings,
+        correlation_matrix,
+        time_horizon_days,
+        strike_price,
+        option_type,
+        risk_free_rate.into(),
+        exercise_dates,
+        basis,
+        true,
+        None,
+        config,
+    )
+}
+
+/// Prices an American or Bermudan option (Call or Put) like `price_american_with_basis`,
+/// bracketing the price between the out-of-sample value of the fitted exercise policy and an
+/// upper bound from the duality
+///
+/// The upper bound (Andersen and Broadie, 2004) is the mean over the outer paths of the
+/// highest discounted exercise value less a martingale built from the exercise policy. The
+/// martingale needs the continuation value of the policy on every exercise date of every
+/// outer path, estimated by inner paths simulated from there, so the simulation costs about
+/// `outer_paths * inner_paths` paths per exercise date.
+///
+/// # Arguments
+/// * `underlyings` - List of underlying assets
+/// * `correlation_matrix` - Correlation matrix (n x n) where n is the number of underlyings.
+///   Must be symmetric, positive semi-definite, with 1.0 on the diagonal.
+/// * `time_horizon_days` - Time to expiration in days
+/// * `strike_price` - Strike price of the option
+/// * `option_type` - Call or Put option
+/// * `risk_free_rate` - Risk-free rate curve or flat annual rate (as a decimal, e.g., 0.05 for 5%)
+/// * `exercise_dates` - Days (from today, at most `time_horizon_days`) on which early exercise
+///   is allowed. Expiry is always an exercise date.
+/// * `basis` - Basis functions of the regression
+/// * `dual` - Numbers of outer and inner paths of the upper bound
+/// * `config` - Number of paths and variance reduction settings (see `price_american`). The
+///   outer and inner paths are not antithetic.
+///
+/// # Returns
+/// The estimated option price with the diagnostics of the regressions and the upper bound
+///
+/// # Errors
+/// Returns `McError::InvalidPaths` if there are no outer or no inner paths, and an error
+/// under the same conditions as `price_american_with_basis`.
+#[allow(clippy::too_many_arguments)]
+pub fn price_american_with_upper_bound(
+    underlyings: &[Underlying],
+    correlation_matrix: &DMatrix<f64>,
+    time_horizon_days: u32,
+    strike_price: f64,
+    option_type: OptionType,
+    risk_free_rate: impl Into<RateCurve>,
+    exercise_dates: &[u32],
+    basis: &RegressionBasis,
+    dual: &DualSettings,
+    config: &SimulationConfig,
+) -> Result<AmericanResult, McError> {
+    if dual.outer_paths == 0 || dual.inner_paths == 0 {
+        return Err(McError::InvalidPaths(0));
+    }
+    longstaff_schwartz(
+        underlyings,
+        correlation_matrix,
+        time_horizon_days,
+        strike_price,
+        option_type,
+        risk_free_rate.into(),
+        exercise_dates,
+        basis,
+        true,
+        Some(dual),
+        config,
+    )
+}
+
+/// Prices an American or Bermudan option (Call or Put) with a given exercise policy, e.g. the
+/// one fitted by `price_american_with_basis` on the unbumped market (policy freezing)
+///
+/// The paths are simulated like in `price_american`; each pays its exercise value on the
+/// first early exercise day the policy exercises, its intrinsic value at expiry otherwise.
+/// Without regressions on the paths, the price is low-biased for any policy, and with a
+/// seed, it moves smoothly with bumps of the market, which makes finite-difference Greeks
+/// far less noisy and cheaper than fitting the policy again.
+///
+/// # Arguments
+/// * `underlyings` - List of underlying assets
+/// * `correlation_matrix` - Correlation matrix (n x n) where n is the number of underlyings.
+///   Must be symmetric, positive semi-definite, with 1.0 on the diagonal.
+/// * `time_horizon_days` - Time to expiration in days
+/// * `strike_price` - Strike price of the option
+/// * `option_type` - Call or Put option
+/// * `risk_free_rate` - Risk-free rate curve or flat annual rate (as a decimal, e.g., 0.05 for 5%)
+/// * `exercise_policy` - Exercise policy, whose exercise days are the early exercise dates
+/// * `config` - Number of paths and variance reduction settings (see `price_american`)
+///
+/// # Returns
+/// The estimated option price together with its standard error
+///
+/// # Errors
+/// Returns `McError::InvalidExercisePolicy` if the policy does not fit the underlyings or
+/// has exercise days outside of the life of the option, an error if the paths do not fit
+/// into memory, or for invalid inputs (see `price_option`).
+#[allow(clippy::too_many_arguments)]
+pub fn price_american_with_policy(
+    underlyings: &[Underlying],
+    correlation_matrix: &DMatrix<f64>,
+    time_horizon_days: u32,
+    strike_price: f64,
+    option_type: OptionType,
+    risk_free_rate: impl Into<RateCurve>,
+    exercise_policy: &ExercisePolicy,
+    config: &SimulationConfig,
+) -> Result<PricingResult, McError> {
+    validation::validate_strike(strike_price)?;
+    exercise_policy.validate(underlyings.len(), time_horizon_days)?;
+    let underlyings = &*with_start_values(underlyings, config)?;
+
+    let rate_curve = risk_free_rate.into();
+    let time_to_expiration = config.day_count.year_fraction(time_horizon_days);
+    let engine = PathEngine::new(
+        underlyings,
+        correlation_matrix,
+        &rate_curve,
+        time_to_expiration,
+        time_horizon_days as usize, // Daily steps
+        config,
+    )?;
+    let num_samples = usize::try_from(
+        config
+            .num_paths
+            .div_ceil(engine::shock_signs(config.antithetic).len() as u64),
+    )
+    .map_err(|_| McError::InvalidPaths(config.num_paths))?;
+    let discount_factors: Vec<f64> = (0..=time_horizon_days)
+        .map(|day| rate_curve.discount_factor(config.day_count.year_fraction(day)))
+        .collect();
+    let (statistics, non_finite_paths) = value_policy(
+        &engine,
+        &mut engine.shock_generator(config),
+        num_samples,
+        exercise_policy,
+        strike_price,
+        option_type,
+        &discount_factors,
+        config,
+    )?;
+    let num_paths = num_samples as u64 * engine::shock_signs(config.antithetic).len() as u64;
+    Ok(american_result(
+        &statistics,
+        num_paths,
+        non_finite_paths,
+        &engine,
+        &underlyings[0],
+        strike_price,
+        option_type,
+        &rate_curve,
+        time_horizon_days,
+        config,
+    ))
+}
+
+/// Prices an American or Bermudan option with the Longstaff-Schwartz method, valuing the
+/// fitted exercise policy out of sample if requested (NaN otherwise) and estimating the dual
+/// upper bound if settings are given
+#[allow(clippy::too_many_arguments)]
+fn longstaff_schwartz(
+    underlyings: &[Underlying],
+    correlation_matrix: &DMatrix<f64>,
+    time_horizon_days: u32,
+    strike_price: f64,
+    option_type: OptionType,
+    rate_curve: RateCurve,
+    exercise_dates: &[u32],
+    basis: &RegressionBasis,
+    is_out_of_sample: bool,
+    dual: Option<&DualSettings>,
+    config: &SimulationConfig,
+) -> Result<AmericanResult, McError> {
+    if basis.order == 0 {
+        return Err(McError::InvalidBasisOrder);
+    }
+    if !exercise_dates
+        .iter()
+        .all(|&day| day > 0 && day <= time_horizon_days)
+    {
+        return Err(McError::InvalidSchedule(format!(
+            "Exercise dates must be between day 1 and day {}",
+            time_horizon_days
+        )));
+    }
+    validation::validate_strike(strike_price)?;
+
+    // Early exercise dates in ascending order, expiry is handled separately
+    let mut early_exercise_days: Vec<u32> = exercise_dates
+        .iter()
+        .copied()
+        .filter(|&day| day < time_horizon_days)
+        .collect();
+    early_exercise_days.sort_unstable();
+    early_exercise_days.dedup();
+
+    let underlyings = &*with_start_values(underlyings, config)?;
+
+    let discount = |day: u32| rate_curve.discount_factor(config.day_count.year_fraction(day));
+    let time_to_expiration = config.day_count.year_fraction(time_horizon_days);
+    let engine = PathEngine::new(
+        underlyings,
+        correlation_matrix,
+        &rate_curve,
+        time_to_expiration,
+        time_horizon_days as usize, // Daily steps
+        config,
+    )?;
+
+    let shock_signs = engine::shock_signs(config.antithetic);
+    // The regression needs all paths at once, so they must fit into memory
+    let num_samples = usize::try_from(config.num_paths.div_ceil(shock_signs.len() as u64))
+        .map_err(|_| McError::InvalidPaths(config.num_paths))?;
+    let num_paths = num_samples * shock_signs.len();
+
+    let (observed_prices, final_prices) = simulate_exercise_prices(
+        &engine,
+        &mut engine.shock_generator(config),
+        shock_signs,
+        num_samples,
+        &early_exercise_days,
+    );
+
+    // Cashflow per path and the day it is paid, initialized with exercise at expiry
+    let mut cashflows: Vec<f64> = final_prices
+        .iter()
+        .map(|&price| intrinsic_value(price, strike_price, option_type))
+        .collect();
+    let mut cashflow_days: Vec<u32> = vec![time_horizon_days; num_paths];
+
+    // Backward induction over the early exercise dates, keeping the regression coefficients
+    // of each date for the out-of-sample valuation
+    let num_functions = basis.num_functions(underlyings.len());
+    let mut exercise_policy = ExercisePolicy {
+        basis: *basis,
+        reference_prices: engine.initial_prices.clone(),
+        exercise_days: early_exercise_days.clone(),
+        coefficients: vec![None; early_exercise_days.len()],
+    };
+    let mut regressions = Vec::new();
+    let mut basis_values = Vec::new();
+    for (date_index, &day) in early_exercise_days.iter().enumerate().rev() {
+        let prices = &observed_prices[date_index];
+        let exercise_values: Vec<f64> = prices
+            .iter()
+            .map(|p| intrinsic_value(p[0], strike_price, option_type))
+            .collect();
+
+        // Only in-the-money paths enter the regression
+        let in_the_money: Vec<usize> = (0..num_paths)
+            .filter(|&path| exercise_values[path] > 0.0)
+            .collect();
+        if in_the_money.is_empty() {
+            continue;
+        }
+
+        basis_values.clear();
+        for &path in &in_the_money {
+            basis.evaluate(&prices[path], &engine.initial_prices, exercise_values[path], &mut basis_values);
+        }
+        let basis_matrix = DMatrix::from_row_slice(in_the_money.len(), num_functions, &basis_values);
+        let discounted_cashflows = DVector::from_iterator(
+            in_the_money.len(),
+            in_the_money.iter().map(|&path| {
+                cashflows[path] * discount(cashflow_days[path]) / discount(day)
+            }),
+        );
+        let coefficients = match basis_matrix
+            .clone()
+            .svd(true, true)
+            .solve(&discounted_cashflows, 1e-12)
+        {
+            Ok(coefficients) => coefficients,
+            Err(_) => continue,
+        };
+        let continuation_values = basis_matrix * &coefficients;
+        regressions.push(RegressionFit {
+            day,
+            num_paths: in_the_money.len(),
+            r_squared: r_squared(&discounted_cashflows, &continuation_values),
+        });
+
+        for (row, &path) in in_the_money.iter().enumerate() {
+            if exercise_values[path] > continuation_values[row] {
+                cashflows[path] = exercise_values[path];
+                cashflow_days[path] = day;
+            }
+        }
+        exercise_policy.coefficients[date_index] = Some(coefficients.as_slice().to_vec());
+    }
+    regressions.reverse();
+
+    // Discount every cashflow from its payment day and average antithetic pairs into samples,
+    // together with the discounted European payoff on the same paths as control
+    let discount_factor = rate_curve.discount_factor(time_to_expiration);
+    let policy = config.non_finite_policy;
+    let mut statistics = ChunkedStatistics::default();
+    let mut non_finite_paths = 0;
+    for sample in 0..num_samples {
+        let mut sample_sum = 0.0;
+        let mut control_sum = 0.0;
+        let mut is_dropped = false;
+        for path in sample * shock_signs.len()..(sample + 1) * shock_signs.len() {
+            let value = cashflows[path] * discount(cashflow_days[path]);
+            let control =
+                intrinsic_value(final_prices[path], strike_price, option_type) * discount_factor;
+            // A non-finite terminal price stands in for the payoffs, which would clip it
+            let (value, control) = match final_prices[path] {
+                price if !price.is_finite() => (price, price),
+                _ => (value, control),
+            };
+
+            // Non-finite values from extreme parameters are treated according to the policy
+            if !value.is_finite() || !control.is_finite() {
+                non_finite_paths += 1;
+            }
+            match (policy.apply(value)?, policy.apply(control)?) {
+                (Some(value), Some(control)) => {
+                    sample_sum += value;
+                    control_sum += control;
+                }
+                _ => is_dropped = true,
+            }
+        }
+        if !is_dropped {
+            let path_count = shock_signs.len() as f64;
+            statistics.add(sample_sum / path_count, control_sum / path_count);
+        }
+    }
+    let statistics = statistics.finish();
+    let in_sample_value = PricingResult::from_statistics(&statistics, num_paths as u64, None).price;
+
+    // The fitted exercise policy on independent paths: exercise on the first date where the
+    // exercise value exceeds the fitted continuation value
+    let discount_factors: Vec<f64> = (0..=time_horizon_days).map(discount).collect();
+    let (out_of_sample_value, out_of_sample_std_error) = if is_out_of_sample {
+        let out_of_sample_config = SimulationConfig {
+            seed: config.seed.map(|seed| seed.wrapping_add(OUT_OF_SAMPLE_SEED_OFFSET)),
+            ..config.clone()
+        };
+        let (statistics, _) = value_policy(
+            &engine,
+            &mut engine.shock_generator(&out_of_sample_config),
+            num_samples,
+            &exercise_policy,
+            strike_price,
+            option_type,
+            &discount_factors,
+            config,
+        )?;
+        let out_of_sample = PricingResult::from_statistics(&statistics, num_paths as u64, None);
+        (out_of_sample.price, out_of_sample.std_error)
+    } else {
+        (f64::NAN, f64::NAN)
+    };
+
+    let upper_bound = dual.map(|settings| {
+        let dual_simulation = DualSimulation {
+            engine: &engine,
+            exercise_policy: &exercise_policy,
+            early_exercise_days: &early_exercise_days,
+            strike_price,
+            option_type,
+            discount_factors: &discount_factors,
+        };
+        dual_simulation.upper_bound(settings, config)
+    });
+
+    let result = american_result(
+        &statistics,
+        num_paths as u64,
+        non_finite_paths,
+        &engine,
+        &underlyings[0],
+        strike_price,
+        option_type,
+        &rate_curve,
+        time_horizon_days,
+        config,
+    );
+    Ok(AmericanResult {
+        pricing: result,
+        regressions,
+        in_sample_value,
+        out_of_sample_value,
+        out_of_sample_std_error,
+        upper_bound,
+        exercise_policy,
+    })
+}
+
+/// Returns the price of the discounted cashflows of an American or Bermudan option with the
+/// European option on the same paths as control variate, if configured, and the bounds of
+/// the price
+#[allow(clippy::too_many_arguments)]
+fn american_result(
+    statistics: &SampleStatistics,
+    num_paths: u64,
+    non_finite_paths: u64,
+    engine: &PathEngine,
+    underlying: &Underlying,
+    strike_price: f64,
+    option_type: OptionType,
+    rate_curve: &RateCurve,
+    time_horizon_days: u32,
+    config: &SimulationConfig,
+) -> PricingResult {
+    let time_to_expiration = config.day_count.year_fraction(time_horizon_days);
+    // Control variate: the discounted European payoff, unless the underlying pays cash
+    // dividends or the model is not lognormal and its expectation is unknown
+    let is_lognormal = config.model.has_black_scholes_marginals();
+    let control_expectation = if config.control_variate && is_lognormal {
+        closed_form::black_scholes_price_with_dividends(
+            &with_effective_volatility(underlying, time_horizon_days, config),
+            strike_price,
+            rate_curve,
+            time_to_expiration,
+            option_type,
+            config.day_count,
+        )
+    } else {
+        None
+    };
+    let mut result = PricingResult::from_statistics(statistics, num_paths, control_expectation);
+    result.record_non_finite_paths(non_finite_paths);
+    result.record_correlation_repair(engine.correlation.adjustment());
+    result.record_dimension_budget(engine.dimension_budget(config.sampling));
+
+    let effective_underlying = with_effective_volatility(underlying, time_horizon_days, config);
+    let mut price_bounds = bounds::american_bounds(
+        &effective_underlying,
+        strike_price,
+        option_type,
+        rate_curve,
+        time_to_expiration,
+        config.day_count,
+    );
+    if !is_lognormal {
+        // The European option is only bounded by its intrinsic value on the forward
+        price_bounds.lower = bounds::no_arbitrage_bounds(
+            &effective_underlying,
+            &Payoff::Vanilla {
+                strike_price,
+                option_type,
+            },
+            rate_curve,
+            time_to_expiration,
+            false,
+            config.day_count,
+        )
+        .lower;
+    }
+    result.apply_bounds(price_bounds);
+    result.check_std_error();
+    result
+}
+
+/// Values an exercise policy on newly simulated paths: each path pays its exercise value on
+/// the first early exercise day the policy exercises, its intrinsic value at expiry otherwise
+///
+/// # Returns
+/// The statistics of the discounted cashflows of the samples, with the discounted European
+/// payoff on the same paths as control, and the number of non-finite values
+#[allow(clippy::too_many_arguments)]
+fn value_policy(
+    engine: &PathEngine,
+    generator: &mut ShockGenerator,
+    num_samples: usize,
+    exercise_policy: &ExercisePolicy,
+    strike_price: f64,
+    option_type: OptionType,
+    discount_factors: &[f64],
+    config: &SimulationConfig,
+) -> Result<(SampleStatistics, u64), McError> {
+    let shock_signs = engine::shock_signs(config.antithetic);
+    let (observed_prices, final_prices) = simulate_exercise_prices(
+        engine,
+        generator,
+        shock_signs,
+        num_samples,
+        &exercise_policy.exercise_days,
+    );
+    let discount_factor = discount_factors[discount_factors.len() - 1];
+    let policy = config.non_finite_policy;
+    let mut statistics = ChunkedStatistics::default();
+    let mut non_finite_paths = 0;
+    let mut basis_values = Vec::new();
+    for sample in 0..num_samples {
+        let mut sample_sum = 0.0;
+        let mut control_sum = 0.0;
+        let mut is_dropped = false;
+        for path in sample * shock_signs.len()..(sample + 1) * shock_signs.len() {
+            let exercise = exercise_policy.exercise_days.iter().enumerate().find_map(|(date_index, &day)| {
+                let prices = &observed_prices[date_index][path];
+                let exercise_value = intrinsic_value(prices[0], strike_price, option_type);
+                exercise_policy
+                    .exercises(date_index, prices, exercise_value, &mut basis_values)
+                    .then(|| exercise_value * discount_factors[day as usize])
+            });
+            let control =
+                intrinsic_value(final_prices[path], strike_price, option_type) * discount_factor;
+            let value = exercise.unwrap_or(control);
+            // A non-finite terminal price stands in for the payoffs, which would clip it
+            let (value, control) = match final_prices[path] {
+                price if !price.is_finite() => (price, price),
+                _ => (value, control),
+            };
+
+            // Non-finite values from extreme parameters are treated according to the policy
+            if !value.is_finite() || !control.is_finite() {
+                non_finite_paths += 1;
+            }
+            match (policy.apply(value)?, policy.apply(control)?) {
+                (Some(value), Some(control)) => {
+                    sample_sum += value;
+                    control_sum += control;
+                }
+                _ => is_dropped = true,
+            }
+        }
+        if !is_dropped {
+            let path_count = shock_signs.len() as f64;
+            statistics.add(sample_sum / path_count, control_sum / path_count);
+        }
+    }
+    Ok((statistics.finish(), non_finite_paths))
+}
+
+/// Simulates all paths with daily steps, recording the prices of all underlyings at every
+/// early exercise date and the price of the first underlying at expiry
+///
+/// # Returns
+/// The prices at each early exercise date of each path (`observed_prices[date][path]`) and
+/// the final price of each path
+fn simulate_exercise_prices(
+    engine: &PathEngine,
+    generator: &mut ShockGenerator,
+    shock_signs: &[f64],
+    num_samples: usize,
+    early_exercise_days: &[u32],
+) -> (Vec<Vec<Vec<f64>>>, Vec<f64>) {
+    let num_paths = num_samples * shock_signs.len();
+    let mut observed_prices: Vec<Vec<Vec<f64>>> =
+        vec![Vec::with_capacity(num_paths); early_exercise_days.len()];
+    let mut final_prices: Vec<f64> = Vec::with_capacity(num_paths);
+    let mut paths: Vec<PathState> = shock_signs
+        .iter()
+        .map(|_| PathState::new(engine, 0))
+        .collect();
+    let mut shocks = engine.new_shocks();
+    for _ in 0..num_samples {
+        for path in paths.iter_mut() {
+            path.reset(engine);
+        }
+        generator.start_path();
+        let mut next_date = 0;
+        for step in 1..=engine.num_steps {
+            engine.draw_shocks(generator, &mut shocks);
+            let is_exercise_date = next_date < early_exercise_days.len()
+                && early_exercise_days[next_date] as usize == step;
+            for (&sign, path) in shock_signs.iter().zip(paths.iter_mut()) {
+                path.advance(engine, step, &shocks, sign);
+                if is_exercise_date || step == engine.num_steps {
+                    path.update_prices();
+                }
+            }
+            if is_exercise_date {
+                observed_prices[next_date].extend(paths.iter().map(|path| path.prices.clone()));
+                next_date += 1;
+            }
+        }
+        final_prices.extend(paths.iter().map(|path| path.prices[0]));
+    }
+    (observed_prices, final_prices)
+}
+
+/// Returns the coefficient of determination of fitted values, 1 if the values do not vary
+fn r_squared(values: &DVector<f64>, fitted_values: &DVector<f64>) -> f64 {
+    let mean = values.mean();
+    let total_sum_of_squares: f64 = values.iter().map(|value| (value - mean).powi(2)).sum();
+    if total_sum_of_squares == 0.0 {
+        return 1.0;
+    }
+    let residual_sum_of_squares = (values - fitted_values).norm_squared();
+    1.0 - residual_sum_of_squares / total_sum_of_squares
+}
+
+/// Exercise policy fitted by the Longstaff-Schwartz regressions
+///
+/// The policy exercises on an early exercise day if the option is in the money and the
+/// exercise value exceeds the continuation value fitted on that day. Pricing again with the
+/// same policy under bumped markets (policy freezing) keeps the exercise decisions from
+/// adding regression noise to finite-difference Greeks.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExercisePolicy {
+    /// Basis functions of the regressions
+    pub basis: RegressionBasis,
+    /// Prices of the underlyings the basis normalizes by: the initial prices of the run the
+    /// policy was fitted on, kept under bumped markets
+    pub reference_prices: Vec<f64>,
+    /// Early exercise days in ascending order
+    pub exercise_days: Vec<u32>,
+    /// Coefficients of the continuation value on each early exercise day, none on days
+    /// without regression, on which the policy does not exercise
+    pub coefficients: Vec<Option<Vec<f64>>>,
+}
+
+impl ExercisePolicy {
+    /// Returns `true` if the policy exercises on the early exercise day with the given index
+    fn exercises(
+        &self,
+        date_index: usize,
+        prices: &[f64],
+        exercise_value: f64,
+        basis_values: &mut Vec<f64>,
+    ) -> bool {
+        let Some(coefficients) = &self.coefficients[date_index] else {
+            return false;
+        };
+        if exercise_value <= 0.0 {
+            return false;
+        }
+        basis_values.clear();
+        self.basis.evaluate(prices, &self.reference_prices, exercise_value, basis_values);
+        let continuation_value: f64 = basis_values
+            .iter()
+            .zip(coefficients)
+            .map(|(value, coefficient)| value * coefficient)
+            .sum();
+        exercise_value > continuation_value
+    }
+
+    /// Checks that the policy applies to an option on the given number of underlyings
+    /// expiring on the given day
+    fn validate(&self, num_underlyings: usize, time_horizon_days: u32) -> Result<(), McError> {
+        let num_functions = self.basis.num_functions(num_underlyings);
+        let reason = if self.basis.order == 0 {
+            Some("the basis has order zero".to_string())
+        } else if self.reference_prices.len() != num_underlyings {
+            Some(format!(
+                "{} reference prices for {} underlyings",
+                self.reference_prices.len(),
+                num_underlyings
+            ))
+        } else if !self.reference_prices.iter().all(|price| price.is_finite() && *price > 0.0) {
+            Some(format!("reference prices must be positive, got {:?}", self.reference_prices))
+        } else if !self.exercise_days.windows(2).all(|days| days[0] < days[1])
+            || self
+                .exercise_days
+                .iter()
+                .any(|&day| day == 0 || day >= time_horizon_days)
+        {
+            Some(format!(
+                "exercise days must increase between day 1 and day {}, got {:?}",
+                time_horizon_days.saturating_sub(1),
+                self.exercise_days
+            ))
+        } else if self.coefficients.len() != self.exercise_days.len() {
+            Some(format!(
+                "{} sets of coefficients for {} exercise days",
+                self.coefficients.len(),
+                self.exercise_days.len()
+            ))
+        } else if self
+            .coefficients
+            .iter()
+            .flatten()
+            .any(|coefficients| coefficients.len() != num_functions)
+        {
+            Some(format!("the basis has {} functions", num_functions))
+        } else {
+            None
+        };
+        match reason {
+            Some(reason) => Err(McError::InvalidExercisePolicy(reason)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Nested simulation of the dual upper bound of an exercise policy
+struct DualSimulation<'a> {
+    engine: &'a PathEngine,
+    exercise_policy: &'a ExercisePolicy,
+    early_exercise_days: &'a [u32],
+    strike_price: f64,
+    option_type: OptionType,
+    /// Discount factor of each day from today to expiry
+    discount_factors: &'a [f64],
+}
+
+impl DualSimulation<'_> {
+    /// Estimates the upper bound `E[max_k (Z_k - M_k)]` over the exercise dates, with `Z_k`
+    /// the discounted exercise value and `M_k` the martingale summing `L_(j+1) - Q_j` over the
+    /// dates before, starting today: `Q_j` is the continuation value of the policy after date
+    /// `j`, and `L_j` the discounted exercise value where the policy exercises, the
+    /// continuation value elsewhere and the exercise value at expiry
+    fn upper_bound(&self, settings: &DualSettings, config: &SimulationConfig) -> DualBound {
+        let engine = self.engine;
+        let seeded = |offset: u64| SimulationConfig {
+            seed: config.seed.map(|seed| seed.wrapping_add(offset)),
+            ..config.clone()
+        };
+        let mut outer_generator = engine.shock_generator(&seeded(DUAL_OUTER_SEED_OFFSET));
+        let mut inner_generator = engine.shock_generator(&seeded(DUAL_INNER_SEED_OFFSET));
+        let mut outer = PathState::new(engine, 0);
+        let mut inner = PathState::new(engine, 0);
+        let mut shocks = engine.new_shocks();
+        let mut basis_values = Vec::new();
+
+        outer.reset(engine);
+        let initial_continuation_value = self.continuation_value(
+            0,
+            &outer,
+            &mut inner,
+            &mut inner_generator,
+            &mut shocks,
+            &mut basis_values,
+            settings.inner_paths,
+        );
+        let mut statistics = ChunkedStatistics::default();
+        for _ in 0..settings.outer_paths {
+            outer.reset(engine);
+            outer_generator.start_path();
+            let mut martingale = 0.0;
+            let mut continuation_value = initial_continuation_value;
+            let mut upper_bound = f64::NEG_INFINITY;
+            let mut next_date = 0;
+            for step in 1..=engine.num_steps {
+                engine.draw_shocks(&mut outer_generator, &mut shocks);
+                outer.advance(engine, step, &shocks, 1.0);
+                let is_early_exercise_date = next_date < self.early_exercise_days.len()
+                    && self.early_exercise_days[next_date] as usize == step;
+                if !is_early_exercise_date && step < engine.num_steps {
+                    continue;
+                }
+                outer.update_prices();
+                let exercise_value = intrinsic_value(outer.prices[0], self.strike_price, self.option_type);
+                let discounted_exercise_value = exercise_value * self.discount_factors[step];
+                let (value, next_continuation_value) = if is_early_exercise_date {
+                    let next_continuation_value = self.continuation_value(
+                        step,
+                        &outer,
+                        &mut inner,
+                        &mut inner_generator,
+                        &mut shocks,
+                        &mut basis_values,
+                        settings.inner_paths,
+                    );
+                    let exercises = self.exercise_policy.exercises(
+                        next_date,
+                        &outer.prices,
+                        exercise_value,
+                        &mut basis_values,
+                    );
+                    next_date += 1;
+                    if exercises {
+                        (discounted_exercise_value, next_continuation_value)
+                    } else {
+                        (next_continuation_value, next_continuation_value)
+                    }
+                } else {
+                    // The option is exercised at expiry, with nothing left to continue
+                    (discounted_exercise_value, 0.0)
+                };
+                martingale += value - continuation_value;
+                continuation_value = next_continuation_value;
+                upper_bound = upper_bound.max(discounted_exercise_value - martingale);
+            }
+            statistics.add(upper_bound, 0.0);
+        }
+        let estimate = PricingResult::from_statistics(&statistics.finish(), settings.outer_paths, None);
+        DualBound {
+            value: estimate.price,
+            std_error: estimate.std_error,
+            settings: *settings,
+        }
+    }
+
+    /// Estimates the continuation value of the exercise policy after the given day on the
+    /// state of a path: the mean discounted cashflow of inner paths simulated from there
+    #[allow(clippy::too_many_arguments)]
+    fn continuation_value(
+        &self,
+        day: usize,
+        state: &PathState,
+        inner: &mut PathState,
+        generator: &mut ShockGenerator,
+        shocks: &mut DVector<f64>,
+        basis_values: &mut Vec<f64>,
+        inner_paths: u64,
+    ) -> f64 {
+        let engine = self.engine;
+        let first_date = self
+            .early_exercise_days
+            .partition_point(|&exercise_day| exercise_day as usize <= day);
+        let mut sum = 0.0;
+        for _ in 0..inner_paths {
+            inner.log_prices.copy_from_slice(&state.log_prices);
+            inner.model_state.copy_from_slice(&state.model_state);
+            generator.start_path();
+            let mut next_date = first_date;
+            let mut cashflow = None;
+            for step in day + 1..=engine.num_steps {
+                engine.draw_shocks(generator, shocks);
+                inner.advance(engine, step, shocks, 1.0);
+                let is_early_exercise_date = next_date < self.early_exercise_days.len()
+                    && self.early_exercise_days[next_date] as usize == step;
+                if !is_early_exercise_date && step < engine.num_steps {
+                    continue;
+                }
+                inner.update_prices();
+                let exercise_value = intrinsic_value(inner.prices[0], self.strike_price, self.option_type);
+                if !is_early_exercise_date
+                    || self.exercise_policy.exercises(
+                        next_date,
+                        &inner.prices,
+                        exercise_value,
+                        basis_values,
+                    )
+                {
+                    cashflow = Some(exercise_value * self.discount_factors[step]);
+                    break;
+                }
+                next_date += 1;
+            }
+            sum += cashflow.unwrap_or(0.0);
+        }
+        sum / inner_paths as f64
+    }
+}
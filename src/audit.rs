@@ -0,0 +1,84 @@
+use crate::barrier::{Barrier, BarrierCorrection, BarrierObservation};
+use crate::config::SimulationConfig;
+use crate::payoff::Payoff;
+use crate::{time_steps, BGK_BARRIER_SHIFT};
+
+/// Setting a price was computed with, recorded in the audit trail of its result so the
+/// valuation can be reproduced without guessing defaults
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AuditEntry {
+    /// Name of the setting, e.g. `day_count` or `barrier_shift`
+    pub setting: String,
+    /// Value the price was computed with
+    pub value: String,
+    /// `true` if the value was not configured but applied by default or derived from the
+    /// product
+    pub is_default: bool,
+}
+
+impl AuditEntry {
+    /// Creates an entry of a setting
+    pub fn new(setting: impl Into<String>, value: impl Into<String>, is_default: bool) -> Self {
+        Self {
+            setting: setting.into(),
+            value: value.into(),
+            is_default,
+        }
+    }
+}
+
+/// Returns the audit trail of the settings of a simulation: the conventions of the
+/// configuration, the time grid, the seed and, for barrier products, the monitoring
+/// assumptions
+pub(crate) fn simulation_audit(
+    time_horizon_days: u32,
+    payoff: &Payoff,
+    barrier: Option<&Barrier>,
+    config: &SimulationConfig,
+) -> Vec<AuditEntry> {
+    fn setting<T: std::fmt::Debug + Default + PartialEq>(name: &str, value: &T) -> AuditEntry {
+        AuditEntry::new(name, format!("{:?}", value), *value == T::default())
+    }
+
+    let mut entries = vec![
+        setting("day_count", &config.day_count),
+        setting("variance_time", &config.variance_time),
+        setting("sampling", &config.sampling),
+        setting("non_finite_policy", &config.non_finite_policy),
+        // The time grid follows from the product and the model, it is never configured
+        AuditEntry::new(
+            "time_steps",
+            time_steps(time_horizon_days, payoff, barrier, config).to_string(),
+            true,
+        ),
+        match config.seed {
+            Some(seed) => AuditEntry::new("seed", seed.to_string(), false),
+            None => AuditEntry::new("seed", "drawn from system entropy", true),
+        },
+    ];
+    if let Some(barrier) = barrier {
+        let correction = barrier.correction(config.barrier_correction);
+        let is_default_correction = barrier.observation == BarrierObservation::Configured
+            && config.barrier_correction == BarrierCorrection::default();
+        let shift = match correction {
+            BarrierCorrection::ShiftedBarrier => BGK_BARRIER_SHIFT,
+            _ => 0.0,
+        };
+        entries.extend([
+            setting("barrier_monitoring", &barrier.monitoring),
+            setting("barrier_observation", &barrier.observation),
+            AuditEntry::new(
+                "barrier_correction",
+                format!("{:?}", correction),
+                is_default_correction,
+            ),
+            AuditEntry::new(
+                "barrier_shift",
+                format!("{} step standard deviations", shift),
+                is_default_correction,
+            ),
+        ]);
+    }
+    entries
+}
@@ -0,0 +1,487 @@
+use nalgebra::DMatrix;
+
+use crate::barrier::BarrierType;
+use crate::calendar::Date;
+use crate::{calculate_reference, with_start_values};
+use crate::config::SimulationConfig;
+use crate::rates::RateCurve;
+use crate::engine::{self, PathEngine, PathState};
+use crate::error::McError;
+use crate::market::MarketSnapshot;
+use crate::result::PricingResult;
+use crate::session::MarketShift;
+use crate::statistics::ChunkedStatistics;
+use crate::underlying::Underlying;
+
+/// Autocallable (Phoenix) structured product on a basket of underlyings
+///
+/// All levels are relative to the initial reference performance of 1.0, where the
+/// performance of each underlying is its price divided by its initial spot price and the
+/// basket performance is aggregated according to `barrier_type` (usually worst-of).
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Autocallable {
+    /// Notional repaid at redemption
+    pub notional: f64,
+    /// Observation days (from today), the last one being the maturity
+    pub observation_days: Vec<u32>,
+    /// Early redemption is triggered if the performance is at or above this level
+    pub autocall_level: f64,
+    /// A coupon is paid if the performance is at or above this level
+    pub coupon_barrier: f64,
+    /// Coupon paid per observation, as a fraction of the notional
+    pub coupon_rate: f64,
+    /// `true` if missed coupons are paid later once the coupon barrier is met again
+    pub memory: bool,
+    /// The capital protection is lost (knock-in put) if the performance falls to or below
+    /// this level
+    pub knock_in_level: f64,
+    /// `true` to check the knock-in level at maturity only, `false` for daily monitoring
+    pub knock_in_at_expiry_only: bool,
+    /// How the performances of the underlyings are aggregated into the basket performance
+    pub barrier_type: BarrierType,
+}
+
+impl Autocallable {
+    /// Creates a worst-of autocallable with daily knock-in monitoring
+    ///
+    /// # Arguments
+    /// * `notional` - Notional repaid at redemption
+    /// * `observation_days` - Observation days (from today), the last one being the maturity
+    /// * `autocall_level` - Relative autocall trigger level (e.g. 1.0)
+    /// * `coupon_barrier` - Relative coupon barrier (e.g. 0.7)
+    /// * `coupon_rate` - Coupon per observation as a fraction of the notional (e.g. 0.02)
+    /// * `memory` - `true` for memory coupons
+    /// * `knock_in_level` - Relative knock-in level of the put at maturity (e.g. 0.6)
+    pub fn new(
+        notional: f64,
+        observation_days: Vec<u32>,
+        autocall_level: f64,
+        coupon_barrier: f64,
+        coupon_rate: f64,
+        memory: bool,
+        knock_in_level: f64,
+    ) -> Self {
+        Self {
+            notional,
+            observation_days,
+            autocall_level,
+            coupon_barrier,
+            coupon_rate,
+            memory,
+            knock_in_level,
+            knock_in_at_expiry_only: false,
+            barrier_type: BarrierType::WorstOf,
+        }
+    }
+
+    /// Replaces the observation days by the given observation dates, the last one being the
+    /// maturity, converted into days from the valuation date with the day count convention
+    /// and calendar of the configuration
+    ///
+    /// # Errors
+    /// Returns `McError::InvalidDate` if an observation date lies before the valuation date.
+    pub fn with_observation_dates(
+        mut self,
+        valuation: Date,
+        observation_dates: &[Date],
+        config: &SimulationConfig,
+    ) -> Result<Self, McError> {
+        self.observation_days = config.day_offsets(valuation, observation_dates)?;
+        Ok(self)
+    }
+
+    /// Returns the maturity of the product in days
+    pub fn maturity_days(&self) -> u32 {
+        self.observation_days.iter().copied().max().unwrap_or(0)
+    }
+}
+
+/// Result of pricing an autocallable product
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AutocallableResult {
+    /// Estimated price of the product
+    pub pricing: PricingResult,
+    /// Expected redemption day (maturity if the product is not called early)
+    pub expected_redemption_day: f64,
+    /// Probability of redemption at each observation day, in the order of the sorted days.
+    /// The last entry includes redemption at maturity without autocall.
+    pub redemption_probabilities: Vec<f64>,
+}
+
+/// Component of an autocallable whose value and Greeks are attributed separately
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AutocallableComponent {
+    /// Notional paid at maturity, whatever the path
+    Bond,
+    /// Coupons paid on the observation days, missed coupons of memory products included
+    Coupons,
+    /// Notional received on the autocall day instead of at maturity
+    EarlyRedemption,
+    /// Knock-in put the investor is short: the loss of the notional at maturity after a
+    /// knock-in, zero or negative
+    KnockInPut,
+}
+
+impl AutocallableComponent {
+    /// All components, in the order of `AutocallableAttribution::components`
+    pub const ALL: [AutocallableComponent; 4] = [
+        AutocallableComponent::Bond,
+        AutocallableComponent::Coupons,
+        AutocallableComponent::EarlyRedemption,
+        AutocallableComponent::KnockInPut,
+    ];
+}
+
+/// Value and Greeks of one component of an autocallable
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ComponentGreeks {
+    /// Attributed component
+    pub component: AutocallableComponent,
+    /// Value of the component
+    pub price: f64,
+    /// Derivative of the value with respect to the spot of the first underlying, with the
+    /// spots of all underlyings moving proportionally
+    pub delta: f64,
+    /// Derivative of the value with respect to a parallel shift of all volatilities (per
+    /// unit of volatility)
+    pub vega: f64,
+}
+
+/// Price and Greeks of an autocallable attributed to its components
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AutocallableAttribution {
+    /// Price of the note
+    pub pricing: PricingResult,
+    /// Delta of the note, the sum of the deltas of the components
+    pub delta: f64,
+    /// Vega of the note, the sum of the vegas of the components
+    pub vega: f64,
+    /// Value and Greeks of each component, in the order of `AutocallableComponent::ALL`
+    pub components: Vec<ComponentGreeks>,
+}
+
+impl AutocallableAttribution {
+    /// Returns the value and Greeks of the given component
+    pub fn component(&self, component: AutocallableComponent) -> &ComponentGreeks {
+        self.components
+            .iter()
+            .find(|greeks| greeks.component == component)
+            .expect("every component is attributed")
+    }
+}
+
+/// Relative bump of the spot prices and absolute bump of the volatilities of the attribution
+const GREEK_BUMP: f64 = 0.01;
+
+/// Prices an autocallable (Phoenix) product using Monte Carlo simulation with daily steps
+///
+/// # Arguments
+/// * `underlyings` - List of underlying assets of the basket
+/// * `correlation_matrix` - Correlation matrix (n x n) where n is the number of underlyings.
+///   Must be symmetric, positive semi-definite, with 1.0 on the diagonal.
+/// * `product` - Terms of the autocallable
+/// * `risk_free_rate` - Risk-free rate curve or flat annual rate (as a decimal, e.g., 0.05 for 5%)
+/// * `config` - Number of paths and variance reduction settings. The control variate is not
+///   applicable to autocallables and is ignored.
+///
+/// # Returns
+/// The estimated price together with the expected redemption day
+///
+/// # Errors
+/// Returns `McError::InvalidSchedule` if the product has no observation day or an
+/// observation day is zero, `McError::InvalidProduct` if the notional is not positive, or an
+/// error for invalid inputs (see `price_option`).
+pub fn price_autocallable(
+    underlyings: &[Underlying],
+    correlation_matrix: &DMatrix<f64>,
+    product: &Autocallable,
+    risk_free_rate: impl Into<RateCurve>,
+    config: &SimulationConfig,
+) -> Result<AutocallableResult, McError> {
+    let underlyings = &*with_start_values(underlyings, config)?;
+    simulate_autocallable(
+        underlyings,
+        correlation_matrix,
+        product,
+        &risk_free_rate.into(),
+        None,
+        config,
+    )
+    .map(|(result, _)| result)
+}
+
+/// Attributes the price, delta and vega of an autocallable to its components, priced on the
+/// same paths for the market and for each bumped market
+///
+/// The note decomposes into a bond paying the notional at maturity, the coupons, the early
+/// redemption (the notional received when called instead of at maturity) and the knock-in
+/// put the investor is short (the loss of the notional at maturity after a knock-in), whose
+/// values add up to the price of the note on every path. Delta and vega are central
+/// differences for relative spot bumps and absolute volatility bumps of all underlyings by
+/// 1%, on the same seed (the configured one, or one drawn for the attribution). The
+/// performances stay relative to the unbumped spots, as for a note struck today.
+///
+/// # Arguments
+/// * `market` - Market of the underlyings of the basket
+/// * `product` - Terms of the autocallable
+/// * `config` - Number of paths and variance reduction settings (see `price_autocallable`).
+///   The error tolerance and the sanity checks are ignored, so all prices simulate the same
+///   paths.
+///
+/// # Returns
+/// The price and Greeks of the note and of each component
+///
+/// # Errors
+/// Returns an error under the same conditions as `price_autocallable`, or if a volatility
+/// bumped down becomes negative.
+pub fn attribute_autocallable_greeks(
+    market: &MarketSnapshot,
+    product: &Autocallable,
+    config: &SimulationConfig,
+) -> Result<AutocallableAttribution, McError> {
+    let market = MarketSnapshot {
+        underlyings: with_start_values(&market.underlyings, config)?.into_owned(),
+        ..market.clone()
+    };
+    let config = SimulationConfig {
+        seed: Some(config.seed.unwrap_or_else(crate::draw_seed)),
+        validate: false,
+        start_values: None,
+        error_tolerance: None,
+        ..config.clone()
+    };
+    let reference_prices: Vec<f64> = market.underlyings.iter().map(|u| u.spot_price).collect();
+    let price = |shift: MarketShift| {
+        let market = shift.apply(&market);
+        simulate_autocallable(
+            &market.underlyings,
+            &market.correlation_matrix,
+            product,
+            &market.risk_free_rate,
+            Some(&reference_prices),
+            &config,
+        )
+    };
+    let (base, base_values) = price(MarketShift::None)?;
+    let (_, spot_up) = price(MarketShift::Spot(GREEK_BUMP))?;
+    let (_, spot_down) = price(MarketShift::Spot(-GREEK_BUMP))?;
+    let (_, volatility_up) = price(MarketShift::Volatility(GREEK_BUMP))?;
+    let (_, volatility_down) = price(MarketShift::Volatility(-GREEK_BUMP))?;
+
+    let spot_bump = GREEK_BUMP * reference_prices[0];
+    let components: Vec<ComponentGreeks> = AutocallableComponent::ALL
+        .iter()
+        .enumerate()
+        .map(|(index, &component)| ComponentGreeks {
+            component,
+            price: base_values[index],
+            delta: (spot_up[index] - spot_down[index]) / (2.0 * spot_bump),
+            vega: (volatility_up[index] - volatility_down[index]) / (2.0 * GREEK_BUMP),
+        })
+        .collect();
+    Ok(AutocallableAttribution {
+        delta: components.iter().map(|component| component.delta).sum(),
+        vega: components.iter().map(|component| component.vega).sum(),
+        pricing: base.pricing,
+        components,
+    })
+}
+
+/// Simulates an autocallable, returning its price and the values of its components in the
+/// order of `AutocallableComponent::ALL`
+///
+/// The performances are relative to `reference_prices`, or to the spot prices if `None`.
+fn simulate_autocallable(
+    underlyings: &[Underlying],
+    correlation_matrix: &DMatrix<f64>,
+    product: &Autocallable,
+    rate_curve: &RateCurve,
+    reference_prices: Option<&[f64]>,
+    config: &SimulationConfig,
+) -> Result<(AutocallableResult, [f64; 4]), McError> {
+    let mut observation_days = product.observation_days.clone();
+    observation_days.sort_unstable();
+    observation_days.dedup();
+    if observation_days.is_empty() || observation_days[0] == 0 {
+        return Err(McError::InvalidSchedule(
+            "Autocallable needs observation days after today".to_string(),
+        ));
+    }
+    if !(product.notional.is_finite() && product.notional > 0.0) {
+        return Err(McError::InvalidProduct(format!(
+            "notional must be positive and finite, got {}",
+            product.notional
+        )));
+    }
+    let maturity_days = product.maturity_days();
+
+    let engine = PathEngine::new(
+        underlyings,
+        correlation_matrix,
+        rate_curve,
+        config.day_count.year_fraction(maturity_days),
+        maturity_days as usize, // Daily steps for knock-in monitoring
+        config,
+    )?;
+    let mut generator = engine.shock_generator(config);
+    let reference_prices = reference_prices.unwrap_or(&engine.initial_prices);
+    let all_indices: Vec<usize> = (0..underlyings.len()).collect();
+    let discount = |day: u32| rate_curve.discount_factor(config.day_count.year_fraction(day));
+
+    let shock_signs = engine::shock_signs(config.antithetic);
+    let num_samples = config.num_paths.div_ceil(shock_signs.len() as u64);
+    let num_paths = num_samples * shock_signs.len() as u64;
+
+    let mut statistics = ChunkedStatistics::default();
+    let mut non_finite_paths = 0;
+    let mut redemption_counts = vec![0u64; observation_days.len()];
+    let mut redemption_day_sum = 0u64;
+    // Sums of the sample values of the coupons, early redemptions and knock-in puts
+    let mut component_sums = [0.0; 3];
+    let mut num_kept_samples = 0u64;
+
+    let mut paths: Vec<PathState> = shock_signs
+        .iter()
+        .map(|_| PathState::new(&engine, 0))
+        .collect();
+    let mut shocks = engine.new_shocks();
+    // Buffers are allocated once and reset for every sample
+    let mut knocked_in = vec![false; paths.len()];
+    let mut redeemed = vec![false; paths.len()];
+    let mut missed_coupons = vec![0u32; paths.len()];
+    let mut values = vec![0.0; paths.len()];
+    let mut component_values = vec![[0.0; 3]; paths.len()];
+    let mut performances = vec![0.0; underlyings.len()];
+    for _ in 0..num_samples {
+        for path in paths.iter_mut() {
+            path.reset(&engine);
+        }
+        generator.start_path();
+        knocked_in.fill(false);
+        redeemed.fill(false);
+        missed_coupons.fill(0);
+        values.fill(0.0);
+        component_values.fill([0.0; 3]);
+        let mut next_observation = 0;
+
+        for step in 1..=engine.num_steps {
+            engine.draw_shocks(&mut generator, &mut shocks);
+            let is_observation = observation_days[next_observation] as usize == step;
+            let is_maturity = step == engine.num_steps;
+
+            for (path, &sign) in shock_signs.iter().enumerate() {
+                if redeemed[path] {
+                    continue;
+                }
+                paths[path].advance(&engine, step, &shocks, sign);
+                paths[path].update_prices();
+
+                let prices = paths[path].prices.iter().zip(reference_prices);
+                for (performance, (price, initial)) in performances.iter_mut().zip(prices) {
+                    *performance = price / initial;
+                }
+                let performance =
+                    calculate_reference(&performances, &all_indices, product.barrier_type);
+
+                if performance <= product.knock_in_level
+                    && (!product.knock_in_at_expiry_only || is_maturity)
+                {
+                    knocked_in[path] = true;
+                }
+
+                if !is_observation {
+                    continue;
+                }
+
+                // Coupon, including missed coupons for memory products
+                if performance >= product.coupon_barrier {
+                    let coupons = 1 + if product.memory {
+                        missed_coupons[path]
+                    } else {
+                        0
+                    };
+                    let coupon = coupons as f64
+                        * product.coupon_rate
+                        * product.notional
+                        * discount(step as u32);
+                    values[path] += coupon;
+                    component_values[path][0] += coupon;
+                    missed_coupons[path] = 0;
+                } else {
+                    missed_coupons[path] += 1;
+                }
+
+                // Early redemption at the autocall trigger, or final redemption at maturity
+                if performance >= product.autocall_level || is_maturity {
+                    let redemption = if is_maturity && knocked_in[path] && performance < 1.0 {
+                        // Knock-in put: the investor bears the loss of the basket
+                        product.notional * performance
+                    } else {
+                        product.notional
+                    };
+                    values[path] += redemption * discount(step as u32);
+                    component_values[path][1] +=
+                        product.notional * (discount(step as u32) - discount(maturity_days));
+                    component_values[path][2] +=
+                        (redemption - product.notional) * discount(maturity_days);
+                    redeemed[path] = true;
+                    redemption_counts[next_observation] += 1;
+                    redemption_day_sum += step as u64;
+                }
+            }
+
+            if is_observation {
+                next_observation += 1;
+            }
+        }
+
+        // Non-finite values from extreme parameters are treated according to the policy
+        let mut value_sum = 0.0;
+        let mut is_dropped = false;
+        for &value in &values {
+            if !value.is_finite() {
+                non_finite_paths += 1;
+            }
+            match config.non_finite_policy.apply(value)? {
+                Some(value) => value_sum += value,
+                None => is_dropped = true,
+            }
+        }
+        if !is_dropped {
+            statistics.add(value_sum / values.len() as f64, 0.0);
+            for path_components in &component_values {
+                for (sum, value) in component_sums.iter_mut().zip(path_components) {
+                    *sum += value / values.len() as f64;
+                }
+            }
+            num_kept_samples += 1;
+        }
+    }
+
+    let mut pricing = PricingResult::from_statistics(&statistics.finish(), num_paths, None);
+    pricing.record_non_finite_paths(non_finite_paths);
+    pricing.record_correlation_repair(engine.correlation.adjustment());
+    pricing.record_dimension_budget(engine.dimension_budget(config.sampling));
+    pricing.check_std_error();
+    let kept_samples = num_kept_samples.max(1) as f64;
+    let components = [
+        product.notional * discount(maturity_days),
+        component_sums[0] / kept_samples,
+        component_sums[1] / kept_samples,
+        component_sums[2] / kept_samples,
+    ];
+    let result = AutocallableResult {
+        pricing,
+        expected_redemption_day: redemption_day_sum as f64 / num_paths as f64,
+        redemption_probabilities: redemption_counts
+            .iter()
+            .map(|&count| count as f64 / num_paths as f64)
+            .collect(),
+    };
+    Ok((result, components))
+}
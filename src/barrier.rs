@@ -1,8 +1,51 @@
 use std::error::Error;
 use std::fmt;
 
+/// Direction from which the barrier reference hits the barrier
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BarrierDirection {
+    /// The barrier is hit if the reference rises to or above the barrier level
+    Up,
+    /// The barrier is hit if the reference falls to or below the barrier level
+    Down,
+}
+
+impl BarrierDirection {
+    /// Returns the direction of an up (`true`) or down (`false`) flag
+    pub(crate) fn from_is_up(is_up: bool) -> Self {
+        if is_up {
+            BarrierDirection::Up
+        } else {
+            BarrierDirection::Down
+        }
+    }
+}
+
+/// Effect of hitting the barrier on the option
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KnockType {
+    /// Knock-in: the option only has value if the barrier was hit
+    In,
+    /// Knock-out: the option only has value if the barrier was not hit
+    Out,
+}
+
+impl KnockType {
+    /// Returns the knock type of an in (`true`) or out (`false`) flag
+    pub(crate) fn from_is_in(is_in: bool) -> Self {
+        if is_in {
+            KnockType::In
+        } else {
+            KnockType::Out
+        }
+    }
+}
+
 /// Type of barrier for multi-underlying options
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BarrierType {
     /// Worst-Of: barrier is hit if the worst performing underlying hits the barrier
     WorstOf,
@@ -14,23 +57,188 @@ pub enum BarrierType {
     Median,
 }
 
+/// Correction of the discrete barrier monitoring on the simulation grid towards a
+/// continuously monitored barrier
+///
+/// Both corrections use the Black-Scholes step variance of the underlyings; for barriers on
+/// several underlyings, the average step variance of the underlyings the barrier applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BarrierCorrection {
+    /// Barrier is only checked at the simulated time steps
+    #[default]
+    None,
+    /// Between two steps, the barrier is crossed with the hit probability of the Brownian
+    /// bridge connecting the log reference values. Paths are weighted with their probability
+    /// of (not) having hit the barrier.
+    BrownianBridge,
+    /// The barrier is shifted towards the spot by 0.5826 standard deviations of a step
+    /// (Broadie-Glasserman-Kou)
+    ShiftedBarrier,
+}
+
+/// Days on which a barrier is observed
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BarrierMonitoring {
+    /// Observed on every simulated day, optionally corrected towards continuous monitoring
+    #[default]
+    Continuous,
+    /// Observed on the given days only (counted from today, e.g. weekly closes or month-ends)
+    Dates(Vec<u32>),
+    /// Observed at expiry only (European barrier)
+    AtExpiry,
+}
+
+impl BarrierMonitoring {
+    /// Returns `true` if the barrier is observed on the given day of a product expiring on
+    /// `time_horizon_days`
+    pub fn is_monitored(&self, day: u32, time_horizon_days: u32) -> bool {
+        match self {
+            BarrierMonitoring::Continuous => true,
+            BarrierMonitoring::Dates(days) => days.contains(&day),
+            BarrierMonitoring::AtExpiry => day == time_horizon_days,
+        }
+    }
+}
+
+/// Time within each monitoring day at which a barrier is observed
+///
+/// Barriers observed on the closing levels only are contractually different from barriers
+/// observed continuously during the day: intraday moves can hit the latter between closes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BarrierObservation {
+    /// Observed as configured by `SimulationConfig::barrier_correction`: corrected towards
+    /// continuous observation if observed every day, on the closes otherwise
+    #[default]
+    Configured,
+    /// Observed on the simulated closes only, whatever the configured correction
+    Close,
+    /// Observed continuously during each monitoring day: the intraday extremes are
+    /// approximated by the Brownian bridge between the close of the previous day and the
+    /// close of the monitoring day, whatever the configured correction
+    Intraday,
+}
+
+/// Time at which the rebate of a knocked-out option is paid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RebateTiming {
+    /// Paid on the day the barrier is hit
+    #[default]
+    AtHit,
+    /// Paid at expiry of the option
+    AtExpiry,
+}
+
+/// Fixed cash amount paid instead of the option payoff if the barrier deactivates the option
+///
+/// Knock-out options pay the rebate when the barrier is hit, at the hit or at expiry.
+/// Knock-in options pay it at expiry if the barrier was never hit, whatever the timing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rebate {
+    /// Cash amount of the rebate
+    pub amount: f64,
+    /// Time at which a knock-out rebate is paid
+    pub timing: RebateTiming,
+}
+
+impl Rebate {
+    /// Creates a rebate of the given amount paid at the given time
+    pub fn new(amount: f64, timing: RebateTiming) -> Self {
+        Self { amount, timing }
+    }
+}
+
+/// Number of barriers of one kind that need to be hit for their effect
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BarrierQuantifier {
+    /// Any of the barriers
+    #[default]
+    Any,
+    /// All of the barriers, each on one of its monitoring days
+    All,
+}
+
+/// Combination of the hits of the barriers of an option with several barriers
+///
+/// The option pays at expiry if the knock-in condition holds and the knock-out condition
+/// does not, whatever the order of the hits: a knock-out is final even if a knock-in
+/// barrier is hit later. Without knock-in barriers, the knock-in condition holds; without
+/// knock-out barriers, the knock-out condition never does. The default, any knock-out and
+/// any knock-in barrier, makes a double knock-out of an up-and-out and a down-and-out
+/// barrier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BarrierCombination {
+    /// Knock-out barriers that need to be hit to knock the option out
+    pub knock_out: BarrierQuantifier,
+    /// Knock-in barriers that need to be hit to knock the option in
+    pub knock_in: BarrierQuantifier,
+}
+
+impl BarrierCombination {
+    /// Creates a combination of the given knock-out and knock-in conditions
+    pub fn new(knock_out: BarrierQuantifier, knock_in: BarrierQuantifier) -> Self {
+        Self {
+            knock_out,
+            knock_in,
+        }
+    }
+
+    /// Returns `true` if an option with the given barriers, of which the ones flagged in
+    /// `hits` were hit, pays at expiry
+    pub fn is_alive(&self, barriers: &[Barrier], hits: &[bool]) -> bool {
+        let is_met = |knock_type: KnockType, quantifier: BarrierQuantifier| {
+            let mut hits = barriers
+                .iter()
+                .zip(hits)
+                .filter(|(barrier, _)| barrier.knock_type == knock_type)
+                .map(|(_, &hit)| hit)
+                .peekable();
+            hits.peek().is_some()
+                && match quantifier {
+                    BarrierQuantifier::Any => hits.any(|hit| hit),
+                    BarrierQuantifier::All => hits.all(|hit| hit),
+                }
+        };
+        let has_knock_in = barriers
+            .iter()
+            .any(|barrier| barrier.knock_type == KnockType::In);
+        (!has_knock_in || is_met(KnockType::In, self.knock_in))
+            && !is_met(KnockType::Out, self.knock_out)
+    }
+}
+
 /// Represents a barrier for barrier options
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Barrier {
     /// Barrier level (same unit as strike and spot price, or relative if `relative` is true)
     pub barrier_level: f64,
-    /// `true` for "in" barrier (option only has value if barrier was hit),
-    /// `false` for "out" barrier (option only has value if barrier was NOT hit)
-    pub in_out: bool,
-    /// `true` for "up" barrier (barrier is hit if price goes above barrier_level),
-    /// `false` for "down" barrier (barrier is hit if price goes below barrier_level)
-    pub up_down: bool,
+    /// Up barrier (hit if the price goes above barrier_level) or down barrier (hit if the
+    /// price goes below barrier_level)
+    pub direction: BarrierDirection,
+    /// Knock-in barrier (option only has value if barrier was hit) or knock-out barrier
+    /// (option only has value if barrier was NOT hit)
+    pub knock_type: KnockType,
     /// Type of barrier for multi-underlying options
     pub barrier_type: BarrierType,
     /// `true` if barrier_level is relative to current spot/avg/median, `false` for absolute level
     pub relative: bool,
     /// Indices into the list of underlyings this barrier applies to
     pub underlying_indices: Vec<usize>,
+    /// Days on which the barrier is observed (every day by default)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub monitoring: BarrierMonitoring,
+    /// Time within the monitoring days at which the barrier is observed (as configured by
+    /// default)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub observation: BarrierObservation,
+    /// Rebate paid if the barrier deactivates the option (none by default)
+    pub rebate: Option<Rebate>,
 }
 
 /// Error type for barrier creation
@@ -52,41 +260,61 @@ impl Barrier {
     ///
     /// # Arguments
     /// * `barrier_level` - Barrier level (absolute or relative)
-    /// * `in_out` - `true` for "in" barrier, `false` for "out" barrier
-    /// * `up_down` - `true` for "up" barrier, `false` for "down" barrier
+    /// * `direction` - Up or down barrier
+    /// * `knock_type` - Knock-in or knock-out barrier
     /// * `relative` - `true` if barrier_level is relative to spot price
-    pub fn new(
+    pub fn single(
         barrier_level: f64,
-        in_out: bool,
-        up_down: bool,
+        direction: BarrierDirection,
+        knock_type: KnockType,
         relative: bool,
     ) -> Self {
         Self {
             barrier_level,
-            in_out,
-            up_down,
+            direction,
+            knock_type,
             barrier_type: BarrierType::WorstOf, // Default for single underlying
             relative,
             underlying_indices: vec![0], // Single underlying at index 0
+            monitoring: BarrierMonitoring::Continuous,
+            observation: BarrierObservation::Configured,
+            rebate: None,
         }
     }
 
-    /// Creates a new barrier for multiple underlyings
+    /// Creates a new barrier for a single underlying from bool flags
     ///
     /// # Arguments
-    /// * `barrier_level` - Barrier level (must be relative if multiple underlyings)
+    /// * `barrier_level` - Barrier level (absolute or relative)
     /// * `in_out` - `true` for "in" barrier, `false` for "out" barrier
     /// * `up_down` - `true` for "up" barrier, `false` for "down" barrier
+    /// * `relative` - `true` if barrier_level is relative to spot price
+    #[deprecated(note = "use `Barrier::single` with `BarrierDirection` and `KnockType`")]
+    pub fn new(barrier_level: f64, in_out: bool, up_down: bool, relative: bool) -> Self {
+        Self::single(
+            barrier_level,
+            BarrierDirection::from_is_up(up_down),
+            KnockType::from_is_in(in_out),
+            relative,
+        )
+    }
+
+    /// Creates a new barrier for multiple underlyings
+    ///
+    /// # Arguments
+    /// * `barrier_level` - Barrier level (must be relative if multiple underlyings)
+    /// * `direction` - Up or down barrier
+    /// * `knock_type` - Knock-in or knock-out barrier
     /// * `barrier_type` - Type of barrier (WorstOf, BestOf, Average, Median)
     /// * `relative` - `true` if barrier_level is relative to spot/avg/median
     /// * `underlying_indices` - Indices into the list of underlyings this barrier applies to
     ///
     /// # Errors
     /// Returns `BarrierError` if multiple underlyings are specified with an absolute barrier level
-    pub fn new_multi(
+    pub fn multi(
         barrier_level: f64,
-        in_out: bool,
-        up_down: bool,
+        direction: BarrierDirection,
+        knock_type: KnockType,
         barrier_type: BarrierType,
         relative: bool,
         underlying_indices: Vec<usize>,
@@ -103,12 +331,76 @@ impl Barrier {
 
         Ok(Self {
             barrier_level,
-            in_out,
-            up_down,
+            direction,
+            knock_type,
             barrier_type,
             relative,
             underlying_indices,
+            monitoring: BarrierMonitoring::Continuous,
+            observation: BarrierObservation::Configured,
+            rebate: None,
         })
     }
+
+    /// Creates a new barrier for multiple underlyings from bool flags
+    ///
+    /// # Arguments
+    /// * `barrier_level` - Barrier level (must be relative if multiple underlyings)
+    /// * `in_out` - `true` for "in" barrier, `false` for "out" barrier
+    /// * `up_down` - `true` for "up" barrier, `false` for "down" barrier
+    /// * `barrier_type` - Type of barrier (WorstOf, BestOf, Average, Median)
+    /// * `relative` - `true` if barrier_level is relative to spot/avg/median
+    /// * `underlying_indices` - Indices into the list of underlyings this barrier applies to
+    ///
+    /// # Errors
+    /// Returns `BarrierError` if multiple underlyings are specified with an absolute barrier level
+    #[deprecated(note = "use `Barrier::multi` with `BarrierDirection` and `KnockType`")]
+    pub fn new_multi(
+        barrier_level: f64,
+        in_out: bool,
+        up_down: bool,
+        barrier_type: BarrierType,
+        relative: bool,
+        underlying_indices: Vec<usize>,
+    ) -> Result<Self, BarrierError> {
+        Self::multi(
+            barrier_level,
+            BarrierDirection::from_is_up(up_down),
+            KnockType::from_is_in(in_out),
+            barrier_type,
+            relative,
+            underlying_indices,
+        )
+    }
+
+    /// Sets the days on which the barrier is observed, e.g. only at expiry
+    pub fn with_monitoring(mut self, monitoring: BarrierMonitoring) -> Self {
+        self.monitoring = monitoring;
+        self
+    }
+
+    /// Sets the time within the monitoring days at which the barrier is observed, e.g. on
+    /// the closes only
+    pub fn with_observation(mut self, observation: BarrierObservation) -> Self {
+        self.observation = observation;
+        self
+    }
+
+    /// Sets the rebate paid if the barrier deactivates the option
+    pub fn with_rebate(mut self, rebate: Rebate) -> Self {
+        self.rebate = Some(rebate);
+        self
+    }
+
+    /// Returns the monitoring correction of the barrier given the configured one
+    pub(crate) fn correction(&self, configured: BarrierCorrection) -> BarrierCorrection {
+        match self.observation {
+            BarrierObservation::Configured if self.monitoring == BarrierMonitoring::Continuous => {
+                configured
+            }
+            BarrierObservation::Configured | BarrierObservation::Close => BarrierCorrection::None,
+            BarrierObservation::Intraday => BarrierCorrection::BrownianBridge,
+        }
+    }
 }
 
@@ -0,0 +1,212 @@
+use nalgebra::DMatrix;
+
+use crate::config::SimulationConfig;
+use crate::error::McError;
+use crate::math;
+use crate::path_payoff::{price_path_payoff, PathPayoff, PathStep};
+use crate::payoff::{Averaging, FixingSchedule, OptionType};
+use crate::rates::RateCurve;
+use crate::result::PricingResult;
+use crate::underlying::Underlying;
+use crate::{intrinsic_value, validation, with_start_values};
+
+/// Option on a basket settled on its average level over the fixings before expiry, like
+/// index-linked notes settling on averaged closing levels
+///
+/// The basket level on a fixing day is the weighted sum of the prices of the underlyings,
+/// or of their performances against the initial prices with `normalized` set. The option
+/// pays `max(A - K, 0)` (calls) or `max(K - A, 0)` (puts) on the average `A` of the basket
+/// levels on the fixing days.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BasketOption {
+    /// Strike on the averaged basket level (a performance, e.g. 1.0, if `normalized`)
+    pub strike_price: f64,
+    /// Call or Put option
+    pub option_type: OptionType,
+    /// Weight of each underlying in the basket
+    pub weights: Vec<f64>,
+    /// `true` if the basket weighs the performances of the underlyings (price over initial
+    /// price) instead of their prices
+    pub normalized: bool,
+    /// Arithmetic or geometric averaging of the basket levels
+    pub averaging: Averaging,
+    /// Days on which the basket level is fixed, e.g. the last five closes
+    pub schedule: FixingSchedule,
+}
+
+impl BasketOption {
+    /// Creates an option on the basket level at expiry, weighing the prices of the
+    /// underlyings
+    pub fn new(strike_price: f64, option_type: OptionType, weights: Vec<f64>) -> Self {
+        Self {
+            strike_price,
+            option_type,
+            weights,
+            normalized: false,
+            averaging: Averaging::Arithmetic,
+            schedule: FixingSchedule::LastN(1),
+        }
+    }
+
+    /// Weighs the performances of the underlyings instead of their prices
+    pub fn with_normalized_levels(mut self) -> Self {
+        self.normalized = true;
+        self
+    }
+
+    /// Averages the basket levels with the given method
+    pub fn with_averaging(mut self, averaging: Averaging) -> Self {
+        self.averaging = averaging;
+        self
+    }
+
+    /// Fixes the basket level on the days of the given schedule
+    pub fn with_schedule(mut self, schedule: FixingSchedule) -> Self {
+        self.schedule = schedule;
+        self
+    }
+}
+
+/// Settlement of a basket option on the simulated paths
+struct BasketSettlement<'a> {
+    option: &'a BasketOption,
+    /// Weight of each underlying's price in the basket level
+    coefficients: Vec<f64>,
+    /// Sorted fixing days
+    fixing_days: Vec<u32>,
+    time_horizon_days: u32,
+}
+
+impl BasketSettlement<'_> {
+    fn level(&self, prices: &[f64]) -> f64 {
+        self.coefficients
+            .iter()
+            .zip(prices)
+            .map(|(coefficient, price)| coefficient * price)
+            .sum()
+    }
+
+    /// Returns the contribution of a basket level to the running sum of the average
+    fn fixing(&self, level: f64) -> f64 {
+        match self.option.averaging {
+            Averaging::Arithmetic => level,
+            Averaging::Geometric => math::ln(level),
+        }
+    }
+}
+
+impl PathPayoff for BasketSettlement<'_> {
+    /// Sum of the fixings (of their logarithms for geometric averaging)
+    type State = f64;
+
+    fn initial_state(&self, _initial_prices: &[f64]) -> f64 {
+        0.0
+    }
+
+    fn on_step(&self, state: &mut f64, step: &PathStep) {
+        if self.fixing_days.binary_search(&(step.step as u32)).is_ok() {
+            *state += self.fixing(self.level(step.prices));
+        }
+    }
+
+    fn evaluate(&self, &sum: &f64, final_prices: &[f64]) -> f64 {
+        // Terminal settlements skip the daily steps
+        let sum = if self.is_terminal() {
+            self.fixing(self.level(final_prices))
+        } else {
+            sum
+        };
+        let mean = sum / self.fixing_days.len() as f64;
+        let average = match self.option.averaging {
+            Averaging::Arithmetic => mean,
+            Averaging::Geometric => math::exp(mean),
+        };
+        intrinsic_value(average, self.option.strike_price, self.option.option_type)
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.fixing_days == [self.time_horizon_days]
+    }
+}
+
+/// Prices an option on a basket settled on its average level over the fixings before expiry
+///
+/// The paths take one step per day (see `price_path_payoff`), unless the basket is only
+/// fixed at expiry, in which case they are simulated directly to expiry.
+///
+/// # Arguments
+/// * `underlyings` - List of underlying assets of the basket
+/// * `correlation_matrix` - Correlation matrix (n x n) where n is the number of underlyings.
+///   Must be symmetric, positive semi-definite, with 1.0 on the diagonal.
+/// * `time_horizon_days` - Time to expiration in days
+/// * `option` - Basket option with its settlement rule
+/// * `risk_free_rate` - Risk-free rate curve or flat annual rate (as a decimal, e.g., 0.05 for 5%)
+/// * `config` - Number of paths and variance reduction settings (see `price_path_payoff`).
+///   Performances are measured against the start values of the configuration, if any.
+///
+/// # Returns
+/// The estimated price together with its standard error
+///
+/// # Errors
+/// Returns `McError::InvalidProduct` if the strike is negative or not finite, or the weights
+/// do not match the underlyings, are negative or not finite, or are all zero, and
+/// `McError::InvalidSchedule` if the fixing days lie outside of the life of the option.
+/// Returns an error for invalid market data (see `price_option`).
+pub fn price_basket_option(
+    underlyings: &[Underlying],
+    correlation_matrix: &DMatrix<f64>,
+    time_horizon_days: u32,
+    option: &BasketOption,
+    risk_free_rate: impl Into<RateCurve>,
+    config: &SimulationConfig,
+) -> Result<PricingResult, McError> {
+    validation::validate_strike(option.strike_price)?;
+    if option.weights.len() != underlyings.len() {
+        return Err(McError::InvalidProduct(format!(
+            "basket has {} weights for {} underlyings",
+            option.weights.len(),
+            underlyings.len()
+        )));
+    }
+    if option
+        .weights
+        .iter()
+        .any(|weight| !(weight.is_finite() && *weight >= 0.0))
+        || option.weights.iter().all(|&weight| weight == 0.0)
+    {
+        return Err(McError::InvalidProduct(format!(
+            "basket weights must be non-negative and finite, and not all zero, got {:?}",
+            option.weights
+        )));
+    }
+    let fixing_days = option.schedule.fixing_days(time_horizon_days)?;
+
+    let start_values = with_start_values(underlyings, config)?;
+    let coefficients = option
+        .weights
+        .iter()
+        .zip(start_values.iter())
+        .map(|(weight, underlying)| {
+            if option.normalized {
+                weight / underlying.spot_price
+            } else {
+                *weight
+            }
+        })
+        .collect();
+    let settlement = BasketSettlement {
+        option,
+        coefficients,
+        fixing_days,
+        time_horizon_days,
+    };
+    price_path_payoff(
+        underlyings,
+        correlation_matrix,
+        time_horizon_days,
+        &settlement,
+        risk_free_rate,
+        config,
+    )
+}
@@ -0,0 +1,362 @@
+use std::time::{Duration, Instant};
+
+use crate::aggregation::{aggregate_results, GroupTotal, GroupingLevel};
+use crate::config::{ErrorTolerance, SimulationConfig};
+use crate::error::McError;
+use crate::market::MarketSnapshot;
+use crate::path_estimate::estimate_paths_needed;
+use crate::price_payoff;
+use crate::product::Product;
+use crate::result::{PricingResult, PricingWarning};
+
+/// Priority of a trade in a batch run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Priority {
+    /// Always priced with the configured number of paths, before any low priority trade
+    #[default]
+    High,
+    /// Priced with fewer paths if needed to finish by the deadline
+    Low,
+}
+
+/// Accuracy a batch trade is priced to: a standard error in basis points of a notional
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AccuracyTarget {
+    /// Standard error of the value of the trade in basis points of the notional
+    pub bps: f64,
+    /// Notional the basis points refer to, in units of the value of the trade
+    pub notional: f64,
+}
+
+impl AccuracyTarget {
+    /// Creates a target of `bps` basis points of `notional`
+    pub fn new(bps: f64, notional: f64) -> Self {
+        Self { bps, notional }
+    }
+
+    /// Returns the standard error of the value of the trade the target requires
+    ///
+    /// # Errors
+    /// Returns `McError::InvalidProduct` if the basis points are not positive or the
+    /// notional is zero or not finite.
+    pub fn target_std_error(&self) -> Result<f64, McError> {
+        let std_error = self.bps * 1e-4 * self.notional.abs();
+        if std_error > 0.0 && std_error.is_finite() {
+            Ok(std_error)
+        } else {
+            Err(McError::InvalidProduct(format!(
+                "accuracy target of {} bps of a notional of {} is not a positive standard error",
+                self.bps, self.notional
+            )))
+        }
+    }
+}
+
+/// Trade of a batch run: a named position in a product with its maturity and priority
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BatchTrade {
+    /// Name of the trade
+    pub name: String,
+    /// Product to price
+    pub product: Product,
+    /// Number of units of the product held, negative for short positions; 1 by default
+    #[cfg_attr(feature = "serde", serde(default = "unit_quantity"))]
+    pub quantity: f64,
+    /// Time to expiration in days
+    pub maturity_days: u32,
+    /// Priority of the trade, high by default
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub priority: Priority,
+    /// Optional accuracy to price the trade to instead of the configured number of paths
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub accuracy: Option<AccuracyTarget>,
+    /// Optional book (desk) the trade is booked in, to aggregate results by
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub book: Option<String>,
+    /// Optional counterparty of the trade; its trades form one netting set
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub counterparty: Option<String>,
+    /// Optional trading strategy the trade belongs to
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub strategy: Option<String>,
+}
+
+impl BatchTrade {
+    /// Creates a high priority trade in one unit of the product
+    pub fn new(name: String, product: Product, maturity_days: u32) -> Self {
+        Self {
+            name,
+            product,
+            quantity: 1.0,
+            maturity_days,
+            priority: Priority::High,
+            accuracy: None,
+            book: None,
+            counterparty: None,
+            strategy: None,
+        }
+    }
+
+    /// Sets the number of units held, negative for short positions
+    pub fn with_quantity(mut self, quantity: f64) -> Self {
+        self.quantity = quantity;
+        self
+    }
+
+    /// Sets the priority of the trade
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Prices the trade to the given accuracy instead of the configured number of paths
+    pub fn with_accuracy(mut self, accuracy: AccuracyTarget) -> Self {
+        self.accuracy = Some(accuracy);
+        self
+    }
+
+    /// Sets the book the trade is booked in
+    pub fn with_book(mut self, book: impl Into<String>) -> Self {
+        self.book = Some(book.into());
+        self
+    }
+
+    /// Sets the counterparty of the trade
+    pub fn with_counterparty(mut self, counterparty: impl Into<String>) -> Self {
+        self.counterparty = Some(counterparty.into());
+        self
+    }
+
+    /// Sets the trading strategy the trade belongs to
+    pub fn with_strategy(mut self, strategy: impl Into<String>) -> Self {
+        self.strategy = Some(strategy.into());
+        self
+    }
+
+    /// Returns the name of the group the trade belongs to on the given level, if any
+    pub fn group(&self, level: GroupingLevel) -> Option<&str> {
+        match level {
+            GroupingLevel::Book => self.book.as_deref(),
+            GroupingLevel::Counterparty => self.counterparty.as_deref(),
+            GroupingLevel::Strategy => self.strategy.as_deref(),
+        }
+    }
+
+    /// Returns the value of the position: the price scaled by the quantity
+    pub(crate) fn value(&self, unit_price: PricingResult) -> Result<PricingResult, McError> {
+        if !self.quantity.is_finite() {
+            return Err(McError::InvalidProduct(format!(
+                "quantity of trade {} must be finite, got {}",
+                self.name, self.quantity
+            )));
+        }
+        Ok(unit_price.scaled(self.quantity))
+    }
+}
+
+/// Default quantity of a deserialized trade
+#[cfg(feature = "serde")]
+fn unit_quantity() -> f64 {
+    1.0
+}
+
+/// Prices of the trades of a batch run
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BatchResult {
+    /// Value of each trade in the order of the trades, or the reason it was rejected
+    pub results: Vec<Result<PricingResult, McError>>,
+    /// Wall-clock time the run took in seconds
+    pub elapsed_seconds: f64,
+}
+
+impl BatchResult {
+    /// Returns the total value of the trades (see `total_value`)
+    ///
+    /// # Errors
+    /// Returns the error of the first rejected trade.
+    pub fn total(&self) -> Result<PricingResult, McError> {
+        total_value(&self.results)
+    }
+
+    /// Returns the totals of the groups of trades along the given hierarchy (see
+    /// `aggregate_results`)
+    ///
+    /// # Errors
+    /// Returns `McError::InvalidProduct` if `trades` are not the trades of the run.
+    pub fn aggregate(
+        &self,
+        trades: &[BatchTrade],
+        levels: &[GroupingLevel],
+    ) -> Result<Vec<GroupTotal>, McError> {
+        aggregate_results(trades, &self.results, levels)
+    }
+
+    /// Returns the number of trades priced with fewer paths than their full accuracy needs
+    /// to meet the deadline
+    pub fn num_reduced(&self) -> usize {
+        self.results
+            .iter()
+            .flatten()
+            .filter(|result| {
+                result
+                    .warnings
+                    .iter()
+                    .any(|warning| matches!(warning, PricingWarning::ReducedPaths { .. }))
+            })
+            .count()
+    }
+}
+
+/// Returns the total value of priced trades, long and short positions netted, with the sum
+/// of their standard errors, an upper bound of the standard error of the total
+///
+/// The total carries the largest number of paths of a trade, and no bounds or warnings.
+///
+/// # Errors
+/// Returns the error of the first rejected trade.
+pub(crate) fn total_value(
+    results: &[Result<PricingResult, McError>],
+) -> Result<PricingResult, McError> {
+    results
+        .iter()
+        .try_fold(PricingResult::new(0.0, 0.0, 0), |total, result| {
+            let result = result.as_ref().map_err(Clone::clone)?;
+            Ok(PricingResult::new(
+                total.price + result.price,
+                total.std_error + result.std_error,
+                total.num_paths.max(result.num_paths),
+            ))
+        })
+}
+
+/// Prices a batch of trades on one market, finishing by a wall-clock deadline
+///
+/// High priority trades are priced first with full accuracy: the configured number of paths,
+/// or for trades with an accuracy target the number of paths a pilot run of
+/// `config.batch_paths` paths estimates it needs (see `estimate_paths_needed`), stopping
+/// early once the target is met. The time left until the deadline is then shared equally
+/// among the low priority trades: each is priced in a pilot run of `config.batch_paths`
+/// paths, whose timing gives the number of paths that fit into its share. Trades priced with
+/// fewer paths than their full accuracy needs are flagged with `PricingWarning::ReducedPaths`;
+/// if the deadline has passed, the pilot run is their price. A trade that cannot be priced
+/// is rejected without aborting the run.
+///
+/// The result of each trade is the value of the position: its price, standard error and
+/// bounds scaled by its quantity, so short positions are worth the negated price and the
+/// results add up to the value of the book.
+///
+/// # Arguments
+/// * `market` - Market all trades are priced on
+/// * `trades` - Trades to price
+/// * `config` - Number of paths and variance reduction settings of a full accuracy price;
+///   trades with an accuracy target ignore the number of paths and the error tolerance
+/// * `deadline` - Time by which the run should finish, or `None` to price all trades with
+///   full accuracy
+///
+/// # Returns
+/// The value of each trade, or the reason it was rejected (see `price_payoff`)
+pub fn price_batch(
+    market: &MarketSnapshot,
+    trades: &[BatchTrade],
+    config: &SimulationConfig,
+    deadline: Option<Instant>,
+) -> BatchResult {
+    let start = Instant::now();
+    let price = |trade: &BatchTrade, config: &SimulationConfig| {
+        price_payoff(
+            &market.underlyings,
+            &market.correlation_matrix,
+            trade.maturity_days,
+            &trade.product.payoff,
+            market.risk_free_rate.clone(),
+            trade.product.barrier.as_ref(),
+            config,
+        )
+        .and_then(|result| trade.value(result))
+    };
+
+    let pilot_paths = config.batch_paths.clamp(1, config.num_paths);
+    let with_paths = |config: &SimulationConfig, num_paths| SimulationConfig {
+        num_paths,
+        ..config.clone()
+    };
+    // Configuration of a full accuracy price of the trade
+    let full_config = |trade: &BatchTrade| match &trade.accuracy {
+        None => Ok(config.clone()),
+        Some(accuracy) => {
+            // The target applies to the value of the position
+            let tolerance =
+                ErrorTolerance::Absolute(accuracy.target_std_error()? / trade.quantity.abs());
+            let estimate = estimate_paths_needed(
+                market,
+                trade.maturity_days,
+                &trade.product,
+                tolerance,
+                &with_paths(config, pilot_paths),
+            )?;
+            Ok(SimulationConfig {
+                num_paths: estimate.paths_needed.unwrap_or(config.num_paths),
+                error_tolerance: Some(tolerance),
+                ..config.clone()
+            })
+        }
+    };
+
+    let mut results: Vec<Option<Result<PricingResult, McError>>> = vec![None; trades.len()];
+    for (index, trade) in trades.iter().enumerate() {
+        if trade.priority == Priority::High {
+            results[index] = Some(full_config(trade).and_then(|full| price(trade, &full)));
+        }
+    }
+
+    let low_priority: Vec<usize> = (0..trades.len())
+        .filter(|&index| trades[index].priority == Priority::Low)
+        .collect();
+    for (position, &index) in low_priority.iter().enumerate() {
+        let trade = &trades[index];
+        let full = match full_config(trade) {
+            Ok(full) => full,
+            Err(error) => {
+                results[index] = Some(Err(error));
+                continue;
+            }
+        };
+        let Some(deadline) = deadline else {
+            results[index] = Some(price(trade, &full));
+            continue;
+        };
+        let time_left = deadline.saturating_duration_since(Instant::now());
+        let share = time_left / (low_priority.len() - position) as u32;
+
+        let pilot_start = Instant::now();
+        let pilot = price(trade, &with_paths(&full, pilot_paths));
+        let pilot_time = pilot_start.elapsed().max(Duration::from_nanos(1));
+        // Paths that fit into the share left after the pilot run
+        let remaining = share.saturating_sub(pilot_time);
+        let num_paths = ((pilot_paths as f64 * remaining.as_secs_f64() / pilot_time.as_secs_f64())
+            as u64)
+            .min(full.num_paths);
+        let (result, num_paths) = if pilot.is_ok() && num_paths > pilot_paths {
+            (price(trade, &with_paths(&full, num_paths)), num_paths)
+        } else {
+            (pilot, pilot_paths)
+        };
+        results[index] = Some(result.map(|mut result| {
+            if num_paths < full.num_paths {
+                result.warnings.push(PricingWarning::ReducedPaths {
+                    requested_paths: full.num_paths,
+                });
+            }
+            result
+        }));
+    }
+
+    BatchResult {
+        results: results.into_iter().flatten().collect(),
+        elapsed_seconds: start.elapsed().as_secs_f64(),
+    }
+}
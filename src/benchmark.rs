@@ -0,0 +1,248 @@
+use nalgebra::DMatrix;
+
+use crate::barrier::{Barrier, BarrierDirection, BarrierObservation, KnockType};
+use crate::closed_form::{
+    black_scholes_barrier_price, black_scholes_price, geometric_average_price,
+};
+use crate::config::SimulationConfig;
+use crate::error::McError;
+use crate::market::MarketSnapshot;
+use crate::payoff::{Averaging, FixingSchedule, OptionType, Payoff};
+use crate::price_payoff;
+use crate::product::Product;
+use crate::result::PricingResult;
+use crate::underlying::Underlying;
+
+/// Number of standard errors a benchmark price may deviate from its reference by
+pub const BENCHMARK_STD_ERRORS: f64 = 4.0;
+
+/// Spot price of the underlying of every benchmark case
+const SPOT: f64 = 100.0;
+
+/// Risk-free rate of every benchmark case
+const RATE: f64 = 0.05;
+
+/// Canonical product with an analytic reference price, to check that an installation
+/// prices correctly
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BenchmarkCase {
+    /// Short description of the product, e.g. `down-and-out call`
+    pub name: String,
+    /// Market of the single underlying
+    pub market: MarketSnapshot,
+    /// Time to expiration of the product in days
+    pub time_horizon_days: u32,
+    /// Product to price
+    pub product: Product,
+    /// Analytic price of the product
+    pub reference_price: f64,
+    /// Deviation from the reference allowed beyond the Monte Carlo error, for references
+    /// that are approximations themselves (zero for exact references)
+    pub tolerance: f64,
+}
+
+/// Monte Carlo price of a benchmark case compared with its reference price
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BenchmarkOutcome {
+    /// Name of the benchmark case
+    pub name: String,
+    /// Analytic price of the product
+    pub reference_price: f64,
+    /// Monte Carlo price of the product
+    pub pricing: PricingResult,
+    /// `true` if the price lies within `BENCHMARK_STD_ERRORS` standard errors plus the
+    /// tolerance of the case from the reference
+    pub passed: bool,
+}
+
+impl BenchmarkOutcome {
+    /// Returns the difference between the Monte Carlo price and the reference price
+    pub fn error(&self) -> f64 {
+        self.pricing.price - self.reference_price
+    }
+}
+
+impl BenchmarkCase {
+    /// Prices the product by Monte Carlo and compares the price with the reference
+    ///
+    /// # Arguments
+    /// * `config` - Number of paths, seed, sampling and variance reduction settings. The
+    ///   references assume the day count, variance clock and Black-Scholes model of
+    ///   `SimulationConfig::new`, which replace the configured ones.
+    ///
+    /// # Errors
+    /// Returns an error if the product cannot be priced (see `price_payoff`).
+    pub fn run(&self, config: &SimulationConfig) -> Result<BenchmarkOutcome, McError> {
+        let defaults = SimulationConfig::new(config.num_paths);
+        let config = SimulationConfig {
+            day_count: defaults.day_count,
+            variance_time: defaults.variance_time,
+            model: defaults.model,
+            ..config.clone()
+        };
+        let pricing = price_payoff(
+            &self.market.underlyings,
+            &self.market.correlation_matrix,
+            self.time_horizon_days,
+            &self.product.payoff,
+            self.market.risk_free_rate.clone(),
+            self.product.barrier.as_ref(),
+            &config,
+        )?;
+        let allowed = BENCHMARK_STD_ERRORS * pricing.std_error + self.tolerance;
+        Ok(BenchmarkOutcome {
+            name: self.name.clone(),
+            reference_price: self.reference_price,
+            passed: (pricing.price - self.reference_price).abs() <= allowed,
+            pricing,
+        })
+    }
+}
+
+/// Returns the canonical benchmark products with their analytic reference prices
+///
+/// The cases cover vanilla options against Black-Scholes, single barrier options against
+/// Reiner-Rubinstein and geometric average-price options against their closed form, all on
+/// one underlying at a spot of 100 with a 5% rate. The barriers are observed intraday, so the
+/// daily simulation matches the continuously monitored references.
+pub fn benchmark_cases() -> Vec<BenchmarkCase> {
+    let vanilla = |name: &str, volatility: f64, days: u32, strike: f64, option_type| {
+        let time = days as f64 / 365.0;
+        BenchmarkCase {
+            name: name.to_string(),
+            market: market(volatility),
+            time_horizon_days: days,
+            product: Product::new(Payoff::Vanilla {
+                strike_price: strike,
+                option_type,
+            }),
+            reference_price: black_scholes_price(SPOT, strike, volatility, RATE, time, option_type),
+            tolerance: 0.0,
+        }
+    };
+    let barrier_option = |name: &str, level: f64, direction, knock_type, option_type| {
+        let (volatility, days, strike) = (0.25, 182, 100.0);
+        let barrier = Barrier {
+            observation: BarrierObservation::Intraday,
+            ..Barrier::single(level, direction, knock_type, false)
+        };
+        let reference_price = black_scholes_barrier_price(
+            SPOT,
+            strike,
+            &barrier,
+            volatility,
+            RATE,
+            days as f64 / 365.0,
+            option_type,
+        )
+        .expect("continuously monitored single barrier");
+        BenchmarkCase {
+            name: name.to_string(),
+            market: market(volatility),
+            time_horizon_days: days,
+            product: Product::new(Payoff::Vanilla {
+                strike_price: strike,
+                option_type,
+            })
+            .with_barrier(barrier),
+            reference_price,
+            tolerance: 0.0,
+        }
+    };
+    let geometric_asian = |name: &str, days: u32, option_type| {
+        let (volatility, strike, schedule) = (0.25, 100.0, FixingSchedule::Monthly);
+        let fixing_times: Vec<f64> = schedule
+            .fixing_days(days)
+            .expect("monthly fixings")
+            .iter()
+            .map(|&day| day as f64 / 365.0)
+            .collect();
+        let reference_price = geometric_average_price(
+            SPOT,
+            strike,
+            volatility,
+            RATE,
+            &fixing_times,
+            days as f64 / 365.0,
+            option_type,
+        )
+        .expect("at least one fixing");
+        BenchmarkCase {
+            name: name.to_string(),
+            market: market(volatility),
+            time_horizon_days: days,
+            product: Product::new(Payoff::AveragePrice {
+                strike_price: strike,
+                option_type,
+                averaging: Averaging::Geometric,
+                schedule,
+            }),
+            reference_price,
+            tolerance: 0.0,
+        }
+    };
+
+    vec![
+        vanilla("at-the-money call", 0.20, 365, 100.0, OptionType::Call),
+        vanilla("out-of-the-money put", 0.20, 182, 90.0, OptionType::Put),
+        vanilla("in-the-money call", 0.30, 91, 80.0, OptionType::Call),
+        barrier_option(
+            "down-and-out call",
+            90.0,
+            BarrierDirection::Down,
+            KnockType::Out,
+            OptionType::Call,
+        ),
+        barrier_option(
+            "down-and-in call",
+            90.0,
+            BarrierDirection::Down,
+            KnockType::In,
+            OptionType::Call,
+        ),
+        barrier_option(
+            "up-and-out put",
+            110.0,
+            BarrierDirection::Up,
+            KnockType::Out,
+            OptionType::Put,
+        ),
+        barrier_option(
+            "up-and-in call",
+            120.0,
+            BarrierDirection::Up,
+            KnockType::In,
+            OptionType::Call,
+        ),
+        geometric_asian("geometric average-price call", 180, OptionType::Call),
+        geometric_asian("geometric average-price put", 365, OptionType::Put),
+    ]
+}
+
+/// Prices every benchmark case by Monte Carlo and compares it with its reference price
+///
+/// A correct installation passes every case, up to the chance of a price lying more than
+/// `BENCHMARK_STD_ERRORS` standard errors from its expectation (below 1e-4 per case).
+///
+/// # Arguments
+/// * `config` - Simulation settings (see `BenchmarkCase::run`)
+///
+/// # Errors
+/// Returns the first error of a case that cannot be priced.
+pub fn run_benchmarks(config: &SimulationConfig) -> Result<Vec<BenchmarkOutcome>, McError> {
+    benchmark_cases()
+        .iter()
+        .map(|case| case.run(config))
+        .collect()
+}
+
+/// Returns the market of a single underlying at the benchmark spot and rate
+fn market(volatility: f64) -> MarketSnapshot {
+    MarketSnapshot::new(
+        vec![Underlying::new("BENCHMARK".to_string(), SPOT, volatility)],
+        DMatrix::identity(1, 1),
+        RATE,
+    )
+}
@@ -0,0 +1,277 @@
+use crate::barrier::{Rebate, RebateTiming};
+use crate::closed_form::black_scholes_price_with_dividends;
+use crate::config::DayCountConvention;
+use crate::payoff::{bounded, OptionType, Payoff};
+use crate::rates::RateCurve;
+use crate::underlying::Underlying;
+
+/// Lower and upper bounds a price must lie within to be free of arbitrage
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PriceBounds {
+    /// Lower bound of the price
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialization::extended_float"))]
+    pub lower: f64,
+    /// Upper bound of the price (may be infinite)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialization::extended_float"))]
+    pub upper: f64,
+}
+
+impl PriceBounds {
+    /// Returns `true` if the price lies within the bounds, allowing for the given tolerance
+    pub fn contains(&self, price: f64, tolerance: f64) -> bool {
+        price >= self.lower - tolerance && price <= self.upper + tolerance
+    }
+}
+
+/// Computes analytic no-arbitrage bounds for a European payoff on the given underlying
+///
+/// Vanilla options are bounded by their intrinsic value on the forward and by the prepaid
+/// forward (calls) or the discounted strike (puts). Barrier options are worth at least zero
+/// and at most the corresponding vanilla option, priced with Black-Scholes.
+///
+/// # Arguments
+/// * `underlying` - Underlying the payoff is written on
+/// * `payoff` - Payoff of the option
+/// * `rate_curve` - Risk-free rate curve
+/// * `time_to_expiration` - Time to expiration in years
+/// * `has_barrier` - `true` if the payoff is subject to a barrier
+/// * `day_count` - Convention converting the ex-dividend days into year fractions
+pub fn no_arbitrage_bounds(
+    underlying: &Underlying,
+    payoff: &Payoff,
+    rate_curve: &RateCurve,
+    time_to_expiration: f64,
+    has_barrier: bool,
+    day_count: DayCountConvention,
+) -> PriceBounds {
+    let spot_price = underlying.spot_price;
+    // Value of receiving the underlying at expiry, net of the dividends paid until then
+    let prepaid_forward = underlying.prepaid_forward(rate_curve, time_to_expiration, day_count);
+    let discount_factor = rate_curve.discount_factor(time_to_expiration);
+    // The discounted expectation of a fixing at t is at most S * DF(T) / DF(t), which is
+    // largest today, at expiry or at a pillar of the curve
+    let max_fixing_value = rate_curve
+        .tenors()
+        .iter()
+        .copied()
+        .filter(|&tenor| tenor < time_to_expiration)
+        .chain([0.0, time_to_expiration])
+        .map(|time| spot_price * discount_factor / rate_curve.discount_factor(time))
+        .fold(0.0, f64::max);
+
+    let (lower, upper) = match payoff {
+        Payoff::Vanilla {
+            strike_price,
+            option_type,
+        } => {
+            let discounted_strike = strike_price * discount_factor;
+            if has_barrier {
+                // A barrier can only remove value from the vanilla option
+                let vanilla = black_scholes_price_with_dividends(
+                    underlying,
+                    *strike_price,
+                    rate_curve,
+                    time_to_expiration,
+                    *option_type,
+                    day_count,
+                )
+                .unwrap_or(if option_type.is_call() {
+                    prepaid_forward
+                } else {
+                    discounted_strike
+                });
+                (0.0, vanilla)
+            } else if option_type.is_call() {
+                (
+                    (prepaid_forward - discounted_strike).max(0.0),
+                    prepaid_forward,
+                )
+            } else {
+                (
+                    (discounted_strike - prepaid_forward).max(0.0),
+                    discounted_strike,
+                )
+            }
+        }
+        Payoff::CashOrNothing { amount, .. } => (0.0, amount * discount_factor),
+        Payoff::AssetOrNothing {
+            strike_price,
+            option_type,
+        } => {
+            // Puts pay less than the strike
+            let upper = if option_type.is_call() {
+                prepaid_forward
+            } else {
+                prepaid_forward.min(strike_price * discount_factor)
+            };
+            (0.0, upper)
+        }
+        Payoff::AveragePrice {
+            strike_price,
+            option_type,
+            ..
+        } => {
+            let upper = if option_type.is_call() {
+                max_fixing_value
+            } else {
+                strike_price * discount_factor
+            };
+            (0.0, upper)
+        }
+        Payoff::AverageStrike { option_type, .. } => {
+            let upper = if option_type.is_call() {
+                spot_price
+            } else {
+                max_fixing_value
+            };
+            (0.0, upper)
+        }
+        Payoff::FloatingLookback { option_type } => {
+            // S_T - min(S) <= S_T, while max(S) - S_T has no model-free upper bound
+            let upper = if option_type.is_call() {
+                prepaid_forward
+            } else {
+                f64::INFINITY
+            };
+            (0.0, upper)
+        }
+        Payoff::FixedLookback {
+            strike_price,
+            option_type,
+        }
+        | Payoff::Ladder {
+            strike_price,
+            option_type,
+            ..
+        } => {
+            // Both dominate the vanilla option with the same strike; puts pay at most K
+            let discounted_strike = strike_price * discount_factor;
+            let (intrinsic, upper) = if option_type.is_call() {
+                (prepaid_forward - discounted_strike, f64::INFINITY)
+            } else {
+                (discounted_strike - prepaid_forward, discounted_strike)
+            };
+            let lower = if has_barrier { 0.0 } else { intrinsic.max(0.0) };
+            (lower, upper)
+        }
+        Payoff::Cliquet {
+            notional,
+            local_floor,
+            local_cap,
+            global_floor,
+            global_cap,
+            schedule,
+        } => {
+            // Every period returns between the local floor and cap
+            let days = (time_to_expiration * day_count.days_per_year()).round() as u32;
+            let num_periods = schedule.fixing_days(days).map_or(0, |days| days.len()) as f64;
+            let lowest = bounded(
+                local_floor.map_or(f64::NEG_INFINITY, |floor| num_periods * floor),
+                *global_floor,
+                *global_cap,
+            );
+            let highest = bounded(
+                local_cap.map_or(f64::INFINITY, |cap| num_periods * cap),
+                *global_floor,
+                *global_cap,
+            );
+            let (lower, upper) = (
+                notional * lowest * discount_factor,
+                notional * highest * discount_factor,
+            );
+            // A barrier may knock the payoff out to zero
+            if has_barrier {
+                (lower.min(0.0), upper.max(0.0))
+            } else {
+                (lower, upper)
+            }
+        }
+        Payoff::VarianceSwap {
+            notional,
+            strike_volatility,
+            ..
+        }
+        | Payoff::VolatilitySwap {
+            notional,
+            strike_volatility,
+            ..
+        } => {
+            // The realized volatility is non-negative and unbounded
+            let strike = match payoff {
+                Payoff::VarianceSwap { .. } => strike_volatility * strike_volatility,
+                _ => *strike_volatility,
+            };
+            let lower = -notional * strike * discount_factor;
+            if has_barrier {
+                (lower.min(0.0), f64::INFINITY)
+            } else {
+                (lower, f64::INFINITY)
+            }
+        }
+    };
+
+    PriceBounds { lower, upper }
+}
+
+/// Computes the upper bound of the present value of a barrier rebate: its amount discounted
+/// from expiry, or from the most favourable hit day for rebates paid at the hit
+pub fn rebate_upper_bound(rebate: &Rebate, rate_curve: &RateCurve, time_to_expiration: f64) -> f64 {
+    let discount_factor = match rebate.timing {
+        RebateTiming::AtExpiry => rate_curve.discount_factor(time_to_expiration),
+        // The discount factor is largest today, at expiry or at a pillar of the curve
+        RebateTiming::AtHit => rate_curve
+            .tenors()
+            .iter()
+            .copied()
+            .filter(|&tenor| tenor < time_to_expiration)
+            .chain([0.0, time_to_expiration])
+            .map(|time| rate_curve.discount_factor(time))
+            .fold(0.0, f64::max),
+    };
+    rebate.amount.max(0.0) * discount_factor
+}
+
+/// Computes analytic no-arbitrage bounds for an American or Bermudan option
+///
+/// The option is worth at least the European option (exercise at expiry is always allowed)
+/// and at most the spot (calls) or the strike (puts). With cash dividends, the European
+/// option is bounded from below by its intrinsic value on the forward instead.
+pub fn american_bounds(
+    underlying: &Underlying,
+    strike_price: f64,
+    option_type: OptionType,
+    rate_curve: &RateCurve,
+    time_to_expiration: f64,
+    day_count: DayCountConvention,
+) -> PriceBounds {
+    let spot_price = underlying.spot_price;
+    let european = black_scholes_price_with_dividends(
+        underlying,
+        strike_price,
+        rate_curve,
+        time_to_expiration,
+        option_type,
+        day_count,
+    )
+    .unwrap_or_else(|| {
+        let forward_value = underlying.prepaid_forward(rate_curve, time_to_expiration, day_count);
+        let discounted_strike = strike_price * rate_curve.discount_factor(time_to_expiration);
+        let intrinsic = if option_type.is_call() {
+            forward_value - discounted_strike
+        } else {
+            discounted_strike - forward_value
+        };
+        intrinsic.max(0.0)
+    });
+    let upper = if option_type.is_call() {
+        spot_price
+    } else {
+        strike_price
+    };
+
+    PriceBounds {
+        lower: european,
+        upper,
+    }
+}
@@ -0,0 +1,275 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::config::DayCountConvention;
+use crate::error::McError;
+
+/// Day of the week
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Weekday {
+    const ALL: [Weekday; 7] = [
+        Weekday::Monday,
+        Weekday::Tuesday,
+        Weekday::Wednesday,
+        Weekday::Thursday,
+        Weekday::Friday,
+        Weekday::Saturday,
+        Weekday::Sunday,
+    ];
+
+    /// Returns the weekday the given number of days later
+    pub fn plus_days(self, days: u32) -> Weekday {
+        Self::ALL[(self as usize + days as usize % 7) % 7]
+    }
+
+    /// Returns `true` for Saturday and Sunday
+    pub fn is_weekend(self) -> bool {
+        matches!(self, Weekday::Saturday | Weekday::Sunday)
+    }
+}
+
+/// Counts the business days (weekdays) among the days `start_day + 1..=end_day`, counted from
+/// a valuation day falling on `valuation_weekday`
+pub fn business_days_between(valuation_weekday: Weekday, start_day: u32, end_day: u32) -> u32 {
+    (start_day + 1..=end_day)
+        .filter(|&day| !valuation_weekday.plus_days(day).is_weekend())
+        .count() as u32
+}
+
+/// Date of the (proleptic) Gregorian calendar, e.g. a valuation, maturity or ex-dividend date
+///
+/// Dates are converted into the day arguments of the pricers with
+/// `DayCountConvention::days_between`, counted from the valuation date. They are written and
+/// parsed as `YYYY-MM-DD`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String", into = "String")
+)]
+pub struct Date {
+    year: i32,
+    month: u32,
+    day: u32,
+}
+
+impl Date {
+    /// Creates the date with the given year, month (1 to 12) and day of the month
+    ///
+    /// # Errors
+    /// Returns `McError::InvalidDate` if the month or the day of the month does not exist.
+    pub fn new(year: i32, month: u32, day: u32) -> Result<Self, McError> {
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            return Err(McError::InvalidDate(format!(
+                "{:04}-{:02}-{:02} does not exist",
+                year, month, day
+            )));
+        }
+        Ok(Self { year, month, day })
+    }
+
+    /// Returns the year
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+
+    /// Returns the month, from 1 for January to 12 for December
+    pub fn month(&self) -> u32 {
+        self.month
+    }
+
+    /// Returns the day of the month, from 1
+    pub fn day(&self) -> u32 {
+        self.day
+    }
+
+    /// Returns the day of the week
+    pub fn weekday(&self) -> Weekday {
+        // 1970-01-01 was a Thursday
+        Weekday::ALL[(self.days_since_epoch() + 3).rem_euclid(7) as usize]
+    }
+
+    /// Returns the date the given number of days later (earlier for negative days)
+    pub fn plus_days(&self, days: i64) -> Date {
+        Self::from_days_since_epoch(self.days_since_epoch() + days)
+    }
+
+    /// Returns the number of calendar days from `start` to this date, negative if `start` is
+    /// later
+    pub fn days_since(&self, start: Date) -> i64 {
+        self.days_since_epoch() - start.days_since_epoch()
+    }
+
+    /// Returns `true` if this is the last day of its month
+    pub fn is_end_of_month(&self) -> bool {
+        self.day == days_in_month(self.year, self.month)
+    }
+
+    /// Returns the number of days since 1970-01-01
+    fn days_since_epoch(&self) -> i64 {
+        // Counts the years from March, so the leap day is the last day of a year
+        let year = i64::from(self.year) - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let year_of_era = year.rem_euclid(400);
+        let month = i64::from(self.month);
+        let day_of_year =
+            (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(self.day) - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146_097 + day_of_era - 719_468
+    }
+
+    /// Returns the date the given number of days after 1970-01-01
+    fn from_days_since_epoch(days: i64) -> Date {
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        } as u32;
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        Date {
+            year: year as i32,
+            month,
+            day,
+        }
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl FromStr for Date {
+    type Err = McError;
+
+    /// Parses a date written as `YYYY-MM-DD`
+    fn from_str(text: &str) -> Result<Self, McError> {
+        let invalid = || McError::InvalidDate(format!("expected YYYY-MM-DD, got {:?}", text));
+        let mut parts = text.trim().splitn(3, '-');
+        let mut next = || parts.next().ok_or_else(invalid);
+        let (year, month, day) = (next()?, next()?, next()?);
+        Date::new(
+            year.parse().map_err(|_| invalid())?,
+            month.parse().map_err(|_| invalid())?,
+            day.parse().map_err(|_| invalid())?,
+        )
+    }
+}
+
+impl TryFrom<String> for Date {
+    type Error = McError;
+
+    fn try_from(text: String) -> Result<Self, McError> {
+        text.parse()
+    }
+}
+
+impl From<Date> for String {
+    fn from(date: Date) -> Self {
+        date.to_string()
+    }
+}
+
+/// Returns `true` if the year has a 29th of February
+fn is_leap_year(year: i32) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+/// Returns the number of days of the month of the year
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Calendar of the business days of a market: the weekdays that are not holidays
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HolidayCalendar {
+    /// Holidays falling on weekdays; weekends are never business days
+    holidays: BTreeSet<Date>,
+}
+
+impl HolidayCalendar {
+    /// Creates a calendar with the given holidays
+    pub fn new(holidays: impl IntoIterator<Item = Date>) -> Self {
+        Self {
+            holidays: holidays.into_iter().collect(),
+        }
+    }
+
+    /// Creates a calendar whose business days are all weekdays
+    pub fn weekends_only() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if the date is neither on a weekend nor a holiday
+    pub fn is_business_day(&self, date: Date) -> bool {
+        !date.weekday().is_weekend() && !self.holidays.contains(&date)
+    }
+
+    /// Returns the date if it is a business day, and the following business day otherwise
+    pub fn following(&self, date: Date) -> Date {
+        let mut date = date;
+        while !self.is_business_day(date) {
+            date = date.plus_days(1);
+        }
+        date
+    }
+
+    /// Returns the number of business days after `start` up to and including `end`, zero if
+    /// `end` is not after `start`
+    pub fn business_days_between(&self, start: Date, end: Date) -> u32 {
+        (1..=end.days_since(start))
+            .filter(|&days| self.is_business_day(start.plus_days(days)))
+            .count() as u32
+    }
+
+    /// Returns the business days after the valuation date up to and including the maturity,
+    /// as days of the convention counted from the valuation date
+    ///
+    /// Use them to monitor a barrier on the business days only, e.g. with
+    /// `BarrierMonitoring::Dates`.
+    ///
+    /// # Errors
+    /// Returns `McError::InvalidDate` if the maturity lies before the valuation date.
+    pub fn business_days(
+        &self,
+        valuation: Date,
+        maturity: Date,
+        day_count: DayCountConvention,
+    ) -> Result<Vec<u32>, McError> {
+        day_count.days_between(valuation, maturity, self)?;
+        let mut days = (1..=maturity.days_since(valuation))
+            .map(|days| valuation.plus_days(days))
+            .filter(|&date| self.is_business_day(date))
+            .map(|date| day_count.days_between(valuation, date, self))
+            .collect::<Result<Vec<u32>, McError>>()?;
+        // Under 30/360, the 30th and 31st of a month fall on the same day
+        days.dedup();
+        Ok(days)
+    }
+}
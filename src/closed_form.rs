@@ -0,0 +1,560 @@
+use crate::barrier::{Barrier, BarrierDirection, BarrierMonitoring, KnockType, RebateTiming};
+use crate::config::DayCountConvention;
+use crate::math;
+use crate::payoff::OptionType;
+use crate::rates::RateCurve;
+use crate::underlying::Underlying;
+
+/// Standard normal cumulative distribution function
+///
+/// Uses Hart's double precision approximation (as given by West, 2005),
+/// accurate to roughly machine precision across the whole real line.
+pub fn norm_cdf(x: f64) -> f64 {
+    let x_abs = x.abs();
+    let tail = if x_abs > 37.0 {
+        0.0
+    } else {
+        let exponential = math::exp(-x_abs * x_abs / 2.0);
+        if x_abs < 7.071_067_811_865_47 {
+            let mut numerator = 3.526_249_659_989_11e-2 * x_abs + 0.700_383_064_443_688;
+            numerator = numerator * x_abs + 6.373_962_203_531_65;
+            numerator = numerator * x_abs + 33.912_866_078_383;
+            numerator = numerator * x_abs + 112.079_291_497_871;
+            numerator = numerator * x_abs + 221.213_596_169_931;
+            numerator = numerator * x_abs + 220.206_867_912_376;
+            let mut denominator = 8.838_834_764_831_84e-2 * x_abs + 1.755_667_163_182_64;
+            denominator = denominator * x_abs + 16.064_177_579_207;
+            denominator = denominator * x_abs + 86.780_732_202_946_1;
+            denominator = denominator * x_abs + 296.564_248_779_674;
+            denominator = denominator * x_abs + 637.333_633_378_831;
+            denominator = denominator * x_abs + 793.826_512_519_948;
+            denominator = denominator * x_abs + 440.413_735_824_752;
+            exponential * numerator / denominator
+        } else {
+            let mut continued_fraction = x_abs + 0.65;
+            continued_fraction = x_abs + 4.0 / continued_fraction;
+            continued_fraction = x_abs + 3.0 / continued_fraction;
+            continued_fraction = x_abs + 2.0 / continued_fraction;
+            continued_fraction = x_abs + 1.0 / continued_fraction;
+            exponential / continued_fraction / 2.506_628_274_631
+        }
+    };
+
+    if x > 0.0 {
+        1.0 - tail
+    } else {
+        tail
+    }
+}
+
+/// Standard normal probability density function
+pub fn norm_pdf(x: f64) -> f64 {
+    math::exp(-0.5 * x * x) / (2.0 * std::f64::consts::PI).sqrt()
+}
+
+/// Inverse of the standard normal cumulative distribution function
+///
+/// Uses Acklam's rational approximation with a relative error below 1.2e-9, accurate enough
+/// to map quasi-random uniforms to normal shocks. Returns infinities for `p` outside (0, 1).
+pub fn inverse_norm_cdf(p: f64) -> f64 {
+    const P_LOW: f64 = 0.024_25;
+    if p <= 0.0 {
+        return f64::NEG_INFINITY;
+    }
+    if p >= 1.0 {
+        return f64::INFINITY;
+    }
+    // Tails: rational function of sqrt(-2 ln p)
+    let tail = |p: f64| {
+        let q = (-2.0 * math::ln(p)).sqrt();
+        let mut numerator = -7.784_894_002_430_293e-3 * q - 3.223_964_580_411_365e-1;
+        numerator = numerator * q - 2.400_758_277_161_838;
+        numerator = numerator * q - 2.549_732_539_343_734;
+        numerator = numerator * q + 4.374_664_141_464_968;
+        numerator = numerator * q + 2.938_163_982_698_783;
+        let mut denominator = 7.784_695_709_041_462e-3 * q + 3.224_671_290_700_398e-1;
+        denominator = denominator * q + 2.445_134_137_142_996;
+        denominator = denominator * q + 3.754_408_661_907_416;
+        denominator = denominator * q + 1.0;
+        numerator / denominator
+    };
+    if p < P_LOW {
+        return tail(p);
+    }
+    if p > 1.0 - P_LOW {
+        return -tail(1.0 - p);
+    }
+    let q = p - 0.5;
+    let r = q * q;
+    let mut numerator = -3.969_683_028_665_376e1 * r + 2.209_460_984_245_205e2;
+    numerator = numerator * r - 2.759_285_104_469_687e2;
+    numerator = numerator * r + 1.383_577_518_672_69e2;
+    numerator = numerator * r - 3.066_479_806_614_716e1;
+    numerator = numerator * r + 2.506_628_277_459_239;
+    let mut denominator = -5.447_609_879_822_406e1 * r + 1.615_858_368_580_409e2;
+    denominator = denominator * r - 1.556_989_798_598_866e2;
+    denominator = denominator * r + 6.680_131_188_771_972e1;
+    denominator = denominator * r - 1.328_068_155_288_572e1;
+    denominator = denominator * r + 1.0;
+    numerator * q / denominator
+}
+
+/// Prices a European option (Call or Put) using the Black-Scholes formula
+///
+/// # Arguments
+/// * `spot_price` - Current spot price of the underlying
+/// * `strike_price` - Strike price of the option
+/// * `volatility` - Annualized volatility (as a decimal, e.g., 0.20 for 20%)
+/// * `risk_free_rate` - Annual risk-free interest rate (as a decimal)
+/// * `time_to_expiration` - Time to expiration in years
+/// * `option_type` - Call or Put option
+///
+/// # Returns
+/// The analytic option price
+pub fn black_scholes_price(
+    spot_price: f64,
+    strike_price: f64,
+    volatility: f64,
+    risk_free_rate: f64,
+    time_to_expiration: f64,
+    option_type: OptionType,
+) -> f64 {
+    let discount_factor = math::exp(-risk_free_rate * time_to_expiration);
+
+    // Degenerate cases: the option is worth its discounted intrinsic value on the forward
+    if time_to_expiration <= 0.0 || volatility <= 0.0 {
+        let forward = spot_price / discount_factor;
+        let intrinsic = match option_type {
+            OptionType::Call => (forward - strike_price).max(0.0),
+            OptionType::Put => (strike_price - forward).max(0.0),
+        };
+        return intrinsic * discount_factor;
+    }
+
+    let std_dev = volatility * time_to_expiration.sqrt();
+    let d1 = (math::ln(spot_price / strike_price)
+        + (risk_free_rate + 0.5 * volatility * volatility) * time_to_expiration)
+        / std_dev;
+    let d2 = d1 - std_dev;
+
+    match option_type {
+        OptionType::Call => {
+            spot_price * norm_cdf(d1) - strike_price * discount_factor * norm_cdf(d2)
+        }
+        OptionType::Put => {
+            strike_price * discount_factor * norm_cdf(-d2) - spot_price * norm_cdf(-d1)
+        }
+    }
+}
+
+/// Prices a cash-or-nothing digital option, paying `amount` at expiry if it expires in the
+/// money, using the Black-Scholes model
+///
+/// # Arguments
+/// * `spot_price` - Current spot price of the underlying
+/// * `strike_price` - Strike price of the option
+/// * `amount` - Cash amount paid in the money
+/// * `volatility` - Annualized volatility (as a decimal, e.g., 0.20 for 20%)
+/// * `risk_free_rate` - Annual risk-free interest rate (as a decimal)
+/// * `time_to_expiration` - Time to expiration in years
+/// * `option_type` - Call or Put option
+///
+/// # Returns
+/// The analytic option price: the discounted amount times the risk-neutral probability of
+/// expiring in the money
+pub fn cash_or_nothing_price(
+    spot_price: f64,
+    strike_price: f64,
+    amount: f64,
+    volatility: f64,
+    risk_free_rate: f64,
+    time_to_expiration: f64,
+    option_type: OptionType,
+) -> f64 {
+    let discount_factor = math::exp(-risk_free_rate * time_to_expiration);
+    let (_, d2) = digital_moneyness(
+        spot_price,
+        strike_price,
+        volatility,
+        risk_free_rate,
+        time_to_expiration,
+        option_type,
+    );
+    amount * discount_factor * norm_cdf(d2)
+}
+
+/// Prices an asset-or-nothing digital option, paying the terminal price if it expires in the
+/// money, using the Black-Scholes model
+///
+/// # Arguments
+/// * `spot_price` - Current spot price of the underlying
+/// * `strike_price` - Strike price of the option
+/// * `volatility` - Annualized volatility (as a decimal, e.g., 0.20 for 20%)
+/// * `risk_free_rate` - Annual risk-free interest rate (as a decimal)
+/// * `time_to_expiration` - Time to expiration in years
+/// * `option_type` - Call or Put option
+///
+/// # Returns
+/// The analytic option price
+pub fn asset_or_nothing_price(
+    spot_price: f64,
+    strike_price: f64,
+    volatility: f64,
+    risk_free_rate: f64,
+    time_to_expiration: f64,
+    option_type: OptionType,
+) -> f64 {
+    let (d1, _) = digital_moneyness(
+        spot_price,
+        strike_price,
+        volatility,
+        risk_free_rate,
+        time_to_expiration,
+        option_type,
+    );
+    spot_price * norm_cdf(d1)
+}
+
+/// Returns `d1` and `d2` of the Black-Scholes formula, negated for puts, so that `N(d2)` is
+/// the risk-neutral probability of expiring in the money. Without volatility or time, the
+/// option expires in the money with certainty if it is in the money on the forward.
+fn digital_moneyness(
+    spot_price: f64,
+    strike_price: f64,
+    volatility: f64,
+    risk_free_rate: f64,
+    time_to_expiration: f64,
+    option_type: OptionType,
+) -> (f64, f64) {
+    let sign = if option_type.is_call() { 1.0 } else { -1.0 };
+    if time_to_expiration <= 0.0 || volatility <= 0.0 {
+        let forward = spot_price * math::exp(risk_free_rate * time_to_expiration);
+        let d = if sign * (forward - strike_price) > 0.0 {
+            f64::INFINITY
+        } else {
+            f64::NEG_INFINITY
+        };
+        return (d, d);
+    }
+    let std_dev = volatility * time_to_expiration.sqrt();
+    let d1 = (math::ln(spot_price / strike_price)
+        + (risk_free_rate + 0.5 * volatility * volatility) * time_to_expiration)
+        / std_dev;
+    (sign * d1, sign * (d1 - std_dev))
+}
+
+/// Black-Scholes price of a European option together with its sensitivities
+///
+/// The sensitivities are the mathematical derivatives of the price, per unit of the
+/// respective input.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlackScholesGreeks {
+    /// Option price
+    pub price: f64,
+    /// Derivative of the price with respect to the spot price
+    pub delta: f64,
+    /// Second derivative of the price with respect to the spot price
+    pub gamma: f64,
+    /// Derivative of the price with respect to the volatility
+    pub vega: f64,
+    /// Derivative of the price with respect to the passage of time, per year
+    pub theta: f64,
+    /// Derivative of the price with respect to the risk-free rate
+    pub rho: f64,
+}
+
+/// Computes the Black-Scholes price and Greeks of a European option (Call or Put)
+///
+/// Without time to expiration or volatility, the option is worth its discounted intrinsic
+/// value on the forward and gamma and vega vanish.
+///
+/// # Arguments
+/// * `spot_price` - Current spot price of the underlying
+/// * `strike_price` - Strike price of the option
+/// * `volatility` - Annualized volatility (as a decimal, e.g., 0.20 for 20%)
+/// * `risk_free_rate` - Annual risk-free interest rate (as a decimal)
+/// * `time_to_expiration` - Time to expiration in years
+/// * `option_type` - Call or Put option
+///
+/// # Returns
+/// The analytic option price and its sensitivities
+pub fn black_scholes_greeks(
+    spot_price: f64,
+    strike_price: f64,
+    volatility: f64,
+    risk_free_rate: f64,
+    time_to_expiration: f64,
+    option_type: OptionType,
+) -> BlackScholesGreeks {
+    let price = black_scholes_price(
+        spot_price,
+        strike_price,
+        volatility,
+        risk_free_rate,
+        time_to_expiration,
+        option_type,
+    );
+    let sign = if option_type.is_call() { 1.0 } else { -1.0 };
+    let discount_factor = math::exp(-risk_free_rate * time_to_expiration);
+    let discounted_strike = strike_price * discount_factor;
+
+    if time_to_expiration <= 0.0 || volatility <= 0.0 {
+        let is_in_the_money = sign * (spot_price - discounted_strike) > 0.0;
+        let exercise = if is_in_the_money { sign } else { 0.0 };
+        return BlackScholesGreeks {
+            price,
+            delta: exercise,
+            gamma: 0.0,
+            vega: 0.0,
+            theta: -exercise * risk_free_rate * discounted_strike,
+            rho: exercise * time_to_expiration * discounted_strike,
+        };
+    }
+
+    let sqrt_time = time_to_expiration.sqrt();
+    let std_dev = volatility * sqrt_time;
+    let d1 = (math::ln(spot_price / strike_price)
+        + (risk_free_rate + 0.5 * volatility * volatility) * time_to_expiration)
+        / std_dev;
+    let d2 = d1 - std_dev;
+    let density = norm_pdf(d1);
+    BlackScholesGreeks {
+        price,
+        delta: sign * norm_cdf(sign * d1),
+        gamma: density / (spot_price * std_dev),
+        vega: spot_price * density * sqrt_time,
+        theta: -spot_price * density * volatility / (2.0 * sqrt_time)
+            - sign * risk_free_rate * discounted_strike * norm_cdf(sign * d2),
+        rho: sign * time_to_expiration * discounted_strike * norm_cdf(sign * d2),
+    }
+}
+
+/// Prices a continuously monitored single barrier option (Call or Put) with the analytic
+/// formulas of Reiner and Rubinstein (1991)
+///
+/// Relative barrier levels are taken relative to the spot price. Rebates of knock-out options
+/// are paid at the hit or at expiry according to their timing, rebates of knock-in options
+/// at expiry. If the spot price is already beyond the barrier, knock-in options are worth the
+/// vanilla option and knock-out options their rebate.
+///
+/// Monte Carlo prices monitor the barrier on the daily closes only, so they match these prices
+/// with a `BarrierCorrection`, with `BarrierObservation::Intraday` or in the limit of many
+/// steps.
+///
+/// # Arguments
+/// * `spot_price` - Current spot price of the underlying
+/// * `strike_price` - Strike price of the option
+/// * `barrier` - Barrier of the option on the underlying
+/// * `volatility` - Annualized volatility (as a decimal, e.g., 0.20 for 20%)
+/// * `risk_free_rate` - Annual risk-free interest rate (as a decimal)
+/// * `time_to_expiration` - Time to expiration in years
+/// * `option_type` - Call or Put option
+///
+/// # Returns
+/// The analytic option price, or `None` if the barrier is not continuously monitored on a
+/// single underlying, or if the volatility or the time to expiration is not positive
+pub fn black_scholes_barrier_price(
+    spot_price: f64,
+    strike_price: f64,
+    barrier: &Barrier,
+    volatility: f64,
+    risk_free_rate: f64,
+    time_to_expiration: f64,
+    option_type: OptionType,
+) -> Option<f64> {
+    if barrier.monitoring != BarrierMonitoring::Continuous
+        || barrier.underlying_indices.len() != 1
+        || volatility <= 0.0
+        || time_to_expiration <= 0.0
+    {
+        return None;
+    }
+    let level = if barrier.relative {
+        barrier.barrier_level * spot_price
+    } else {
+        barrier.barrier_level
+    };
+    let rebate = barrier.rebate.map_or(0.0, |rebate| rebate.amount);
+    let discount_factor = math::exp(-risk_free_rate * time_to_expiration);
+
+    let is_hit = match barrier.direction {
+        BarrierDirection::Up => spot_price >= level,
+        BarrierDirection::Down => spot_price <= level,
+    };
+    if is_hit {
+        return Some(if barrier.knock_type == KnockType::In {
+            black_scholes_price(
+                spot_price,
+                strike_price,
+                volatility,
+                risk_free_rate,
+                time_to_expiration,
+                option_type,
+            )
+        } else {
+            rebate
+        });
+    }
+
+    // Notation of Haug, The Complete Guide to Option Pricing Formulas, without dividends
+    let phi = if option_type.is_call() { 1.0 } else { -1.0 };
+    let eta = match barrier.direction {
+        BarrierDirection::Up => -1.0,
+        BarrierDirection::Down => 1.0,
+    };
+    let variance = volatility * volatility;
+    let std_dev = volatility * time_to_expiration.sqrt();
+    let mu = (risk_free_rate - 0.5 * variance) / variance;
+    let lambda = (mu * mu + 2.0 * risk_free_rate / variance).sqrt();
+    let ratio = level / spot_price;
+    let shifted = |log_moneyness: f64| log_moneyness / std_dev + (1.0 + mu) * std_dev;
+    let x1 = shifted(math::ln(spot_price / strike_price));
+    let x2 = shifted(math::ln(spot_price / level));
+    let y1 = shifted(math::ln(level * level / (spot_price * strike_price)));
+    let y2 = shifted(math::ln(ratio));
+    let z = math::ln(ratio) / std_dev + lambda * std_dev;
+
+    let discounted_strike = strike_price * discount_factor;
+    let vanilla_term = |x: f64| {
+        phi * spot_price * norm_cdf(phi * x)
+            - phi * discounted_strike * norm_cdf(phi * (x - std_dev))
+    };
+    let reflected_term = |y: f64| {
+        phi * spot_price * math::powf(ratio, 2.0 * (mu + 1.0)) * norm_cdf(eta * y)
+            - phi * discounted_strike * math::powf(ratio, 2.0 * mu) * norm_cdf(eta * (y - std_dev))
+    };
+    let a = vanilla_term(x1);
+    let b = vanilla_term(x2);
+    let c = reflected_term(y1);
+    let d = reflected_term(y2);
+    // Value of the amount paid at expiry if the barrier is not hit, and at the hit
+    let no_hit_value = discount_factor
+        * (norm_cdf(eta * (x2 - std_dev)) - math::powf(ratio, 2.0 * mu) * norm_cdf(eta * (y2 - std_dev)));
+    let hit_value = math::powf(ratio, mu + lambda) * norm_cdf(eta * z)
+        + math::powf(ratio, mu - lambda) * norm_cdf(eta * (z - 2.0 * lambda * std_dev));
+
+    let is_strike_above = strike_price > level;
+    // Down-and-out calls and up-and-out puts share their formulas, and so on
+    let is_down_call = (barrier.direction == BarrierDirection::Down) == option_type.is_call();
+    let payoff_value = match (
+        barrier.knock_type,
+        is_down_call,
+        is_strike_above == option_type.is_call(),
+    ) {
+        (KnockType::In, true, true) => c,
+        (KnockType::In, true, false) => a - b + d,
+        (KnockType::In, false, true) => a,
+        (KnockType::In, false, false) => b - c + d,
+        (KnockType::Out, true, true) => a - c,
+        (KnockType::Out, true, false) => b - d,
+        (KnockType::Out, false, true) => 0.0,
+        (KnockType::Out, false, false) => a - b + c - d,
+    };
+    let rebate_value = match barrier.rebate {
+        _ if barrier.knock_type == KnockType::In => rebate * no_hit_value,
+        Some(rebate) if rebate.timing == RebateTiming::AtExpiry => {
+            rebate.amount * (discount_factor - no_hit_value)
+        }
+        _ => rebate * hit_value,
+    };
+    Some(payoff_value + rebate_value)
+}
+
+/// Prices a discretely monitored geometric average-price option (Call or Put) using the
+/// Black-Scholes model
+///
+/// The geometric average of lognormal fixings is lognormal, so the option is a Black-Scholes
+/// option on the average with its mean and variance (Kemna and Vorst, 1990).
+///
+/// # Arguments
+/// * `spot_price` - Current spot price of the underlying
+/// * `strike_price` - Strike price of the option
+/// * `volatility` - Annualized volatility (as a decimal, e.g., 0.20 for 20%)
+/// * `risk_free_rate` - Annual risk-free interest rate (as a decimal)
+/// * `fixing_times` - Times of the fixings averaged in years, e.g. from
+///   `FixingSchedule::fixing_days`
+/// * `time_to_expiration` - Time to expiration in years, when the option pays
+/// * `option_type` - Call or Put option
+///
+/// # Returns
+/// The analytic option price, or `None` without fixings
+pub fn geometric_average_price(
+    spot_price: f64,
+    strike_price: f64,
+    volatility: f64,
+    risk_free_rate: f64,
+    fixing_times: &[f64],
+    time_to_expiration: f64,
+    option_type: OptionType,
+) -> Option<f64> {
+    if fixing_times.is_empty() {
+        return None;
+    }
+    let count = fixing_times.len() as f64;
+    // Mean and variance of the log of the geometric average
+    let mean = math::ln(spot_price)
+        + (risk_free_rate - 0.5 * volatility * volatility) * fixing_times.iter().sum::<f64>()
+            / count;
+    let variance = volatility * volatility
+        * fixing_times
+            .iter()
+            .map(|&first| fixing_times.iter().map(|&second| first.min(second)).sum::<f64>())
+            .sum::<f64>()
+        / (count * count);
+    let discount_factor = math::exp(-risk_free_rate * time_to_expiration);
+    let forward = math::exp(mean + 0.5 * variance);
+
+    if variance <= 0.0 {
+        let intrinsic = match option_type {
+            OptionType::Call => (forward - strike_price).max(0.0),
+            OptionType::Put => (strike_price - forward).max(0.0),
+        };
+        return Some(intrinsic * discount_factor);
+    }
+    let std_dev = variance.sqrt();
+    let d1 = (math::ln(forward / strike_price) + 0.5 * variance) / std_dev;
+    let d2 = d1 - std_dev;
+    Some(
+        discount_factor
+            * match option_type {
+                OptionType::Call => forward * norm_cdf(d1) - strike_price * norm_cdf(d2),
+                OptionType::Put => strike_price * norm_cdf(-d2) - forward * norm_cdf(-d1),
+            },
+    )
+}
+
+/// Prices a European option on an underlying with dividends using the Black-Scholes formula
+/// on the prepaid forward
+///
+/// The price is exact for continuous dividend yields and proportional dividends, which keep
+/// the terminal price lognormal. Cash dividends do not, so `None` is returned for them.
+///
+/// # Arguments
+/// * `underlying` - Underlying asset, including its dividends
+/// * `strike_price` - Strike price of the option
+/// * `rate_curve` - Risk-free rate curve
+/// * `time_to_expiration` - Time to expiration in years
+/// * `option_type` - Call or Put option
+/// * `day_count` - Convention converting the ex-dividend days into year fractions
+pub fn black_scholes_price_with_dividends(
+    underlying: &Underlying,
+    strike_price: f64,
+    rate_curve: &RateCurve,
+    time_to_expiration: f64,
+    option_type: OptionType,
+    day_count: DayCountConvention,
+) -> Option<f64> {
+    if underlying.has_cash_dividends() {
+        return None;
+    }
+    let prepaid_forward = underlying.prepaid_forward(rate_curve, time_to_expiration, day_count);
+    Some(black_scholes_price(
+        prepaid_forward,
+        strike_price,
+        underlying.volatility,
+        // Deterministic rates enter the Black-Scholes price through the zero rate to expiry
+        rate_curve.zero_rate(time_to_expiration),
+        time_to_expiration,
+        option_type,
+    ))
+}
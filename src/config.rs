@@ -0,0 +1,522 @@
+use std::sync::Arc;
+
+use crate::barrier::BarrierCorrection;
+use crate::calendar::{business_days_between, Date, HolidayCalendar, Weekday};
+use crate::error::McError;
+use crate::model::{BlackScholes, Model};
+use crate::observer::PathObserver;
+
+/// Convention for converting day counts into year fractions
+///
+/// All day arguments (maturities, fixing, exercise and observation days) are counted in the
+/// days of the convention, and the simulation takes one step per such day when it monitors
+/// a path. Volatilities and rates are annual, so both scale with the year fraction.
+/// Dates are converted into such days with `days_between`, or with the date entry points of
+/// the pricers (e.g. `PricingRequest::maturity_date`), which use the convention and calendar
+/// of the `SimulationConfig`.
+///
+/// Under `Actual360`, a year fraction is a 360th of the calendar days, so volatilities are
+/// quoted per 360 days too: an option over 365 days accrues 365/360 years of variance. A
+/// volatility quoted on ACT/365 prices identically under `Actual360` when multiplied by
+/// `sqrt(360 / 365)`. Under `Thirty360`, a year has 360 days of the convention, so annual
+/// volatilities need no adjustment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DayCountConvention {
+    /// Calendar days, 365 per year (ACT/365)
+    #[default]
+    Calendar365,
+    /// Trading (business) days, 252 per year
+    Trading252,
+    /// Calendar days, 360 per year (ACT/360); variance accrues per 360 days as well
+    Actual360,
+    /// Days of months of 30 days, 360 per year (30/360 US bond basis)
+    Thirty360,
+}
+
+impl DayCountConvention {
+    /// Returns the number of days per year of the convention
+    pub fn days_per_year(&self) -> f64 {
+        match self {
+            DayCountConvention::Calendar365 => 365.0,
+            DayCountConvention::Trading252 => 252.0,
+            DayCountConvention::Actual360 | DayCountConvention::Thirty360 => 360.0,
+        }
+    }
+
+    /// Converts a number of days into a year fraction
+    pub fn year_fraction(&self, days: u32) -> f64 {
+        days as f64 / self.days_per_year()
+    }
+
+    /// Returns the number of days of the convention from `start` to `end`: calendar days,
+    /// business days of the calendar for `Trading252`, or days of 30-day months for
+    /// `Thirty360` (a start on the 31st counts from the 30th, and so does an end on the 31st
+    /// if the start is on the 30th or 31st)
+    ///
+    /// With `start` the valuation date, these are the day arguments of the pricers, e.g. the
+    /// time horizon for the maturity date or the days of a `FixingSchedule::Dates`.
+    ///
+    /// # Errors
+    /// Returns `McError::InvalidDate` if `end` lies before `start`.
+    pub fn days_between(
+        &self,
+        start: Date,
+        end: Date,
+        calendar: &HolidayCalendar,
+    ) -> Result<u32, McError> {
+        if end < start {
+            return Err(McError::InvalidDate(format!(
+                "{} lies before {}",
+                end, start
+            )));
+        }
+        let days = match self {
+            DayCountConvention::Calendar365 | DayCountConvention::Actual360 => {
+                end.days_since(start)
+            }
+            DayCountConvention::Trading252 => i64::from(calendar.business_days_between(start, end)),
+            DayCountConvention::Thirty360 => {
+                let start_day = start.day().min(30);
+                let end_day = if start_day == 30 {
+                    end.day().min(30)
+                } else {
+                    end.day()
+                };
+                360 * i64::from(end.year() - start.year())
+                    + 30 * (i64::from(end.month()) - i64::from(start.month()))
+                    + i64::from(end_day)
+                    - i64::from(start_day)
+            }
+        };
+        u32::try_from(days)
+            .map_err(|_| McError::InvalidDate(format!("{} is too far from {}", end, start)))
+    }
+
+    /// Returns the days of the convention from the valuation date to each of the dates, e.g.
+    /// the observation days of an autocallable
+    ///
+    /// # Errors
+    /// Returns `McError::InvalidDate` if a date lies before the valuation date.
+    pub fn day_offsets(
+        &self,
+        valuation: Date,
+        dates: &[Date],
+        calendar: &HolidayCalendar,
+    ) -> Result<Vec<u32>, McError> {
+        dates
+            .iter()
+            .map(|&date| self.days_between(valuation, date, calendar))
+            .collect()
+    }
+
+    /// Returns the year fraction from `start` to `end` under the convention
+    ///
+    /// # Errors
+    /// Returns `McError::InvalidDate` if `end` lies before `start`.
+    pub fn year_fraction_between(
+        &self,
+        start: Date,
+        end: Date,
+        calendar: &HolidayCalendar,
+    ) -> Result<f64, McError> {
+        Ok(self.year_fraction(self.days_between(start, end, calendar)?))
+    }
+}
+
+/// Clock along which variance accrues
+///
+/// With the calendar clock, variance accrues uniformly over all days. With the business
+/// clock, it accrues on weekdays only, so an option is not exposed to price moves over a
+/// weekend; the annual variance stays the same. The clocks only differ for the 365-day
+/// convention, since trading days are business days already.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VarianceTime {
+    /// Variance accrues over all calendar days
+    #[default]
+    Calendar,
+    /// Variance accrues over weekdays only, counted from a valuation day on the given weekday
+    Business(Weekday),
+}
+
+impl VarianceTime {
+    /// Returns the year fraction of variance accrued from `start_day` to `end_day`
+    pub fn year_fraction(
+        &self,
+        day_count: DayCountConvention,
+        start_day: u32,
+        end_day: u32,
+    ) -> f64 {
+        match (self, day_count) {
+            (
+                VarianceTime::Business(weekday),
+                DayCountConvention::Calendar365 | DayCountConvention::Actual360,
+            ) => {
+                // A year of 365 calendar days has 365 * 5/7 weekdays on average
+                business_days_between(*weekday, start_day, end_day) as f64
+                    / (day_count.days_per_year() * 5.0 / 7.0)
+            }
+            _ => day_count.year_fraction(end_day - start_day),
+        }
+    }
+}
+
+/// Treatment of paths whose payoff or control is not finite (NaN or infinite), which extreme
+/// parameters such as huge volatilities can produce
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum NonFinitePolicy {
+    /// Fail the pricing with `McError::NonFiniteValue` as soon as a non-finite value occurs
+    Error,
+    /// Drop the affected sample (both paths of an antithetic pair) and count the paths
+    #[default]
+    Drop,
+    /// Clamp infinite values to the given magnitude and replace NaN by zero
+    Clamp(f64),
+}
+
+impl NonFinitePolicy {
+    /// Applies the policy to a path value, returning `None` if the sample is dropped
+    ///
+    /// # Errors
+    /// Returns `McError::NonFiniteValue` for non-finite values under the `Error` policy.
+    pub(crate) fn apply(&self, value: f64) -> Result<Option<f64>, McError> {
+        if value.is_finite() {
+            return Ok(Some(value));
+        }
+        match self {
+            NonFinitePolicy::Error => Err(McError::NonFiniteValue(value)),
+            NonFinitePolicy::Drop => Ok(None),
+            NonFinitePolicy::Clamp(_) if value.is_nan() => Ok(Some(0.0)),
+            NonFinitePolicy::Clamp(max_abs) => Ok(Some(value.clamp(-max_abs, *max_abs))),
+        }
+    }
+}
+
+/// Standard error at which a simulation with adaptive stopping stops
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorTolerance {
+    /// Absolute standard error of the price
+    Absolute(f64),
+    /// Standard error relative to the absolute price
+    Relative(f64),
+}
+
+impl ErrorTolerance {
+    /// Returns the standard error an estimate of the given price needs to reach
+    pub fn target_std_error(&self, price: f64) -> f64 {
+        match self {
+            ErrorTolerance::Absolute(std_error) => *std_error,
+            ErrorTolerance::Relative(relative_error) => relative_error * price.abs(),
+        }
+    }
+
+    /// Returns `true` if an estimate of the given price and standard error meets the tolerance
+    pub fn is_met(&self, price: f64, std_error: f64) -> bool {
+        std_error <= self.target_std_error(price)
+    }
+}
+
+/// Default number of paths simulated between the checks of the error tolerance
+pub(crate) const DEFAULT_BATCH_PATHS: u64 = 10_000;
+
+/// Number of paths of the quick quote configuration
+pub(crate) const QUICK_QUOTE_PATHS: u64 = 4_096;
+
+/// Source of the random shocks driving the paths
+///
+/// The quasi-random sequences provide a limited number of dimensions per path (21 for Sobol,
+/// 32 for Halton). Paths with more shocks (underlyings and extra model shocks times steps)
+/// draw the shocks of as many whole leading steps as fit from the sequence and the others
+/// pseudo-randomly; results report the split with `PricingWarning::QuasiRandomPadding`.
+/// The Sobol sequences provide 2^32 - 1 points, so they are limited to as many paths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Sampling {
+    /// Pseudo-random normal shocks
+    #[default]
+    PseudoRandom,
+    /// Digitally shifted Sobol quasi-random shocks for the leading dimensions of each path
+    /// (the shocks of the first steps), pseudo-random shocks for the remaining ones. The
+    /// reported standard error treats the samples as independent and is conservative.
+    Sobol,
+    /// Owen-scrambled Sobol quasi-random shocks for the leading dimensions of each path, which
+    /// converge faster than the shifted ones for smooth payoffs
+    ScrambledSobol,
+    /// Digitally shifted Halton quasi-random shocks for the leading dimensions of each path
+    Halton,
+}
+
+impl Sampling {
+    /// Returns `true` for the quasi-random samplings
+    pub fn is_quasi_random(&self) -> bool {
+        *self != Sampling::PseudoRandom
+    }
+}
+
+/// Configuration of the Monte Carlo simulation engine
+#[derive(Debug, Clone)]
+pub struct SimulationConfig {
+    /// Number of Monte Carlo simulation paths, or the maximum number with an error tolerance
+    pub num_paths: u64,
+    /// `true` to simulate every path together with its antithetic counterpart (Z and -Z).
+    /// The two paths of a pair count towards `num_paths` and are averaged into one sample.
+    pub antithetic: bool,
+    /// `true` to use the analytic Black-Scholes price of the vanilla option on the first
+    /// underlying as a control variate (ignored if it pays discrete cash dividends)
+    pub control_variate: bool,
+    /// Optional seed for the random number generator. With a seed, runs are reproducible;
+    /// without one, the generator is seeded from system entropy.
+    pub seed: Option<u64>,
+    /// `true` to run sanity checks (put-call parity, monotonicity in strike and barrier level,
+    /// no-arbitrage bounds) after pricing and attach warnings to the result
+    pub validate: bool,
+    /// Convention for converting days into year fractions (365 calendar days by default)
+    pub day_count: DayCountConvention,
+    /// Business days the date entry points count under `Trading252` (all weekdays by
+    /// default)
+    pub calendar: HolidayCalendar,
+    /// Clock along which variance accrues (calendar time by default)
+    pub variance_time: VarianceTime,
+    /// Treatment of non-finite path values (dropped and counted by default)
+    pub non_finite_policy: NonFinitePolicy,
+    /// Dynamics of the underlyings (correlated geometric Brownian motions by default). The
+    /// control variate and the Black-Scholes bounds are only used with lognormal marginals.
+    pub model: Arc<dyn Model>,
+    /// `true` to compare barriers on log levels and exponentiate the simulated log prices
+    /// only at fixings and expiry, instead of at every step
+    pub log_space: bool,
+    /// Optional start value of each underlying's paths, overriding its spot price (e.g. for
+    /// forward-starting products or shocked scenarios). Relative barriers and the analytic
+    /// control variate and bounds refer to these values.
+    pub start_values: Option<Vec<f64>>,
+    /// Correction of the discrete barrier monitoring towards continuous monitoring (none by
+    /// default), for barriers observed every day unless their `BarrierObservation` overrides
+    /// it
+    pub barrier_correction: BarrierCorrection,
+    /// Source of the random shocks (pseudo-random by default)
+    pub sampling: Sampling,
+    /// Number of independently randomized runs of quasi-random sampling the paths are split
+    /// into (1 by default). With two or more, `price_payoff` and the pricers built on it
+    /// average the runs and estimate the standard error from their spread, which is unbiased
+    /// and reflects the faster convergence of the quasi-random numbers. Ignored with
+    /// pseudo-random sampling and by the other pricers.
+    pub qmc_replications: u64,
+    /// `true` to replace a correlation matrix that is not positive definite by the nearest
+    /// correlation matrix (see `correlation::nearest_psd`) and report the adjustment as a
+    /// warning, instead of failing
+    pub repair_correlation: bool,
+    /// Optional observer of the simulated paths for diagnostics (none by default). Sanity
+    /// check repricings are not observed.
+    pub path_observer: Option<Arc<dyn PathObserver>>,
+    /// Optional standard error at which the simulation stops before `num_paths` are
+    /// simulated (none by default). Only `price_payoff`, `Simulation` and the pricers built
+    /// on them stop early; the others always simulate `num_paths`.
+    pub error_tolerance: Option<ErrorTolerance>,
+    /// Number of paths simulated between the checks of the error tolerance, and per poll of
+    /// a `Simulation`
+    pub batch_paths: u64,
+    /// `true` to rescale the terminal prices of each batch so their mean matches the forward
+    /// and, with lognormal marginals, the standard deviation of their log returns matches the
+    /// ATM implied volatility of each underlying (see `price_terminal_payoff`). Only
+    /// `price_terminal_payoff` matches the moments; the other pricers ignore the setting.
+    pub moment_matching: bool,
+    /// Optional non-negative weight of every simulated path (none by default), e.g. scenario
+    /// probabilities or likelihood ratios of an importance sampling. The price is the
+    /// weighted mean of the samples and its standard error uses their effective sample size.
+    /// Only `price_payoff`, the pricers built on it and `PricingSession` weight the paths;
+    /// the others ignore the weights.
+    pub path_weights: Option<Arc<[f64]>>,
+}
+
+impl SimulationConfig {
+    /// Creates a new configuration without any variance reduction
+    pub fn new(num_paths: u64) -> Self {
+        Self {
+            num_paths,
+            antithetic: false,
+            control_variate: false,
+            seed: None,
+            validate: false,
+            day_count: DayCountConvention::Calendar365,
+            calendar: HolidayCalendar::weekends_only(),
+            variance_time: VarianceTime::Calendar,
+            non_finite_policy: NonFinitePolicy::Drop,
+            model: Arc::new(BlackScholes),
+            log_space: false,
+            start_values: None,
+            barrier_correction: BarrierCorrection::None,
+            sampling: Sampling::PseudoRandom,
+            qmc_replications: 1,
+            repair_correlation: false,
+            path_observer: None,
+            error_tolerance: None,
+            batch_paths: DEFAULT_BATCH_PATHS,
+            moment_matching: false,
+            path_weights: None,
+        }
+    }
+
+    /// Creates a configuration for fast interactive quotes: few Sobol quasi-random paths
+    pub fn quick_quote() -> Self {
+        Self::new(QUICK_QUOTE_PATHS).with_sampling(Sampling::Sobol)
+    }
+
+    /// Enables or disables antithetic sampling
+    pub fn with_antithetic(mut self, antithetic: bool) -> Self {
+        self.antithetic = antithetic;
+        self
+    }
+
+    /// Enables or disables the Black-Scholes control variate
+    pub fn with_control_variate(mut self, control_variate: bool) -> Self {
+        self.control_variate = control_variate;
+        self
+    }
+
+    /// Sets the seed of the random number generator for reproducible runs
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Enables or disables the post-pricing sanity checks
+    pub fn with_validation(mut self, validate: bool) -> Self {
+        self.validate = validate;
+        self
+    }
+
+    /// Sets the day count convention, e.g. 252 trading days per year
+    pub fn with_day_count(mut self, day_count: DayCountConvention) -> Self {
+        self.day_count = day_count;
+        self
+    }
+
+    /// Sets the holiday calendar of the business days the date entry points count under
+    /// `Trading252`
+    pub fn with_calendar(mut self, calendar: HolidayCalendar) -> Self {
+        self.calendar = calendar;
+        self
+    }
+
+    /// Sets the clock along which variance accrues, e.g. business time without weekends
+    pub fn with_variance_time(mut self, variance_time: VarianceTime) -> Self {
+        self.variance_time = variance_time;
+        self
+    }
+
+    /// Sets the treatment of non-finite path values
+    pub fn with_non_finite_policy(mut self, non_finite_policy: NonFinitePolicy) -> Self {
+        self.non_finite_policy = non_finite_policy;
+        self
+    }
+
+    /// Sets the dynamics of the underlyings, e.g. the Heston stochastic volatility model
+    pub fn with_model(mut self, model: impl Model + 'static) -> Self {
+        self.model = Arc::new(model);
+        self
+    }
+
+    /// Enables or disables the barrier comparison on log levels without exponentiating the
+    /// log prices at every step
+    pub fn with_log_space(mut self, log_space: bool) -> Self {
+        self.log_space = log_space;
+        self
+    }
+
+    /// Starts the paths of the underlyings at the given values instead of their spot prices,
+    /// leaving the underlyings themselves untouched
+    pub fn with_start_values(mut self, start_values: Vec<f64>) -> Self {
+        self.start_values = Some(start_values);
+        self
+    }
+
+    /// Sets the correction of the discrete barrier monitoring, e.g. the Brownian-bridge
+    /// hit probability between steps
+    pub fn with_barrier_correction(mut self, barrier_correction: BarrierCorrection) -> Self {
+        self.barrier_correction = barrier_correction;
+        self
+    }
+
+    /// Sets the source of the random shocks, e.g. Sobol quasi-random numbers
+    pub fn with_sampling(mut self, sampling: Sampling) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    /// Splits quasi-random sampling into the given number of independently randomized runs,
+    /// e.g. `with_sampling(Sampling::ScrambledSobol).with_qmc_replications(16)`
+    ///
+    /// # Panics
+    /// Panics if `qmc_replications` is zero.
+    pub fn with_qmc_replications(mut self, qmc_replications: u64) -> Self {
+        assert!(
+            qmc_replications > 0,
+            "Number of replications must be positive"
+        );
+        self.qmc_replications = qmc_replications;
+        self
+    }
+
+    /// Enables or disables the repair of correlation matrices that are not positive definite
+    pub fn with_correlation_repair(mut self, repair_correlation: bool) -> Self {
+        self.repair_correlation = repair_correlation;
+        self
+    }
+
+    /// Streams the simulated paths to the observer, e.g. a `PathRecorder` kept by the caller
+    pub fn with_path_observer(mut self, path_observer: Arc<dyn PathObserver>) -> Self {
+        self.path_observer = Some(path_observer);
+        self
+    }
+
+    /// Simulates the paths in batches until the standard error meets the tolerance, with
+    /// `num_paths` as the maximum number of paths
+    pub fn with_error_tolerance(mut self, error_tolerance: ErrorTolerance) -> Self {
+        self.error_tolerance = Some(error_tolerance);
+        self
+    }
+
+    /// Sets the number of paths simulated between the checks of the error tolerance
+    pub fn with_batch_paths(mut self, batch_paths: u64) -> Self {
+        self.batch_paths = batch_paths;
+        self
+    }
+
+    /// Enables or disables the matching of the terminal prices to the forwards and ATM
+    /// implied volatilities of the underlyings
+    pub fn with_moment_matching(mut self, moment_matching: bool) -> Self {
+        self.moment_matching = moment_matching;
+        self
+    }
+
+    /// Weights the simulated paths, one non-negative weight per path in the order of
+    /// simulation. With antithetic sampling, the paths of a pair follow each other and an odd
+    /// `num_paths` is rounded up to whole pairs.
+    pub fn with_path_weights(mut self, path_weights: impl Into<Arc<[f64]>>) -> Self {
+        self.path_weights = Some(path_weights.into());
+        self
+    }
+
+    /// Returns the days of the day count convention from the valuation date to `date`,
+    /// counting business days of the calendar under `Trading252` (see
+    /// `DayCountConvention::days_between`)
+    ///
+    /// # Errors
+    /// Returns `McError::InvalidDate` if `date` lies before the valuation date.
+    pub fn days_between(&self, valuation: Date, date: Date) -> Result<u32, McError> {
+        self.day_count.days_between(valuation, date, &self.calendar)
+    }
+
+    /// Returns the days of the day count convention from the valuation date to each of the
+    /// dates (see `days_between`)
+    ///
+    /// # Errors
+    /// Returns `McError::InvalidDate` if a date lies before the valuation date.
+    pub fn day_offsets(&self, valuation: Date, dates: &[Date]) -> Result<Vec<u32>, McError> {
+        self.day_count.day_offsets(valuation, dates, &self.calendar)
+    }
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self::new(10_000)
+    }
+}
@@ -0,0 +1,462 @@
+use nalgebra::{DMatrix, DVector};
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::engine;
+use crate::error::McError;
+use crate::math;
+use crate::validation;
+
+/// Smallest eigenvalue of a repaired correlation matrix, so it stays positive definite and
+/// has a Cholesky decomposition
+const MIN_EIGENVALUE: f64 = 1e-8;
+
+/// Maximum number of alternating projections of `nearest_psd`
+const MAX_ITERATIONS: usize = 200;
+
+/// Relative change between two projections below which `nearest_psd` has converged
+const TOLERANCE: f64 = 1e-12;
+
+/// Returns the nearest correlation matrix to the given symmetric matrix in the Frobenius
+/// norm, using Higham's alternating projections with Dykstra's correction
+///
+/// The iteration alternates between the projection onto the positive semi-definite matrices
+/// (clipping the eigenvalues) and onto the matrices with a unit diagonal. The result is
+/// symmetric with a unit diagonal and its eigenvalues are at least a small positive floor, so
+/// it is positive definite. Matrices that are already valid correlation matrices come back
+/// unchanged up to rounding. Use `(repaired - matrix).norm()` for the size of the adjustment.
+///
+/// # Arguments
+/// * `matrix` - Square matrix, usually an empirical correlation matrix that is not positive
+///   semi-definite. Only its lower triangle is used.
+///
+/// # Errors
+/// Returns `McError::InvalidCorrelationMatrix` if the matrix is not square or has non-finite
+/// entries.
+pub fn nearest_psd(matrix: &DMatrix<f64>) -> Result<DMatrix<f64>, McError> {
+    if !matrix.is_square() {
+        return Err(McError::InvalidCorrelationMatrix(format!(
+            "expected a square matrix, got {}x{}",
+            matrix.nrows(),
+            matrix.ncols()
+        )));
+    }
+    if matrix.iter().any(|entry| !entry.is_finite()) {
+        return Err(McError::InvalidCorrelationMatrix(
+            "matrix has non-finite entries".to_string(),
+        ));
+    }
+
+    let mut y = matrix.clone();
+    y.fill_upper_triangle_with_lower_triangle();
+    let mut correction = DMatrix::zeros(y.nrows(), y.ncols());
+    for _ in 0..MAX_ITERATIONS {
+        let r = &y - &correction;
+        let x = clip_eigenvalues(r.clone(), 0.0);
+        correction = &x - r;
+        let previous = y;
+        y = x;
+        y.fill_diagonal(1.0);
+        if (&y - &previous).norm() <= TOLERANCE * y.norm() {
+            break;
+        }
+    }
+
+    // The unit diagonal projection may leave eigenvalues slightly below zero; after clipping
+    // them, rescaling to a unit diagonal keeps the matrix positive definite
+    let clipped = clip_eigenvalues(y, MIN_EIGENVALUE);
+    let scaling = DMatrix::from_diagonal(&clipped.diagonal().map(|d| d.sqrt().recip()));
+    let mut repaired = &scaling * clipped * &scaling;
+    repaired.fill_diagonal(1.0);
+    Ok(repaired)
+}
+
+/// Returns the correlation matrix with its market-wide correlation level stressed: its
+/// leading eigenvalue moves by `shift * (n - 1)`, the move of a uniform correlation by
+/// `shift`, while the eigenvectors stay
+///
+/// The other eigenvalues are scaled so that they keep adding up to `n` with the leading
+/// one, and the result is rescaled to a unit diagonal, so it is a valid correlation matrix
+/// whatever the shift. A uniform correlation `rho` becomes `rho + shift`; other matrices
+/// move most along their dominant factor instead of pair by pair. The leading eigenvalue
+/// is kept between 1 and `n`, i.e. the shift saturates at independent underlyings below
+/// and at a single common factor above. Without a dominant factor, e.g. for uncorrelated underlyings, the
+/// leading eigenvector is a single underlying and the correlations stay unchanged.
+///
+/// # Arguments
+/// * `correlation_matrix` - Valid correlation matrix (n x n)
+/// * `shift` - Shift of the correlation level, e.g. 0.2 for 20 correlation points up
+///
+/// # Panics
+/// Panics if the matrix is not square.
+pub fn stress_principal_component(correlation_matrix: &DMatrix<f64>, shift: f64) -> DMatrix<f64> {
+    let size = correlation_matrix.nrows() as f64;
+    let mut eigen = correlation_matrix.clone().symmetric_eigen();
+    let (leading, &eigenvalue) = eigen
+        .eigenvalues
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .expect("correlation matrix has eigenvalues");
+    let stressed = (eigenvalue + shift * (size - 1.0)).clamp(1.0, size);
+    // The other eigenvalues make up the rest of the trace
+    let scale = if size - eigenvalue > 0.0 {
+        (size - stressed) / (size - eigenvalue)
+    } else {
+        0.0
+    };
+    for (index, value) in eigen.eigenvalues.iter_mut().enumerate() {
+        *value = if index == leading {
+            stressed
+        } else {
+            (*value * scale).max(0.0)
+        };
+    }
+    let mut stressed = eigen.recompose();
+    stressed.fill_upper_triangle_with_lower_triangle();
+    let scaling = DMatrix::from_diagonal(&stressed.diagonal().map(|d| d.sqrt().recip()));
+    let mut stressed = &scaling * stressed * &scaling;
+    stressed.fill_diagonal(1.0);
+    stressed
+}
+
+/// Algorithm of `RandomCorrelation` to draw from the LKJ distribution; both draw from the
+/// same distribution
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CorrelationSampling {
+    /// Onion method: grows the matrix by one underlying at a time, drawing its correlations
+    /// with the previous ones from an elliptical distribution
+    #[default]
+    Onion,
+    /// Vine method: draws the partial correlations of a C-vine from Beta distributions and
+    /// converts them into correlations
+    Vine,
+}
+
+/// Sampler of random valid correlation matrices, e.g. to price over an uncertain
+/// correlation or to test on diverse dependence structures
+///
+/// Matrices are drawn from the LKJ distribution (Lewandowski, Kurowicka and Joe, 2009), with
+/// a density proportional to `det(C)^(concentration - 1)`: a concentration of 1 draws
+/// uniformly from all correlation matrices, larger ones draw closer to the identity. With an
+/// average correlation, each draw is blended with the matrix of perfectly correlated
+/// underlyings (or, to lower it, with the most negative uniform correlation `-1 / (n - 1)`)
+/// until the mean of its correlations between different underlyings matches exactly. The
+/// blend is positive definite like the draw, but its correlations spread less around the
+/// average the further the average is from the draw's.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RandomCorrelation {
+    /// Number of underlyings
+    pub size: usize,
+    /// Sampling algorithm, the onion method by default
+    pub sampling: CorrelationSampling,
+    /// LKJ concentration, positive; 1 by default
+    pub concentration: f64,
+    /// Mean of the correlations between different underlyings of every draw, in
+    /// `(-1 / (n - 1), 1)`; by default the mean of the LKJ draw, zero on average
+    pub average_correlation: Option<f64>,
+}
+
+impl RandomCorrelation {
+    /// Creates a sampler of uniformly distributed correlation matrices of the given size
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            sampling: CorrelationSampling::Onion,
+            concentration: 1.0,
+            average_correlation: None,
+        }
+    }
+
+    /// Sets the sampling algorithm
+    pub fn with_sampling(mut self, sampling: CorrelationSampling) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    /// Sets the LKJ concentration: above 1 for weaker, below 1 for stronger correlations
+    pub fn with_concentration(mut self, concentration: f64) -> Self {
+        self.concentration = concentration;
+        self
+    }
+
+    /// Sets the mean of the correlations between different underlyings of every draw
+    pub fn with_average_correlation(mut self, average_correlation: f64) -> Self {
+        self.average_correlation = Some(average_correlation);
+        self
+    }
+
+    /// Draws a random correlation matrix
+    ///
+    /// # Arguments
+    /// * `rng` - Source of randomness, e.g. a seeded `StdRng` for reproducible draws
+    ///
+    /// # Returns
+    /// A symmetric, positive definite matrix with a unit diagonal
+    ///
+    /// # Errors
+    /// Returns `McError::InvalidCorrelationMatrix` for a size of zero, a concentration that
+    /// is not positive or an average correlation out of its range.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Result<DMatrix<f64>, McError> {
+        if self.size == 0 {
+            return Err(McError::InvalidCorrelationMatrix(
+                "random correlation matrices need at least one underlying".to_string(),
+            ));
+        }
+        if !(self.concentration > 0.0 && self.concentration.is_finite()) {
+            return Err(McError::InvalidCorrelationMatrix(format!(
+                "concentration must be positive, got {}",
+                self.concentration
+            )));
+        }
+        let size = self.size as f64;
+        let lowest = -1.0 / (size - 1.0);
+        if let Some(average) = self.average_correlation {
+            if self.size > 1 && !(average > lowest && average < 1.0) {
+                return Err(McError::InvalidCorrelationMatrix(format!(
+                    "average correlation of {} underlyings must be in ({}, 1), got {}",
+                    self.size, lowest, average
+                )));
+            }
+        }
+        let matrix = match self.sampling {
+            CorrelationSampling::Onion => onion_sample(rng, self.size, self.concentration),
+            CorrelationSampling::Vine => vine_sample(rng, self.size, self.concentration),
+        };
+        let average = match self.average_correlation {
+            Some(average) if self.size > 1 => average,
+            _ => return Ok(matrix),
+        };
+        let drawn = (matrix.sum() - size) / (size * (size - 1.0));
+        let anchor = if average >= drawn { 1.0 } else { lowest };
+        let weight = (average - drawn) / (anchor - drawn);
+        Ok(matrix.map_with_location(|i, j, correlation| {
+            if i == j {
+                1.0
+            } else {
+                (1.0 - weight) * correlation + weight * anchor
+            }
+        }))
+    }
+}
+
+/// Draws an LKJ correlation matrix with the onion method
+fn onion_sample<R: Rng + ?Sized>(rng: &mut R, size: usize, concentration: f64) -> DMatrix<f64> {
+    let mut matrix = DMatrix::identity(size, size);
+    if size == 1 {
+        return matrix;
+    }
+    let mut beta = concentration + (size as f64 - 2.0) / 2.0;
+    let correlation = 2.0 * beta_sample(rng, beta, beta) - 1.0;
+    matrix[(0, 1)] = correlation;
+    matrix[(1, 0)] = correlation;
+    for dimension in 2..size {
+        beta -= 0.5;
+        // Radius from a Beta distribution, direction uniform on the sphere
+        let radius = beta_sample(rng, dimension as f64 / 2.0, beta).sqrt();
+        let direction = DVector::from_fn(dimension, |_, _| math::standard_normal(rng));
+        let point = direction.normalize() * radius;
+        let lower = matrix
+            .view((0, 0), (dimension, dimension))
+            .clone_owned()
+            .cholesky()
+            .expect("onion draws stay positive definite")
+            .l();
+        let correlations = lower * point;
+        for (index, &correlation) in correlations.iter().enumerate() {
+            matrix[(dimension, index)] = correlation;
+            matrix[(index, dimension)] = correlation;
+        }
+    }
+    matrix
+}
+
+/// Draws an LKJ correlation matrix with the vine method
+fn vine_sample<R: Rng + ?Sized>(rng: &mut R, size: usize, concentration: f64) -> DMatrix<f64> {
+    let mut partial = DMatrix::zeros(size, size);
+    let mut matrix = DMatrix::identity(size, size);
+    let mut beta = concentration + (size as f64 - 1.0) / 2.0;
+    for level in 0..size.saturating_sub(1) {
+        beta -= 0.5;
+        for index in level + 1..size {
+            partial[(level, index)] = 2.0 * beta_sample(rng, beta, beta) - 1.0;
+            // Convert the partial correlation given the earlier levels into a correlation
+            let mut correlation = partial[(level, index)];
+            for earlier in (0..level).rev() {
+                let (with_index, with_level) =
+                    (partial[(earlier, index)], partial[(earlier, level)]);
+                correlation = correlation
+                    * ((1.0 - with_index * with_index) * (1.0 - with_level * with_level)).sqrt()
+                    + with_index * with_level;
+            }
+            matrix[(level, index)] = correlation;
+            matrix[(index, level)] = correlation;
+        }
+    }
+    matrix
+}
+
+/// Draws from the Beta distribution with the given shapes
+fn beta_sample<R: Rng + ?Sized>(rng: &mut R, alpha: f64, beta: f64) -> f64 {
+    math::beta(rng, alpha, beta)
+}
+
+/// Lower triangular Cholesky factor `L` of a validated correlation matrix, with `L * L^T`
+/// the correlation of the simulated shocks
+///
+/// This is the factorization the pricers use: a matrix that is not positive definite is
+/// replaced by its nearest correlation matrix (see `nearest_psd`) if the repair is enabled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorrelationFactor {
+    lower: DMatrix<f64>,
+    adjustment: Option<f64>,
+}
+
+impl CorrelationFactor {
+    /// Validates and factorizes a correlation matrix
+    ///
+    /// # Arguments
+    /// * `correlation_matrix` - Symmetric matrix with a unit diagonal and entries in [-1, 1]
+    /// * `repair` - Replaces a matrix that is not positive definite by its nearest correlation
+    ///   matrix instead of failing, as `SimulationConfig::repair_correlation` does
+    ///
+    /// # Errors
+    /// Returns `McError::InvalidCorrelationMatrix` if the matrix is not a valid correlation
+    /// matrix, or is not positive definite and `repair` is not set.
+    pub fn new(correlation_matrix: &DMatrix<f64>, repair: bool) -> Result<Self, McError> {
+        if !correlation_matrix.is_square() {
+            return Err(McError::InvalidCorrelationMatrix(format!(
+                "expected a square matrix, got {}x{}",
+                correlation_matrix.nrows(),
+                correlation_matrix.ncols()
+            )));
+        }
+        validation::validate_correlation_matrix(correlation_matrix, correlation_matrix.nrows())?;
+
+        let not_positive_definite =
+            || McError::InvalidCorrelationMatrix("matrix is not positive definite".to_string());
+        let (cholesky, adjustment) = match correlation_matrix.clone().cholesky() {
+            Some(cholesky) => (cholesky, None),
+            None if repair => {
+                let repaired = nearest_psd(correlation_matrix)?;
+                let adjustment = (&repaired - correlation_matrix).norm();
+                let cholesky = repaired.cholesky().ok_or_else(not_positive_definite)?;
+                (cholesky, Some(adjustment))
+            }
+            None => return Err(not_positive_definite()),
+        };
+        Ok(Self {
+            lower: cholesky.l(),
+            adjustment,
+        })
+    }
+
+    /// Returns the number of correlated variables
+    pub fn dimension(&self) -> usize {
+        self.lower.nrows()
+    }
+
+    /// Returns the lower triangular factor `L`
+    pub fn lower(&self) -> &DMatrix<f64> {
+        &self.lower
+    }
+
+    /// Returns the correlation matrix `L * L^T` the factor realizes, which differs from the
+    /// given one if it was repaired
+    pub fn correlation_matrix(&self) -> DMatrix<f64> {
+        &self.lower * self.lower.transpose()
+    }
+
+    /// Returns the Frobenius norm of the repair of the correlation matrix, if it was not
+    /// positive definite
+    pub fn adjustment(&self) -> Option<f64> {
+        self.adjustment
+    }
+
+    /// Correlates independent standard normal variables in place, replacing `z` by `L * z`
+    ///
+    /// Values beyond the dimension of the factor are left independent, e.g. the extra shocks
+    /// of stochastic volatility models.
+    ///
+    /// # Panics
+    /// Panics if `values` is shorter than the dimension of the factor.
+    pub fn correlate(&self, values: &mut [f64]) {
+        assert!(
+            values.len() >= self.dimension(),
+            "Expected at least {} values, got {}",
+            self.dimension(),
+            values.len()
+        );
+        // From the last row up, so each row only reads values not yet replaced
+        for row in (0..self.dimension()).rev() {
+            values[row] = (0..=row)
+                .map(|col| self.lower[(row, col)] * values[col])
+                .sum();
+        }
+    }
+}
+
+/// Generator of correlated standard normal shocks, e.g. for simulations of custom payoffs on
+/// the correlation the pricers use
+#[derive(Debug, Clone)]
+pub struct CorrelatedNormalGenerator {
+    factor: CorrelationFactor,
+    rng: StdRng,
+}
+
+impl CorrelatedNormalGenerator {
+    /// Creates a generator of shocks with the correlation of the factor
+    ///
+    /// # Arguments
+    /// * `factor` - Factorized correlation of the shocks
+    /// * `seed` - Seed for reproducible shocks; without one, the generator is seeded from
+    ///   the operating system
+    pub fn new(factor: CorrelationFactor, seed: Option<u64>) -> Self {
+        Self {
+            factor,
+            rng: engine::create_rng(seed),
+        }
+    }
+
+    /// Returns the factorized correlation of the shocks
+    pub fn factor(&self) -> &CorrelationFactor {
+        &self.factor
+    }
+
+    /// Fills `shocks` with standard normal shocks: the leading ones correlated by the factor,
+    /// any further ones independent
+    ///
+    /// With the same seed, a Black-Scholes simulation with pseudo-random sampling draws the
+    /// same shocks per step.
+    ///
+    /// # Panics
+    /// Panics if `shocks` is shorter than the dimension of the factor.
+    pub fn fill(&mut self, shocks: &mut [f64]) {
+        for shock in shocks.iter_mut() {
+            *shock = math::standard_normal(&mut self.rng);
+        }
+        self.factor.correlate(shocks);
+    }
+
+    /// Returns the next vector of correlated shocks, one per dimension of the factor
+    pub fn next_shocks(&mut self) -> Vec<f64> {
+        let mut shocks = vec![0.0; self.factor.dimension()];
+        self.fill(&mut shocks);
+        shocks
+    }
+}
+
+/// Projects a symmetric matrix onto the matrices with eigenvalues of at least `floor`
+fn clip_eigenvalues(matrix: DMatrix<f64>, floor: f64) -> DMatrix<f64> {
+    let mut eigen = matrix.symmetric_eigen();
+    eigen
+        .eigenvalues
+        .iter_mut()
+        .for_each(|eigenvalue| *eigenvalue = eigenvalue.max(floor));
+    let mut clipped = eigen.recompose();
+    // Symmetrize the rounding noise of the recomposition
+    clipped.fill_upper_triangle_with_lower_triangle();
+    clipped
+}
@@ -0,0 +1,399 @@
+use std::sync::Arc;
+
+use nalgebra::{DMatrix, DVector};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::closed_form::inverse_norm_cdf;
+use crate::correlation::CorrelationFactor;
+use crate::config::{Sampling, SimulationConfig};
+use crate::error::McError;
+use crate::math;
+use crate::qmc::{DimensionBudget, QuasiRandom};
+use crate::model::{Model, StepInputs};
+use crate::payoff::PathObservables;
+use crate::rates::RateCurve;
+use crate::underlying::{Dividend, Underlying};
+use crate::validation;
+
+/// Path engine shared by all pricers, evolving the underlyings with the configured model
+pub(crate) struct PathEngine {
+    /// Spot prices the paths start from
+    pub initial_prices: Vec<f64>,
+    /// Logarithms of the spot prices
+    pub initial_log_prices: Vec<f64>,
+    /// Model state the paths start from (e.g. the variances)
+    pub initial_state: Vec<f64>,
+    /// Number of time steps per path
+    pub num_steps: usize,
+    /// Length of one time step in years
+    pub dt: f64,
+    /// Factorized correlation of the underlyings, repaired if configured
+    pub correlation: CorrelationFactor,
+    model: Arc<dyn Model>,
+    /// Growth rate of each underlying over each step (see `Underlying::carry_rate`), quanto
+    /// adjustment included: step_carry_rates[step - 1][underlying]
+    step_carry_rates: Vec<Vec<f64>>,
+    /// Variance of each underlying's log price accrued over each step, from its volatility
+    /// term structure and the variance clock: step_variances[step - 1][underlying]
+    step_variances: Vec<Vec<f64>>,
+    /// Discrete dividends applied at the end of each step (index = step - 1), as pairs of
+    /// underlying index and dividend. Cash amounts are carried forward from the ex-dividend
+    /// day to the end of the step.
+    step_dividends: Vec<Vec<(usize, Dividend)>>,
+}
+
+impl PathEngine {
+    /// Creates a path engine simulating `num_steps` equal steps up to `time_to_expiration`,
+    /// using the day count convention and variance clock of the configuration
+    ///
+    /// # Errors
+    /// Returns an error for invalid market data or path counts (see
+    /// `validation::validate_inputs`) or if the correlation matrix is not positive definite
+    /// and its repair is not enabled.
+    pub fn new(
+        underlyings: &[Underlying],
+        correlation_matrix: &DMatrix<f64>,
+        rate_curve: &RateCurve,
+        time_to_expiration: f64,
+        num_steps: usize,
+        config: &SimulationConfig,
+    ) -> Result<Self, McError> {
+        let day_count = config.day_count;
+        validation::validate_inputs(underlyings, correlation_matrix, config)?;
+
+        // Cholesky decomposition of the correlation matrix for correlated random variables,
+        // falling back to the nearest correlation matrix if configured
+        let correlation = CorrelationFactor::new(correlation_matrix, config.repair_correlation)?;
+
+        // Pre-compute the drift of each underlying over each step, implied from its marked
+        // forwards if given, plus the quanto adjustment of underlyings paid in another currency
+        let dt = time_to_expiration / num_steps as f64;
+        let horizon_days = (time_to_expiration * day_count.days_per_year()).round();
+        let step_end_day =
+            |step: usize| (horizon_days * step as f64 / num_steps as f64).round() as u32;
+        let step_carry_rates = (0..num_steps)
+            .map(|step| {
+                let (start, end) = (step as f64 * dt, (step + 1) as f64 * dt);
+                let rate = rate_curve.forward_rate(start, end);
+                underlyings
+                    .iter()
+                    .map(|u| {
+                        let carry_rate = match (
+                            u.marked_forward(start, day_count),
+                            u.marked_forward(end, day_count),
+                        ) {
+                            (Some(start_forward), Some(end_forward)) if dt > 0.0 => {
+                                math::ln(end_forward / start_forward) / dt
+                            }
+                            _ => u.carry_rate(rate),
+                        };
+                        // Without time to expiry no drift accrues, and the rates implied from
+                        // the forwards and the quanto adjustment per unit of time are undefined
+                        if dt == 0.0 {
+                            return carry_rate;
+                        }
+                        let (start_day, end_day) = (step_end_day(step), step_end_day(step + 1));
+                        carry_rate + u.quanto_adjustment(start_day, end_day, day_count) / dt
+                    })
+                    .collect()
+            })
+            .collect();
+
+        // Variance accrued over each step, following the volatility term structures and
+        // skipping weekends on the business clock
+        let step_variances = (0..num_steps)
+            .map(|step| {
+                underlyings
+                    .iter()
+                    .map(|u| {
+                        u.integrated_variance(
+                            step_end_day(step),
+                            step_end_day(step + 1),
+                            day_count,
+                            config.variance_time,
+                        )
+                    })
+                    .collect()
+            })
+            .collect();
+
+        // Assign every discrete dividend up to expiration to the step containing its ex-day
+        let mut step_dividends = vec![Vec::new(); num_steps];
+        for (i, underlying) in underlyings.iter().enumerate() {
+            // Marked forwards already embed the dividends, other asset classes pay none
+            if !underlying.pays_dividends() {
+                continue;
+            }
+            // Cash dividends of a quanto underlying accrue at the foreign rate
+            let dividend_curve = match underlying.quanto {
+                Some(quanto) => &RateCurve::flat(quanto.foreign_rate),
+                None => rate_curve,
+            };
+            for dividend in &underlying.dividends {
+                let ex_time = day_count.year_fraction(dividend.day());
+                if dividend.day() == 0 || ex_time > time_to_expiration * (1.0 + 1e-12) {
+                    continue;
+                }
+                let step = ((ex_time / dt - 1e-9).ceil() as usize).clamp(1, num_steps);
+                let step_end = step as f64 * dt;
+                let dividend = match *dividend {
+                    Dividend::Cash { day, amount } => Dividend::Cash {
+                        day,
+                        amount: amount * dividend_curve.discount_factor(ex_time)
+                            / dividend_curve.discount_factor(step_end)
+                            * math::exp(-underlying.dividend_yield * (step_end - ex_time)),
+                    },
+                    proportional => proportional,
+                };
+                step_dividends[step - 1].push((i, dividend));
+            }
+        }
+
+        Ok(Self {
+            initial_prices: underlyings.iter().map(|u| u.spot_price).collect(),
+            initial_log_prices: underlyings.iter().map(|u| math::ln(u.spot_price)).collect(),
+            num_steps,
+            dt,
+            correlation,
+            initial_state: config.model.initial_state(underlyings),
+            model: Arc::clone(&config.model),
+            step_carry_rates,
+            step_variances,
+            step_dividends,
+        })
+    }
+
+    /// Returns the number of shocks drawn per step
+    pub fn shocks_per_step(&self) -> usize {
+        self.initial_prices.len() * (1 + self.model.num_extra_shocks())
+    }
+
+    /// Returns a buffer for the shocks of one step
+    pub fn new_shocks(&self) -> DVector<f64> {
+        DVector::zeros(self.shocks_per_step())
+    }
+
+    /// Returns the split of the shocks of each path between the quasi-random sequence of the
+    /// sampling and pseudo-random padding
+    pub fn dimension_budget(&self, sampling: Sampling) -> DimensionBudget {
+        DimensionBudget::new(sampling, self.shocks_per_step(), self.num_steps)
+    }
+
+    /// Creates the generator of the shocks of a run, as configured
+    pub fn shock_generator(&self, config: &SimulationConfig) -> ShockGenerator {
+        let mut rng = create_rng(config.seed);
+        // Quasi-random numbers cover the shocks of the first steps of each path
+        let quasi_random = QuasiRandom::new(
+            config.sampling,
+            self.dimension_budget(config.sampling).quasi_random,
+            &mut rng,
+        );
+        ShockGenerator {
+            point: vec![0.0; quasi_random.as_ref().map_or(0, QuasiRandom::dimensions)],
+            rng,
+            quasi_random,
+            dimension: 0,
+        }
+    }
+
+    /// Draws the shocks of one step into `shocks`: a correlated standard normal shock per
+    /// underlying, followed by the independent shocks the model needs
+    pub fn draw_shocks(&self, generator: &mut ShockGenerator, shocks: &mut DVector<f64>) {
+        // Generate independent standard normal random variables
+        for shock in shocks.iter_mut() {
+            *shock = generator.next_normal();
+        }
+
+        // Transform the leading shocks to correlated random variables using the lower
+        // triangular Cholesky factor
+        self.correlation.correlate(shocks.as_mut_slice());
+    }
+
+    /// Returns the Black-Scholes variance of the underlying's log price over the given time
+    /// step (counted from 1)
+    pub fn step_variance(&self, step: usize, underlying: usize) -> f64 {
+        self.step_variances[step - 1][underlying]
+    }
+
+    /// Returns the growth rate of the underlying over the given time step (counted from 1),
+    /// quanto adjustment included
+    pub fn step_carry_rate(&self, step: usize, underlying: usize) -> f64 {
+        self.step_carry_rates[step - 1][underlying]
+    }
+
+    /// Returns the discrete dividends going ex during the given time step (counted from 1),
+    /// as pairs of underlying index and dividend
+    pub fn step_dividends(&self, step: usize) -> &[(usize, Dividend)] {
+        &self.step_dividends[step - 1]
+    }
+
+    /// Advances the log prices and the model state over the given time step (counted from 1),
+    /// applying the shocks with the given sign (`-1.0` for the antithetic path) and the
+    /// dividends of the step
+    pub fn advance(
+        &self,
+        step: usize,
+        log_prices: &mut [f64],
+        state: &mut [f64],
+        shocks: &DVector<f64>,
+        sign: f64,
+    ) {
+        let inputs = StepInputs {
+            time: (step - 1) as f64 * self.dt,
+            dt: self.dt,
+            carry_rates: &self.step_carry_rates[step - 1],
+            variances: &self.step_variances[step - 1],
+        };
+        self.model
+            .evolve_step(&inputs, log_prices, state, shocks.as_slice(), sign);
+
+        // Prices drop by the discrete dividends going ex during the step
+        for &(i, dividend) in &self.step_dividends[step - 1] {
+            log_prices[i] = ex_dividend_log_price(log_prices[i], dividend);
+        }
+    }
+}
+
+/// Returns the log price after the price dropped by the dividend
+pub(crate) fn ex_dividend_log_price(log_price: f64, dividend: Dividend) -> f64 {
+    match dividend {
+        Dividend::Cash { amount, .. } => math::ln((math::exp(log_price) - amount).max(0.0)),
+        Dividend::Proportional { ratio, .. } => log_price + math::ln(1.0 - ratio),
+    }
+}
+
+/// State of a single simulated path
+pub(crate) struct PathState {
+    /// Current prices of all underlyings, refreshed from the log prices by `update_prices`
+    pub prices: Vec<f64>,
+    /// Current log prices of all underlyings, as evolved by the engine
+    pub log_prices: Vec<f64>,
+    /// Current model state (e.g. the variances)
+    pub model_state: Vec<f64>,
+    /// `true` once the barrier has been hit on this path
+    pub barrier_hit: bool,
+    /// Probability that the barrier was not crossed between the simulated steps, from the
+    /// Brownian-bridge correction (1.0 without it)
+    pub barrier_survival: f64,
+    /// Probability of having hit the barrier, with each hit weighted by the growth factor of
+    /// a knock-out rebate from its payment to expiry
+    pub rebate_weight: f64,
+    /// Recorded fixings of the first underlying
+    pub fixings: Vec<f64>,
+    /// Price of the first underlying at the start of the path
+    pub initial_price: f64,
+    /// Highest log price of each underlying observed so far (initial price included)
+    pub running_log_max: Vec<f64>,
+    /// Lowest log price of each underlying observed so far (initial price included)
+    pub running_log_min: Vec<f64>,
+}
+
+impl PathState {
+    /// Creates the state of a path starting at the initial prices and model state of the engine
+    pub fn new(engine: &PathEngine, num_fixings: usize) -> Self {
+        Self {
+            prices: engine.initial_prices.clone(),
+            log_prices: engine.initial_log_prices.clone(),
+            model_state: engine.initial_state.clone(),
+            barrier_hit: false,
+            barrier_survival: 1.0,
+            rebate_weight: 0.0,
+            fixings: Vec::with_capacity(num_fixings),
+            initial_price: engine.initial_prices[0],
+            running_log_max: engine.initial_log_prices.clone(),
+            running_log_min: engine.initial_log_prices.clone(),
+        }
+    }
+
+    /// Resets the path to the initial prices and model state of the engine, keeping the
+    /// allocated buffers
+    pub fn reset(&mut self, engine: &PathEngine) {
+        self.prices.copy_from_slice(&engine.initial_prices);
+        self.log_prices.copy_from_slice(&engine.initial_log_prices);
+        self.model_state.copy_from_slice(&engine.initial_state);
+        self.barrier_hit = false;
+        self.barrier_survival = 1.0;
+        self.rebate_weight = 0.0;
+        self.fixings.clear();
+        self.running_log_max.copy_from_slice(&engine.initial_log_prices);
+        self.running_log_min.copy_from_slice(&engine.initial_log_prices);
+    }
+
+    /// Advances the path over the given time step (counted from 1) and updates the running
+    /// extremes; the prices are left to `update_prices`
+    pub fn advance(&mut self, engine: &PathEngine, step: usize, shocks: &DVector<f64>, sign: f64) {
+        engine.advance(step, &mut self.log_prices, &mut self.model_state, shocks, sign);
+        for (i, &log_price) in self.log_prices.iter().enumerate() {
+            self.running_log_max[i] = self.running_log_max[i].max(log_price);
+            self.running_log_min[i] = self.running_log_min[i].min(log_price);
+        }
+    }
+
+    /// Exponentiates the current log prices into the prices
+    pub fn update_prices(&mut self) {
+        for (price, &log_price) in self.prices.iter_mut().zip(&self.log_prices) {
+            *price = math::exp(log_price);
+        }
+    }
+
+    /// Returns the observables of the first underlying the payoffs depend on; the prices must
+    /// be up to date
+    pub fn observables(&self) -> PathObservables<'_> {
+        PathObservables {
+            initial_price: self.initial_price,
+            final_price: self.prices[0],
+            running_max: math::exp(self.running_log_max[0]),
+            running_min: math::exp(self.running_log_min[0]),
+            fixings: &self.fixings,
+        }
+    }
+}
+
+/// Generator of the standard normal shocks of a run, drawing the shocks of each path in a
+/// fixed order of dimensions
+pub(crate) struct ShockGenerator {
+    rng: StdRng,
+    /// Quasi-random sequence for the leading dimensions of each path, if configured
+    quasi_random: Option<QuasiRandom>,
+    /// Current quasi-random point as uniforms
+    point: Vec<f64>,
+    /// Dimension of the next shock within the current path
+    dimension: usize,
+}
+
+impl ShockGenerator {
+    /// Starts a new path, moving on to the next quasi-random point
+    pub fn start_path(&mut self) {
+        if let Some(quasi_random) = &mut self.quasi_random {
+            quasi_random.next_point(&mut self.point);
+        }
+        self.dimension = 0;
+    }
+
+    /// Returns the next standard normal shock of the current path
+    pub fn next_normal(&mut self) -> f64 {
+        let shock = match self.point.get(self.dimension) {
+            Some(&uniform) => inverse_norm_cdf(uniform),
+            None => math::standard_normal(&mut self.rng),
+        };
+        self.dimension += 1;
+        shock
+    }
+}
+
+/// Creates the random number generator for a run, seeded if a seed is given and from a
+/// drawn seed otherwise (see `draw_seed`)
+pub(crate) fn create_rng(seed: Option<u64>) -> StdRng {
+    StdRng::seed_from_u64(seed.unwrap_or_else(crate::draw_seed))
+}
+
+/// Returns the shock signs simulated per sample: antithetic sampling simulates each
+/// path with Z and -Z, and the pair forms one sample
+pub(crate) fn shock_signs(antithetic: bool) -> &'static [f64] {
+    if antithetic {
+        &[1.0, -1.0]
+    } else {
+        &[1.0]
+    }
+}
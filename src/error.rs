@@ -0,0 +1,174 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use crate::barrier::BarrierError;
+
+/// Error returned by the pricing functions for invalid inputs or failed simulations
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum McError {
+    /// The correlation matrix does not match the underlyings, is not a symmetric matrix with
+    /// a unit diagonal and entries in [-1, 1], or is not positive definite
+    InvalidCorrelationMatrix(String),
+    /// No underlyings were given
+    NoUnderlyings,
+    /// The path weights of the configuration do not match the number of simulated paths, are
+    /// negative or not finite, or are all zero
+    InvalidPathWeights(String),
+    /// A spot price (or start value) is not positive and finite
+    InvalidSpotPrice {
+        /// Name of the underlying
+        underlying: String,
+        /// Offending spot price
+        spot_price: f64,
+    },
+    /// A volatility (flat, from the term structure or of a volatility event) is negative or
+    /// not finite
+    InvalidVolatility {
+        /// Name of the underlying
+        underlying: String,
+        /// Offending volatility
+        volatility: f64,
+    },
+    /// The quanto terms of an underlying have a negative or non-finite FX volatility or an
+    /// FX correlation outside of [-1, 1]
+    InvalidQuanto {
+        /// Name of the underlying
+        underlying: String,
+        /// Reason the terms are invalid
+        reason: String,
+    },
+    /// The parameters of the volatility smile of an underlying do not describe a smile (see
+    /// `VolatilitySmile::validate`)
+    InvalidSmile {
+        /// Name of the underlying
+        underlying: String,
+        /// Reason the smile is invalid
+        reason: String,
+    },
+    /// The number of paths is zero, exceeds the addressable memory of a pricer that keeps
+    /// all paths or the 2^32 - 1 points of the Sobol sequences
+    InvalidPaths(u64),
+    /// A barrier applies to an underlying that does not exist
+    BarrierIndexOutOfRange {
+        /// Offending underlying index
+        index: usize,
+        /// Number of underlyings
+        num_underlyings: usize,
+    },
+    /// A barrier could not be created
+    InvalidBarrier(String),
+    /// The start values do not match the underlyings
+    InvalidStartValues {
+        /// Number of underlyings
+        expected: usize,
+        /// Number of start values
+        actual: usize,
+    },
+    /// The model parameters do not match the underlyings
+    InvalidModel(String),
+    /// Fixing, exercise or observation days lie outside of the life of the product
+    InvalidSchedule(String),
+    /// The terms of the product are invalid, e.g. a negative strike
+    InvalidProduct(String),
+    /// The regression basis of the American pricer has no powers of the prices
+    InvalidBasisOrder,
+    /// The simulation produced a non-finite path value under `NonFinitePolicy::Error`
+    NonFiniteValue(f64),
+    /// Reading or writing a file failed
+    Io(String),
+    /// A file is not a scenario or result file of a supported version, or its scenarios were
+    /// simulated for another session
+    InvalidFile(String),
+    /// A price history cannot be parsed, has non-positive prices or too few returns to
+    /// estimate from, or the estimation settings are invalid
+    InvalidPriceHistory(String),
+    /// A date does not exist, cannot be parsed, or lies before the date it is counted from
+    InvalidDate(String),
+    /// An exercise policy does not fit the option or the underlyings it is applied to
+    InvalidExercisePolicy(String),
+}
+
+impl fmt::Display for McError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            McError::InvalidCorrelationMatrix(reason) => {
+                write!(f, "Invalid correlation matrix: {}", reason)
+            }
+            McError::NoUnderlyings => write!(f, "At least one underlying is required"),
+            McError::InvalidPathWeights(reason) => write!(f, "Invalid path weights: {}", reason),
+            McError::InvalidSpotPrice {
+                underlying,
+                spot_price,
+            } => write!(
+                f,
+                "Spot price of {} must be positive and finite, got {}",
+                underlying, spot_price
+            ),
+            McError::InvalidVolatility {
+                underlying,
+                volatility,
+            } => write!(
+                f,
+                "Volatility of {} must be non-negative and finite, got {}",
+                underlying, volatility
+            ),
+            McError::InvalidPaths(num_paths) => write!(
+                f,
+                "Number of paths must be positive and fit into memory and the quasi-random sequence, got {}",
+                num_paths
+            ),
+            McError::BarrierIndexOutOfRange {
+                index,
+                num_underlyings,
+            } => write!(
+                f,
+                "Barrier applies to underlying {}, but there are only {} underlyings",
+                index, num_underlyings
+            ),
+            McError::InvalidQuanto { underlying, reason } => {
+                write!(f, "Invalid quanto terms of {}: {}", underlying, reason)
+            }
+            McError::InvalidSmile { underlying, reason } => {
+                write!(f, "Invalid volatility smile of {}: {}", underlying, reason)
+            }
+            McError::InvalidBarrier(reason) => write!(f, "Invalid barrier: {}", reason),
+            McError::InvalidStartValues { expected, actual } => write!(
+                f,
+                "Start values must be given for each of the {} underlyings, got {}",
+                expected, actual
+            ),
+            McError::InvalidModel(reason) => write!(f, "Invalid model: {}", reason),
+            McError::InvalidSchedule(reason) => write!(f, "Invalid schedule: {}", reason),
+            McError::InvalidProduct(reason) => write!(f, "Invalid product: {}", reason),
+            McError::InvalidBasisOrder => write!(f, "Basis order must be at least 1"),
+            McError::NonFiniteValue(value) => {
+                write!(f, "Simulation produced a non-finite path value: {}", value)
+            }
+            McError::Io(reason) => write!(f, "I/O error: {}", reason),
+            McError::InvalidFile(reason) => write!(f, "Invalid file: {}", reason),
+            McError::InvalidPriceHistory(reason) => {
+                write!(f, "Invalid price history: {}", reason)
+            }
+            McError::InvalidDate(reason) => write!(f, "Invalid date: {}", reason),
+            McError::InvalidExercisePolicy(reason) => {
+                write!(f, "Invalid exercise policy: {}", reason)
+            }
+        }
+    }
+}
+
+impl Error for McError {}
+
+impl From<BarrierError> for McError {
+    fn from(error: BarrierError) -> Self {
+        McError::InvalidBarrier(error.to_string())
+    }
+}
+
+impl From<io::Error> for McError {
+    fn from(error: io::Error) -> Self {
+        McError::Io(error.to_string())
+    }
+}
@@ -0,0 +1,248 @@
+use nalgebra::DMatrix;
+
+use crate::barrier::{KnockType, RebateTiming};
+use crate::config::SimulationConfig;
+use crate::engine::{self, PathEngine, PathState};
+use crate::error::McError;
+use crate::payoff::PathObservables;
+use crate::product::Product;
+use crate::rates::RateCurve;
+use crate::statistics::weighted_percentiles;
+use crate::underlying::Underlying;
+use crate::{effective_barrier_level, is_barrier_hit, validation, with_start_values};
+
+/// Exposure profile of a product over future observation days, e.g. for counterparty credit
+/// risk (CVA)
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExposureProfile {
+    /// Observation days, counted from today, in increasing order
+    pub days: Vec<u32>,
+    /// Expected exposure on each observation day: the mean over the paths of the positive
+    /// part of the discounted value
+    pub expected_exposure: Vec<f64>,
+    /// Potential future exposure on each observation day: the `confidence` quantile over the
+    /// paths of the positive part of the discounted value
+    pub potential_future_exposure: Vec<f64>,
+    /// Confidence level of the potential future exposure, e.g. 0.95
+    pub confidence: f64,
+    /// Number of simulated paths
+    pub num_paths: u64,
+}
+
+impl ExposureProfile {
+    /// Returns the expected positive exposure: the average of the expected exposure over the
+    /// time to the last observation day, each day weighing the days since the previous one
+    pub fn expected_positive_exposure(&self) -> f64 {
+        let mut previous_day = 0;
+        let mut weighted_sum = 0.0;
+        for (&day, &exposure) in self.days.iter().zip(&self.expected_exposure) {
+            weighted_sum += (day - previous_day) as f64 * exposure;
+            previous_day = day;
+        }
+        weighted_sum / previous_day as f64
+    }
+
+    /// Returns the highest potential future exposure over the observation days
+    pub fn peak_exposure(&self) -> f64 {
+        self.potential_future_exposure
+            .iter()
+            .fold(0.0, |peak: f64, &exposure| peak.max(exposure))
+    }
+}
+
+/// Simulates the exposure profile of a product: its discounted value on each path at each of
+/// the observation days, summarized by the expected and potential future exposures
+///
+/// The value of a path on an observation day is the intrinsic value of the product if it
+/// settled on that day, discounted to today: the payoff on the current price and running
+/// extremes, with the current price standing in for the fixings not taken yet. Barriers are
+/// observed on the simulated closes of their monitoring days; a knocked-out option is worth
+/// its rebate if it is paid at expiry, a knock-in option not knocked in yet its rebate.
+///
+/// The paths take one step per day up to the last observation day.
+///
+/// # Arguments
+/// * `underlyings` - List of underlying assets
+/// * `correlation_matrix` - Correlation matrix (n x n) where n is the number of underlyings.
+///   Must be symmetric, positive semi-definite, with 1.0 on the diagonal.
+/// * `time_horizon_days` - Time to expiration in days
+/// * `product` - Product on the first underlying
+/// * `risk_free_rate` - Risk-free rate curve or flat annual rate (as a decimal, e.g., 0.05 for 5%)
+/// * `observation_days` - Days on which the exposure is observed, counted from today
+/// * `confidence` - Confidence level of the potential future exposure, e.g. 0.95
+/// * `config` - Number of paths and variance reduction settings. The control variate, the
+///   error tolerance, the path weights, the path observer and the barrier correction are not
+///   supported and ignored.
+///
+/// # Returns
+/// The exposure profile on the sorted and deduplicated observation days
+///
+/// # Errors
+/// Returns `McError::InvalidSchedule` if there are no observation days or they lie outside
+/// of the life of the product, `McError::InvalidProduct` if the confidence level lies outside
+/// of (0, 1), and an error for invalid inputs (see `price_option`).
+#[allow(clippy::too_many_arguments)]
+pub fn simulate_exposure(
+    underlyings: &[Underlying],
+    correlation_matrix: &DMatrix<f64>,
+    time_horizon_days: u32,
+    product: &Product,
+    risk_free_rate: impl Into<RateCurve>,
+    observation_days: &[u32],
+    confidence: f64,
+    config: &SimulationConfig,
+) -> Result<ExposureProfile, McError> {
+    let underlyings = &*with_start_values(underlyings, config)?;
+    validation::validate_inputs(underlyings, correlation_matrix, config)?;
+    validation::validate_payoff(&product.payoff)?;
+    if let Some(barrier) = &product.barrier {
+        validation::validate_barrier(barrier, underlyings.len())?;
+    }
+    let mut days = observation_days.to_vec();
+    days.sort_unstable();
+    days.dedup();
+    match (days.first(), days.last()) {
+        (Some(&first), Some(&last)) if first > 0 && last <= time_horizon_days => {}
+        _ => {
+            return Err(McError::InvalidSchedule(format!(
+                "observation days must lie in 1..={}, got {:?}",
+                time_horizon_days, observation_days
+            )))
+        }
+    }
+    if !(confidence > 0.0 && confidence < 1.0) {
+        return Err(McError::InvalidProduct(format!(
+            "confidence level must lie in (0, 1), got {}",
+            confidence
+        )));
+    }
+    let fixing_days = match product.payoff.schedule() {
+        Some(schedule) => schedule.fixing_days(time_horizon_days)?,
+        None => Vec::new(),
+    };
+
+    let rate_curve = risk_free_rate.into();
+    let time_to_expiration = config.day_count.year_fraction(time_horizon_days);
+    let num_steps = time_horizon_days as usize;
+    let engine = PathEngine::new(
+        underlyings,
+        correlation_matrix,
+        &rate_curve,
+        time_to_expiration,
+        num_steps,
+        config,
+    )?;
+    let mut generator = engine.shock_generator(config);
+    let discount_factors: Vec<f64> = days
+        .iter()
+        .map(|&day| rate_curve.discount_factor(day as f64 * time_to_expiration / num_steps as f64))
+        .collect();
+    let barrier = product.barrier.as_ref();
+    let barrier_level =
+        barrier.map(|barrier| effective_barrier_level(barrier, &engine.initial_prices));
+
+    let shock_signs = engine::shock_signs(config.antithetic);
+    let num_samples = config.num_paths.div_ceil(shock_signs.len() as u64);
+    let num_paths = num_samples * shock_signs.len() as u64;
+    let mut values: Vec<Vec<(f64, f64)>> = days
+        .iter()
+        .map(|_| Vec::with_capacity(num_paths as usize))
+        .collect();
+    let mut paths: Vec<PathState> = shock_signs
+        .iter()
+        .map(|_| PathState::new(&engine, fixing_days.len()))
+        .collect();
+    let mut shocks = engine.new_shocks();
+    let mut fixings = Vec::with_capacity(fixing_days.len());
+    let last_day = days[days.len() - 1] as usize;
+    for _ in 0..num_samples {
+        for path in &mut paths {
+            path.reset(&engine);
+        }
+        generator.start_path();
+
+        let mut next_fixing = 0;
+        let mut next_observation = 0;
+        for step in 1..=last_day {
+            engine.draw_shocks(&mut generator, &mut shocks);
+            let is_fixing_day =
+                next_fixing < fixing_days.len() && fixing_days[next_fixing] as usize == step;
+            let is_observation_day = days[next_observation] as usize == step;
+            for (&sign, path) in shock_signs.iter().zip(&mut paths) {
+                path.advance(&engine, step, &shocks, sign);
+                path.update_prices();
+                if let (Some(barrier), Some(level)) = (barrier, barrier_level) {
+                    if !path.barrier_hit
+                        && barrier
+                            .monitoring
+                            .is_monitored(step as u32, time_horizon_days)
+                        && is_barrier_hit(barrier, level, &path.prices)
+                    {
+                        path.barrier_hit = true;
+                    }
+                }
+                if is_fixing_day {
+                    path.fixings.push(path.prices[0]);
+                }
+                if !is_observation_day {
+                    continue;
+                }
+
+                // The current price stands in for the fixings not taken yet
+                fixings.clear();
+                fixings.extend_from_slice(&path.fixings);
+                fixings.resize(fixing_days.len(), path.prices[0]);
+                let intrinsic_payoff = product.payoff.evaluate(&PathObservables {
+                    fixings: &fixings,
+                    ..path.observables()
+                });
+                let value = match barrier {
+                    Some(barrier) => {
+                        let is_alive = path.barrier_hit == (barrier.knock_type == KnockType::In);
+                        let is_rebate_owed = barrier.rebate.is_some_and(|rebate| {
+                            barrier.knock_type == KnockType::In
+                                || rebate.timing == RebateTiming::AtExpiry
+                        });
+                        match barrier.rebate {
+                            _ if is_alive => intrinsic_payoff,
+                            Some(rebate) if is_rebate_owed => rebate.amount,
+                            _ => 0.0,
+                        }
+                    }
+                    None => intrinsic_payoff,
+                };
+                // Non-finite values from extreme parameters are treated according to the policy
+                if let Some(value) = config.non_finite_policy.apply(value)? {
+                    let exposure = (value * discount_factors[next_observation]).max(0.0);
+                    values[next_observation].push((exposure, 1.0));
+                }
+            }
+
+            if is_fixing_day {
+                next_fixing += 1;
+            }
+            if is_observation_day {
+                next_observation += 1;
+            }
+        }
+    }
+
+    let expected_exposure = values
+        .iter()
+        .map(|exposures| {
+            exposures.iter().map(|&(exposure, _)| exposure).sum::<f64>() / exposures.len() as f64
+        })
+        .collect();
+    let potential_future_exposure = values
+        .iter_mut()
+        .map(|exposures| weighted_percentiles(exposures, &[confidence])[0])
+        .collect();
+    Ok(ExposureProfile {
+        days,
+        expected_exposure,
+        potential_future_exposure,
+        confidence,
+        num_paths,
+    })
+}
@@ -0,0 +1,267 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::error::McError;
+
+/// Size of every value in a persisted file, so all values are aligned for `f64`
+pub(crate) const WORD: usize = 8;
+
+/// Version of the format written by this crate, shared by all kinds of files
+///
+/// Version 1 only existed for scenario files, which wrote their fields without records.
+pub(crate) const VERSION: u64 = 2;
+
+/// Oldest format version able to read the files written by this crate
+pub(crate) const COMPATIBLE_VERSION: u64 = 2;
+
+/// Writer of a persisted file in the framed format shared by scenario and result files
+///
+/// A file consists of 8-byte little-endian words: the magic bytes of its kind, the version
+/// of the format it was written with and the oldest version able to read it, followed by
+/// records. Each record starts with its tag and the byte length of its fields.
+///
+/// The format evolves without breaking older files or readers:
+/// * Readers skip records with unknown tags and ignore fields beyond the ones they know at
+///   the end of a record, so fields are only ever appended to a record and new data gets
+///   new tags.
+/// * Fields missing at the end of a record written by an older version take their defaults.
+/// * A change older readers cannot skip safely raises the compatible version, and readers
+///   reject files whose compatible version is above their own version.
+pub(crate) struct FileWriter(BufWriter<File>);
+
+impl FileWriter {
+    /// Creates the file and writes its magic bytes and versions
+    pub(crate) fn create(path: &Path, magic: &[u8; WORD]) -> Result<Self, McError> {
+        let mut writer = FileWriter(BufWriter::new(File::create(path)?));
+        writer.0.write_all(magic)?;
+        writer.0.write_all(&VERSION.to_le_bytes())?;
+        writer.0.write_all(&COMPATIBLE_VERSION.to_le_bytes())?;
+        Ok(writer)
+    }
+
+    /// Writes a record of the fields followed by the columns of values
+    pub(crate) fn record(
+        &mut self,
+        tag: u64,
+        fields: &Fields,
+        columns: &[&[f64]],
+    ) -> Result<(), McError> {
+        let len = fields.0.len()
+            + columns
+                .iter()
+                .map(|column| column.len() * WORD)
+                .sum::<usize>();
+        self.0.write_all(&tag.to_le_bytes())?;
+        self.0.write_all(&(len as u64).to_le_bytes())?;
+        self.0.write_all(&fields.0)?;
+        for column in columns {
+            for value in column.iter() {
+                self.0.write_all(&value.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Flushes the records to the file
+    pub(crate) fn finish(mut self) -> Result<(), McError> {
+        self.0.flush()?;
+        Ok(())
+    }
+}
+
+/// Encoder of the fields of a record
+#[derive(Default)]
+pub(crate) struct Fields(Vec<u8>);
+
+impl Fields {
+    pub(crate) fn word(&mut self, word: u64) -> &mut Self {
+        self.0.extend_from_slice(&word.to_le_bytes());
+        self
+    }
+
+    pub(crate) fn len(&mut self, len: usize) -> &mut Self {
+        self.word(len as u64)
+    }
+
+    pub(crate) fn flag(&mut self, flag: bool) -> &mut Self {
+        self.word(u64::from(flag))
+    }
+
+    pub(crate) fn value(&mut self, value: f64) -> &mut Self {
+        self.word(value.to_bits())
+    }
+
+    /// Encodes the number of values followed by the values
+    pub(crate) fn values(&mut self, values: &[f64]) -> &mut Self {
+        self.len(values.len());
+        values.iter().for_each(|&value| {
+            self.value(value);
+        });
+        self
+    }
+
+    /// Encodes a nested record of the given fields, which readers skip if they do not know
+    /// its tag
+    pub(crate) fn record(&mut self, tag: u64, fields: &Fields) -> &mut Self {
+        self.word(tag).len(fields.0.len());
+        self.0.extend_from_slice(&fields.0);
+        self
+    }
+
+    /// Encodes the number of days followed by the days
+    pub(crate) fn days(&mut self, days: &[u32]) -> &mut Self {
+        self.len(days.len());
+        days.iter().for_each(|&day| {
+            self.word(u64::from(day));
+        });
+        self
+    }
+
+    /// Encodes the byte length of a UTF-8 text followed by its bytes, padded with zeros to
+    /// whole words
+    pub(crate) fn text(&mut self, text: &str) -> &mut Self {
+        self.len(text.len());
+        self.0.extend_from_slice(text.as_bytes());
+        self.0.resize(self.0.len().next_multiple_of(WORD), 0);
+        self
+    }
+}
+
+/// Sequential reader of the words of a persisted file or of one of its records, rejecting
+/// truncated data
+pub(crate) struct Reader<'a> {
+    contents: &'a [u8],
+    position: usize,
+    end: usize,
+}
+
+impl<'a> Reader<'a> {
+    /// Creates a reader of the whole contents of a file
+    pub(crate) fn new(contents: &'a [u8]) -> Self {
+        Self {
+            contents,
+            position: 0,
+            end: contents.len(),
+        }
+    }
+
+    /// Checks the magic bytes of the file and returns the format version it was written with
+    ///
+    /// # Errors
+    /// Returns `McError::InvalidFile` if the magic bytes differ or the file needs a reader of a
+    /// later format version.
+    pub(crate) fn preamble(&mut self, magic: &[u8; WORD]) -> Result<u64, McError> {
+        if self.bytes(WORD).ok() != Some(magic.as_slice()) {
+            return Err(invalid("unknown file type"));
+        }
+        let file_version = self.word()?;
+        // Files of version 1 predate the compatible version
+        let compatible_version = if file_version > 1 {
+            self.word()?
+        } else {
+            file_version
+        };
+        if file_version == 0 || compatible_version > VERSION {
+            return Err(invalid(&format!(
+                "file version {} is not supported, the supported version is {}",
+                file_version, VERSION
+            )));
+        }
+        Ok(file_version)
+    }
+
+    /// Returns `true` if everything was read
+    pub(crate) fn is_empty(&self) -> bool {
+        self.position == self.end
+    }
+
+    /// Reads the tag of the next record (or nested record) and returns a reader of its fields
+    pub(crate) fn record(&mut self) -> Result<(u64, Reader<'a>), McError> {
+        let tag = self.word()?;
+        let len = self.len()?;
+        if len % WORD != 0 {
+            return Err(invalid("record is not aligned"));
+        }
+        let start = self.skip(len)?;
+        Ok((
+            tag,
+            Reader {
+                contents: self.contents,
+                position: start,
+                end: start + len,
+            },
+        ))
+    }
+
+    /// Skips the given number of bytes, returning their offset in the contents
+    pub(crate) fn skip(&mut self, len: usize) -> Result<usize, McError> {
+        let start = self.position;
+        self.bytes(len)?;
+        Ok(start)
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], McError> {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|&end| end <= self.end)
+            .ok_or_else(|| invalid("file is truncated"))?;
+        let bytes = &self.contents[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    pub(crate) fn word(&mut self) -> Result<u64, McError> {
+        let bytes = self.bytes(WORD)?;
+        Ok(u64::from_le_bytes(
+            bytes.try_into().expect("word of 8 bytes"),
+        ))
+    }
+
+    pub(crate) fn len(&mut self) -> Result<usize, McError> {
+        let len = self.word()?;
+        usize::try_from(len).map_err(|_| invalid(&format!("length {} is out of range", len)))
+    }
+
+    pub(crate) fn day(&mut self) -> Result<u32, McError> {
+        let day = self.word()?;
+        u32::try_from(day).map_err(|_| invalid(&format!("day {} is out of range", day)))
+    }
+
+    pub(crate) fn flag(&mut self) -> Result<bool, McError> {
+        match self.word()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            flag => Err(invalid(&format!("invalid flag {}", flag))),
+        }
+    }
+
+    pub(crate) fn value(&mut self) -> Result<f64, McError> {
+        Ok(f64::from_bits(self.word()?))
+    }
+
+    /// Reads the number of values followed by the values
+    pub(crate) fn values(&mut self) -> Result<Vec<f64>, McError> {
+        let len = self.len()?;
+        (0..len).map(|_| self.value()).collect()
+    }
+
+    /// Reads the number of days followed by the days
+    pub(crate) fn days(&mut self) -> Result<Vec<u32>, McError> {
+        let len = self.len()?;
+        (0..len).map(|_| self.day()).collect()
+    }
+
+    /// Reads the byte length of a UTF-8 text followed by its padded bytes
+    pub(crate) fn text(&mut self) -> Result<String, McError> {
+        let len = self.len()?;
+        let bytes = self.bytes(len.next_multiple_of(WORD))?;
+        String::from_utf8(bytes[..len].to_vec()).map_err(|_| invalid("text is not UTF-8"))
+    }
+}
+
+/// Returns the error of a file that cannot be read
+pub(crate) fn invalid(reason: &str) -> McError {
+    McError::InvalidFile(reason.to_string())
+}
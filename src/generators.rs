@@ -0,0 +1,384 @@
+use nalgebra::DMatrix;
+#[cfg(feature = "proptest")]
+use proptest::prelude::*;
+#[cfg(feature = "quickcheck")]
+use quickcheck::{Arbitrary, Gen};
+use rand::Rng;
+
+use crate::barrier::{Barrier, BarrierDirection, BarrierType, KnockType};
+use crate::math;
+use crate::underlying::Underlying;
+
+// The generators below draw from any `Rng`. Inputs drawn from a seed cannot shrink, so
+// property tests use the strategies of the `proptest` feature or the `Arbitrary` wrappers of
+// the `quickcheck` feature instead, which generate the same kinds of inputs and shrink a
+// failing case towards a small one: fewer underlyings, weaker correlations, barriers closer
+// to the spot.
+
+/// Number of factors per dimension beyond the identity in the generated correlation matrices
+#[cfg(any(feature = "proptest", feature = "quickcheck"))]
+const CORRELATION_FACTORS: usize = 2;
+
+/// Largest absolute factor loading of the generated correlation matrices
+#[cfg(any(feature = "proptest", feature = "quickcheck"))]
+const MAX_LOADING: f64 = 3.0;
+
+/// Largest distance of a generated relative barrier level from the nearest of 0.95 and 1.05
+#[cfg(any(feature = "proptest", feature = "quickcheck"))]
+const MAX_BARRIER_DISTANCE: f64 = 0.45;
+
+/// Generates a valid underlying with spot in [10, 500] and volatility in [5%, 80%]
+pub fn underlying<R: Rng + ?Sized>(rng: &mut R) -> Underlying {
+    let id: u32 = rng.gen_range(0..10_000);
+    Underlying::new(
+        format!("GEN{}", id),
+        rng.gen_range(10.0..500.0),
+        rng.gen_range(0.05..0.80),
+    )
+}
+
+/// Generates `count` valid underlyings
+pub fn underlyings<R: Rng + ?Sized>(rng: &mut R, count: usize) -> Vec<Underlying> {
+    (0..count).map(|_| underlying(rng)).collect()
+}
+
+/// Generates a random positive definite correlation matrix of the given size
+///
+/// The matrix is built as the normalized Gram matrix of random Gaussian vectors, so it is
+/// symmetric with a unit diagonal and admits a Cholesky decomposition.
+pub fn correlation_matrix<R: Rng + ?Sized>(rng: &mut R, size: usize) -> DMatrix<f64> {
+    // Using more factors than dimensions keeps the matrix well away from singularity
+    let num_factors = size + 2;
+    let factors: DMatrix<f64> =
+        DMatrix::from_fn(size, num_factors, |_, _| math::standard_normal(rng));
+    let gram = &factors * factors.transpose();
+    DMatrix::from_fn(size, size, |i, j| {
+        if i == j {
+            1.0
+        } else {
+            gram[(i, j)] / (gram[(i, i)] * gram[(j, j)]).sqrt()
+        }
+    })
+}
+
+/// Generates a valid barrier on a random non-empty subset of `num_underlyings` underlyings
+///
+/// Down barriers are placed below and up barriers above the initial reference level, so a
+/// fresh path never starts on the wrong side. Multi-underlying barriers are always relative.
+pub fn barrier<R: Rng + ?Sized>(rng: &mut R, num_underlyings: usize) -> Barrier {
+    let mut underlying_indices: Vec<usize> =
+        (0..num_underlyings).filter(|_| rng.gen_bool(0.5)).collect();
+    if underlying_indices.is_empty() {
+        underlying_indices.push(rng.gen_range(0..num_underlyings));
+    }
+
+    let barrier_type = match rng.gen_range(0..4) {
+        0 => BarrierType::WorstOf,
+        1 => BarrierType::BestOf,
+        2 => BarrierType::Average,
+        _ => BarrierType::Median,
+    };
+    let direction = BarrierDirection::from_is_up(rng.gen_bool(0.5));
+    let barrier_level = match direction {
+        BarrierDirection::Up => rng.gen_range(1.05..1.50),
+        BarrierDirection::Down => rng.gen_range(0.50..0.95),
+    };
+
+    relative_barrier(
+        barrier_level,
+        direction,
+        KnockType::from_is_in(rng.gen_bool(0.5)),
+        barrier_type,
+        underlying_indices,
+    )
+}
+
+/// Types of the generated barriers
+#[cfg(any(feature = "proptest", feature = "quickcheck"))]
+const BARRIER_TYPES: [BarrierType; 4] = [
+    BarrierType::WorstOf,
+    BarrierType::BestOf,
+    BarrierType::Average,
+    BarrierType::Median,
+];
+
+/// Returns the relative barrier of a generated level, which is valid for any number of
+/// underlyings
+fn relative_barrier(
+    barrier_level: f64,
+    direction: BarrierDirection,
+    knock_type: KnockType,
+    barrier_type: BarrierType,
+    underlying_indices: Vec<usize>,
+) -> Barrier {
+    Barrier::multi(
+        barrier_level,
+        direction,
+        knock_type,
+        barrier_type,
+        true,
+        underlying_indices,
+    )
+    .expect("Relative barriers are always valid")
+}
+
+/// Returns the relative level of a barrier at the given distance beyond 0.95 (down) or 1.05
+/// (up)
+#[cfg(any(feature = "proptest", feature = "quickcheck"))]
+fn barrier_level(direction: BarrierDirection, distance: f64) -> f64 {
+    match direction {
+        BarrierDirection::Up => 1.05 + distance,
+        BarrierDirection::Down => 0.95 - distance,
+    }
+}
+
+/// Returns the normalized Gram matrix of the rows `[I | L]`, where `L` holds the factor
+/// loadings of each dimension row by row
+///
+/// The identity block keeps the Gram matrix at least as positive definite as the identity,
+/// so the matrix admits a Cholesky decomposition for any loadings, and loadings of zero give
+/// independent underlyings.
+#[cfg(any(feature = "proptest", feature = "quickcheck"))]
+fn loaded_correlation_matrix(size: usize, loadings: &[f64]) -> DMatrix<f64> {
+    let factors = DMatrix::from_fn(size, size + CORRELATION_FACTORS, |i, j| {
+        if j < size {
+            if i == j {
+                1.0
+            } else {
+                0.0
+            }
+        } else {
+            loadings[i * CORRELATION_FACTORS + j - size]
+        }
+    });
+    let gram = &factors * factors.transpose();
+    DMatrix::from_fn(size, size, |i, j| {
+        if i == j {
+            1.0
+        } else {
+            gram[(i, j)] / (gram[(i, i)] * gram[(j, j)]).sqrt()
+        }
+    })
+}
+
+/// Strategy of valid underlyings with spot in [10, 500] and volatility in [5%, 80%]
+///
+/// Failing cases shrink towards the lowest spot and volatility.
+#[cfg(feature = "proptest")]
+pub fn underlying_strategy() -> impl Strategy<Value = Underlying> {
+    (0..10_000u32, 10.0..500.0, 0.05..0.80).prop_map(|(id, spot_price, volatility)| {
+        Underlying::new(format!("GEN{}", id), spot_price, volatility)
+    })
+}
+
+/// Strategy of `count` valid underlyings (see `underlying_strategy`)
+#[cfg(feature = "proptest")]
+pub fn underlyings_strategy(count: usize) -> impl Strategy<Value = Vec<Underlying>> {
+    proptest::collection::vec(underlying_strategy(), count)
+}
+
+/// Strategy of positive definite correlation matrices of the given size
+///
+/// The matrices are normalized Gram matrices of factor loadings with an identity block, so
+/// failing cases shrink towards independent underlyings.
+#[cfg(feature = "proptest")]
+pub fn correlation_strategy(size: usize) -> impl Strategy<Value = DMatrix<f64>> {
+    proptest::collection::vec(-MAX_LOADING..MAX_LOADING, size * CORRELATION_FACTORS)
+        .prop_map(move |loadings| loaded_correlation_matrix(size, &loadings))
+}
+
+/// Strategy of valid relative barriers on non-empty subsets of `num_underlyings` underlyings
+///
+/// As with `barrier`, down barriers lie in [0.50, 0.95] and up barriers in [1.05, 1.50] of
+/// the initial reference level. Failing cases shrink towards fewer underlyings and levels
+/// closer to the reference.
+///
+/// # Panics
+/// Panics if `num_underlyings` is zero.
+#[cfg(feature = "proptest")]
+pub fn barrier_strategy(num_underlyings: usize) -> impl Strategy<Value = Barrier> {
+    assert!(num_underlyings > 0, "Expected at least one underlying");
+    let indices: Vec<usize> = (0..num_underlyings).collect();
+    (
+        proptest::sample::subsequence(indices, 1..=num_underlyings),
+        proptest::sample::select(BARRIER_TYPES.to_vec()),
+        any::<bool>(),
+        any::<bool>(),
+        0.0..MAX_BARRIER_DISTANCE,
+    )
+        .prop_map(|(underlying_indices, barrier_type, is_up, is_in, distance)| {
+            let direction = BarrierDirection::from_is_up(is_up);
+            relative_barrier(
+                barrier_level(direction, distance),
+                direction,
+                KnockType::from_is_in(is_in),
+                barrier_type,
+                underlying_indices,
+            )
+        })
+}
+
+/// Valid underlying for quickcheck, generated like `underlying`
+///
+/// Failing cases shrink towards a spot of 100 and a volatility of 20%.
+#[cfg(feature = "quickcheck")]
+#[derive(Debug, Clone)]
+pub struct ArbitraryUnderlying(pub Underlying);
+
+#[cfg(feature = "quickcheck")]
+impl Arbitrary for ArbitraryUnderlying {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Self(Underlying::new(
+            format!("GEN{}", u32::arbitrary(g) % 10_000),
+            10.0 + 490.0 * unit_draw(g),
+            0.05 + 0.75 * unit_draw(g),
+        ))
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        let underlying = self.0.clone();
+        let spots = shrink_towards(underlying.spot_price, 100.0).map({
+            let underlying = underlying.clone();
+            move |spot_price| {
+                Self(Underlying {
+                    spot_price,
+                    ..underlying.clone()
+                })
+            }
+        });
+        let volatilities = shrink_towards(underlying.volatility, 0.2).map(move |volatility| {
+            Self(Underlying {
+                volatility,
+                ..underlying.clone()
+            })
+        });
+        Box::new(spots.chain(volatilities))
+    }
+}
+
+/// Positive definite correlation matrix of one to six underlyings for quickcheck
+///
+/// Failing cases shrink towards fewer underlyings, by dropping the last one, and towards
+/// independent underlyings, by halving the correlations.
+#[cfg(feature = "quickcheck")]
+#[derive(Debug, Clone)]
+pub struct ArbitraryCorrelation(pub DMatrix<f64>);
+
+#[cfg(feature = "quickcheck")]
+impl Arbitrary for ArbitraryCorrelation {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let size = *g.choose(&[1, 2, 3, 4, 5, 6]).expect("sizes are not empty");
+        let loadings: Vec<f64> = (0..size * CORRELATION_FACTORS)
+            .map(|_| MAX_LOADING * (2.0 * unit_draw(g) - 1.0))
+            .collect();
+        Self(loaded_correlation_matrix(size, &loadings))
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        let matrix = &self.0;
+        let size = matrix.nrows();
+        let mut candidates = Vec::new();
+        // Principal submatrices and averages with the identity stay positive definite
+        if size > 1 {
+            candidates.push(Self(matrix.view((0, 0), (size - 1, size - 1)).into_owned()));
+        }
+        let identity = DMatrix::identity(size, size);
+        if (matrix - &identity).amax() > 1e-3 {
+            candidates.push(Self((matrix + &identity) / 2.0));
+        } else if *matrix != identity {
+            candidates.push(Self(identity));
+        }
+        Box::new(candidates.into_iter())
+    }
+}
+
+/// Valid relative barrier on a non-empty subset of one to five underlyings for quickcheck,
+/// with the number of underlyings it was generated for
+///
+/// Failing cases shrink towards fewer underlyings and levels closer to the reference.
+#[cfg(feature = "quickcheck")]
+#[derive(Debug, Clone)]
+pub struct ArbitraryBarrier {
+    /// Generated barrier
+    pub barrier: Barrier,
+    /// Number of underlyings the barrier may apply to
+    pub num_underlyings: usize,
+}
+
+#[cfg(feature = "quickcheck")]
+impl Arbitrary for ArbitraryBarrier {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let num_underlyings = *g.choose(&[1, 2, 3, 4, 5]).expect("counts are not empty");
+        let mut underlying_indices: Vec<usize> =
+            (0..num_underlyings).filter(|_| bool::arbitrary(g)).collect();
+        if underlying_indices.is_empty() {
+            underlying_indices.push(usize::arbitrary(g) % num_underlyings);
+        }
+        let direction = BarrierDirection::from_is_up(bool::arbitrary(g));
+        Self {
+            barrier: relative_barrier(
+                barrier_level(direction, MAX_BARRIER_DISTANCE * unit_draw(g)),
+                direction,
+                KnockType::from_is_in(bool::arbitrary(g)),
+                *g.choose(&BARRIER_TYPES).expect("types are not empty"),
+                underlying_indices,
+            ),
+            num_underlyings,
+        }
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        let barrier = &self.barrier;
+        let indices = &barrier.underlying_indices;
+        let with_indices = |underlying_indices: Vec<usize>| {
+            let num_underlyings = underlying_indices.iter().max().map_or(0, |&i| i + 1);
+            Self {
+                barrier: Barrier {
+                    underlying_indices,
+                    ..barrier.clone()
+                },
+                num_underlyings,
+            }
+        };
+        let mut candidates = Vec::new();
+        if indices.len() > 1 {
+            candidates.extend((0..indices.len()).map(|removed| {
+                let mut remaining = indices.clone();
+                remaining.remove(removed);
+                with_indices(remaining)
+            }));
+        }
+        if indices.iter().max().map_or(0, |&i| i + 1) < self.num_underlyings {
+            candidates.push(with_indices(indices.clone()));
+        }
+        let nearest = barrier_level(barrier.direction, 0.0);
+        candidates.extend(shrink_towards(barrier.barrier_level, nearest).map(|barrier_level| {
+            Self {
+                barrier: Barrier {
+                    barrier_level,
+                    ..barrier.clone()
+                },
+                num_underlyings: self.num_underlyings,
+            }
+        }));
+        Box::new(candidates.into_iter())
+    }
+}
+
+/// Draws a number in [0, 1] from a quickcheck generator
+#[cfg(feature = "quickcheck")]
+fn unit_draw(g: &mut Gen) -> f64 {
+    f64::from(u32::arbitrary(g)) / f64::from(u32::MAX)
+}
+
+/// Returns the candidates a number shrinks to: the target, then halfway to it
+#[cfg(feature = "quickcheck")]
+fn shrink_towards(value: f64, target: f64) -> impl Iterator<Item = f64> {
+    let halfway = target + (value - target) / 2.0;
+    let candidates = if (value - target).abs() > 1e-6 {
+        vec![target, halfway]
+    } else {
+        Vec::new()
+    };
+    candidates.into_iter()
+}
@@ -0,0 +1,240 @@
+use std::num::NonZeroUsize;
+use std::thread;
+
+use crate::closed_form::{norm_cdf, norm_pdf};
+use crate::math;
+use crate::payoff::OptionType;
+use crate::rates::RateCurve;
+
+/// Smallest number of quotes inverted by one thread of `implied_volatilities`; smaller
+/// chains are inverted on the calling thread
+const MIN_QUOTES_PER_THREAD: usize = 512;
+
+/// Maximum number of safeguarded Newton iterations per quote
+const MAX_ITERATIONS: usize = 100;
+
+/// Relative price tolerance of the inversion, in units of the upper price bound
+const PRICE_TOLERANCE: f64 = 1e-14;
+
+/// Quoted price of a European option in a chain
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OptionQuote {
+    /// Strike price of the option
+    pub strike_price: f64,
+    /// Time to expiration in years
+    pub time_to_expiration: f64,
+    /// Call or Put option
+    pub option_type: OptionType,
+    /// Quoted option price
+    pub price: f64,
+}
+
+impl OptionQuote {
+    /// Creates a quote of a European option
+    pub fn new(
+        strike_price: f64,
+        time_to_expiration: f64,
+        option_type: OptionType,
+        price: f64,
+    ) -> Self {
+        Self {
+            strike_price,
+            time_to_expiration,
+            option_type,
+            price,
+        }
+    }
+}
+
+/// Outcome of the inversion of a quote into its Black-Scholes implied volatility
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ImpliedVolatility {
+    /// Annualized volatility that reprices the quote; zero for a quote at its discounted
+    /// intrinsic value on the forward
+    Solved(f64),
+    /// The price lies below the lower no-arbitrage bound, the discounted intrinsic value on
+    /// the forward, so no volatility reprices it
+    BelowLowerBound,
+    /// The price reaches the upper no-arbitrage bound, the spot price for calls and the
+    /// discounted strike for puts, so no finite volatility reprices it
+    AboveUpperBound,
+    /// The spot, strike or time to expiration is not finite or not positive, or the rate or
+    /// the price is not finite
+    InvalidQuote,
+}
+
+impl ImpliedVolatility {
+    /// Returns the implied volatility if the quote could be inverted
+    pub fn volatility(&self) -> Option<f64> {
+        match *self {
+            ImpliedVolatility::Solved(volatility) => Some(volatility),
+            _ => None,
+        }
+    }
+}
+
+/// Inverts the Black-Scholes formula for the volatility that reprices a quote
+///
+/// The inversion runs a Newton iteration on the out-of-the-money option, obtained by put-call
+/// parity, started at the volatility of maximal vega and safeguarded by bisection, which
+/// converges to machine precision within a few iterations for all quotes strictly inside
+/// the no-arbitrage bounds.
+///
+/// # Arguments
+/// * `spot_price` - Current spot price of the underlying, or its prepaid forward (the spot
+///   net of the present value of the dividends) for underlyings with dividends
+/// * `risk_free_rate` - Risk-free rate curve or flat annual rate (as a decimal, e.g., 0.05 for 5%)
+/// * `quote` - Quoted option price
+///
+/// # Returns
+/// The implied volatility, or why the quote has none
+pub fn implied_volatility(
+    spot_price: f64,
+    risk_free_rate: impl Into<RateCurve>,
+    quote: &OptionQuote,
+) -> ImpliedVolatility {
+    invert(spot_price, &risk_free_rate.into(), quote)
+}
+
+/// Inverts a whole chain of quotes into their Black-Scholes implied volatilities
+///
+/// The quotes are split into contiguous chunks inverted in parallel on all available cores,
+/// each in a tight loop without allocations (see `implied_volatility`). Quotes outside of
+/// the no-arbitrage bounds or with invalid inputs do not fail the chain; their outcome says
+/// why they have no implied volatility.
+///
+/// # Arguments
+/// * `spot_price` - Current spot price of the underlying, or its prepaid forward (the spot
+///   net of the present value of the dividends) for underlyings with dividends
+/// * `risk_free_rate` - Risk-free rate curve or flat annual rate (as a decimal, e.g., 0.05 for 5%)
+/// * `quotes` - Quoted option prices, e.g. of all strikes and maturities of a chain
+///
+/// # Returns
+/// The implied volatility of each quote, or why it has none, in the order of the quotes
+pub fn implied_volatilities(
+    spot_price: f64,
+    risk_free_rate: impl Into<RateCurve>,
+    quotes: &[OptionQuote],
+) -> Vec<ImpliedVolatility> {
+    let rate_curve = risk_free_rate.into();
+    let mut volatilities = vec![ImpliedVolatility::InvalidQuote; quotes.len()];
+    // Without threads, e.g. on wasm32-unknown-unknown, the chain is inverted on this thread
+    let num_threads = thread::available_parallelism()
+        .map_or(1, NonZeroUsize::get)
+        .min(quotes.len().div_ceil(MIN_QUOTES_PER_THREAD))
+        .max(1);
+    let chunk_size = quotes.len().div_ceil(num_threads).max(1);
+    let invert_chunk = |quotes: &[OptionQuote], volatilities: &mut [ImpliedVolatility]| {
+        for (quote, volatility) in quotes.iter().zip(volatilities) {
+            *volatility = invert(spot_price, &rate_curve, quote);
+        }
+    };
+    if num_threads == 1 {
+        invert_chunk(quotes, &mut volatilities);
+        return volatilities;
+    }
+    thread::scope(|scope| {
+        for (quotes, volatilities) in quotes
+            .chunks(chunk_size)
+            .zip(volatilities.chunks_mut(chunk_size))
+        {
+            scope.spawn(|| invert_chunk(quotes, volatilities));
+        }
+    });
+    volatilities
+}
+
+/// Inverts a single quote (see `implied_volatility`)
+fn invert(spot_price: f64, rate_curve: &RateCurve, quote: &OptionQuote) -> ImpliedVolatility {
+    let OptionQuote {
+        strike_price,
+        time_to_expiration,
+        option_type,
+        price,
+    } = *quote;
+    let rate = rate_curve.zero_rate(time_to_expiration);
+    let is_valid = [spot_price, strike_price, time_to_expiration]
+        .iter()
+        .all(|value| value.is_finite() && *value > 0.0)
+        && rate.is_finite()
+        && price.is_finite();
+    if !is_valid {
+        return ImpliedVolatility::InvalidQuote;
+    }
+
+    let discounted_strike = strike_price * math::exp(-rate * time_to_expiration);
+    let (lower_bound, upper_bound) = match option_type {
+        OptionType::Call => ((spot_price - discounted_strike).max(0.0), spot_price),
+        OptionType::Put => ((discounted_strike - spot_price).max(0.0), discounted_strike),
+    };
+    let tolerance = PRICE_TOLERANCE * upper_bound;
+    if price < lower_bound - tolerance {
+        return ImpliedVolatility::BelowLowerBound;
+    }
+    if price >= upper_bound {
+        return ImpliedVolatility::AboveUpperBound;
+    }
+    // The time value of the out-of-the-money option equals that of the quote by put-call
+    // parity, and is inverted without the cancellation in the intrinsic value
+    let time_value = price - lower_bound;
+    if time_value <= tolerance {
+        return ImpliedVolatility::Solved(0.0);
+    }
+    let is_call = spot_price < discounted_strike;
+
+    let sqrt_time = time_to_expiration.sqrt();
+    let log_moneyness = math::ln(spot_price / discounted_strike);
+    let out_of_the_money_price = |volatility: f64| {
+        let std_dev = volatility * sqrt_time;
+        let d1 = log_moneyness / std_dev + 0.5 * std_dev;
+        let d2 = d1 - std_dev;
+        let price = if is_call {
+            spot_price * norm_cdf(d1) - discounted_strike * norm_cdf(d2)
+        } else {
+            discounted_strike * norm_cdf(-d2) - spot_price * norm_cdf(-d1)
+        };
+        (price, spot_price * norm_pdf(d1) * sqrt_time)
+    };
+
+    // Bracket the volatility; the price increases with the volatility towards the bound
+    let mut low = 0.0;
+    let mut high = 1.0;
+    while out_of_the_money_price(high).0 < time_value {
+        low = high;
+        high *= 2.0;
+        if high > 1e6 {
+            return ImpliedVolatility::AboveUpperBound;
+        }
+    }
+    // Newton from the volatility of maximal vega, bisecting whenever it leaves the bracket
+    let mut volatility = (2.0 * log_moneyness.abs() / time_to_expiration).sqrt();
+    if !(volatility > low && volatility < high) {
+        volatility = 0.5 * (low + high);
+    }
+    for _ in 0..MAX_ITERATIONS {
+        let (price, vega) = out_of_the_money_price(volatility);
+        let error = price - time_value;
+        if error.abs() <= tolerance {
+            break;
+        }
+        if error > 0.0 {
+            high = volatility;
+        } else {
+            low = volatility;
+        }
+        let newton = volatility - error / vega;
+        let next = if newton > low && newton < high {
+            newton
+        } else {
+            0.5 * (low + high)
+        };
+        if (next - volatility).abs() <= f64::EPSILON * volatility {
+            volatility = next;
+            break;
+        }
+        volatility = next;
+    }
+    ImpliedVolatility::Solved(volatility)
+}
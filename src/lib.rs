@@ -1,13 +1,143 @@
+pub mod aggregation;
+pub mod american;
+pub mod audit;
+pub mod autocallable;
 pub mod barrier;
+pub mod basket;
+pub mod batch;
+pub mod benchmark;
+pub mod bounds;
+pub mod calendar;
+pub mod closed_form;
+pub mod config;
+pub mod correlation;
+mod engine;
+pub mod error;
+pub mod exposure;
+mod format;
+#[cfg(feature = "test_utils")]
+pub mod generators;
+pub mod implied_vol;
+pub mod local_vol;
+pub mod market;
+pub mod market_data;
+mod math;
+pub mod model;
+pub mod multi_barrier;
+pub mod observer;
+pub mod package;
+pub mod path_estimate;
+pub mod path_payoff;
+pub mod payoff;
+pub mod portfolio;
+pub mod portfolio_state;
+pub mod product;
+#[cfg(feature = "python")]
+pub mod python;
+mod qmc;
+pub mod quick_quote;
+pub mod quotation;
+pub mod rates;
+pub mod replication;
+pub mod request;
+pub mod result;
+pub mod roll_down;
+mod scenario;
+#[cfg(feature = "serde")]
+mod serialization;
+pub mod session;
+pub mod simulation;
+pub mod smile;
+mod statistics;
+pub mod stress;
+pub mod terminal_payoff;
+#[cfg(feature = "test_utils")]
+pub mod test_utils;
+pub mod tornado;
 pub mod underlying;
+mod validation;
+pub mod variance_swap;
+pub mod vol_surface;
+pub mod vectorized;
 
-use nalgebra::{DMatrix, DVector};
-use rand_distr::{Distribution, Normal};
-pub use barrier::{Barrier, BarrierType};
-pub use underlying::Underlying;
+use std::borrow::Cow;
+
+use engine::{PathEngine, PathState};
+use qmc::DimensionBudget;
+use statistics::{ChunkedStatistics, SampleStatistics};
+use nalgebra::DMatrix;
+pub use aggregation::{aggregate_results, GroupTotal, GroupingLevel};
+pub use american::{
+    price_american, price_american_with_basis, price_american_with_policy,
+    price_american_with_upper_bound, AmericanResult, BasisFamily, DualBound, DualSettings,
+    ExercisePolicy, RegressionBasis, RegressionFit,
+};
+pub use audit::AuditEntry;
+pub use autocallable::{
+    attribute_autocallable_greeks, price_autocallable, Autocallable, AutocallableAttribution,
+    AutocallableComponent, AutocallableResult, ComponentGreeks,
+};
+pub use barrier::{
+    Barrier, BarrierCombination, BarrierCorrection, BarrierDirection, BarrierMonitoring,
+    BarrierObservation, BarrierQuantifier, BarrierType, KnockType, Rebate, RebateTiming,
+};
+pub use basket::{price_basket_option, BasketOption};
+pub use batch::{price_batch, AccuracyTarget, BatchResult, BatchTrade, Priority};
+pub use benchmark::{
+    benchmark_cases, run_benchmarks, BenchmarkCase, BenchmarkOutcome, BENCHMARK_STD_ERRORS,
+};
+pub use bounds::PriceBounds;
+pub use calendar::{Date, HolidayCalendar, Weekday};
+pub use correlation::{
+    stress_principal_component, CorrelatedNormalGenerator, CorrelationFactor,
+    CorrelationSampling, RandomCorrelation,
+};
+pub use error::McError;
+pub use exposure::{simulate_exposure, ExposureProfile};
+pub use implied_vol::{implied_volatilities, implied_volatility, ImpliedVolatility, OptionQuote};
+pub use config::{
+    DayCountConvention, ErrorTolerance, NonFinitePolicy, Sampling, SimulationConfig, VarianceTime,
+};
+pub use local_vol::{LocalVolSurface, LocalVolatility};
+pub use market::MarketSnapshot;
+pub use market_data::{EstimationSettings, PriceHistory};
+pub use model::{BlackScholes, Heston, HestonParameters, Model, StepInputs};
+pub use multi_barrier::price_option_with_barriers;
+pub use observer::{ObservedPath, PathObserver, PathRecorder, RecordedPath};
+pub use package::{price_barrier_package, BarrierLeg, PackageResult, VanillaLeg};
+pub use path_estimate::{estimate_paths_needed, PathEstimate};
+pub use path_payoff::{price_path_payoff, PathPayoff, PathStep};
+pub use payoff::{Averaging, FixingSchedule, OptionType, Payoff};
+pub use portfolio::{
+    price_portfolio, price_portfolio_with_maturities, price_term_structure, PortfolioResult,
+};
+pub use portfolio_state::{PortfolioState, PortfolioUpdate};
+pub use product::{Product, ProductId};
+pub use quick_quote::quick_quote;
+pub use quotation::{
+    DeltaUnit, GreekConvention, QuotationConvention, Quote, QuoteUnit, RhoUnit, Rounding, VegaUnit,
+};
+pub use rates::{Interpolation, RateCurve};
+pub use replication::{
+    replicate_product, ReplicatingInstrument, ReplicationLeg, StaticReplication,
+};
+pub use request::PricingRequest;
+pub use result::{load_results, save_results, PricingResult, PricingWarning};
+pub use roll_down::{roll_down_report, RollDownPoint, RollDownReport};
+pub use session::{Greeks, PortfolioGreeks, PricingSession};
+pub use simulation::{Simulation, SimulationProgress};
+pub use smile::{SmileDynamics, VolatilitySmile};
+pub use stress::{stress_test, CorrelationStress, StressGrid, StressResult, StressScenario};
+pub use terminal_payoff::{price_terminal_payoff, TerminalPayoff};
+pub use tornado::{tornado_report, TornadoBar, TornadoBumps, TornadoInput, TornadoReport};
+pub use underlying::{AssetClass, Dividend, Quanto, Underlying, VolatilityEvent};
+pub use variance_swap::{fair_swap_strikes, FairStrikes};
+pub use vectorized::price_vectorized;
+pub use vol_surface::{ArbitrageKind, ArbitrageViolation, ImpliedVolSurface, VolatilityAdjustment};
 
 /// Prices a European option (Call or Put) using Monte Carlo simulation
 /// Supports multiple underlyings with correlation and barrier options.
+/// Shorthand for a `PricingRequest` with the default simulation settings.
 ///
 /// # Arguments
 /// * `underlyings` - List of underlying assets
@@ -15,210 +145,1070 @@ pub use underlying::Underlying;
 ///   Must be symmetric, positive semi-definite, with 1.0 on the diagonal.
 /// * `time_horizon_days` - Time to expiration in days
 /// * `strike_price` - Strike price of the option
-/// * `is_call` - `true` for Call option, `false` for Put option
-/// * `risk_free_rate` - Annual risk-free interest rate (as a decimal, e.g., 0.05 for 5%)
+/// * `option_type` - Call or Put option
+/// * `risk_free_rate` - Risk-free rate curve or flat annual rate (as a decimal, e.g., 0.05 for 5%)
 /// * `num_paths` - Number of Monte Carlo simulation paths
 /// * `barrier` - Optional barrier for barrier options. If `None`, prices a vanilla option.
 ///
 /// # Returns
 /// The estimated option price
+///
+/// # Errors
+/// Returns an error for invalid inputs, e.g. a correlation matrix that is not positive
+/// definite (unless `SimulationConfig::repair_correlation` is set), negative volatilities,
+/// zero paths or barrier indices out of range.
+#[allow(clippy::too_many_arguments)]
 pub fn price_option(
     underlyings: &[Underlying],
     correlation_matrix: &DMatrix<f64>,
     time_horizon_days: u32,
     strike_price: f64,
-    is_call: bool,
-    risk_free_rate: f64,
-    num_paths: usize,
+    option_type: OptionType,
+    risk_free_rate: impl Into<RateCurve>,
+    num_paths: u64,
     barrier: Option<&Barrier>,
-) -> f64 {
-    let num_underlyings = underlyings.len();
-    
-    // Validate correlation matrix dimensions
-    assert_eq!(
-        correlation_matrix.nrows(),
-        num_underlyings,
-        "Correlation matrix must have {} rows",
-        num_underlyings
+) -> Result<f64, McError> {
+    let mut request = PricingRequest::new()
+        .underlyings(underlyings.iter().cloned())
+        .correlation(correlation_matrix.clone())
+        .rate(risk_free_rate)
+        .maturity_days(time_horizon_days)
+        .strike(strike_price)
+        .option_type(option_type)
+        .paths(num_paths);
+    if let Some(barrier) = barrier {
+        request = request.barrier(barrier.clone());
+    }
+    request.price().map(|result| result.price)
+}
+
+/// Prices a European option maturing on a date, with the days to maturity counted from the
+/// valuation date in calendar days (ACT/365) as by `SimulationConfig::default()`
+///
+/// Use `PricingRequest::maturity_date` with a configuration for other day count conventions
+/// and holiday calendars.
+///
+/// # Errors
+/// Returns `McError::InvalidDate` if the maturity lies before the valuation date, or an error
+/// for invalid inputs (see `price_option`).
+#[allow(clippy::too_many_arguments)]
+pub fn price_option_on_dates(
+    underlyings: &[Underlying],
+    correlation_matrix: &DMatrix<f64>,
+    valuation_date: Date,
+    maturity_date: Date,
+    strike_price: f64,
+    option_type: OptionType,
+    risk_free_rate: impl Into<RateCurve>,
+    num_paths: u64,
+    barrier: Option<&Barrier>,
+) -> Result<f64, McError> {
+    let mut request = PricingRequest::new()
+        .underlyings(underlyings.iter().cloned())
+        .correlation(correlation_matrix.clone())
+        .rate(risk_free_rate)
+        .valuation_date(valuation_date)
+        .maturity_date(maturity_date)
+        .strike(strike_price)
+        .option_type(option_type)
+        .paths(num_paths);
+    if let Some(barrier) = barrier {
+        request = request.barrier(barrier.clone());
+    }
+    request.price().map(|result| result.price)
+}
+
+/// Prices a European option (Call or Put) using Monte Carlo simulation with the given
+/// simulation configuration, reporting the standard error of the estimate.
+///
+/// # Arguments
+/// * `underlyings` - List of underlying assets
+/// * `correlation_matrix` - Correlation matrix (n x n) where n is the number of underlyings.
+///   Must be symmetric, positive semi-definite, with 1.0 on the diagonal.
+/// * `time_horizon_days` - Time to expiration in days
+/// * `strike_price` - Strike price of the option
+/// * `option_type` - Call or Put option
+/// * `risk_free_rate` - Risk-free rate curve or flat annual rate (as a decimal, e.g., 0.05 for 5%)
+/// * `barrier` - Optional barrier for barrier options. If `None`, prices a vanilla option.
+/// * `config` - Number of paths and variance reduction settings
+///
+/// # Returns
+/// The estimated option price together with its standard error
+///
+/// # Errors
+/// Returns an error for invalid inputs (see `price_option`).
+#[allow(clippy::too_many_arguments)]
+pub fn price_option_with_config(
+    underlyings: &[Underlying],
+    correlation_matrix: &DMatrix<f64>,
+    time_horizon_days: u32,
+    strike_price: f64,
+    option_type: OptionType,
+    risk_free_rate: impl Into<RateCurve>,
+    barrier: Option<&Barrier>,
+    config: &SimulationConfig,
+) -> Result<PricingResult, McError> {
+    price_payoff(
+        underlyings,
+        correlation_matrix,
+        time_horizon_days,
+        &Payoff::Vanilla {
+            strike_price,
+            option_type,
+        },
+        risk_free_rate,
+        barrier,
+        config,
+    )
+}
+
+/// Prices an option with an arbitrary payoff on the first underlying using Monte Carlo
+/// simulation, reporting the standard error of the estimate.
+///
+/// # Arguments
+/// * `underlyings` - List of underlying assets
+/// * `correlation_matrix` - Correlation matrix (n x n) where n is the number of underlyings.
+///   Must be symmetric, positive semi-definite, with 1.0 on the diagonal.
+/// * `time_horizon_days` - Time to expiration in days
+/// * `payoff` - Payoff of the option, e.g. vanilla or Asian
+/// * `risk_free_rate` - Risk-free rate curve or flat annual rate (as a decimal, e.g., 0.05 for 5%)
+/// * `barrier` - Optional barrier for barrier options
+/// * `config` - Number of paths and variance reduction settings
+///
+/// # Returns
+/// The estimated option price together with its standard error
+///
+/// A vanilla option without barrier on an underlying with a smile is priced with the smile
+/// volatility at its strike in place of the volatility and term structure of the underlying.
+///
+/// # Errors
+/// Returns an error for invalid inputs (see `price_option`) or an invalid fixing schedule.
+pub fn price_payoff(
+    underlyings: &[Underlying],
+    correlation_matrix: &DMatrix<f64>,
+    time_horizon_days: u32,
+    payoff: &Payoff,
+    risk_free_rate: impl Into<RateCurve>,
+    barrier: Option<&Barrier>,
+    config: &SimulationConfig,
+) -> Result<PricingResult, McError> {
+    let underlyings = &*with_start_values(underlyings, config)?;
+    let rate_curve = risk_free_rate.into();
+    let underlyings = &*with_smile_volatility(
+        underlyings,
+        time_horizon_days,
+        payoff,
+        &rate_curve,
+        barrier,
+        config,
     );
-    assert_eq!(
-        correlation_matrix.ncols(),
-        num_underlyings,
-        "Correlation matrix must have {} columns",
-        num_underlyings
+    // The sanity checks reprice related products on the same paths and the audit trail
+    // records the seed to reproduce the price, so fix the seed
+    let original_seed = config.seed;
+    let seed = original_seed.unwrap_or_else(draw_seed);
+    let config = config.clone().with_seed(seed);
+    let mut result = simulate_payoff(
+        underlyings,
+        correlation_matrix,
+        time_horizon_days,
+        payoff,
+        &rate_curve,
+        barrier,
+        &config,
+    )?;
+
+    attach_diagnostics(
+        &mut result,
+        underlyings,
+        time_horizon_days,
+        payoff,
+        &rate_curve,
+        barrier,
+        &config,
     );
-    
-    let time_to_expiration = time_horizon_days as f64 / 365.0; // Convert days to years
-    
-    // Compute Cholesky decomposition of correlation matrix for correlated random variables
-    let cholesky = correlation_matrix
-        .clone()
-        .cholesky()
-        .expect("Correlation matrix must be positive semi-definite");
-    
-    // Pre-compute drift and diffusion parameters for each underlying
-    let drifts: Vec<f64> = underlyings
-        .iter()
-        .map(|u| risk_free_rate - 0.5 * u.volatility * u.volatility)
-        .collect();
-    let diffusions: Vec<f64> = underlyings.iter().map(|u| u.volatility).collect();
-    
-    let mut rng = rand::thread_rng();
-    let normal = Normal::new(0.0, 1.0).expect("Failed to create normal distribution");
-    
-    let mut payoffs = Vec::with_capacity(num_paths);
-    
-    // Determine number of time steps for simulation
-    // For barrier options, we need multiple steps to check barrier hits
-    // For vanilla options, we can use a single step
-    let num_steps = if barrier.is_some() {
-        time_horizon_days as usize // Daily steps for barrier checking
+    if original_seed.is_none() {
+        result.record_setting(AuditEntry::new("seed", seed.to_string(), true));
+    }
+
+    if config.validate {
+        let checks = validation::run_checks(
+            underlyings,
+            correlation_matrix,
+            time_horizon_days,
+            payoff,
+            &rate_curve,
+            barrier,
+            &config,
+            &result,
+        )?;
+        result.warnings.extend(checks);
+    }
+    Ok(result)
+}
+
+/// Attaches the no-arbitrage bounds, the standard error check, the barrier proximity
+/// warning and the audit trail of the settings to the result of `price_payoff`
+pub(crate) fn attach_diagnostics(
+    result: &mut PricingResult,
+    underlyings: &[Underlying],
+    time_horizon_days: u32,
+    payoff: &Payoff,
+    rate_curve: &RateCurve,
+    barrier: Option<&Barrier>,
+    config: &SimulationConfig,
+) {
+    // Without lognormal marginals, barrier options are only bounded by the model-free bounds
+    // of the vanilla option
+    let is_lognormal = config.model.has_black_scholes_marginals();
+    let mut price_bounds = bounds::no_arbitrage_bounds(
+        &with_effective_volatility(&underlyings[0], time_horizon_days, config),
+        payoff,
+        rate_curve,
+        config.day_count.year_fraction(time_horizon_days),
+        barrier.is_some() && is_lognormal,
+        config.day_count,
+    );
+    if barrier.is_some() && !is_lognormal {
+        price_bounds.lower = 0.0;
+    }
+    if let Some(rebate) = barrier.and_then(|barrier| barrier.rebate) {
+        // The rebate is paid on the paths without the option payoff
+        price_bounds.upper += bounds::rebate_upper_bound(
+            &rebate,
+            rate_curve,
+            config.day_count.year_fraction(time_horizon_days),
+        );
+    }
+    result.apply_bounds(price_bounds);
+
+    result.check_std_error();
+    if let Some(barrier) = barrier {
+        result
+            .warnings
+            .extend(validation::barrier_warning(underlyings, barrier, config));
+    }
+    result.audit = audit::simulation_audit(time_horizon_days, payoff, barrier, config);
+}
+
+/// Runs the Monte Carlo simulation for `price_payoff`
+pub(crate) fn simulate_payoff(
+    underlyings: &[Underlying],
+    correlation_matrix: &DMatrix<f64>,
+    time_horizon_days: u32,
+    payoff: &Payoff,
+    rate_curve: &RateCurve,
+    barrier: Option<&Barrier>,
+    config: &SimulationConfig,
+) -> Result<PricingResult, McError> {
+    if config.sampling.is_quasi_random() && config.qmc_replications > 1 {
+        return simulate_replications(
+            underlyings,
+            correlation_matrix,
+            time_horizon_days,
+            payoff,
+            rate_curve,
+            barrier,
+            config,
+        );
+    }
+    let first = underlyings.first().ok_or(McError::NoUnderlyings)?;
+    let control_strike = payoff.control_strike(first.spot_price);
+    let control_expectation =
+        control_expectation(underlyings, time_horizon_days, payoff, rate_curve, config);
+    let simulation = simulate_paths(
+        underlyings,
+        correlation_matrix,
+        time_horizon_days,
+        payoff,
+        rate_curve,
+        barrier,
+        (control_strike, payoff.option_type()),
+        config,
+        &|statistics| {
+            let estimate = PricingResult::from_statistics(statistics, 0, control_expectation);
+            (estimate.price, estimate.std_error)
+        },
+    )?;
+
+    let mut result = PricingResult::from_statistics(
+        &simulation.statistics,
+        simulation.num_paths,
+        control_expectation,
+    );
+    result.record_non_finite_paths(simulation.non_finite_paths);
+    result.record_correlation_repair(simulation.correlation_adjustment);
+    result.record_dimension_budget(simulation.dimension_budget);
+    result.check_error_tolerance(config.error_tolerance);
+    Ok(result)
+}
+
+/// Runs the simulation for `price_payoff` as independently randomized quasi-random runs
+///
+/// Each run simulates an equal share of the paths with its own seed. The runs are independent
+/// estimates of the price, so their average is the price and their spread gives the standard
+/// error. With an error tolerance, runs are added until at least two of them meet it.
+fn simulate_replications(
+    underlyings: &[Underlying],
+    correlation_matrix: &DMatrix<f64>,
+    time_horizon_days: u32,
+    payoff: &Payoff,
+    rate_curve: &RateCurve,
+    barrier: Option<&Barrier>,
+    config: &SimulationConfig,
+) -> Result<PricingResult, McError> {
+    // The runs only validate their share of the paths
+    validation::validate_inputs(underlyings, correlation_matrix, config)?;
+    let seed = config.seed.unwrap_or_else(draw_seed);
+    let mut runs = Vec::new();
+    let mut result = None;
+    for replication in 0..config.qmc_replications {
+        let num_paths = config.num_paths.div_ceil(config.qmc_replications);
+        // Each run weights its paths with its share of the path weights
+        let path_weights = config.path_weights.as_ref().map(|path_weights| {
+            let start = (replication * num_paths).min(path_weights.len() as u64) as usize;
+            path_weights[start..].into()
+        });
+        let run_config = SimulationConfig {
+            num_paths,
+            seed: Some(seed.wrapping_add(replication)),
+            path_weights,
+            qmc_replications: 1,
+            error_tolerance: None,
+            ..config.clone()
+        };
+        runs.push(simulate_payoff(
+            underlyings,
+            correlation_matrix,
+            time_horizon_days,
+            payoff,
+            rate_curve,
+            barrier,
+            &run_config,
+        )?);
+        let combined = combine_runs(&runs);
+        let is_met = config
+            .error_tolerance
+            .is_some_and(|tolerance| tolerance.is_met(combined.price, combined.std_error));
+        result = Some(combined);
+        if runs.len() >= 2 && is_met {
+            break;
+        }
+    }
+    let mut result = result.expect("at least one replication");
+    result.check_error_tolerance(config.error_tolerance);
+    Ok(result)
+}
+
+/// Combines independent runs into one result: the average of their prices with the standard
+/// error from their spread
+fn combine_runs(runs: &[PricingResult]) -> PricingResult {
+    let count = runs.len() as f64;
+    let mean = runs.iter().map(|run| run.price).sum::<f64>() / count;
+    let variance = if runs.len() < 2 {
+        0.0
     } else {
-        1 // Single step for vanilla options
+        runs.iter().map(|run| (run.price - mean).powi(2)).sum::<f64>() / (count - 1.0)
     };
-    
-    let dt = time_to_expiration / num_steps as f64;
-    let sqrt_dt = dt.sqrt();
-    
-    // Helper function to calculate reference value based on barrier type
-    let calculate_reference = |prices: &[f64], indices: &[usize], barrier_type: BarrierType| -> f64 {
-        match barrier_type {
-            BarrierType::WorstOf => {
-                indices
-                    .iter()
-                    .map(|&idx| prices[idx])
-                    .fold(f64::INFINITY, f64::min)
-            }
-            BarrierType::BestOf => {
-                indices
-                    .iter()
-                    .map(|&idx| prices[idx])
-                    .fold(f64::NEG_INFINITY, f64::max)
-            }
-            BarrierType::Average => {
-                let sum: f64 = indices.iter().map(|&idx| prices[idx]).sum();
-                sum / indices.len() as f64
-            }
-            BarrierType::Median => {
-                let mut values: Vec<f64> = indices.iter().map(|&idx| prices[idx]).collect();
-                values.sort_by(|a, b| a.partial_cmp(b).unwrap());
-                let mid = values.len() / 2;
-                if values.len() % 2 == 0 {
-                    (values[mid - 1] + values[mid]) / 2.0
-                } else {
-                    values[mid]
+    let mut result = PricingResult::new(
+        mean,
+        (variance / count).sqrt(),
+        runs.iter().map(|run| run.num_paths).sum(),
+    );
+    result.record_non_finite_paths(runs.iter().map(|run| run.non_finite_paths).sum());
+    // All runs simulate with the same correlation matrix and dimensions
+    result.warnings.extend(
+        runs[0]
+            .warnings
+            .iter()
+            .filter(|warning| {
+                matches!(
+                    warning,
+                    PricingWarning::CorrelationRepaired { .. }
+                        | PricingWarning::QuasiRandomPadding { .. }
+                )
+            })
+            .cloned(),
+    );
+    result
+}
+
+/// Returns the expectation of the control variate of a payoff if it is configured and known
+///
+/// The control is the discounted vanilla payoff on the first underlying at the control
+/// strike, whose expectation is known analytically from Black-Scholes (unless it pays cash
+/// dividends or the model is not lognormal).
+pub(crate) fn control_expectation(
+    underlyings: &[Underlying],
+    time_horizon_days: u32,
+    payoff: &Payoff,
+    rate_curve: &RateCurve,
+    config: &SimulationConfig,
+) -> Option<f64> {
+    if !config.control_variate || !config.model.has_black_scholes_marginals() {
+        return None;
+    }
+    closed_form::black_scholes_price_with_dividends(
+        &with_effective_volatility(&underlyings[0], time_horizon_days, config),
+        payoff.control_strike(underlyings[0].spot_price),
+        rate_curve,
+        config.day_count.year_fraction(time_horizon_days),
+        payoff.option_type(),
+        config.day_count,
+    )
+}
+
+/// Returns the number of time steps `simulate_paths` simulates a payoff with
+///
+/// Barrier options need multiple steps to check barrier hits, path-dependent payoffs every
+/// fixing day and extreme on the time grid, and models other than Black-Scholes a fine time
+/// grid for their discretization. Vanilla options under Black-Scholes need a single step.
+pub(crate) fn time_steps(
+    time_horizon_days: u32,
+    payoff: &Payoff,
+    barrier: Option<&Barrier>,
+    config: &SimulationConfig,
+) -> usize {
+    if barrier.is_some()
+        || payoff.is_path_dependent()
+        || !config.model.has_black_scholes_marginals()
+    {
+        time_horizon_days as usize
+    } else {
+        1
+    }
+}
+
+/// Discounted payoff statistics of a Monte Carlo run
+pub(crate) struct PathSimulation {
+    /// Statistics of the samples, with the vanilla control payoff as control
+    pub statistics: SampleStatistics,
+    /// Number of simulated paths
+    pub num_paths: u64,
+    /// Number of paths with a non-finite payoff or control
+    pub non_finite_paths: u64,
+    /// Adjustment of the correlation matrix if it was repaired
+    pub correlation_adjustment: Option<f64>,
+    /// Split of the shocks of each path between quasi-random and pseudo-random numbers
+    pub dimension_budget: DimensionBudget,
+}
+
+/// Simulates the paths of a payoff, recording its discounted samples together with the
+/// discounted payoff of the vanilla option `(strike, option_type)` on the first underlying on the
+/// same paths as control
+///
+/// With an error tolerance in the configuration, the paths are simulated in batches until
+/// the `(price, std_error)` that `estimate` derives from the samples so far meets it.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn simulate_paths(
+    underlyings: &[Underlying],
+    correlation_matrix: &DMatrix<f64>,
+    time_horizon_days: u32,
+    payoff: &Payoff,
+    rate_curve: &RateCurve,
+    barrier: Option<&Barrier>,
+    (control_strike, control_type): (f64, OptionType),
+    config: &SimulationConfig,
+    estimate: &dyn Fn(&SampleStatistics) -> (f64, f64),
+) -> Result<PathSimulation, McError> {
+    let time_to_expiration = config.day_count.year_fraction(time_horizon_days); // Days to years
+    let discount_factor = rate_curve.discount_factor(time_to_expiration);
+
+    // Days on which the payoff needs the price of the first underlying recorded
+    let fixing_days = payoff
+        .schedule()
+        .map(|schedule| schedule.fixing_days(time_horizon_days))
+        .transpose()?
+        .unwrap_or_default();
+    validation::validate_payoff(payoff)?;
+    if let Some(barrier) = barrier {
+        validation::validate_barrier(barrier, underlyings.len())?;
+    }
+
+    let num_steps = time_steps(time_horizon_days, payoff, barrier, config);
+
+    let engine = PathEngine::new(
+        underlyings,
+        correlation_matrix,
+        rate_curve,
+        time_to_expiration,
+        num_steps,
+        config,
+    )?;
+    let mut generator = engine.shock_generator(config);
+
+    // Pre-calculate initial reference for relative barriers (once before the loop)
+    let effective_barrier_level =
+        barrier.map(|barrier| effective_barrier_level(barrier, &engine.initial_prices));
+    let log_barrier_level = effective_barrier_level.map(math::ln);
+    let barrier_correction = barrier.map_or(BarrierCorrection::None, |barrier| {
+        barrier.correction(config.barrier_correction)
+    });
+    // Step variance of the barrier reference for the monitoring corrections
+    let barrier_step_variance = |barrier: &Barrier, step: usize| {
+        barrier
+            .underlying_indices
+            .iter()
+            .map(|&i| engine.step_variance(step, i))
+            .sum::<f64>()
+            / barrier.underlying_indices.len() as f64
+    };
+
+    // Growth factor from the end of each step to expiry of a knock-out rebate paid when the
+    // barrier is hit on that step; rebates paid at expiry do not grow
+    let rebate_growth: Vec<f64> = match barrier.and_then(|barrier| barrier.rebate) {
+        Some(rebate) if rebate.timing == RebateTiming::AtHit => (1..=num_steps)
+            .map(|step| {
+                rate_curve.discount_factor(step as f64 * time_to_expiration / num_steps as f64)
+                    / discount_factor
+            })
+            .collect(),
+        _ => vec![1.0; num_steps],
+    };
+
+    // Steps on which the barrier is observed; with a barrier, every step is a day
+    let is_monitoring_step: Vec<bool> = (1..=num_steps as u32)
+        .map(|day| {
+            barrier.is_none_or(|barrier| barrier.monitoring.is_monitored(day, time_horizon_days))
+        })
+        .collect();
+
+    let shock_signs = engine::shock_signs(config.antithetic);
+    let num_samples = config.num_paths.div_ceil(shock_signs.len() as u64);
+    // The standard error needs at least two samples per batch
+    let batch_samples = config
+        .batch_paths
+        .div_ceil(shock_signs.len() as u64)
+        .max(2);
+    let mut num_simulated_samples = num_samples;
+    if let Some(path_weights) = &config.path_weights {
+        validation::validate_path_weights(path_weights, num_samples * shock_signs.len() as u64)?;
+    }
+
+    // Samples are accumulated on the fly, so the path count is not limited by memory
+    let mut statistics = ChunkedStatistics::default();
+    let mut non_finite_paths = 0;
+
+    // Path states and shocks are allocated once and reused for every sample
+    let mut paths: Vec<PathState> = shock_signs
+        .iter()
+        .map(|_| PathState::new(&engine, fixing_days.len()))
+        .collect();
+    let mut shocks = engine.new_shocks();
+    let observer = config.path_observer.as_deref();
+    let mut is_observed = vec![false; paths.len()];
+
+    // Generate Monte Carlo paths
+    for sample in 0..num_samples {
+        if let Some(tolerance) = config.error_tolerance {
+            if sample > 0 && sample.is_multiple_of(batch_samples) {
+                let (price, std_error) = estimate(&statistics.current());
+                if tolerance.is_met(price, std_error) {
+                    num_simulated_samples = sample;
+                    break;
                 }
             }
         }
-    };
-    
-    // Pre-calculate initial reference for relative barriers (once before the loop)
-    let initial_reference = if let Some(barrier) = barrier {
-        if barrier.relative {
-            let initial_prices: Vec<f64> = underlyings.iter().map(|u| u.spot_price).collect();
-            Some(calculate_reference(&initial_prices, &barrier.underlying_indices, barrier.barrier_type))
-        } else {
-            None
+        let first_path_index = sample * paths.len() as u64;
+        for (index, (path, is_observed)) in paths.iter_mut().zip(&mut is_observed).enumerate() {
+            path.reset(&engine);
+            let path_index = first_path_index + index as u64;
+            *is_observed = observer.is_some_and(|observer| observer.observes(path_index));
+            if let (Some(observer), true) = (observer, *is_observed) {
+                observer.on_step(path_index, 0, &path.prices);
+            }
         }
-    } else {
-        None
-    };
-    
-    // Generate Monte Carlo paths
-    for _ in 0..num_paths {
-        let mut current_prices: Vec<f64> = underlyings.iter().map(|u| u.spot_price).collect();
-        let mut barrier_hit = false;
-        
+        generator.start_path();
+
         // Simulate path step by step
-        for _ in 0..num_steps {
-            // Generate independent standard normal random variables
-            let z_independent = DVector::from_iterator(
-                num_underlyings,
-                (0..num_underlyings).map(|_| normal.sample(&mut rng)),
-            );
-            
-            // Transform to correlated random variables using Cholesky decomposition
-            let z_correlated = cholesky.l() * z_independent;
-            
-            // Update prices for each underlying using geometric Brownian motion
-            for i in 0..num_underlyings {
-                // S_{t+dt} = S_t * exp((r - 0.5*σ²)*dt + σ*√dt*Z)
-                current_prices[i] = current_prices[i]
-                    * (drifts[i] * dt + diffusions[i] * sqrt_dt * z_correlated[i]).exp();
-            }
-            
-            // Check if barrier was hit (only if barrier exists)
-            if let Some(barrier) = barrier {
-                // Calculate the effective barrier level
-                let effective_barrier_level = if barrier.relative {
-                    // For relative barriers, multiply initial reference by barrier_level
-                    initial_reference.unwrap() * barrier.barrier_level
-                } else {
-                    barrier.barrier_level
-                };
-                
-                // Calculate the current comparison value based on barrier type
-                let comparison_value = calculate_reference(
-                    &current_prices,
-                    &barrier.underlying_indices,
-                    barrier.barrier_type,
-                );
-                
-                let hit = if barrier.up_down {
-                    // Up barrier: hit if value goes above barrier level
-                    comparison_value >= effective_barrier_level
-                } else {
-                    // Down barrier: hit if value goes below barrier level
-                    comparison_value <= effective_barrier_level
+        let mut next_fixing = 0;
+        for step in 1..=engine.num_steps {
+            engine.draw_shocks(&mut generator, &mut shocks);
+            let is_fixing_day =
+                next_fixing < fixing_days.len() && fixing_days[next_fixing] as usize == step;
+
+            let paths = shock_signs.iter().zip(paths.iter_mut()).zip(&is_observed);
+            for (index, ((&sign, path), &is_observed)) in paths.enumerate() {
+                let previous_log_reference = match (barrier, barrier_correction) {
+                    (Some(barrier), BarrierCorrection::BrownianBridge) if !path.barrier_hit => {
+                        Some(log_reference(barrier, &path.log_prices))
+                    }
+                    _ => None,
                 };
-                
-                if hit {
-                    barrier_hit = true;
+                path.advance(&engine, step, &shocks, sign);
+                // In log space, prices are only needed for the payoff at expiry
+                if !config.log_space || step == engine.num_steps {
+                    path.update_prices();
                 }
+
+                // Check if barrier was hit (only if barrier exists)
+                if let (Some(barrier), Some(level), Some(log_level), true) = (
+                    barrier,
+                    effective_barrier_level,
+                    log_barrier_level,
+                    is_monitoring_step[step - 1],
+                ) {
+                    let is_hit = match barrier_correction {
+                        BarrierCorrection::ShiftedBarrier => {
+                            // Shift the barrier towards the spot, so the discrete checks
+                            // catch the crossings between the steps
+                            let shift =
+                                BGK_BARRIER_SHIFT * barrier_step_variance(barrier, step).sqrt();
+                            let shifted_level = match barrier.direction {
+                                BarrierDirection::Up => log_level - shift,
+                                BarrierDirection::Down => log_level + shift,
+                            };
+                            is_log_barrier_hit(barrier, shifted_level, &path.log_prices)
+                        }
+                        _ if config.log_space => {
+                            is_log_barrier_hit(barrier, log_level, &path.log_prices)
+                        }
+                        _ => is_barrier_hit(barrier, level, &path.prices),
+                    };
+                    if is_hit && !path.barrier_hit {
+                        // The paths that survived the crossings between steps hit now
+                        path.barrier_hit = true;
+                        path.rebate_weight += path.barrier_survival * rebate_growth[step - 1];
+                    } else if let Some(previous) = previous_log_reference {
+                        let crossing_probability = bridge_crossing_probability(
+                            previous,
+                            log_reference(barrier, &path.log_prices),
+                            log_level,
+                            barrier_step_variance(barrier, step),
+                        );
+                        path.rebate_weight += path.barrier_survival
+                            * crossing_probability
+                            * rebate_growth[step - 1];
+                        path.barrier_survival *= 1.0 - crossing_probability;
+                    }
+                }
+
+                // Record the fixing of the first underlying
+                if is_fixing_day {
+                    path.fixings.push(math::exp(path.log_prices[0]));
+                }
+
+                if let (Some(observer), true) = (observer, is_observed) {
+                    path.update_prices();
+                    observer.on_step(first_path_index + index as u64, step, &path.prices);
+                }
+            }
+
+            if is_fixing_day {
+                next_fixing += 1;
             }
         }
-        
-        // Calculate payoff based on option type
-        // For multi-underlying, use the first underlying's price (can be extended)
-        // Call: max(S_T - K, 0), Put: max(K - S_T, 0)
-        let final_price = current_prices[0]; // Using first underlying for payoff
-        let intrinsic_payoff = if is_call {
-            (final_price - strike_price).max(0.0)
-        } else {
-            (strike_price - final_price).max(0.0)
-        };
-        
-        // Apply barrier logic if barrier exists
-        let payoff = if let Some(barrier) = barrier {
-            if barrier.in_out {
-                // "In" barrier: option only has value if barrier was hit
-                if barrier_hit {
-                    intrinsic_payoff
-                } else {
-                    0.0
-                }
+
+        let mut payoff_sum = 0.0;
+        let mut control_sum = 0.0;
+        let mut weight_sum = 0.0;
+        let mut is_dropped = false;
+        for (index, (path, &is_observed)) in paths.iter().zip(&is_observed).enumerate() {
+            // Calculate payoff on the first underlying (can be extended)
+            let final_price = path.prices[0]; // Using first underlying for payoff
+            let observables = path.observables();
+            let intrinsic_payoff = payoff.evaluate(&observables);
+            // Probability that the barrier was hit, on the grid or in between
+            let hit_probability = if path.barrier_hit {
+                1.0
             } else {
-                // "Out" barrier: option only has value if barrier was NOT hit
-                if barrier_hit {
-                    0.0
-                } else {
-                    intrinsic_payoff
+                1.0 - path.barrier_survival
+            };
+
+            // Apply barrier logic if barrier exists
+            let barrier_payoff = match barrier {
+                Some(barrier) => {
+                    apply_barrier(barrier, intrinsic_payoff, hit_probability, path.rebate_weight)
                 }
+                None => intrinsic_payoff,
+            };
+            // A non-finite price on the path stands in for the payoff, which would clip it
+            let non_finite_price = path
+                .prices
+                .iter()
+                .copied()
+                .find(|price| !price.is_finite())
+                .or_else(|| observables.non_finite_price());
+            let barrier_payoff = non_finite_price.unwrap_or(barrier_payoff);
+            if let (Some(observer), true) = (observer, is_observed) {
+                observer.on_path_end(&ObservedPath {
+                    path_index: first_path_index + index as u64,
+                    final_prices: &path.prices,
+                    barrier_hit_probability: hit_probability,
+                    payoff: barrier_payoff,
+                });
             }
-        } else {
-            // Vanilla option: no barrier logic
-            intrinsic_payoff
-        };
-        
-        payoffs.push(payoff);
+
+            let control = non_finite_price
+                .unwrap_or_else(|| intrinsic_value(final_price, control_strike, control_type));
+
+            // Non-finite values from extreme parameters are treated according to the policy
+            if !barrier_payoff.is_finite() || !control.is_finite() {
+                non_finite_paths += 1;
+            }
+            let policy = config.non_finite_policy;
+            match (policy.apply(barrier_payoff)?, policy.apply(control)?) {
+                (Some(barrier_payoff), Some(control)) => {
+                    let weight = path_weight(config, first_path_index + index as u64);
+                    payoff_sum += weight * barrier_payoff;
+                    control_sum += weight * control;
+                    weight_sum += weight;
+                }
+                _ => is_dropped = true,
+            }
+        }
+        if is_dropped {
+            continue;
+        }
+
+        // Discount to present value; a sample weighs the mean weight of its paths
+        statistics.add_weighted(
+            payoff_sum / weight_sum * discount_factor,
+            control_sum / weight_sum * discount_factor,
+            weight_sum / paths.len() as f64,
+        );
+    }
+
+    Ok(PathSimulation {
+        statistics: statistics.finish(),
+        num_paths: num_simulated_samples * shock_signs.len() as u64,
+        non_finite_paths,
+        correlation_adjustment: engine.correlation.adjustment(),
+        dimension_budget: engine.dimension_budget(config.sampling),
+    })
+}
+
+/// Returns the weight of the path with the given index in the order of simulation, 1 without
+/// path weights in the configuration
+pub(crate) fn path_weight(config: &SimulationConfig, path_index: u64) -> f64 {
+    config
+        .path_weights
+        .as_ref()
+        .map_or(1.0, |path_weights| path_weights[path_index as usize])
+}
+
+/// Returns the underlyings with their spot prices replaced by the start values of the
+/// configuration, if any
+///
+/// # Errors
+/// Returns `McError::InvalidStartValues` if the number of start values does not match the
+/// number of underlyings.
+pub(crate) fn with_start_values<'a>(
+    underlyings: &'a [Underlying],
+    config: &SimulationConfig,
+) -> Result<Cow<'a, [Underlying]>, McError> {
+    match &config.start_values {
+        None => Ok(Cow::Borrowed(underlyings)),
+        Some(start_values) if start_values.len() != underlyings.len() => {
+            Err(McError::InvalidStartValues {
+                expected: underlyings.len(),
+                actual: start_values.len(),
+            })
+        }
+        Some(start_values) => Ok(Cow::Owned(
+            underlyings
+                .iter()
+                .zip(start_values)
+                .map(|(underlying, &start_value)| Underlying {
+                    spot_price: start_value,
+                    ..underlying.clone()
+                })
+                .collect(),
+        )),
     }
-    
-    // Calculate average payoff
-    let average_payoff: f64 = payoffs.iter().sum::<f64>() / num_paths as f64;
-    
-    // Discount to present value
-    let option_price = average_payoff * (-risk_free_rate * time_to_expiration).exp();
-    
-    option_price
 }
 
+/// Returns the undiscounted value of a path of a barrier option: its intrinsic payoff if
+/// the barrier activates it, the rebate if it deactivates it
+///
+/// # Arguments
+/// * `barrier` - Barrier of the option
+/// * `intrinsic_payoff` - Payoff of the option without barrier
+/// * `hit_probability` - Probability that the barrier was hit: 0 or 1 on the simulated steps,
+///   in between with the Brownian-bridge correction
+/// * `rebate_weight` - Probability of a hit, weighted by the growth factor of a knock-out
+///   rebate from its payment to expiry
+pub(crate) fn apply_barrier(
+    barrier: &Barrier,
+    intrinsic_payoff: f64,
+    hit_probability: f64,
+    rebate_weight: f64,
+) -> f64 {
+    // "In" barrier: option only has value if barrier was hit
+    // "Out" barrier: option only has value if barrier was NOT hit
+    let alive_probability = match barrier.knock_type {
+        KnockType::In => hit_probability,
+        KnockType::Out => 1.0 - hit_probability,
+    };
+    let option_value = if alive_probability == 0.0 {
+        0.0
+    } else {
+        intrinsic_payoff * alive_probability
+    };
+    // The rebate is paid on the paths where the option was deactivated
+    let rebate_value = match (barrier.rebate, barrier.knock_type) {
+        (Some(rebate), KnockType::In) => rebate.amount * (1.0 - hit_probability),
+        (Some(rebate), KnockType::Out) => rebate.amount * rebate_weight,
+        (None, _) => 0.0,
+    };
+    option_value + rebate_value
+}
+
+/// Shift of the barrier in step standard deviations that corrects discrete monitoring to
+/// continuous monitoring (Broadie, Glasserman and Kou): -ζ(1/2) / √(2π)
+pub(crate) const BGK_BARRIER_SHIFT: f64 = 0.5826;
+
+/// Returns a copy of the underlying with a flat volatility which, applied over the calendar
+/// time to expiration, yields the variance accrued along its volatility term structure on
+/// the configured variance clock. Analytic Black-Scholes prices of the simulated dynamics
+/// use this underlying.
+pub(crate) fn with_effective_volatility(
+    underlying: &Underlying,
+    time_horizon_days: u32,
+    config: &SimulationConfig,
+) -> Underlying {
+    let mut effective = underlying.clone();
+    let time_to_expiration = config.day_count.year_fraction(time_horizon_days);
+    if time_to_expiration > 0.0 {
+        let variance = underlying.integrated_variance(
+            0,
+            time_horizon_days,
+            config.day_count,
+            config.variance_time,
+        );
+        effective.volatility = (variance / time_to_expiration).sqrt();
+        effective.volatility_term_structure.clear();
+    }
+    effective
+}
+
+/// Returns the underlyings with the first one's volatility replaced by its smile volatility
+/// at the strike, for vanilla options without barrier on an underlying with a smile
+pub(crate) fn with_smile_volatility<'a>(
+    underlyings: &'a [Underlying],
+    time_horizon_days: u32,
+    payoff: &Payoff,
+    rate_curve: &RateCurve,
+    barrier: Option<&Barrier>,
+    config: &SimulationConfig,
+) -> Cow<'a, [Underlying]> {
+    let (Some(first), Payoff::Vanilla { strike_price, .. }, None) =
+        (underlyings.first(), payoff, barrier)
+    else {
+        return Cow::Borrowed(underlyings);
+    };
+    let time_to_expiration = config.day_count.year_fraction(time_horizon_days);
+    let forward = first.prepaid_forward(rate_curve, time_to_expiration, config.day_count)
+        / rate_curve.discount_factor(time_to_expiration);
+    match first.smile_volatility(*strike_price, forward) {
+        Some(volatility) if *strike_price > 0.0 => {
+            let mut underlyings = underlyings.to_vec();
+            underlyings[0].volatility = volatility;
+            underlyings[0].volatility_term_structure.clear();
+            Cow::Owned(underlyings)
+        }
+        _ => Cow::Borrowed(underlyings),
+    }
+}
+
+/// Returns the underlyings like `with_smile_volatility`, with the smile volatility shifted by
+/// the volatility shock of a scenario, as the smile replaces the shocked volatility
+pub(crate) fn with_shocked_smile_volatility<'a>(
+    underlyings: &'a [Underlying],
+    time_horizon_days: u32,
+    payoff: &Payoff,
+    rate_curve: &RateCurve,
+    barrier: Option<&Barrier>,
+    volatility_shift: f64,
+    config: &SimulationConfig,
+) -> Cow<'a, [Underlying]> {
+    match with_smile_volatility(
+        underlyings,
+        time_horizon_days,
+        payoff,
+        rate_curve,
+        barrier,
+        config,
+    ) {
+        Cow::Owned(mut underlyings) => {
+            underlyings[0].volatility += volatility_shift;
+            Cow::Owned(underlyings)
+        }
+        underlyings => underlyings,
+    }
+}
+
+/// Intrinsic value of a Call (`max(S - K, 0)`) or Put (`max(K - S, 0)`)
+pub(crate) fn intrinsic_value(price: f64, strike_price: f64, option_type: OptionType) -> f64 {
+    match option_type {
+        OptionType::Call => (price - strike_price).max(0.0),
+        OptionType::Put => (strike_price - price).max(0.0),
+    }
+}
+
+/// Returns the absolute barrier level, converting relative barriers using the initial prices
+pub(crate) fn effective_barrier_level(barrier: &Barrier, initial_prices: &[f64]) -> f64 {
+    if barrier.relative {
+        // For relative barriers, multiply initial reference by barrier_level
+        calculate_reference(initial_prices, &barrier.underlying_indices, barrier.barrier_type)
+            * barrier.barrier_level
+    } else {
+        barrier.barrier_level
+    }
+}
+
+/// Calculates the reference value of the given underlyings based on the barrier type
+pub(crate) fn calculate_reference(prices: &[f64], indices: &[usize], barrier_type: BarrierType) -> f64 {
+    match barrier_type {
+        BarrierType::WorstOf => {
+            indices
+                .iter()
+                .map(|&idx| prices[idx])
+                .fold(f64::INFINITY, f64::min)
+        }
+        BarrierType::BestOf => {
+            indices
+                .iter()
+                .map(|&idx| prices[idx])
+                .fold(f64::NEG_INFINITY, f64::max)
+        }
+        BarrierType::Average => {
+            let sum: f64 = indices.iter().map(|&idx| prices[idx]).sum();
+            sum / indices.len() as f64
+        }
+        BarrierType::Median => {
+            let (lower, upper) = middle_values(prices, indices);
+            (lower + upper) / 2.0
+        }
+    }
+}
+
+/// Returns the two middle values of the given prices in sorted order, the same value twice
+/// for an odd number of prices
+///
+/// The order statistics are found by counting instead of sorting a copy, so the barrier
+/// checks of every step do not allocate.
+fn middle_values(prices: &[f64], indices: &[usize]) -> (f64, f64) {
+    let count = indices.len();
+    let order_statistic = |rank: usize| {
+        indices
+            .iter()
+            .map(|&idx| prices[idx])
+            .find(|&value| {
+                let below = indices.iter().filter(|&&idx| prices[idx] < value).count();
+                let equal = indices.iter().filter(|&&idx| prices[idx] == value).count();
+                below <= rank && rank < below + equal
+            })
+            .unwrap_or(f64::NAN)
+    };
+    let upper = order_statistic(count / 2);
+    if count.is_multiple_of(2) {
+        (order_statistic(count / 2 - 1), upper)
+    } else {
+        (upper, upper)
+    }
+}
+
+/// Checks whether the current prices breach the barrier at the given effective level
+pub(crate) fn is_barrier_hit(barrier: &Barrier, effective_barrier_level: f64, current_prices: &[f64]) -> bool {
+    // Calculate the current comparison value based on barrier type
+    let comparison_value = calculate_reference(
+        current_prices,
+        &barrier.underlying_indices,
+        barrier.barrier_type,
+    );
+
+    match barrier.direction {
+        // Up barrier: hit if value goes above barrier level
+        BarrierDirection::Up => comparison_value >= effective_barrier_level,
+        // Down barrier: hit if value goes below barrier level
+        BarrierDirection::Down => comparison_value <= effective_barrier_level,
+    }
+}
+
+/// Returns the logarithm of the barrier reference value of the given log prices. Worst-of and
+/// best-of references are taken on the log prices directly; averages and medians need the
+/// prices.
+pub(crate) fn log_reference(barrier: &Barrier, log_prices: &[f64]) -> f64 {
+    match barrier.barrier_type {
+        BarrierType::WorstOf | BarrierType::BestOf => {
+            calculate_reference(log_prices, &barrier.underlying_indices, barrier.barrier_type)
+        }
+        BarrierType::Average => {
+            let indices = &barrier.underlying_indices;
+            let sum: f64 = indices.iter().map(|&idx| math::exp(log_prices[idx])).sum();
+            math::ln(sum / indices.len() as f64)
+        }
+        // The exponential preserves the order, so the middle log prices are those of the prices
+        BarrierType::Median => {
+            let (lower, upper) = middle_values(log_prices, &barrier.underlying_indices);
+            math::ln((math::exp(lower) + math::exp(upper)) / 2.0)
+        }
+    }
+}
+
+/// Checks on log levels whether the current log prices breach the barrier at the given
+/// effective log level
+pub(crate) fn is_log_barrier_hit(barrier: &Barrier, log_barrier_level: f64, log_prices: &[f64]) -> bool {
+    let comparison_value = log_reference(barrier, log_prices);
+
+    match barrier.direction {
+        BarrierDirection::Up => comparison_value >= log_barrier_level,
+        BarrierDirection::Down => comparison_value <= log_barrier_level,
+    }
+}
+
+/// Probability that a Brownian bridge with the given variance between two log values on the
+/// same side of the log barrier level crosses the barrier
+pub(crate) fn bridge_crossing_probability(start: f64, end: f64, log_barrier_level: f64, variance: f64) -> f64 {
+    if variance <= 0.0 {
+        return 0.0;
+    }
+    math::exp(-2.0 * (start - log_barrier_level) * (end - log_barrier_level) / variance)
+}
+
+/// Returns a stable 64-bit FNV-1a hash of the debug representation of the value
+///
+/// The derived debug representations list every field, with floats in their shortest
+/// round-trip form, so equal values hash equally across runs and platforms and new fields
+/// are covered without further changes. The hash may change between versions of the crate.
+pub(crate) fn fingerprint(value: &impl std::fmt::Debug) -> u64 {
+    struct Fnv(u64);
+
+    impl std::fmt::Write for Fnv {
+        fn write_str(&mut self, text: &str) -> std::fmt::Result {
+            for &byte in text.as_bytes() {
+                self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+            }
+            Ok(())
+        }
+    }
+
+    let mut hasher = Fnv(0xcbf2_9ce4_8422_2325);
+    std::fmt::write(&mut hasher, format_args!("{:?}", value)).expect("hashing cannot fail");
+    hasher.0
+}
+
+/// Returns a seed for a simulation without a configured seed
+///
+/// With the `entropy` feature, seeds are drawn from the entropy of the operating system.
+/// Without it, e.g. on `wasm32-unknown-unknown`, they follow a fixed sequence of the process:
+/// unseeded simulations differ from each other, but are the same in every run.
+pub(crate) fn draw_seed() -> u64 {
+    #[cfg(feature = "entropy")]
+    {
+        rand::random()
+    }
+    #[cfg(not(feature = "entropy"))]
+    {
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        // SplitMix64 on a shared counter
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let mut z = COUNTER
+            .fetch_add(0x9e37_79b9_7f4a_7c15, Ordering::Relaxed)
+            .wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
@@ -0,0 +1,239 @@
+use crate::error::McError;
+use crate::math;
+use crate::model::{Model, StepInputs};
+use crate::smile::VolatilitySmile;
+use crate::underlying::Underlying;
+use crate::vol_surface::ImpliedVolSurface;
+
+/// Local volatility surface σ(S, t) on a strike × maturity grid
+///
+/// Volatilities are interpolated bilinearly between the grid points and extrapolated flat
+/// beyond the first and last strike and maturity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalVolSurface {
+    /// Strike axis of the grid (spot levels), strictly increasing
+    strikes: Vec<f64>,
+    /// Maturity axis of the grid in years, strictly increasing
+    maturities: Vec<f64>,
+    /// Local volatilities: volatilities[maturity][strike]
+    volatilities: Vec<Vec<f64>>,
+}
+
+impl LocalVolSurface {
+    /// Creates a surface from its strikes, maturities in years and volatilities
+    /// (one row of volatilities per maturity, one column per strike)
+    ///
+    /// # Panics
+    /// Panics if a grid axis is empty or not strictly increasing, or if the volatilities do
+    /// not match the grid.
+    pub fn new(strikes: Vec<f64>, maturities: Vec<f64>, volatilities: Vec<Vec<f64>>) -> Self {
+        for (axis, name) in [(&strikes, "strikes"), (&maturities, "maturities")] {
+            assert!(
+                !axis.is_empty() && axis.windows(2).all(|pair| pair[0] < pair[1]),
+                "Local volatility {} must be non-empty and strictly increasing",
+                name
+            );
+        }
+        assert!(
+            volatilities.len() == maturities.len()
+                && volatilities.iter().all(|row| row.len() == strikes.len()),
+            "Local volatility grid must have {} rows of {} volatilities",
+            maturities.len(),
+            strikes.len()
+        );
+        Self {
+            strikes,
+            maturities,
+            volatilities,
+        }
+    }
+
+    /// Creates a surface with the same volatility everywhere
+    pub fn flat(volatility: f64) -> Self {
+        Self::new(vec![1.0], vec![1.0], vec![vec![volatility]])
+    }
+
+    /// Creates a surface approximating the local volatilities of a single-expiry smile with
+    /// Dupire's formula (see `VolatilitySmile::local_volatility`)
+    ///
+    /// The log-moneyness of each spot level is taken against the same forward at all
+    /// maturities, so the drift of the forward is ignored.
+    ///
+    /// # Arguments
+    /// * `smile` - Implied volatility smile, assumed the same at every expiry
+    /// * `forward` - Forward the smile is quoted against
+    /// * `strikes` - Strike axis of the grid (spot levels), strictly increasing
+    /// * `maturities` - Maturity axis of the grid in years, strictly increasing
+    ///
+    /// # Panics
+    /// Panics if a grid axis is empty or not strictly increasing.
+    pub fn from_smile(
+        smile: &VolatilitySmile,
+        forward: f64,
+        strikes: Vec<f64>,
+        maturities: Vec<f64>,
+    ) -> Self {
+        let volatilities = maturities
+            .iter()
+            .map(|&time| {
+                strikes
+                    .iter()
+                    .map(|&strike| smile.local_volatility(math::ln(strike / forward), time))
+                    .collect()
+            })
+            .collect();
+        Self::new(strikes, maturities, volatilities)
+    }
+
+    /// Creates a surface of the local volatilities of an implied volatility surface with
+    /// Dupire's formula on its grid (see `ImpliedVolSurface::repaired` to remove the static
+    /// arbitrage of the grid first)
+    ///
+    /// The derivatives of the total variance are taken by finite differences between the
+    /// grid points, so the local volatilities have the grid of the implied volatilities,
+    /// with the strikes at the log-moneyness against the same forward at all expiries.
+    /// Where the grid admits arbitrage (the numerator or denominator of Dupire's formula is
+    /// not positive), the implied volatility is used instead.
+    ///
+    /// # Arguments
+    /// * `surface` - Implied volatility surface
+    /// * `forward` - Forward the log-moneyness of the surface is taken against
+    pub fn from_implied_surface(surface: &ImpliedVolSurface, forward: f64) -> Self {
+        let log_moneyness = surface.log_moneyness();
+        let expiries = surface.expiries();
+        let total_variances: Vec<Vec<f64>> = surface
+            .volatilities()
+            .iter()
+            .zip(expiries)
+            .map(|(row, &expiry)| {
+                row.iter()
+                    .map(|volatility| volatility * volatility * expiry)
+                    .collect()
+            })
+            .collect();
+        let num_strikes = log_moneyness.len();
+        let volatilities = (0..expiries.len())
+            .map(|j| {
+                let row = &total_variances[j];
+                (0..num_strikes)
+                    .map(|i| {
+                        let w = row[i];
+                        // Three-point differences on the nonuniform grid, one-sided at its ends
+                        let (slope, convexity) = if num_strikes < 3 {
+                            (0.0, 0.0)
+                        } else {
+                            let c = i.clamp(1, num_strikes - 2);
+                            let (h1, h2) = (
+                                log_moneyness[c] - log_moneyness[c - 1],
+                                log_moneyness[c + 1] - log_moneyness[c],
+                            );
+                            let (left, middle, right) = (row[c - 1], row[c], row[c + 1]);
+                            let convexity = 2.0 * (h2 * left - (h1 + h2) * middle + h1 * right)
+                                / (h1 * h2 * (h1 + h2));
+                            let central = (right - left) / (h1 + h2);
+                            let midpoint = 0.5 * (log_moneyness[c - 1] + log_moneyness[c + 1]);
+                            let slope = central + convexity * (log_moneyness[i] - midpoint);
+                            (slope, convexity)
+                        };
+                        let time_slope = match j {
+                            0 => w / expiries[0],
+                            _ if j + 1 < expiries.len() => {
+                                (total_variances[j + 1][i] - total_variances[j - 1][i])
+                                    / (expiries[j + 1] - expiries[j - 1])
+                            }
+                            _ => (w - total_variances[j - 1][i]) / (expiries[j] - expiries[j - 1]),
+                        };
+                        let k = log_moneyness[i];
+                        let denominator = 1.0 - k * slope / w
+                            + 0.25 * (-0.25 - 1.0 / w + k * k / (w * w)) * slope * slope
+                            + 0.5 * convexity;
+                        if w > 0.0 && time_slope > 0.0 && denominator > 0.0 {
+                            (time_slope / denominator).sqrt()
+                        } else {
+                            (w / expiries[j]).sqrt()
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        let strikes = log_moneyness.iter().map(|&k| forward * math::exp(k)).collect();
+        Self::new(strikes, expiries.to_vec(), volatilities)
+    }
+
+    /// Returns the local volatility at the given spot level and time in years
+    pub fn volatility(&self, spot: f64, time: f64) -> f64 {
+        let (strike_index, strike_weight) = grid_position(&self.strikes, spot);
+        let (maturity_index, maturity_weight) = grid_position(&self.maturities, time);
+        let along_strikes = |row: &[f64]| {
+            row[strike_index]
+                + strike_weight * (row[(strike_index + 1).min(row.len() - 1)] - row[strike_index])
+        };
+        let lower = along_strikes(&self.volatilities[maturity_index]);
+        let upper = along_strikes(
+            &self.volatilities[(maturity_index + 1).min(self.maturities.len() - 1)],
+        );
+        lower + maturity_weight * (upper - lower)
+    }
+}
+
+/// Returns the grid index at or below the value and the interpolation weight towards the next
+/// grid point, clamped to the ends of the grid
+pub(crate) fn grid_position(grid: &[f64], value: f64) -> (usize, f64) {
+    let last = grid.len() - 1;
+    if value <= grid[0] {
+        return (0, 0.0);
+    }
+    if value >= grid[last] {
+        return (last, 0.0);
+    }
+    let upper = grid.partition_point(|&point| point < value);
+    let lower = upper - 1;
+    (lower, (value - grid[lower]) / (grid[upper] - grid[lower]))
+}
+
+/// Local volatility model: each underlying diffuses with the volatility its surface assigns
+/// to the current spot level and time
+///
+/// The log prices are advanced with the Euler scheme, looking up the volatility at the start
+/// of every step. The volatilities and the variance clock of the underlyings are not used.
+#[derive(Debug, Clone)]
+pub struct LocalVolatility {
+    /// Surface of each underlying, in the order of the underlyings
+    pub surfaces: Vec<LocalVolSurface>,
+}
+
+impl LocalVolatility {
+    /// Creates a local volatility model with the surface of each underlying
+    pub fn new(surfaces: Vec<LocalVolSurface>) -> Self {
+        Self { surfaces }
+    }
+}
+
+impl Model for LocalVolatility {
+    fn validate(&self, underlyings: &[Underlying]) -> Result<(), McError> {
+        if self.surfaces.len() != underlyings.len() {
+            return Err(McError::InvalidModel(format!(
+                "Local volatility model needs a surface for each of the {} underlyings, got {}",
+                underlyings.len(),
+                self.surfaces.len()
+            )));
+        }
+        Ok(())
+    }
+
+    fn evolve_step(
+        &self,
+        inputs: &StepInputs,
+        log_prices: &mut [f64],
+        _state: &mut [f64],
+        shocks: &[f64],
+        sign: f64,
+    ) {
+        for (i, log_price) in log_prices.iter_mut().enumerate() {
+            let volatility = self.surfaces[i].volatility(math::exp(*log_price), inputs.time);
+            let variance = volatility * volatility * inputs.dt;
+            *log_price += inputs.carry_rates[i] * inputs.dt - 0.5 * variance
+                + variance.sqrt() * sign * shocks[i];
+        }
+    }
+}
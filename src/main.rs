@@ -1,57 +1,330 @@
-use mcproton::{price_option, Underlying};
-use nalgebra::DMatrix;
-
-fn main() {
-    // Example usage
-    let underlying = Underlying::new(
-        "AAPL".to_string(),
-        150.0,  // Current spot price
-        0.25,   // 25% annual volatility
-    );
-    
-    // Create correlation matrix (1x1 identity matrix for single underlying)
-    let correlation_matrix = DMatrix::from_row_slice(1, 1, &[1.0]);
-    
-    let time_horizon_days = 30;
-    let strike_price = 155.0;
-    let risk_free_rate = 0.05; // 5% annual risk-free rate
-    let num_paths = 1000;
-    
-    // Price a Call option (vanilla, no barrier)
-    let call_price = price_option(
-        &[underlying.clone()],
-        &correlation_matrix,
-        time_horizon_days,
-        strike_price,
-        true,  // is_call = true
-        risk_free_rate,
-        num_paths,
-        None,  // No barrier
-    );
-    
-    // Price a Put option (vanilla, no barrier)
-    let put_price = price_option(
-        &[underlying.clone()],
-        &correlation_matrix,
-        time_horizon_days,
-        strike_price,
-        false,  // is_call = false
-        risk_free_rate,
-        num_paths,
-        None,  // No barrier
+use std::collections::btree_map::{BTreeMap, Entry};
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use mcproton::{MarketSnapshot, PricingSession, Product, SimulationConfig};
+use serde::{Deserialize, Serialize};
+
+/// Monte Carlo pricer of option trades
+#[derive(Parser)]
+#[command(name = "mcproton", version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Prices the trades of a JSON trade file on the market it defines
+    Price(PriceArgs),
+}
+
+#[derive(Args)]
+struct PriceArgs {
+    /// JSON file with the market and the trades
+    trade_file: PathBuf,
+    /// Number of Monte Carlo paths, e.g. 100000 or 1e6
+    #[arg(long, default_value = "100000", value_parser = parse_count)]
+    paths: u64,
+    /// Seed of the random numbers, for reproducible prices
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Simulates every path together with its antithetic counterpart
+    #[arg(long)]
+    antithetic: bool,
+    /// Also computes delta, gamma, vega and rho of every trade
+    #[arg(long)]
+    greeks: bool,
+    /// Format of the results
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+    output: OutputFormat,
+    /// Writes the results to this file instead of the standard output
+    #[arg(long)]
+    out: Option<PathBuf>,
+}
+
+/// Format of the priced trades
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// Aligned columns for reading
+    Table,
+    /// Array of objects, one per trade
+    Json,
+    /// Header and one row per trade
+    Csv,
+}
+
+/// Trade file: the market and the trades priced on it
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TradeFile {
+    /// Underlyings, correlation matrix and rate curve
+    market: MarketSnapshot,
+    trades: Vec<Trade>,
+}
+
+/// Named position in a product with its maturity
+#[derive(Deserialize)]
+struct Trade {
+    name: String,
+    /// Time to expiration in days
+    maturity_days: u32,
+    /// Number of units held, negative for short positions
+    #[serde(default = "unit_quantity")]
+    quantity: f64,
+    /// Payoff and optional barrier
+    #[serde(flatten)]
+    product: Product,
+}
+
+fn unit_quantity() -> f64 {
+    1.0
+}
+
+/// Result of a trade as exported
+#[derive(Serialize)]
+struct PricedTrade {
+    name: String,
+    /// Price, standard error and path count; none if the trade was rejected
+    price: Option<f64>,
+    std_error: Option<f64>,
+    num_paths: Option<u64>,
+    /// Greeks as derivatives per unit of spot, volatility and rate, if requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    greeks: Option<TradeGreeks>,
+    warnings: Vec<String>,
+    /// Reason the trade was rejected
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct TradeGreeks {
+    delta: f64,
+    gamma: f64,
+    vega: f64,
+    rho: f64,
+}
+
+fn main() -> ExitCode {
+    let Command::Price(args) = Cli::parse().command;
+    match price(&args) {
+        Ok(priced) => {
+            // Rejected trades fail the run once all other trades are priced and written
+            let rejected: Vec<_> = priced
+                .iter()
+                .filter(|trade| trade.error.is_some())
+                .collect();
+            for trade in &rejected {
+                eprintln!(
+                    "error: trade {}: {}",
+                    trade.name,
+                    trade.error.as_deref().unwrap_or_default()
+                );
+            }
+            if rejected.is_empty() {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+        Err(error) => {
+            eprintln!("error: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Prices the trades of the trade file and writes the results
+///
+/// The trades of each maturity are priced in one pricing session, so they share their paths
+/// and their prices are computed on common random numbers. The prices and Greeks are those
+/// of the positions, scaled by their quantities. A trade that cannot be priced is written
+/// with the reason it was rejected, and the other trades are priced nonetheless.
+fn price(args: &PriceArgs) -> Result<Vec<PricedTrade>, Box<dyn Error>> {
+    let contents = fs::read_to_string(&args.trade_file)
+        .map_err(|error| format!("cannot read {}: {}", args.trade_file.display(), error))?;
+    let trade_file: TradeFile = serde_json::from_str(&contents).map_err(|error| {
+        format!(
+            "invalid trade file {}: {}",
+            args.trade_file.display(),
+            error
+        )
+    })?;
+
+    let mut config = SimulationConfig::new(args.paths).with_antithetic(args.antithetic);
+    if let Some(seed) = args.seed {
+        config = config.with_seed(seed);
+    }
+    let mut sessions = BTreeMap::new();
+    let mut priced = Vec::with_capacity(trade_file.trades.len());
+    for trade in &trade_file.trades {
+        let session = match sessions.entry(trade.maturity_days) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(PricingSession::new(
+                trade_file.market.clone(),
+                trade.maturity_days,
+                &config,
+            )?),
+        };
+        let pricing = if args.greeks {
+            session.greeks(&trade.product).map(|greeks| {
+                let greeks = greeks.scaled(trade.quantity);
+                let trade_greeks = TradeGreeks {
+                    delta: greeks.delta,
+                    gamma: greeks.gamma,
+                    vega: greeks.vega,
+                    rho: greeks.rho,
+                };
+                (greeks.pricing, Some(trade_greeks))
+            })
+        } else {
+            session
+                .price(&trade.product)
+                .map(|pricing| (pricing.scaled(trade.quantity), None))
+        };
+        priced.push(match pricing {
+            Ok((pricing, greeks)) => PricedTrade {
+                name: trade.name.clone(),
+                price: Some(pricing.price),
+                std_error: Some(pricing.std_error),
+                num_paths: Some(pricing.num_paths),
+                greeks,
+                warnings: pricing.warnings.iter().map(ToString::to_string).collect(),
+                error: None,
+            },
+            Err(error) => PricedTrade {
+                name: trade.name.clone(),
+                price: None,
+                std_error: None,
+                num_paths: None,
+                greeks: None,
+                warnings: Vec::new(),
+                error: Some(error.to_string()),
+            },
+        });
+    }
+
+    let output = match args.output {
+        OutputFormat::Table => table(&priced),
+        OutputFormat::Json => serde_json::to_string_pretty(&priced)? + "\n",
+        OutputFormat::Csv => csv(&priced, args.greeks),
+    };
+    match &args.out {
+        Some(path) => fs::write(path, output)
+            .map_err(|error| format!("cannot write {}: {}", path.display(), error))?,
+        None => print!("{}", output),
+    }
+    Ok(priced)
+}
+
+/// Parses a count written as an integer or in scientific notation, e.g. `1e6`
+fn parse_count(value: &str) -> Result<u64, String> {
+    if let Ok(count) = value.replace('_', "").parse::<u64>() {
+        return Ok(count);
+    }
+    match value.parse::<f64>() {
+        Ok(count) if count >= 0.0 && count.fract() == 0.0 && count <= u64::MAX as f64 => {
+            Ok(count as u64)
+        }
+        _ => Err(format!("{} is not a whole number of paths", value)),
+    }
+}
+
+/// Formats the priced trades as aligned columns, followed by their warnings
+fn table(priced: &[PricedTrade]) -> String {
+    let name_width = priced
+        .iter()
+        .map(|trade| trade.name.len())
+        .fold("Trade".len(), usize::max);
+    let has_greeks = priced.iter().any(|trade| trade.greeks.is_some());
+    let mut output = format!(
+        "{:<name_width$} {:>12} {:>10}",
+        "Trade", "Price", "Std error"
     );
-    
-    println!("Monte Carlo Option Pricing");
-    println!("==========================");
-    println!("Underlying: {}", underlying.name);
-    println!("Spot Price: ${:.2}", underlying.spot_price);
-    println!("Volatility: {:.2}%", underlying.volatility * 100.0);
-    println!("Time Horizon: {} days", time_horizon_days);
-    println!("Strike Price: ${:.2}", strike_price);
-    println!("Risk-Free Rate: {:.2}%", risk_free_rate * 100.0);
-    println!("Number of Paths: {}", num_paths);
-    println!("==========================");
-    println!("Estimated CALL Option Price: ${:.4}", call_price);
-    println!("Estimated PUT Option Price: ${:.4}", put_price);
+    if has_greeks {
+        output += &format!(
+            " {:>10} {:>10} {:>10} {:>10}",
+            "Delta", "Gamma", "Vega", "Rho"
+        );
+    }
+    output += "\n";
+    for trade in priced {
+        let (Some(price), Some(std_error)) = (trade.price, trade.std_error) else {
+            output += &format!("{:<name_width$} {:>12} {:>10}\n", trade.name, "-", "-");
+            continue;
+        };
+        output += &format!(
+            "{:<name_width$} {:>12.6} {:>10.6}",
+            trade.name, price, std_error
+        );
+        if let Some(greeks) = &trade.greeks {
+            output += &format!(
+                " {:>10.6} {:>10.6} {:>10.6} {:>10.6}",
+                greeks.delta, greeks.gamma, greeks.vega, greeks.rho
+            );
+        }
+        output += "\n";
+    }
+    for trade in priced {
+        for warning in &trade.warnings {
+            output += &format!("warning: {}: {}\n", trade.name, warning);
+        }
+        if let Some(error) = &trade.error {
+            output += &format!("rejected: {}: {}\n", trade.name, error);
+        }
+    }
+    output
+}
+
+/// Formats the priced trades as comma-separated values with a header, the warnings of a
+/// trade separated by semicolons; the values of rejected trades are empty
+fn csv(priced: &[PricedTrade], with_greeks: bool) -> String {
+    let mut output = String::from("name,price,std_error,num_paths");
+    if with_greeks {
+        output += ",delta,gamma,vega,rho";
+    }
+    output += ",warnings,error\n";
+    for trade in priced {
+        output += &format!(
+            "{},{},{},{}",
+            csv_field(&trade.name),
+            optional(trade.price),
+            optional(trade.std_error),
+            optional(trade.num_paths)
+        );
+        match &trade.greeks {
+            Some(greeks) => {
+                output += &format!(
+                    ",{},{},{},{}",
+                    greeks.delta, greeks.gamma, greeks.vega, greeks.rho
+                )
+            }
+            None if with_greeks => output += ",,,,",
+            None => {}
+        }
+        output += &format!(
+            ",{},{}\n",
+            csv_field(&trade.warnings.join("; ")),
+            csv_field(trade.error.as_deref().unwrap_or_default())
+        );
+    }
+    output
+}
+
+/// Formats an optional value, empty if there is none
+fn optional(value: Option<impl ToString>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
 }
 
+/// Quotes a CSV field if it contains separators, quotes or line breaks
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,38 @@
+use nalgebra::DMatrix;
+
+use crate::rates::RateCurve;
+use crate::underlying::Underlying;
+
+/// Snapshot of the market data required for pricing
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MarketSnapshot {
+    /// List of underlying assets
+    pub underlyings: Vec<Underlying>,
+    /// Correlation matrix (n x n) where n is the number of underlyings, serialized as its rows
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialization::matrix_rows"))]
+    pub correlation_matrix: DMatrix<f64>,
+    /// Risk-free rate curve
+    pub risk_free_rate: RateCurve,
+}
+
+impl MarketSnapshot {
+    /// Creates a new market snapshot from a rate curve or a flat annual rate
+    pub fn new(
+        underlyings: Vec<Underlying>,
+        correlation_matrix: DMatrix<f64>,
+        risk_free_rate: impl Into<RateCurve>,
+    ) -> Self {
+        Self {
+            underlyings,
+            correlation_matrix,
+            risk_free_rate: risk_free_rate.into(),
+        }
+    }
+
+    /// Returns a stable hash of the market data: equal snapshots have equal fingerprints in
+    /// every run. The fingerprints may change between versions of the crate.
+    pub fn fingerprint(&self) -> u64 {
+        crate::fingerprint(self)
+    }
+}
@@ -0,0 +1,372 @@
+use std::fs;
+use std::path::Path;
+
+use nalgebra::DMatrix;
+
+use crate::error::McError;
+use crate::math;
+use crate::underlying::Underlying;
+
+/// Closing prices of several assets on common dates, the input of the estimation of
+/// volatilities and correlations
+///
+/// Missing prices are `NaN`. The returns into and out of a missing price are skipped, so
+/// each asset is estimated from its available returns and each pair of assets from the
+/// returns available for both.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PriceHistory {
+    /// Names of the assets
+    pub names: Vec<String>,
+    /// Dates of the prices, oldest first, as given in the source
+    pub dates: Vec<String>,
+    /// Prices of each asset in the order of `names`, one per date
+    pub prices: Vec<Vec<f64>>,
+}
+
+/// Settings of the estimation of volatilities and correlations from a price history
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EstimationSettings {
+    /// Number of most recent returns to estimate from, or `None` for all of them
+    pub lookback: Option<usize>,
+    /// Decay factor of exponentially weighted returns in (0, 1]: a return one period older
+    /// weighs `ewma_decay` times as much (e.g. 0.94 as in RiskMetrics). `None` weighs all
+    /// returns equally.
+    pub ewma_decay: Option<f64>,
+    /// Number of returns per year the estimates are annualized with (252 by default for
+    /// daily closes)
+    pub periods_per_year: f64,
+}
+
+impl Default for EstimationSettings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EstimationSettings {
+    /// Creates settings for equally weighted daily returns over the whole history
+    pub fn new() -> Self {
+        Self {
+            lookback: None,
+            ewma_decay: None,
+            periods_per_year: 252.0,
+        }
+    }
+
+    /// Estimates from the given number of most recent returns only
+    pub fn with_lookback(mut self, lookback: usize) -> Self {
+        self.lookback = Some(lookback);
+        self
+    }
+
+    /// Weighs the returns exponentially with the given decay factor
+    pub fn with_ewma_decay(mut self, ewma_decay: f64) -> Self {
+        self.ewma_decay = Some(ewma_decay);
+        self
+    }
+
+    /// Sets the number of returns per year, e.g. 52 for weekly closes
+    pub fn with_periods_per_year(mut self, periods_per_year: f64) -> Self {
+        self.periods_per_year = periods_per_year;
+        self
+    }
+
+    /// Returns an error if the lookback is below two returns, the decay factor lies outside
+    /// (0, 1] or the number of periods per year is not positive and finite
+    fn validate(&self) -> Result<(), McError> {
+        if self.lookback.is_some_and(|lookback| lookback < 2) {
+            return Err(McError::InvalidPriceHistory(
+                "lookback must cover at least two returns".to_string(),
+            ));
+        }
+        if self
+            .ewma_decay
+            .is_some_and(|decay| !(decay > 0.0 && decay <= 1.0))
+        {
+            return Err(McError::InvalidPriceHistory(format!(
+                "EWMA decay factor must lie in (0, 1], got {:?}",
+                self.ewma_decay
+            )));
+        }
+        if !(self.periods_per_year.is_finite() && self.periods_per_year > 0.0) {
+            return Err(McError::InvalidPriceHistory(format!(
+                "periods per year must be positive and finite, got {}",
+                self.periods_per_year
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl PriceHistory {
+    /// Reads a price history from a CSV file (see `parse_csv`)
+    ///
+    /// # Errors
+    /// Returns `McError::Io` if the file cannot be read and `McError::InvalidPriceHistory` if
+    /// it is not a valid price history.
+    pub fn from_csv(path: impl AsRef<Path>) -> Result<Self, McError> {
+        Self::parse_csv(&fs::read_to_string(path)?)
+    }
+
+    /// Parses a price history from CSV text
+    ///
+    /// The header row holds a date column followed by the names of the assets, and each
+    /// further row a date followed by the closing prices of the assets, oldest date first.
+    /// Empty cells are missing prices; blank lines are skipped. Fields may be enclosed in
+    /// double quotes but must not contain commas.
+    ///
+    /// # Errors
+    /// Returns `McError::InvalidPriceHistory` if the header has no assets, a row has another
+    /// number of fields than the header, or a price is not a positive finite number.
+    pub fn parse_csv(text: &str) -> Result<Self, McError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty());
+        let split = |line: &str| -> Vec<String> {
+            line.split(',')
+                .map(|field| field.trim().trim_matches('"').to_string())
+                .collect()
+        };
+        let names = match lines.next() {
+            Some((_, header)) => split(header).split_off(1),
+            None => Vec::new(),
+        };
+        if names.is_empty() {
+            return Err(McError::InvalidPriceHistory(
+                "header must name a date column and at least one asset".to_string(),
+            ));
+        }
+
+        let mut dates = Vec::new();
+        let mut prices = vec![Vec::new(); names.len()];
+        for (index, line) in lines {
+            let mut fields = split(line).into_iter();
+            dates.push(fields.next().unwrap_or_default());
+            let row: Vec<String> = fields.collect();
+            if row.len() != names.len() {
+                return Err(McError::InvalidPriceHistory(format!(
+                    "line {} has {} prices, expected {}",
+                    index + 1,
+                    row.len(),
+                    names.len()
+                )));
+            }
+            for (series, field) in prices.iter_mut().zip(row) {
+                let price = if field.is_empty() {
+                    f64::NAN
+                } else {
+                    field.parse().map_err(|_| {
+                        McError::InvalidPriceHistory(format!(
+                            "line {} has the invalid price {:?}",
+                            index + 1,
+                            field
+                        ))
+                    })?
+                };
+                series.push(price);
+            }
+        }
+        let history = Self {
+            names,
+            dates,
+            prices,
+        };
+        history.validate()?;
+        Ok(history)
+    }
+
+    /// Returns an error if the series do not match the names and dates or a price is not
+    /// positive and finite
+    fn validate(&self) -> Result<(), McError> {
+        if self.prices.len() != self.names.len() {
+            return Err(McError::InvalidPriceHistory(format!(
+                "{} price series given for {} assets",
+                self.prices.len(),
+                self.names.len()
+            )));
+        }
+        for (name, series) in self.names.iter().zip(&self.prices) {
+            if series.len() != self.dates.len() {
+                return Err(McError::InvalidPriceHistory(format!(
+                    "{} has {} prices for {} dates",
+                    name,
+                    series.len(),
+                    self.dates.len()
+                )));
+            }
+            if let Some(price) = series
+                .iter()
+                .find(|&&price| !(price.is_nan() || price.is_finite() && price > 0.0))
+            {
+                return Err(McError::InvalidPriceHistory(format!(
+                    "{} has the non-positive or infinite price {}",
+                    name, price
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the log returns of each asset within the lookback, oldest first, with `NaN`
+    /// for returns into or out of a missing price
+    fn log_returns(&self, settings: &EstimationSettings) -> Vec<Vec<f64>> {
+        self.prices
+            .iter()
+            .map(|series| {
+                let returns: Vec<f64> = series
+                    .windows(2)
+                    .map(|pair| math::ln(pair[1] / pair[0]))
+                    .collect();
+                let skipped = settings
+                    .lookback
+                    .map_or(0, |lookback| returns.len().saturating_sub(lookback));
+                returns[skipped..].to_vec()
+            })
+            .collect()
+    }
+
+    /// Returns the annualized weighted covariance of two return series over the returns
+    /// available in both, with the variances of both series over the same returns
+    fn covariance(
+        first: &[f64],
+        second: &[f64],
+        settings: &EstimationSettings,
+    ) -> Option<(f64, f64, f64)> {
+        let decay = settings.ewma_decay.unwrap_or(1.0);
+        let len = first.len();
+        let samples: Vec<(f64, f64, f64)> = first
+            .iter()
+            .zip(second)
+            .enumerate()
+            .filter(|(_, (x, y))| !x.is_nan() && !y.is_nan())
+            .map(|(index, (&x, &y))| (math::powi(decay, (len - 1 - index) as i32), x, y))
+            .collect();
+        if samples.len() < 2 {
+            return None;
+        }
+
+        let weight_sum: f64 = samples.iter().map(|(weight, _, _)| weight).sum();
+        let squared_weight_sum: f64 = samples.iter().map(|(weight, _, _)| weight * weight).sum();
+        let mean_x = samples.iter().map(|(w, x, _)| w * x).sum::<f64>() / weight_sum;
+        let mean_y = samples.iter().map(|(w, _, y)| w * y).sum::<f64>() / weight_sum;
+        // Reliability weights: equal weights give the unbiased sample estimates
+        let normalizer = (weight_sum - squared_weight_sum / weight_sum) / settings.periods_per_year;
+        let moment = |f: &dyn Fn(f64, f64) -> f64| {
+            samples
+                .iter()
+                .map(|&(w, x, y)| w * f(x - mean_x, y - mean_y))
+                .sum::<f64>()
+                / normalizer
+        };
+        Some((
+            moment(&|dx, dy| dx * dy),
+            moment(&|dx, _| dx * dx),
+            moment(&|_, dy| dy * dy),
+        ))
+    }
+
+    /// Estimates the annualized realized volatility of each asset from its log returns
+    ///
+    /// # Errors
+    /// Returns `McError::InvalidPriceHistory` for invalid settings or history, or if an asset
+    /// has fewer than two returns within the lookback.
+    pub fn realized_volatilities(
+        &self,
+        settings: &EstimationSettings,
+    ) -> Result<Vec<f64>, McError> {
+        settings.validate()?;
+        self.validate()?;
+        self.log_returns(settings)
+            .iter()
+            .zip(&self.names)
+            .map(|(returns, name)| {
+                Self::covariance(returns, returns, settings)
+                    .map(|(variance, _, _)| variance.sqrt())
+                    .ok_or_else(|| {
+                        McError::InvalidPriceHistory(format!(
+                            "{} has fewer than two returns within the lookback",
+                            name
+                        ))
+                    })
+            })
+            .collect()
+    }
+
+    /// Estimates the correlation matrix of the log returns of the assets
+    ///
+    /// Each correlation is estimated from the returns available for both assets, so with
+    /// missing prices the matrix may not be positive semi-definite; set
+    /// `SimulationConfig::repair_correlation` to price with it nonetheless. Assets without
+    /// variation within the lookback are uncorrelated with all others.
+    ///
+    /// # Errors
+    /// Returns `McError::InvalidPriceHistory` for invalid settings or history, or if a pair
+    /// of assets has fewer than two common returns within the lookback.
+    pub fn correlation_matrix(
+        &self,
+        settings: &EstimationSettings,
+    ) -> Result<DMatrix<f64>, McError> {
+        settings.validate()?;
+        self.validate()?;
+        let returns = self.log_returns(settings);
+        let n = returns.len();
+        let mut correlation = DMatrix::identity(n, n);
+        for i in 0..n {
+            for j in 0..i {
+                let (covariance, variance_i, variance_j) =
+                    Self::covariance(&returns[i], &returns[j], settings).ok_or_else(|| {
+                        McError::InvalidPriceHistory(format!(
+                            "{} and {} have fewer than two common returns within the lookback",
+                            self.names[i], self.names[j]
+                        ))
+                    })?;
+                let scale = (variance_i * variance_j).sqrt();
+                let value = if scale > 0.0 {
+                    (covariance / scale).clamp(-1.0, 1.0)
+                } else {
+                    0.0
+                };
+                correlation[(i, j)] = value;
+                correlation[(j, i)] = value;
+            }
+        }
+        Ok(correlation)
+    }
+
+    /// Estimates the inputs of the pricer from the history: one underlying per asset, with
+    /// its latest available price as spot and its realized volatility, and the correlation
+    /// matrix of the assets
+    ///
+    /// The underlyings carry no dividends, forwards or term structures; add them before
+    /// pricing where needed.
+    ///
+    /// # Errors
+    /// Returns an error under the same conditions as `realized_volatilities` and
+    /// `correlation_matrix`.
+    pub fn estimate(
+        &self,
+        settings: &EstimationSettings,
+    ) -> Result<(Vec<Underlying>, DMatrix<f64>), McError> {
+        let volatilities = self.realized_volatilities(settings)?;
+        let correlation_matrix = self.correlation_matrix(settings)?;
+        let underlyings = self
+            .names
+            .iter()
+            .zip(&self.prices)
+            .zip(volatilities)
+            .map(|((name, series), volatility)| {
+                let spot_price = series
+                    .iter()
+                    .rev()
+                    .find(|price| !price.is_nan())
+                    .copied()
+                    .unwrap_or(f64::NAN);
+                Underlying::new(name.clone(), spot_price, volatility)
+            })
+            .collect();
+        Ok((underlyings, correlation_matrix))
+    }
+}
@@ -0,0 +1,151 @@
+use rand::Rng;
+#[cfg(not(feature = "deterministic_math"))]
+use rand_distr::{Beta, Distribution, StandardNormal};
+
+// The pricers compute every exponential, logarithm and power through these functions, so the
+// `deterministic_math` feature can swap the platform's math library for a portable one. With
+// the feature, prices depend on the seed only and are bit-identical across operating systems,
+// C libraries and CPU vendors; without it, they may differ in the last bits between
+// platforms. Square roots and the basic arithmetic are correctly rounded by IEEE 754 and
+// portable either way.
+
+/// Returns `e^x`
+#[inline]
+pub(crate) fn exp(x: f64) -> f64 {
+    #[cfg(feature = "deterministic_math")]
+    {
+        libm::exp(x)
+    }
+    #[cfg(not(feature = "deterministic_math"))]
+    {
+        x.exp()
+    }
+}
+
+/// Returns the natural logarithm of `x`
+#[inline]
+pub(crate) fn ln(x: f64) -> f64 {
+    #[cfg(feature = "deterministic_math")]
+    {
+        libm::log(x)
+    }
+    #[cfg(not(feature = "deterministic_math"))]
+    {
+        x.ln()
+    }
+}
+
+/// Returns `x` raised to the power `y`
+#[inline]
+pub(crate) fn powf(x: f64, y: f64) -> f64 {
+    #[cfg(feature = "deterministic_math")]
+    {
+        libm::pow(x, y)
+    }
+    #[cfg(not(feature = "deterministic_math"))]
+    {
+        x.powf(y)
+    }
+}
+
+/// Returns the base 2 logarithm of `x`
+#[inline]
+pub(crate) fn log2(x: f64) -> f64 {
+    #[cfg(feature = "deterministic_math")]
+    {
+        libm::log2(x)
+    }
+    #[cfg(not(feature = "deterministic_math"))]
+    {
+        x.log2()
+    }
+}
+
+/// Returns `x` raised to the integer power `n`
+///
+/// With `deterministic_math`, the power is computed by repeated squaring in a fixed order of
+/// multiplications, since the platform may compute `powi` differently.
+#[inline]
+pub(crate) fn powi(x: f64, n: i32) -> f64 {
+    #[cfg(feature = "deterministic_math")]
+    {
+        let (mut base, mut exponent, mut power) = (x, n.unsigned_abs(), 1.0);
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                power *= base;
+            }
+            base *= base;
+            exponent >>= 1;
+        }
+        if n < 0 {
+            1.0 / power
+        } else {
+            power
+        }
+    }
+    #[cfg(not(feature = "deterministic_math"))]
+    {
+        x.powi(n)
+    }
+}
+
+/// Draws a pseudo-random standard normal shock
+///
+/// With `deterministic_math`, the shock is the inverse normal distribution function of a
+/// uniform in (0, 1) (see `closed_form::inverse_norm_cdf`), computed with the portable
+/// logarithm; the ziggurat sampler of `rand_distr` relies on the platform's exponential.
+/// Both samplers draw different shocks from the same seed.
+#[inline]
+pub(crate) fn standard_normal<R: Rng + ?Sized>(rng: &mut R) -> f64 {
+    #[cfg(feature = "deterministic_math")]
+    {
+        crate::closed_form::inverse_norm_cdf(rng.sample(rand::distributions::Open01))
+    }
+    #[cfg(not(feature = "deterministic_math"))]
+    {
+        StandardNormal.sample(rng)
+    }
+}
+
+/// Draws a pseudo-random sample of the Beta distribution with positive shape parameters
+///
+/// With `deterministic_math`, the sample is the ratio of two Gamma samples drawn with the
+/// method of Marsaglia and Tsang from the portable normal shocks and logarithm.
+pub(crate) fn beta<R: Rng + ?Sized>(rng: &mut R, alpha: f64, beta: f64) -> f64 {
+    #[cfg(feature = "deterministic_math")]
+    {
+        let x = gamma(rng, alpha);
+        x / (x + gamma(rng, beta))
+    }
+    #[cfg(not(feature = "deterministic_math"))]
+    {
+        Beta::new(alpha, beta)
+            .expect("Beta shape parameters are positive")
+            .sample(rng)
+    }
+}
+
+/// Draws a pseudo-random sample of the Gamma distribution with unit scale and a positive
+/// shape (Marsaglia and Tsang, 2000)
+#[cfg(feature = "deterministic_math")]
+fn gamma<R: Rng + ?Sized>(rng: &mut R, shape: f64) -> f64 {
+    if shape < 1.0 {
+        // Boost the shape above one: Gamma(a) = Gamma(a + 1) * U^(1/a)
+        let uniform: f64 = rng.sample(rand::distributions::Open01);
+        return gamma(rng, shape + 1.0) * powf(uniform, 1.0 / shape);
+    }
+    let d = shape - 1.0 / 3.0;
+    let c = 1.0 / (9.0 * d).sqrt();
+    loop {
+        let normal = standard_normal(rng);
+        let v = 1.0 + c * normal;
+        if v <= 0.0 {
+            continue;
+        }
+        let v = v * v * v;
+        let uniform: f64 = rng.sample(rand::distributions::Open01);
+        if ln(uniform) < 0.5 * normal * normal + d - d * v + d * ln(v) {
+            return d * v;
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use std::fmt;
+
+use crate::error::McError;
+use crate::underlying::Underlying;
+
+/// Inputs of one simulation step shared by all models
+#[derive(Debug, Clone, Copy)]
+pub struct StepInputs<'a> {
+    /// Start of the step in years
+    pub time: f64,
+    /// Length of the step in years
+    pub dt: f64,
+    /// Risk-neutral growth rate of each underlying over the step: the forward risk-free rate
+    /// minus the dividend yield for equities, zero for futures, the cost of carry for
+    /// commodities
+    pub carry_rates: &'a [f64],
+    /// Black-Scholes variance of each underlying's log price over the step, following its
+    /// volatility term structure and the variance clock
+    pub variances: &'a [f64],
+}
+
+/// Dynamics of the underlyings in the path simulation
+///
+/// A model advances the log prices of all underlyings by one step, driven by one correlated
+/// standard normal shock per underlying (correlated through the correlation matrix) and
+/// optionally further independent shocks. Models may carry a state per path, such as the
+/// instantaneous variance. Discrete dividends are applied by the engine after each step.
+pub trait Model: fmt::Debug + Send + Sync {
+    /// Number of independent standard normal shocks per underlying and step, drawn in
+    /// addition to the correlated spot shocks
+    fn num_extra_shocks(&self) -> usize {
+        0
+    }
+
+    /// Checks that the model parameters fit the underlyings before a simulation
+    ///
+    /// # Errors
+    /// Returns `McError::InvalidModel` if the parameters do not match the underlyings.
+    fn validate(&self, _underlyings: &[Underlying]) -> Result<(), McError> {
+        Ok(())
+    }
+
+    /// Returns the initial state of a path, e.g. the variance of each underlying
+    fn initial_state(&self, _underlyings: &[Underlying]) -> Vec<f64> {
+        Vec::new()
+    }
+
+    /// Advances the log prices and the state of a path by one step
+    ///
+    /// `shocks` holds the correlated spot shocks of the `n` underlyings followed by the
+    /// `n * num_extra_shocks()` independent shocks; every shock enters multiplied by `sign`
+    /// (`-1.0` for the antithetic path).
+    fn evolve_step(
+        &self,
+        inputs: &StepInputs,
+        log_prices: &mut [f64],
+        state: &mut [f64],
+        shocks: &[f64],
+        sign: f64,
+    );
+
+    /// Returns `true` if the terminal price of each underlying is lognormal with its
+    /// Black-Scholes variance, so analytic Black-Scholes prices can serve as control variates
+    /// and bounds
+    fn has_black_scholes_marginals(&self) -> bool {
+        false
+    }
+}
+
+/// Correlated geometric Brownian motions with deterministic volatilities (the default)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BlackScholes;
+
+impl Model for BlackScholes {
+    fn evolve_step(
+        &self,
+        inputs: &StepInputs,
+        log_prices: &mut [f64],
+        _state: &mut [f64],
+        shocks: &[f64],
+        sign: f64,
+    ) {
+        for (i, log_price) in log_prices.iter_mut().enumerate() {
+            // ln S_{t+dt} = ln S_t + (r - q)*dt - 0.5*w + √w*Z, with the step variance w = σ²*dt
+            // on the calendar clock with a flat volatility
+            let variance = inputs.variances[i];
+            *log_price += inputs.carry_rates[i] * inputs.dt - 0.5 * variance
+                + variance.sqrt() * sign * shocks[i];
+        }
+    }
+
+    fn has_black_scholes_marginals(&self) -> bool {
+        true
+    }
+}
+
+/// Parameters of the Heston stochastic variance of one underlying
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HestonParameters {
+    /// Instantaneous variance today (e.g. 0.04 for 20% volatility)
+    pub initial_variance: f64,
+    /// Speed of mean reversion of the variance
+    pub mean_reversion: f64,
+    /// Long-term variance the variance reverts to
+    pub long_term_variance: f64,
+    /// Volatility of the variance
+    pub vol_of_vol: f64,
+    /// Correlation between the spot and the variance shocks (usually negative for equities)
+    pub correlation: f64,
+}
+
+/// Heston stochastic volatility model
+///
+/// The variance of each underlying follows `dv = κ(θ - v)dt + ξ√v dW`, correlated with its
+/// spot. It is discretized with the full truncation Euler scheme: the variance may turn
+/// negative, but only its positive part enters the drift and the diffusion. The volatilities
+/// and the variance clock of the underlyings are not used.
+#[derive(Debug, Clone)]
+pub struct Heston {
+    /// Parameters of each underlying, in the order of the underlyings
+    pub parameters: Vec<HestonParameters>,
+}
+
+impl Heston {
+    /// Creates a Heston model with the parameters of each underlying
+    pub fn new(parameters: Vec<HestonParameters>) -> Self {
+        Self { parameters }
+    }
+}
+
+impl Model for Heston {
+    fn num_extra_shocks(&self) -> usize {
+        1
+    }
+
+    fn validate(&self, underlyings: &[Underlying]) -> Result<(), McError> {
+        if self.parameters.len() != underlyings.len() {
+            return Err(McError::InvalidModel(format!(
+                "Heston model needs parameters for each of the {} underlyings, got {}",
+                underlyings.len(),
+                self.parameters.len()
+            )));
+        }
+        let is_valid = |parameters: &HestonParameters| {
+            parameters.initial_variance >= 0.0
+                && parameters.mean_reversion >= 0.0
+                && parameters.long_term_variance >= 0.0
+                && parameters.vol_of_vol >= 0.0
+                && (-1.0..=1.0).contains(&parameters.correlation)
+        };
+        match self.parameters.iter().position(|p| !is_valid(p)) {
+            Some(i) => Err(McError::InvalidModel(format!(
+                "Heston parameters of {} must have non-negative variances, mean reversion and \
+                 vol of vol and a correlation in [-1, 1]",
+                underlyings[i].name
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    fn initial_state(&self, _underlyings: &[Underlying]) -> Vec<f64> {
+        self.parameters
+            .iter()
+            .map(|parameters| parameters.initial_variance)
+            .collect()
+    }
+
+    fn evolve_step(
+        &self,
+        inputs: &StepInputs,
+        log_prices: &mut [f64],
+        state: &mut [f64],
+        shocks: &[f64],
+        sign: f64,
+    ) {
+        let num_underlyings = log_prices.len();
+        for (i, (log_price, variance)) in log_prices.iter_mut().zip(state.iter_mut()).enumerate()
+        {
+            let parameters = &self.parameters[i];
+            let spot_shock = sign * shocks[i];
+            let variance_shock = parameters.correlation * spot_shock
+                + (1.0 - parameters.correlation * parameters.correlation).sqrt()
+                    * sign
+                    * shocks[num_underlyings + i];
+
+            // Full truncation: only the positive part of the variance is used
+            let positive_variance = variance.max(0.0);
+            let step_std_dev = (positive_variance * inputs.dt).sqrt();
+            *log_price += (inputs.carry_rates[i] - 0.5 * positive_variance) * inputs.dt
+                + step_std_dev * spot_shock;
+            *variance += parameters.mean_reversion
+                * (parameters.long_term_variance - positive_variance)
+                * inputs.dt
+                + parameters.vol_of_vol * step_std_dev * variance_shock;
+        }
+    }
+}
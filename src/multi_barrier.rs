@@ -0,0 +1,130 @@
+use nalgebra::DMatrix;
+
+use crate::barrier::{Barrier, BarrierCombination};
+use crate::config::SimulationConfig;
+use crate::engine::{self, PathEngine, PathState};
+use crate::error::McError;
+use crate::payoff::OptionType;
+use crate::rates::RateCurve;
+use crate::result::PricingResult;
+use crate::statistics::ChunkedStatistics;
+use crate::underlying::Underlying;
+use crate::validation;
+use crate::{effective_barrier_level, intrinsic_value, is_barrier_hit, with_start_values};
+
+/// Prices a European option (Call or Put) on the first underlying with several barriers,
+/// e.g. a double knock-out or knock-out barriers on different baskets, using Monte Carlo
+/// simulation
+///
+/// Each barrier is checked on the simulated daily closes of its monitoring days, and the
+/// combination decides from the hits whether the option pays at expiry (see
+/// `BarrierCombination`). Neither the configured barrier correction nor the observation of
+/// the barriers applies, so the barriers are monitored discretely on the closes, and rebates
+/// of the barriers are not paid.
+///
+/// # Arguments
+/// * `underlyings` - List of underlying assets
+/// * `correlation_matrix` - Correlation matrix (n x n) where n is the number of underlyings.
+///   Must be symmetric, positive semi-definite, with 1.0 on the diagonal.
+/// * `time_horizon_days` - Time to expiration in days
+/// * `strike_price` - Strike price of the option
+/// * `option_type` - Call or Put option
+/// * `risk_free_rate` - Risk-free rate curve or flat annual rate (as a decimal, e.g., 0.05 for 5%)
+/// * `barriers` - Barriers of the option; without barriers, prices a vanilla option
+/// * `combination` - Knock-out and knock-in conditions combining the hits of the barriers
+/// * `config` - Number of paths and further settings
+///
+/// # Returns
+/// The estimated option price together with its standard error
+///
+/// # Errors
+/// Returns `McError::BarrierIndexOutOfRange` if a barrier applies to an underlying that is
+/// not in `underlyings`, or an error for invalid inputs (see `price_option`).
+#[allow(clippy::too_many_arguments)]
+pub fn price_option_with_barriers(
+    underlyings: &[Underlying],
+    correlation_matrix: &DMatrix<f64>,
+    time_horizon_days: u32,
+    strike_price: f64,
+    option_type: OptionType,
+    risk_free_rate: impl Into<RateCurve>,
+    barriers: &[Barrier],
+    combination: BarrierCombination,
+    config: &SimulationConfig,
+) -> Result<PricingResult, McError> {
+    validation::validate_strike(strike_price)?;
+    for barrier in barriers {
+        validation::validate_barrier(barrier, underlyings.len())?;
+    }
+    let underlyings = &*with_start_values(underlyings, config)?;
+    let rate_curve = risk_free_rate.into();
+    let time_to_expiration = config.day_count.year_fraction(time_horizon_days);
+    let discount_factor = rate_curve.discount_factor(time_to_expiration);
+
+    // Daily steps, so every step is a day on which the barriers may be observed
+    let engine = PathEngine::new(
+        underlyings,
+        correlation_matrix,
+        &rate_curve,
+        time_to_expiration,
+        time_horizon_days as usize,
+        config,
+    )?;
+    let mut generator = engine.shock_generator(config);
+    let levels: Vec<f64> = barriers
+        .iter()
+        .map(|barrier| effective_barrier_level(barrier, &engine.initial_prices))
+        .collect();
+
+    let shock_signs = engine::shock_signs(config.antithetic);
+    let num_samples = config.num_paths.div_ceil(shock_signs.len() as u64);
+    let mut statistics = ChunkedStatistics::default();
+
+    // Path states, barrier hits and shocks are allocated once and reused for every sample
+    let mut paths: Vec<PathState> = shock_signs
+        .iter()
+        .map(|_| PathState::new(&engine, 0))
+        .collect();
+    let mut hits = vec![vec![false; barriers.len()]; shock_signs.len()];
+    let mut shocks = engine.new_shocks();
+
+    for _ in 0..num_samples {
+        for (path, hits) in paths.iter_mut().zip(&mut hits) {
+            path.reset(&engine);
+            hits.fill(false);
+        }
+        generator.start_path();
+
+        for step in 1..=engine.num_steps {
+            engine.draw_shocks(&mut generator, &mut shocks);
+            for ((&sign, path), hits) in shock_signs.iter().zip(&mut paths).zip(&mut hits) {
+                path.advance(&engine, step, &shocks, sign);
+                path.update_prices();
+                let barriers = barriers.iter().zip(&levels);
+                for ((barrier, &level), hit) in barriers.zip(hits.iter_mut()) {
+                    if !*hit && barrier.monitoring.is_monitored(step as u32, time_horizon_days) {
+                        *hit = is_barrier_hit(barrier, level, &path.prices);
+                    }
+                }
+            }
+        }
+
+        let payoff_sum: f64 = paths
+            .iter()
+            .zip(&hits)
+            .filter(|(_, hits)| combination.is_alive(barriers, hits))
+            .map(|(path, _)| intrinsic_value(path.prices[0], strike_price, option_type))
+            .sum();
+        statistics.add(payoff_sum / paths.len() as f64 * discount_factor, 0.0);
+    }
+
+    let mut result = PricingResult::from_statistics(
+        &statistics.finish(),
+        num_samples * shock_signs.len() as u64,
+        None,
+    );
+    result.record_correlation_repair(engine.correlation.adjustment());
+    result.record_dimension_budget(engine.dimension_budget(config.sampling));
+    result.check_std_error();
+    Ok(result)
+}
@@ -0,0 +1,176 @@
+use std::fmt;
+use std::sync::Mutex;
+
+/// Receiver of the paths simulated by a pricer, e.g. to check barrier hit rates, price
+/// distributions or the correlation the engine realizes
+///
+/// The pricers that simulate each product path by path (`price_option`, `price_payoff` and
+/// the pricers built on them) call the observer for the paths it observes, in the order they
+/// are simulated. Pricing sessions simulate their paths once for all products and do not
+/// call it. The observer is shared through the configuration, so it records through interior
+/// mutability; it never affects the prices.
+pub trait PathObserver: fmt::Debug + Send + Sync {
+    /// Returns `true` if the path with the given index is observed, e.g. to observe a
+    /// subsample of the paths (all paths by default)
+    ///
+    /// Paths are numbered from 0 in the order they are simulated; the antithetic
+    /// counterpart of a path follows it.
+    fn observes(&self, path_index: u64) -> bool {
+        let _ = path_index;
+        true
+    }
+
+    /// Called with the prices of all underlyings at the start of an observed path (step 0)
+    /// and after each of its steps
+    fn on_step(&self, path_index: u64, step: usize, prices: &[f64]) {
+        let _ = (path_index, step, prices);
+    }
+
+    /// Called once an observed path is complete, before its payoff enters the price
+    fn on_path_end(&self, path: &ObservedPath) {
+        let _ = path;
+    }
+}
+
+/// Outcome of an observed path
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ObservedPath<'a> {
+    /// Index of the path in the order of simulation
+    pub path_index: u64,
+    /// Prices of all underlyings at expiry
+    pub final_prices: &'a [f64],
+    /// Probability that the barrier was hit: 0 or 1 on the simulated steps, in between with
+    /// the Brownian-bridge correction (0 without a barrier)
+    pub barrier_hit_probability: f64,
+    /// Undiscounted payoff of the path, including barrier and rebate, before the
+    /// `NonFinitePolicy` is applied
+    pub payoff: f64,
+}
+
+/// Path recorded by a `PathRecorder`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedPath {
+    /// Index of the path in the order of simulation
+    pub path_index: u64,
+    /// Prices of all underlyings at the start and after each step, if steps are recorded
+    pub prices: Vec<Vec<f64>>,
+    /// Prices of all underlyings at expiry
+    pub final_prices: Vec<f64>,
+    /// Probability that the barrier was hit (see `ObservedPath::barrier_hit_probability`)
+    pub barrier_hit_probability: f64,
+    /// Undiscounted payoff of the path
+    pub payoff: f64,
+}
+
+impl RecordedPath {
+    /// Creates the record of a path whose outcome is not known yet
+    fn started(path_index: u64) -> Self {
+        Self {
+            path_index,
+            prices: Vec::new(),
+            final_prices: Vec::new(),
+            barrier_hit_probability: 0.0,
+            payoff: 0.0,
+        }
+    }
+}
+
+/// Observer recording every n-th simulated path in memory
+#[derive(Debug)]
+pub struct PathRecorder {
+    every: u64,
+    max_paths: Option<u64>,
+    record_steps: bool,
+    paths: Mutex<Vec<RecordedPath>>,
+}
+
+impl PathRecorder {
+    /// Creates a recorder of every `every`-th path (paths 0, `every`, 2 * `every`, ...),
+    /// recording the prices after each step
+    ///
+    /// # Panics
+    /// Panics if `every` is zero.
+    pub fn new(every: u64) -> Self {
+        assert!(every > 0, "Recording interval must be positive");
+        Self {
+            every,
+            max_paths: None,
+            record_steps: true,
+            paths: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Stops recording after the given number of paths
+    pub fn with_max_paths(mut self, max_paths: u64) -> Self {
+        self.max_paths = Some(max_paths);
+        self
+    }
+
+    /// Enables or disables the recording of the prices after each step; without them, only
+    /// the outcome of each path is recorded
+    pub fn with_steps(mut self, record_steps: bool) -> Self {
+        self.record_steps = record_steps;
+        self
+    }
+
+    /// Returns the paths recorded so far, in the order of simulation
+    pub fn paths(&self) -> Vec<RecordedPath> {
+        self.lock().clone()
+    }
+
+    /// Removes and returns the paths recorded so far, e.g. before recording another pricing
+    pub fn take_paths(&self) -> Vec<RecordedPath> {
+        std::mem::take(&mut *self.lock())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<RecordedPath>> {
+        // A panicking observer cannot leave a recorded path inconsistent
+        self.paths
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl PathObserver for PathRecorder {
+    fn observes(&self, path_index: u64) -> bool {
+        path_index.is_multiple_of(self.every)
+            && self
+                .max_paths
+                .is_none_or(|max_paths| path_index / self.every < max_paths)
+    }
+
+    fn on_step(&self, path_index: u64, step: usize, prices: &[f64]) {
+        if !self.record_steps {
+            return;
+        }
+        let mut paths = self.lock();
+        if step == 0 {
+            paths.push(RecordedPath::started(path_index));
+        }
+        // The steps of an antithetic pair interleave, so the path is one of the last ones
+        if let Some(path) = paths
+            .iter_mut()
+            .rev()
+            .find(|path| path.path_index == path_index)
+        {
+            path.prices.push(prices.to_vec());
+        }
+    }
+
+    fn on_path_end(&self, observed: &ObservedPath) {
+        let mut paths = self.lock();
+        let position = paths.iter().rposition(|path| {
+            path.path_index == observed.path_index && path.final_prices.is_empty()
+        });
+        let path = match position {
+            Some(position) => &mut paths[position],
+            None => {
+                paths.push(RecordedPath::started(observed.path_index));
+                paths.last_mut().expect("path was just recorded")
+            }
+        };
+        path.final_prices = observed.final_prices.to_vec();
+        path.barrier_hit_probability = observed.barrier_hit_probability;
+        path.payoff = observed.payoff;
+    }
+}
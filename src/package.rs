@@ -0,0 +1,166 @@
+use nalgebra::DMatrix;
+
+use crate::barrier::Barrier;
+use crate::closed_form;
+use crate::config::SimulationConfig;
+use crate::error::McError;
+use crate::payoff::{OptionType, Payoff};
+use crate::rates::RateCurve;
+use crate::result::PricingResult;
+use crate::statistics::SampleStatistics;
+use crate::underlying::Underlying;
+use crate::validation;
+use crate::{simulate_paths, with_effective_volatility, with_start_values};
+
+/// Vanilla option leg of a package on the first underlying
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VanillaLeg {
+    /// Number of options held (negative for sold options)
+    pub quantity: f64,
+    /// Strike price of the option
+    pub strike_price: f64,
+    /// Call or Put option
+    pub option_type: OptionType,
+}
+
+/// Barrier option leg of a package on the first underlying
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BarrierLeg {
+    /// Number of options held (negative for sold options)
+    pub quantity: f64,
+    /// Strike price of the option
+    pub strike_price: f64,
+    /// Call or Put option
+    pub option_type: OptionType,
+    /// Barrier of the option
+    pub barrier: Barrier,
+}
+
+/// Result of pricing a package of a vanilla and a barrier option
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PackageResult {
+    /// Price of the whole package with its standard error
+    pub pricing: PricingResult,
+    /// Price of one vanilla option
+    pub vanilla_price: f64,
+    /// Price of one barrier option
+    pub barrier_price: f64,
+    /// `true` if the vanilla leg was priced analytically with Black-Scholes
+    pub is_vanilla_analytic: bool,
+}
+
+/// Prices a package of a vanilla and a barrier option, simulating only what has no
+/// analytic price
+///
+/// The vanilla leg is priced with Black-Scholes and enters the simulation of the barrier leg
+/// as control variate on the same paths, so the package carries only the remaining error of
+/// the barrier leg. Without an analytic price (cash dividends or a model without lognormal
+/// marginals), both legs are simulated on the same paths and the standard error of the
+/// package accounts for their covariance.
+///
+/// # Arguments
+/// * `underlyings` - List of underlying assets, the legs are written on the first one
+/// * `correlation_matrix` - Correlation matrix (n x n) where n is the number of underlyings.
+///   Must be symmetric, positive semi-definite, with 1.0 on the diagonal.
+/// * `time_horizon_days` - Time to expiration of both legs in days
+/// * `vanilla` - Vanilla option leg
+/// * `barrier_leg` - Barrier option leg
+/// * `risk_free_rate` - Risk-free rate curve or flat annual rate (as a decimal, e.g., 0.05 for 5%)
+/// * `config` - Number of paths and variance reduction settings. The control variate setting
+///   is ignored, the vanilla leg always serves as control.
+///
+/// # Errors
+/// Returns an error for invalid inputs (see `price_option`).
+#[allow(clippy::too_many_arguments)]
+pub fn price_barrier_package(
+    underlyings: &[Underlying],
+    correlation_matrix: &DMatrix<f64>,
+    time_horizon_days: u32,
+    vanilla: &VanillaLeg,
+    barrier_leg: &BarrierLeg,
+    risk_free_rate: impl Into<RateCurve>,
+    config: &SimulationConfig,
+) -> Result<PackageResult, McError> {
+    validation::validate_strike(vanilla.strike_price)?;
+    let underlyings = &*with_start_values(underlyings, config)?;
+    let rate_curve = risk_free_rate.into();
+    let analytic_vanilla = if config.model.has_black_scholes_marginals() {
+        closed_form::black_scholes_price_with_dividends(
+            &with_effective_volatility(&underlyings[0], time_horizon_days, config),
+            vanilla.strike_price,
+            &rate_curve,
+            config.day_count.year_fraction(time_horizon_days),
+            vanilla.option_type,
+            config.day_count,
+        )
+    } else {
+        None
+    };
+    // Returns the package price and its standard error together with the leg prices
+    let estimate = |statistics: &SampleStatistics| {
+        let (price, variance, vanilla_price, barrier_price) = match analytic_vanilla {
+            Some(vanilla_price) => {
+                let (barrier_price, barrier_variance) =
+                    statistics.controlled_mean_and_variance(vanilla_price);
+                (
+                    vanilla.quantity * vanilla_price + barrier_leg.quantity * barrier_price,
+                    barrier_leg.quantity * barrier_leg.quantity * barrier_variance,
+                    vanilla_price,
+                    barrier_price,
+                )
+            }
+            None => {
+                let (price, variance) =
+                    statistics.combined_mean_and_variance(barrier_leg.quantity, vanilla.quantity);
+                (
+                    price,
+                    variance,
+                    statistics.control_mean(),
+                    statistics.mean(),
+                )
+            }
+        };
+        let std_error = (variance / statistics.effective_count()).sqrt();
+        (price, std_error, vanilla_price, barrier_price)
+    };
+
+    let simulation = simulate_paths(
+        underlyings,
+        correlation_matrix,
+        time_horizon_days,
+        &Payoff::Vanilla {
+            strike_price: barrier_leg.strike_price,
+            option_type: barrier_leg.option_type,
+        },
+        &rate_curve,
+        Some(&barrier_leg.barrier),
+        (vanilla.strike_price, vanilla.option_type),
+        config,
+        &|statistics| {
+            let (price, std_error, ..) = estimate(statistics);
+            (price, std_error)
+        },
+    )?;
+    let (price, std_error, vanilla_price, barrier_price) = estimate(&simulation.statistics);
+
+    let mut pricing = PricingResult::new(price, std_error, simulation.num_paths);
+    pricing.record_non_finite_paths(simulation.non_finite_paths);
+    pricing.record_correlation_repair(simulation.correlation_adjustment);
+    pricing.record_dimension_budget(simulation.dimension_budget);
+    pricing.check_error_tolerance(config.error_tolerance);
+    pricing.check_std_error();
+    pricing.warnings.extend(validation::barrier_warning(
+        underlyings,
+        &barrier_leg.barrier,
+        config,
+    ));
+    Ok(PackageResult {
+        pricing,
+        vanilla_price,
+        barrier_price,
+        is_vanilla_analytic: analytic_vanilla.is_some(),
+    })
+}
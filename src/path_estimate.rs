@@ -0,0 +1,84 @@
+use crate::config::{ErrorTolerance, SimulationConfig};
+use crate::error::McError;
+use crate::market::MarketSnapshot;
+use crate::price_payoff;
+use crate::product::Product;
+use crate::result::PricingResult;
+
+/// Number of paths a product needs for a given accuracy, estimated from a pilot run
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PathEstimate {
+    /// Price of the pilot run
+    pub pilot: PricingResult,
+    /// Standard error the tolerance requires at the pilot price
+    pub target_std_error: f64,
+    /// Estimated number of paths to meet the tolerance, or `None` if no number of paths
+    /// can, e.g. for a relative tolerance at a price of zero
+    pub paths_needed: Option<u64>,
+}
+
+impl PathEstimate {
+    /// Returns the standard error expected with the given number of paths, extrapolated
+    /// from the pilot run
+    pub fn std_error_at(&self, num_paths: u64) -> f64 {
+        self.pilot.std_error * (self.pilot.num_paths as f64 / num_paths as f64).sqrt()
+    }
+}
+
+/// Estimates the number of paths a product needs to reach an absolute or relative accuracy,
+/// from the variance of a pilot run
+///
+/// The pilot run simulates `config.num_paths` paths with the other settings of the
+/// configuration, so the estimate accounts for its variance reduction. The standard error
+/// is assumed to fall with the square root of the number of paths (see
+/// `PricingResult::paths_needed`); with antithetic sampling, the estimate is rounded up to
+/// an even number of paths.
+///
+/// # Arguments
+/// * `market` - Market of the underlyings
+/// * `time_horizon_days` - Time to expiration of the product in days
+/// * `product` - Product to price
+/// * `tolerance` - Accuracy to reach, as an absolute or relative standard error
+/// * `config` - Settings of the pilot run, with `num_paths` its number of paths. The error
+///   tolerance and the sanity checks are ignored.
+///
+/// # Returns
+/// The pilot price, the standard error to reach and the estimated number of paths
+///
+/// # Errors
+/// Returns an error for invalid inputs (see `price_payoff`).
+pub fn estimate_paths_needed(
+    market: &MarketSnapshot,
+    time_horizon_days: u32,
+    product: &Product,
+    tolerance: ErrorTolerance,
+    config: &SimulationConfig,
+) -> Result<PathEstimate, McError> {
+    let pilot_config = SimulationConfig {
+        validate: false,
+        error_tolerance: None,
+        ..config.clone()
+    };
+    let pilot = price_payoff(
+        &market.underlyings,
+        &market.correlation_matrix,
+        time_horizon_days,
+        &product.payoff,
+        market.risk_free_rate.clone(),
+        product.barrier.as_ref(),
+        &pilot_config,
+    )?;
+    let paths_needed = pilot.paths_needed(tolerance).map(|num_paths| {
+        if config.antithetic {
+            num_paths.saturating_add(num_paths % 2)
+        } else {
+            num_paths
+        }
+    });
+    Ok(PathEstimate {
+        target_std_error: tolerance.target_std_error(pilot.price),
+        paths_needed,
+        pilot,
+    })
+}
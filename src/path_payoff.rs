@@ -0,0 +1,208 @@
+use nalgebra::{DMatrix, DVector};
+
+use crate::config::SimulationConfig;
+use crate::engine::{self, PathEngine, PathState};
+use crate::error::McError;
+use crate::rates::RateCurve;
+use crate::result::PricingResult;
+use crate::statistics::ChunkedStatistics;
+use crate::terminal_payoff::price_terminal_payoff;
+use crate::underlying::Underlying;
+use crate::with_start_values;
+
+/// Prices of a path around one of its daily steps, as passed to `PathPayoff::on_step`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PathStep<'a> {
+    /// Day of the step, counted from 1
+    pub step: usize,
+    /// Time from the start of the path to the end of the step in years
+    pub time: f64,
+    /// Prices of all underlyings at the end of the step
+    pub prices: &'a [f64],
+    /// Prices of all underlyings at the start of the step
+    pub previous_prices: &'a [f64],
+}
+
+/// Payoff carrying its own state along each path, e.g. the running realized variance of a
+/// variance swap or a counter of the days in a range
+///
+/// Each path starts from `initial_state`, which `on_step` updates after every daily step;
+/// the payoff is then evaluated from the final state. The state is typed by the payoff, so
+/// exotic features need neither a new `Payoff` variant nor changes to the engine.
+///
+/// Payoffs that only depend on the prices at expiry declare it with `is_terminal`, so the
+/// engine can skip the daily steps.
+pub trait PathPayoff {
+    /// State of the payoff on one path
+    type State;
+
+    /// Returns the state at the start of a path, given the initial prices of all underlyings
+    fn initial_state(&self, initial_prices: &[f64]) -> Self::State;
+
+    /// Updates the state of a path after one of its steps
+    fn on_step(&self, state: &mut Self::State, step: &PathStep);
+
+    /// Returns the undiscounted payoff of a path from its final state and the prices of all
+    /// underlyings at expiry
+    fn evaluate(&self, state: &Self::State, final_prices: &[f64]) -> f64;
+
+    /// Returns `true` if the payoff only depends on the prices at expiry, so `on_step` is
+    /// never needed (`false` by default)
+    fn is_terminal(&self) -> bool {
+        false
+    }
+}
+
+/// Prices a payoff with its own per-path state, simulating the paths with one step per day
+///
+/// Terminal payoffs (see `PathPayoff::is_terminal`) are priced like a `TerminalPayoff`
+/// instead: the paths are simulated directly to expiry in a single step under Black-Scholes,
+/// with the moments of the terminal prices matched if configured (see
+/// `price_terminal_payoff`), and each path is evaluated from its initial state without
+/// calling `on_step`.
+///
+/// # Arguments
+/// * `underlyings` - List of underlying assets
+/// * `correlation_matrix` - Correlation matrix (n x n) where n is the number of underlyings.
+///   Must be symmetric, positive semi-definite, with 1.0 on the diagonal.
+/// * `time_horizon_days` - Time to expiration in days, the number of steps of each path
+/// * `payoff` - Payoff updating its state on every step
+/// * `risk_free_rate` - Risk-free rate curve or flat annual rate (as a decimal, e.g., 0.05 for 5%)
+/// * `config` - Number of paths and variance reduction settings. The control variate, the
+///   path observer and the sanity checks are not supported and ignored; quasi-random
+///   sampling runs once instead of in replications.
+///
+/// # Returns
+/// The estimated price together with its standard error
+///
+/// # Errors
+/// Returns an error for invalid inputs (see `price_option`).
+pub fn price_path_payoff<P: PathPayoff>(
+    underlyings: &[Underlying],
+    correlation_matrix: &DMatrix<f64>,
+    time_horizon_days: u32,
+    payoff: &P,
+    risk_free_rate: impl Into<RateCurve>,
+    config: &SimulationConfig,
+) -> Result<PricingResult, McError> {
+    let underlyings = &*with_start_values(underlyings, config)?;
+    let rate_curve = risk_free_rate.into();
+    if payoff.is_terminal() {
+        let initial_prices: Vec<f64> = underlyings.iter().map(|u| u.spot_price).collect();
+        let terminal_payoff = |terminal_prices: &DMatrix<f64>| {
+            let mut final_prices = vec![0.0; terminal_prices.ncols()];
+            DVector::from_iterator(
+                terminal_prices.nrows(),
+                terminal_prices.row_iter().map(|row| {
+                    for (final_price, &price) in final_prices.iter_mut().zip(row.iter()) {
+                        *final_price = price;
+                    }
+                    payoff.evaluate(&payoff.initial_state(&initial_prices), &final_prices)
+                }),
+            )
+        };
+        return price_terminal_payoff(
+            underlyings,
+            correlation_matrix,
+            time_horizon_days,
+            &terminal_payoff,
+            rate_curve,
+            config,
+        );
+    }
+    let time_to_expiration = config.day_count.year_fraction(time_horizon_days);
+    let discount_factor = rate_curve.discount_factor(time_to_expiration);
+    let num_steps = time_horizon_days as usize;
+    let engine = PathEngine::new(
+        underlyings,
+        correlation_matrix,
+        &rate_curve,
+        time_to_expiration,
+        num_steps,
+        config,
+    )?;
+    let mut generator = engine.shock_generator(config);
+
+    let shock_signs = engine::shock_signs(config.antithetic);
+    let num_samples = config.num_paths.div_ceil(shock_signs.len() as u64);
+    // The standard error needs at least two samples per batch
+    let batch_samples = config.batch_paths.div_ceil(shock_signs.len() as u64).max(2);
+    let mut num_simulated_samples = num_samples;
+
+    let mut statistics = ChunkedStatistics::default();
+    let mut non_finite_paths = 0;
+    let mut paths: Vec<PathState> = shock_signs
+        .iter()
+        .map(|_| PathState::new(&engine, 0))
+        .collect();
+    let mut previous_prices = engine.initial_prices.clone();
+    let mut shocks = engine.new_shocks();
+    let mut states: Vec<P::State> = Vec::with_capacity(paths.len());
+    for sample in 0..num_samples {
+        if let Some(tolerance) = config.error_tolerance {
+            if sample > 0 && sample.is_multiple_of(batch_samples) {
+                let estimate = PricingResult::from_statistics(&statistics.current(), 0, None);
+                if tolerance.is_met(estimate.price, estimate.std_error) {
+                    num_simulated_samples = sample;
+                    break;
+                }
+            }
+        }
+        states.clear();
+        states.extend(paths.iter_mut().map(|path| {
+            path.reset(&engine);
+            payoff.initial_state(&path.prices)
+        }));
+        generator.start_path();
+
+        for step in 1..=engine.num_steps {
+            engine.draw_shocks(&mut generator, &mut shocks);
+            let time = step as f64 * time_to_expiration / num_steps as f64;
+            let paths = shock_signs.iter().zip(&mut paths).zip(&mut states);
+            for ((&sign, path), state) in paths {
+                previous_prices.copy_from_slice(&path.prices);
+                path.advance(&engine, step, &shocks, sign);
+                path.update_prices();
+                payoff.on_step(
+                    state,
+                    &PathStep {
+                        step,
+                        time,
+                        prices: &path.prices,
+                        previous_prices: &previous_prices,
+                    },
+                );
+            }
+        }
+
+        let mut payoff_sum = 0.0;
+        let mut is_dropped = false;
+        for (path, state) in paths.iter().zip(&states) {
+            let value = payoff.evaluate(state, &path.prices);
+            // Non-finite values from extreme parameters are treated according to the policy
+            if !value.is_finite() {
+                non_finite_paths += 1;
+            }
+            match config.non_finite_policy.apply(value)? {
+                Some(value) => payoff_sum += value,
+                None => is_dropped = true,
+            }
+        }
+        if !is_dropped {
+            // Discount to present value
+            statistics.add(payoff_sum / paths.len() as f64 * discount_factor, 0.0);
+        }
+    }
+
+    let mut result = PricingResult::from_statistics(
+        &statistics.finish(),
+        num_simulated_samples * shock_signs.len() as u64,
+        None,
+    );
+    result.record_non_finite_paths(non_finite_paths);
+    result.record_correlation_repair(engine.correlation.adjustment());
+    result.record_dimension_budget(engine.dimension_budget(config.sampling));
+    result.check_error_tolerance(config.error_tolerance);
+    result.check_std_error();
+    Ok(result)
+}
@@ -0,0 +1,545 @@
+use crate::calendar::Date;
+use crate::config::SimulationConfig;
+use crate::error::McError;
+use crate::intrinsic_value;
+use crate::math;
+
+/// Observables of a simulated path that payoffs on the first underlying depend on
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PathObservables<'a> {
+    /// Price of the first underlying at the start of the path
+    pub initial_price: f64,
+    /// Price of the first underlying at expiry
+    pub final_price: f64,
+    /// Highest price of the first underlying on the path (initial price included)
+    pub running_max: f64,
+    /// Lowest price of the first underlying on the path (initial price included)
+    pub running_min: f64,
+    /// Recorded fixings of the first underlying
+    pub fixings: &'a [f64],
+}
+
+impl PathObservables<'_> {
+    /// Returns the first non-finite price of the path, if any
+    ///
+    /// Payoffs clip prices, e.g. a call pays 0 on a NaN terminal price, so the prices are
+    /// checked before the payoff is applied. The running extremes are left out: in log space
+    /// they may overflow mid-path without affecting the payoffs that do not depend on them.
+    pub fn non_finite_price(&self) -> Option<f64> {
+        std::iter::once(self.final_price)
+            .chain(self.fixings.iter().copied())
+            .find(|price| !price.is_finite())
+    }
+}
+
+/// Type of an option: the right to buy (Call) or to sell (Put)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OptionType {
+    /// Right to buy the underlying at the strike
+    Call,
+    /// Right to sell the underlying at the strike
+    Put,
+}
+
+impl OptionType {
+    /// Returns `true` for calls and `false` for puts
+    pub fn is_call(&self) -> bool {
+        *self == OptionType::Call
+    }
+
+    /// Returns the other option type, a Put for a Call and vice versa
+    pub fn complement(&self) -> Self {
+        match self {
+            OptionType::Call => OptionType::Put,
+            OptionType::Put => OptionType::Call,
+        }
+    }
+
+    /// Returns the option type of a call (`true`) or put (`false`) flag
+    pub(crate) fn from_is_call(is_call: bool) -> Self {
+        if is_call {
+            OptionType::Call
+        } else {
+            OptionType::Put
+        }
+    }
+}
+
+/// Averaging method for Asian payoffs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Averaging {
+    /// Arithmetic mean of the fixings
+    Arithmetic,
+    /// Geometric mean of the fixings
+    Geometric,
+}
+
+/// Schedule of the fixing days an Asian payoff averages over, or the reset days of a cliquet
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FixingSchedule {
+    /// A fixing on every day up to and including expiry
+    Daily,
+    /// A fixing every 30 days, counted backwards from expiry (expiry included)
+    Monthly,
+    /// The last N daily fixings up to and including expiry
+    LastN(u32),
+    /// Explicit fixing days (from today, between day 1 and expiry)
+    Dates(Vec<u32>),
+}
+
+impl FixingSchedule {
+    /// Creates a schedule of explicit fixing dates, converted into days from the valuation
+    /// date with the day count convention and calendar of the configuration
+    ///
+    /// # Errors
+    /// Returns `McError::InvalidDate` if a fixing date lies before the valuation date.
+    pub fn on_dates(
+        valuation: Date,
+        fixing_dates: &[Date],
+        config: &SimulationConfig,
+    ) -> Result<Self, McError> {
+        Ok(FixingSchedule::Dates(config.day_offsets(valuation, fixing_dates)?))
+    }
+
+    /// Returns the sorted, de-duplicated fixing days for an option expiring after
+    /// `time_horizon_days`
+    ///
+    /// # Errors
+    /// Returns `McError::InvalidSchedule` if explicit dates lie outside of day 1 to expiry or
+    /// no fixing day remains.
+    pub fn fixing_days(&self, time_horizon_days: u32) -> Result<Vec<u32>, McError> {
+        let mut days: Vec<u32> = match self {
+            FixingSchedule::Daily => (1..=time_horizon_days).collect(),
+            FixingSchedule::Monthly => (0..time_horizon_days)
+                .step_by(30)
+                .map(|offset| time_horizon_days - offset)
+                .collect(),
+            FixingSchedule::LastN(count) => {
+                let first_day = time_horizon_days.saturating_sub(*count) + 1;
+                (first_day.max(1)..=time_horizon_days).collect()
+            }
+            FixingSchedule::Dates(dates) => {
+                if !dates.iter().all(|&day| day > 0 && day <= time_horizon_days) {
+                    return Err(McError::InvalidSchedule(format!(
+                        "Fixing dates must be between day 1 and day {}",
+                        time_horizon_days
+                    )));
+                }
+                dates.clone()
+            }
+        };
+        days.sort_unstable();
+        days.dedup();
+        if days.is_empty() {
+            return Err(McError::InvalidSchedule(
+                "Fixing schedule must contain at least one day".to_string(),
+            ));
+        }
+        Ok(days)
+    }
+}
+
+/// Payoff of an option on the first underlying
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Payoff {
+    /// European payoff on the terminal price: `max(S_T - K, 0)` or `max(K - S_T, 0)`
+    Vanilla {
+        /// Strike price of the option
+        strike_price: f64,
+        /// Call or Put option
+        option_type: OptionType,
+    },
+    /// Cash-or-nothing digital: a fixed amount if `S_T > K` (calls) or `S_T < K` (puts)
+    CashOrNothing {
+        /// Strike price of the option
+        strike_price: f64,
+        /// Call or Put option
+        option_type: OptionType,
+        /// Cash amount paid if the option expires in the money
+        amount: f64,
+    },
+    /// Asset-or-nothing digital: the terminal price if `S_T > K` (calls) or `S_T < K` (puts)
+    AssetOrNothing {
+        /// Strike price of the option
+        strike_price: f64,
+        /// Call or Put option
+        option_type: OptionType,
+    },
+    /// Asian average-price payoff: `max(A - K, 0)` or `max(K - A, 0)`
+    AveragePrice {
+        /// Strike price of the option
+        strike_price: f64,
+        /// Call or Put option
+        option_type: OptionType,
+        /// Arithmetic or geometric averaging of the fixings
+        averaging: Averaging,
+        /// Days on which the fixings are taken
+        schedule: FixingSchedule,
+    },
+    /// Asian average-strike payoff: `max(S_T - A, 0)` or `max(A - S_T, 0)`
+    AverageStrike {
+        /// Call or Put option
+        option_type: OptionType,
+        /// Arithmetic or geometric averaging of the fixings
+        averaging: Averaging,
+        /// Days on which the fixings are taken
+        schedule: FixingSchedule,
+    },
+    /// Floating-strike lookback: `S_T - min(S)` for calls, `max(S) - S_T` for puts
+    FloatingLookback {
+        /// Call or Put option
+        option_type: OptionType,
+    },
+    /// Fixed-strike lookback: `max(max(S) - K, 0)` for calls, `max(K - min(S), 0)` for puts
+    FixedLookback {
+        /// Strike price of the option
+        strike_price: f64,
+        /// Call or Put option
+        option_type: OptionType,
+    },
+    /// Ladder option: locks in the best rung reached by the path. Calls pay
+    /// `max(S_T - K, R - K, 0)` with R the highest rung at or below the running maximum,
+    /// puts pay `max(K - S_T, K - R, 0)` with R the lowest rung at or above the running minimum.
+    Ladder {
+        /// Strike price of the option
+        strike_price: f64,
+        /// Call or Put option
+        option_type: OptionType,
+        /// Rung levels (same unit as strike and spot price)
+        rungs: Vec<f64>,
+    },
+    /// Cliquet (ratchet): sums the returns over the periods between consecutive reset days,
+    /// the first starting at the initial price, each bounded by the local floor and cap, and
+    /// pays `notional * R` with `R` the sum bounded by the global floor and cap
+    Cliquet {
+        /// Amount the summed return applies to
+        notional: f64,
+        /// Lowest return counted for a period (e.g. 0.0), none for no floor
+        local_floor: Option<f64>,
+        /// Highest return counted for a period (e.g. 0.05), none for no cap
+        local_cap: Option<f64>,
+        /// Lowest summed return paid (e.g. 0.0 for capital protection), none for no floor
+        global_floor: Option<f64>,
+        /// Highest summed return paid, none for no cap
+        global_cap: Option<f64>,
+        /// Reset days, each ending a period
+        schedule: FixingSchedule,
+    },
+    /// Variance swap: pays `notional * (σ² - K²)` with `σ²` the realized variance of the log
+    /// returns between consecutive fixings, the first starting at the initial price,
+    /// annualized as `observations_per_year / N * Σ ln(S_i / S_{i-1})²` over the `N` returns
+    VarianceSwap {
+        /// Amount paid per unit of variance (the variance notional)
+        notional: f64,
+        /// Strike `K` quoted as a volatility (e.g. 0.20 for a variance strike of 0.04)
+        strike_volatility: f64,
+        /// Number of returns per year annualizing the realized variance (e.g. 365 for daily
+        /// fixings on calendar days, 252 on business days)
+        observations_per_year: f64,
+        /// Fixing days, each ending a return
+        schedule: FixingSchedule,
+    },
+    /// Volatility swap: pays `notional * (σ - K)` with `σ` the square root of the realized
+    /// variance of a variance swap on the same fixings
+    VolatilitySwap {
+        /// Amount paid per unit of volatility (the vega notional)
+        notional: f64,
+        /// Strike `K` as a volatility
+        strike_volatility: f64,
+        /// Number of returns per year annualizing the realized variance
+        observations_per_year: f64,
+        /// Fixing days, each ending a return
+        schedule: FixingSchedule,
+    },
+}
+
+impl Payoff {
+    /// Returns `true` if the payoff depends on more than the terminal price
+    pub fn is_path_dependent(&self) -> bool {
+        !matches!(
+            self,
+            Payoff::Vanilla { .. } | Payoff::CashOrNothing { .. } | Payoff::AssetOrNothing { .. }
+        )
+    }
+
+    /// Returns the fixing schedule the payoff depends on, if any
+    pub fn schedule(&self) -> Option<&FixingSchedule> {
+        match self {
+            Payoff::AveragePrice { schedule, .. }
+            | Payoff::AverageStrike { schedule, .. }
+            | Payoff::Cliquet { schedule, .. }
+            | Payoff::VarianceSwap { schedule, .. }
+            | Payoff::VolatilitySwap { schedule, .. } => Some(schedule),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the payoff is a Call or a Put; cliquets pay on rising prices and
+    /// variance and volatility swaps on rising volatility like calls
+    pub fn option_type(&self) -> OptionType {
+        match self {
+            Payoff::Vanilla { option_type, .. }
+            | Payoff::CashOrNothing { option_type, .. }
+            | Payoff::AssetOrNothing { option_type, .. }
+            | Payoff::AveragePrice { option_type, .. }
+            | Payoff::AverageStrike { option_type, .. }
+            | Payoff::FloatingLookback { option_type }
+            | Payoff::FixedLookback { option_type, .. }
+            | Payoff::Ladder { option_type, .. } => *option_type,
+            Payoff::Cliquet { .. }
+            | Payoff::VarianceSwap { .. }
+            | Payoff::VolatilitySwap { .. } => OptionType::Call,
+        }
+    }
+
+    /// Returns `true` for Call payoffs and `false` for Put payoffs
+    pub fn is_call(&self) -> bool {
+        self.option_type().is_call()
+    }
+
+    /// Returns the fixed strike price of the payoff, if it has one (the volatility strikes of
+    /// swaps are not strike prices)
+    pub fn strike_price(&self) -> Option<f64> {
+        match self {
+            Payoff::Vanilla { strike_price, .. }
+            | Payoff::CashOrNothing { strike_price, .. }
+            | Payoff::AssetOrNothing { strike_price, .. }
+            | Payoff::AveragePrice { strike_price, .. }
+            | Payoff::FixedLookback { strike_price, .. }
+            | Payoff::Ladder { strike_price, .. } => Some(*strike_price),
+            Payoff::AverageStrike { .. }
+            | Payoff::FloatingLookback { .. }
+            | Payoff::Cliquet { .. }
+            | Payoff::VarianceSwap { .. }
+            | Payoff::VolatilitySwap { .. } => None,
+        }
+    }
+
+    /// Returns a copy of the payoff with the fixed strike replaced (unchanged if it has none)
+    pub(crate) fn with_strike_price(&self, new_strike_price: f64) -> Payoff {
+        let mut payoff = self.clone();
+        if let Payoff::Vanilla { strike_price, .. }
+        | Payoff::CashOrNothing { strike_price, .. }
+        | Payoff::AssetOrNothing { strike_price, .. }
+        | Payoff::AveragePrice { strike_price, .. }
+        | Payoff::FixedLookback { strike_price, .. }
+        | Payoff::Ladder { strike_price, .. } = &mut payoff
+        {
+            *strike_price = new_strike_price;
+        }
+        payoff
+    }
+
+    /// Returns a copy of the payoff with Call and Put swapped (unchanged for cliquets and
+    /// swaps)
+    pub(crate) fn complement(&self) -> Payoff {
+        let mut payoff = self.clone();
+        match &mut payoff {
+            Payoff::Vanilla { option_type, .. }
+            | Payoff::CashOrNothing { option_type, .. }
+            | Payoff::AssetOrNothing { option_type, .. }
+            | Payoff::AveragePrice { option_type, .. }
+            | Payoff::AverageStrike { option_type, .. }
+            | Payoff::FloatingLookback { option_type }
+            | Payoff::FixedLookback { option_type, .. }
+            | Payoff::Ladder { option_type, .. } => *option_type = option_type.complement(),
+            Payoff::Cliquet { .. }
+            | Payoff::VarianceSwap { .. }
+            | Payoff::VolatilitySwap { .. } => {}
+        }
+        payoff
+    }
+
+    /// Returns the canonical form of the payoff: explicit fixing days and ladder rungs sorted
+    /// and de-duplicated, and a strike of negative zero replaced by zero
+    pub(crate) fn canonical(&self) -> Payoff {
+        let mut payoff = self.with_strike_price(self.strike_price().unwrap_or_default() + 0.0);
+        match &mut payoff {
+            Payoff::AveragePrice { schedule, .. }
+            | Payoff::AverageStrike { schedule, .. }
+            | Payoff::Cliquet { schedule, .. }
+            | Payoff::VarianceSwap { schedule, .. }
+            | Payoff::VolatilitySwap { schedule, .. } => {
+                if let FixingSchedule::Dates(days) = schedule {
+                    days.sort_unstable();
+                    days.dedup();
+                }
+            }
+            Payoff::Ladder { rungs, .. } => {
+                rungs.sort_by(f64::total_cmp);
+                rungs.dedup();
+            }
+            _ => {}
+        }
+        payoff
+    }
+
+    /// Strike of the vanilla option used as control variate: the payoff's strike, or the
+    /// given spot price (at-the-money) for payoffs without a fixed strike
+    pub(crate) fn control_strike(&self, spot_price: f64) -> f64 {
+        self.strike_price().unwrap_or(spot_price)
+    }
+
+    /// Evaluates the (undiscounted) payoff on the observables of a simulated path
+    pub(crate) fn evaluate(&self, path: &PathObservables) -> f64 {
+        let PathObservables {
+            initial_price,
+            final_price,
+            running_max,
+            running_min,
+            fixings,
+        } = *path;
+        match self {
+            Payoff::Vanilla {
+                strike_price,
+                option_type,
+            } => intrinsic_value(final_price, *strike_price, *option_type),
+            Payoff::CashOrNothing {
+                strike_price,
+                option_type,
+                amount,
+            } => {
+                if is_in_the_money(final_price, *strike_price, *option_type) {
+                    *amount
+                } else {
+                    0.0
+                }
+            }
+            Payoff::AssetOrNothing {
+                strike_price,
+                option_type,
+            } => {
+                if is_in_the_money(final_price, *strike_price, *option_type) {
+                    final_price
+                } else {
+                    0.0
+                }
+            }
+            Payoff::AveragePrice {
+                strike_price,
+                option_type,
+                averaging,
+                ..
+            } => intrinsic_value(average(fixings, *averaging), *strike_price, *option_type),
+            Payoff::AverageStrike {
+                option_type,
+                averaging,
+                ..
+            } => intrinsic_value(final_price, average(fixings, *averaging), *option_type),
+            Payoff::FloatingLookback { option_type } => match option_type {
+                OptionType::Call => final_price - running_min,
+                OptionType::Put => running_max - final_price,
+            },
+            Payoff::FixedLookback {
+                strike_price,
+                option_type,
+            } => {
+                let extreme = match option_type {
+                    OptionType::Call => running_max,
+                    OptionType::Put => running_min,
+                };
+                intrinsic_value(extreme, *strike_price, *option_type)
+            }
+            Payoff::Ladder {
+                strike_price,
+                option_type,
+                rungs,
+            } => {
+                let terminal_value = intrinsic_value(final_price, *strike_price, *option_type);
+                let locked_in_value = match option_type {
+                    OptionType::Call => rungs
+                        .iter()
+                        .filter(|&&rung| running_max >= rung)
+                        .fold(f64::NEG_INFINITY, |best, &rung| best.max(rung)),
+                    OptionType::Put => rungs
+                        .iter()
+                        .filter(|&&rung| running_min <= rung)
+                        .fold(f64::INFINITY, |best, &rung| best.min(rung)),
+                };
+                if locked_in_value.is_finite() {
+                    terminal_value.max(intrinsic_value(
+                        locked_in_value,
+                        *strike_price,
+                        *option_type,
+                    ))
+                } else {
+                    terminal_value
+                }
+            }
+            Payoff::Cliquet {
+                notional,
+                local_floor,
+                local_cap,
+                global_floor,
+                global_cap,
+                ..
+            } => {
+                let mut period_start = initial_price;
+                let mut summed_return = 0.0;
+                for &fixing in fixings {
+                    summed_return += bounded(fixing / period_start - 1.0, *local_floor, *local_cap);
+                    period_start = fixing;
+                }
+                notional * bounded(summed_return, *global_floor, *global_cap)
+            }
+            Payoff::VarianceSwap {
+                notional,
+                strike_volatility,
+                observations_per_year,
+                ..
+            } => {
+                let variance = realized_variance(initial_price, fixings, *observations_per_year);
+                notional * (variance - strike_volatility * strike_volatility)
+            }
+            Payoff::VolatilitySwap {
+                notional,
+                strike_volatility,
+                observations_per_year,
+                ..
+            } => {
+                let variance = realized_variance(initial_price, fixings, *observations_per_year);
+                notional * (variance.sqrt() - strike_volatility)
+            }
+        }
+    }
+}
+
+/// Returns the annualized realized variance of the log returns between consecutive fixings,
+/// the first starting at the initial price
+fn realized_variance(initial_price: f64, fixings: &[f64], observations_per_year: f64) -> f64 {
+    let mut previous = initial_price;
+    let mut sum_of_squares = 0.0;
+    for &fixing in fixings {
+        let log_return = math::ln(fixing / previous);
+        sum_of_squares += log_return * log_return;
+        previous = fixing;
+    }
+    observations_per_year * sum_of_squares / fixings.len() as f64
+}
+
+/// Bounds a value by an optional floor and an optional cap
+pub(crate) fn bounded(value: f64, floor: Option<f64>, cap: Option<f64>) -> f64 {
+    let value = floor.map_or(value, |floor| value.max(floor));
+    cap.map_or(value, |cap| value.min(cap))
+}
+
+/// Returns `true` if a digital option with the given strike pays at the given price
+fn is_in_the_money(price: f64, strike_price: f64, option_type: OptionType) -> bool {
+    match option_type {
+        OptionType::Call => price > strike_price,
+        OptionType::Put => price < strike_price,
+    }
+}
+
+/// Averages the fixings with the given method
+fn average(fixings: &[f64], averaging: Averaging) -> f64 {
+    let count = fixings.len() as f64;
+    match averaging {
+        Averaging::Arithmetic => fixings.iter().sum::<f64>() / count,
+        Averaging::Geometric => math::exp(fixings.iter().map(|&fixing| math::ln(fixing)).sum::<f64>() / count),
+    }
+}
@@ -0,0 +1,640 @@
+use nalgebra::DMatrix;
+
+use crate::barrier::{Barrier, BarrierCorrection, BarrierDirection, RebateTiming};
+use crate::config::SimulationConfig;
+use crate::engine::{self, PathEngine, PathState};
+use crate::error::McError;
+use crate::math;
+use crate::payoff::PathObservables;
+use crate::product::Product;
+use crate::rates::RateCurve;
+use crate::result::PricingResult;
+use crate::statistics::{ChunkedStatistics, CorrelationStatistics};
+use crate::underlying::Underlying;
+use crate::validation;
+use crate::{
+    apply_barrier, attach_diagnostics, bridge_crossing_probability, control_expectation,
+    effective_barrier_level, intrinsic_value, is_barrier_hit, is_log_barrier_hit, log_reference,
+    with_start_values, BGK_BARRIER_SHIFT,
+};
+
+/// Result of pricing a portfolio of products on the same paths
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PortfolioResult {
+    /// Price of each product with its standard error, or the error the product was rejected
+    /// with, in the order of the products
+    pub results: Vec<Result<PricingResult, McError>>,
+    /// Correlation matrix of the discounted payoffs of the products across the paths, i.e.
+    /// of their P&L at expiry. Rejected products and products whose payoff does not vary
+    /// have correlation 0.0 with all others.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialization::matrix_rows"))]
+    pub payoff_correlation: DMatrix<f64>,
+}
+
+impl PortfolioResult {
+    /// Returns the value of positions in the products, long and short positions netted
+    ///
+    /// The standard error of the total accounts for the correlation of the payoffs across
+    /// the shared paths, so offsetting positions, e.g. a hedge, net their Monte Carlo errors
+    /// too.
+    ///
+    /// # Arguments
+    /// * `quantities` - Number of units of each product held, negative for short positions,
+    ///   in the order of the products
+    ///
+    /// # Returns
+    /// The total value and its standard error, without bounds or warnings
+    ///
+    /// # Errors
+    /// Returns the error of the first rejected product held in a nonzero quantity.
+    ///
+    /// # Panics
+    /// Panics if there is not one quantity per product.
+    pub fn aggregate(&self, quantities: &[f64]) -> Result<PricingResult, McError> {
+        assert_eq!(
+            quantities.len(),
+            self.results.len(),
+            "Expected one quantity per product"
+        );
+        let mut positions = Vec::with_capacity(quantities.len());
+        for (index, (&quantity, result)) in quantities.iter().zip(&self.results).enumerate() {
+            if quantity != 0.0 {
+                positions.push((index, quantity, result.as_ref().map_err(Clone::clone)?));
+            }
+        }
+        let price = positions
+            .iter()
+            .map(|(_, quantity, result)| quantity * result.price)
+            .sum();
+        let variance: f64 = positions
+            .iter()
+            .flat_map(|&(i, quantity_i, result_i)| {
+                positions.iter().map(move |&(j, quantity_j, result_j)| {
+                    quantity_i
+                        * quantity_j
+                        * self.payoff_correlation[(i, j)]
+                        * result_i.std_error
+                        * result_j.std_error
+                })
+            })
+            .sum();
+        let num_paths = positions
+            .iter()
+            .map(|(_, _, result)| result.num_paths)
+            .max()
+            .unwrap_or(0);
+        Ok(PricingResult::new(price, variance.max(0.0).sqrt(), num_paths))
+    }
+}
+
+/// Barrier of a product with the levels and schedules its monitoring needs
+struct MonitoredBarrier<'a> {
+    barrier: &'a Barrier,
+    /// Absolute barrier level
+    level: f64,
+    log_level: f64,
+    /// Monitoring correction; only barriers observed every day are corrected
+    correction: BarrierCorrection,
+    /// Growth factor from the end of each step to expiry of a rebate paid at the hit
+    rebate_growth: Vec<f64>,
+    /// Steps on which the barrier is observed
+    is_monitoring_step: Vec<bool>,
+}
+
+/// Barrier state of a product on one path, as the barrier fields of `PathState`
+#[derive(Debug, Clone, Copy)]
+struct BarrierState {
+    hit: bool,
+    survival: f64,
+    rebate_weight: f64,
+}
+
+impl BarrierState {
+    const INITIAL: Self = Self {
+        hit: false,
+        survival: 1.0,
+        rebate_weight: 0.0,
+    };
+}
+
+/// Prices a portfolio of products on the first underlying by simulating the paths once and
+/// evaluating every payoff on each path
+///
+/// Each product is priced like `price_payoff` would price it with the same seed, and the
+/// prices are computed on common random numbers. Pricing many strikes, barriers or
+/// averaging schedules on the same market thus costs one simulation instead of one per
+/// product. The paths take one step per day if any product needs daily observations (a
+/// barrier or a path-dependent payoff) or the model is not Black-Scholes, and a single step
+/// otherwise, so vanilla products priced together with path-dependent ones are priced on the
+/// daily paths. Products maturing on different days are priced by
+/// `price_portfolio_with_maturities`.
+///
+/// Beside the prices, the result holds the correlation of the discounted payoffs of the
+/// products across the simulated paths. Samples that the non-finite policy drops for any
+/// product do not enter the correlation.
+///
+/// An invalid product (e.g. a negative strike, a barrier on a missing underlying or an
+/// invalid fixing schedule) does not abort the run: it is rejected with its error in the
+/// results, and the other products are priced as if it was not in the portfolio. So is a
+/// product whose non-finite values the policy turns into an error.
+///
+/// # Arguments
+/// * `underlyings` - List of underlying assets, the products are written on the first one
+/// * `correlation_matrix` - Correlation matrix (n x n) where n is the number of underlyings.
+///   Must be symmetric, positive semi-definite, with 1.0 on the diagonal.
+/// * `time_horizon_days` - Time to expiration of all products in days
+/// * `products` - Products of the portfolio
+/// * `risk_free_rate` - Risk-free rate curve or flat annual rate (as a decimal, e.g., 0.05 for 5%)
+/// * `config` - Number of paths and variance reduction settings. The error tolerance, the
+///   path observer and the sanity checks are not supported and ignored; quasi-random
+///   sampling runs once instead of in replications.
+///
+/// # Returns
+/// The price or the error of each product and the correlation matrix of their payoffs
+///
+/// # Errors
+/// Returns an error for invalid market data or path counts (see `price_option`).
+pub fn price_portfolio(
+    underlyings: &[Underlying],
+    correlation_matrix: &DMatrix<f64>,
+    time_horizon_days: u32,
+    products: &[Product],
+    risk_free_rate: impl Into<RateCurve>,
+    config: &SimulationConfig,
+) -> Result<PortfolioResult, McError> {
+    price_portfolio_with_maturities(
+        underlyings,
+        correlation_matrix,
+        products,
+        &vec![time_horizon_days; products.len()],
+        risk_free_rate,
+        config,
+    )
+}
+
+/// Prices a portfolio of products maturing on different days by simulating the paths once,
+/// up to the last maturity, and evaluating every payoff on each path at its own expiry
+///
+/// Each product is priced like `price_portfolio` prices it with its maturity as the time
+/// horizon: its fixings, barrier monitoring and rebates end at its expiry, and its payoff is
+/// discounted from its expiry. The paths take one step per day if the products mature on
+/// different days, so products maturing on the same day as all others are priced as by
+/// `price_portfolio`.
+///
+/// # Arguments
+/// * `underlyings` - List of underlying assets, the products are written on the first one
+/// * `correlation_matrix` - Correlation matrix (n x n) where n is the number of underlyings.
+///   Must be symmetric, positive semi-definite, with 1.0 on the diagonal.
+/// * `products` - Products of the portfolio
+/// * `maturity_days` - Time to expiration of each product in days, in the order of the
+///   products
+/// * `risk_free_rate` - Risk-free rate curve or flat annual rate (as a decimal, e.g., 0.05 for 5%)
+/// * `config` - Number of paths and variance reduction settings (see `price_portfolio`)
+///
+/// # Returns
+/// The price or the error of each product and the correlation matrix of their payoffs
+///
+/// # Errors
+/// Returns an error for invalid market data or path counts (see `price_option`).
+///
+/// # Panics
+/// Panics if there is not one maturity per product.
+pub fn price_portfolio_with_maturities(
+    underlyings: &[Underlying],
+    correlation_matrix: &DMatrix<f64>,
+    products: &[Product],
+    maturity_days: &[u32],
+    risk_free_rate: impl Into<RateCurve>,
+    config: &SimulationConfig,
+) -> Result<PortfolioResult, McError> {
+    assert_eq!(
+        products.len(),
+        maturity_days.len(),
+        "Portfolio needs one maturity per product"
+    );
+    let underlyings = &*with_start_values(underlyings, config)?;
+    let rate_curve = risk_free_rate.into();
+    let time_horizon_days = maturity_days.iter().copied().max().unwrap_or(0);
+    let time_to_expiration = config.day_count.year_fraction(time_horizon_days);
+    let discount_factors: Vec<f64> = maturity_days
+        .iter()
+        .map(|&days| rate_curve.discount_factor(config.day_count.year_fraction(days)))
+        .collect();
+
+    // Fixing days of each product, and all of them as recorded on the paths. Invalid
+    // products are rejected and neither observed nor priced.
+    let mut errors = Vec::with_capacity(products.len());
+    let mut product_fixing_days = Vec::with_capacity(products.len());
+    for (product, &maturity) in products.iter().zip(maturity_days) {
+        match validate_product(product, underlyings.len(), maturity) {
+            Ok(days) => {
+                product_fixing_days.push(days);
+                errors.push(None);
+            }
+            Err(error) => {
+                product_fixing_days.push(Vec::new());
+                errors.push(Some(error));
+            }
+        }
+    }
+    let mut fixing_days: Vec<u32> = product_fixing_days.iter().flatten().copied().collect();
+    fixing_days.sort_unstable();
+    fixing_days.dedup();
+    // Positions of the fixings of each product among the recorded ones
+    let fixing_indices: Vec<Vec<usize>> = product_fixing_days
+        .iter()
+        .map(|days| {
+            days.iter()
+                .map(|day| fixing_days.binary_search(day).expect("recorded fixing day"))
+                .collect()
+        })
+        .collect();
+
+    let needs_daily_steps = products
+        .iter()
+        .zip(&errors)
+        .filter(|(_, error)| error.is_none())
+        .any(|(product, _)| product.barrier.is_some() || product.payoff.is_path_dependent());
+    let has_single_maturity = maturity_days
+        .iter()
+        .all(|&maturity| maturity == time_horizon_days);
+    let num_steps =
+        if needs_daily_steps || !has_single_maturity || !config.model.has_black_scholes_marginals()
+        {
+            time_horizon_days as usize
+        } else {
+            1
+        };
+    let engine = PathEngine::new(
+        underlyings,
+        correlation_matrix,
+        &rate_curve,
+        time_to_expiration,
+        num_steps,
+        config,
+    )?;
+    let mut generator = engine.shock_generator(config);
+    // Step on which each product expires, and the products expiring on each step
+    let expiry_steps: Vec<usize> = maturity_days
+        .iter()
+        .map(|&maturity| maturity as usize * num_steps / (time_horizon_days as usize).max(1))
+        .collect();
+    let mut expiring_products = vec![Vec::new(); num_steps + 1];
+    for (index, &step) in expiry_steps.iter().enumerate() {
+        if errors[index].is_none() {
+            expiring_products[step].push(index);
+        }
+    }
+
+    let barriers: Vec<Option<MonitoredBarrier>> = products
+        .iter()
+        .enumerate()
+        .map(|(index, product)| {
+            let barrier = product.barrier.as_ref().filter(|_| errors[index].is_none());
+            barrier.map(|barrier| {
+                let level = effective_barrier_level(barrier, &engine.initial_prices);
+                let rebate_growth = match barrier.rebate {
+                    Some(rebate) if rebate.timing == RebateTiming::AtHit => (1..=num_steps)
+                        .map(|step| {
+                            rate_curve.discount_factor(
+                                step as f64 * time_to_expiration / num_steps as f64,
+                            ) / discount_factors[index]
+                        })
+                        .collect(),
+                    _ => vec![1.0; num_steps],
+                };
+                // The barrier is no longer observed after the product expired
+                MonitoredBarrier {
+                    barrier,
+                    level,
+                    log_level: math::ln(level),
+                    correction: barrier.correction(config.barrier_correction),
+                    rebate_growth,
+                    is_monitoring_step: (1..=num_steps as u32)
+                        .map(|day| {
+                            day as usize <= expiry_steps[index]
+                                && barrier.monitoring.is_monitored(day, maturity_days[index])
+                        })
+                        .collect(),
+                }
+            })
+        })
+        .collect();
+    // Step variance of the barrier reference for the monitoring corrections
+    let barrier_step_variance = |barrier: &Barrier, step: usize| {
+        barrier
+            .underlying_indices
+            .iter()
+            .map(|&i| engine.step_variance(step, i))
+            .sum::<f64>()
+            / barrier.underlying_indices.len() as f64
+    };
+
+    let shock_signs = engine::shock_signs(config.antithetic);
+    let num_samples = config.num_paths.div_ceil(shock_signs.len() as u64);
+    let mut paths: Vec<PathState> = shock_signs
+        .iter()
+        .map(|_| PathState::new(&engine, fixing_days.len()))
+        .collect();
+    let mut barrier_states = vec![vec![BarrierState::INITIAL; products.len()]; paths.len()];
+    let mut previous_log_references = vec![None; products.len()];
+    let mut shocks = engine.new_shocks();
+    let mut product_fixings = Vec::with_capacity(fixing_days.len());
+
+    let mut statistics = vec![ChunkedStatistics::default(); products.len()];
+    let mut correlation = CorrelationStatistics::new(products.len());
+    let mut non_finite_paths = vec![0; products.len()];
+    let mut payoff_sums = vec![0.0; products.len()];
+    let mut control_sums = vec![0.0; products.len()];
+    let mut is_dropped = vec![false; products.len()];
+    let mut samples = vec![0.0; products.len()];
+    let control_strikes: Vec<f64> = products
+        .iter()
+        .map(|product| product.payoff.control_strike(engine.initial_prices[0]))
+        .collect();
+
+    for _ in 0..num_samples {
+        for (path, states) in paths.iter_mut().zip(&mut barrier_states) {
+            path.reset(&engine);
+            states.fill(BarrierState::INITIAL);
+        }
+        generator.start_path();
+
+        // Simulate the paths step by step, tracking the barrier of every product, and
+        // evaluate the products at their expiry (step 0 for products expiring today)
+        payoff_sums.fill(0.0);
+        control_sums.fill(0.0);
+        is_dropped.fill(false);
+        let mut next_fixing = 0;
+        for (step, expiring) in expiring_products.iter().enumerate() {
+            if step > 0 {
+                engine.draw_shocks(&mut generator, &mut shocks);
+                let is_fixing_day =
+                    next_fixing < fixing_days.len() && fixing_days[next_fixing] as usize == step;
+
+                let paths = shock_signs.iter().zip(&mut paths).zip(&mut barrier_states);
+                for ((&sign, path), states) in paths {
+                    for ((previous, barrier), state) in previous_log_references
+                        .iter_mut()
+                        .zip(&barriers)
+                        .zip(&*states)
+                    {
+                        *previous = match barrier {
+                            Some(barrier)
+                                if barrier.correction == BarrierCorrection::BrownianBridge
+                                    && !state.hit =>
+                            {
+                                Some(log_reference(barrier.barrier, &path.log_prices))
+                            }
+                            _ => None,
+                        };
+                    }
+                    path.advance(&engine, step, &shocks, sign);
+                    // In log space, prices are only needed for the payoffs at expiry
+                    if !config.log_space || !expiring.is_empty() {
+                        path.update_prices();
+                    }
+
+                    let barriers = barriers.iter().zip(states.iter_mut());
+                    for ((barrier, state), previous) in barriers.zip(&previous_log_references) {
+                        let Some(barrier) = barrier else { continue };
+                        if !barrier.is_monitoring_step[step - 1] {
+                            continue;
+                        }
+                        let is_hit = match barrier.correction {
+                            BarrierCorrection::ShiftedBarrier => {
+                                // Shift the barrier towards the spot, so the discrete checks
+                                // catch the crossings between the steps
+                                let shift = BGK_BARRIER_SHIFT
+                                    * barrier_step_variance(barrier.barrier, step).sqrt();
+                                let shifted_level = match barrier.barrier.direction {
+                                    BarrierDirection::Up => barrier.log_level - shift,
+                                    BarrierDirection::Down => barrier.log_level + shift,
+                                };
+                                is_log_barrier_hit(barrier.barrier, shifted_level, &path.log_prices)
+                            }
+                            _ if config.log_space => is_log_barrier_hit(
+                                barrier.barrier,
+                                barrier.log_level,
+                                &path.log_prices,
+                            ),
+                            _ => is_barrier_hit(barrier.barrier, barrier.level, &path.prices),
+                        };
+                        if is_hit && !state.hit {
+                            // The paths that survived the crossings between steps hit now
+                            state.hit = true;
+                            state.rebate_weight += state.survival * barrier.rebate_growth[step - 1];
+                        } else if let Some(previous) = *previous {
+                            let crossing_probability = bridge_crossing_probability(
+                                previous,
+                                log_reference(barrier.barrier, &path.log_prices),
+                                barrier.log_level,
+                                barrier_step_variance(barrier.barrier, step),
+                            );
+                            state.rebate_weight += state.survival
+                                * crossing_probability
+                                * barrier.rebate_growth[step - 1];
+                            state.survival *= 1.0 - crossing_probability;
+                        }
+                    }
+
+                    if is_fixing_day {
+                        path.fixings.push(math::exp(path.log_prices[0]));
+                    }
+                }
+
+                if is_fixing_day {
+                    next_fixing += 1;
+                }
+            }
+
+            // Evaluate the products expiring on this step on the paths of the sample
+            for (path, states) in paths.iter().zip(&barrier_states) {
+                let observables = path.observables();
+                for &index in expiring {
+                    let product = &products[index];
+                    if errors[index].is_some() {
+                        continue;
+                    }
+                    product_fixings.clear();
+                    product_fixings.extend(fixing_indices[index].iter().map(|&i| path.fixings[i]));
+                    let intrinsic_payoff = product.payoff.evaluate(&PathObservables {
+                        fixings: &product_fixings,
+                        ..observables
+                    });
+                    let value = match &barriers[index] {
+                        Some(barrier) => {
+                            let state = states[index];
+                            let hit_probability =
+                                if state.hit { 1.0 } else { 1.0 - state.survival };
+                            apply_barrier(
+                                barrier.barrier,
+                                intrinsic_payoff,
+                                hit_probability,
+                                state.rebate_weight,
+                            )
+                        }
+                        None => intrinsic_payoff,
+                    };
+                    // A non-finite price on the path stands in for the payoff, which would
+                    // clip it
+                    let non_finite_price = observables.non_finite_price();
+                    let value = non_finite_price.unwrap_or(value);
+                    let control = non_finite_price.unwrap_or_else(|| {
+                        intrinsic_value(
+                            observables.final_price,
+                            control_strikes[index],
+                            product.payoff.option_type(),
+                        )
+                    });
+
+                    // Non-finite values from extreme parameters are treated according to the
+                    // policy
+                    if !value.is_finite() || !control.is_finite() {
+                        non_finite_paths[index] += 1;
+                    }
+                    let policy = config.non_finite_policy;
+                    match (policy.apply(value), policy.apply(control)) {
+                        (Ok(Some(value)), Ok(Some(control))) => {
+                            payoff_sums[index] += value;
+                            control_sums[index] += control;
+                        }
+                        (Err(error), _) | (_, Err(error)) => {
+                            errors[index] = Some(error);
+                            is_dropped[index] = true;
+                        }
+                        _ => is_dropped[index] = true,
+                    }
+                }
+            }
+        }
+
+        // Discount to present value
+        let path_count = paths.len() as f64;
+        for index in 0..products.len() {
+            samples[index] = payoff_sums[index] / path_count * discount_factors[index];
+            if !is_dropped[index] {
+                statistics[index].add(
+                    samples[index],
+                    control_sums[index] / path_count * discount_factors[index],
+                );
+            }
+        }
+        if !is_dropped.contains(&true) {
+            correlation.add(&samples);
+        }
+    }
+
+    let num_paths = num_samples * shock_signs.len() as u64;
+    let mut results = Vec::with_capacity(products.len());
+    for ((((product, statistics), non_finite_paths), error), &maturity) in products
+        .iter()
+        .zip(statistics)
+        .zip(non_finite_paths)
+        .zip(&errors)
+        .zip(maturity_days)
+    {
+        if let Some(error) = error {
+            results.push(Err(error.clone()));
+            continue;
+        }
+        let payoff = &product.payoff;
+        let mut result = PricingResult::from_statistics(
+            &statistics.finish(),
+            num_paths,
+            control_expectation(underlyings, maturity, payoff, &rate_curve, config),
+        );
+        result.record_non_finite_paths(non_finite_paths);
+        result.record_correlation_repair(engine.correlation.adjustment());
+        result.record_dimension_budget(engine.dimension_budget(config.sampling));
+        attach_diagnostics(
+            &mut result,
+            underlyings,
+            maturity,
+            payoff,
+            &rate_curve,
+            product.barrier.as_ref(),
+            config,
+        );
+        results.push(Ok(result));
+    }
+    let mut payoff_correlation = correlation.finish();
+    for (index, _) in errors
+        .iter()
+        .enumerate()
+        .filter(|(_, error)| error.is_some())
+    {
+        payoff_correlation.row_mut(index).fill(0.0);
+        payoff_correlation.column_mut(index).fill(0.0);
+        payoff_correlation[(index, index)] = 1.0;
+    }
+    Ok(PortfolioResult {
+        results,
+        payoff_correlation,
+    })
+}
+
+/// Prices a product at several maturities, e.g. for the term structure of its prices, on
+/// paths simulated once up to the last maturity
+///
+/// Each maturity is priced on the initial segments of the same paths (see
+/// `price_portfolio_with_maturities`), so the whole term structure costs about one
+/// simulation to the last maturity instead of one per maturity, and the prices are computed
+/// on common random numbers. Fixing schedules and barrier monitoring follow each maturity.
+///
+/// # Arguments
+/// * `underlyings` - List of underlying assets, the product is written on the first one
+/// * `correlation_matrix` - Correlation matrix (n x n) where n is the number of underlyings.
+///   Must be symmetric, positive semi-definite, with 1.0 on the diagonal.
+/// * `product` - Product to price
+/// * `maturity_days` - Times to expiration in days
+/// * `risk_free_rate` - Risk-free rate curve or flat annual rate (as a decimal, e.g., 0.05 for 5%)
+/// * `config` - Number of paths and variance reduction settings (see `price_portfolio`)
+///
+/// # Returns
+/// The price at each maturity, in the order of the maturities
+///
+/// # Errors
+/// Returns the error of the first maturity the product is invalid at, e.g. for a fixing
+/// schedule beyond it, or an error for invalid market data or path counts (see
+/// `price_option`).
+pub fn price_term_structure(
+    underlyings: &[Underlying],
+    correlation_matrix: &DMatrix<f64>,
+    product: &Product,
+    maturity_days: &[u32],
+    risk_free_rate: impl Into<RateCurve>,
+    config: &SimulationConfig,
+) -> Result<Vec<PricingResult>, McError> {
+    let products = vec![product.clone(); maturity_days.len()];
+    price_portfolio_with_maturities(
+        underlyings,
+        correlation_matrix,
+        &products,
+        maturity_days,
+        risk_free_rate,
+        config,
+    )?
+    .results
+    .into_iter()
+    .collect()
+}
+
+/// Validates a product of a portfolio, returning its fixing days
+fn validate_product(
+    product: &Product,
+    num_underlyings: usize,
+    time_horizon_days: u32,
+) -> Result<Vec<u32>, McError> {
+    validation::validate_payoff(&product.payoff)?;
+    if let Some(barrier) = &product.barrier {
+        validation::validate_barrier(barrier, num_underlyings)?;
+    }
+    Ok(product
+        .payoff
+        .schedule()
+        .map(|schedule| schedule.fixing_days(time_horizon_days))
+        .transpose()?
+        .unwrap_or_default())
+}
@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+use crate::aggregation::{aggregate_results, GroupTotal, GroupingLevel};
+use crate::batch::{total_value, BatchTrade};
+use crate::config::SimulationConfig;
+use crate::error::McError;
+use crate::market::MarketSnapshot;
+use crate::price_payoff;
+use crate::result::PricingResult;
+
+/// Cached valuation of a book of trades, repricing only the trades whose terms or market
+/// inputs changed since the last update
+///
+/// Trades are identified by their names across updates. A trade is repriced if it is new,
+/// its product or maturity changed, the rate curve changed, or one of the underlyings it
+/// depends on (the first one and those of its barrier) or a correlation between them
+/// changed. If the number of underlyings changed, all trades are repriced. A changed
+/// quantity only rescales the cached price, and the priorities of the trades are ignored.
+#[derive(Debug, Clone)]
+pub struct PortfolioState {
+    config: SimulationConfig,
+    market: Option<MarketSnapshot>,
+    cache: HashMap<String, (BatchTrade, Result<PricingResult, McError>)>,
+}
+
+/// Valuation of a book after an update of a `PortfolioState`
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PortfolioUpdate {
+    /// Value of each trade in the order of the trades (its price scaled by its quantity), or
+    /// the reason it was rejected
+    pub results: Vec<Result<PricingResult, McError>>,
+    /// Names of the trades repriced by the update; the others were taken from the cache
+    pub repriced: Vec<String>,
+}
+
+impl PortfolioUpdate {
+    /// Returns the total value of the book, long and short positions netted, with the sum of
+    /// the standard errors of the trades
+    ///
+    /// # Errors
+    /// Returns the error of the first rejected trade.
+    pub fn total(&self) -> Result<PricingResult, McError> {
+        total_value(&self.results)
+    }
+
+    /// Returns the totals of the groups of trades along the given hierarchy (see
+    /// `aggregate_results`)
+    ///
+    /// # Errors
+    /// Returns `McError::InvalidProduct` if `trades` are not the trades of the update.
+    pub fn aggregate(
+        &self,
+        trades: &[BatchTrade],
+        levels: &[GroupingLevel],
+    ) -> Result<Vec<GroupTotal>, McError> {
+        aggregate_results(trades, &self.results, levels)
+    }
+}
+
+impl PortfolioState {
+    /// Creates a state without cached prices, pricing with the given configuration
+    ///
+    /// Set a seed in the configuration to make repricings of unchanged inputs reproducible.
+    pub fn new(config: &SimulationConfig) -> Self {
+        Self {
+            config: config.clone(),
+            market: None,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Returns the market of the last update, if any
+    pub fn market(&self) -> Option<&MarketSnapshot> {
+        self.market.as_ref()
+    }
+
+    /// Values the book on the market, repricing only the changed trades
+    ///
+    /// Trades that are no longer in the book are dropped from the cache.
+    ///
+    /// # Arguments
+    /// * `market` - Current market
+    /// * `trades` - Current trades of the book, with unique names
+    ///
+    /// # Returns
+    /// The value of each trade, or the reason it was rejected (see `price_payoff`), and the
+    /// names of the repriced trades
+    pub fn update(&mut self, market: &MarketSnapshot, trades: &[BatchTrade]) -> PortfolioUpdate {
+        let mut cache = HashMap::with_capacity(trades.len());
+        let mut results = Vec::with_capacity(trades.len());
+        let mut repriced = Vec::new();
+        for trade in trades {
+            let cached = self
+                .cache
+                .remove(&trade.name)
+                .filter(|(cached, _)| !self.is_stale(cached, trade, market));
+            let result = match cached {
+                Some((_, result)) => result,
+                None => {
+                    repriced.push(trade.name.clone());
+                    price_payoff(
+                        &market.underlyings,
+                        &market.correlation_matrix,
+                        trade.maturity_days,
+                        &trade.product.payoff,
+                        market.risk_free_rate.clone(),
+                        trade.product.barrier.as_ref(),
+                        &self.config,
+                    )
+                }
+            };
+            results.push(result.clone().and_then(|result| trade.value(result)));
+            cache.insert(trade.name.clone(), (trade.clone(), result));
+        }
+        self.cache = cache;
+        self.market = Some(market.clone());
+        PortfolioUpdate { results, repriced }
+    }
+
+    /// Returns `true` if the cached price of a trade is outdated by changes of its terms or
+    /// of the market inputs it depends on
+    fn is_stale(&self, cached: &BatchTrade, trade: &BatchTrade, market: &MarketSnapshot) -> bool {
+        let Some(previous) = &self.market else {
+            return true;
+        };
+        if cached.product != trade.product
+            || cached.maturity_days != trade.maturity_days
+            || previous.risk_free_rate != market.risk_free_rate
+            || previous.underlyings.len() != market.underlyings.len()
+        {
+            return true;
+        }
+        let mut indices = vec![0];
+        if let Some(barrier) = &trade.product.barrier {
+            indices.extend(&barrier.underlying_indices);
+        }
+        // Indices beyond the underlyings make the trade invalid on both markets
+        indices.retain(|&index| index < market.underlyings.len());
+        indices.iter().any(|&i| {
+            previous.underlyings[i] != market.underlyings[i]
+                || indices.iter().any(|&j| {
+                    previous.correlation_matrix.get((i, j)) != market.correlation_matrix.get((i, j))
+                })
+        })
+    }
+}
@@ -0,0 +1,234 @@
+use std::fmt;
+
+use crate::barrier::{Barrier, BarrierMonitoring, BarrierType, KnockType, Rebate, RebateTiming};
+use crate::error::McError;
+use crate::payoff::{FixingSchedule, OptionType, Payoff};
+use crate::{effective_barrier_level, validation};
+
+/// Identifier of the economics of a product, equal for all products with the same canonical
+/// form (see `Product::canonicalize`)
+///
+/// Identifiers are displayed as 16 hexadecimal digits. They are the same in every run and on
+/// every platform, but may change between versions of the crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProductId(pub u64);
+
+impl fmt::Display for ProductId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// Option on the first underlying: a payoff, optionally subject to a barrier
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Product {
+    /// Payoff of the option, e.g. vanilla or Asian
+    pub payoff: Payoff,
+    /// Optional barrier; without one, the payoff is always paid
+    pub barrier: Option<Barrier>,
+}
+
+impl Product {
+    /// Creates an option with the given payoff and no barrier
+    pub fn new(payoff: Payoff) -> Self {
+        Self {
+            payoff,
+            barrier: None,
+        }
+    }
+
+    /// Creates a vanilla Call option without barrier
+    pub fn call(strike_price: f64) -> Self {
+        Self::new(Payoff::Vanilla {
+            strike_price,
+            option_type: OptionType::Call,
+        })
+    }
+
+    /// Creates a vanilla Put option without barrier
+    pub fn put(strike_price: f64) -> Self {
+        Self::new(Payoff::Vanilla {
+            strike_price,
+            option_type: OptionType::Put,
+        })
+    }
+
+    /// Creates a vanilla Call (`is_call` is `true`) or Put option without barrier
+    #[deprecated(note = "use `Product::call` or `Product::put`")]
+    pub fn vanilla(strike_price: f64, is_call: bool) -> Self {
+        Self::new(Payoff::Vanilla {
+            strike_price,
+            option_type: OptionType::from_is_call(is_call),
+        })
+    }
+
+    /// Creates a cash-or-nothing digital paying `amount` at expiry if it expires in the money
+    pub fn cash_or_nothing(strike_price: f64, option_type: OptionType, amount: f64) -> Self {
+        Self::new(Payoff::CashOrNothing {
+            strike_price,
+            option_type,
+            amount,
+        })
+    }
+
+    /// Creates an asset-or-nothing digital paying the terminal price if it expires in the
+    /// money
+    pub fn asset_or_nothing(strike_price: f64, option_type: OptionType) -> Self {
+        Self::new(Payoff::AssetOrNothing {
+            strike_price,
+            option_type,
+        })
+    }
+
+    /// Creates a one-touch paying `amount` if the barrier is hit, on the day of the hit or
+    /// at expiry
+    ///
+    /// The level, direction, underlyings and monitoring are taken from the barrier; its knock
+    /// type and rebate are replaced.
+    pub fn one_touch(barrier: Barrier, amount: f64, timing: RebateTiming) -> Self {
+        // Nothing is paid unless the barrier is hit, which pays the knock-out rebate
+        Self::cash_or_nothing(0.0, OptionType::Call, 0.0).with_barrier(Barrier {
+            knock_type: KnockType::Out,
+            rebate: Some(Rebate::new(amount, timing)),
+            ..barrier
+        })
+    }
+
+    /// Creates a no-touch paying `amount` at expiry if the barrier is never hit
+    ///
+    /// The level, direction, underlyings and monitoring are taken from the barrier; its knock
+    /// type and rebate are replaced.
+    pub fn no_touch(barrier: Barrier, amount: f64) -> Self {
+        // A digital call struck at zero always pays, unless the barrier knocks it out
+        Self::cash_or_nothing(0.0, OptionType::Call, amount).with_barrier(Barrier {
+            knock_type: KnockType::Out,
+            rebate: None,
+            ..barrier
+        })
+    }
+
+    /// Creates a variance swap paying `notional * (σ² - K²)` on the realized variance of the
+    /// returns between the fixings
+    pub fn variance_swap(
+        notional: f64,
+        strike_volatility: f64,
+        observations_per_year: f64,
+        schedule: FixingSchedule,
+    ) -> Self {
+        Self::new(Payoff::VarianceSwap {
+            notional,
+            strike_volatility,
+            observations_per_year,
+            schedule,
+        })
+    }
+
+    /// Creates a volatility swap paying `notional * (σ - K)` on the realized volatility of
+    /// the returns between the fixings
+    pub fn volatility_swap(
+        notional: f64,
+        strike_volatility: f64,
+        observations_per_year: f64,
+        schedule: FixingSchedule,
+    ) -> Self {
+        Self::new(Payoff::VolatilitySwap {
+            notional,
+            strike_volatility,
+            observations_per_year,
+            schedule,
+        })
+    }
+
+    /// Subjects the option to the given barrier
+    pub fn with_barrier(mut self, barrier: Barrier) -> Self {
+        self.barrier = Some(barrier);
+        self
+    }
+
+    /// Returns a stable hash of the terms of the product: equal products have equal
+    /// fingerprints in every run, e.g. to deduplicate the trades of a portfolio. The
+    /// fingerprints may change between versions of the crate.
+    pub fn fingerprint(&self) -> u64 {
+        crate::fingerprint(self)
+    }
+
+    /// Returns the canonical form of the product, equal for all products with the same
+    /// economics on the given spot prices
+    ///
+    /// The canonical form has:
+    /// * an absolute barrier level, converting relative levels with the spot prices;
+    /// * sorted barrier underlyings, de-duplicated for worst-of and best-of barriers, and the
+    ///   worst-of type for a barrier on a single underlying;
+    /// * sorted and de-duplicated observation days, fixing days and ladder rungs;
+    /// * no rebate instead of a rebate of zero, and zero instead of a strike of negative
+    ///   zero.
+    ///
+    /// # Arguments
+    /// * `spot_prices` - Spot prices of the underlyings the product is priced on
+    ///
+    /// # Errors
+    /// Returns an error if the barrier refers to an underlying without spot price or has an
+    /// invalid level (see `price_option`).
+    pub fn canonicalize(&self, spot_prices: &[f64]) -> Result<Self, McError> {
+        let barrier = match &self.barrier {
+            Some(barrier) => {
+                validation::validate_barrier(barrier, spot_prices.len())?;
+                Some(canonical_barrier(barrier, spot_prices))
+            }
+            None => None,
+        };
+        Ok(Self {
+            payoff: self.payoff.canonical(),
+            barrier,
+        })
+    }
+
+    /// Returns the identifier of the canonical form of the product on the given spot prices,
+    /// e.g. to deduplicate trades booked in different systems
+    ///
+    /// # Errors
+    /// Returns an error under the same conditions as `canonicalize`.
+    pub fn id(&self, spot_prices: &[f64]) -> Result<ProductId, McError> {
+        Ok(ProductId(self.canonicalize(spot_prices)?.fingerprint()))
+    }
+}
+
+/// Returns the canonical form of a valid barrier on the given spot prices
+fn canonical_barrier(barrier: &Barrier, spot_prices: &[f64]) -> Barrier {
+    let mut underlying_indices = barrier.underlying_indices.clone();
+    underlying_indices.sort_unstable();
+    let mut barrier_type = barrier.barrier_type;
+    if underlying_indices.first() == underlying_indices.last() {
+        // All types agree on a single underlying, however often it is listed
+        barrier_type = BarrierType::WorstOf;
+    }
+    if matches!(barrier_type, BarrierType::WorstOf | BarrierType::BestOf) {
+        underlying_indices.dedup();
+    }
+    let monitoring = match &barrier.monitoring {
+        BarrierMonitoring::Dates(days) => {
+            let mut days = days.clone();
+            days.sort_unstable();
+            days.dedup();
+            BarrierMonitoring::Dates(days)
+        }
+        monitoring => monitoring.clone(),
+    };
+    Barrier {
+        barrier_level: effective_barrier_level(barrier, spot_prices),
+        barrier_type,
+        relative: false,
+        underlying_indices,
+        monitoring,
+        rebate: barrier.rebate.filter(|rebate| rebate.amount != 0.0),
+        ..barrier.clone()
+    }
+}
+
+impl From<Payoff> for Product {
+    fn from(payoff: Payoff) -> Self {
+        Self::new(payoff)
+    }
+}
@@ -0,0 +1,428 @@
+use nalgebra::DMatrix;
+use numpy::PyReadonlyArray2;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::barrier::{Barrier, BarrierDirection, BarrierType, KnockType};
+use crate::config::SimulationConfig;
+use crate::error::McError;
+use crate::market::MarketSnapshot;
+use crate::payoff::OptionType;
+use crate::price_payoff;
+use crate::product::Product;
+use crate::result::PricingResult;
+use crate::session::{Greeks, PricingSession};
+use crate::underlying::Underlying;
+
+// Thin wrappers of the pricing API for the `mcproton` Python extension module. Enumerations
+// are passed as lower-case strings, e.g. `"call"` or `"worst_of"`, and pricing errors are
+// raised as `ValueError`. The simulations release the GIL, so Python threads may price
+// concurrently.
+
+impl From<McError> for PyErr {
+    fn from(error: McError) -> Self {
+        PyValueError::new_err(error.to_string())
+    }
+}
+
+/// Underlying asset with a spot price and a flat volatility
+#[pyclass(name = "Underlying", module = "mcproton", from_py_object)]
+#[derive(Debug, Clone)]
+pub struct PyUnderlying(pub Underlying);
+
+#[pymethods]
+impl PyUnderlying {
+    #[new]
+    #[pyo3(signature = (name, spot_price, volatility, dividend_yield = 0.0))]
+    fn new(name: String, spot_price: f64, volatility: f64, dividend_yield: f64) -> Self {
+        Self(Underlying {
+            dividend_yield,
+            ..Underlying::new(name, spot_price, volatility)
+        })
+    }
+
+    #[getter]
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    #[getter]
+    fn spot_price(&self) -> f64 {
+        self.0.spot_price
+    }
+
+    #[setter]
+    fn set_spot_price(&mut self, spot_price: f64) {
+        self.0.spot_price = spot_price;
+    }
+
+    #[getter]
+    fn volatility(&self) -> f64 {
+        self.0.volatility
+    }
+
+    #[setter]
+    fn set_volatility(&mut self, volatility: f64) {
+        self.0.volatility = volatility;
+    }
+
+    #[getter]
+    fn dividend_yield(&self) -> f64 {
+        self.0.dividend_yield
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Underlying({:?}, spot_price={}, volatility={})",
+            self.0.name, self.0.spot_price, self.0.volatility
+        )
+    }
+}
+
+/// Barrier on one or several underlyings
+#[pyclass(name = "Barrier", module = "mcproton", from_py_object)]
+#[derive(Debug, Clone)]
+pub struct PyBarrier(pub Barrier);
+
+#[pymethods]
+impl PyBarrier {
+    /// Creates a barrier on the first underlying; `direction` is `"up"` or `"down"` and
+    /// `knock_type` is `"in"` or `"out"`
+    #[staticmethod]
+    #[pyo3(signature = (barrier_level, direction, knock_type, relative = false))]
+    fn single(
+        barrier_level: f64,
+        direction: &str,
+        knock_type: &str,
+        relative: bool,
+    ) -> PyResult<Self> {
+        Ok(Self(Barrier::single(
+            barrier_level,
+            parse_direction(direction)?,
+            parse_knock_type(knock_type)?,
+            relative,
+        )))
+    }
+
+    /// Creates a barrier on several underlyings; `barrier_type` is `"worst_of"`,
+    /// `"best_of"`, `"average"` or `"median"`
+    #[staticmethod]
+    #[pyo3(signature = (barrier_level, direction, knock_type, barrier_type, underlying_indices, relative = true))]
+    fn multi(
+        barrier_level: f64,
+        direction: &str,
+        knock_type: &str,
+        barrier_type: &str,
+        underlying_indices: Vec<usize>,
+        relative: bool,
+    ) -> PyResult<Self> {
+        let barrier_type = match barrier_type {
+            "worst_of" => BarrierType::WorstOf,
+            "best_of" => BarrierType::BestOf,
+            "average" => BarrierType::Average,
+            "median" => BarrierType::Median,
+            _ => return Err(invalid_choice("barrier type", barrier_type)),
+        };
+        Barrier::multi(
+            barrier_level,
+            parse_direction(direction)?,
+            parse_knock_type(knock_type)?,
+            barrier_type,
+            relative,
+            underlying_indices,
+        )
+        .map(Self)
+        .map_err(|error| PyValueError::new_err(error.to_string()))
+    }
+
+    #[getter]
+    fn barrier_level(&self) -> f64 {
+        self.0.barrier_level
+    }
+
+    #[getter]
+    fn underlying_indices(&self) -> Vec<usize> {
+        self.0.underlying_indices.clone()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.0)
+    }
+}
+
+/// Product to price: a payoff with an optional barrier
+#[pyclass(name = "Product", module = "mcproton", from_py_object)]
+#[derive(Debug, Clone)]
+pub struct PyProduct(pub Product);
+
+#[pymethods]
+impl PyProduct {
+    /// Creates a vanilla call option
+    #[staticmethod]
+    fn call(strike_price: f64) -> Self {
+        Self(Product::call(strike_price))
+    }
+
+    /// Creates a vanilla put option
+    #[staticmethod]
+    fn put(strike_price: f64) -> Self {
+        Self(Product::put(strike_price))
+    }
+
+    /// Creates a digital option paying `amount` if it expires in the money; `option_type`
+    /// is `"call"` or `"put"`
+    #[staticmethod]
+    fn cash_or_nothing(strike_price: f64, option_type: &str, amount: f64) -> PyResult<Self> {
+        Ok(Self(Product::cash_or_nothing(
+            strike_price,
+            parse_option_type(option_type)?,
+            amount,
+        )))
+    }
+
+    /// Returns a copy of the product with the barrier attached
+    fn with_barrier(&self, barrier: PyBarrier) -> Self {
+        Self(self.0.clone().with_barrier(barrier.0))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.0)
+    }
+}
+
+/// Number of paths, seed and variance reduction settings of a simulation
+#[pyclass(name = "SimulationConfig", module = "mcproton", from_py_object)]
+#[derive(Debug, Clone)]
+pub struct PySimulationConfig(pub SimulationConfig);
+
+#[pymethods]
+impl PySimulationConfig {
+    #[new]
+    #[pyo3(signature = (num_paths, seed = None, antithetic = false, control_variate = false, validate = false))]
+    fn new(
+        num_paths: u64,
+        seed: Option<u64>,
+        antithetic: bool,
+        control_variate: bool,
+        validate: bool,
+    ) -> Self {
+        Self(SimulationConfig {
+            seed,
+            ..SimulationConfig::new(num_paths)
+                .with_antithetic(antithetic)
+                .with_control_variate(control_variate)
+                .with_validation(validate)
+        })
+    }
+
+    #[getter]
+    fn num_paths(&self) -> u64 {
+        self.0.num_paths
+    }
+
+    #[getter]
+    fn seed(&self) -> Option<u64> {
+        self.0.seed
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "SimulationConfig(num_paths={}, seed={:?})",
+            self.0.num_paths, self.0.seed
+        )
+    }
+}
+
+/// Price estimate with its standard error
+#[pyclass(name = "PricingResult", module = "mcproton", from_py_object)]
+#[derive(Debug, Clone)]
+pub struct PyPricingResult(pub PricingResult);
+
+#[pymethods]
+impl PyPricingResult {
+    #[getter]
+    fn price(&self) -> f64 {
+        self.0.price
+    }
+
+    #[getter]
+    fn std_error(&self) -> f64 {
+        self.0.std_error
+    }
+
+    #[getter]
+    fn num_paths(&self) -> u64 {
+        self.0.num_paths
+    }
+
+    /// Descriptions of the quality concerns detected while pricing
+    #[getter]
+    fn warnings(&self) -> Vec<String> {
+        self.0.warnings.iter().map(ToString::to_string).collect()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "PricingResult(price={}, std_error={})",
+            self.0.price, self.0.std_error
+        )
+    }
+}
+
+/// Price and sensitivities of a product
+#[pyclass(name = "Greeks", module = "mcproton", from_py_object)]
+#[derive(Debug, Clone)]
+pub struct PyGreeks(pub Greeks);
+
+#[pymethods]
+impl PyGreeks {
+    #[getter]
+    fn pricing(&self) -> PyPricingResult {
+        PyPricingResult(self.0.pricing.clone())
+    }
+
+    #[getter]
+    fn delta(&self) -> f64 {
+        self.0.delta
+    }
+
+    #[getter]
+    fn gamma(&self) -> f64 {
+        self.0.gamma
+    }
+
+    #[getter]
+    fn vega(&self) -> f64 {
+        self.0.vega
+    }
+
+    #[getter]
+    fn rho(&self) -> f64 {
+        self.0.rho
+    }
+
+    #[getter]
+    fn fx_delta(&self) -> f64 {
+        self.0.fx_delta
+    }
+
+    #[getter]
+    fn fx_vega(&self) -> f64 {
+        self.0.fx_vega
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Greeks(price={}, delta={}, gamma={}, vega={}, rho={})",
+            self.0.pricing.price, self.0.delta, self.0.gamma, self.0.vega, self.0.rho
+        )
+    }
+}
+
+/// Prices the product by Monte Carlo simulation (see `price_payoff`)
+#[pyfunction]
+#[pyo3(signature = (underlyings, correlation_matrix, time_horizon_days, product, risk_free_rate, config))]
+fn price(
+    py: Python<'_>,
+    underlyings: Vec<PyUnderlying>,
+    correlation_matrix: PyReadonlyArray2<'_, f64>,
+    time_horizon_days: u32,
+    product: PyProduct,
+    risk_free_rate: f64,
+    config: PySimulationConfig,
+) -> PyResult<PyPricingResult> {
+    let market = market(underlyings, &correlation_matrix, risk_free_rate);
+    let result = py.detach(|| {
+        price_payoff(
+            &market.underlyings,
+            &market.correlation_matrix,
+            time_horizon_days,
+            &product.0.payoff,
+            market.risk_free_rate.clone(),
+            product.0.barrier.as_ref(),
+            &config.0,
+        )
+    })?;
+    Ok(PyPricingResult(result))
+}
+
+/// Computes the price and the Greeks of the product (see `PricingSession::greeks`)
+#[pyfunction]
+#[pyo3(signature = (underlyings, correlation_matrix, time_horizon_days, product, risk_free_rate, config))]
+fn greeks(
+    py: Python<'_>,
+    underlyings: Vec<PyUnderlying>,
+    correlation_matrix: PyReadonlyArray2<'_, f64>,
+    time_horizon_days: u32,
+    product: PyProduct,
+    risk_free_rate: f64,
+    config: PySimulationConfig,
+) -> PyResult<PyGreeks> {
+    let market = market(underlyings, &correlation_matrix, risk_free_rate);
+    let greeks = py.detach(|| {
+        PricingSession::new(market, time_horizon_days, &config.0)?.greeks(&product.0)
+    })?;
+    Ok(PyGreeks(greeks))
+}
+
+/// Monte Carlo pricer of options on correlated underlyings
+#[pymodule]
+#[pyo3(name = "mcproton")]
+pub fn python_module(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyUnderlying>()?;
+    module.add_class::<PyBarrier>()?;
+    module.add_class::<PyProduct>()?;
+    module.add_class::<PySimulationConfig>()?;
+    module.add_class::<PyPricingResult>()?;
+    module.add_class::<PyGreeks>()?;
+    module.add_function(wrap_pyfunction!(price, module)?)?;
+    module.add_function(wrap_pyfunction!(greeks, module)?)?;
+    Ok(())
+}
+
+/// Returns the market of the underlyings, copying the NumPy correlation matrix
+fn market(
+    underlyings: Vec<PyUnderlying>,
+    correlation_matrix: &PyReadonlyArray2<'_, f64>,
+    risk_free_rate: f64,
+) -> MarketSnapshot {
+    let correlation_matrix = correlation_matrix.as_array();
+    let (rows, columns) = correlation_matrix.dim();
+    MarketSnapshot::new(
+        underlyings.into_iter().map(|underlying| underlying.0).collect(),
+        DMatrix::from_fn(rows, columns, |i, j| correlation_matrix[[i, j]]),
+        risk_free_rate,
+    )
+}
+
+/// Parses `"call"` or `"put"`
+fn parse_option_type(option_type: &str) -> PyResult<OptionType> {
+    match option_type {
+        "call" => Ok(OptionType::Call),
+        "put" => Ok(OptionType::Put),
+        _ => Err(invalid_choice("option type", option_type)),
+    }
+}
+
+/// Parses `"up"` or `"down"`
+fn parse_direction(direction: &str) -> PyResult<BarrierDirection> {
+    match direction {
+        "up" => Ok(BarrierDirection::Up),
+        "down" => Ok(BarrierDirection::Down),
+        _ => Err(invalid_choice("barrier direction", direction)),
+    }
+}
+
+/// Parses `"in"` or `"out"`
+fn parse_knock_type(knock_type: &str) -> PyResult<KnockType> {
+    match knock_type {
+        "in" => Ok(KnockType::In),
+        "out" => Ok(KnockType::Out),
+        _ => Err(invalid_choice("knock type", knock_type)),
+    }
+}
+
+/// Returns the error of a string that names none of the choices of an enumeration
+fn invalid_choice(kind: &str, value: &str) -> PyErr {
+    PyValueError::new_err(format!("Unknown {}: {:?}", kind, value))
+}
@@ -0,0 +1,331 @@
+use rand::Rng;
+
+use crate::config::Sampling;
+use crate::math;
+
+/// Number of bits of the Sobol points
+const BITS: usize = 32;
+
+/// Primitive polynomials and initial direction numbers of the Sobol dimensions after the
+/// first (Joe and Kuo): (degree, inner coefficients, initial direction numbers)
+const DIRECTION_NUMBERS: [(usize, u32, &[u32]); 20] = [
+    (1, 0, &[1]),
+    (2, 1, &[1, 3]),
+    (3, 1, &[1, 3, 1]),
+    (3, 2, &[1, 1, 1]),
+    (4, 1, &[1, 1, 3, 3]),
+    (4, 4, &[1, 3, 5, 13]),
+    (5, 2, &[1, 1, 5, 5, 17]),
+    (5, 4, &[1, 1, 5, 5, 5]),
+    (5, 7, &[1, 1, 7, 11, 19]),
+    (5, 11, &[1, 1, 5, 1, 1]),
+    (5, 13, &[1, 1, 1, 3, 11]),
+    (5, 14, &[1, 3, 5, 5, 31]),
+    (6, 1, &[1, 3, 3, 9, 7, 49]),
+    (6, 13, &[1, 1, 1, 15, 21, 21]),
+    (6, 16, &[1, 3, 1, 13, 27, 49]),
+    (6, 19, &[1, 1, 1, 15, 7, 5]),
+    (6, 22, &[1, 3, 1, 15, 13, 25]),
+    (6, 25, &[1, 1, 5, 5, 19, 61]),
+    (7, 1, &[1, 3, 7, 11, 23, 15, 103]),
+    (7, 4, &[1, 3, 7, 13, 13, 15, 69]),
+];
+
+/// Number of dimensions the Sobol sequence is available in
+pub(crate) const SOBOL_MAX_DIMENSIONS: usize = DIRECTION_NUMBERS.len() + 1;
+
+/// Number of points the Sobol sequence provides, one per path at most
+pub(crate) const SOBOL_MAX_POINTS: u64 = u32::MAX as u64;
+
+/// Bases of the Halton dimensions: the first primes
+const HALTON_BASES: [u32; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131,
+];
+
+/// Number of dimensions the Halton sequence is available in
+pub(crate) const HALTON_MAX_DIMENSIONS: usize = HALTON_BASES.len();
+
+/// Randomization of the Sobol points
+enum Randomization {
+    /// XOR of every coordinate with a random bit pattern per dimension
+    DigitalShift(Vec<u32>),
+    /// Nested uniform (Owen) scrambling with a random seed per dimension
+    OwenScrambling(Vec<u32>),
+}
+
+/// Sobol low-discrepancy sequence with a random digital shift or Owen scrambling
+///
+/// Points are generated in Gray code order, skipping the origin. Both randomizations keep the
+/// equidistribution of the sequence while making each randomization an unbiased estimator, so
+/// independent randomizations yield an error estimate. The digital shift (an XOR of every
+/// coordinate with a random bit pattern) flips the same bits of all points; Owen scrambling
+/// randomly permutes the digits of each point depending on its leading digits, which also
+/// removes the structure of the shifted points and improves the convergence for smooth
+/// integrands.
+pub(crate) struct Sobol {
+    /// Direction numbers of each dimension
+    directions: Vec<[u32; BITS]>,
+    /// Current point as integers
+    state: Vec<u32>,
+    randomization: Randomization,
+    /// Number of points generated so far
+    index: u32,
+}
+
+impl Sobol {
+    /// Creates a digitally shifted Sobol sequence in the given number of dimensions
+    ///
+    /// # Panics
+    /// Panics if more than `SOBOL_MAX_DIMENSIONS` dimensions are requested.
+    pub fn new<R: Rng + ?Sized>(dimensions: usize, rng: &mut R) -> Self {
+        let mut sobol = Self::unrandomized(dimensions);
+        sobol.randomization =
+            Randomization::DigitalShift((0..dimensions).map(|_| rng.gen()).collect());
+        sobol
+    }
+
+    /// Creates an Owen-scrambled Sobol sequence in the given number of dimensions
+    ///
+    /// # Panics
+    /// Panics if more than `SOBOL_MAX_DIMENSIONS` dimensions are requested.
+    pub fn scrambled<R: Rng + ?Sized>(dimensions: usize, rng: &mut R) -> Self {
+        let mut sobol = Self::unrandomized(dimensions);
+        sobol.randomization =
+            Randomization::OwenScrambling((0..dimensions).map(|_| rng.gen()).collect());
+        sobol
+    }
+
+    fn unrandomized(dimensions: usize) -> Self {
+        assert!(
+            dimensions <= SOBOL_MAX_DIMENSIONS,
+            "Sobol sequence is available in up to {} dimensions",
+            SOBOL_MAX_DIMENSIONS
+        );
+        let mut directions = Vec::with_capacity(dimensions);
+        if dimensions > 0 {
+            // The first dimension is the van der Corput sequence in base 2
+            directions.push(std::array::from_fn(|bit| 1 << (BITS - 1 - bit)));
+        }
+        let sobol_dimensions = DIRECTION_NUMBERS.iter().take(dimensions.saturating_sub(1));
+        for &(degree, coefficients, initial) in sobol_dimensions {
+            let mut numbers = [0u32; BITS];
+            for bit in 0..BITS {
+                numbers[bit] = if bit < degree {
+                    initial[bit] << (BITS - 1 - bit)
+                } else {
+                    let mut number = numbers[bit - degree] ^ (numbers[bit - degree] >> degree);
+                    for term in 1..degree {
+                        if (coefficients >> (degree - 1 - term)) & 1 == 1 {
+                            number ^= numbers[bit - term];
+                        }
+                    }
+                    number
+                };
+            }
+            directions.push(numbers);
+        }
+        Self {
+            directions,
+            state: vec![0; dimensions],
+            randomization: Randomization::DigitalShift(vec![0; dimensions]),
+            index: 0,
+        }
+    }
+
+    /// Returns the number of dimensions of the points
+    pub fn dimensions(&self) -> usize {
+        self.state.len()
+    }
+
+    /// Writes the next point of the sequence as uniforms in (0, 1) into `point`
+    ///
+    /// # Panics
+    /// Panics once 2^32 - 1 points have been generated.
+    pub fn next_point(&mut self, point: &mut [f64]) {
+        assert!(self.index < u32::MAX, "Sobol sequence exhausted");
+        // Gray code order: flip the direction number of the lowest zero bit of the index
+        let bit = self.index.trailing_ones() as usize;
+        self.index += 1;
+        for (dimension, value) in point.iter_mut().enumerate() {
+            self.state[dimension] ^= self.directions[dimension][bit];
+            let randomized = match &self.randomization {
+                Randomization::DigitalShift(shift) => self.state[dimension] ^ shift[dimension],
+                Randomization::OwenScrambling(seeds) => {
+                    owen_scramble(self.state[dimension], seeds[dimension])
+                }
+            };
+            *value = (randomized as f64 + 0.5) / (1u64 << BITS) as f64;
+        }
+    }
+}
+
+/// Scrambles the bits of a point, from the most significant one down, each flipped depending
+/// on the seed and the bits above it (hash-based nested uniform scrambling of Burley, 2020)
+fn owen_scramble(value: u32, seed: u32) -> u32 {
+    // The Laine-Karras permutation scrambles each bit depending on the bits below it, so it is
+    // applied to the reversed bits
+    let mut x = value.reverse_bits().wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50_b47c);
+    x ^= x.wrapping_mul(0xb82f_1e52);
+    x ^= x.wrapping_mul(0xc7af_e638);
+    x ^= x.wrapping_mul(0x8d22_f6e6);
+    x.reverse_bits()
+}
+
+/// Halton low-discrepancy sequence with a random digital shift in the base of each dimension
+///
+/// Coordinate `d` of point `i` is the radical inverse of `i` in the `d`-th prime base, with a
+/// random digit added to each of its digits modulo the base. The shift makes each
+/// randomization an unbiased estimator, like the digital shift of the Sobol sequence.
+pub(crate) struct Halton {
+    /// Random shift of each digit of each dimension
+    shifts: Vec<Vec<u32>>,
+    /// Number of points generated so far
+    index: u64,
+}
+
+impl Halton {
+    /// Creates a digitally shifted Halton sequence in the given number of dimensions
+    ///
+    /// # Panics
+    /// Panics if more than `HALTON_MAX_DIMENSIONS` dimensions are requested.
+    pub fn new<R: Rng + ?Sized>(dimensions: usize, rng: &mut R) -> Self {
+        assert!(
+            dimensions <= HALTON_MAX_DIMENSIONS,
+            "Halton sequence is available in up to {} dimensions",
+            HALTON_MAX_DIMENSIONS
+        );
+        let shifts = HALTON_BASES[..dimensions]
+            .iter()
+            .map(|&base| {
+                (0..Self::num_digits(base))
+                    .map(|_| rng.gen_range(0..base))
+                    .collect()
+            })
+            .collect();
+        Self { shifts, index: 0 }
+    }
+
+    /// Returns the number of digits of the coordinates in the given base: enough to resolve
+    /// the points as finely as the 32 bits of the Sobol points
+    fn num_digits(base: u32) -> usize {
+        (BITS as f64 / math::log2(f64::from(base))).ceil() as usize
+    }
+
+    /// Returns the number of dimensions of the points
+    pub fn dimensions(&self) -> usize {
+        self.shifts.len()
+    }
+
+    /// Writes the next point of the sequence as uniforms in (0, 1) into `point`
+    pub fn next_point(&mut self, point: &mut [f64]) {
+        let index = self.index;
+        self.index += 1;
+        for ((value, shift), &base) in point.iter_mut().zip(&self.shifts).zip(&HALTON_BASES) {
+            let base_f64 = f64::from(base);
+            let mut remaining = index;
+            let mut scale = 1.0;
+            *value = 0.0;
+            for &digit_shift in shift {
+                let digit = (remaining % u64::from(base)) as u32;
+                remaining /= u64::from(base);
+                scale /= base_f64;
+                *value += f64::from((digit + digit_shift) % base) * scale;
+            }
+            // Centre the point in its cell of the finest digit, so it is never 0
+            *value += 0.5 * scale;
+        }
+    }
+}
+
+/// Split of the shocks of each path into leading dimensions driven by the quasi-random
+/// sequence of the sampling and pseudo-random padding for the remaining ones
+///
+/// If a path has more dimensions (shocks per step times steps) than the sequence provides,
+/// the sequence covers as many whole steps as fit, so all shocks of a covered step are
+/// quasi-random and their correlation is not mixed with pseudo-random shocks. If not even
+/// one step fits, it covers the leading shocks of the first step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct DimensionBudget {
+    /// Number of leading dimensions driven by the quasi-random sequence
+    pub quasi_random: usize,
+    /// Number of dimensions of each path
+    pub total: usize,
+}
+
+impl DimensionBudget {
+    /// Budgets the dimensions of paths of `num_steps` steps with `shocks_per_step` shocks
+    pub fn new(sampling: Sampling, shocks_per_step: usize, num_steps: usize) -> Self {
+        let total = shocks_per_step * num_steps;
+        let max_dimensions = match sampling {
+            Sampling::PseudoRandom => 0,
+            Sampling::Sobol | Sampling::ScrambledSobol => SOBOL_MAX_DIMENSIONS,
+            Sampling::Halton => HALTON_MAX_DIMENSIONS,
+        };
+        let quasi_random = if total <= max_dimensions {
+            total
+        } else if max_dimensions >= shocks_per_step {
+            max_dimensions / shocks_per_step * shocks_per_step
+        } else {
+            max_dimensions
+        };
+        Self {
+            quasi_random,
+            total,
+        }
+    }
+
+    /// Returns `true` if quasi-random numbers drive some but not all dimensions
+    pub fn is_padded(&self) -> bool {
+        self.quasi_random > 0 && self.quasi_random < self.total
+    }
+}
+
+/// Quasi-random sequence driving the leading dimensions of each path
+pub(crate) enum QuasiRandom {
+    Sobol(Sobol),
+    Halton(Halton),
+}
+
+impl QuasiRandom {
+    /// Creates the randomized sequence of the sampling in up to the given number of
+    /// dimensions, or `None` for pseudo-random sampling
+    pub fn new<R: Rng + ?Sized>(
+        sampling: Sampling,
+        dimensions: usize,
+        rng: &mut R,
+    ) -> Option<Self> {
+        match sampling {
+            Sampling::PseudoRandom => None,
+            Sampling::Sobol => Some(Self::Sobol(Sobol::new(
+                dimensions.min(SOBOL_MAX_DIMENSIONS),
+                rng,
+            ))),
+            Sampling::ScrambledSobol => Some(Self::Sobol(Sobol::scrambled(
+                dimensions.min(SOBOL_MAX_DIMENSIONS),
+                rng,
+            ))),
+            Sampling::Halton => Some(Self::Halton(Halton::new(
+                dimensions.min(HALTON_MAX_DIMENSIONS),
+                rng,
+            ))),
+        }
+    }
+
+    /// Returns the number of dimensions of the points
+    pub fn dimensions(&self) -> usize {
+        match self {
+            Self::Sobol(sobol) => sobol.dimensions(),
+            Self::Halton(halton) => halton.dimensions(),
+        }
+    }
+
+    /// Writes the next point of the sequence as uniforms in (0, 1) into `point`
+    pub fn next_point(&mut self, point: &mut [f64]) {
+        match self {
+            Self::Sobol(sobol) => sobol.next_point(point),
+            Self::Halton(halton) => halton.next_point(point),
+        }
+    }
+}
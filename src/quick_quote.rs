@@ -0,0 +1,87 @@
+use nalgebra::DMatrix;
+
+use crate::barrier::Barrier;
+use crate::config::{Sampling, SimulationConfig};
+use crate::error::McError;
+use crate::payoff::Payoff;
+use crate::rates::RateCurve;
+use crate::result::PricingResult;
+use crate::underlying::Underlying;
+use crate::{attach_diagnostics, simulate_payoff, with_start_values};
+
+/// Number of independently randomized quasi-random runs a quick quote is split into
+const REPLICATIONS: u64 = 8;
+
+/// Prices an option quickly for interactive use, with an error estimate from randomized
+/// quasi-Monte Carlo
+///
+/// The paths are split into independent runs, each driven by its own randomized quasi-random
+/// sequence (see `SimulationConfig::qmc_replications`). The price is the average of the runs and the standard error is estimated from
+/// their spread, which captures the faster convergence of the quasi-random numbers. Samples
+/// and path states are allocated once per run and barriers are compared on log levels. Use
+/// `SimulationConfig::quick_quote()` for the default path count.
+///
+/// Products monitored daily (barriers, path-dependent payoffs) take one step per day, so
+/// their latency grows with the time horizon, unlike terminal vanilla payoffs.
+///
+/// # Arguments
+/// * `underlyings` - List of underlying assets
+/// * `correlation_matrix` - Correlation matrix (n x n) where n is the number of underlyings.
+///   Must be symmetric, positive semi-definite, with 1.0 on the diagonal.
+/// * `time_horizon_days` - Time to expiration in days
+/// * `payoff` - Payoff of the option, e.g. vanilla or Asian
+/// * `risk_free_rate` - Risk-free rate curve or flat annual rate (as a decimal, e.g., 0.05 for 5%)
+/// * `barrier` - Optional barrier for barrier options
+/// * `config` - Number of paths and further settings. Quasi-random sampling is always used
+///   (Sobol unless another quasi-random sampling is configured), with 8 runs unless more are
+///   configured, and the sanity checks are skipped.
+///
+/// # Returns
+/// The estimated option price together with its standard error
+///
+/// # Errors
+/// Returns an error for invalid inputs (see `price_payoff`).
+pub fn quick_quote(
+    underlyings: &[Underlying],
+    correlation_matrix: &DMatrix<f64>,
+    time_horizon_days: u32,
+    payoff: &Payoff,
+    risk_free_rate: impl Into<RateCurve>,
+    barrier: Option<&Barrier>,
+    config: &SimulationConfig,
+) -> Result<PricingResult, McError> {
+    let underlyings = &*with_start_values(underlyings, config)?;
+    let rate_curve = risk_free_rate.into();
+    let sampling = if config.sampling.is_quasi_random() {
+        config.sampling
+    } else {
+        Sampling::Sobol
+    };
+    let run_config = SimulationConfig {
+        validate: false,
+        sampling,
+        qmc_replications: config.qmc_replications.max(REPLICATIONS),
+        log_space: true,
+        error_tolerance: None,
+        ..config.clone()
+    };
+    let mut result = simulate_payoff(
+        underlyings,
+        correlation_matrix,
+        time_horizon_days,
+        payoff,
+        &rate_curve,
+        barrier,
+        &run_config,
+    )?;
+    attach_diagnostics(
+        &mut result,
+        underlyings,
+        time_horizon_days,
+        payoff,
+        &rate_curve,
+        barrier,
+        config,
+    );
+    Ok(result)
+}
@@ -0,0 +1,321 @@
+use crate::result::PricingResult;
+use crate::session::{Greeks, PortfolioGreeks};
+
+/// Unit a price is quoted in
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum QuoteUnit {
+    /// Per unit of the underlying, as computed by the pricers
+    #[default]
+    PerUnit,
+    /// Per contract on the given number of units of the underlying
+    PerContract(f64),
+    /// In percent of the given notional per unit of the underlying, e.g. the initial spot of a
+    /// structured product
+    PercentOfNotional(f64),
+}
+
+/// Direction in which quoted prices are rounded to the tick size
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rounding {
+    /// To the nearest tick, halfway prices away from zero
+    #[default]
+    Nearest,
+    /// To the tick below, e.g. for bids
+    Down,
+    /// To the tick above, e.g. for offers
+    Up,
+}
+
+/// Convention in which a market quotes prices: the unit and the tick size prices are
+/// rounded to
+///
+/// The default quotes per unit of the underlying without rounding, as the pricers compute.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct QuotationConvention {
+    unit: QuoteUnit,
+    tick_size: Option<f64>,
+    rounding: Rounding,
+}
+
+/// Price of a pricing result in a quotation convention
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quote {
+    /// Price in the unit of the convention, rounded to its tick size
+    pub price: f64,
+    /// Standard error of the price in the unit of the convention, not rounded
+    pub std_error: f64,
+    /// Convention the price is quoted in
+    pub convention: QuotationConvention,
+}
+
+impl QuotationConvention {
+    /// Creates a convention quoting per unit of the underlying
+    pub fn per_unit() -> Self {
+        Self::default()
+    }
+
+    /// Creates a convention quoting per contract on `multiplier` units of the underlying
+    ///
+    /// # Panics
+    /// Panics if the multiplier is not positive and finite.
+    pub fn per_contract(multiplier: f64) -> Self {
+        assert!(
+            multiplier > 0.0 && multiplier.is_finite(),
+            "Contract multiplier must be positive and finite"
+        );
+        Self {
+            unit: QuoteUnit::PerContract(multiplier),
+            ..Self::default()
+        }
+    }
+
+    /// Creates a convention quoting in percent of the given notional per unit of the
+    /// underlying
+    ///
+    /// # Panics
+    /// Panics if the notional is not positive and finite.
+    pub fn percent_of_notional(notional: f64) -> Self {
+        assert!(
+            notional > 0.0 && notional.is_finite(),
+            "Notional must be positive and finite"
+        );
+        Self {
+            unit: QuoteUnit::PercentOfNotional(notional),
+            ..Self::default()
+        }
+    }
+
+    /// Rounds quoted prices to multiples of the tick size (in the unit of the convention)
+    ///
+    /// # Panics
+    /// Panics if the tick size is not positive and finite.
+    pub fn with_tick_size(mut self, tick_size: f64) -> Self {
+        assert!(
+            tick_size > 0.0 && tick_size.is_finite(),
+            "Tick size must be positive and finite"
+        );
+        self.tick_size = Some(tick_size);
+        self
+    }
+
+    /// Sets the direction of the rounding to the tick size
+    pub fn with_rounding(mut self, rounding: Rounding) -> Self {
+        self.rounding = rounding;
+        self
+    }
+
+    /// Returns the unit prices are quoted in
+    pub fn unit(&self) -> QuoteUnit {
+        self.unit
+    }
+
+    /// Returns the tick size prices are rounded to, if any
+    pub fn tick_size(&self) -> Option<f64> {
+        self.tick_size
+    }
+
+    /// Returns the direction of the rounding to the tick size
+    pub fn rounding(&self) -> Rounding {
+        self.rounding
+    }
+
+    /// Returns the factor converting a price per unit of the underlying into the unit of the
+    /// convention
+    pub fn scale(&self) -> f64 {
+        match self.unit {
+            QuoteUnit::PerUnit => 1.0,
+            QuoteUnit::PerContract(multiplier) => multiplier,
+            QuoteUnit::PercentOfNotional(notional) => 100.0 / notional,
+        }
+    }
+
+    /// Converts a price per unit of the underlying into the unit of the convention and rounds
+    /// it to the tick size
+    pub fn quote(&self, price: f64) -> f64 {
+        let price = price * self.scale();
+        let Some(tick_size) = self.tick_size else {
+            return price;
+        };
+        let ticks = price / tick_size;
+        // Prices on a tick may come out a rounding error off it
+        let nearest = ticks.round();
+        let ticks = if (ticks - nearest).abs() <= 1e-9 * nearest.abs().max(1.0) {
+            nearest
+        } else {
+            match self.rounding {
+                Rounding::Nearest => nearest,
+                Rounding::Down => ticks.floor(),
+                Rounding::Up => ticks.ceil(),
+            }
+        };
+        // Dividing by a whole number of ticks per unit keeps decimal ticks exact, e.g. 10.45
+        // rather than 10.450000000000001 for 209 ticks of 0.05
+        let ticks_per_unit = tick_size.recip();
+        if (ticks_per_unit - ticks_per_unit.round()).abs() <= 1e-9 * ticks_per_unit {
+            ticks / ticks_per_unit.round()
+        } else {
+            ticks * tick_size
+        }
+    }
+}
+
+impl PricingResult {
+    /// Returns the price and standard error in the given quotation convention
+    pub fn quoted(&self, convention: &QuotationConvention) -> Quote {
+        Quote {
+            price: convention.quote(self.price),
+            std_error: self.std_error * convention.scale(),
+            convention: *convention,
+        }
+    }
+}
+
+/// Unit of delta and gamma
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DeltaUnit {
+    /// Derivatives with respect to the spot: delta in units of the underlying to hold, gamma
+    /// as the change of this delta per unit of spot
+    #[default]
+    Shares,
+    /// Delta times the spot, the value of the underlying to hold; gamma as the change of this
+    /// cash delta for a 1% spot move, `gamma * spot^2 / 100`
+    Cash,
+    /// Change of the price for a 1% spot move; gamma as the change of this delta for another
+    /// 1% spot move, `gamma * spot^2 / 10000`
+    PerPercent,
+}
+
+/// Unit of vega
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VegaUnit {
+    /// Derivative with respect to the volatility, per 100 vol points
+    #[default]
+    PerUnit,
+    /// Change of the price for a volatility move of one vol point (0.01)
+    PerVolPoint,
+}
+
+/// Unit of rho
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RhoUnit {
+    /// Derivative with respect to the rates, per 100% of rate
+    #[default]
+    PerUnit,
+    /// Change of the price for a rate move of 1%
+    PerPercent,
+    /// Change of the price for a rate move of one basis point (0.0001)
+    PerBasisPoint,
+}
+
+/// Units in which Greeks are expressed
+///
+/// The default expresses all Greeks as mathematical derivatives per unit of the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GreekConvention {
+    /// Unit of delta and gamma
+    pub delta: DeltaUnit,
+    /// Unit of vega
+    pub vega: VegaUnit,
+    /// Unit of rho
+    pub rho: RhoUnit,
+}
+
+impl GreekConvention {
+    /// Creates the convention of mathematical derivatives
+    pub fn derivatives() -> Self {
+        Self::default()
+    }
+
+    /// Creates the usual trading desk convention: cash delta and gamma, vega per vol point
+    /// and rho per basis point
+    pub fn market() -> Self {
+        Self {
+            delta: DeltaUnit::Cash,
+            vega: VegaUnit::PerVolPoint,
+            rho: RhoUnit::PerBasisPoint,
+        }
+    }
+
+    /// Sets the unit of delta and gamma
+    pub fn with_delta(mut self, delta: DeltaUnit) -> Self {
+        self.delta = delta;
+        self
+    }
+
+    /// Sets the unit of vega
+    pub fn with_vega(mut self, vega: VegaUnit) -> Self {
+        self.vega = vega;
+        self
+    }
+
+    /// Sets the unit of rho
+    pub fn with_rho(mut self, rho: RhoUnit) -> Self {
+        self.rho = rho;
+        self
+    }
+
+    /// Returns the factors converting derivatives into delta, gamma, vega and rho in this
+    /// convention at the given spot
+    fn scales(&self, spot_price: f64) -> [f64; 4] {
+        let (delta, gamma) = match self.delta {
+            DeltaUnit::Shares => (1.0, 1.0),
+            DeltaUnit::Cash => (spot_price, spot_price * spot_price / 100.0),
+            DeltaUnit::PerPercent => (spot_price / 100.0, spot_price * spot_price / 10_000.0),
+        };
+        let vega = match self.vega {
+            VegaUnit::PerUnit => 1.0,
+            VegaUnit::PerVolPoint => 0.01,
+        };
+        let rho = match self.rho {
+            RhoUnit::PerUnit => 1.0,
+            RhoUnit::PerPercent => 0.01,
+            RhoUnit::PerBasisPoint => 0.0001,
+        };
+        [delta, gamma, vega, rho]
+    }
+}
+
+impl Greeks {
+    /// Returns the Greeks expressed in the given convention, which they record
+    pub fn in_convention(&self, convention: &GreekConvention) -> Greeks {
+        let from = self.convention.scales(self.spot_price);
+        let to = convention.scales(self.spot_price);
+        let convert = |value: f64, index: usize| value / from[index] * to[index];
+        // The FX delta is in units of the exchange rate it was computed at
+        let fx_delta = match self.fx_spot_price {
+            Some(fx_spot_price) => {
+                self.fx_delta / self.convention.scales(fx_spot_price)[0]
+                    * convention.scales(fx_spot_price)[0]
+            }
+            None => self.fx_delta,
+        };
+        Greeks {
+            delta: convert(self.delta, 0),
+            gamma: convert(self.gamma, 1),
+            vega: convert(self.vega, 2),
+            rho: convert(self.rho, 3),
+            fx_delta,
+            fx_vega: convert(self.fx_vega, 2),
+            convention: *convention,
+            ..self.clone()
+        }
+    }
+}
+
+impl PortfolioGreeks {
+    /// Returns the Greeks of the positions and their total expressed in the given convention
+    pub fn in_convention(&self, convention: &GreekConvention) -> PortfolioGreeks {
+        PortfolioGreeks {
+            positions: self
+                .positions
+                .iter()
+                .map(|greeks| greeks.in_convention(convention))
+                .collect(),
+            total: self.total.in_convention(convention),
+        }
+    }
+}
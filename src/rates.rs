@@ -0,0 +1,193 @@
+use crate::math;
+
+/// Interpolation scheme of a rate curve between its pillars
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Interpolation {
+    /// Linear interpolation of the zero rates
+    #[default]
+    Linear,
+    /// Linear interpolation of the log discount factors (piecewise flat forward rates)
+    LogLinear,
+}
+
+/// Term structure of continuously compounded risk-free zero rates
+///
+/// Zero rates are extrapolated flat before the first and after the last pillar. A flat rate
+/// converts into a curve with `RateCurve::from(0.05)`, so all pricers accept plain `f64` rates.
+/// With the `serde` feature, a flat curve serializes as its rate and other curves as their
+/// pillars and interpolation.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "RateCurveRepr", try_from = "RateCurveRepr")
+)]
+pub struct RateCurve {
+    /// Pillar tenors in years, strictly increasing
+    tenors: Vec<f64>,
+    /// Continuously compounded zero rates at the pillars
+    rates: Vec<f64>,
+    /// Interpolation between the pillars
+    interpolation: Interpolation,
+}
+
+impl RateCurve {
+    /// Creates a curve with the same zero rate for every tenor
+    pub fn flat(rate: f64) -> Self {
+        Self {
+            tenors: vec![1.0],
+            rates: vec![rate],
+            interpolation: Interpolation::Linear,
+        }
+    }
+
+    /// Creates a curve from (tenor in years, continuously compounded zero rate) pairs
+    ///
+    /// # Panics
+    /// Panics if no pillar is given, a tenor is not positive, or the tenors are not strictly
+    /// increasing.
+    pub fn new(points: &[(f64, f64)], interpolation: Interpolation) -> Self {
+        assert!(!points.is_empty(), "Rate curve needs at least one pillar");
+        assert!(
+            points[0].0 > 0.0 && points.windows(2).all(|pair| pair[0].0 < pair[1].0),
+            "Rate curve tenors must be positive and strictly increasing"
+        );
+        Self {
+            tenors: points.iter().map(|&(tenor, _)| tenor).collect(),
+            rates: points.iter().map(|&(_, rate)| rate).collect(),
+            interpolation,
+        }
+    }
+
+    /// Returns the pillar tenors in years
+    pub fn tenors(&self) -> &[f64] {
+        &self.tenors
+    }
+
+    /// Returns the curve with all zero rates shifted by the same amount (parallel shift)
+    pub fn shifted(&self, shift: f64) -> Self {
+        Self {
+            rates: self.rates.iter().map(|rate| rate + shift).collect(),
+            ..self.clone()
+        }
+    }
+
+    /// Returns the curve as seen `time` years from today if today's forward rates are
+    /// realized: the zero rate of each later pillar becomes the forward rate from `time` to
+    /// the pillar, at the tenor remaining after `time`
+    ///
+    /// Past the last pillar, the curve is flat at the forward rate beyond `time`.
+    pub fn rolled(&self, time: f64) -> Self {
+        let points: Vec<(f64, f64)> = self
+            .tenors
+            .iter()
+            .filter(|&&tenor| tenor > time)
+            .map(|&tenor| (tenor - time, self.forward_rate(time, tenor)))
+            .collect();
+        if points.is_empty() {
+            return Self::flat(self.forward_rate(time, time + 1.0));
+        }
+        Self::new(&points, self.interpolation)
+    }
+
+    /// Returns the continuously compounded zero rate for the given tenor in years
+    pub fn zero_rate(&self, time: f64) -> f64 {
+        let last = self.tenors.len() - 1;
+        if time <= self.tenors[0] {
+            return self.rates[0];
+        }
+        if time >= self.tenors[last] {
+            return self.rates[last];
+        }
+        // Pillars enclosing the tenor: tenors[upper - 1] < time < tenors[upper]
+        let upper = self.tenors.partition_point(|&tenor| tenor < time);
+        let (t0, t1) = (self.tenors[upper - 1], self.tenors[upper]);
+        let (r0, r1) = (self.rates[upper - 1], self.rates[upper]);
+        let weight = (time - t0) / (t1 - t0);
+        match self.interpolation {
+            Interpolation::Linear => r0 + weight * (r1 - r0),
+            Interpolation::LogLinear => (r0 * t0 + weight * (r1 * t1 - r0 * t0)) / time,
+        }
+    }
+
+    /// Returns the discount factor for the given tenor in years
+    pub fn discount_factor(&self, time: f64) -> f64 {
+        math::exp(-self.zero_rate(time) * time)
+    }
+
+    /// Returns the continuously compounded forward rate between two tenors in years
+    pub fn forward_rate(&self, start: f64, end: f64) -> f64 {
+        if end <= start {
+            return self.zero_rate(start);
+        }
+        (self.zero_rate(end) * end - self.zero_rate(start) * start) / (end - start)
+    }
+}
+
+impl From<f64> for RateCurve {
+    fn from(rate: f64) -> Self {
+        Self::flat(rate)
+    }
+}
+
+impl From<&RateCurve> for RateCurve {
+    fn from(curve: &RateCurve) -> Self {
+        curve.clone()
+    }
+}
+
+/// Serialized form of a rate curve
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+enum RateCurveRepr {
+    /// Same zero rate for every tenor
+    Flat(f64),
+    /// (tenor in years, zero rate) pairs
+    Pillars {
+        pillars: Vec<(f64, f64)>,
+        #[serde(default)]
+        interpolation: Interpolation,
+    },
+}
+
+#[cfg(feature = "serde")]
+impl From<RateCurve> for RateCurveRepr {
+    fn from(curve: RateCurve) -> Self {
+        if curve == RateCurve::flat(curve.rates[0]) {
+            return RateCurveRepr::Flat(curve.rates[0]);
+        }
+        RateCurveRepr::Pillars {
+            pillars: curve.tenors.into_iter().zip(curve.rates).collect(),
+            interpolation: curve.interpolation,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RateCurveRepr> for RateCurve {
+    type Error = String;
+
+    /// Converts the serialized form, checking the pillars like `RateCurve::new` instead of
+    /// panicking
+    fn try_from(repr: RateCurveRepr) -> Result<Self, Self::Error> {
+        match repr {
+            RateCurveRepr::Flat(rate) => Ok(RateCurve::flat(rate)),
+            RateCurveRepr::Pillars {
+                pillars,
+                interpolation,
+            } => {
+                if pillars.is_empty() {
+                    return Err("Rate curve needs at least one pillar".to_string());
+                }
+                if !(pillars[0].0 > 0.0 && pillars.windows(2).all(|pair| pair[0].0 < pair[1].0)) {
+                    return Err(
+                        "Rate curve tenors must be positive and strictly increasing".to_string()
+                    );
+                }
+                Ok(RateCurve::new(&pillars, interpolation))
+            }
+        }
+    }
+}
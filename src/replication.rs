@@ -0,0 +1,256 @@
+use std::sync::Arc;
+
+use crate::closed_form;
+use crate::config::SimulationConfig;
+use crate::error::McError;
+use crate::market::MarketSnapshot;
+use crate::observer::{PathRecorder, RecordedPath};
+use crate::payoff::{OptionType, PathObservables};
+use crate::product::Product;
+use crate::result::PricingResult;
+use crate::stress::ScenarioPricer;
+use crate::{path_weight, with_effective_volatility};
+
+/// Instrument of a static replication, all expiring with the replicated product
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ReplicatingInstrument {
+    /// Zero-coupon bond paying 1 at expiry
+    Bond,
+    /// First underlying delivered at expiry, worth its prepaid forward
+    Forward,
+    /// Vanilla call on the first underlying at the given strike
+    Call(f64),
+}
+
+/// Position in one instrument of a static replication
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReplicationLeg {
+    /// Replicating instrument
+    pub instrument: ReplicatingInstrument,
+    /// Number of units held, negative for short positions
+    pub quantity: f64,
+    /// Price of one unit of the instrument
+    pub unit_price: f64,
+}
+
+impl ReplicationLeg {
+    /// Returns the value of the position: the quantity times the unit price
+    pub fn value(&self) -> f64 {
+        self.quantity * self.unit_price
+    }
+}
+
+/// Decomposition of a product into bonds, a forward and a strip of vanilla calls, reported
+/// alongside its Monte Carlo price
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StaticReplication {
+    /// Monte Carlo price of the product
+    pub pricing: PricingResult,
+    /// Bond, forward and calls in the order of their strikes; positions of zero are left out
+    pub legs: Vec<ReplicationLeg>,
+    /// `true` if the payoff depends on the terminal price of the first underlying only, so
+    /// the legs replicate it statically up to the interpolation between the strikes;
+    /// `false` if they replicate its expectation given the terminal price
+    pub is_static: bool,
+}
+
+impl StaticReplication {
+    /// Returns the value of the replicating portfolio
+    pub fn value(&self) -> f64 {
+        self.legs.iter().map(ReplicationLeg::value).sum()
+    }
+
+    /// Returns the part of the Monte Carlo price the legs do not explain: the price less the
+    /// value of the replicating portfolio
+    pub fn residual(&self) -> f64 {
+        self.pricing.price - self.value()
+    }
+}
+
+/// Decomposes a product into a portfolio of bonds, the forward and vanilla calls on the first
+/// underlying expiring with it, and prices the product by Monte Carlo alongside
+///
+/// The payoff is interpolated linearly between the strikes and extrapolated linearly beyond
+/// them, which a bond, a forward and one call per inner strike replicate exactly. Products
+/// whose payoff depends on the terminal price of the first underlying only (vanillas and
+/// digitals without barrier) are replicated statically: digitals become call spreads
+/// between the strikes around their strike. For path-dependent products and barriers, the
+/// expected payoff given the terminal price is estimated from the simulated paths, each
+/// strike taking the mean payoff of the paths ending closest to it, so the legs are the best
+/// static hedge on the terminal price rather than a replication, and the residual shows
+/// what they leave unexplained.
+///
+/// The calls are priced with Black-Scholes at the smile volatility of their strike, or the
+/// effective volatility of the underlying without smile. With cash dividends or a model
+/// without lognormal marginals, they are priced on the simulated paths instead.
+///
+/// # Arguments
+/// * `market` - Market of the underlyings
+/// * `time_horizon_days` - Time to expiration of the product in days
+/// * `product` - Product to decompose
+/// * `strikes` - Strikes of the replicating calls, positive and strictly increasing; the
+///   finer the grid, the closer the interpolation
+/// * `config` - Number of paths and variance reduction settings. The error tolerance and
+///   the sanity checks are ignored and the path observer is replaced.
+///
+/// # Returns
+/// The Monte Carlo price of the product and the legs of the replicating portfolio
+///
+/// # Errors
+/// Returns `McError::InvalidProduct` for fewer than two strikes or strikes that are not
+/// positive and strictly increasing, or an error for invalid inputs (see `price_option`).
+pub fn replicate_product(
+    market: &MarketSnapshot,
+    time_horizon_days: u32,
+    product: &Product,
+    strikes: &[f64],
+    config: &SimulationConfig,
+) -> Result<StaticReplication, McError> {
+    if strikes.len() < 2 || strikes[0] <= 0.0 || !strikes.windows(2).all(|pair| pair[0] < pair[1]) {
+        return Err(McError::InvalidProduct(
+            "replication needs at least two positive, strictly increasing strikes".to_string(),
+        ));
+    }
+    let is_static = product.barrier.is_none() && !product.payoff.is_path_dependent();
+    let recorder = Arc::new(PathRecorder::new(1).with_steps(false));
+    let config = SimulationConfig {
+        path_observer: Some(recorder.clone()),
+        ..config.clone()
+    };
+    let pricer = ScenarioPricer::new(market, time_horizon_days, product, &config)?;
+    let pricing = pricer.price(&pricer.market, pricer.barrier.as_ref(), 0.0)?;
+    let paths = recorder.take_paths();
+
+    let underlying = &pricer.market.underlyings[0];
+    let payoffs: Vec<f64> = if is_static {
+        strikes
+            .iter()
+            .map(|&strike| {
+                product.payoff.evaluate(&PathObservables {
+                    initial_price: underlying.spot_price,
+                    final_price: strike,
+                    running_max: strike,
+                    running_min: strike,
+                    fixings: &[],
+                })
+            })
+            .collect()
+    } else {
+        conditional_payoffs(&paths, strikes, &config)
+    };
+
+    let rate_curve = &pricer.market.risk_free_rate;
+    let time_to_expiration = config.day_count.year_fraction(time_horizon_days);
+    let discount_factor = rate_curve.discount_factor(time_to_expiration);
+    let prepaid_forward =
+        underlying.prepaid_forward(rate_curve, time_to_expiration, config.day_count);
+    let forward = prepaid_forward / discount_factor;
+    let call_price = |strike: f64| {
+        let mut effective = with_effective_volatility(underlying, time_horizon_days, &config);
+        if let Some(volatility) = underlying.smile_volatility(strike, forward) {
+            effective.volatility = volatility;
+        }
+        let closed_form = config.model.has_black_scholes_marginals().then(|| {
+            closed_form::black_scholes_price_with_dividends(
+                &effective,
+                strike,
+                rate_curve,
+                time_to_expiration,
+                OptionType::Call,
+                config.day_count,
+            )
+        });
+        closed_form.flatten().unwrap_or_else(|| {
+            let (sum, weights) = paths.iter().fold((0.0, 0.0), |(sum, weights), path| {
+                let weight = path_weight(&config, path.path_index);
+                (
+                    sum + weight * (path.final_prices[0] - strike).max(0.0),
+                    weights + weight,
+                )
+            });
+            sum / weights * discount_factor
+        })
+    };
+
+    // Slopes of the segments between the strikes; the outer ones continue beyond the grid
+    let slopes: Vec<f64> = strikes
+        .windows(2)
+        .zip(payoffs.windows(2))
+        .map(|(strike, payoff)| (payoff[1] - payoff[0]) / (strike[1] - strike[0]))
+        .collect();
+    let mut legs = vec![
+        ReplicationLeg {
+            instrument: ReplicatingInstrument::Bond,
+            quantity: payoffs[0] - slopes[0] * strikes[0],
+            unit_price: discount_factor,
+        },
+        ReplicationLeg {
+            instrument: ReplicatingInstrument::Forward,
+            quantity: slopes[0],
+            unit_price: prepaid_forward,
+        },
+    ];
+    for (index, &strike) in strikes.iter().enumerate().skip(1).take(slopes.len() - 1) {
+        legs.push(ReplicationLeg {
+            instrument: ReplicatingInstrument::Call(strike),
+            quantity: slopes[index] - slopes[index - 1],
+            unit_price: call_price(strike),
+        });
+    }
+    legs.retain(|leg| leg.quantity != 0.0);
+    Ok(StaticReplication {
+        pricing,
+        legs,
+        is_static,
+    })
+}
+
+/// Returns the mean undiscounted payoff of the paths ending closest to each strike, with the
+/// paths weighted like for the price
+///
+/// Strikes no path ends closest to take the payoff interpolated linearly from their
+/// neighbours, or of the nearest strike with paths beyond the outermost ones.
+fn conditional_payoffs(
+    paths: &[RecordedPath],
+    strikes: &[f64],
+    config: &SimulationConfig,
+) -> Vec<f64> {
+    let mut sums = vec![0.0; strikes.len()];
+    let mut weights = vec![0.0; strikes.len()];
+    for path in paths.iter().filter(|path| path.payoff.is_finite()) {
+        let final_price = path.final_prices[0];
+        let upper = strikes.partition_point(|&strike| strike < final_price);
+        let index = match upper {
+            0 => 0,
+            _ if upper == strikes.len() => upper - 1,
+            _ if final_price - strikes[upper - 1] < strikes[upper] - final_price => upper - 1,
+            _ => upper,
+        };
+        let weight = path_weight(config, path.path_index);
+        sums[index] += weight * path.payoff;
+        weights[index] += weight;
+    }
+    let observed: Vec<(f64, f64)> = strikes
+        .iter()
+        .zip(sums.iter().zip(&weights))
+        .filter(|(_, (_, &weight))| weight > 0.0)
+        .map(|(&strike, (&sum, &weight))| (strike, sum / weight))
+        .collect();
+    strikes
+        .iter()
+        .map(|&strike| {
+            let upper = observed.partition_point(|&(observed, _)| observed < strike);
+            match (observed.get(upper.wrapping_sub(1)), observed.get(upper)) {
+                (_, Some(&(right, payoff))) if right == strike => payoff,
+                (Some(&(left, left_payoff)), Some(&(right, right_payoff))) => {
+                    left_payoff + (right_payoff - left_payoff) * (strike - left) / (right - left)
+                }
+                (Some(&(_, payoff)), None) | (None, Some(&(_, payoff))) => payoff,
+                (None, None) => 0.0,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,206 @@
+use nalgebra::DMatrix;
+
+use crate::barrier::Barrier;
+use crate::calendar::Date;
+use crate::config::SimulationConfig;
+use crate::error::McError;
+use crate::market::MarketSnapshot;
+use crate::payoff::{OptionType, Payoff};
+use crate::price_payoff;
+use crate::rates::RateCurve;
+use crate::result::PricingResult;
+use crate::underlying::Underlying;
+
+/// Builder of a European option pricing request, as an alternative to the positional
+/// arguments of `price_option`
+///
+/// Without a correlation matrix, the underlyings are uncorrelated. Without a payoff, the
+/// request prices a vanilla Call (or a Put after `put()`) at the strike set with `strike()`.
+/// The risk-free rate defaults to zero and the simulation to `SimulationConfig::default()`.
+///
+/// With the `serde` feature, the request serializes without its simulation configuration,
+/// which deserialized requests take from `SimulationConfig::default()` until `config()` sets
+/// it.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PricingRequest {
+    underlyings: Vec<Underlying>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serialization::optional_matrix_rows")
+    )]
+    correlation_matrix: Option<DMatrix<f64>>,
+    risk_free_rate: Option<RateCurve>,
+    maturity_days: u32,
+    valuation_date: Option<Date>,
+    maturity_date: Option<Date>,
+    strike_price: Option<f64>,
+    /// Type of the vanilla option, a Call if not set
+    option_type: Option<OptionType>,
+    payoff: Option<Payoff>,
+    barrier: Option<Barrier>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    config: SimulationConfig,
+}
+
+impl PricingRequest {
+    /// Creates an empty request
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an underlying; the payoff is written on the first one
+    pub fn underlying(mut self, underlying: Underlying) -> Self {
+        self.underlyings.push(underlying);
+        self
+    }
+
+    /// Adds several underlyings
+    pub fn underlyings(mut self, underlyings: impl IntoIterator<Item = Underlying>) -> Self {
+        self.underlyings.extend(underlyings);
+        self
+    }
+
+    /// Sets the correlation matrix of the underlyings
+    pub fn correlation(mut self, correlation_matrix: DMatrix<f64>) -> Self {
+        self.correlation_matrix = Some(correlation_matrix);
+        self
+    }
+
+    /// Sets the risk-free rate curve or flat annual rate
+    pub fn rate(mut self, risk_free_rate: impl Into<RateCurve>) -> Self {
+        self.risk_free_rate = Some(risk_free_rate.into());
+        self
+    }
+
+    /// Takes the underlyings, correlation matrix and rate curve of a market snapshot,
+    /// replacing any set before
+    pub fn market(mut self, market: MarketSnapshot) -> Self {
+        self.underlyings = market.underlyings;
+        self.correlation_matrix = Some(market.correlation_matrix);
+        self.risk_free_rate = Some(market.risk_free_rate);
+        self
+    }
+
+    /// Sets the time to expiration in days, unless a maturity date is set
+    pub fn maturity_days(mut self, maturity_days: u32) -> Self {
+        self.maturity_days = maturity_days;
+        self
+    }
+
+    /// Sets the valuation date the maturity date is counted from
+    pub fn valuation_date(mut self, valuation_date: Date) -> Self {
+        self.valuation_date = Some(valuation_date);
+        self
+    }
+
+    /// Sets the maturity date, converted into days from the valuation date with the day
+    /// count convention and calendar of the simulation configuration when pricing. It takes
+    /// precedence over `maturity_days`.
+    pub fn maturity_date(mut self, maturity_date: Date) -> Self {
+        self.maturity_date = Some(maturity_date);
+        self
+    }
+
+    /// Sets the strike price of the vanilla option
+    pub fn strike(mut self, strike_price: f64) -> Self {
+        self.strike_price = Some(strike_price);
+        self
+    }
+
+    /// Sets the type of the vanilla option, a Call by default
+    pub fn option_type(mut self, option_type: OptionType) -> Self {
+        self.option_type = Some(option_type);
+        self
+    }
+
+    /// Prices a Call option (the default)
+    pub fn call(self) -> Self {
+        self.option_type(OptionType::Call)
+    }
+
+    /// Prices a Put option
+    pub fn put(self) -> Self {
+        self.option_type(OptionType::Put)
+    }
+
+    /// Prices the given payoff instead of a vanilla option, ignoring strike and option type
+    pub fn payoff(mut self, payoff: Payoff) -> Self {
+        self.payoff = Some(payoff);
+        self
+    }
+
+    /// Subjects the option to a barrier
+    pub fn barrier(mut self, barrier: Barrier) -> Self {
+        self.barrier = Some(barrier);
+        self
+    }
+
+    /// Sets the simulation configuration, replacing path count and seed set before
+    pub fn config(mut self, config: SimulationConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Sets the number of Monte Carlo paths
+    pub fn paths(mut self, num_paths: u64) -> Self {
+        self.config.num_paths = num_paths;
+        self
+    }
+
+    /// Sets the seed of the random number generator for reproducible runs
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.config.seed = Some(seed);
+        self
+    }
+
+    /// Prices the requested option
+    ///
+    /// # Returns
+    /// The estimated option price together with its standard error
+    ///
+    /// # Errors
+    /// Returns `McError::InvalidProduct` if neither a strike nor a payoff was set,
+    /// `McError::InvalidDate` if a maturity date was set without a valuation date or lies
+    /// before it, or an error for invalid inputs (see `price_option`).
+    pub fn price(&self) -> Result<PricingResult, McError> {
+        let maturity_days = match (self.valuation_date, self.maturity_date) {
+            (_, None) => self.maturity_days,
+            (Some(valuation_date), Some(maturity_date)) => {
+                self.config.days_between(valuation_date, maturity_date)?
+            }
+            (None, Some(maturity_date)) => {
+                return Err(McError::InvalidDate(format!(
+                    "maturity date {} needs a valuation date",
+                    maturity_date
+                )))
+            }
+        };
+        let payoff = match (&self.payoff, self.strike_price) {
+            (Some(payoff), _) => payoff.clone(),
+            (None, Some(strike_price)) => Payoff::Vanilla {
+                strike_price,
+                option_type: self.option_type.unwrap_or(OptionType::Call),
+            },
+            (None, None) => {
+                return Err(McError::InvalidProduct(
+                    "request needs a strike or a payoff".to_string(),
+                ))
+            }
+        };
+        let num_underlyings = self.underlyings.len();
+        let correlation_matrix = self
+            .correlation_matrix
+            .clone()
+            .unwrap_or_else(|| DMatrix::identity(num_underlyings, num_underlyings));
+        price_payoff(
+            &self.underlyings,
+            &correlation_matrix,
+            maturity_days,
+            &payoff,
+            self.risk_free_rate.clone().unwrap_or(RateCurve::flat(0.0)),
+            self.barrier.as_ref(),
+            &self.config,
+        )
+    }
+}
@@ -0,0 +1,587 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::audit::AuditEntry;
+use crate::bounds::PriceBounds;
+use crate::config::ErrorTolerance;
+use crate::error::McError;
+use crate::format::{invalid, Fields, FileWriter, Reader, WORD};
+use crate::qmc::DimensionBudget;
+use crate::statistics::SampleStatistics;
+
+/// Result of a Monte Carlo pricing run
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PricingResult {
+    /// Estimated option price
+    pub price: f64,
+    /// Standard error of the price estimate
+    pub std_error: f64,
+    /// Number of simulated paths (antithetic counterparts included)
+    pub num_paths: u64,
+    /// Analytic no-arbitrage bounds of the price, if known for the product
+    pub bounds: Option<PriceBounds>,
+    /// Number of paths with a non-finite payoff, dropped or clamped according to the policy
+    pub non_finite_paths: u64,
+    /// Non-fatal quality concerns detected while pricing
+    pub warnings: Vec<PricingWarning>,
+    /// Settings the price was computed with, defaults included, so the valuation can be
+    /// reproduced and challenged later (empty for pricers that do not record them)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub audit: Vec<AuditEntry>,
+}
+
+/// Number of standard errors a check may be violated by before a warning is raised
+pub(crate) const TOLERANCE_STD_ERRORS: f64 = 4.0;
+
+/// Rounding error relative to the price (or to one, for smaller prices) a bound may be
+/// violated by beyond the Monte Carlo error, e.g. by estimates without variance such as the
+/// prices of expiring options
+const ROUNDING_TOLERANCE: f64 = 1e-12;
+
+/// Standard error relative to the price above which a warning is raised
+pub(crate) const MAX_RELATIVE_STD_ERROR: f64 = 0.01;
+
+/// Non-fatal quality concern attached to a pricing result
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PricingWarning {
+    /// Call and put prices on the same paths violate put-call parity beyond Monte Carlo error
+    PutCallParity {
+        /// Difference between `C - P` and the discounted forward value
+        residual: f64,
+        /// Tolerance derived from the standard errors
+        tolerance: f64,
+    },
+    /// The price moved in the wrong direction when the strike was increased
+    StrikeMonotonicity {
+        /// Strike the option was repriced with
+        bumped_strike: f64,
+        /// Price at the bumped strike
+        bumped_price: f64,
+    },
+    /// The price moved in the wrong direction when the barrier was moved towards the spot
+    BarrierMonotonicity {
+        /// Barrier level the option was repriced with
+        bumped_level: f64,
+        /// Price at the bumped barrier level
+        bumped_price: f64,
+    },
+    /// The price lies outside of the no-arbitrage bounds beyond Monte Carlo error
+    OutsideNoArbitrageBounds {
+        /// Lower no-arbitrage bound
+        lower: f64,
+        /// Upper no-arbitrage bound
+        upper: f64,
+    },
+    /// The standard error is large relative to the price, more paths are needed
+    HighStandardError {
+        /// Standard error divided by the absolute price
+        relative_error: f64,
+    },
+    /// The barrier is within a few daily moves of the spot, so the discrete daily monitoring
+    /// differs noticeably from continuous monitoring
+    BarrierNearSpot {
+        /// Relative distance between the initial reference value and the barrier level
+        distance: f64,
+        /// Daily standard deviation of the reference value's relative moves
+        daily_std_dev: f64,
+    },
+    /// Some paths produced non-finite values and were dropped or clamped
+    NonFinitePaths {
+        /// Number of affected paths
+        count: u64,
+    },
+    /// The correlation matrix was not positive definite and the paths were simulated with
+    /// the nearest correlation matrix instead
+    CorrelationRepaired {
+        /// Frobenius norm of the difference between the repaired and the given matrix
+        adjustment: f64,
+    },
+    /// The maximum number of paths was simulated before the standard error met the tolerance
+    ErrorToleranceNotMet {
+        /// Standard error the tolerance requires at the estimated price
+        target_std_error: f64,
+    },
+    /// Each path has more dimensions than the quasi-random sequence provides reliably, so
+    /// only its leading dimensions are quasi-random and the others pseudo-random, which
+    /// converge more slowly
+    QuasiRandomPadding {
+        /// Number of leading dimensions of each path driven by the quasi-random sequence
+        quasi_random_dimensions: u64,
+        /// Number of dimensions of each path (shocks per step times steps)
+        total_dimensions: u64,
+    },
+    /// The trade was priced with fewer paths than configured to finish a batch run by its
+    /// deadline
+    ReducedPaths {
+        /// Number of paths of a full accuracy price
+        requested_paths: u64,
+    },
+}
+
+impl fmt::Display for PricingWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PricingWarning::PutCallParity {
+                residual,
+                tolerance,
+            } => write!(
+                f,
+                "Put-call parity violated by {:.6} (tolerance {:.6})",
+                residual, tolerance
+            ),
+            PricingWarning::StrikeMonotonicity {
+                bumped_strike,
+                bumped_price,
+            } => write!(
+                f,
+                "Price is not monotone in strike: price {:.6} at strike {:.4}",
+                bumped_price, bumped_strike
+            ),
+            PricingWarning::BarrierMonotonicity {
+                bumped_level,
+                bumped_price,
+            } => write!(
+                f,
+                "Price is not monotone in barrier level: price {:.6} at level {:.4}",
+                bumped_price, bumped_level
+            ),
+            PricingWarning::OutsideNoArbitrageBounds { lower, upper } => write!(
+                f,
+                "Price lies outside of the no-arbitrage bounds [{:.6}, {:.6}]",
+                lower, upper
+            ),
+            PricingWarning::HighStandardError { relative_error } => write!(
+                f,
+                "Standard error is {:.2}% of the price, consider more paths",
+                relative_error * 100.0
+            ),
+            PricingWarning::BarrierNearSpot {
+                distance,
+                daily_std_dev,
+            } => write!(
+                f,
+                "Barrier is {:.4}% away from the spot with daily moves of {:.4}%, daily monitoring is coarse",
+                distance * 100.0,
+                daily_std_dev * 100.0
+            ),
+            PricingWarning::NonFinitePaths { count } => {
+                write!(f, "{} paths produced non-finite values", count)
+            }
+            PricingWarning::CorrelationRepaired { adjustment } => write!(
+                f,
+                "Correlation matrix was not positive definite and was adjusted by {:.6}",
+                adjustment
+            ),
+            PricingWarning::ErrorToleranceNotMet { target_std_error } => write!(
+                f,
+                "Standard error did not reach the tolerance of {:.6} within the maximum number of paths",
+                target_std_error
+            ),
+            PricingWarning::QuasiRandomPadding {
+                quasi_random_dimensions,
+                total_dimensions,
+            } => write!(
+                f,
+                "Quasi-random numbers drive {} of the {} dimensions of each path, the others are pseudo-random",
+                quasi_random_dimensions, total_dimensions
+            ),
+            PricingWarning::ReducedPaths { requested_paths } => write!(
+                f,
+                "Priced with fewer than the {} requested paths to meet the deadline",
+                requested_paths
+            ),
+        }
+    }
+}
+
+impl PricingResult {
+    /// Builds a result from the streamed statistics of independent discounted samples,
+    /// adjusting them by the control variate if its expectation is given
+    pub(crate) fn from_statistics(
+        statistics: &SampleStatistics,
+        num_paths: u64,
+        control_expectation: Option<f64>,
+    ) -> Self {
+        let (mean, variance) = match control_expectation {
+            Some(control_expectation) => {
+                statistics.controlled_mean_and_variance(control_expectation)
+            }
+            None => (statistics.mean(), statistics.variance()),
+        };
+        Self::new(
+            mean,
+            (variance / statistics.effective_count()).sqrt(),
+            num_paths,
+        )
+    }
+
+    /// Returns the estimated number of paths at which the standard error meets the
+    /// tolerance, treating this result as a pilot run
+    ///
+    /// The standard error is assumed to fall with the square root of the number of paths,
+    /// as for pseudo-random sampling; quasi-random sampling usually converges faster, so
+    /// the estimate is conservative for it.
+    ///
+    /// # Returns
+    /// At least one path, or `None` if the tolerance requires a standard error of zero the
+    /// estimate does not have, e.g. a relative tolerance at a price of zero
+    pub fn paths_needed(&self, tolerance: ErrorTolerance) -> Option<u64> {
+        if self.std_error == 0.0 {
+            return Some(1);
+        }
+        let target_std_error = tolerance.target_std_error(self.price);
+        if target_std_error.is_nan() || target_std_error <= 0.0 || !self.std_error.is_finite() {
+            return None;
+        }
+        let ratio = self.std_error / target_std_error;
+        Some(((self.num_paths as f64 * ratio * ratio).ceil() as u64).max(1))
+    }
+
+    /// Returns the result of a position of `quantity` units of the priced product, negative
+    /// for short positions
+    ///
+    /// The price and the bounds are scaled by the quantity, the bounds swapped for short
+    /// positions, and the standard error by its absolute value. Warnings are kept as they
+    /// are, quoted per unit of the product.
+    pub fn scaled(&self, quantity: f64) -> Self {
+        let scale = |value: f64| if quantity == 0.0 { 0.0 } else { value * quantity };
+        Self {
+            price: self.price * quantity,
+            std_error: self.std_error * quantity.abs(),
+            bounds: self.bounds.map(|bounds| {
+                let (lower, upper) = (scale(bounds.lower), scale(bounds.upper));
+                PriceBounds {
+                    lower: lower.min(upper),
+                    upper: lower.max(upper),
+                }
+            }),
+            ..self.clone()
+        }
+    }
+
+    /// Creates a result from an estimate and its standard error, without bounds or warnings
+    pub(crate) fn new(price: f64, std_error: f64, num_paths: u64) -> Self {
+        Self {
+            price,
+            std_error,
+            num_paths,
+            non_finite_paths: 0,
+            bounds: None,
+            warnings: Vec::new(),
+            audit: Vec::new(),
+        }
+    }
+
+    /// Returns the audit entry of a setting, if it was recorded
+    pub fn audit_entry(&self, setting: &str) -> Option<&AuditEntry> {
+        self.audit.iter().find(|entry| entry.setting == setting)
+    }
+
+    /// Records a setting in the audit trail, replacing an earlier entry of the same setting
+    pub(crate) fn record_setting(&mut self, entry: AuditEntry) {
+        match self.audit.iter_mut().find(|audit| audit.setting == entry.setting) {
+            Some(audit) => *audit = entry,
+            None => self.audit.push(entry),
+        }
+    }
+
+    /// Returns `true` unless the estimate lies outside of its no-arbitrage bounds by more
+    /// than the Monte Carlo error, i.e. by more than 4 standard errors plus rounding
+    ///
+    /// A Monte Carlo estimate of a price at one of its bounds (e.g. a deep out-of-the-money
+    /// barrier option) falls outside of them about half of the time, so the bounds are only
+    /// violated beyond its error. Results without bounds are always within them.
+    pub fn is_within_bounds(&self) -> bool {
+        self.bounds.is_none_or(|bounds| {
+            let rounding = ROUNDING_TOLERANCE * self.price.abs().max(1.0);
+            bounds.contains(self.price, TOLERANCE_STD_ERRORS * self.std_error + rounding)
+        })
+    }
+
+    /// Attaches the no-arbitrage bounds and flags the estimate if it lies outside of them
+    /// by more than the Monte Carlo error (see `is_within_bounds`)
+    pub(crate) fn apply_bounds(&mut self, bounds: PriceBounds) {
+        self.bounds = Some(bounds);
+        if !self.is_within_bounds() {
+            self.warnings
+                .push(PricingWarning::OutsideNoArbitrageBounds {
+                    lower: bounds.lower,
+                    upper: bounds.upper,
+                });
+        }
+    }
+
+    /// Records the number of paths with non-finite values and flags them
+    pub(crate) fn record_non_finite_paths(&mut self, count: u64) {
+        self.non_finite_paths = count;
+        if count > 0 {
+            self.warnings.push(PricingWarning::NonFinitePaths { count });
+        }
+    }
+
+    /// Flags a correlation matrix that was repaired before the simulation
+    pub(crate) fn record_correlation_repair(&mut self, adjustment: Option<f64>) {
+        if let Some(adjustment) = adjustment {
+            self.warnings
+                .push(PricingWarning::CorrelationRepaired { adjustment });
+        }
+    }
+
+    /// Flags quasi-random sampling that only drives the leading dimensions of each path
+    pub(crate) fn record_dimension_budget(&mut self, budget: DimensionBudget) {
+        if budget.is_padded() {
+            self.warnings.push(PricingWarning::QuasiRandomPadding {
+                quasi_random_dimensions: budget.quasi_random as u64,
+                total_dimensions: budget.total as u64,
+            });
+        }
+    }
+
+    /// Flags the estimate if its standard error does not meet the tolerance of an adaptive
+    /// simulation
+    pub(crate) fn check_error_tolerance(&mut self, tolerance: Option<ErrorTolerance>) {
+        if let Some(tolerance) = tolerance {
+            if !tolerance.is_met(self.price, self.std_error) {
+                self.warnings.push(PricingWarning::ErrorToleranceNotMet {
+                    target_std_error: tolerance.target_std_error(self.price),
+                });
+            }
+        }
+    }
+
+    /// Flags the estimate if its standard error exceeds 1% of the price
+    pub(crate) fn check_std_error(&mut self) {
+        let relative_error = self.std_error / self.price.abs();
+        if self.price != 0.0 && relative_error > MAX_RELATIVE_STD_ERROR {
+            self.warnings
+                .push(PricingWarning::HighStandardError { relative_error });
+        }
+    }
+}
+
+/// Leading bytes of every result file
+const MAGIC: &[u8; WORD] = b"MCPRSLT\0";
+
+/// Tag of the record with one pricing result
+const RESULT_RECORD: u64 = 1;
+
+/// Writes pricing results to a file, e.g. to compare them with later runs
+///
+/// The file uses the versioned format of scenario files: results written by one version of
+/// the crate remain readable by later versions, and fields added by later versions are
+/// ignored by earlier ones.
+///
+/// # Arguments
+/// * `path` - File to create or overwrite
+/// * `results` - Results to write
+///
+/// # Errors
+/// Returns `McError::Io` if the file cannot be written.
+pub fn save_results(path: impl AsRef<Path>, results: &[PricingResult]) -> Result<(), McError> {
+    let mut writer = FileWriter::create(path.as_ref(), MAGIC)?;
+    for result in results {
+        let mut fields = Fields::default();
+        fields
+            .value(result.price)
+            .value(result.std_error)
+            .word(result.num_paths)
+            .word(result.non_finite_paths)
+            .flag(result.bounds.is_some());
+        let bounds = result
+            .bounds
+            .map_or((0.0, 0.0), |bounds| (bounds.lower, bounds.upper));
+        fields.value(bounds.0).value(bounds.1);
+        fields.len(result.warnings.len());
+        for warning in &result.warnings {
+            let (kind, warning_fields) = warning.encode();
+            fields.record(kind, &warning_fields);
+        }
+        fields.len(result.audit.len());
+        for entry in &result.audit {
+            fields
+                .text(&entry.setting)
+                .text(&entry.value)
+                .flag(entry.is_default);
+        }
+        writer.record(RESULT_RECORD, &fields, &[])?;
+    }
+    writer.finish()
+}
+
+/// Reads the pricing results of a file written by `save_results`
+///
+/// Warnings of kinds unknown to this version of the crate are dropped.
+///
+/// # Arguments
+/// * `path` - File to read
+///
+/// # Errors
+/// Returns `McError::Io` if the file cannot be read and `McError::InvalidFile` if it is not a
+/// result file of a supported version.
+pub fn load_results(path: impl AsRef<Path>) -> Result<Vec<PricingResult>, McError> {
+    let contents = fs::read(path)?;
+    let mut reader = Reader::new(&contents);
+    reader.preamble(MAGIC)?;
+    let mut results = Vec::new();
+    while !reader.is_empty() {
+        let (tag, mut record) = reader.record()?;
+        if tag == RESULT_RECORD {
+            results.push(read_result(&mut record)?);
+        }
+    }
+    Ok(results)
+}
+
+/// Reads the fields of a result record
+fn read_result(record: &mut Reader) -> Result<PricingResult, McError> {
+    let price = record.value()?;
+    let std_error = record.value()?;
+    let num_paths = record.word()?;
+    let mut result = PricingResult::new(price, std_error, num_paths);
+    result.non_finite_paths = record.word()?;
+    let has_bounds = record.flag()?;
+    let bounds = PriceBounds {
+        lower: record.value()?,
+        upper: record.value()?,
+    };
+    result.bounds = has_bounds.then_some(bounds);
+    let num_warnings = record.len()?;
+    for _ in 0..num_warnings {
+        let (kind, mut fields) = record.record()?;
+        result
+            .warnings
+            .extend(PricingWarning::decode(kind, &mut fields)?);
+    }
+    // Results written before the audit trail was recorded end here
+    if !record.is_empty() {
+        let num_entries = record.len()?;
+        for _ in 0..num_entries {
+            result.audit.push(AuditEntry {
+                setting: record.text()?,
+                value: record.text()?,
+                is_default: record.flag()?,
+            });
+        }
+    }
+    Ok(result)
+}
+
+impl PricingWarning {
+    /// Returns the kind of the warning and its encoded fields
+    fn encode(&self) -> (u64, Fields) {
+        let mut fields = Fields::default();
+        let kind = match *self {
+            PricingWarning::PutCallParity {
+                residual,
+                tolerance,
+            } => {
+                fields.value(residual).value(tolerance);
+                1
+            }
+            PricingWarning::StrikeMonotonicity {
+                bumped_strike,
+                bumped_price,
+            } => {
+                fields.value(bumped_strike).value(bumped_price);
+                2
+            }
+            PricingWarning::BarrierMonotonicity {
+                bumped_level,
+                bumped_price,
+            } => {
+                fields.value(bumped_level).value(bumped_price);
+                3
+            }
+            PricingWarning::OutsideNoArbitrageBounds { lower, upper } => {
+                fields.value(lower).value(upper);
+                4
+            }
+            PricingWarning::HighStandardError { relative_error } => {
+                fields.value(relative_error);
+                5
+            }
+            PricingWarning::BarrierNearSpot {
+                distance,
+                daily_std_dev,
+            } => {
+                fields.value(distance).value(daily_std_dev);
+                6
+            }
+            PricingWarning::NonFinitePaths { count } => {
+                fields.word(count);
+                7
+            }
+            PricingWarning::CorrelationRepaired { adjustment } => {
+                fields.value(adjustment);
+                8
+            }
+            PricingWarning::ErrorToleranceNotMet { target_std_error } => {
+                fields.value(target_std_error);
+                9
+            }
+            PricingWarning::QuasiRandomPadding {
+                quasi_random_dimensions,
+                total_dimensions,
+            } => {
+                fields.word(quasi_random_dimensions).word(total_dimensions);
+                10
+            }
+            PricingWarning::ReducedPaths { requested_paths } => {
+                fields.word(requested_paths);
+                11
+            }
+        };
+        (kind, fields)
+    }
+
+    /// Decodes a warning of the given kind, or returns `None` for kinds of later versions
+    fn decode(kind: u64, fields: &mut Reader) -> Result<Option<Self>, McError> {
+        let warning = match kind {
+            1 => PricingWarning::PutCallParity {
+                residual: fields.value()?,
+                tolerance: fields.value()?,
+            },
+            2 => PricingWarning::StrikeMonotonicity {
+                bumped_strike: fields.value()?,
+                bumped_price: fields.value()?,
+            },
+            3 => PricingWarning::BarrierMonotonicity {
+                bumped_level: fields.value()?,
+                bumped_price: fields.value()?,
+            },
+            4 => PricingWarning::OutsideNoArbitrageBounds {
+                lower: fields.value()?,
+                upper: fields.value()?,
+            },
+            5 => PricingWarning::HighStandardError {
+                relative_error: fields.value()?,
+            },
+            6 => PricingWarning::BarrierNearSpot {
+                distance: fields.value()?,
+                daily_std_dev: fields.value()?,
+            },
+            7 => PricingWarning::NonFinitePaths {
+                count: fields.word()?,
+            },
+            8 => PricingWarning::CorrelationRepaired {
+                adjustment: fields.value()?,
+            },
+            9 => PricingWarning::ErrorToleranceNotMet {
+                target_std_error: fields.value()?,
+            },
+            10 => PricingWarning::QuasiRandomPadding {
+                quasi_random_dimensions: fields.word()?,
+                total_dimensions: fields.word()?,
+            },
+            11 => PricingWarning::ReducedPaths {
+                requested_paths: fields.word()?,
+            },
+            0 => return Err(invalid("unknown warning kind 0")),
+            _ => return Ok(None),
+        };
+        Ok(Some(warning))
+    }
+}
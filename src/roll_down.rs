@@ -0,0 +1,254 @@
+use crate::barrier::BarrierMonitoring;
+use crate::config::SimulationConfig;
+use crate::error::McError;
+use crate::market::MarketSnapshot;
+use crate::payoff::{FixingSchedule, Payoff};
+use crate::product::Product;
+use crate::result::PricingResult;
+use crate::stress::ScenarioPricer;
+use crate::underlying::{Dividend, Underlying, VolatilityEvent};
+use crate::with_start_values;
+
+/// Values of a product on one future day of its life
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RollDownPoint {
+    /// Day the product is revalued on, counted from today
+    pub day: u32,
+    /// Price on that day with today's market unchanged: the same spots, zero rates per
+    /// tenor, volatilities and dividend yields (pure theta roll)
+    pub unchanged: PricingResult,
+    /// Price on that day on the market today's market implies for it: spots at their
+    /// forwards and zero rates at the forward rates
+    pub forward: PricingResult,
+}
+
+/// Expected evolution of a product's value over its life, revalued on future days
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RollDownReport {
+    /// Price of the product today
+    pub base: PricingResult,
+    /// One point per roll day, in the order of the roll days
+    pub points: Vec<RollDownPoint>,
+}
+
+impl RollDownReport {
+    /// Returns the changes of the unchanged and forward-consistent prices against the base
+    /// price per roll day
+    pub fn price_changes(&self) -> Vec<(u32, f64, f64)> {
+        self.points
+            .iter()
+            .map(|point| {
+                (
+                    point.day,
+                    point.unchanged.price - self.base.price,
+                    point.forward.price - self.base.price,
+                )
+            })
+            .collect()
+    }
+}
+
+/// Revalues a product on future days of its life, under unchanged markets and under the
+/// markets today's market implies for those days, the roll-down (aging) of its value
+///
+/// On each roll day, the product has its remaining life to expiry. Under unchanged markets,
+/// the spots, the zero rates per tenor, the flat volatilities and the dividend yields are
+/// today's. Under forward-consistent markets, the spots roll to their forwards for the roll
+/// day and the rate curve to its forward rates (see `RateCurve::rolled`). In both,
+/// volatility term structures, volatility events, discrete dividends and marked forwards
+/// keep their dates, those up to the roll day dropping out, and smiles keep their shape in
+/// moneyness. Prices are values on the roll day, not discounted to today.
+///
+/// The fixings and barrier observations before a roll day are unknown to the report: the
+/// explicit fixing and monitoring dates up to the roll day drop out, fixing schedules
+/// relative to expiry (e.g. `FixingSchedule::Daily`) cover the remaining life only, and
+/// path extremes start at the roll day. Relative barriers keep their level relative to
+/// today's spots. All prices are simulated with the same seed (the configured one, or one
+/// drawn for the whole report).
+///
+/// # Arguments
+/// * `market` - Market today
+/// * `time_horizon_days` - Time to expiration of the product in days
+/// * `product` - Product to revalue
+/// * `roll_days` - Days to revalue the product on, counted from today, before expiry
+/// * `config` - Number of paths and variance reduction settings. The error tolerance and
+///   the sanity checks are ignored, so all prices simulate the same paths.
+///
+/// # Returns
+/// The price today and the unchanged and forward-consistent prices on each roll day
+///
+/// # Errors
+/// Returns `McError::InvalidSchedule` if a roll day is not before expiry or no explicit
+/// fixing date remains after it, or an error for invalid inputs (see `price_option`).
+pub fn roll_down_report(
+    market: &MarketSnapshot,
+    time_horizon_days: u32,
+    product: &Product,
+    roll_days: &[u32],
+    config: &SimulationConfig,
+) -> Result<RollDownReport, McError> {
+    if let Some(&day) = roll_days.iter().find(|&&day| day >= time_horizon_days) {
+        return Err(McError::InvalidSchedule(format!(
+            "Roll day {} must be before expiry on day {}",
+            day, time_horizon_days
+        )));
+    }
+    let market = MarketSnapshot {
+        underlyings: with_start_values(&market.underlyings, config)?.into_owned(),
+        ..market.clone()
+    };
+    let config = SimulationConfig {
+        seed: Some(config.seed.unwrap_or_else(crate::draw_seed)),
+        start_values: None,
+        ..config.clone()
+    };
+    let spot_prices: Vec<f64> = market.underlyings.iter().map(|u| u.spot_price).collect();
+    let product = product.canonicalize(&spot_prices)?;
+
+    let price = |market: &MarketSnapshot, days: u32, product: &Product| {
+        let pricer = ScenarioPricer::new(market, days, product, &config)?;
+        pricer.price(&pricer.market, pricer.barrier.as_ref(), 0.0)
+    };
+    let points = roll_days
+        .iter()
+        .map(|&day| {
+            let remaining_days = time_horizon_days - day;
+            let product = aged_product(&product, day)?;
+            Ok(RollDownPoint {
+                day,
+                unchanged: price(
+                    &rolled_market(&market, day, false, &config),
+                    remaining_days,
+                    &product,
+                )?,
+                forward: price(
+                    &rolled_market(&market, day, true, &config),
+                    remaining_days,
+                    &product,
+                )?,
+            })
+        })
+        .collect::<Result<Vec<_>, McError>>()?;
+    Ok(RollDownReport {
+        base: price(&market, time_horizon_days, &product)?,
+        points,
+    })
+}
+
+/// Returns the market `days` days from today, with the spots at their forwards and the
+/// rates at their forward rates if `forward_consistent`, and today's spots and zero rates
+/// otherwise
+fn rolled_market(
+    market: &MarketSnapshot,
+    days: u32,
+    forward_consistent: bool,
+    config: &SimulationConfig,
+) -> MarketSnapshot {
+    let time = config.day_count.year_fraction(days);
+    let rate_curve = &market.risk_free_rate;
+    let underlyings = market
+        .underlyings
+        .iter()
+        .map(|underlying| {
+            let spot_price = if forward_consistent {
+                underlying.prepaid_forward(rate_curve, time, config.day_count)
+                    / rate_curve.discount_factor(time)
+            } else {
+                underlying.spot_price
+            };
+            rolled_underlying(underlying, days, spot_price)
+        })
+        .collect();
+    MarketSnapshot {
+        underlyings,
+        correlation_matrix: market.correlation_matrix.clone(),
+        risk_free_rate: if forward_consistent {
+            rate_curve.rolled(time)
+        } else {
+            rate_curve.clone()
+        },
+    }
+}
+
+/// Returns the underlying `days` days from today at the given spot price, with its dated
+/// data counted from then
+fn rolled_underlying(underlying: &Underlying, days: u32, spot_price: f64) -> Underlying {
+    // Data dated on the roll day or before is past
+    let roll = |day: u32| day.checked_sub(days).filter(|&day| day > 0);
+    let volatility_term_structure: Vec<(u32, f64)> = underlying
+        .volatility_term_structure
+        .iter()
+        .filter_map(|&(end_day, volatility)| Some((roll(end_day)?, volatility)))
+        .collect();
+    // The last forward volatility applies beyond the term structure, also once it is past
+    let volatility = match underlying.volatility_term_structure.last() {
+        Some(&(_, volatility)) if volatility_term_structure.is_empty() => volatility,
+        _ => underlying.volatility,
+    };
+    let volatility_events = underlying
+        .volatility_events
+        .iter()
+        .filter_map(|event| Some(VolatilityEvent::new(roll(event.day)?, event.std_dev)))
+        .collect();
+    let dividends = underlying
+        .dividends
+        .iter()
+        .filter_map(|dividend| {
+            let day = roll(dividend.day())?;
+            Some(match *dividend {
+                Dividend::Cash { amount, .. } => Dividend::Cash { day, amount },
+                Dividend::Proportional { ratio, .. } => Dividend::Proportional { day, ratio },
+            })
+        })
+        .collect();
+    Underlying {
+        spot_price,
+        volatility,
+        volatility_term_structure,
+        volatility_events,
+        dividends,
+        forward_curve: underlying
+            .forward_curve
+            .iter()
+            .filter_map(|&(day, forward)| Some((roll(day)?, forward)))
+            .collect(),
+        ..underlying.clone()
+    }
+}
+
+/// Returns the product `days` days from today, with its explicit fixing and monitoring
+/// dates counted from then and the past ones dropped
+fn aged_product(product: &Product, days: u32) -> Result<Product, McError> {
+    let roll = |dates: &[u32]| -> Vec<u32> {
+        dates
+            .iter()
+            .filter(|&&day| day > days)
+            .map(|&day| day - days)
+            .collect()
+    };
+    let mut product = product.clone();
+    if let Payoff::AveragePrice { schedule, .. }
+    | Payoff::AverageStrike { schedule, .. }
+    | Payoff::Cliquet { schedule, .. }
+    | Payoff::VarianceSwap { schedule, .. }
+    | Payoff::VolatilitySwap { schedule, .. } = &mut product.payoff
+    {
+        if let FixingSchedule::Dates(dates) = schedule {
+            *dates = roll(dates);
+            if dates.is_empty() {
+                return Err(McError::InvalidSchedule(format!(
+                    "No fixing date remains after day {}",
+                    days
+                )));
+            }
+        }
+    }
+    if let Some(barrier) = &mut product.barrier {
+        if let BarrierMonitoring::Dates(dates) = &mut barrier.monitoring {
+            *dates = roll(dates);
+        }
+    }
+    Ok(product)
+}
@@ -0,0 +1,382 @@
+use std::fs::File;
+#[cfg(all(unix, target_endian = "little"))]
+use std::io;
+use std::ops::Deref;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::barrier::{Barrier, BarrierDirection, BarrierMonitoring, BarrierType, KnockType};
+use crate::error::McError;
+use crate::format::{invalid, Fields, FileWriter, Reader, WORD};
+use crate::session::{MarketShift, PathCache};
+
+/// Leading bytes of every scenario file
+const MAGIC: &[u8; WORD] = b"MCPSCEN\0";
+
+/// Tag of the record with the session header
+const HEADER_RECORD: u64 = 1;
+
+/// Tag of the record with the fields and columns of one path set
+const PATH_SET_RECORD: u64 = 2;
+
+/// Parameters of the session the scenarios were simulated for, checked before a session
+/// reuses them
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ScenarioHeader {
+    pub(crate) time_horizon_days: u32,
+    pub(crate) seed: u64,
+    pub(crate) num_paths: u64,
+    pub(crate) antithetic: bool,
+    pub(crate) spot_prices: Vec<f64>,
+}
+
+/// Values recorded for every path, held in memory or mapped from a scenario file
+pub(crate) enum Column {
+    Owned(Vec<f64>),
+    #[cfg(all(unix, target_endian = "little"))]
+    Mapped {
+        mapping: Arc<Mapping>,
+        /// Byte offset of the first value in the mapping
+        offset: usize,
+        len: usize,
+    },
+}
+
+impl From<Vec<f64>> for Column {
+    fn from(values: Vec<f64>) -> Self {
+        Column::Owned(values)
+    }
+}
+
+impl Deref for Column {
+    type Target = [f64];
+
+    fn deref(&self) -> &[f64] {
+        match self {
+            Column::Owned(values) => values,
+            #[cfg(all(unix, target_endian = "little"))]
+            Column::Mapped {
+                mapping,
+                offset,
+                len,
+            } => {
+                let bytes = &mapping[*offset..*offset + *len * WORD];
+                // SAFETY: the mapping is page-aligned and the offset a multiple of the word
+                // size, so the bytes are aligned for `f64`, and every bit pattern is a valid
+                // `f64`. The mapping is read-only and outlives the column.
+                unsafe { std::slice::from_raw_parts(bytes.as_ptr().cast::<f64>(), *len) }
+            }
+        }
+    }
+}
+
+/// Read-only shared memory mapping of a whole file
+///
+/// Processes mapping the same file share its pages through the page cache, and pages are only
+/// read from disk when a path is priced. The file must not be truncated while it is mapped.
+#[cfg(all(unix, target_endian = "little"))]
+pub(crate) struct Mapping {
+    address: *mut libc::c_void,
+    len: usize,
+}
+
+// SAFETY: the mapping is read-only and never changes, so it can be read from any thread
+#[cfg(all(unix, target_endian = "little"))]
+unsafe impl Send for Mapping {}
+#[cfg(all(unix, target_endian = "little"))]
+unsafe impl Sync for Mapping {}
+
+#[cfg(all(unix, target_endian = "little"))]
+impl Mapping {
+    /// Maps the non-empty file into memory
+    fn new(file: &File, len: usize) -> io::Result<Self> {
+        use std::os::unix::io::AsRawFd;
+
+        // SAFETY: a fresh read-only mapping of the file does not alias any Rust memory
+        let address = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if address == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self { address, len })
+    }
+}
+
+#[cfg(all(unix, target_endian = "little"))]
+impl Deref for Mapping {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        // SAFETY: the mapping covers `len` readable bytes until it is dropped
+        unsafe { std::slice::from_raw_parts(self.address.cast::<u8>(), self.len) }
+    }
+}
+
+#[cfg(all(unix, target_endian = "little"))]
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        // SAFETY: the mapping was created by `mmap` with this address and length, and no
+        // column refers to it anymore
+        unsafe {
+            libc::munmap(self.address, self.len);
+        }
+    }
+}
+
+/// Contents of an opened scenario file: mapped on little-endian Unix targets, read into memory
+/// elsewhere
+#[cfg(all(unix, target_endian = "little"))]
+type Contents = Arc<Mapping>;
+#[cfg(not(all(unix, target_endian = "little")))]
+type Contents = Vec<u8>;
+
+/// Writes the header and the recorded path sets of a session to a scenario file
+///
+/// The file holds a record with the session header and a record per path set with its market
+/// shift, correlation adjustment, fixing days and barrier reference followed by its columns
+/// of path values.
+pub(crate) fn write_scenarios(
+    path: &Path,
+    header: &ScenarioHeader,
+    sets: &[(MarketShift, Arc<PathCache>)],
+) -> Result<(), McError> {
+    let mut writer = FileWriter::create(path, MAGIC)?;
+    let mut fields = Fields::default();
+    fields
+        .word(u64::from(header.time_horizon_days))
+        .word(header.seed)
+        .word(header.num_paths)
+        .flag(header.antithetic)
+        .values(&header.spot_prices);
+    writer.record(HEADER_RECORD, &fields, &[])?;
+
+    for (shift, cache) in sets {
+        let (kind, amount) = match *shift {
+            MarketShift::None => (0, 0.0),
+            MarketShift::Spot(amount) => (1, amount),
+            MarketShift::Volatility(amount) => (2, amount),
+            MarketShift::Rate(amount) => (3, amount),
+            MarketShift::FxSpot(amount) => (4, amount),
+            MarketShift::FxVolatility(amount) => (5, amount),
+        };
+        let mut fields = Fields::default();
+        fields
+            .word(kind)
+            .value(amount)
+            .flag(cache.correlation_adjustment.is_some())
+            .value(cache.correlation_adjustment.unwrap_or(0.0))
+            .days(&cache.fixing_days);
+        write_barrier_reference(&mut fields, cache.barrier.as_ref());
+        fields.len(cache.final_prices.len());
+        let columns = [
+            &*cache.final_prices,
+            &*cache.running_maxima,
+            &*cache.running_minima,
+            &*cache.fixings,
+            &*cache.reference_minima,
+            &*cache.reference_maxima,
+        ];
+        writer.record(PATH_SET_RECORD, &fields, &columns)?;
+    }
+    writer.finish()
+}
+
+/// Opens a scenario file written by `write_scenarios` of this or an earlier version, mapping
+/// its path values into memory where supported
+///
+/// # Errors
+/// Returns `McError::Io` if the file cannot be read or mapped and `McError::InvalidFile` if it
+/// is not a scenario file of a supported version.
+pub(crate) fn read_scenarios(
+    path: &Path,
+) -> Result<(ScenarioHeader, Vec<(MarketShift, PathCache)>), McError> {
+    let contents = open(path)?;
+    let mut reader = Reader::new(&contents);
+    // Version 1 wrote the header and the path sets one after the other, without records
+    if reader.preamble(MAGIC)? == 1 {
+        let header = read_header(&mut reader)?;
+        let num_sets = reader.len()?;
+        let sets = (0..num_sets)
+            .map(|_| read_path_set(&mut reader, &contents))
+            .collect::<Result<_, _>>()?;
+        if !reader.is_empty() {
+            return Err(invalid("unexpected data after the last path set"));
+        }
+        return Ok((header, sets));
+    }
+
+    let mut header = None;
+    let mut sets = Vec::new();
+    while !reader.is_empty() {
+        let (tag, mut record) = reader.record()?;
+        match tag {
+            HEADER_RECORD => header = Some(read_header(&mut record)?),
+            PATH_SET_RECORD => sets.push(read_path_set(&mut record, &contents)?),
+            // Records of later versions
+            _ => {}
+        }
+    }
+    let header = header.ok_or_else(|| invalid("scenario file has no header"))?;
+    Ok((header, sets))
+}
+
+/// Reads the parameters of the session the scenarios were simulated for
+fn read_header(reader: &mut Reader) -> Result<ScenarioHeader, McError> {
+    Ok(ScenarioHeader {
+        time_horizon_days: reader.day()?,
+        seed: reader.word()?,
+        num_paths: reader.word()?,
+        antithetic: reader.flag()?,
+        spot_prices: reader.values()?,
+    })
+}
+
+/// Reads the fields and columns of a path set, referring to the columns in the contents
+fn read_path_set(
+    reader: &mut Reader,
+    contents: &Contents,
+) -> Result<(MarketShift, PathCache), McError> {
+    let kind = reader.word()?;
+    let amount = reader.value()?;
+    let shift = match kind {
+        0 => MarketShift::None,
+        1 => MarketShift::Spot(amount),
+        2 => MarketShift::Volatility(amount),
+        3 => MarketShift::Rate(amount),
+        4 => MarketShift::FxSpot(amount),
+        5 => MarketShift::FxVolatility(amount),
+        _ => return Err(invalid(&format!("unknown market shift {}", kind))),
+    };
+    let has_adjustment = reader.flag()?;
+    let adjustment = reader.value()?;
+    let fixing_days = reader.days()?;
+    let barrier = read_barrier_reference(reader)?;
+
+    let num_paths = reader.len()?;
+    let num_values = num_paths
+        .checked_mul(fixing_days.len())
+        .ok_or_else(|| invalid("fixings exceed the addressable memory"))?;
+    let mut next_column = |len: usize| {
+        let size = len
+            .checked_mul(WORD)
+            .ok_or_else(|| invalid("file is truncated"))?;
+        Ok::<_, McError>(column(contents, reader.skip(size)?, len))
+    };
+    let cache = PathCache {
+        final_prices: next_column(num_paths)?,
+        running_maxima: next_column(num_paths)?,
+        running_minima: next_column(num_paths)?,
+        fixings: next_column(num_values)?,
+        reference_minima: next_column(num_paths)?,
+        reference_maxima: next_column(num_paths)?,
+        fixing_days,
+        barrier,
+        correlation_adjustment: has_adjustment.then_some(adjustment),
+    };
+    Ok((shift, cache))
+}
+
+/// Encodes the barrier type, underlyings and monitoring days a path set recorded the
+/// reference extremes for
+fn write_barrier_reference(fields: &mut Fields, barrier: Option<&Barrier>) {
+    let Some(barrier) = barrier else {
+        fields.flag(false);
+        return;
+    };
+    fields.flag(true).word(match barrier.barrier_type {
+        BarrierType::WorstOf => 0,
+        BarrierType::BestOf => 1,
+        BarrierType::Average => 2,
+        BarrierType::Median => 3,
+    });
+    fields.len(barrier.underlying_indices.len());
+    for &index in &barrier.underlying_indices {
+        fields.len(index);
+    }
+    match &barrier.monitoring {
+        BarrierMonitoring::Continuous => fields.word(0),
+        BarrierMonitoring::Dates(days) => fields.word(1).days(days),
+        BarrierMonitoring::AtExpiry => fields.word(2),
+    };
+}
+
+/// Reads a barrier reference written by `write_barrier_reference`, as a barrier whose level
+/// and direction do not matter
+fn read_barrier_reference(reader: &mut Reader) -> Result<Option<Barrier>, McError> {
+    if !reader.flag()? {
+        return Ok(None);
+    }
+    let barrier_type = match reader.word()? {
+        0 => BarrierType::WorstOf,
+        1 => BarrierType::BestOf,
+        2 => BarrierType::Average,
+        3 => BarrierType::Median,
+        kind => return Err(invalid(&format!("unknown barrier type {}", kind))),
+    };
+    let num_indices = reader.len()?;
+    let underlying_indices = (0..num_indices)
+        .map(|_| reader.len())
+        .collect::<Result<Vec<_>, _>>()?;
+    let monitoring = match reader.word()? {
+        0 => BarrierMonitoring::Continuous,
+        1 => BarrierMonitoring::Dates(reader.days()?),
+        2 => BarrierMonitoring::AtExpiry,
+        kind => return Err(invalid(&format!("unknown barrier monitoring {}", kind))),
+    };
+    Ok(Some(Barrier {
+        barrier_type,
+        underlying_indices,
+        monitoring,
+        ..Barrier::single(1.0, BarrierDirection::Down, KnockType::Out, false)
+    }))
+}
+
+/// Opens the file and maps or reads its contents
+fn open(path: &Path) -> Result<Contents, McError> {
+    let file = File::open(path)?;
+    let len = usize::try_from(file.metadata()?.len())
+        .map_err(|_| invalid("file exceeds the addressable memory"))?;
+    if len < MAGIC.len() {
+        return Err(invalid("unknown file type"));
+    }
+    #[cfg(all(unix, target_endian = "little"))]
+    let contents = Arc::new(Mapping::new(&file, len)?);
+    #[cfg(not(all(unix, target_endian = "little")))]
+    let contents = {
+        use std::io::Read;
+
+        let mut contents = Vec::with_capacity(len);
+        (&file).read_to_end(&mut contents)?;
+        contents
+    };
+    Ok(contents)
+}
+
+/// Returns the `len` values at the byte offset of the mapped contents, without reading them
+#[cfg(all(unix, target_endian = "little"))]
+fn column(contents: &Contents, offset: usize, len: usize) -> Column {
+    Column::Mapped {
+        mapping: Arc::clone(contents),
+        offset,
+        len,
+    }
+}
+
+/// Decodes the `len` values at the byte offset of the contents
+#[cfg(not(all(unix, target_endian = "little")))]
+fn column(contents: &Contents, offset: usize, len: usize) -> Column {
+    Column::Owned(
+        contents[offset..offset + len * WORD]
+            .chunks_exact(WORD)
+            .map(|value| f64::from_le_bytes(value.try_into().expect("word of 8 bytes")))
+            .collect(),
+    )
+}
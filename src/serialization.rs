@@ -0,0 +1,95 @@
+use nalgebra::DMatrix;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Serialization of a matrix as the list of its rows, e.g. `[[1.0, 0.5], [0.5, 1.0]]` for a
+/// correlation matrix; rows of different lengths are rejected
+pub(crate) mod matrix_rows {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(
+        matrix: &DMatrix<f64>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        rows(matrix).serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<DMatrix<f64>, D::Error> {
+        from_rows(Vec::deserialize(deserializer)?)
+    }
+}
+
+/// Serialization of an optional matrix as the list of its rows, or none
+pub(crate) mod optional_matrix_rows {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(
+        matrix: &Option<DMatrix<f64>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        matrix.as_ref().map(rows).serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<DMatrix<f64>>, D::Error> {
+        Option::<Vec<Vec<f64>>>::deserialize(deserializer)?
+            .map(from_rows)
+            .transpose()
+    }
+}
+
+/// Serialization of a value that may not be finite: finite values as numbers, the others as
+/// `"inf"`, `"-inf"` or `"NaN"`, which formats like JSON cannot represent as numbers
+pub(crate) mod extended_float {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(untagged)]
+    enum ExtendedFloat {
+        Finite(f64),
+        NonFinite(String),
+    }
+
+    pub(crate) fn serialize<S: Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+        let value = if value.is_finite() {
+            ExtendedFloat::Finite(*value)
+        } else {
+            ExtendedFloat::NonFinite(value.to_string())
+        };
+        value.serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+        match ExtendedFloat::deserialize(deserializer)? {
+            ExtendedFloat::Finite(value) => Ok(value),
+            ExtendedFloat::NonFinite(name) => match name.as_str() {
+                "inf" => Ok(f64::INFINITY),
+                "-inf" => Ok(f64::NEG_INFINITY),
+                "NaN" => Ok(f64::NAN),
+                _ => Err(D::Error::custom(format!("invalid number {:?}", name))),
+            },
+        }
+    }
+}
+
+fn rows(matrix: &DMatrix<f64>) -> Vec<Vec<f64>> {
+    matrix
+        .row_iter()
+        .map(|row| row.iter().copied().collect())
+        .collect()
+}
+
+fn from_rows<E: Error>(rows: Vec<Vec<f64>>) -> Result<DMatrix<f64>, E> {
+    let num_columns = rows.first().map_or(0, Vec::len);
+    if rows.iter().any(|row| row.len() != num_columns) {
+        return Err(E::custom("matrix rows must have the same length"));
+    }
+    Ok(DMatrix::from_row_iterator(
+        rows.len(),
+        num_columns,
+        rows.into_iter().flatten(),
+    ))
+}
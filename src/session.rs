@@ -0,0 +1,1108 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, PoisonError, RwLock};
+
+use crate::barrier::{Barrier, BarrierCorrection, BarrierDirection, KnockType, RebateTiming};
+use crate::calendar::Date;
+use crate::config::SimulationConfig;
+use crate::engine::{self, PathEngine, PathState};
+use crate::error::McError;
+use crate::market::MarketSnapshot;
+use crate::math;
+use crate::payoff::{PathObservables, Payoff};
+use crate::product::Product;
+use crate::qmc::DimensionBudget;
+use crate::quotation::GreekConvention;
+use crate::result::PricingResult;
+use crate::scenario::{self, Column, ScenarioHeader};
+use crate::smile::SmileDynamics;
+use crate::statistics::{weighted_percentiles, ChunkedStatistics};
+use crate::underlying::Underlying;
+use crate::validation;
+use crate::{
+    attach_diagnostics, calculate_reference, control_expectation, effective_barrier_level,
+    intrinsic_value, path_weight, simulate_payoff, with_shocked_smile_volatility,
+    with_start_values,
+};
+
+/// Observables of all simulated paths of a session, recorded for one fixing schedule and one
+/// barrier reference, in memory or mapped from a scenario file
+pub(crate) struct PathCache {
+    /// Fixing days recorded on every path
+    pub(crate) fixing_days: Vec<u32>,
+    /// Barrier whose reference extremes are recorded; its level and direction do not matter
+    pub(crate) barrier: Option<Barrier>,
+    /// Price of the first underlying at expiry of each path
+    pub(crate) final_prices: Column,
+    /// Highest price of the first underlying of each path
+    pub(crate) running_maxima: Column,
+    /// Lowest price of the first underlying of each path
+    pub(crate) running_minima: Column,
+    /// Fixings of the first underlying, `fixing_days.len()` per path
+    pub(crate) fixings: Column,
+    /// Lowest barrier reference value on the monitoring days of each path
+    pub(crate) reference_minima: Column,
+    /// Highest barrier reference value on the monitoring days of each path
+    pub(crate) reference_maxima: Column,
+    /// Adjustment of the correlation matrix if it was repaired
+    pub(crate) correlation_adjustment: Option<f64>,
+}
+
+impl PathCache {
+    /// Returns `true` if the cache records everything the payoff with the given fixing days
+    /// and the barrier depend on
+    fn records(&self, fixing_days: &[u32], barrier: Option<&Barrier>) -> bool {
+        let has_fixings = fixing_days.is_empty() || fixing_days == self.fixing_days;
+        let has_barrier = match (barrier, &self.barrier) {
+            (None, _) => true,
+            (Some(barrier), Some(recorded)) => {
+                barrier.barrier_type == recorded.barrier_type
+                    && barrier.underlying_indices == recorded.underlying_indices
+                    && barrier.monitoring == recorded.monitoring
+            }
+            (Some(_), None) => false,
+        };
+        has_fixings && has_barrier
+    }
+
+    /// Returns the payoff observables of the given path, which started at the given price
+    fn observables(&self, path: usize, initial_price: f64) -> PathObservables<'_> {
+        let num_fixings = self.fixing_days.len();
+        PathObservables {
+            initial_price,
+            final_price: self.final_prices[path],
+            running_max: self.running_maxima[path],
+            running_min: self.running_minima[path],
+            fixings: &self.fixings[path * num_fixings..(path + 1) * num_fixings],
+        }
+    }
+}
+
+/// Relative bump of the spot prices for delta and gamma
+const SPOT_BUMP: f64 = 0.01;
+
+/// Absolute bump of the volatilities for vega
+const VOLATILITY_BUMP: f64 = 0.01;
+
+/// Absolute bump of the zero rates for rho
+const RATE_BUMP: f64 = 0.0001;
+
+/// Parallel shift of the session market that a set of paths is simulated on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum MarketShift {
+    /// The session market itself
+    None,
+    /// Spot prices and marked forwards of all underlyings scaled by `1 + shift`, their smiles
+    /// re-marked according to their smile dynamics
+    Spot(f64),
+    /// Volatilities of all underlyings, term structures included, shifted by the amount
+    Volatility(f64),
+    /// Zero rates shifted by the amount
+    Rate(f64),
+    /// Spot prices and marked forwards of the exchange rates scaled by `1 + shift`, like
+    /// `Spot`
+    FxSpot(f64),
+    /// Volatilities of the exchange rates, term structures included, and the exchange rate
+    /// volatilities of the quanto terms shifted by the amount
+    FxVolatility(f64),
+}
+
+impl MarketShift {
+    /// Returns a copy of the market with the shift applied
+    pub(crate) fn apply(&self, market: &MarketSnapshot) -> MarketSnapshot {
+        let mut market = market.clone();
+        match *self {
+            MarketShift::None => {}
+            MarketShift::Spot(shift) => {
+                for underlying in &mut market.underlyings {
+                    shift_spot(underlying, shift);
+                }
+            }
+            MarketShift::Volatility(shift) => {
+                for underlying in &mut market.underlyings {
+                    shift_volatility(underlying, shift);
+                }
+            }
+            MarketShift::Rate(shift) => {
+                market.risk_free_rate = market.risk_free_rate.shifted(shift);
+            }
+            MarketShift::FxSpot(shift) => {
+                for underlying in market.underlyings.iter_mut().filter(|u| u.is_fx()) {
+                    shift_spot(underlying, shift);
+                }
+            }
+            MarketShift::FxVolatility(shift) => {
+                for underlying in &mut market.underlyings {
+                    if underlying.is_fx() {
+                        shift_volatility(underlying, shift);
+                    }
+                    if let Some(quanto) = &mut underlying.quanto {
+                        quanto.fx_volatility += shift;
+                    }
+                }
+            }
+        }
+        market
+    }
+}
+
+/// Scales the spot price and the marked forwards of the underlying by `1 + shift`,
+/// re-marking its smile according to its smile dynamics
+fn shift_spot(underlying: &mut Underlying, shift: f64) {
+    underlying.spot_price *= 1.0 + shift;
+    for (_, forward) in &mut underlying.forward_curve {
+        *forward *= 1.0 + shift;
+    }
+    // The log-moneyness of each strike falls by the log of the spot move
+    if underlying.smile_dynamics == SmileDynamics::StickyStrike {
+        underlying.smile = underlying
+            .smile
+            .map(|smile| smile.shifted(math::ln(1.0 + shift)));
+    }
+}
+
+/// Shifts the volatility of the underlying, term structure included, by the amount
+fn shift_volatility(underlying: &mut Underlying, shift: f64) {
+    underlying.volatility += shift;
+    for (_, volatility) in &mut underlying.volatility_term_structure {
+        *volatility += shift;
+    }
+}
+
+/// Sensitivities of a product's price, estimated by central differences of prices on bumped
+/// markets simulated with the same random numbers
+///
+/// The sensitivities are in the units of `convention`, by default the mathematical
+/// derivatives per unit of the respective input. Use `in_convention` for market conventions.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Greeks {
+    /// Price of the product on the session market
+    pub pricing: PricingResult,
+    /// Derivative of the price with respect to the spot of the first underlying, with the
+    /// spots of all underlyings moving proportionally
+    pub delta: f64,
+    /// Second derivative of the price with respect to the spot of the first underlying, with
+    /// the spots of all underlyings moving proportionally
+    pub gamma: f64,
+    /// Derivative of the price with respect to a parallel shift of all volatilities (per unit
+    /// of volatility). Models with their own volatility dynamics do not use these volatilities.
+    pub vega: f64,
+    /// Derivative of the price with respect to a parallel shift of the zero rates (per unit
+    /// of rate)
+    pub rho: f64,
+    /// Derivative of the price with respect to the spot of the first exchange rate among the
+    /// underlyings, with all exchange rates moving proportionally; zero without exchange rates
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fx_delta: f64,
+    /// Derivative of the price with respect to a parallel shift of the volatilities of the
+    /// exchange rates, those of the quanto terms included (per unit of volatility); zero
+    /// without exposure to exchange rates
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fx_vega: f64,
+    /// Spot of the first underlying the sensitivities were computed at
+    pub spot_price: f64,
+    /// Spot of the first exchange rate among the underlyings the FX delta was computed at
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fx_spot_price: Option<f64>,
+    /// Units the sensitivities are expressed in
+    pub convention: GreekConvention,
+}
+
+impl Greeks {
+    /// Returns the Greeks of a position of `quantity` units of the product, negative for
+    /// short positions (see `PricingResult::scaled`)
+    pub fn scaled(&self, quantity: f64) -> Self {
+        Self {
+            pricing: self.pricing.scaled(quantity),
+            delta: self.delta * quantity,
+            gamma: self.gamma * quantity,
+            vega: self.vega * quantity,
+            rho: self.rho * quantity,
+            fx_delta: self.fx_delta * quantity,
+            fx_vega: self.fx_vega * quantity,
+            ..self.clone()
+        }
+    }
+}
+
+/// Greeks of a portfolio of products priced on the same paths
+///
+/// Every position is priced on the same random numbers for the session market and for each
+/// bumped market, so the sensitivities of the positions add up to those of the portfolio.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PortfolioGreeks {
+    /// Greeks of each position per unit of its product, in the order of the positions
+    pub positions: Vec<Greeks>,
+    /// Quantity-weighted sums of the Greeks of the positions. The standard error of the total
+    /// price is the quantity-weighted sum of the standard errors, an upper bound of the error
+    /// of the portfolio since the prices are correlated.
+    pub total: Greeks,
+}
+
+/// Price of a product on a shifted market, memoized by a session
+type MemoizedResult = (MarketShift, Product, PricingResult);
+
+/// Pricing session that simulates the paths of a market once and reprices products on them
+///
+/// The session records the observables of every path (terminal price, running extremes and
+/// fixings of the first underlying, extremes of a barrier reference), so products differing
+/// only in strike, barrier level or direction, in/out flag or payoff type are repriced
+/// without simulating again. A product needing other observables (a new fixing schedule or
+/// barrier reference) simulates the same paths again with the session's seed, recording
+/// them as well, so all prices of a session share common random numbers. Greeks and spot
+/// ladders price on shifted markets, whose paths are recorded in the same way.
+///
+/// The recorded paths can be saved to a scenario file with `save_scenarios`, e.g. by an
+/// overnight job, and loaded into sessions of other processes with `load_scenarios`. Loaded
+/// paths are memory-mapped where supported, so they are neither simulated again nor copied
+/// into the memory of each process.
+///
+/// Paths take one step per day and all observables are held in memory. Barriers are checked
+/// on the daily closes; continuously monitored barriers with a configured monitoring
+/// correction and rebates paid at the hit are priced by a full simulation on the same seed
+/// instead. The sanity checks are skipped.
+///
+/// The session is `Send + Sync` and prices through shared references, so one session (e.g.
+/// in an `Arc`) can serve concurrent requests. Recorded paths are immutable and shared by
+/// all requests; a request needing new observables simulates without blocking the others
+/// and then publishes its paths. Concurrent requests needing new observables of the same
+/// market may both simulate, and the paths published last replace the others.
+///
+/// All paths of a session, including those of full simulations, are simulated from the
+/// session's seed with the configured number of paths, so an error tolerance of the
+/// configuration is ignored.
+///
+/// The session memoizes its prices by the canonical form of the product (see
+/// `Product::canonicalize`) and the market shift, so identical products (e.g. the same retail
+/// trade held in many portfolios, booked with relative or absolute barriers) are priced once,
+/// and their Greeks and ladders reuse the prices on the bumped markets.
+pub struct PricingSession {
+    /// Market of the session, with the start values of the configuration applied
+    market: MarketSnapshot,
+    time_horizon_days: u32,
+    config: SimulationConfig,
+    /// Recorded paths of the session market and of its shifted markets
+    caches: RwLock<Vec<(MarketShift, Arc<PathCache>)>>,
+    /// Prices of the products on the shifted markets, keyed by their fingerprints
+    results: RwLock<HashMap<u64, Vec<MemoizedResult>>>,
+    num_simulations: AtomicUsize,
+}
+
+impl PricingSession {
+    /// Creates a session for products expiring after `time_horizon_days` on the given market
+    ///
+    /// Without a seed in the configuration, the session draws one, so all of its prices are
+    /// computed on the same random numbers.
+    ///
+    /// # Errors
+    /// Returns an error for invalid market data or path counts (see `price_option`).
+    pub fn new(
+        market: MarketSnapshot,
+        time_horizon_days: u32,
+        config: &SimulationConfig,
+    ) -> Result<Self, McError> {
+        let underlyings = with_start_values(&market.underlyings, config)?.into_owned();
+        validation::validate_inputs(&underlyings, &market.correlation_matrix, config)?;
+        if let Some(path_weights) = &config.path_weights {
+            let paths_per_sample = engine::shock_signs(config.antithetic).len() as u64;
+            validation::validate_path_weights(
+                path_weights,
+                config.num_paths.div_ceil(paths_per_sample) * paths_per_sample,
+            )?;
+        }
+        let config = SimulationConfig {
+            seed: Some(config.seed.unwrap_or_else(crate::draw_seed)),
+            validate: false,
+            start_values: None,
+            error_tolerance: None,
+            ..config.clone()
+        };
+        Ok(Self {
+            market: MarketSnapshot {
+                underlyings,
+                ..market
+            },
+            time_horizon_days,
+            config,
+            caches: RwLock::new(Vec::new()),
+            results: RwLock::new(HashMap::new()),
+            num_simulations: AtomicUsize::new(0),
+        })
+    }
+
+    /// Creates a session for products maturing on `maturity`, converted into days from the
+    /// valuation date with the day count convention and calendar of the configuration (see
+    /// `new`)
+    ///
+    /// # Errors
+    /// Returns `McError::InvalidDate` if the maturity lies before the valuation date, or an
+    /// error for invalid market data or path counts (see `price_option`).
+    pub fn on_dates(
+        market: MarketSnapshot,
+        valuation: Date,
+        maturity: Date,
+        config: &SimulationConfig,
+    ) -> Result<Self, McError> {
+        let time_horizon_days = config.days_between(valuation, maturity)?;
+        Self::new(market, time_horizon_days, config)
+    }
+
+    /// Returns the market the session prices on, with the start values of the configuration
+    /// as spot prices
+    pub fn market(&self) -> &MarketSnapshot {
+        &self.market
+    }
+
+    /// Returns the number of times the session simulated paths so far
+    pub fn num_simulations(&self) -> usize {
+        self.num_simulations.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of prices the session memoized so far, one per distinct product
+    /// and (bumped) market
+    pub fn num_memoized_results(&self) -> usize {
+        self.results
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .values()
+            .map(Vec::len)
+            .sum()
+    }
+
+    /// Discards the memoized prices, e.g. to bound the memory of a long-lived session; the
+    /// recorded paths are kept
+    pub fn clear_memoized_results(&self) {
+        self.results
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+
+    /// Writes the recorded paths of the session and of its shifted markets to a scenario file
+    ///
+    /// If no paths of the session market are recorded yet, they are simulated first, without
+    /// fixings or barrier reference. Price the products of interest (and their Greeks) before
+    /// saving, so the file holds all observables they need.
+    ///
+    /// # Arguments
+    /// * `path` - File to create or overwrite
+    ///
+    /// # Errors
+    /// Returns `McError::Io` if the file cannot be written, or an error under the same
+    /// conditions as `price` if the paths need to be simulated.
+    pub fn save_scenarios(&self, path: impl AsRef<Path>) -> Result<(), McError> {
+        self.recorded_paths(&self.market, MarketShift::None, Vec::new(), None)?;
+        let caches = self
+            .caches
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        scenario::write_scenarios(path.as_ref(), &self.scenario_header(), &caches)
+    }
+
+    /// Loads the paths of a scenario file written by `save_scenarios`, replacing the recorded
+    /// paths of the same markets
+    ///
+    /// Files written by earlier versions of the crate remain readable. Files of later versions
+    /// are read if their format is compatible, ignoring data this version does not know.
+    ///
+    /// The paths are memory-mapped on little-endian Unix targets and read into memory
+    /// elsewhere. The file must have been saved by a session with the same market and
+    /// configuration; only the horizon, seed, path count, antithetic sampling and spot prices
+    /// are checked. The file must not be modified while the session prices on its paths.
+    ///
+    /// # Arguments
+    /// * `path` - Scenario file to load
+    ///
+    /// # Errors
+    /// Returns `McError::Io` if the file cannot be read and `McError::InvalidFile` if
+    /// it is not a valid scenario file or was saved by a session with other parameters.
+    pub fn load_scenarios(&self, path: impl AsRef<Path>) -> Result<(), McError> {
+        let (header, sets) = scenario::read_scenarios(path.as_ref())?;
+        if header != self.scenario_header() {
+            return Err(McError::InvalidFile(
+                "scenarios were simulated for a session with other parameters".to_string(),
+            ));
+        }
+        let mut caches = self.caches.write().unwrap_or_else(PoisonError::into_inner);
+        for (shift, cache) in sets {
+            let cache = Arc::new(cache);
+            match caches.iter_mut().find(|(cached, _)| *cached == shift) {
+                Some((_, cached)) => *cached = cache,
+                None => caches.push((shift, cache)),
+            }
+        }
+        self.clear_memoized_results();
+        Ok(())
+    }
+
+    /// Returns the parameters identifying the paths of the session in a scenario file
+    fn scenario_header(&self) -> ScenarioHeader {
+        ScenarioHeader {
+            time_horizon_days: self.time_horizon_days,
+            seed: self.config.seed.unwrap_or_default(),
+            num_paths: self.config.num_paths,
+            antithetic: self.config.antithetic,
+            spot_prices: self
+                .market
+                .underlyings
+                .iter()
+                .map(|u| u.spot_price)
+                .collect(),
+        }
+    }
+
+    /// Prices the product, reusing the recorded paths where possible
+    ///
+    /// Vanilla options without barrier on an underlying with a smile are simulated with the
+    /// smile volatility of their strike (see `Underlying::smile`), so they do not share the
+    /// recorded paths.
+    ///
+    /// # Returns
+    /// The estimated price together with its standard error
+    ///
+    /// # Errors
+    /// Returns an error if the path count exceeds the addressable memory, or for invalid
+    /// products or market data (see `price_option`).
+    pub fn price(&self, product: &Product) -> Result<PricingResult, McError> {
+        self.price_shifted(product, MarketShift::None)
+    }
+
+    /// Computes the price and the Greeks of the product by bumping the spots, volatilities
+    /// and rates of the market up and down and repricing on the same random numbers
+    ///
+    /// On markets with exchange rates among the underlyings or quanto underlyings, the spots
+    /// of the exchange rates and the FX volatilities are bumped separately for the FX delta
+    /// and vega, which are hedged apart from the other risks. Relative barriers keep their level relative to the unbumped spots. The paths of the
+    /// bumped markets are recorded, so further products reuse them.
+    ///
+    /// # Errors
+    /// Returns an error under the same conditions as `price`, or if a volatility bumped down
+    /// becomes negative.
+    pub fn greeks(&self, product: &Product) -> Result<Greeks, McError> {
+        let pricing = self.price(product)?;
+        let spot_bump = SPOT_BUMP * self.market.underlyings[0].spot_price;
+        let central_difference = |up: MarketShift, down: MarketShift, bump: f64| {
+            let up = self.price_shifted(product, up)?.price;
+            let down = self.price_shifted(product, down)?.price;
+            Ok::<_, McError>(((up - down) / (2.0 * bump), up + down))
+        };
+        let (delta, spot_sum) = central_difference(
+            MarketShift::Spot(SPOT_BUMP),
+            MarketShift::Spot(-SPOT_BUMP),
+            spot_bump,
+        )?;
+        let (vega, _) = central_difference(
+            MarketShift::Volatility(VOLATILITY_BUMP),
+            MarketShift::Volatility(-VOLATILITY_BUMP),
+            VOLATILITY_BUMP,
+        )?;
+        let (rho, _) = central_difference(
+            MarketShift::Rate(RATE_BUMP),
+            MarketShift::Rate(-RATE_BUMP),
+            RATE_BUMP,
+        )?;
+        let fx_spot_price = self.fx_spot_price();
+        let fx_delta = match fx_spot_price {
+            Some(fx_spot_price) => {
+                central_difference(
+                    MarketShift::FxSpot(SPOT_BUMP),
+                    MarketShift::FxSpot(-SPOT_BUMP),
+                    SPOT_BUMP * fx_spot_price,
+                )?
+                .0
+            }
+            None => 0.0,
+        };
+        let has_quanto = self.market.underlyings.iter().any(|u| u.quanto.is_some());
+        let fx_vega = if fx_spot_price.is_some() || has_quanto {
+            central_difference(
+                MarketShift::FxVolatility(VOLATILITY_BUMP),
+                MarketShift::FxVolatility(-VOLATILITY_BUMP),
+                VOLATILITY_BUMP,
+            )?
+            .0
+        } else {
+            0.0
+        };
+        Ok(Greeks {
+            delta,
+            gamma: (spot_sum - 2.0 * pricing.price) / (spot_bump * spot_bump),
+            vega,
+            rho,
+            fx_delta,
+            fx_vega,
+            pricing,
+            spot_price: self.market.underlyings[0].spot_price,
+            fx_spot_price,
+            convention: GreekConvention::default(),
+        })
+    }
+
+    /// Computes the Greeks of a portfolio of positions, each a quantity of a product, on the
+    /// same random numbers for all positions and bumps
+    ///
+    /// # Arguments
+    /// * `positions` - Quantity (negative for short positions) and product of each position
+    ///
+    /// # Returns
+    /// The Greeks of each position per unit of its product and their quantity-weighted sums
+    ///
+    /// # Errors
+    /// Returns an error under the same conditions as `greeks` for any of the products.
+    pub fn portfolio_greeks(
+        &self,
+        positions: &[(f64, Product)],
+    ) -> Result<PortfolioGreeks, McError> {
+        let greeks = positions
+            .iter()
+            .map(|(_, product)| self.greeks(product))
+            .collect::<Result<Vec<_>, _>>()?;
+        // All positions are priced on the paths of the session
+        let num_paths = greeks.first().map_or(0, |greeks| greeks.pricing.num_paths);
+        let mut total = Greeks {
+            pricing: PricingResult::new(0.0, 0.0, num_paths),
+            delta: 0.0,
+            gamma: 0.0,
+            vega: 0.0,
+            rho: 0.0,
+            fx_delta: 0.0,
+            fx_vega: 0.0,
+            spot_price: self.market.underlyings[0].spot_price,
+            fx_spot_price: self.fx_spot_price(),
+            convention: GreekConvention::default(),
+        };
+        for ((quantity, _), position) in positions.iter().zip(&greeks) {
+            total.pricing.price += quantity * position.pricing.price;
+            total.pricing.std_error += quantity.abs() * position.pricing.std_error;
+            total.delta += quantity * position.delta;
+            total.gamma += quantity * position.gamma;
+            total.vega += quantity * position.vega;
+            total.rho += quantity * position.rho;
+            total.fx_delta += quantity * position.fx_delta;
+            total.fx_vega += quantity * position.fx_vega;
+        }
+        Ok(PortfolioGreeks {
+            positions: greeks,
+            total,
+        })
+    }
+
+    /// Returns the spot of the first exchange rate among the underlyings, if any
+    fn fx_spot_price(&self) -> Option<f64> {
+        self.market
+            .underlyings
+            .iter()
+            .find(|u| u.is_fx())
+            .map(|u| u.spot_price)
+    }
+
+    /// Prices the product with the spots of all underlyings shifted by each of the given
+    /// relative shifts (e.g. -0.1 for 10% down), on the same random numbers
+    ///
+    /// Relative barriers keep their level relative to the unshifted spots. The paths of the
+    /// shifted markets are recorded, so further products reuse them.
+    ///
+    /// # Errors
+    /// Returns an error under the same conditions as `price`, or if a shift takes the spots
+    /// to zero or below.
+    pub fn ladder(
+        &self,
+        product: &Product,
+        spot_shifts: &[f64],
+    ) -> Result<Vec<PricingResult>, McError> {
+        spot_shifts
+            .iter()
+            .map(|&shift| {
+                let shift = if shift == 0.0 {
+                    MarketShift::None
+                } else {
+                    MarketShift::Spot(shift)
+                };
+                self.price_shifted(product, shift)
+            })
+            .collect()
+    }
+
+    /// Returns the percentiles of the discounted value of the product over the recorded paths
+    /// of the session market, with the samples weighted like for its price
+    ///
+    /// With antithetic sampling, a sample is the mean value of the two paths of a pair.
+    ///
+    /// # Arguments
+    /// * `product` - Product to evaluate
+    /// * `probabilities` - Probabilities of the percentiles, e.g. 0.05 for the 5th percentile
+    ///
+    /// # Returns
+    /// One percentile per probability
+    ///
+    /// # Errors
+    /// Returns an error under the same conditions as `price`, or `McError::InvalidBarrier`
+    /// for barriers that `price` simulates afresh instead of evaluating on recorded paths:
+    /// continuously monitored barriers with a monitoring correction and knock-out rebates
+    /// paid at the hit.
+    ///
+    /// # Panics
+    /// Panics if a probability lies outside of [0, 1].
+    pub fn percentiles(
+        &self,
+        product: &Product,
+        probabilities: &[f64],
+    ) -> Result<Vec<f64>, McError> {
+        assert!(
+            probabilities
+                .iter()
+                .all(|probability| (0.0..=1.0).contains(probability)),
+            "Probabilities must lie in [0, 1]"
+        );
+        let payoff = &product.payoff;
+        let barrier = self.absolute_barrier(product)?;
+        let barrier = barrier.as_ref();
+        if !barrier.is_none_or(|barrier| is_recordable(barrier, self.config.barrier_correction)) {
+            return Err(McError::InvalidBarrier(
+                "percentiles need a barrier evaluated on the recorded paths".to_string(),
+            ));
+        }
+        let fixing_days = payoff
+            .schedule()
+            .map(|schedule| schedule.fixing_days(self.time_horizon_days))
+            .transpose()?
+            .unwrap_or_default();
+        let cache = self.recorded_paths(&self.market, MarketShift::None, fixing_days, barrier)?;
+
+        let discount_factor = self
+            .market
+            .risk_free_rate
+            .discount_factor(self.config.day_count.year_fraction(self.time_horizon_days));
+        let mut samples = Vec::with_capacity(cache.final_prices.len());
+        self.evaluate_samples(
+            &cache,
+            payoff,
+            barrier,
+            self.market.underlyings[0].spot_price,
+            discount_factor,
+            &mut |value, _, weight| samples.push((value, weight)),
+        )?;
+        Ok(weighted_percentiles(&mut samples, probabilities))
+    }
+
+    /// Validates the product and returns its barrier with an absolute level; relative
+    /// barriers are fixed relative to the session spots, whatever the market shift
+    fn absolute_barrier(&self, product: &Product) -> Result<Option<Barrier>, McError> {
+        validation::validate_payoff(&product.payoff)?;
+        if let Some(barrier) = &product.barrier {
+            validation::validate_barrier(barrier, self.market.underlyings.len())?;
+        }
+        let initial_prices: Vec<f64> = self
+            .market
+            .underlyings
+            .iter()
+            .map(|u| u.spot_price)
+            .collect();
+        Ok(product.barrier.as_ref().map(|barrier| Barrier {
+            barrier_level: effective_barrier_level(barrier, &initial_prices),
+            relative: false,
+            ..barrier.clone()
+        }))
+    }
+
+    /// Prices the product on the shifted market, returning the memoized price of an identical
+    /// product if there is one
+    fn price_shifted(
+        &self,
+        product: &Product,
+        shift: MarketShift,
+    ) -> Result<PricingResult, McError> {
+        let spot_prices: Vec<f64> = self
+            .market
+            .underlyings
+            .iter()
+            .map(|u| u.spot_price)
+            .collect();
+        let product = &product.canonicalize(&spot_prices)?;
+        // A hash collision must not return the price of another product
+        let key = crate::fingerprint(&(shift, product));
+        let memoized = self
+            .results
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&key)
+            .and_then(|results| {
+                results
+                    .iter()
+                    .find(|(cached, cached_product, _)| {
+                        *cached == shift && cached_product == product
+                    })
+                    .map(|(_, _, result)| result.clone())
+            });
+        if let Some(result) = memoized {
+            return Ok(result);
+        }
+
+        let result = self.evaluate_shifted(product, shift)?;
+        let mut results = self.results.write().unwrap_or_else(PoisonError::into_inner);
+        let results = results.entry(key).or_default();
+        if !results
+            .iter()
+            .any(|(cached, cached_product, _)| *cached == shift && cached_product == product)
+        {
+            results.push((shift, product.clone(), result.clone()));
+        }
+        Ok(result)
+    }
+
+    /// Prices the product on the shifted market, reusing its recorded paths where possible
+    fn evaluate_shifted(
+        &self,
+        product: &Product,
+        shift: MarketShift,
+    ) -> Result<PricingResult, McError> {
+        let market = shift.apply(&self.market);
+        let time_horizon_days = self.time_horizon_days;
+        let payoff = &product.payoff;
+        let barrier = self.absolute_barrier(product)?;
+        let barrier = barrier.as_ref();
+        let volatility_shift = match shift {
+            MarketShift::Volatility(shift) => shift,
+            MarketShift::FxVolatility(shift) if market.underlyings[0].is_fx() => shift,
+            _ => 0.0,
+        };
+        // Vanilla options on a smile are simulated with the volatility of their strike
+        let smile_underlyings = with_shocked_smile_volatility(
+            &market.underlyings,
+            time_horizon_days,
+            payoff,
+            &market.risk_free_rate,
+            barrier,
+            volatility_shift,
+            &self.config,
+        );
+
+        if matches!(smile_underlyings, Cow::Owned(_))
+            || !barrier.is_none_or(|barrier| is_recordable(barrier, self.config.barrier_correction))
+        {
+            self.num_simulations.fetch_add(1, Ordering::Relaxed);
+            let mut result = simulate_payoff(
+                &smile_underlyings,
+                &market.correlation_matrix,
+                time_horizon_days,
+                payoff,
+                &market.risk_free_rate,
+                barrier,
+                &self.config,
+            )?;
+            attach_diagnostics(
+                &mut result,
+                &smile_underlyings,
+                time_horizon_days,
+                payoff,
+                &market.risk_free_rate,
+                barrier,
+                &self.config,
+            );
+            return Ok(result);
+        }
+
+        let fixing_days = payoff
+            .schedule()
+            .map(|schedule| schedule.fixing_days(time_horizon_days))
+            .transpose()?
+            .unwrap_or_default();
+        let cache = self.recorded_paths(&market, shift, fixing_days, barrier)?;
+
+        let config = &self.config;
+        let underlyings = &market.underlyings;
+        let rate_curve = &market.risk_free_rate;
+        let discount_factor =
+            rate_curve.discount_factor(config.day_count.year_fraction(time_horizon_days));
+        let mut statistics = ChunkedStatistics::default();
+        let non_finite_paths = self.evaluate_samples(
+            &cache,
+            payoff,
+            barrier,
+            underlyings[0].spot_price,
+            discount_factor,
+            &mut |value, control, weight| statistics.add_weighted(value, control, weight),
+        )?;
+
+        let mut result = PricingResult::from_statistics(
+            &statistics.finish(),
+            cache.final_prices.len() as u64,
+            control_expectation(underlyings, time_horizon_days, payoff, rate_curve, config),
+        );
+        result.record_non_finite_paths(non_finite_paths);
+        result.record_correlation_repair(cache.correlation_adjustment);
+        // The recorded paths take one step per day
+        result.record_dimension_budget(DimensionBudget::new(
+            config.sampling,
+            underlyings.len() * (1 + config.model.num_extra_shocks()),
+            time_horizon_days as usize,
+        ));
+        attach_diagnostics(
+            &mut result,
+            underlyings,
+            time_horizon_days,
+            payoff,
+            rate_curve,
+            barrier,
+            config,
+        );
+        Ok(result)
+    }
+
+    /// Evaluates the product on the recorded paths, passing the discounted value, the
+    /// discounted control payoff and the weight of every sample to `add_sample`
+    ///
+    /// # Arguments
+    /// * `cache` - Recorded paths with the observables of the payoff and the barrier
+    /// * `payoff` - Payoff of the product
+    /// * `barrier` - Barrier of the product with an absolute level
+    /// * `initial_price` - Price of the first underlying the paths start at
+    /// * `discount_factor` - Discount factor from expiry
+    /// * `add_sample` - Receives the value, control and weight of each sample
+    ///
+    /// # Returns
+    /// The number of paths with a non-finite value or control
+    ///
+    /// # Errors
+    /// Returns `McError::NonFiniteValue` for a non-finite value under
+    /// `NonFinitePolicy::Error`.
+    fn evaluate_samples(
+        &self,
+        cache: &PathCache,
+        payoff: &Payoff,
+        barrier: Option<&Barrier>,
+        initial_price: f64,
+        discount_factor: f64,
+        add_sample: &mut dyn FnMut(f64, f64, f64),
+    ) -> Result<u64, McError> {
+        let config = &self.config;
+        let control_strike = payoff.control_strike(initial_price);
+        let paths_per_sample = engine::shock_signs(config.antithetic).len();
+        let mut non_finite_paths = 0;
+        for sample in 0..cache.final_prices.len() / paths_per_sample {
+            let mut payoff_sum = 0.0;
+            let mut control_sum = 0.0;
+            let mut weight_sum = 0.0;
+            let mut is_dropped = false;
+            for path in sample * paths_per_sample..(sample + 1) * paths_per_sample {
+                let observables = cache.observables(path, initial_price);
+                let intrinsic_payoff = payoff.evaluate(&observables);
+                let value = match barrier {
+                    Some(barrier) => barrier_value(
+                        barrier,
+                        (cache.reference_minima[path], cache.reference_maxima[path]),
+                        intrinsic_payoff,
+                    ),
+                    None => intrinsic_payoff,
+                };
+                // A non-finite price on the path stands in for the payoff, which would clip it
+                let non_finite_price = observables.non_finite_price();
+                let value = non_finite_price.unwrap_or(value);
+                let control = non_finite_price.unwrap_or_else(|| {
+                    intrinsic_value(observables.final_price, control_strike, payoff.option_type())
+                });
+
+                // Non-finite values from extreme parameters are treated according to the policy
+                if !value.is_finite() || !control.is_finite() {
+                    non_finite_paths += 1;
+                }
+                let policy = config.non_finite_policy;
+                match (policy.apply(value)?, policy.apply(control)?) {
+                    (Some(value), Some(control)) => {
+                        let weight = path_weight(config, path as u64);
+                        payoff_sum += weight * value;
+                        control_sum += weight * control;
+                        weight_sum += weight;
+                    }
+                    _ => is_dropped = true,
+                }
+            }
+            if !is_dropped {
+                // A sample weighs the mean weight of its paths
+                add_sample(
+                    payoff_sum / weight_sum * discount_factor,
+                    control_sum / weight_sum * discount_factor,
+                    weight_sum / paths_per_sample as f64,
+                );
+            }
+        }
+        Ok(non_finite_paths)
+    }
+
+    /// Returns the recorded paths of the shifted market with the given fixings and barrier
+    /// reference, simulating and publishing them if they are not recorded yet
+    fn recorded_paths(
+        &self,
+        market: &MarketSnapshot,
+        shift: MarketShift,
+        fixing_days: Vec<u32>,
+        barrier: Option<&Barrier>,
+    ) -> Result<Arc<PathCache>, McError> {
+        // The caches are only ever replaced as a whole, so a poisoned lock holds valid data
+        let previous = {
+            let caches = self.caches.read().unwrap_or_else(PoisonError::into_inner);
+            match caches.iter().find(|(cached, _)| *cached == shift) {
+                Some((_, cache)) if cache.records(&fixing_days, barrier) => {
+                    return Ok(Arc::clone(cache));
+                }
+                Some((_, cache)) => Some(Arc::clone(cache)),
+                None => None,
+            }
+        };
+
+        // Keep recording what the previous cache recorded and the product does not need
+        let (fixing_days, recorded_barrier) = match previous {
+            Some(cache) => (
+                if fixing_days.is_empty() {
+                    cache.fixing_days.clone()
+                } else {
+                    fixing_days
+                },
+                barrier.or(cache.barrier.as_ref()).cloned(),
+            ),
+            None => (fixing_days, barrier.cloned()),
+        };
+        self.num_simulations.fetch_add(1, Ordering::Relaxed);
+        let cache = Arc::new(self.simulate(market, fixing_days, recorded_barrier)?);
+
+        let mut caches = self.caches.write().unwrap_or_else(PoisonError::into_inner);
+        match caches.iter_mut().find(|(cached, _)| *cached == shift) {
+            Some((_, cached)) => *cached = Arc::clone(&cache),
+            None => caches.push((shift, Arc::clone(&cache))),
+        }
+        Ok(cache)
+    }
+
+    /// Simulates the paths of the market with daily steps, recording the fixings on the
+    /// given days and the reference extremes of the given barrier
+    fn simulate(
+        &self,
+        market: &MarketSnapshot,
+        fixing_days: Vec<u32>,
+        barrier: Option<Barrier>,
+    ) -> Result<PathCache, McError> {
+        let config = &self.config;
+        let time_horizon_days = self.time_horizon_days;
+        let engine = PathEngine::new(
+            &market.underlyings,
+            &market.correlation_matrix,
+            &market.risk_free_rate,
+            config.day_count.year_fraction(time_horizon_days),
+            time_horizon_days as usize,
+            config,
+        )?;
+        let mut generator = engine.shock_generator(config);
+
+        let shock_signs = engine::shock_signs(config.antithetic);
+        // All observables are recorded, so the paths must fit into memory
+        let num_samples = usize::try_from(config.num_paths.div_ceil(shock_signs.len() as u64))
+            .map_err(|_| McError::InvalidPaths(config.num_paths))?;
+        let num_paths = num_samples * shock_signs.len();
+        let mut final_prices = Vec::with_capacity(num_paths);
+        let mut running_maxima = Vec::with_capacity(num_paths);
+        let mut running_minima = Vec::with_capacity(num_paths);
+        let mut fixings = Vec::with_capacity(num_paths * fixing_days.len());
+        let mut reference_minima = Vec::with_capacity(num_paths);
+        let mut reference_maxima = Vec::with_capacity(num_paths);
+
+        let mut paths: Vec<PathState> = shock_signs
+            .iter()
+            .map(|_| PathState::new(&engine, fixing_days.len()))
+            .collect();
+        let mut extremes = vec![(f64::INFINITY, f64::NEG_INFINITY); shock_signs.len()];
+        let mut shocks = engine.new_shocks();
+        for _ in 0..num_samples {
+            for (path, extreme) in paths.iter_mut().zip(&mut extremes) {
+                path.reset(&engine);
+                *extreme = (f64::INFINITY, f64::NEG_INFINITY);
+            }
+            generator.start_path();
+
+            let mut next_fixing = 0;
+            for step in 1..=engine.num_steps {
+                engine.draw_shocks(&mut generator, &mut shocks);
+                let is_fixing_day =
+                    next_fixing < fixing_days.len() && fixing_days[next_fixing] as usize == step;
+                let paths = shock_signs.iter().zip(&mut paths).zip(&mut extremes);
+                for ((&sign, path), extreme) in paths {
+                    path.advance(&engine, step, &shocks, sign);
+                    path.update_prices();
+                    if let Some(barrier) = &barrier {
+                        if barrier
+                            .monitoring
+                            .is_monitored(step as u32, time_horizon_days)
+                        {
+                            let reference = calculate_reference(
+                                &path.prices,
+                                &barrier.underlying_indices,
+                                barrier.barrier_type,
+                            );
+                            *extreme = (extreme.0.min(reference), extreme.1.max(reference));
+                        }
+                    }
+                    if is_fixing_day {
+                        path.fixings.push(path.prices[0]);
+                    }
+                }
+                if is_fixing_day {
+                    next_fixing += 1;
+                }
+            }
+
+            for (path, &(reference_min, reference_max)) in paths.iter().zip(&extremes) {
+                let observables = path.observables();
+                final_prices.push(observables.final_price);
+                running_maxima.push(observables.running_max);
+                running_minima.push(observables.running_min);
+                fixings.extend_from_slice(observables.fixings);
+                reference_minima.push(reference_min);
+                reference_maxima.push(reference_max);
+            }
+        }
+        Ok(PathCache {
+            fixing_days,
+            barrier,
+            final_prices: final_prices.into(),
+            running_maxima: running_maxima.into(),
+            running_minima: running_minima.into(),
+            fixings: fixings.into(),
+            reference_minima: reference_minima.into(),
+            reference_maxima: reference_maxima.into(),
+            correlation_adjustment: engine.correlation.adjustment(),
+        })
+    }
+}
+
+/// Returns `true` if the barrier is priced from the reference extremes of the paths: it needs
+/// neither a monitoring correction nor the hit day for its rebate
+fn is_recordable(barrier: &Barrier, correction: BarrierCorrection) -> bool {
+    let is_corrected = barrier.correction(correction) != BarrierCorrection::None;
+    let is_paid_at_hit = barrier.rebate.is_some_and(|rebate| {
+        barrier.knock_type == KnockType::Out && rebate.timing == RebateTiming::AtHit
+    });
+    !is_corrected && !is_paid_at_hit
+}
+
+/// Value at expiry of a barrier option with an absolute level on a path with the given lowest
+/// and highest barrier reference values
+fn barrier_value(
+    barrier: &Barrier,
+    (reference_min, reference_max): (f64, f64),
+    intrinsic_payoff: f64,
+) -> f64 {
+    let is_hit = match barrier.direction {
+        BarrierDirection::Up => reference_max >= barrier.barrier_level,
+        BarrierDirection::Down => reference_min <= barrier.barrier_level,
+    };
+    if is_hit == (barrier.knock_type == KnockType::In) {
+        intrinsic_payoff
+    } else {
+        // Rebates of knock-out options are paid at expiry here
+        barrier.rebate.map_or(0.0, |rebate| rebate.amount)
+    }
+}
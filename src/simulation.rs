@@ -0,0 +1,267 @@
+use nalgebra::DMatrix;
+
+use crate::barrier::Barrier;
+use crate::config::SimulationConfig;
+use crate::error::McError;
+use crate::payoff::Payoff;
+use crate::rates::RateCurve;
+use crate::result::{PricingResult, PricingWarning};
+use crate::underlying::Underlying;
+use crate::{
+    attach_diagnostics, draw_seed, simulate_payoff, validation, with_smile_volatility,
+    with_start_values,
+};
+
+/// Progress of an incremental simulation
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SimulationProgress {
+    /// Number of paths simulated so far
+    pub paths_completed: u64,
+    /// Number of paths of the whole simulation
+    pub num_paths: u64,
+    /// Estimate from the paths simulated so far, none before the first chunk
+    pub estimate: Option<PricingResult>,
+}
+
+impl SimulationProgress {
+    /// Returns the share of the paths simulated so far, between 0 and 1
+    pub fn fraction(&self) -> f64 {
+        (self.paths_completed as f64 / self.num_paths as f64).min(1.0)
+    }
+}
+
+/// Simulation of a `price_payoff` price in chunks of paths, advanced by polling, e.g. from
+/// the event loop of a browser between frames
+///
+/// Each call to `poll` simulates one chunk of `SimulationConfig::batch_paths` paths and
+/// reports the progress with the running estimate. The simulation finishes once
+/// `num_paths` paths are simulated or the configured error tolerance is met, and can be
+/// cancelled between chunks.
+///
+/// The chunks are independent runs with seeds derived from the seed of the configuration
+/// (drawn once if none is configured), so a simulation with a seed always produces the same
+/// result, though not the one of `price_payoff` with that seed. The sanity checks are
+/// skipped.
+pub struct Simulation {
+    underlyings: Vec<Underlying>,
+    correlation_matrix: DMatrix<f64>,
+    time_horizon_days: u32,
+    payoff: Payoff,
+    rate_curve: RateCurve,
+    barrier: Option<Barrier>,
+    /// Configuration with a seed and the start values applied
+    config: SimulationConfig,
+    /// Results of the simulated chunks, in order
+    chunks: Vec<PricingResult>,
+    is_cancelled: bool,
+}
+
+impl Simulation {
+    /// Prepares the simulation of a price without simulating any paths
+    ///
+    /// # Arguments
+    /// * `underlyings` - List of underlying assets
+    /// * `correlation_matrix` - Correlation matrix (n x n) where n is the number of
+    ///   underlyings. Must be symmetric, positive semi-definite, with 1.0 on the diagonal.
+    /// * `time_horizon_days` - Time to expiration in days
+    /// * `payoff` - Payoff of the option
+    /// * `risk_free_rate` - Risk-free rate curve or flat annual rate (as a decimal, e.g.,
+    ///   0.05 for 5%)
+    /// * `barrier` - Optional barrier for barrier options
+    /// * `config` - Number of paths, chunk size (`batch_paths`), error tolerance and
+    ///   variance reduction settings
+    ///
+    /// # Errors
+    /// Returns an error for invalid inputs (see `price_option`) or an invalid fixing
+    /// schedule, and `McError::InvalidPathWeights` if path weights are configured, which
+    /// incremental simulations do not support. A correlation matrix that is not positive
+    /// definite is only reported by the first `poll`.
+    pub fn new(
+        underlyings: &[Underlying],
+        correlation_matrix: &DMatrix<f64>,
+        time_horizon_days: u32,
+        payoff: &Payoff,
+        risk_free_rate: impl Into<RateCurve>,
+        barrier: Option<&Barrier>,
+        config: &SimulationConfig,
+    ) -> Result<Self, McError> {
+        let underlyings = with_start_values(underlyings, config)?;
+        let rate_curve = risk_free_rate.into();
+        let underlyings = with_smile_volatility(
+            &underlyings,
+            time_horizon_days,
+            payoff,
+            &rate_curve,
+            barrier,
+            config,
+        )
+        .into_owned();
+        validation::validate_inputs(&underlyings, correlation_matrix, config)?;
+        validation::validate_payoff(payoff)?;
+        if let Some(barrier) = barrier {
+            validation::validate_barrier(barrier, underlyings.len())?;
+        }
+        if let Some(schedule) = payoff.schedule() {
+            schedule.fixing_days(time_horizon_days)?;
+        }
+        if config.path_weights.is_some() {
+            return Err(McError::InvalidPathWeights(
+                "incremental simulations do not support path weights".to_string(),
+            ));
+        }
+        Ok(Self {
+            underlyings,
+            correlation_matrix: correlation_matrix.clone(),
+            time_horizon_days,
+            payoff: payoff.clone(),
+            rate_curve,
+            barrier: barrier.cloned(),
+            config: SimulationConfig {
+                seed: Some(config.seed.unwrap_or_else(draw_seed)),
+                start_values: None,
+                validate: false,
+                batch_paths: config.batch_paths.max(1),
+                ..config.clone()
+            },
+            chunks: Vec::new(),
+            is_cancelled: false,
+        })
+    }
+
+    /// Returns the number of paths simulated so far
+    pub fn paths_completed(&self) -> u64 {
+        self.chunks.iter().map(|chunk| chunk.num_paths).sum()
+    }
+
+    /// Returns `true` once all paths are simulated, the error tolerance is met or the
+    /// simulation is cancelled
+    pub fn is_finished(&self) -> bool {
+        self.is_cancelled
+            || self.paths_completed() >= self.config.num_paths
+            || self.config.error_tolerance.is_some_and(|tolerance| {
+                let estimate = combine_chunks(&self.chunks);
+                self.chunks.len() >= 2 && tolerance.is_met(estimate.price, estimate.std_error)
+            })
+    }
+
+    /// Returns `true` if the simulation was cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.is_cancelled
+    }
+
+    /// Stops the simulation: later polls simulate nothing and there is no final result.
+    /// The progress keeps the estimate of the paths simulated so far.
+    pub fn cancel(&mut self) {
+        self.is_cancelled = true;
+    }
+
+    /// Returns the progress with the running estimate
+    pub fn progress(&self) -> SimulationProgress {
+        SimulationProgress {
+            paths_completed: self.paths_completed(),
+            num_paths: self.config.num_paths,
+            estimate: (!self.chunks.is_empty()).then(|| combine_chunks(&self.chunks)),
+        }
+    }
+
+    /// Simulates the next chunk of paths, unless the simulation is finished, and returns the
+    /// progress
+    ///
+    /// # Errors
+    /// Returns an error if the simulation of the chunk fails (see `price_payoff`); the
+    /// simulation can be polled again.
+    pub fn poll(&mut self) -> Result<SimulationProgress, McError> {
+        if !self.is_finished() {
+            let chunk = self.chunks.len() as u64;
+            let remaining = self.config.num_paths - self.paths_completed();
+            let config = SimulationConfig {
+                num_paths: remaining.min(self.config.batch_paths),
+                seed: self
+                    .config
+                    .seed
+                    .map(|seed| seed.wrapping_add(chunk.wrapping_mul(0x9e37_79b9_7f4a_7c15))),
+                error_tolerance: None,
+                ..self.config.clone()
+            };
+            let result = simulate_payoff(
+                &self.underlyings,
+                &self.correlation_matrix,
+                self.time_horizon_days,
+                &self.payoff,
+                &self.rate_curve,
+                self.barrier.as_ref(),
+                &config,
+            )?;
+            self.chunks.push(result);
+        }
+        Ok(self.progress())
+    }
+
+    /// Returns the price with its diagnostics once the simulation finished, or none while it
+    /// is running or if it was cancelled
+    pub fn result(&self) -> Option<PricingResult> {
+        if self.is_cancelled || !self.is_finished() {
+            return None;
+        }
+        let mut result = combine_chunks(&self.chunks);
+        attach_diagnostics(
+            &mut result,
+            &self.underlyings,
+            self.time_horizon_days,
+            &self.payoff,
+            &self.rate_curve,
+            self.barrier.as_ref(),
+            &self.config,
+        );
+        result.check_error_tolerance(self.config.error_tolerance);
+        Some(result)
+    }
+
+    /// Polls until the simulation is finished and returns its result, or none if it was
+    /// cancelled
+    ///
+    /// # Errors
+    /// Returns an error if the simulation of a chunk fails (see `price_payoff`).
+    pub fn run(&mut self) -> Result<Option<PricingResult>, McError> {
+        while !self.is_finished() {
+            self.poll()?;
+        }
+        Ok(self.result())
+    }
+}
+
+/// Combines the results of chunks into one result: the path-weighted average of their prices
+/// with the standard error of that average
+fn combine_chunks(chunks: &[PricingResult]) -> PricingResult {
+    let num_paths: u64 = chunks.iter().map(|chunk| chunk.num_paths).sum();
+    let total = num_paths as f64;
+    let price = chunks
+        .iter()
+        .map(|chunk| chunk.num_paths as f64 * chunk.price)
+        .sum::<f64>()
+        / total;
+    let variance = chunks
+        .iter()
+        .map(|chunk| (chunk.num_paths as f64 * chunk.std_error).powi(2))
+        .sum::<f64>();
+    let mut result = PricingResult::new(price, variance.sqrt() / total, num_paths);
+    result.record_non_finite_paths(chunks.iter().map(|chunk| chunk.non_finite_paths).sum());
+    // All chunks simulate with the same correlation matrix and dimensions
+    if let Some(first) = chunks.first() {
+        result.warnings.extend(
+            first
+                .warnings
+                .iter()
+                .filter(|warning| {
+                    matches!(
+                        warning,
+                        PricingWarning::CorrelationRepaired { .. }
+                            | PricingWarning::QuasiRandomPadding { .. }
+                    )
+                })
+                .cloned(),
+        );
+    }
+    result
+}
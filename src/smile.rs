@@ -0,0 +1,192 @@
+/// Implied volatility smile of a single expiry, parametrized in the log-moneyness
+/// `k = ln(K / F)` of the strike `K` against the forward `F`
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VolatilitySmile {
+    /// Implied volatility quadratic in the log-moneyness:
+    /// `atm_volatility + skew * k + curvature * k²`, floored at zero
+    Quadratic {
+        /// Implied volatility at the forward
+        atm_volatility: f64,
+        /// Slope of the implied volatility in the log-moneyness (negative for equity skews)
+        skew: f64,
+        /// Convexity of the implied volatility in the log-moneyness
+        curvature: f64,
+    },
+    /// Raw SVI slice of the implied total variance (Gatheral):
+    /// `a + b * (rho * (k - m) + sqrt((k - m)² + sigma²))`
+    Svi {
+        /// Expiry of the slice in years, converting the total variance into a volatility
+        expiry: f64,
+        /// Level of the total variance
+        a: f64,
+        /// Slope of the wings
+        b: f64,
+        /// Asymmetry of the wings, in (-1, 1)
+        rho: f64,
+        /// Log-moneyness of the vertex
+        m: f64,
+        /// Smoothness of the vertex, positive
+        sigma: f64,
+    },
+}
+
+/// Re-marking of a smile when the spot price of its underlying moves, e.g. in scenarios and
+/// for Greeks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SmileDynamics {
+    /// The smile moves with the forward: the volatility at each log-moneyness, and so
+    /// approximately at each delta, is unchanged
+    #[default]
+    StickyDelta,
+    /// The smile stays at its strikes: the volatility at each strike is unchanged, so the
+    /// at-the-money volatility slides along the skew
+    StickyStrike,
+}
+
+impl VolatilitySmile {
+    /// Returns the smile shifted along the log-moneyness: its volatility at `k` is the
+    /// volatility of this smile at `k + shift`
+    pub fn shifted(&self, shift: f64) -> Self {
+        match *self {
+            VolatilitySmile::Quadratic {
+                atm_volatility,
+                skew,
+                curvature,
+            } => VolatilitySmile::Quadratic {
+                atm_volatility: atm_volatility + skew * shift + curvature * shift * shift,
+                skew: skew + 2.0 * curvature * shift,
+                curvature,
+            },
+            VolatilitySmile::Svi {
+                expiry,
+                a,
+                b,
+                rho,
+                m,
+                sigma,
+            } => VolatilitySmile::Svi {
+                expiry,
+                a,
+                b,
+                rho,
+                m: m - shift,
+                sigma,
+            },
+        }
+    }
+
+    /// Returns the implied volatility at the given log-moneyness
+    pub fn implied_volatility(&self, log_moneyness: f64) -> f64 {
+        match *self {
+            VolatilitySmile::Quadratic {
+                atm_volatility,
+                skew,
+                curvature,
+            } => {
+                (atm_volatility + skew * log_moneyness + curvature * log_moneyness * log_moneyness)
+                    .max(0.0)
+            }
+            VolatilitySmile::Svi {
+                expiry,
+                a,
+                b,
+                rho,
+                m,
+                sigma,
+            } => {
+                let shifted = log_moneyness - m;
+                let total_variance =
+                    a + b * (rho * shifted + (shifted * shifted + sigma * sigma).sqrt());
+                (total_variance.max(0.0) / expiry).sqrt()
+            }
+        }
+    }
+
+    /// Returns the reason the parameters do not describe a smile, if any: non-finite
+    /// parameters, or for SVI a non-positive expiry or sigma, a negative slope, an asymmetry
+    /// outside of (-1, 1) or a negative minimum total variance
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            VolatilitySmile::Quadratic {
+                atm_volatility,
+                skew,
+                curvature,
+            } => {
+                if ![atm_volatility, skew, curvature]
+                    .iter()
+                    .all(|value| value.is_finite())
+                {
+                    return Err("parameters must be finite".to_string());
+                }
+                if atm_volatility < 0.0 {
+                    return Err(format!(
+                        "ATM volatility {} must be non-negative",
+                        atm_volatility
+                    ));
+                }
+            }
+            VolatilitySmile::Svi {
+                expiry,
+                a,
+                b,
+                rho,
+                m,
+                sigma,
+            } => {
+                if ![expiry, a, b, rho, m, sigma]
+                    .iter()
+                    .all(|value| value.is_finite())
+                {
+                    return Err("parameters must be finite".to_string());
+                }
+                if expiry <= 0.0 || sigma <= 0.0 {
+                    return Err(format!(
+                        "expiry {} and sigma {} must be positive",
+                        expiry, sigma
+                    ));
+                }
+                if b < 0.0 {
+                    return Err(format!("slope {} must be non-negative", b));
+                }
+                if rho.abs() >= 1.0 {
+                    return Err(format!("asymmetry {} is outside of (-1, 1)", rho));
+                }
+                if a + b * sigma * (1.0 - rho * rho).sqrt() < 0.0 {
+                    return Err("minimum total variance is negative".to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the local volatility at the given log-moneyness and time in years, from
+    /// Dupire's formula on the total variance `t * σ(k)²`, i.e. with the same smile at every
+    /// expiry
+    ///
+    /// Where the smile admits butterfly arbitrage (the denominator of Dupire's formula is
+    /// not positive), the implied volatility is returned instead.
+    pub fn local_volatility(&self, log_moneyness: f64, time: f64) -> f64 {
+        const STEP: f64 = 1e-4;
+        let variance = |k: f64| self.implied_volatility(k).powi(2);
+        let k = log_moneyness;
+        let v = variance(k);
+        if v <= 0.0 {
+            return 0.0;
+        }
+        let slope = (variance(k + STEP) - variance(k - STEP)) / (2.0 * STEP);
+        let convexity = (variance(k + STEP) - 2.0 * v + variance(k - STEP)) / (STEP * STEP);
+        // Dupire's denominator with w = t v, divided through by t where possible
+        let denominator = 1.0 - k * slope / v
+            + 0.25
+                * (-0.25 * time * time * slope * slope - time * slope * slope / v
+                    + k * k * slope * slope / (v * v))
+            + 0.5 * time * convexity;
+        if denominator > 0.0 {
+            (v / denominator).sqrt()
+        } else {
+            v.sqrt()
+        }
+    }
+}
@@ -0,0 +1,289 @@
+use nalgebra::{DMatrix, DVector};
+
+/// Streaming accumulator of weighted samples and their controls
+///
+/// Means, variances and the covariance are updated one sample at a time (West's weighted
+/// form of Welford's method), so no sample needs to be stored and no large sums of squares
+/// can lose precision or overflow. Accumulators of separate chunks of samples can be merged.
+/// Unweighted samples have weight 1.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct SampleStatistics {
+    count: u64,
+    /// Sum of the weights of the samples
+    weight_sum: f64,
+    /// Sum of the squared weights of the samples
+    squared_weight_sum: f64,
+    mean: f64,
+    control_mean: f64,
+    /// Sum of squared deviations of the samples from their mean
+    sum_squares: f64,
+    /// Sum of squared deviations of the controls from their mean
+    control_sum_squares: f64,
+    /// Sum of products of the sample and control deviations
+    sum_products: f64,
+}
+
+impl SampleStatistics {
+    /// Adds a sample together with its control and its non-negative weight. Samples of
+    /// weight zero are counted but do not contribute to the moments.
+    pub fn add_weighted(&mut self, sample: f64, control: f64, weight: f64) {
+        self.count += 1;
+        if weight == 0.0 {
+            return;
+        }
+        self.weight_sum += weight;
+        self.squared_weight_sum += weight * weight;
+        let delta = sample - self.mean;
+        let control_delta = control - self.control_mean;
+        self.mean += delta * weight / self.weight_sum;
+        self.control_mean += control_delta * weight / self.weight_sum;
+        self.sum_squares += weight * delta * (sample - self.mean);
+        self.control_sum_squares += weight * control_delta * (control - self.control_mean);
+        self.sum_products += weight * delta * (control - self.control_mean);
+    }
+
+    /// Merges the statistics of another, disjoint set of samples into this one
+    pub fn merge(&mut self, other: &SampleStatistics) {
+        if other.weight_sum == 0.0 {
+            self.count += other.count;
+            return;
+        }
+        if self.weight_sum == 0.0 {
+            let count = self.count + other.count;
+            *self = *other;
+            self.count = count;
+            return;
+        }
+        let weight_sum = self.weight_sum + other.weight_sum;
+        let weight = self.weight_sum * other.weight_sum / weight_sum;
+        let delta = other.mean - self.mean;
+        let control_delta = other.control_mean - self.control_mean;
+
+        self.sum_squares += other.sum_squares + delta * delta * weight;
+        self.control_sum_squares += other.control_sum_squares + control_delta * control_delta * weight;
+        self.sum_products += other.sum_products + delta * control_delta * weight;
+        self.mean += delta * other.weight_sum / weight_sum;
+        self.control_mean += control_delta * other.weight_sum / weight_sum;
+        self.count += other.count;
+        self.weight_sum = weight_sum;
+        self.squared_weight_sum += other.squared_weight_sum;
+    }
+
+    /// Returns the number of samples
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns Kish's effective sample size `(Σw)² / Σw²`, the number of unweighted samples
+    /// with the same standard error of the mean; the number of samples without weights
+    pub fn effective_count(&self) -> f64 {
+        if self.squared_weight_sum > 0.0 {
+            self.weight_sum * self.weight_sum / self.squared_weight_sum
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns the sample mean
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    /// Returns the mean of the controls
+    pub fn control_mean(&self) -> f64 {
+        self.control_mean
+    }
+
+    /// Returns the unbiased (reliability-weighted) sample variance
+    pub fn variance(&self) -> f64 {
+        self.unbiased(self.sum_squares)
+    }
+
+    /// Returns the mean and unbiased variance of the combination
+    /// `weight * sample + control_weight * control`
+    pub fn combined_mean_and_variance(&self, weight: f64, control_weight: f64) -> (f64, f64) {
+        let mean = weight * self.mean + control_weight * self.control_mean;
+        let sum_squares = weight * weight * self.sum_squares
+            + 2.0 * weight * control_weight * self.sum_products
+            + control_weight * control_weight * self.control_sum_squares;
+        (mean, self.unbiased(sum_squares.max(0.0)))
+    }
+
+    /// Returns the mean and unbiased variance of the samples adjusted by the control variate
+    /// `sample - beta * (control - control_expectation)`, with the variance-minimizing `beta`
+    pub fn controlled_mean_and_variance(&self, control_expectation: f64) -> (f64, f64) {
+        let beta = if self.control_sum_squares > 0.0 {
+            self.sum_products / self.control_sum_squares
+        } else {
+            0.0
+        };
+        let mean = self.mean - beta * (self.control_mean - control_expectation);
+        let sum_squares = self.sum_squares - 2.0 * beta * self.sum_products
+            + beta * beta * self.control_sum_squares;
+        (mean, self.unbiased(sum_squares.max(0.0)))
+    }
+
+    fn unbiased(&self, sum_squares: f64) -> f64 {
+        // Σw - Σw²/Σw, which is n - 1 for unweighted samples
+        let denominator = if self.weight_sum > 0.0 {
+            self.weight_sum - self.squared_weight_sum / self.weight_sum
+        } else {
+            0.0
+        };
+        if denominator > 0.0 {
+            sum_squares / denominator
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Number of samples accumulated separately before merging into the total
+const CHUNK_SIZE: u64 = 1 << 16;
+
+/// Streaming accumulator for very large sample counts
+///
+/// Samples are accumulated in chunks of fixed size which are merged into the total, so the
+/// rounding error of the running means grows with the number of chunks rather than the
+/// number of samples.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ChunkedStatistics {
+    total: SampleStatistics,
+    chunk: SampleStatistics,
+}
+
+impl ChunkedStatistics {
+    /// Adds a sample together with its control (pass `0.0` if there is none)
+    pub fn add(&mut self, sample: f64, control: f64) {
+        self.add_weighted(sample, control, 1.0);
+    }
+
+    /// Adds a sample together with its control and its non-negative weight
+    pub fn add_weighted(&mut self, sample: f64, control: f64, weight: f64) {
+        self.chunk.add_weighted(sample, control, weight);
+        if self.chunk.count() == CHUNK_SIZE {
+            self.total.merge(&self.chunk);
+            self.chunk = SampleStatistics::default();
+        }
+    }
+
+    /// Returns the statistics of the samples added so far
+    pub fn current(&self) -> SampleStatistics {
+        self.clone().finish()
+    }
+
+    /// Returns the statistics of all samples added
+    pub fn finish(mut self) -> SampleStatistics {
+        self.total.merge(&self.chunk);
+        self.total
+    }
+}
+
+/// Returns the weighted percentiles of the samples at the given probabilities in [0, 1]: the
+/// smallest sample whose cumulative weight reaches the probability of the total weight
+///
+/// # Arguments
+/// * `samples` - Pairs of sample and non-negative weight, sorted in place by sample
+/// * `probabilities` - Probabilities of the percentiles
+///
+/// # Returns
+/// One percentile per probability, or NaN for all of them if the samples have no weight
+pub(crate) fn weighted_percentiles(samples: &mut [(f64, f64)], probabilities: &[f64]) -> Vec<f64> {
+    samples.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let total_weight: f64 = samples.iter().map(|&(_, weight)| weight).sum();
+    probabilities
+        .iter()
+        .map(|&probability| {
+            if total_weight <= 0.0 {
+                return f64::NAN;
+            }
+            let target = probability * total_weight;
+            let mut cumulative_weight = 0.0;
+            samples
+                .iter()
+                .filter(|&&(_, weight)| weight > 0.0)
+                .find(|&&(_, weight)| {
+                    cumulative_weight += weight;
+                    cumulative_weight >= target
+                })
+                .or_else(|| samples.iter().rev().find(|&&(_, weight)| weight > 0.0))
+                .map_or(f64::NAN, |&(sample, _)| sample)
+        })
+        .collect()
+}
+
+/// Number of sample vectors buffered before their co-moments are merged into the total
+const BLOCK_SIZE: usize = 256;
+
+/// Streaming accumulator of the correlation matrix of sample vectors
+///
+/// Samples are buffered in blocks; the co-moments of a block are computed by one matrix
+/// product and merged into the total like the chunks of `ChunkedStatistics`, so the cost per
+/// sample stays that of a matrix product rather than a rank-one update per sample.
+#[derive(Debug, Clone)]
+pub(crate) struct CorrelationStatistics {
+    count: u64,
+    mean: DVector<f64>,
+    /// Sums of products of the deviations from the means
+    comoments: DMatrix<f64>,
+    /// Buffered samples, one after the other
+    block: Vec<f64>,
+}
+
+impl CorrelationStatistics {
+    /// Creates an accumulator for samples of the given dimension
+    pub fn new(dimension: usize) -> Self {
+        Self {
+            count: 0,
+            mean: DVector::zeros(dimension),
+            comoments: DMatrix::zeros(dimension, dimension),
+            block: Vec::with_capacity(BLOCK_SIZE * dimension),
+        }
+    }
+
+    /// Adds a sample vector
+    pub fn add(&mut self, sample: &[f64]) {
+        self.block.extend_from_slice(sample);
+        if self.block.len() == BLOCK_SIZE * self.mean.len() {
+            self.merge_block();
+        }
+    }
+
+    /// Returns the correlation matrix of all samples added, with 1.0 on the diagonal and 0.0
+    /// for the correlations of components without variance
+    pub fn finish(mut self) -> DMatrix<f64> {
+        self.merge_block();
+        let dimension = self.mean.len();
+        DMatrix::from_fn(dimension, dimension, |i, j| {
+            let variances = self.comoments[(i, i)] * self.comoments[(j, j)];
+            if i == j {
+                1.0
+            } else if variances > 0.0 {
+                (self.comoments[(i, j)] / variances.sqrt()).clamp(-1.0, 1.0)
+            } else {
+                0.0
+            }
+        })
+    }
+
+    fn merge_block(&mut self) {
+        let dimension = self.mean.len();
+        if dimension == 0 || self.block.is_empty() {
+            return;
+        }
+        let block_count = self.block.len() / dimension;
+        let mut block = DMatrix::from_row_slice(block_count, dimension, &self.block);
+        self.block.clear();
+        let block_mean = block.row_mean().transpose();
+        for mut row in block.row_iter_mut() {
+            row -= block_mean.transpose();
+        }
+
+        let count = self.count as f64 + block_count as f64;
+        let weight = self.count as f64 * block_count as f64 / count;
+        let delta = block_mean - &self.mean;
+        self.comoments += block.tr_mul(&block) + &delta * delta.transpose() * weight;
+        self.mean += delta * (block_count as f64 / count);
+        self.count += block_count as u64;
+    }
+}
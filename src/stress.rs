@@ -0,0 +1,383 @@
+use nalgebra::DMatrix;
+
+use crate::barrier::Barrier;
+use crate::config::SimulationConfig;
+use crate::correlation::stress_principal_component;
+use crate::error::McError;
+use crate::market::MarketSnapshot;
+use crate::product::Product;
+use crate::result::PricingResult;
+use crate::session::MarketShift;
+use crate::{
+    attach_diagnostics, effective_barrier_level, simulate_payoff, with_shocked_smile_volatility,
+    with_start_values,
+};
+
+/// How the correlation shifts of a stress grid move the correlation matrix
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CorrelationStress {
+    /// Every correlation between different underlyings shifts by the same amount, clamped
+    /// to [-1, 1]; large shifts may leave the positive semi-definite matrices
+    #[default]
+    Pairwise,
+    /// The leading eigenvalue, the market-wide correlation level, moves by the shift times
+    /// `n - 1` (see `stress_principal_component`), which always yields a valid matrix
+    PrincipalComponent,
+}
+
+/// Grid of market shocks to reprice a product on: every combination of a spot shock, a
+/// volatility shock, a rate shift and a correlation stress is one scenario
+///
+/// Each list defaults to the single shock 0.0, so a grid of spot and volatility shocks
+/// leaves the rates and correlations unchanged.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StressGrid {
+    /// Relative shocks of the spot prices and marked forwards of all underlyings, e.g. -0.1
+    /// for 10% down
+    pub spot_shifts: Vec<f64>,
+    /// Absolute shocks of the volatilities of all underlyings, term structures included,
+    /// e.g. 0.05 for 5 volatility points up
+    pub volatility_shifts: Vec<f64>,
+    /// Parallel shifts of the zero rates
+    pub rate_shifts: Vec<f64>,
+    /// Shifts of all correlations between different underlyings, clamped to [-1, 1]
+    pub correlation_shifts: Vec<f64>,
+    /// How the correlation shifts are applied, pairwise by default
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub correlation_stress: CorrelationStress,
+}
+
+impl Default for StressGrid {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StressGrid {
+    /// Creates a grid with the single unstressed scenario
+    pub fn new() -> Self {
+        Self {
+            spot_shifts: vec![0.0],
+            volatility_shifts: vec![0.0],
+            rate_shifts: vec![0.0],
+            correlation_shifts: vec![0.0],
+            correlation_stress: CorrelationStress::Pairwise,
+        }
+    }
+
+    /// Sets the relative spot shocks
+    pub fn with_spot_shifts(mut self, spot_shifts: Vec<f64>) -> Self {
+        self.spot_shifts = spot_shifts;
+        self
+    }
+
+    /// Sets the absolute volatility shocks
+    pub fn with_volatility_shifts(mut self, volatility_shifts: Vec<f64>) -> Self {
+        self.volatility_shifts = volatility_shifts;
+        self
+    }
+
+    /// Sets the parallel shifts of the zero rates
+    pub fn with_rate_shifts(mut self, rate_shifts: Vec<f64>) -> Self {
+        self.rate_shifts = rate_shifts;
+        self
+    }
+
+    /// Sets the shifts of the correlations
+    pub fn with_correlation_shifts(mut self, correlation_shifts: Vec<f64>) -> Self {
+        self.correlation_shifts = correlation_shifts;
+        self
+    }
+
+    /// Sets how the correlation shifts are applied, e.g. to the leading principal component
+    pub fn with_correlation_stress(mut self, correlation_stress: CorrelationStress) -> Self {
+        self.correlation_stress = correlation_stress;
+        self
+    }
+
+    /// Returns the scenarios of the grid, the correlation stresses varying fastest and the
+    /// spot shocks slowest
+    pub fn scenarios(&self) -> Vec<StressScenario> {
+        let mut scenarios = Vec::new();
+        for &spot_shift in &self.spot_shifts {
+            for &volatility_shift in &self.volatility_shifts {
+                for &rate_shift in &self.rate_shifts {
+                    for &correlation_shift in &self.correlation_shifts {
+                        scenarios.push(StressScenario {
+                            spot_shift,
+                            volatility_shift,
+                            rate_shift,
+                            correlation_shift,
+                        });
+                    }
+                }
+            }
+        }
+        scenarios
+    }
+}
+
+/// Market shocks of one scenario of a `StressGrid`
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StressScenario {
+    /// Relative shock of the spot prices
+    pub spot_shift: f64,
+    /// Absolute shock of the volatilities
+    pub volatility_shift: f64,
+    /// Parallel shift of the zero rates
+    pub rate_shift: f64,
+    /// Shift of the correlations between different underlyings
+    pub correlation_shift: f64,
+}
+
+impl StressScenario {
+    /// Returns a copy of the market with the shocks applied, the correlation shift as given
+    /// by `correlation_stress`
+    pub(crate) fn apply(
+        &self,
+        market: &MarketSnapshot,
+        correlation_stress: CorrelationStress,
+    ) -> MarketSnapshot {
+        let market = MarketShift::Spot(self.spot_shift).apply(market);
+        let market = MarketShift::Volatility(self.volatility_shift).apply(&market);
+        let mut market = MarketShift::Rate(self.rate_shift).apply(&market);
+        if correlation_stress == CorrelationStress::PrincipalComponent {
+            if self.correlation_shift != 0.0 {
+                market.correlation_matrix =
+                    stress_principal_component(&market.correlation_matrix, self.correlation_shift);
+            }
+            return market;
+        }
+        let size = market.correlation_matrix.nrows();
+        for i in 0..size {
+            for j in 0..size {
+                if i != j {
+                    let correlation = &mut market.correlation_matrix[(i, j)];
+                    *correlation = (*correlation + self.correlation_shift).clamp(-1.0, 1.0);
+                }
+            }
+        }
+        market
+    }
+}
+
+/// Prices of a product on the scenarios of a stress grid
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StressResult {
+    /// Grid the scenarios were built from
+    pub grid: StressGrid,
+    /// Price of the product on the unstressed market
+    pub base: PricingResult,
+    /// Scenarios in the order of `StressGrid::scenarios`
+    pub scenarios: Vec<StressScenario>,
+    /// Price of the product on each scenario
+    pub results: Vec<PricingResult>,
+}
+
+impl StressResult {
+    /// Returns the price on the scenario with the given positions in the lists of shocks of
+    /// the grid
+    ///
+    /// # Panics
+    /// Panics if a position is out of range of its list.
+    pub fn get(
+        &self,
+        spot_index: usize,
+        volatility_index: usize,
+        rate_index: usize,
+        correlation_index: usize,
+    ) -> &PricingResult {
+        let grid = &self.grid;
+        assert!(
+            spot_index < grid.spot_shifts.len(),
+            "spot index out of range"
+        );
+        assert!(
+            volatility_index < grid.volatility_shifts.len(),
+            "volatility index out of range"
+        );
+        assert!(
+            rate_index < grid.rate_shifts.len(),
+            "rate index out of range"
+        );
+        assert!(
+            correlation_index < grid.correlation_shifts.len(),
+            "correlation index out of range"
+        );
+        let index = ((spot_index * grid.volatility_shifts.len() + volatility_index)
+            * grid.rate_shifts.len()
+            + rate_index)
+            * grid.correlation_shifts.len()
+            + correlation_index;
+        &self.results[index]
+    }
+
+    /// Returns the P&L of the scenarios against the base price as a matrix with a row per
+    /// spot shock and a column per volatility shock, for the given rate and correlation
+    /// positions in the grid
+    ///
+    /// # Panics
+    /// Panics if a position is out of range of its list.
+    pub fn pnl_matrix(&self, rate_index: usize, correlation_index: usize) -> DMatrix<f64> {
+        DMatrix::from_fn(
+            self.grid.spot_shifts.len(),
+            self.grid.volatility_shifts.len(),
+            |spot_index, volatility_index| {
+                self.get(spot_index, volatility_index, rate_index, correlation_index)
+                    .price
+                    - self.base.price
+            },
+        )
+    }
+}
+
+/// Reprices a product on every scenario of a stress grid
+///
+/// All scenarios are simulated with the same seed (the configured one, or one drawn for the
+/// whole grid), so the prices are computed on common random numbers and their differences
+/// carry little Monte Carlo noise. Relative barriers keep their level relative to the
+/// unstressed spots, like the barrier of a trade struck before the shock.
+///
+/// # Arguments
+/// * `market` - Unstressed market
+/// * `time_horizon_days` - Time to expiration of the product in days
+/// * `product` - Product to reprice
+/// * `grid` - Shocks of the scenarios
+/// * `config` - Number of paths and variance reduction settings. The error tolerance and
+///   the sanity checks are ignored, so all scenarios simulate the same paths.
+///
+/// # Returns
+/// The base price and the price on each scenario
+///
+/// # Errors
+/// Returns an error for invalid inputs (see `price_option`), in particular if a shock takes
+/// a volatility below zero or stresses the correlation matrix out of positive
+/// semi-definiteness (unless `SimulationConfig::repair_correlation` is set).
+pub fn stress_test(
+    market: &MarketSnapshot,
+    time_horizon_days: u32,
+    product: &Product,
+    grid: &StressGrid,
+    config: &SimulationConfig,
+) -> Result<StressResult, McError> {
+    let pricer = ScenarioPricer::new(market, time_horizon_days, product, config)?;
+    let barrier = pricer.barrier.as_ref();
+    let scenarios = grid.scenarios();
+    let results = scenarios
+        .iter()
+        .map(|scenario| {
+            pricer.price(
+                &scenario.apply(&pricer.market, grid.correlation_stress),
+                barrier,
+                scenario.volatility_shift,
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(StressResult {
+        grid: grid.clone(),
+        base: pricer.price(&pricer.market, barrier, 0.0)?,
+        scenarios,
+        results,
+    })
+}
+
+/// Prices a product on shocked markets, all with the same seed so the prices are computed
+/// on common random numbers
+pub(crate) struct ScenarioPricer<'a> {
+    /// Unshocked market, with the start values of the configuration as spot prices
+    pub(crate) market: MarketSnapshot,
+    /// Barrier of the product with its level fixed relative to the unshocked spots
+    pub(crate) barrier: Option<Barrier>,
+    time_horizon_days: u32,
+    product: &'a Product,
+    config: SimulationConfig,
+}
+
+impl<'a> ScenarioPricer<'a> {
+    /// Creates a pricer of the product on shocks of the market, with the configured seed or
+    /// one drawn for all shocks, and without error tolerance or sanity checks
+    ///
+    /// # Errors
+    /// Returns `McError::InvalidStartValues` if the start values of the configuration do not
+    /// match the underlyings.
+    pub(crate) fn new(
+        market: &MarketSnapshot,
+        time_horizon_days: u32,
+        product: &'a Product,
+        config: &SimulationConfig,
+    ) -> Result<Self, McError> {
+        let market = MarketSnapshot {
+            underlyings: with_start_values(&market.underlyings, config)?.into_owned(),
+            ..market.clone()
+        };
+        let config = SimulationConfig {
+            seed: Some(config.seed.unwrap_or_else(crate::draw_seed)),
+            validate: false,
+            start_values: None,
+            error_tolerance: None,
+            ..config.clone()
+        };
+        // Relative barriers are fixed relative to the unshocked spots
+        let initial_prices: Vec<f64> = market.underlyings.iter().map(|u| u.spot_price).collect();
+        let barrier = product.barrier.as_ref().map(|barrier| Barrier {
+            barrier_level: effective_barrier_level(barrier, &initial_prices),
+            relative: false,
+            ..barrier.clone()
+        });
+        Ok(Self {
+            market,
+            barrier,
+            time_horizon_days,
+            product,
+            config,
+        })
+    }
+
+    /// Prices the payoff of the product with the given barrier on the shocked market, whose
+    /// volatilities were shocked by `volatility_shift`
+    ///
+    /// Vanilla options on an underlying with a smile are priced with the smile volatility of
+    /// their strike on the shocked market, shocked like the volatilities.
+    ///
+    /// # Errors
+    /// Returns an error for invalid inputs (see `price_option`).
+    pub(crate) fn price(
+        &self,
+        market: &MarketSnapshot,
+        barrier: Option<&Barrier>,
+        volatility_shift: f64,
+    ) -> Result<PricingResult, McError> {
+        let underlyings = &*with_shocked_smile_volatility(
+            &market.underlyings,
+            self.time_horizon_days,
+            &self.product.payoff,
+            &market.risk_free_rate,
+            barrier,
+            volatility_shift,
+            &self.config,
+        );
+        let mut result = simulate_payoff(
+            underlyings,
+            &market.correlation_matrix,
+            self.time_horizon_days,
+            &self.product.payoff,
+            &market.risk_free_rate,
+            barrier,
+            &self.config,
+        )?;
+        attach_diagnostics(
+            &mut result,
+            underlyings,
+            self.time_horizon_days,
+            &self.product.payoff,
+            &market.risk_free_rate,
+            barrier,
+            &self.config,
+        );
+        Ok(result)
+    }
+}
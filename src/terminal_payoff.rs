@@ -0,0 +1,248 @@
+use nalgebra::{DMatrix, DVector};
+
+use crate::config::SimulationConfig;
+use crate::engine::{self, PathEngine, PathState};
+use crate::error::McError;
+use crate::math;
+use crate::rates::RateCurve;
+use crate::result::PricingResult;
+use crate::statistics::ChunkedStatistics;
+use crate::underlying::Underlying;
+use crate::with_start_values;
+
+/// European payoff on the terminal prices of all underlyings, evaluated for a whole batch of
+/// paths at once
+///
+/// The payoff receives the prices at expiry as a matrix with one row per path and one column
+/// per underlying, so it can be written with vectorized matrix operations instead of a loop
+/// over the paths. Closures `Fn(&DMatrix<f64>) -> DVector<f64>` implement the trait.
+pub trait TerminalPayoff {
+    /// Returns the undiscounted payoff of each path, one entry per row of `terminal_prices`
+    fn evaluate(&self, terminal_prices: &DMatrix<f64>) -> DVector<f64>;
+}
+
+impl<F: Fn(&DMatrix<f64>) -> DVector<f64>> TerminalPayoff for F {
+    fn evaluate(&self, terminal_prices: &DMatrix<f64>) -> DVector<f64> {
+        self(terminal_prices)
+    }
+}
+
+/// Prices a European payoff on the terminal prices of several underlyings, evaluating it for
+/// a batch of `config.batch_paths` paths at a time
+///
+/// The antithetic counterpart of a path follows it in the rows of the batch. Under
+/// Black-Scholes the paths take a single step to expiry, otherwise one step per day. The
+/// paths of a batch are checked against the error tolerance of the configuration once the
+/// batch is priced.
+///
+/// With moment matching configured, the terminal prices of each underlying in a batch are
+/// rescaled before the payoff is evaluated, removing the small-sample bias of their drift and
+/// volatility: their log returns are scaled around their mean to the standard deviation of
+/// the ATM implied volatility (from the smile of the underlying if it has one, otherwise its
+/// integrated variance), then all prices are scaled so their mean is the forward. Without
+/// lognormal marginals, only the mean is matched. The matched paths of a batch are no longer
+/// independent, so the standard error is approximate.
+///
+/// # Arguments
+/// * `underlyings` - List of underlying assets, one column of the terminal prices each
+/// * `correlation_matrix` - Correlation matrix (n x n) where n is the number of underlyings.
+///   Must be symmetric, positive semi-definite, with 1.0 on the diagonal.
+/// * `time_horizon_days` - Time to expiration in days
+/// * `payoff` - Payoff evaluated on the terminal prices of each batch of paths
+/// * `risk_free_rate` - Risk-free rate curve or flat annual rate (as a decimal, e.g., 0.05 for 5%)
+/// * `config` - Number of paths and variance reduction settings. The control variate, the
+///   path observer and the sanity checks are not supported and ignored; quasi-random
+///   sampling runs once instead of in replications.
+///
+/// # Returns
+/// The estimated price together with its standard error
+///
+/// # Errors
+/// Returns an error for invalid inputs (see `price_option`), or `McError::InvalidProduct`
+/// if the payoff returns a vector whose length is not the number of paths of the batch.
+pub fn price_terminal_payoff(
+    underlyings: &[Underlying],
+    correlation_matrix: &DMatrix<f64>,
+    time_horizon_days: u32,
+    payoff: &dyn TerminalPayoff,
+    risk_free_rate: impl Into<RateCurve>,
+    config: &SimulationConfig,
+) -> Result<PricingResult, McError> {
+    let underlyings = &*with_start_values(underlyings, config)?;
+    let rate_curve = risk_free_rate.into();
+    let time_to_expiration = config.day_count.year_fraction(time_horizon_days);
+    let discount_factor = rate_curve.discount_factor(time_to_expiration);
+    let num_steps = if config.model.has_black_scholes_marginals() {
+        1
+    } else {
+        time_horizon_days as usize
+    };
+    let engine = PathEngine::new(
+        underlyings,
+        correlation_matrix,
+        &rate_curve,
+        time_to_expiration,
+        num_steps,
+        config,
+    )?;
+    let mut generator = engine.shock_generator(config);
+    let targets = moment_targets(underlyings, &rate_curve, time_horizon_days, config);
+
+    let shock_signs = engine::shock_signs(config.antithetic);
+    let num_samples = config.num_paths.div_ceil(shock_signs.len() as u64);
+    // The standard error needs at least two samples per batch
+    let batch_samples = config.batch_paths.div_ceil(shock_signs.len() as u64).max(2);
+    let mut num_simulated_samples = 0;
+
+    let mut statistics = ChunkedStatistics::default();
+    let mut non_finite_paths = 0;
+    let mut paths: Vec<PathState> = shock_signs
+        .iter()
+        .map(|_| PathState::new(&engine, 0))
+        .collect();
+    let mut shocks = engine.new_shocks();
+    while num_simulated_samples < num_samples {
+        if let Some(tolerance) = config.error_tolerance {
+            if num_simulated_samples > 0 {
+                let estimate = PricingResult::from_statistics(&statistics.current(), 0, None);
+                if tolerance.is_met(estimate.price, estimate.std_error) {
+                    break;
+                }
+            }
+        }
+        let samples = batch_samples.min(num_samples - num_simulated_samples) as usize;
+        let mut terminal_prices = DMatrix::zeros(samples * shock_signs.len(), underlyings.len());
+        for sample in 0..samples {
+            for path in &mut paths {
+                path.reset(&engine);
+            }
+            generator.start_path();
+            for step in 1..=engine.num_steps {
+                engine.draw_shocks(&mut generator, &mut shocks);
+                for (&sign, path) in shock_signs.iter().zip(&mut paths) {
+                    path.advance(&engine, step, &shocks, sign);
+                }
+            }
+            for (index, path) in paths.iter_mut().enumerate() {
+                path.update_prices();
+                let row = sample * shock_signs.len() + index;
+                for (column, &price) in path.prices.iter().enumerate() {
+                    terminal_prices[(row, column)] = price;
+                }
+            }
+        }
+
+        if config.moment_matching {
+            match_moments(&mut terminal_prices, &targets);
+        }
+        let payoffs = payoff.evaluate(&terminal_prices);
+        if payoffs.len() != terminal_prices.nrows() {
+            return Err(McError::InvalidProduct(format!(
+                "terminal payoff returned {} values for {} paths",
+                payoffs.len(),
+                terminal_prices.nrows()
+            )));
+        }
+        for sample_payoffs in payoffs.as_slice().chunks(shock_signs.len()) {
+            let mut payoff_sum = 0.0;
+            let mut is_dropped = false;
+            for &value in sample_payoffs {
+                // Non-finite values from extreme parameters are treated according to the policy
+                if !value.is_finite() {
+                    non_finite_paths += 1;
+                }
+                match config.non_finite_policy.apply(value)? {
+                    Some(value) => payoff_sum += value,
+                    None => is_dropped = true,
+                }
+            }
+            if !is_dropped {
+                // Discount to present value
+                statistics.add(payoff_sum / shock_signs.len() as f64 * discount_factor, 0.0);
+            }
+        }
+        num_simulated_samples += samples as u64;
+    }
+
+    let mut result = PricingResult::from_statistics(
+        &statistics.finish(),
+        num_simulated_samples * shock_signs.len() as u64,
+        None,
+    );
+    result.record_non_finite_paths(non_finite_paths);
+    result.record_correlation_repair(engine.correlation.adjustment());
+    result.record_dimension_budget(engine.dimension_budget(config.sampling));
+    result.check_error_tolerance(config.error_tolerance);
+    result.check_std_error();
+    Ok(result)
+}
+
+/// Moments the terminal prices of an underlying are matched to
+struct MomentTarget {
+    /// Forward price at expiry
+    forward: f64,
+    /// Standard deviation of the log returns to expiry, if it is matched
+    log_std_dev: Option<f64>,
+}
+
+/// Returns the forward of each underlying and, with lognormal marginals, the standard
+/// deviation of its log returns at its ATM implied volatility
+fn moment_targets(
+    underlyings: &[Underlying],
+    rate_curve: &RateCurve,
+    time_horizon_days: u32,
+    config: &SimulationConfig,
+) -> Vec<MomentTarget> {
+    let time_to_expiration = config.day_count.year_fraction(time_horizon_days);
+    let discount_factor = rate_curve.discount_factor(time_to_expiration);
+    underlyings
+        .iter()
+        .map(|underlying| {
+            let forward =
+                underlying.prepaid_forward(rate_curve, time_to_expiration, config.day_count)
+                    / discount_factor;
+            let log_std_dev = config
+                .model
+                .has_black_scholes_marginals()
+                .then(|| match underlying.smile_volatility(forward, forward) {
+                    Some(volatility) => volatility * time_to_expiration.sqrt(),
+                    None => underlying
+                        .integrated_variance(
+                            0,
+                            time_horizon_days,
+                            config.day_count,
+                            config.variance_time,
+                        )
+                        .sqrt(),
+                });
+            MomentTarget {
+                forward,
+                log_std_dev,
+            }
+        })
+        .collect()
+}
+
+/// Rescales each column of terminal prices to the moments of its target
+fn match_moments(terminal_prices: &mut DMatrix<f64>, targets: &[MomentTarget]) {
+    let num_paths = terminal_prices.nrows() as f64;
+    for (mut column, target) in terminal_prices.column_iter_mut().zip(targets) {
+        if let Some(log_std_dev) = target.log_std_dev {
+            let mean = column.iter().map(|&price| math::ln(price)).sum::<f64>() / num_paths;
+            let std_dev = (column
+                .iter()
+                .map(|&price| (math::ln(price) - mean).powi(2))
+                .sum::<f64>()
+                / num_paths)
+                .sqrt();
+            if std_dev > 0.0 {
+                let scale = log_std_dev / std_dev;
+                column.apply(|price| *price = math::exp(mean + (math::ln(*price) - mean) * scale));
+            }
+        }
+        let mean = column.sum() / num_paths;
+        if mean > 0.0 {
+            column *= target.forward / mean;
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use nalgebra::DMatrix;
+
+use crate::config::SimulationConfig;
+use crate::market::MarketSnapshot;
+use crate::result::PricingResult;
+use crate::underlying::Underlying;
+
+/// Seed used by the deterministic configurations
+pub const TEST_SEED: u64 = 42;
+
+/// Creates a seeded configuration, so repeated runs produce identical prices
+pub fn deterministic_config(num_paths: u64) -> SimulationConfig {
+    SimulationConfig::new(num_paths).with_seed(TEST_SEED)
+}
+
+/// Returns `true` if the estimated price lies within `k` standard errors of `expected`
+pub fn is_within_std_errors(result: &PricingResult, expected: f64, k: f64) -> bool {
+    (result.price - expected).abs() <= k * result.std_error
+}
+
+/// Asserts that the estimated price lies within `k` standard errors of `expected`
+///
+/// # Panics
+/// Panics with a descriptive message if the price is further away than `k` standard errors.
+#[track_caller]
+pub fn assert_within_std_errors(result: &PricingResult, expected: f64, k: f64) {
+    assert!(
+        is_within_std_errors(result, expected, k),
+        "Price {} is not within {} standard errors ({}) of expected {}",
+        result.price,
+        k,
+        result.std_error,
+        expected
+    );
+}
+
+/// Single stock with spot 100, 20% volatility and a 5% risk-free rate
+pub fn single_stock() -> MarketSnapshot {
+    MarketSnapshot::new(
+        vec![Underlying::new("STOCK".to_string(), 100.0, 0.20)],
+        DMatrix::identity(1, 1),
+        0.05,
+    )
+}
+
+/// Two stocks at spot 100 with 20% and 25% volatility, correlated at 0.5
+pub fn two_asset_basket() -> MarketSnapshot {
+    MarketSnapshot::new(
+        vec![
+            Underlying::new("STOCK1".to_string(), 100.0, 0.20),
+            Underlying::new("STOCK2".to_string(), 100.0, 0.25),
+        ],
+        uniform_correlation(2, 0.5),
+        0.05,
+    )
+}
+
+/// Three stocks at spot 100 with 20%, 25% and 30% volatility, pairwise correlated at 0.4
+pub fn three_asset_basket() -> MarketSnapshot {
+    MarketSnapshot::new(
+        vec![
+            Underlying::new("STOCK1".to_string(), 100.0, 0.20),
+            Underlying::new("STOCK2".to_string(), 100.0, 0.25),
+            Underlying::new("STOCK3".to_string(), 100.0, 0.30),
+        ],
+        uniform_correlation(3, 0.4),
+        0.05,
+    )
+}
+
+/// Creates an n x n correlation matrix with the same correlation between every pair
+pub fn uniform_correlation(size: usize, correlation: f64) -> DMatrix<f64> {
+    DMatrix::from_fn(size, size, |i, j| if i == j { 1.0 } else { correlation })
+}
@@ -0,0 +1,255 @@
+use crate::barrier::Barrier;
+use crate::config::SimulationConfig;
+use crate::error::McError;
+use crate::market::MarketSnapshot;
+use crate::product::Product;
+use crate::result::PricingResult;
+use crate::stress::{CorrelationStress, ScenarioPricer, StressScenario};
+
+/// Input of a product's price perturbed by a tornado report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TornadoInput {
+    /// Spot prices and marked forwards of all underlyings
+    Spot,
+    /// Volatilities of all underlyings, term structures included
+    Volatility,
+    /// Zero rates
+    Rate,
+    /// Correlations between different underlyings
+    Correlation,
+    /// Continuous dividend yields of all underlyings
+    DividendYield,
+    /// Level of the barrier
+    BarrierLevel,
+}
+
+/// Sizes of the perturbations of a tornado report, each applied down and up
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TornadoBumps {
+    /// Relative shift of the spot prices and marked forwards (10% by default)
+    pub spot: f64,
+    /// Absolute shift of the volatilities (5 volatility points by default)
+    pub volatility: f64,
+    /// Parallel shift of the zero rates (100 basis points by default)
+    pub rate: f64,
+    /// Shift of the correlations, clamped to [-1, 1] (0.1 by default)
+    pub correlation: f64,
+    /// Absolute shift of the continuous dividend yields (1% by default)
+    pub dividend_yield: f64,
+    /// Relative shift of the barrier level (5% by default)
+    pub barrier_level: f64,
+}
+
+impl Default for TornadoBumps {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TornadoBumps {
+    /// Creates the standard perturbations
+    pub fn new() -> Self {
+        Self {
+            spot: 0.10,
+            volatility: 0.05,
+            rate: 0.01,
+            correlation: 0.10,
+            dividend_yield: 0.01,
+            barrier_level: 0.05,
+        }
+    }
+
+    /// Sets the relative shift of the spot prices
+    pub fn with_spot(mut self, spot: f64) -> Self {
+        self.spot = spot;
+        self
+    }
+
+    /// Sets the absolute shift of the volatilities
+    pub fn with_volatility(mut self, volatility: f64) -> Self {
+        self.volatility = volatility;
+        self
+    }
+
+    /// Sets the parallel shift of the zero rates
+    pub fn with_rate(mut self, rate: f64) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    /// Sets the shift of the correlations
+    pub fn with_correlation(mut self, correlation: f64) -> Self {
+        self.correlation = correlation;
+        self
+    }
+
+    /// Sets the absolute shift of the dividend yields
+    pub fn with_dividend_yield(mut self, dividend_yield: f64) -> Self {
+        self.dividend_yield = dividend_yield;
+        self
+    }
+
+    /// Sets the relative shift of the barrier level
+    pub fn with_barrier_level(mut self, barrier_level: f64) -> Self {
+        self.barrier_level = barrier_level;
+        self
+    }
+}
+
+/// Prices of a product with one input perturbed down and up, a bar of the tornado diagram
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TornadoBar {
+    /// Perturbed input
+    pub input: TornadoInput,
+    /// Size of the perturbation
+    pub bump: f64,
+    /// Price with the input perturbed down
+    pub down: PricingResult,
+    /// Price with the input perturbed up
+    pub up: PricingResult,
+}
+
+impl TornadoBar {
+    /// Returns the width of the bar: the absolute difference of the up and down prices
+    pub fn swing(&self) -> f64 {
+        (self.up.price - self.down.price).abs()
+    }
+}
+
+/// Price impacts of perturbing each input of a product, ranked by their swing
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TornadoReport {
+    /// Price of the product on the unperturbed market
+    pub base: PricingResult,
+    /// One bar per perturbed input, the widest first
+    pub bars: Vec<TornadoBar>,
+}
+
+impl TornadoReport {
+    /// Returns the bar of the given input, if it was perturbed
+    pub fn bar(&self, input: TornadoInput) -> Option<&TornadoBar> {
+        self.bars.iter().find(|bar| bar.input == input)
+    }
+
+    /// Returns the changes of the down and up prices against the base price per input, the
+    /// widest bar first
+    pub fn impacts(&self) -> Vec<(TornadoInput, f64, f64)> {
+        self.bars
+            .iter()
+            .map(|bar| {
+                (
+                    bar.input,
+                    bar.down.price - self.base.price,
+                    bar.up.price - self.base.price,
+                )
+            })
+            .collect()
+    }
+}
+
+/// Perturbs every input of a product's price down and up by standard amounts and ranks the
+/// price impacts, the data of a tornado diagram
+///
+/// Spots, volatilities, rates, correlations and dividend yields are shifted for all
+/// underlyings at once, like a scenario of a `StressGrid`. Correlations are only perturbed
+/// with several underlyings and the barrier level only for products with a barrier.
+/// Dividend yields have no impact on underlyings with marked forwards. All prices are
+/// simulated with the same seed (the configured one, or one drawn for the whole report), so
+/// the impacts carry little Monte Carlo noise. Relative barriers keep their level relative
+/// to the unperturbed spots.
+///
+/// # Arguments
+/// * `market` - Unperturbed market
+/// * `time_horizon_days` - Time to expiration of the product in days
+/// * `product` - Product to reprice
+/// * `bumps` - Sizes of the perturbations
+/// * `config` - Number of paths and variance reduction settings. The error tolerance and
+///   the sanity checks are ignored, so all prices simulate the same paths.
+///
+/// # Returns
+/// The base price and one bar per perturbed input, sorted by descending swing
+///
+/// # Errors
+/// Returns an error for invalid inputs (see `price_option`), in particular if a perturbation
+/// takes a volatility below zero or the correlation matrix out of positive
+/// semi-definiteness (unless `SimulationConfig::repair_correlation` is set).
+pub fn tornado_report(
+    market: &MarketSnapshot,
+    time_horizon_days: u32,
+    product: &Product,
+    bumps: &TornadoBumps,
+    config: &SimulationConfig,
+) -> Result<TornadoReport, McError> {
+    let pricer = ScenarioPricer::new(market, time_horizon_days, product, config)?;
+    let market = &pricer.market;
+    let barrier = pricer.barrier.as_ref();
+
+    let mut inputs = vec![
+        (TornadoInput::Spot, bumps.spot),
+        (TornadoInput::Volatility, bumps.volatility),
+        (TornadoInput::Rate, bumps.rate),
+    ];
+    if market.underlyings.len() > 1 {
+        inputs.push((TornadoInput::Correlation, bumps.correlation));
+    }
+    inputs.push((TornadoInput::DividendYield, bumps.dividend_yield));
+    if barrier.is_some() {
+        inputs.push((TornadoInput::BarrierLevel, bumps.barrier_level));
+    }
+
+    let price = |input: TornadoInput, shift: f64| match input {
+        TornadoInput::BarrierLevel => {
+            let barrier = barrier.map(|barrier| Barrier {
+                barrier_level: barrier.barrier_level * (1.0 + shift),
+                ..barrier.clone()
+            });
+            pricer.price(market, barrier.as_ref(), 0.0)
+        }
+        TornadoInput::DividendYield => {
+            let mut market = market.clone();
+            for underlying in &mut market.underlyings {
+                underlying.dividend_yield += shift;
+            }
+            pricer.price(&market, barrier, 0.0)
+        }
+        _ => {
+            let mut scenario = StressScenario {
+                spot_shift: 0.0,
+                volatility_shift: 0.0,
+                rate_shift: 0.0,
+                correlation_shift: 0.0,
+            };
+            match input {
+                TornadoInput::Spot => scenario.spot_shift = shift,
+                TornadoInput::Volatility => scenario.volatility_shift = shift,
+                TornadoInput::Rate => scenario.rate_shift = shift,
+                _ => scenario.correlation_shift = shift,
+            }
+            pricer.price(
+                &scenario.apply(market, CorrelationStress::Pairwise),
+                barrier,
+                scenario.volatility_shift,
+            )
+        }
+    };
+    let mut bars = inputs
+        .into_iter()
+        .map(|(input, bump)| {
+            Ok(TornadoBar {
+                input,
+                bump,
+                down: price(input, -bump)?,
+                up: price(input, bump)?,
+            })
+        })
+        .collect::<Result<Vec<_>, McError>>()?;
+    bars.sort_by(|a, b| b.swing().total_cmp(&a.swing()));
+    Ok(TornadoReport {
+        base: pricer.price(market, barrier, 0.0)?,
+        bars,
+    })
+}
@@ -1,23 +1,517 @@
+use crate::calendar::Date;
+use crate::config::{DayCountConvention, SimulationConfig, VarianceTime};
+use crate::error::McError;
+use crate::math;
+use crate::rates::RateCurve;
+use crate::smile::{SmileDynamics, VolatilitySmile};
+
+/// Discrete dividend paid by an underlying asset
+///
+/// The price drops by the dividend on the ex-dividend day, counted in days from today.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Dividend {
+    /// Fixed cash amount
+    Cash {
+        /// Ex-dividend day
+        day: u32,
+        /// Cash amount per share
+        amount: f64,
+    },
+    /// Fraction of the price on the ex-dividend day (e.g. 0.02 for 2%)
+    Proportional {
+        /// Ex-dividend day
+        day: u32,
+        /// Fraction of the price paid out
+        ratio: f64,
+    },
+}
+
+impl Dividend {
+    /// Creates a cash dividend going ex on the given date, converted into a day from the
+    /// valuation date with the day count convention and calendar of the configuration
+    ///
+    /// # Errors
+    /// Returns `McError::InvalidDate` if the ex-dividend date lies before the valuation date.
+    pub fn cash_on(
+        valuation: Date,
+        ex_date: Date,
+        amount: f64,
+        config: &SimulationConfig,
+    ) -> Result<Self, McError> {
+        Ok(Dividend::Cash {
+            day: config.days_between(valuation, ex_date)?,
+            amount,
+        })
+    }
+
+    /// Creates a proportional dividend going ex on the given date (see `cash_on`)
+    ///
+    /// # Errors
+    /// Returns `McError::InvalidDate` if the ex-dividend date lies before the valuation date.
+    pub fn proportional_on(
+        valuation: Date,
+        ex_date: Date,
+        ratio: f64,
+        config: &SimulationConfig,
+    ) -> Result<Self, McError> {
+        Ok(Dividend::Proportional {
+            day: config.days_between(valuation, ex_date)?,
+            ratio,
+        })
+    }
+
+    /// Returns the ex-dividend day
+    pub fn day(&self) -> u32 {
+        match self {
+            Dividend::Cash { day, .. } | Dividend::Proportional { day, .. } => *day,
+        }
+    }
+}
+
+/// Scheduled event moving an underlying on one day, e.g. an earnings announcement, whose
+/// variance adds to the variance of the day
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VolatilityEvent {
+    /// Day of the event, counted from today
+    pub day: u32,
+    /// Standard deviation of the log return the event adds (e.g. 0.05 for an expected move
+    /// of 5%)
+    pub std_dev: f64,
+}
+
+impl VolatilityEvent {
+    /// Creates an event on the given day
+    pub fn new(day: u32, std_dev: f64) -> Self {
+        Self { day, std_dev }
+    }
+}
+
+/// Kind of asset an underlying is, which determines how its price grows under the pricing
+/// measure
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AssetClass {
+    /// Stock or index, growing at the risk-free rate less its dividend yield and dropping by
+    /// its discrete dividends
+    #[default]
+    Equity,
+    /// Forward or futures price, which does not grow under the pricing measure (Black-76
+    /// dynamics). Dividends are ignored.
+    Future,
+    /// Commodity, growing at the cost of carry: the risk-free rate less the convenience yield.
+    /// Dividends are ignored.
+    Commodity {
+        /// Convenience yield net of storage costs (annualized, as a decimal); negative if
+        /// storing the commodity costs more than holding it yields
+        convenience_yield: f64,
+    },
+    /// Exchange rate in units of the domestic currency per unit of a foreign currency,
+    /// growing at the domestic less the foreign rate (Garman-Kohlhagen dynamics). Dividends
+    /// are ignored.
+    Fx {
+        /// Risk-free rate of the foreign currency (annualized, as a decimal)
+        foreign_rate: f64,
+    },
+}
+
+/// Terms of an underlying quoted in a foreign currency whose payoff is paid in the domestic
+/// currency at a fixed exchange rate of one, e.g. a Nikkei leg of a USD basket
+///
+/// The underlying then grows at the foreign rate instead of the domestic one, less the
+/// quanto adjustment for the covariance with the exchange rate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Quanto {
+    /// Risk-free rate of the currency the underlying is quoted in (annualized, as a decimal)
+    pub foreign_rate: f64,
+    /// Volatility of the exchange rate in units of the domestic currency per unit of the
+    /// foreign one
+    pub fx_volatility: f64,
+    /// Correlation between the log returns of the underlying and of the exchange rate
+    pub fx_correlation: f64,
+}
+
+impl Quanto {
+    /// Creates the quanto terms of an underlying
+    pub fn new(foreign_rate: f64, fx_volatility: f64, fx_correlation: f64) -> Self {
+        Self {
+            foreign_rate,
+            fx_volatility,
+            fx_correlation,
+        }
+    }
+
+    /// Returns the quanto adjustment of the drift, given the volatility of the underlying:
+    /// `-fx_correlation * fx_volatility * volatility`
+    pub fn drift_adjustment(&self, volatility: f64) -> f64 {
+        -self.fx_correlation * self.fx_volatility * volatility
+    }
+}
+
 /// Represents an underlying asset for option pricing
-#[derive(Debug, Clone)]
+///
+/// With the `serde` feature, the term structure, volatility events, smile, smile dynamics,
+/// dividends, forward curve, asset class and quanto terms may be omitted when deserializing, like in
+/// `Underlying::new`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Underlying {
     /// Name of the underlying asset
     pub name: String,
     /// Current spot price
     pub spot_price: f64,
-    /// Volatility (annualized, as a decimal, e.g., 0.20 for 20%)
+    /// Volatility (annualized, as a decimal, e.g., 0.20 for 20%), used for all days not
+    /// covered by the volatility term structure
     pub volatility: f64,
+    /// Piecewise-constant forward volatilities as (end day, volatility) pairs sorted by day:
+    /// each volatility applies from the previous end day (or today) up to its end day. Beyond
+    /// the last end day, the last volatility applies. Empty for a flat `volatility`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub volatility_term_structure: Vec<(u32, f64)>,
+    /// Scheduled events adding variance on their day, sorted by day
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub volatility_events: Vec<VolatilityEvent>,
+    /// Implied volatility smile picking the volatility of vanilla options by their strike
+    /// (see `smile_volatility`). Path-dependent payoffs are simulated with the volatility
+    /// and term structure instead.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub smile: Option<VolatilitySmile>,
+    /// Re-marking of the smile when the spot price is bumped in scenarios and for Greeks,
+    /// sticky delta by default
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub smile_dynamics: SmileDynamics,
+    /// Continuous dividend yield (annualized, as a decimal, e.g., 0.02 for 2%)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub dividend_yield: f64,
+    /// Discrete dividends, sorted by ex-dividend day
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub dividends: Vec<Dividend>,
+    /// Marked forward prices as (delivery day, forward) pairs sorted by day, e.g. from
+    /// dividend futures or broker forwards. If given, the drift is implied from the forwards
+    /// and the dividend yield and discrete dividends are ignored. Empty to imply the forwards
+    /// from the rates and dividends.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub forward_curve: Vec<(u32, f64)>,
+    /// Kind of asset, equity by default. Marked forwards imply the drift of any asset class.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub asset_class: AssetClass,
+    /// Quanto terms if the underlying is quoted in a foreign currency and paid in the
+    /// domestic one. Prices, strikes, barriers, dividends and marked forwards are all in the
+    /// foreign currency, taken as domestic amounts.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub quanto: Option<Quanto>,
 }
 
 impl Underlying {
-    /// Creates a new underlying asset
+    /// Creates a new underlying asset without dividends
     pub fn new(name: String, spot_price: f64, volatility: f64) -> Self {
         Self {
             name,
             spot_price,
             volatility,
+            volatility_term_structure: Vec::new(),
+            volatility_events: Vec::new(),
+            smile: None,
+            smile_dynamics: SmileDynamics::StickyDelta,
+            dividend_yield: 0.0,
+            dividends: Vec::new(),
+            forward_curve: Vec::new(),
+            asset_class: AssetClass::Equity,
+            quanto: None,
         }
     }
-}
 
+    /// Sets the kind of asset, e.g. `AssetClass::Future` for Black-76 dynamics
+    pub fn with_asset_class(mut self, asset_class: AssetClass) -> Self {
+        self.asset_class = asset_class;
+        self
+    }
+
+    /// Sets the quanto terms, for an underlying quoted in a foreign currency and paid in the
+    /// domestic one
+    pub fn with_quanto(mut self, quanto: Quanto) -> Self {
+        self.quanto = Some(quanto);
+        self
+    }
+
+    /// Returns the growth rate of the price without marked forwards, given the risk-free rate
+    /// over the same period: the rate less the dividend yield for equities, zero for futures,
+    /// the cost of carry for commodities and the rate differential for exchange rates
+    ///
+    /// For a quanto underlying, the foreign rate replaces the risk-free rate, and the quanto
+    /// adjustment (see `Quanto::drift_adjustment`) is not included.
+    pub fn carry_rate(&self, risk_free_rate: f64) -> f64 {
+        let rate = self
+            .quanto
+            .map_or(risk_free_rate, |quanto| quanto.foreign_rate);
+        match self.asset_class {
+            AssetClass::Equity => rate - self.dividend_yield,
+            AssetClass::Future => 0.0,
+            AssetClass::Commodity { convenience_yield } => rate - convenience_yield,
+            AssetClass::Fx { foreign_rate } => rate - foreign_rate,
+        }
+    }
+
+    /// Returns `true` if the price drops by the discrete dividends: the underlying is an
+    /// equity without marked forwards, which would embed them
+    pub fn pays_dividends(&self) -> bool {
+        self.asset_class == AssetClass::Equity && self.forward_curve.is_empty()
+    }
+
+    /// Returns `true` if the underlying is an exchange rate
+    pub fn is_fx(&self) -> bool {
+        matches!(self.asset_class, AssetClass::Fx { .. })
+    }
 
+    /// Sets piecewise-constant forward volatilities as (end day, volatility) pairs
+    ///
+    /// # Panics
+    /// Panics if the end days are not strictly increasing.
+    pub fn with_volatility_term_structure(mut self, forward_volatilities: Vec<(u32, f64)>) -> Self {
+        assert!(
+            forward_volatilities.windows(2).all(|pair| pair[0].0 < pair[1].0),
+            "Volatility term structure end days must be strictly increasing"
+        );
+        self.volatility_term_structure = forward_volatilities;
+        self
+    }
+
+    /// Sets the scheduled events adding variance on their day, e.g. earnings announcements.
+    /// Events on day 0 are considered past and are ignored.
+    pub fn with_volatility_events(mut self, mut events: Vec<VolatilityEvent>) -> Self {
+        events.sort_by_key(|event| event.day);
+        self.volatility_events = events;
+        self
+    }
+
+    /// Sets the implied volatility smile, used for vanilla options instead of the volatility
+    /// and term structure
+    pub fn with_smile(mut self, smile: VolatilitySmile) -> Self {
+        self.smile = Some(smile);
+        self
+    }
+
+    /// Sets the re-marking of the smile when the spot price is bumped: sticky strike keeps the
+    /// volatility of each strike, which changes the delta of vanilla options on a skew
+    pub fn with_smile_dynamics(mut self, smile_dynamics: SmileDynamics) -> Self {
+        self.smile_dynamics = smile_dynamics;
+        self
+    }
+
+    /// Returns the implied volatility of a vanilla option struck at `strike` on the given
+    /// forward: the smile volatility at the log-moneyness `ln(strike / forward)`, or `None`
+    /// without a smile
+    pub fn smile_volatility(&self, strike: f64, forward: f64) -> Option<f64> {
+        self.smile
+            .map(|smile| smile.implied_volatility(math::ln(strike / forward)))
+    }
+
+    /// Returns the forward volatility on the given day (the move from `day - 1` to `day`),
+    /// without the volatility events
+    pub fn volatility_on(&self, day: u32) -> f64 {
+        self.volatility_term_structure
+            .iter()
+            .find(|&&(end_day, _)| day <= end_day)
+            .or(self.volatility_term_structure.last())
+            .map_or(self.volatility, |&(_, volatility)| volatility)
+    }
+
+    /// Returns the variance of the log price accrued from `start_day` to `end_day`, i.e. the
+    /// squared forward volatilities integrated over the variance time of each bucket, plus the
+    /// variance of the events after `start_day` up to and including `end_day`
+    pub fn integrated_variance(
+        &self,
+        start_day: u32,
+        end_day: u32,
+        day_count: DayCountConvention,
+        variance_time: VarianceTime,
+    ) -> f64 {
+        if end_day <= start_day {
+            return 0.0;
+        }
+        // Events add their variance whatever the variance clock
+        let mut variance: f64 = self
+            .volatility_events
+            .iter()
+            .filter(|event| event.day > start_day && event.day <= end_day)
+            .map(|event| event.std_dev * event.std_dev)
+            .sum();
+        let mut bucket_start = start_day;
+        for &(bucket_end, volatility) in &self.volatility_term_structure {
+            if bucket_end <= bucket_start {
+                continue;
+            }
+            let bucket_end = bucket_end.min(end_day);
+            variance += volatility
+                * volatility
+                * variance_time.year_fraction(day_count, bucket_start, bucket_end);
+            bucket_start = bucket_end;
+            if bucket_start == end_day {
+                return variance;
+            }
+        }
+        // The last volatility, or the flat one, applies beyond the term structure
+        let volatility = self.volatility_on(end_day);
+        variance + volatility * volatility * variance_time.year_fraction(day_count, bucket_start, end_day)
+    }
+
+    /// Sets the continuous dividend yield
+    pub fn with_dividend_yield(mut self, dividend_yield: f64) -> Self {
+        self.dividend_yield = dividend_yield;
+        self
+    }
+
+    /// Sets the discrete dividends. Dividends on day 0 are considered paid already and
+    /// are ignored by the simulation.
+    pub fn with_dividends(mut self, mut dividends: Vec<Dividend>) -> Self {
+        dividends.sort_by_key(Dividend::day);
+        self.dividends = dividends;
+        self
+    }
+
+    /// Returns `true` if the underlying pays discrete cash dividends, which make the
+    /// terminal price distribution non-lognormal. Dividends embedded in a marked forward
+    /// curve do not count.
+    pub fn has_cash_dividends(&self) -> bool {
+        self.pays_dividends()
+            && self
+                .dividends
+                .iter()
+                .any(|dividend| matches!(dividend, Dividend::Cash { .. }))
+    }
+
+    /// Sets the marked forward prices as (delivery day, forward) pairs, replacing the
+    /// dividend yield and discrete dividends in the drift
+    ///
+    /// # Panics
+    /// Panics if the delivery days are not positive and strictly increasing, or a forward is
+    /// not positive.
+    pub fn with_forward_curve(mut self, forwards: Vec<(u32, f64)>) -> Self {
+        assert!(
+            forwards.first().is_none_or(|&(day, _)| day > 0)
+                && forwards.windows(2).all(|pair| pair[0].0 < pair[1].0),
+            "Forward curve delivery days must be positive and strictly increasing"
+        );
+        assert!(
+            forwards.iter().all(|&(_, forward)| forward > 0.0),
+            "Forward prices must be positive"
+        );
+        self.forward_curve = forwards;
+        self
+    }
+
+    /// Returns the marked forward price for delivery in `time` years, or `None` without a
+    /// forward curve
+    ///
+    /// Forwards are interpolated log-linearly between the spot today and the marks, i.e. with
+    /// a constant carry rate between two marks. Beyond the last mark, the carry of the last
+    /// segment continues.
+    pub fn marked_forward(&self, time: f64, day_count: DayCountConvention) -> Option<f64> {
+        let mut previous = (0.0, math::ln(self.spot_price));
+        let mut carry = 0.0;
+        for &(day, forward) in &self.forward_curve {
+            let mark = (day_count.year_fraction(day), math::ln(forward));
+            carry = (mark.1 - previous.1) / (mark.0 - previous.0);
+            if time <= mark.0 {
+                break;
+            }
+            previous = mark;
+        }
+        if self.forward_curve.is_empty() {
+            return None;
+        }
+        Some(math::exp(previous.1 + carry * (time - previous.0)))
+    }
+
+    /// Returns the prepaid forward, i.e. the present value of receiving the asset at
+    /// `time_to_expiration` years, net of all dividends paid until then
+    ///
+    /// For a quanto underlying, this is the present value in the domestic currency of
+    /// receiving the foreign price as a domestic amount: the foreign forward with the quanto
+    /// adjustment, discounted on the domestic curve.
+    ///
+    /// # Arguments
+    /// * `rate_curve` - Risk-free rate curve discounting the cash dividends
+    /// * `time_to_expiration` - Delivery time in years
+    /// * `day_count` - Convention converting the ex-dividend days into year fractions
+    pub fn prepaid_forward(
+        &self,
+        rate_curve: &RateCurve,
+        time_to_expiration: f64,
+        day_count: DayCountConvention,
+    ) -> f64 {
+        let Some(quanto) = self.quanto else {
+            return self.local_prepaid_forward(rate_curve, time_to_expiration, day_count);
+        };
+        let foreign_curve = RateCurve::flat(quanto.foreign_rate);
+        let forward = self.local_prepaid_forward(&foreign_curve, time_to_expiration, day_count)
+            / foreign_curve.discount_factor(time_to_expiration);
+        let days = (time_to_expiration * day_count.days_per_year()).round() as u32;
+        forward
+            * math::exp(self.quanto_adjustment(0, days, day_count))
+            * rate_curve.discount_factor(time_to_expiration)
+    }
+
+    /// Returns the quanto adjustment of the log forward from `start_day` to `end_day`,
+    /// accrued with the forward volatility of each day; zero without quanto terms
+    pub(crate) fn quanto_adjustment(
+        &self,
+        start_day: u32,
+        end_day: u32,
+        day_count: DayCountConvention,
+    ) -> f64 {
+        let Some(quanto) = self.quanto else {
+            return 0.0;
+        };
+        (start_day + 1..=end_day)
+            .map(|day| quanto.drift_adjustment(self.volatility_on(day)))
+            .sum::<f64>()
+            / day_count.days_per_year()
+    }
+
+    /// Returns the prepaid forward in the currency the underlying is quoted in, discounting
+    /// on the given curve
+    fn local_prepaid_forward(
+        &self,
+        rate_curve: &RateCurve,
+        time_to_expiration: f64,
+        day_count: DayCountConvention,
+    ) -> f64 {
+        if let Some(forward) = self.marked_forward(time_to_expiration, day_count) {
+            return forward * rate_curve.discount_factor(time_to_expiration);
+        }
+        match self.asset_class {
+            AssetClass::Equity => {}
+            AssetClass::Future => {
+                return self.spot_price * rate_curve.discount_factor(time_to_expiration)
+            }
+            AssetClass::Commodity { convenience_yield } => {
+                return self.spot_price * math::exp(-convenience_yield * time_to_expiration)
+            }
+            AssetClass::Fx { foreign_rate } => {
+                return self.spot_price * math::exp(-foreign_rate * time_to_expiration)
+            }
+        }
+        // Prepaid forward for delivery at each ex-dividend day in turn: the yield accrues in
+        // between, cash dividends are deducted at their present value
+        let mut prepaid_forward = self.spot_price;
+        let mut time = 0.0;
+        for dividend in &self.dividends {
+            let ex_time = day_count.year_fraction(dividend.day());
+            if dividend.day() == 0 || ex_time > time_to_expiration {
+                continue;
+            }
+            prepaid_forward *= math::exp(-self.dividend_yield * (ex_time - time));
+            time = ex_time;
+            match dividend {
+                Dividend::Cash { amount, .. } => {
+                    prepaid_forward -= amount * rate_curve.discount_factor(ex_time)
+                }
+                Dividend::Proportional { ratio, .. } => prepaid_forward *= 1.0 - ratio,
+            }
+        }
+        prepaid_forward * math::exp(-self.dividend_yield * (time_to_expiration - time))
+    }
+}
@@ -0,0 +1,409 @@
+use nalgebra::DMatrix;
+
+use crate::barrier::{Barrier, BarrierDirection, BarrierMonitoring, KnockType};
+use crate::config::{Sampling, SimulationConfig};
+use crate::error::McError;
+use crate::payoff::Payoff;
+use crate::qmc::SOBOL_MAX_POINTS;
+use crate::rates::RateCurve;
+use crate::result::{PricingResult, PricingWarning, TOLERANCE_STD_ERRORS};
+use crate::{calculate_reference, effective_barrier_level, simulate_payoff};
+use crate::underlying::Underlying;
+
+/// Relative size of the strike and barrier level bumps for the monotonicity checks
+const RELATIVE_BUMP: f64 = 0.01;
+
+/// Number of daily standard deviations within which a barrier counts as close to the spot
+const BARRIER_PROXIMITY_STD_DEVS: f64 = 2.0;
+
+/// Largest deviation of the correlation matrix from symmetry and from a unit diagonal that
+/// is accepted as rounding noise
+const CORRELATION_TOLERANCE: f64 = 1e-10;
+
+/// Checks the market data and the path count of a simulation: at least one underlying with a
+/// positive spot price, non-negative volatilities and valid quanto terms, a correlation matrix of matching
+/// dimensions that is symmetric with a unit diagonal and entries in [-1, 1], model parameters
+/// for every underlying and at least one path, but no more than the Sobol sequences provide
+/// points for
+///
+/// Whether the correlation matrix is positive definite is checked by its decomposition in
+/// the path engine.
+pub(crate) fn validate_inputs(
+    underlyings: &[Underlying],
+    correlation_matrix: &DMatrix<f64>,
+    config: &SimulationConfig,
+) -> Result<(), McError> {
+    if underlyings.is_empty() {
+        return Err(McError::NoUnderlyings);
+    }
+    if config.num_paths == 0 {
+        return Err(McError::InvalidPaths(config.num_paths));
+    }
+    // The replications split the paths, so no run needs more points than there are paths
+    if matches!(config.sampling, Sampling::Sobol | Sampling::ScrambledSobol)
+        && config.num_paths > SOBOL_MAX_POINTS
+    {
+        return Err(McError::InvalidPaths(config.num_paths));
+    }
+    for underlying in underlyings {
+        if !(underlying.spot_price.is_finite() && underlying.spot_price > 0.0) {
+            return Err(McError::InvalidSpotPrice {
+                underlying: underlying.name.clone(),
+                spot_price: underlying.spot_price,
+            });
+        }
+        if let Some(smile) = &underlying.smile {
+            smile.validate().map_err(|reason| McError::InvalidSmile {
+                underlying: underlying.name.clone(),
+                reason,
+            })?;
+        }
+        let volatilities = std::iter::once(underlying.volatility)
+            .chain(
+                underlying
+                    .volatility_term_structure
+                    .iter()
+                    .map(|&(_, volatility)| volatility),
+            )
+            .chain(underlying.volatility_events.iter().map(|event| event.std_dev));
+        for volatility in volatilities {
+            if !(volatility.is_finite() && volatility >= 0.0) {
+                return Err(McError::InvalidVolatility {
+                    underlying: underlying.name.clone(),
+                    volatility,
+                });
+            }
+        }
+        if let Some(quanto) = &underlying.quanto {
+            let reason = if !(quanto.fx_volatility.is_finite() && quanto.fx_volatility >= 0.0) {
+                Some(format!("FX volatility {} is negative or not finite", quanto.fx_volatility))
+            } else if !(-1.0..=1.0).contains(&quanto.fx_correlation) {
+                Some(format!("FX correlation {} is outside of [-1, 1]", quanto.fx_correlation))
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                return Err(McError::InvalidQuanto {
+                    underlying: underlying.name.clone(),
+                    reason,
+                });
+            }
+        }
+    }
+
+    validate_correlation_matrix(correlation_matrix, underlyings.len())?;
+    config.model.validate(underlyings)
+}
+
+/// Checks that there is a weight for each of the `num_paths` simulated paths, that the
+/// weights are non-negative and finite, and that the paths do not all have weight zero;
+/// weights of further paths are ignored
+pub(crate) fn validate_path_weights(path_weights: &[f64], num_paths: u64) -> Result<(), McError> {
+    let Some(weights) = usize::try_from(num_paths)
+        .ok()
+        .and_then(|num_paths| path_weights.get(..num_paths))
+    else {
+        return Err(McError::InvalidPathWeights(format!(
+            "expected a weight for each of the {} simulated paths, got {}",
+            num_paths,
+            path_weights.len()
+        )));
+    };
+    if let Some(&weight) = weights
+        .iter()
+        .find(|&&weight| !(weight.is_finite() && weight >= 0.0))
+    {
+        return Err(McError::InvalidPathWeights(format!(
+            "weight {} is negative or not finite",
+            weight
+        )));
+    }
+    if weights.iter().all(|&weight| weight == 0.0) {
+        return Err(McError::InvalidPathWeights("all paths have weight zero".to_string()));
+    }
+    Ok(())
+}
+
+/// Checks that the correlation matrix is `n x n`, symmetric with a unit diagonal and has
+/// entries in [-1, 1]; it need not be positive definite
+pub(crate) fn validate_correlation_matrix(
+    correlation_matrix: &DMatrix<f64>,
+    n: usize,
+) -> Result<(), McError> {
+    if correlation_matrix.nrows() != n || correlation_matrix.ncols() != n {
+        return Err(McError::InvalidCorrelationMatrix(format!(
+            "expected {}x{} for {} underlyings, got {}x{}",
+            n,
+            n,
+            n,
+            correlation_matrix.nrows(),
+            correlation_matrix.ncols()
+        )));
+    }
+    for i in 0..n {
+        let diagonal = correlation_matrix[(i, i)];
+        if diagonal.is_nan() || (diagonal - 1.0).abs() > CORRELATION_TOLERANCE {
+            return Err(McError::InvalidCorrelationMatrix(format!(
+                "diagonal entry {} is {}, not 1",
+                i, diagonal
+            )));
+        }
+        for j in 0..i {
+            let (lower, upper) = (correlation_matrix[(i, j)], correlation_matrix[(j, i)]);
+            if !(-1.0..=1.0).contains(&lower) {
+                return Err(McError::InvalidCorrelationMatrix(format!(
+                    "entry ({}, {}) is {}, outside of [-1, 1]",
+                    i, j, lower
+                )));
+            }
+            if (lower - upper).abs() > CORRELATION_TOLERANCE {
+                return Err(McError::InvalidCorrelationMatrix(format!(
+                    "entries ({}, {}) and ({}, {}) differ: {} vs {}",
+                    i, j, j, i, lower, upper
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the barrier only refers to existing underlyings and has a positive level
+pub(crate) fn validate_barrier(barrier: &Barrier, num_underlyings: usize) -> Result<(), McError> {
+    if let Some(&index) = barrier
+        .underlying_indices
+        .iter()
+        .find(|&&index| index >= num_underlyings)
+    {
+        return Err(McError::BarrierIndexOutOfRange {
+            index,
+            num_underlyings,
+        });
+    }
+    if barrier.underlying_indices.is_empty() {
+        return Err(McError::InvalidBarrier(
+            "barrier must apply to at least one underlying".to_string(),
+        ));
+    }
+    if !(barrier.barrier_level.is_finite() && barrier.barrier_level > 0.0) {
+        return Err(McError::InvalidBarrier(format!(
+            "barrier level must be positive and finite, got {}",
+            barrier.barrier_level
+        )));
+    }
+    Ok(())
+}
+
+/// Checks the terms of a payoff: a non-negative and finite strike price, digital amount,
+/// cliquet and swap notional and swap strike, cliquet floors not above their caps, and a
+/// positive and finite number of swap observations per year
+pub(crate) fn validate_payoff(payoff: &Payoff) -> Result<(), McError> {
+    if let Some(strike_price) = payoff.strike_price() {
+        validate_strike(strike_price)?;
+    }
+    match payoff {
+        Payoff::CashOrNothing { amount, .. } if !(amount.is_finite() && *amount >= 0.0) => {
+            Err(McError::InvalidProduct(format!(
+                "digital amount must be non-negative and finite, got {}",
+                amount
+            )))
+        }
+        Payoff::Cliquet { notional, .. } if !(notional.is_finite() && *notional >= 0.0) => {
+            Err(McError::InvalidProduct(format!(
+                "cliquet notional must be non-negative and finite, got {}",
+                notional
+            )))
+        }
+        Payoff::VarianceSwap {
+            notional,
+            strike_volatility,
+            observations_per_year,
+            ..
+        }
+        | Payoff::VolatilitySwap {
+            notional,
+            strike_volatility,
+            observations_per_year,
+            ..
+        } => {
+            if !(notional.is_finite() && *notional >= 0.0) {
+                Err(McError::InvalidProduct(format!(
+                    "swap notional must be non-negative and finite, got {}",
+                    notional
+                )))
+            } else if !(strike_volatility.is_finite() && *strike_volatility >= 0.0) {
+                Err(McError::InvalidProduct(format!(
+                    "swap strike must be non-negative and finite, got {}",
+                    strike_volatility
+                )))
+            } else if !(observations_per_year.is_finite() && *observations_per_year > 0.0) {
+                Err(McError::InvalidProduct(format!(
+                    "swap observations per year must be positive and finite, got {}",
+                    observations_per_year
+                )))
+            } else {
+                Ok(())
+            }
+        }
+        Payoff::Cliquet {
+            local_floor: Some(floor),
+            local_cap: Some(cap),
+            ..
+        }
+        | Payoff::Cliquet {
+            global_floor: Some(floor),
+            global_cap: Some(cap),
+            ..
+        } if floor > cap => Err(McError::InvalidProduct(format!(
+            "cliquet floor {} lies above its cap {}",
+            floor, cap
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Checks that the strike price of an option is non-negative and finite
+pub(crate) fn validate_strike(strike_price: f64) -> Result<(), McError> {
+    if !(strike_price.is_finite() && strike_price >= 0.0) {
+        return Err(McError::InvalidProduct(format!(
+            "strike price must be non-negative and finite, got {}",
+            strike_price
+        )));
+    }
+    Ok(())
+}
+
+/// Runs the sanity checks for a priced product, repricing related products on the same
+/// paths (the config must be seeded) and returning a warning for every violated check
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn run_checks(
+    underlyings: &[Underlying],
+    correlation_matrix: &DMatrix<f64>,
+    time_horizon_days: u32,
+    payoff: &Payoff,
+    rate_curve: &RateCurve,
+    barrier: Option<&Barrier>,
+    config: &SimulationConfig,
+    result: &PricingResult,
+) -> Result<Vec<PricingWarning>, McError> {
+    // The observer only sees the paths of the priced product, and the repricings run on the
+    // paths the price was estimated on
+    let config = &SimulationConfig {
+        path_observer: None,
+        num_paths: result.num_paths,
+        error_tolerance: None,
+        ..config.clone()
+    };
+    let reprice = |payoff: &Payoff, barrier: Option<&Barrier>| {
+        simulate_payoff(
+            underlyings,
+            correlation_matrix,
+            time_horizon_days,
+            payoff,
+            rate_curve,
+            barrier,
+            config,
+        )
+    };
+    let spot_price = underlyings[0].spot_price;
+    let time_to_expiration = config.day_count.year_fraction(time_horizon_days);
+    let discount_factor = rate_curve.discount_factor(time_to_expiration);
+    let tolerance = TOLERANCE_STD_ERRORS * result.std_error;
+    let mut warnings = Vec::new();
+
+    // Put-call parity: C - P = S* - K * e^(-rT) for vanilla options, where S* is the prepaid
+    // forward net of dividends
+    if let (Payoff::Vanilla { strike_price, .. }, None) = (payoff, barrier) {
+        let complement = reprice(&payoff.complement(), None)?;
+        let (call, put) = if payoff.is_call() {
+            (result.price, complement.price)
+        } else {
+            (complement.price, result.price)
+        };
+        let prepaid_forward =
+            underlyings[0].prepaid_forward(rate_curve, time_to_expiration, config.day_count);
+        let residual = call - put - (prepaid_forward - strike_price * discount_factor);
+        // A small absolute floor avoids flagging rounding noise of exact (zero error) estimates
+        let parity_tolerance = TOLERANCE_STD_ERRORS * (result.std_error + complement.std_error)
+            + 1e-9 * spot_price;
+        if residual.abs() > parity_tolerance {
+            warnings.push(PricingWarning::PutCallParity {
+                residual,
+                tolerance: parity_tolerance,
+            });
+        }
+    }
+
+    // Monotonicity in strike: calls must not gain and puts must not lose value as K rises
+    if let Some(strike_price) = payoff.strike_price() {
+        let bumped_strike = strike_price * (1.0 + RELATIVE_BUMP);
+        let bumped = reprice(&payoff.with_strike_price(bumped_strike), barrier)?;
+        let is_violated = if payoff.is_call() {
+            bumped.price > result.price + tolerance
+        } else {
+            bumped.price < result.price - tolerance
+        };
+        if is_violated {
+            warnings.push(PricingWarning::StrikeMonotonicity {
+                bumped_strike,
+                bumped_price: bumped.price,
+            });
+        }
+    }
+
+    // Monotonicity in barrier level: moving the barrier towards the spot makes hits more
+    // likely, so "out" options must not gain and "in" options must not lose value. A rebate
+    // gains value as the hits become more likely, so the price need not be monotone.
+    if let Some(barrier) = barrier.filter(|barrier| barrier.rebate.is_none()) {
+        let mut bumped_barrier = barrier.clone();
+        bumped_barrier.barrier_level *= match barrier.direction {
+            BarrierDirection::Up => 1.0 - RELATIVE_BUMP,
+            BarrierDirection::Down => 1.0 + RELATIVE_BUMP,
+        };
+        let bumped = reprice(payoff, Some(&bumped_barrier))?;
+        let is_violated = if barrier.knock_type == KnockType::In {
+            bumped.price < result.price - tolerance
+        } else {
+            bumped.price > result.price + tolerance
+        };
+        if is_violated {
+            warnings.push(PricingWarning::BarrierMonotonicity {
+                bumped_level: bumped_barrier.barrier_level,
+                bumped_price: bumped.price,
+            });
+        }
+    }
+
+    Ok(warnings)
+}
+
+/// Warns if a continuously monitored barrier is so close to the spot that a few daily moves
+/// decide whether it is hit, where the daily monitoring grid misses many continuous crossings
+pub(crate) fn barrier_warning(
+    underlyings: &[Underlying],
+    barrier: &Barrier,
+    config: &SimulationConfig,
+) -> Option<PricingWarning> {
+    if barrier.monitoring != BarrierMonitoring::Continuous {
+        return None;
+    }
+    let initial_prices: Vec<f64> = underlyings.iter().map(|u| u.spot_price).collect();
+    let reference = calculate_reference(
+        &initial_prices,
+        &barrier.underlying_indices,
+        barrier.barrier_type,
+    );
+    let distance = (effective_barrier_level(barrier, &initial_prices) / reference - 1.0).abs();
+    let max_volatility = barrier
+        .underlying_indices
+        .iter()
+        .map(|&idx| underlyings[idx].volatility_on(1))
+        .fold(0.0, f64::max);
+    let daily_std_dev = max_volatility * config.day_count.year_fraction(1).sqrt();
+
+    (distance < BARRIER_PROXIMITY_STD_DEVS * daily_std_dev).then_some(
+        PricingWarning::BarrierNearSpot {
+            distance,
+            daily_std_dev,
+        },
+    )
+}
@@ -0,0 +1,72 @@
+use crate::config::SimulationConfig;
+use crate::error::McError;
+use crate::market::MarketSnapshot;
+use crate::payoff::FixingSchedule;
+use crate::product::Product;
+use crate::session::PricingSession;
+
+/// Fair strikes of a variance and a volatility swap on the same fixings, at which both swaps
+/// are worth zero
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FairStrikes {
+    /// Fair variance strike quoted as a volatility: the square root of the expected realized
+    /// variance
+    pub variance_strike: f64,
+    /// Standard error of the fair variance strike
+    pub variance_std_error: f64,
+    /// Fair volatility strike: the expected realized volatility, below the variance strike by
+    /// the convexity adjustment
+    pub volatility_strike: f64,
+    /// Standard error of the fair volatility strike
+    pub volatility_std_error: f64,
+}
+
+/// Computes the fair strikes of a variance and a volatility swap on the first underlying,
+/// from the realized variance of the same simulated paths
+///
+/// # Arguments
+/// * `market` - Underlyings, correlations and rate curve
+/// * `time_horizon_days` - Time to expiration in days
+/// * `observations_per_year` - Number of returns per year annualizing the realized variance
+/// * `schedule` - Fixing days, each ending a return
+/// * `config` - Number of paths and further settings
+///
+/// # Returns
+/// The fair variance and volatility strikes with their standard errors
+///
+/// # Errors
+/// Returns an error for invalid market data, path counts or swap terms (see `price_payoff`).
+pub fn fair_swap_strikes(
+    market: &MarketSnapshot,
+    time_horizon_days: u32,
+    observations_per_year: f64,
+    schedule: FixingSchedule,
+    config: &SimulationConfig,
+) -> Result<FairStrikes, McError> {
+    let session = PricingSession::new(market.clone(), time_horizon_days, config)?;
+    let discount_factor = market
+        .risk_free_rate
+        .discount_factor(config.day_count.year_fraction(time_horizon_days));
+    // At a zero strike and unit notional, the swaps are worth the discounted expectations
+    let variance = session.price(&Product::variance_swap(
+        1.0,
+        0.0,
+        observations_per_year,
+        schedule.clone(),
+    ))?;
+    let volatility = session.price(&Product::volatility_swap(
+        1.0,
+        0.0,
+        observations_per_year,
+        schedule,
+    ))?;
+    let variance_strike = (variance.price / discount_factor).max(0.0).sqrt();
+    Ok(FairStrikes {
+        variance_strike,
+        // Delta method on the square root
+        variance_std_error: variance.std_error / discount_factor / (2.0 * variance_strike),
+        volatility_strike: volatility.price / discount_factor,
+        volatility_std_error: volatility.std_error / discount_factor,
+    })
+}
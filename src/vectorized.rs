@@ -0,0 +1,368 @@
+use nalgebra::DMatrix;
+
+use crate::barrier::{Barrier, BarrierCorrection, BarrierDirection, RebateTiming};
+use crate::config::SimulationConfig;
+use crate::engine::{self, PathEngine};
+use crate::error::McError;
+use crate::math;
+use crate::payoff::{PathObservables, Payoff};
+use crate::rates::RateCurve;
+use crate::result::PricingResult;
+use crate::statistics::ChunkedStatistics;
+use crate::underlying::Underlying;
+use crate::validation;
+use crate::{
+    apply_barrier, attach_diagnostics, bridge_crossing_probability, effective_barrier_level,
+    with_start_values, BGK_BARRIER_SHIFT,
+};
+
+/// State of a block of paths of the first underlying, one entry per path in each buffer
+///
+/// The antithetic counterparts of the paths follow all paths of the block.
+struct PathBlock {
+    log_prices: Vec<f64>,
+    /// Log prices at the start of the current step, for the Brownian-bridge correction
+    previous_log_prices: Vec<f64>,
+    running_log_max: Vec<f64>,
+    running_log_min: Vec<f64>,
+    barrier_hit: Vec<bool>,
+    barrier_survival: Vec<f64>,
+    rebate_weight: Vec<f64>,
+    /// Fixings of each path, one after the other
+    fixings: Vec<f64>,
+    /// Shocks of the current step, one per sample
+    shocks: Vec<f64>,
+}
+
+impl PathBlock {
+    /// Resets the block to the given number of samples starting at the log spot, keeping the
+    /// allocated buffers
+    fn reset(&mut self, num_samples: usize, num_signs: usize, num_fixings: usize, log_spot: f64) {
+        let num_paths = num_samples * num_signs;
+        for buffer in [
+            &mut self.log_prices,
+            &mut self.previous_log_prices,
+            &mut self.running_log_max,
+            &mut self.running_log_min,
+        ] {
+            buffer.clear();
+            buffer.resize(num_paths, log_spot);
+        }
+        self.barrier_hit.clear();
+        self.barrier_hit.resize(num_paths, false);
+        self.barrier_survival.clear();
+        self.barrier_survival.resize(num_paths, 1.0);
+        self.rebate_weight.clear();
+        self.rebate_weight.resize(num_paths, 0.0);
+        self.fixings.clear();
+        self.fixings.resize(num_paths * num_fixings, 0.0);
+        self.shocks.clear();
+        self.shocks.resize(num_samples, 0.0);
+    }
+}
+
+/// Prices an option on the first underlying by advancing blocks of `config.batch_paths` paths
+/// together, one step of all paths of a block at a time
+///
+/// Each step updates the log prices, running extremes and barrier states of the whole block
+/// in tight loops over contiguous buffers, which the compiler vectorizes, instead of taking
+/// every path through all steps on its own. Only the first underlying is simulated, so the
+/// barrier may only reference the first underlying. The prices are statistically equivalent
+/// to those of `price_payoff` but drawn from other random numbers.
+///
+/// # Arguments
+/// * `underlyings` - List of underlying assets; only the first one is simulated
+/// * `correlation_matrix` - Correlation matrix (n x n) where n is the number of underlyings.
+///   Must be symmetric, positive semi-definite, with 1.0 on the diagonal.
+/// * `time_horizon_days` - Time to expiration in days
+/// * `payoff` - Payoff of the option, e.g. vanilla or Asian
+/// * `risk_free_rate` - Risk-free rate curve or flat annual rate (as a decimal, e.g., 0.05 for 5%)
+/// * `barrier` - Optional barrier on the first underlying
+/// * `config` - Number of paths, block size (`batch_paths`) and further settings.
+///   Pseudo-random numbers are always used; the control variate, the path observer and the
+///   sanity checks are not supported and ignored.
+///
+/// # Returns
+/// The estimated option price together with its standard error
+///
+/// # Errors
+/// Returns an error for invalid inputs (see `price_payoff`), `McError::InvalidModel` for
+/// models without Black-Scholes marginals, or `McError::InvalidBarrier` for barriers on
+/// other underlyings than the first.
+pub fn price_vectorized(
+    underlyings: &[Underlying],
+    correlation_matrix: &DMatrix<f64>,
+    time_horizon_days: u32,
+    payoff: &Payoff,
+    risk_free_rate: impl Into<RateCurve>,
+    barrier: Option<&Barrier>,
+    config: &SimulationConfig,
+) -> Result<PricingResult, McError> {
+    let underlyings = &*with_start_values(underlyings, config)?;
+    let rate_curve = risk_free_rate.into();
+    if !config.model.has_black_scholes_marginals() {
+        return Err(McError::InvalidModel(
+            "vectorized pricing supports the Black-Scholes model only".to_string(),
+        ));
+    }
+    validation::validate_payoff(payoff)?;
+    if let Some(barrier) = barrier {
+        validation::validate_barrier(barrier, underlyings.len())?;
+        if barrier.underlying_indices != [0] {
+            return Err(McError::InvalidBarrier(
+                "vectorized pricing supports barriers on the first underlying only".to_string(),
+            ));
+        }
+    }
+    let fixing_days = payoff
+        .schedule()
+        .map(|schedule| schedule.fixing_days(time_horizon_days))
+        .transpose()?
+        .unwrap_or_default();
+
+    let time_to_expiration = config.day_count.year_fraction(time_horizon_days);
+    let discount_factor = rate_curve.discount_factor(time_to_expiration);
+    // Like `price_payoff`, daily steps for barriers and path-dependent payoffs only
+    let num_steps = if barrier.is_some() || payoff.is_path_dependent() {
+        time_horizon_days as usize
+    } else {
+        1
+    };
+    let engine = PathEngine::new(
+        underlyings,
+        correlation_matrix,
+        &rate_curve,
+        time_to_expiration,
+        num_steps,
+        config,
+    )?;
+    let mut rng = engine::create_rng(config.seed);
+
+    let log_barrier_level =
+        barrier.map(|barrier| math::ln(effective_barrier_level(barrier, &engine.initial_prices)));
+    let barrier_correction = barrier.map_or(BarrierCorrection::None, |barrier| {
+        barrier.correction(config.barrier_correction)
+    });
+    // Growth factor from the end of each step to expiry of a knock-out rebate paid at the hit
+    let rebate_growth: Vec<f64> = match barrier.and_then(|barrier| barrier.rebate) {
+        Some(rebate) if rebate.timing == RebateTiming::AtHit => (1..=num_steps)
+            .map(|step| {
+                rate_curve.discount_factor(step as f64 * time_to_expiration / num_steps as f64)
+                    / discount_factor
+            })
+            .collect(),
+        _ => vec![1.0; num_steps],
+    };
+
+    let shock_signs = engine::shock_signs(config.antithetic);
+    let num_signs = shock_signs.len();
+    let num_samples = config.num_paths.div_ceil(num_signs as u64);
+    // The standard error needs at least two samples per block
+    let block_samples = config.batch_paths.div_ceil(num_signs as u64).max(2);
+    let num_fixings = fixing_days.len();
+    let log_spot = engine.initial_log_prices[0];
+    let mut num_simulated_samples = 0;
+
+    let mut statistics = ChunkedStatistics::default();
+    let mut non_finite_paths = 0;
+    let mut block = PathBlock {
+        log_prices: Vec::new(),
+        previous_log_prices: Vec::new(),
+        running_log_max: Vec::new(),
+        running_log_min: Vec::new(),
+        barrier_hit: Vec::new(),
+        barrier_survival: Vec::new(),
+        rebate_weight: Vec::new(),
+        fixings: Vec::new(),
+        shocks: Vec::new(),
+    };
+    while num_simulated_samples < num_samples {
+        if let Some(tolerance) = config.error_tolerance {
+            if num_simulated_samples > 0 {
+                let estimate = PricingResult::from_statistics(&statistics.current(), 0, None);
+                if tolerance.is_met(estimate.price, estimate.std_error) {
+                    break;
+                }
+            }
+        }
+        let samples = block_samples.min(num_samples - num_simulated_samples) as usize;
+        block.reset(samples, num_signs, num_fixings, log_spot);
+
+        let mut next_fixing = 0;
+        for step in 1..=num_steps {
+            for shock in &mut block.shocks {
+                *shock = math::standard_normal(&mut rng);
+            }
+            let variance = engine.step_variance(step, 0);
+            let drift = engine.step_carry_rate(step, 0) * engine.dt - 0.5 * variance;
+            let volatility = variance.sqrt();
+            if barrier_correction == BarrierCorrection::BrownianBridge {
+                block.previous_log_prices.copy_from_slice(&block.log_prices);
+            }
+            for (log_prices, &sign) in block.log_prices.chunks_exact_mut(samples).zip(shock_signs) {
+                let diffusion = volatility * sign;
+                for (log_price, &shock) in log_prices.iter_mut().zip(&block.shocks) {
+                    *log_price += drift + diffusion * shock;
+                }
+            }
+            for &(_, dividend) in engine.step_dividends(step).iter().filter(|(i, _)| *i == 0) {
+                for log_price in &mut block.log_prices {
+                    *log_price = engine::ex_dividend_log_price(*log_price, dividend);
+                }
+            }
+            for ((max, min), &log_price) in block
+                .running_log_max
+                .iter_mut()
+                .zip(&mut block.running_log_min)
+                .zip(&block.log_prices)
+            {
+                *max = max.max(log_price);
+                *min = min.min(log_price);
+            }
+
+            if let (Some(barrier), Some(log_level)) = (barrier, log_barrier_level) {
+                if barrier
+                    .monitoring
+                    .is_monitored(step as u32, time_horizon_days)
+                {
+                    update_barrier_states(
+                        &mut block,
+                        barrier,
+                        log_level,
+                        barrier_correction,
+                        variance,
+                        rebate_growth[step - 1],
+                    );
+                }
+            }
+
+            if next_fixing < num_fixings && fixing_days[next_fixing] as usize == step {
+                for (path, &log_price) in block.log_prices.iter().enumerate() {
+                    block.fixings[path * num_fixings + next_fixing] = math::exp(log_price);
+                }
+                next_fixing += 1;
+            }
+        }
+
+        for sample in 0..samples {
+            let mut payoff_sum = 0.0;
+            let mut is_dropped = false;
+            for path in (0..num_signs).map(|sign| sign * samples + sample) {
+                let intrinsic_payoff = payoff.evaluate(&PathObservables {
+                    initial_price: engine.initial_prices[0],
+                    final_price: math::exp(block.log_prices[path]),
+                    running_max: math::exp(block.running_log_max[path]),
+                    running_min: math::exp(block.running_log_min[path]),
+                    fixings: &block.fixings[path * num_fixings..(path + 1) * num_fixings],
+                });
+                let value = match barrier {
+                    Some(barrier) => {
+                        let hit_probability = if block.barrier_hit[path] {
+                            1.0
+                        } else {
+                            1.0 - block.barrier_survival[path]
+                        };
+                        apply_barrier(
+                            barrier,
+                            intrinsic_payoff,
+                            hit_probability,
+                            block.rebate_weight[path],
+                        )
+                    }
+                    None => intrinsic_payoff,
+                };
+                // Non-finite values from extreme parameters are treated according to the policy
+                if !value.is_finite() {
+                    non_finite_paths += 1;
+                }
+                match config.non_finite_policy.apply(value)? {
+                    Some(value) => payoff_sum += value,
+                    None => is_dropped = true,
+                }
+            }
+            if !is_dropped {
+                // Discount to present value
+                statistics.add(payoff_sum / num_signs as f64 * discount_factor, 0.0);
+            }
+        }
+        num_simulated_samples += samples as u64;
+    }
+
+    let mut result = PricingResult::from_statistics(
+        &statistics.finish(),
+        num_simulated_samples * num_signs as u64,
+        None,
+    );
+    result.record_non_finite_paths(non_finite_paths);
+    result.record_correlation_repair(engine.correlation.adjustment());
+    result.check_error_tolerance(config.error_tolerance);
+    attach_diagnostics(
+        &mut result,
+        underlyings,
+        time_horizon_days,
+        payoff,
+        &rate_curve,
+        barrier,
+        config,
+    );
+    Ok(result)
+}
+
+/// Checks the barrier on the log prices of the block at the end of a monitored step, like
+/// `price_payoff` does path by path
+///
+/// # Arguments
+/// * `block` - Paths of the block, with the log prices at the end of the step
+/// * `barrier` - Barrier on the first underlying
+/// * `log_level` - Effective log level of the barrier
+/// * `correction` - Monitoring correction of the barrier
+/// * `variance` - Variance of the log price over the step
+/// * `rebate_growth` - Growth factor to expiry of a rebate paid on this step
+fn update_barrier_states(
+    block: &mut PathBlock,
+    barrier: &Barrier,
+    log_level: f64,
+    correction: BarrierCorrection,
+    variance: f64,
+    rebate_growth: f64,
+) {
+    // Shift the barrier towards the spot, so the discrete checks catch the crossings between
+    // the steps
+    let shift = match correction {
+        BarrierCorrection::ShiftedBarrier => BGK_BARRIER_SHIFT * variance.sqrt(),
+        _ => 0.0,
+    };
+    let is_up = barrier.direction == BarrierDirection::Up;
+    let level = if is_up {
+        log_level - shift
+    } else {
+        log_level + shift
+    };
+    let is_bridged = correction == BarrierCorrection::BrownianBridge;
+    for path in 0..block.log_prices.len() {
+        let log_price = block.log_prices[path];
+        let is_hit = if is_up {
+            log_price >= level
+        } else {
+            log_price <= level
+        };
+        if block.barrier_hit[path] {
+            continue;
+        }
+        if is_hit {
+            // The paths that survived the crossings between steps hit now
+            block.barrier_hit[path] = true;
+            block.rebate_weight[path] += block.barrier_survival[path] * rebate_growth;
+        } else if is_bridged {
+            let crossing_probability = bridge_crossing_probability(
+                block.previous_log_prices[path],
+                log_price,
+                log_level,
+                variance,
+            );
+            block.rebate_weight[path] +=
+                block.barrier_survival[path] * crossing_probability * rebate_growth;
+            block.barrier_survival[path] *= 1.0 - crossing_probability;
+        }
+    }
+}
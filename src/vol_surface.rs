@@ -0,0 +1,336 @@
+use crate::closed_form::black_scholes_price;
+use crate::implied_vol::{implied_volatility, OptionQuote};
+use crate::local_vol::grid_position;
+use crate::math;
+use crate::payoff::OptionType;
+
+/// Tolerance of the arbitrage checks on the undiscounted call prices per unit of forward
+const ARBITRAGE_TOLERANCE: f64 = 1e-10;
+
+/// Maximum number of alternating calendar and butterfly repairs
+const MAX_REPAIR_PASSES: usize = 50;
+
+/// Implied volatility surface on a log-moneyness × expiry grid, e.g. loaded from a chain of
+/// quotes
+///
+/// The log-moneyness `k = ln(K / F)` of a strike `K` is taken against the forward `F` to its
+/// expiry. Between the grid points, the variance is interpolated linearly in the
+/// log-moneyness and the total variance `t * σ²` linearly in the expiry; the volatility is
+/// extrapolated flat beyond the first and last log-moneyness and expiry.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImpliedVolSurface {
+    /// Log-moneyness axis of the grid, strictly increasing
+    log_moneyness: Vec<f64>,
+    /// Expiry axis of the grid in years, positive and strictly increasing
+    expiries: Vec<f64>,
+    /// Implied volatilities: volatilities[expiry][log-moneyness]
+    volatilities: Vec<Vec<f64>>,
+}
+
+/// Kind of a static arbitrage between the options of an implied volatility surface
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ArbitrageKind {
+    /// The total variance decreases from the previous expiry at the same log-moneyness, so a
+    /// calendar spread has negative cost
+    Calendar,
+    /// The call price is not convex in the strike, so a butterfly spread has negative cost
+    Butterfly,
+    /// The call price increases with the strike or falls faster than the strike rises, so a
+    /// call spread has negative cost or pays less than its cost
+    CallSpread,
+}
+
+/// Static arbitrage found at a grid point of an implied volatility surface
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ArbitrageViolation {
+    /// Kind of the arbitrage
+    pub kind: ArbitrageKind,
+    /// Index of the expiry of the grid point
+    pub expiry_index: usize,
+    /// Index of the log-moneyness of the grid point: the middle strike of a butterfly and
+    /// the upper strike of a call spread
+    pub strike_index: usize,
+    /// Size of the violation: the decrease of the total variance, the concavity of the call
+    /// prices or the excess slope of the call spread, per unit of forward
+    pub amount: f64,
+}
+
+/// Change of an implied volatility by the repair of a surface
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VolatilityAdjustment {
+    /// Index of the expiry of the grid point
+    pub expiry_index: usize,
+    /// Index of the log-moneyness of the grid point
+    pub strike_index: usize,
+    /// Input implied volatility
+    pub original: f64,
+    /// Implied volatility of the repaired surface
+    pub repaired: f64,
+}
+
+impl ImpliedVolSurface {
+    /// Creates a surface from its log-moneyness, expiries in years and implied volatilities
+    /// (one row of volatilities per expiry, one column per log-moneyness)
+    ///
+    /// # Panics
+    /// Panics if a grid axis is empty or not strictly increasing, if an expiry is not
+    /// positive, if the volatilities do not match the grid or if a volatility is not finite
+    /// or negative.
+    pub fn new(log_moneyness: Vec<f64>, expiries: Vec<f64>, volatilities: Vec<Vec<f64>>) -> Self {
+        for (axis, name) in [(&log_moneyness, "log-moneyness"), (&expiries, "expiries")] {
+            assert!(
+                !axis.is_empty()
+                    && axis.iter().all(|value| value.is_finite())
+                    && axis.windows(2).all(|pair| pair[0] < pair[1]),
+                "Implied volatility {} must be non-empty and strictly increasing",
+                name
+            );
+        }
+        assert!(
+            expiries[0] > 0.0,
+            "Implied volatility expiries must be positive"
+        );
+        assert!(
+            volatilities.len() == expiries.len()
+                && volatilities
+                    .iter()
+                    .all(|row| row.len() == log_moneyness.len()),
+            "Implied volatility grid must have {} rows of {} volatilities",
+            expiries.len(),
+            log_moneyness.len()
+        );
+        assert!(
+            volatilities
+                .iter()
+                .flatten()
+                .all(|volatility| volatility.is_finite() && *volatility >= 0.0),
+            "Implied volatilities must be finite and non-negative"
+        );
+        Self {
+            log_moneyness,
+            expiries,
+            volatilities,
+        }
+    }
+
+    /// Returns the log-moneyness axis of the grid
+    pub fn log_moneyness(&self) -> &[f64] {
+        &self.log_moneyness
+    }
+
+    /// Returns the expiry axis of the grid in years
+    pub fn expiries(&self) -> &[f64] {
+        &self.expiries
+    }
+
+    /// Returns the implied volatilities, one row per expiry
+    pub fn volatilities(&self) -> &[Vec<f64>] {
+        &self.volatilities
+    }
+
+    /// Returns the implied volatility at the given log-moneyness and time in years
+    pub fn implied_volatility(&self, log_moneyness: f64, time: f64) -> f64 {
+        let (strike_index, strike_weight) = grid_position(&self.log_moneyness, log_moneyness);
+        let (expiry_index, expiry_weight) = grid_position(&self.expiries, time);
+        let total_variance = |expiry_index: usize| {
+            let row = &self.volatilities[expiry_index];
+            let next = (strike_index + 1).min(row.len() - 1);
+            let variance = row[strike_index].powi(2)
+                + strike_weight * (row[next].powi(2) - row[strike_index].powi(2));
+            variance * self.expiries[expiry_index]
+        };
+        let lower = total_variance(expiry_index);
+        let upper = total_variance((expiry_index + 1).min(self.expiries.len() - 1));
+        let time = time.clamp(self.expiries[0], self.expiries[self.expiries.len() - 1]);
+        ((lower + expiry_weight * (upper - lower)) / time).sqrt()
+    }
+
+    /// Checks the grid for static arbitrage: total variances decreasing with the expiry
+    /// (calendar), and undiscounted call prices not convex (butterfly) or with slopes outside
+    /// of [-1, 0] in the strike (call spread)
+    ///
+    /// # Returns
+    /// The violations, empty for an arbitrage-free grid
+    pub fn arbitrage_violations(&self) -> Vec<ArbitrageViolation> {
+        let mut violations = Vec::new();
+        for (expiry_index, row) in self.volatilities.iter().enumerate().skip(1) {
+            let previous = &self.volatilities[expiry_index - 1];
+            for strike_index in 0..row.len() {
+                let decrease = self.total_variance(expiry_index - 1, previous[strike_index])
+                    - self.total_variance(expiry_index, row[strike_index]);
+                if decrease > ARBITRAGE_TOLERANCE {
+                    violations.push(ArbitrageViolation {
+                        kind: ArbitrageKind::Calendar,
+                        expiry_index,
+                        strike_index,
+                        amount: decrease,
+                    });
+                }
+            }
+        }
+        let strikes = self.strikes();
+        for expiry_index in 0..self.expiries.len() {
+            let prices = self.call_prices(expiry_index);
+            let slopes: Vec<f64> = (1..strikes.len())
+                .map(|i| (prices[i] - prices[i - 1]) / (strikes[i] - strikes[i - 1]))
+                .collect();
+            for (segment, &slope) in slopes.iter().enumerate() {
+                let excess = slope.max(0.0) + (-1.0 - slope).max(0.0);
+                if excess > ARBITRAGE_TOLERANCE {
+                    violations.push(ArbitrageViolation {
+                        kind: ArbitrageKind::CallSpread,
+                        expiry_index,
+                        strike_index: segment + 1,
+                        amount: excess,
+                    });
+                }
+            }
+            for (segment, pair) in slopes.windows(2).enumerate() {
+                let concavity = pair[0] - pair[1];
+                if concavity > ARBITRAGE_TOLERANCE {
+                    violations.push(ArbitrageViolation {
+                        kind: ArbitrageKind::Butterfly,
+                        expiry_index,
+                        strike_index: segment + 1,
+                        amount: concavity,
+                    });
+                }
+            }
+        }
+        violations
+    }
+
+    /// Repairs the static arbitrage of the grid with small changes of the volatilities
+    ///
+    /// Calendar arbitrage is removed by raising each total variance to that of the previous
+    /// expiry at the same log-moneyness. Butterfly and call spread arbitrage is removed by
+    /// lowering the call prices of each expiry to their greatest convex minorant, together
+    /// with the price of the forward at strike zero, and flattening it where it increases.
+    /// The repairs alternate until the grid is arbitrage-free. Volatilities without
+    /// arbitrage are left unchanged.
+    ///
+    /// # Returns
+    /// The repaired surface and the adjusted volatilities
+    pub fn repaired(&self) -> (Self, Vec<VolatilityAdjustment>) {
+        let mut surface = self.clone();
+        for _ in 0..MAX_REPAIR_PASSES {
+            let violations = surface.arbitrage_violations();
+            if violations.is_empty() {
+                break;
+            }
+            if violations
+                .iter()
+                .any(|violation| violation.kind == ArbitrageKind::Calendar)
+            {
+                surface.repair_calendar();
+            } else {
+                for expiry_index in 0..surface.expiries.len() {
+                    surface.repair_butterflies(expiry_index);
+                }
+            }
+        }
+
+        let mut adjustments = Vec::new();
+        for (expiry_index, (original, repaired)) in self
+            .volatilities
+            .iter()
+            .zip(&surface.volatilities)
+            .enumerate()
+        {
+            for (strike_index, (&original, &repaired)) in original.iter().zip(repaired).enumerate()
+            {
+                if original != repaired {
+                    adjustments.push(VolatilityAdjustment {
+                        expiry_index,
+                        strike_index,
+                        original,
+                        repaired,
+                    });
+                }
+            }
+        }
+        (surface, adjustments)
+    }
+
+    /// Returns the total variance of a volatility at the given expiry of the grid
+    fn total_variance(&self, expiry_index: usize, volatility: f64) -> f64 {
+        volatility * volatility * self.expiries[expiry_index]
+    }
+
+    /// Returns the strikes of the grid per unit of forward
+    fn strikes(&self) -> Vec<f64> {
+        self.log_moneyness.iter().map(|&k| math::exp(k)).collect()
+    }
+
+    /// Returns the undiscounted call prices per unit of forward at the given expiry
+    fn call_prices(&self, expiry_index: usize) -> Vec<f64> {
+        let expiry = self.expiries[expiry_index];
+        self.log_moneyness
+            .iter()
+            .zip(&self.volatilities[expiry_index])
+            .map(|(k, &volatility)| {
+                black_scholes_price(1.0, math::exp(*k), volatility, 0.0, expiry, OptionType::Call)
+            })
+            .collect()
+    }
+
+    /// Raises each total variance to that of the previous expiry where it is lower
+    fn repair_calendar(&mut self) {
+        for expiry_index in 1..self.expiries.len() {
+            for strike_index in 0..self.log_moneyness.len() {
+                let previous = self.total_variance(
+                    expiry_index - 1,
+                    self.volatilities[expiry_index - 1][strike_index],
+                );
+                let volatility = &mut self.volatilities[expiry_index][strike_index];
+                if *volatility * *volatility * self.expiries[expiry_index] < previous {
+                    *volatility = (previous / self.expiries[expiry_index]).sqrt();
+                }
+            }
+        }
+    }
+
+    /// Lowers the call prices of an expiry to their greatest convex non-increasing minorant
+    /// through the forward at strike zero, and implies the volatilities back
+    fn repair_butterflies(&mut self, expiry_index: usize) {
+        let strikes = self.strikes();
+        let prices = self.call_prices(expiry_index);
+        // Lower convex hull of the prices, starting from the forward at strike zero
+        let mut hull: Vec<(f64, f64)> = vec![(0.0, 1.0)];
+        for (&strike, &price) in strikes.iter().zip(&prices) {
+            while hull.len() >= 2 {
+                let (x0, y0) = hull[hull.len() - 2];
+                let (x1, y1) = hull[hull.len() - 1];
+                if (y1 - y0) * (strike - x0) >= (price - y0) * (x1 - x0) {
+                    hull.pop();
+                } else {
+                    break;
+                }
+            }
+            hull.push((strike, price));
+        }
+        let mut minimum = f64::INFINITY;
+        let expiry = self.expiries[expiry_index];
+        for (strike_index, &strike) in strikes.iter().enumerate() {
+            let upper = hull
+                .partition_point(|&(x, _)| x < strike)
+                .min(hull.len() - 1);
+            let (x0, y0) = hull[upper - 1];
+            let (x1, y1) = hull[upper];
+            let hull_price = y0 + (y1 - y0) * (strike - x0) / (x1 - x0);
+            minimum = minimum.min(hull_price);
+            if minimum < prices[strike_index] {
+                let quote = OptionQuote::new(strike, expiry, OptionType::Call, minimum);
+                // Prices at their intrinsic value on the forward have no time value
+                self.volatilities[expiry_index][strike_index] =
+                    implied_volatility(1.0, 0.0, &quote)
+                        .volatility()
+                        .unwrap_or(0.0);
+            }
+        }
+    }
+}
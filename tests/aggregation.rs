@@ -0,0 +1,99 @@
+use mcproton::test_utils::{deterministic_config, single_stock};
+use mcproton::{
+    aggregate_results, price_batch, BatchTrade, GroupingLevel, McError, PortfolioState, Product,
+};
+
+fn trades() -> Vec<BatchTrade> {
+    vec![
+        BatchTrade::new("call".to_string(), Product::call(100.0), 90)
+            .with_book("equity")
+            .with_counterparty("ACME")
+            .with_strategy("vol"),
+        BatchTrade::new("short call".to_string(), Product::call(100.0), 90)
+            .with_quantity(-1.0)
+            .with_book("equity")
+            .with_counterparty("Globex"),
+        BatchTrade::new("put".to_string(), Product::put(95.0), 60)
+            .with_quantity(2.0)
+            .with_book("hedges")
+            .with_counterparty("ACME"),
+        BatchTrade::new("short put".to_string(), Product::put(95.0), 60)
+            .with_quantity(-2.0)
+            .with_book("equity")
+            .with_counterparty("ACME"),
+    ]
+}
+
+#[test]
+fn test_totals_along_books_and_counterparties() {
+    let trades = trades();
+    let batch = price_batch(&single_stock(), &trades, &deterministic_config(2_000), None);
+    let values: Vec<f64> = batch
+        .results
+        .iter()
+        .map(|result| result.as_ref().unwrap().price)
+        .collect();
+    let groups = batch
+        .aggregate(&trades, &[GroupingLevel::Book, GroupingLevel::Counterparty])
+        .unwrap();
+    let paths: Vec<Vec<&str>> = groups
+        .iter()
+        .map(|group| group.path.iter().map(|name| name.as_deref().unwrap()).collect())
+        .collect();
+    assert_eq!(
+        paths,
+        [
+            vec![],
+            vec!["equity"],
+            vec!["equity", "ACME"],
+            vec!["equity", "Globex"],
+            vec!["hedges"],
+            vec!["hedges", "ACME"],
+        ]
+    );
+    let total = |index: usize| groups[index].total.as_ref().unwrap().price;
+    assert_eq!(total(0), batch.total().unwrap().price);
+    assert_eq!(groups[1].trades, ["call", "short call", "short put"]);
+    // The long and short calls of the equity book net out on the same paths
+    assert!((total(1) - values[3]).abs() < 1e-12);
+    assert!((total(2) - (values[0] + values[3])).abs() < 1e-12);
+    assert_eq!(total(3), values[1]);
+    assert_eq!(total(5), values[2]);
+    assert_eq!(groups[4].depth(), 1);
+
+    // Trades without a group on a level are aggregated apart from a group named ""
+    let mut trades = trades;
+    trades[2] = trades[2].clone().with_strategy("");
+    let groups = aggregate_results(&trades, &batch.results, &[GroupingLevel::Strategy]).unwrap();
+    assert_eq!(groups.len(), 4);
+    assert_eq!(groups[1].path, [None]);
+    assert_eq!(groups[1].trades, ["short call", "short put"]);
+    assert_eq!(groups[2].path, [Some(String::new())]);
+    assert_eq!(groups[2].trades, ["put"]);
+    assert_eq!(groups[3].path, [Some("vol".to_string())]);
+}
+
+#[test]
+fn test_results_of_other_trades_are_rejected() {
+    let trades = trades();
+    let batch = price_batch(&single_stock(), &trades[..3], &deterministic_config(500), None);
+    assert!(matches!(
+        batch.aggregate(&trades, &[GroupingLevel::Book]),
+        Err(McError::InvalidProduct(_))
+    ));
+}
+
+#[test]
+fn test_rejected_trades_fail_only_their_groups() {
+    let mut trades = trades();
+    trades[1].product = Product::call(-1.0);
+    let mut state = PortfolioState::new(&deterministic_config(1_000));
+    let update = state.update(&single_stock(), &trades);
+    let groups = update
+        .aggregate(&trades, &[GroupingLevel::Counterparty])
+        .unwrap();
+    assert!(matches!(groups[0].total, Err(McError::InvalidProduct(_))));
+    assert_eq!(groups[1].path, [Some("ACME".to_string())]);
+    assert!(groups[1].total.is_ok());
+    assert!(matches!(groups[2].total, Err(McError::InvalidProduct(_))));
+}
@@ -0,0 +1,56 @@
+use mcproton::closed_form::black_scholes_price;
+use mcproton::{price_american, McError, OptionType, SimulationConfig, Underlying};
+use nalgebra::DMatrix;
+
+fn single_underlying(spot: f64, volatility: f64) -> (Vec<Underlying>, DMatrix<f64>) {
+    (
+        vec![Underlying::new("TEST".to_string(), spot, volatility)],
+        DMatrix::from_row_slice(1, 1, &[1.0]),
+    )
+}
+
+#[test]
+fn test_american_put_exceeds_european_put() {
+    // Longstaff-Schwartz reference case: S = 36, K = 40, σ = 20%, r = 6%, T = 1 year
+    // American put ≈ 4.48, European put ≈ 3.84
+    let (underlyings, correlation) = single_underlying(36.0, 0.20);
+    let exercise_dates: Vec<u32> = (1..=365).step_by(7).collect();
+    let config = SimulationConfig::new(4_000).with_seed(7).with_antithetic(true);
+    let result = price_american(&underlyings, &correlation, 365, 40.0, OptionType::Put, 0.06, &exercise_dates, 2, &config).unwrap();
+    let european = black_scholes_price(36.0, 40.0, 0.20, 0.06, 1.0, OptionType::Put);
+    assert!(result.price > european + 0.3, "American put {} should exceed European put {}", result.price, european);
+    assert!((result.price - 4.48).abs() < 0.2, "American put {} should be close to 4.48", result.price);
+}
+
+#[test]
+fn test_expiry_only_exercise_matches_european() {
+    let (underlyings, correlation) = single_underlying(100.0, 0.25);
+    let config = SimulationConfig::new(10_000).with_seed(11).with_control_variate(true);
+    let result = price_american(&underlyings, &correlation, 60, 100.0, OptionType::Put, 0.05, &[60], 3, &config).unwrap();
+    let european = black_scholes_price(100.0, 100.0, 0.25, 0.05, 60.0 / 365.0, OptionType::Put);
+    assert!((result.price - european).abs() < 1e-9);
+}
+
+#[test]
+fn test_american_call_without_dividends_is_never_exercised_early() {
+    let (underlyings, correlation) = single_underlying(100.0, 0.20);
+    let exercise_dates: Vec<u32> = (1..=90).collect();
+    let config = SimulationConfig::new(5_000).with_seed(3);
+    let result = price_american(&underlyings, &correlation, 90, 95.0, OptionType::Call, 0.05, &exercise_dates, 2, &config).unwrap();
+    let european = black_scholes_price(100.0, 95.0, 0.20, 0.05, 90.0 / 365.0, OptionType::Call);
+    assert!(
+        (result.price - european).abs() < 4.0 * result.std_error + 0.05,
+        "American call {} should be close to European call {}",
+        result.price,
+        european
+    );
+}
+
+#[test]
+fn test_exercise_date_after_expiry_is_rejected() {
+    let (underlyings, correlation) = single_underlying(100.0, 0.20);
+    let result = price_american(&underlyings, &correlation, 30, 100.0, OptionType::Put, 0.05, &[45], 2, &SimulationConfig::new(100));
+    assert!(matches!(result, Err(McError::InvalidSchedule(_))));
+    let result = price_american(&underlyings, &correlation, 30, 100.0, OptionType::Put, 0.05, &[15], 0, &SimulationConfig::new(100));
+    assert_eq!(result.unwrap_err(), McError::InvalidBasisOrder);
+}
@@ -0,0 +1,89 @@
+use mcproton::closed_form::{black_scholes_price, norm_cdf};
+use mcproton::{
+    price_payoff, Averaging, FixingSchedule, OptionType, Payoff, SimulationConfig, Underlying,
+};
+use nalgebra::DMatrix;
+
+fn single_underlying() -> (Vec<Underlying>, DMatrix<f64>) {
+    (
+        vec![Underlying::new("TEST".to_string(), 100.0, 0.25)],
+        DMatrix::from_row_slice(1, 1, &[1.0]),
+    )
+}
+
+/// Closed-form price of a discretely monitored geometric average-price call
+fn geometric_asian_call(spot: f64, strike: f64, vol: f64, rate: f64, fixing_days: &[u32], expiry_days: u32) -> f64 {
+    let times: Vec<f64> = fixing_days.iter().map(|&d| d as f64 / 365.0).collect();
+    let n = times.len() as f64;
+    let mean = spot.ln() + (rate - 0.5 * vol * vol) * times.iter().sum::<f64>() / n;
+    let variance = vol * vol
+        * times.iter().map(|&ti| times.iter().map(|&tj| ti.min(tj)).sum::<f64>()).sum::<f64>()
+        / (n * n);
+    let std_dev = variance.sqrt();
+    let d2 = (mean - strike.ln()) / std_dev;
+    let d1 = d2 + std_dev;
+    let expiry = expiry_days as f64 / 365.0;
+    (-rate * expiry).exp() * ((mean + 0.5 * variance).exp() * norm_cdf(d1) - strike * norm_cdf(d2))
+}
+
+#[test]
+fn test_fixing_schedules() {
+    assert_eq!(FixingSchedule::Monthly.fixing_days(90).unwrap(), vec![30, 60, 90]);
+    assert_eq!(FixingSchedule::Monthly.fixing_days(100).unwrap(), vec![10, 40, 70, 100]);
+    assert_eq!(FixingSchedule::LastN(5).fixing_days(30).unwrap(), vec![26, 27, 28, 29, 30]);
+    assert_eq!(FixingSchedule::LastN(50).fixing_days(3).unwrap(), vec![1, 2, 3]);
+    assert_eq!(FixingSchedule::Daily.fixing_days(4).unwrap(), vec![1, 2, 3, 4]);
+    assert_eq!(FixingSchedule::Dates(vec![20, 10, 20]).fixing_days(30).unwrap(), vec![10, 20]);
+}
+
+#[test]
+fn test_geometric_average_price_matches_closed_form() {
+    let (underlyings, correlation) = single_underlying();
+    let schedule = FixingSchedule::Monthly;
+    let payoff = Payoff::AveragePrice {
+        strike_price: 100.0,
+        option_type: OptionType::Call,
+        averaging: Averaging::Geometric,
+        schedule: schedule.clone(),
+    };
+    let config = SimulationConfig::new(20_000).with_seed(5).with_antithetic(true);
+    let result = price_payoff(&underlyings, &correlation, 180, &payoff, 0.05, None, &config).unwrap();
+    let analytic = geometric_asian_call(100.0, 100.0, 0.25, 0.05, &schedule.fixing_days(180).unwrap(), 180);
+    assert!(
+        (result.price - analytic).abs() < 4.0 * result.std_error,
+        "Geometric Asian {} should be within 4 standard errors of {}",
+        result.price,
+        analytic
+    );
+}
+
+#[test]
+fn test_arithmetic_average_price_between_geometric_and_vanilla() {
+    let (underlyings, correlation) = single_underlying();
+    let config = SimulationConfig::new(5_000).with_seed(9);
+    let asian = |averaging| Payoff::AveragePrice {
+        strike_price: 100.0,
+        option_type: OptionType::Call,
+        averaging,
+        schedule: FixingSchedule::Daily,
+    };
+    let arithmetic = price_payoff(&underlyings, &correlation, 60, &asian(Averaging::Arithmetic), 0.05, None, &config).unwrap();
+    let geometric = price_payoff(&underlyings, &correlation, 60, &asian(Averaging::Geometric), 0.05, None, &config).unwrap();
+    let vanilla = black_scholes_price(100.0, 100.0, 0.25, 0.05, 60.0 / 365.0, OptionType::Call);
+    // With common random numbers the arithmetic mean dominates the geometric mean path by path
+    assert!(arithmetic.price >= geometric.price);
+    assert!(arithmetic.price < vanilla, "Averaging must reduce the call value");
+}
+
+#[test]
+fn test_average_strike_with_single_final_fixing_is_worthless() {
+    // Averaging only the expiry fixing makes the strike equal to the terminal price
+    let (underlyings, correlation) = single_underlying();
+    let payoff = Payoff::AverageStrike {
+        option_type: OptionType::Put,
+        averaging: Averaging::Arithmetic,
+        schedule: FixingSchedule::LastN(1),
+    };
+    let result = price_payoff(&underlyings, &correlation, 30, &payoff, 0.05, None, &SimulationConfig::new(500)).unwrap();
+    assert!(result.price.abs() < 1e-12);
+}
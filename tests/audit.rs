@@ -0,0 +1,80 @@
+use mcproton::test_utils::single_stock;
+use mcproton::{
+    price_payoff, AuditEntry, Barrier, BarrierCorrection, BarrierDirection, DayCountConvention,
+    KnockType, Product, PricingResult, SimulationConfig,
+};
+
+const DAYS: u32 = 30;
+
+fn price(config: &SimulationConfig) -> PricingResult {
+    let market = single_stock();
+    let product = Product::call(100.0).with_barrier(Barrier::single(
+        90.0,
+        BarrierDirection::Down,
+        KnockType::Out,
+        false,
+    ));
+    price_payoff(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        &product.payoff,
+        market.risk_free_rate.clone(),
+        product.barrier.as_ref(),
+        config,
+    )
+    .unwrap()
+}
+
+fn entry(result: &PricingResult, setting: &str) -> AuditEntry {
+    result.audit_entry(setting).unwrap().clone()
+}
+
+#[test]
+fn test_defaults_are_recorded_explicitly() {
+    let result = price(&SimulationConfig::new(1_000));
+    assert_eq!(
+        entry(&result, "day_count"),
+        AuditEntry::new("day_count", "Calendar365", true)
+    );
+    assert_eq!(
+        entry(&result, "time_steps"),
+        AuditEntry::new("time_steps", DAYS.to_string(), true)
+    );
+    assert_eq!(
+        entry(&result, "barrier_monitoring"),
+        AuditEntry::new("barrier_monitoring", "Continuous", true)
+    );
+    assert_eq!(
+        entry(&result, "barrier_shift"),
+        AuditEntry::new("barrier_shift", "0 step standard deviations", true)
+    );
+
+    // The drawn seed is recorded, so the price can be reproduced
+    let seed = entry(&result, "seed");
+    assert!(seed.is_default);
+    let reproduced = price(&SimulationConfig::new(1_000).with_seed(seed.value.parse().unwrap()));
+    assert_eq!(reproduced.price, result.price);
+    assert!(!entry(&reproduced, "seed").is_default);
+}
+
+#[test]
+fn test_configured_settings_are_not_defaults() {
+    let config = SimulationConfig::new(1_000)
+        .with_seed(3)
+        .with_day_count(DayCountConvention::Trading252)
+        .with_barrier_correction(BarrierCorrection::ShiftedBarrier);
+    let result = price(&config);
+    assert_eq!(
+        entry(&result, "day_count"),
+        AuditEntry::new("day_count", "Trading252", false)
+    );
+    assert_eq!(
+        entry(&result, "barrier_correction"),
+        AuditEntry::new("barrier_correction", "ShiftedBarrier", false)
+    );
+    assert_eq!(
+        entry(&result, "barrier_shift"),
+        AuditEntry::new("barrier_shift", "0.5826 step standard deviations", false)
+    );
+}
@@ -0,0 +1,112 @@
+use mcproton::{
+    attribute_autocallable_greeks, price_autocallable, Autocallable, AutocallableComponent,
+    MarketSnapshot, SimulationConfig, Underlying,
+};
+use nalgebra::DMatrix;
+
+fn single_underlying(volatility: f64) -> (Vec<Underlying>, DMatrix<f64>) {
+    (
+        vec![Underlying::new("TEST".to_string(), 100.0, volatility)],
+        DMatrix::from_row_slice(1, 1, &[1.0]),
+    )
+}
+
+fn quarterly_phoenix(memory: bool) -> Autocallable {
+    Autocallable::new(1000.0, vec![91, 182, 273, 365], 1.0, 0.8, 0.02, memory, 0.6)
+}
+
+#[test]
+fn test_unreachable_autocall_without_risk_is_a_bond() {
+    // Almost no volatility: no knock-in, every coupon paid, redemption at maturity
+    let (underlyings, correlation) = single_underlying(1e-6);
+    let mut product = quarterly_phoenix(false);
+    product.autocall_level = 10.0;
+    let config = SimulationConfig::new(200).with_seed(1);
+    let result = price_autocallable(&underlyings, &correlation, &product, 0.0, &config).unwrap();
+    let expected = 1000.0 + 4.0 * 20.0;
+    assert!(
+        (result.pricing.price - expected).abs() < 1e-6,
+        "Riskless autocallable {} should be worth {}",
+        result.pricing.price,
+        expected
+    );
+    assert_eq!(result.expected_redemption_day, 365.0);
+    assert_eq!(result.redemption_probabilities, vec![0.0, 0.0, 0.0, 1.0]);
+}
+
+#[test]
+fn test_low_trigger_calls_at_first_observation() {
+    let (underlyings, correlation) = single_underlying(0.2);
+    let mut product = quarterly_phoenix(false);
+    product.autocall_level = 0.01;
+    product.coupon_barrier = 0.01;
+    let config = SimulationConfig::new(500).with_seed(2);
+    let result = price_autocallable(&underlyings, &correlation, &product, 0.03, &config).unwrap();
+    assert_eq!(result.expected_redemption_day, 91.0);
+    assert_eq!(result.redemption_probabilities[0], 1.0);
+    let expected = 1020.0 * (-0.03 * 91.0_f64 / 365.0).exp();
+    assert!((result.pricing.price - expected).abs() < 1e-9);
+}
+
+#[test]
+fn test_memory_coupons_add_value() {
+    let (underlyings, correlation) = single_underlying(0.3);
+    let config = SimulationConfig::new(2_000).with_seed(3);
+    let price = |memory| {
+        price_autocallable(&underlyings, &correlation, &quarterly_phoenix(memory), 0.03, &config).unwrap()
+            .pricing
+            .price
+    };
+    let (plain, memory) = (price(false), price(true));
+    assert!(memory > plain, "Memory coupons {} should be worth more than plain {}", memory, plain);
+}
+
+#[test]
+fn test_redemption_probabilities_sum_to_one() {
+    let underlyings = vec![
+        Underlying::new("A".to_string(), 100.0, 0.25),
+        Underlying::new("B".to_string(), 50.0, 0.35),
+    ];
+    let correlation = DMatrix::from_row_slice(2, 2, &[1.0, 0.5, 0.5, 1.0]);
+    let config = SimulationConfig::new(1_000).with_seed(4).with_antithetic(true);
+    let result =
+        price_autocallable(&underlyings, &correlation, &quarterly_phoenix(true), 0.03, &config).unwrap();
+    let total: f64 = result.redemption_probabilities.iter().sum();
+    assert!((total - 1.0).abs() < 1e-12, "Redemption probabilities sum to {}", total);
+    assert!(result.expected_redemption_day > 91.0 && result.expected_redemption_day < 365.0);
+    assert!(result.pricing.price < 1000.0 + 4.0 * 20.0);
+}
+
+#[test]
+fn test_components_add_up_to_the_note() {
+    let (underlyings, correlation) = single_underlying(0.25);
+    let config = SimulationConfig::new(4_000).with_seed(5).with_antithetic(true);
+    let product = quarterly_phoenix(true);
+    let market = MarketSnapshot::new(underlyings.clone(), correlation.clone(), 0.03);
+    let attribution = attribute_autocallable_greeks(&market, &product, &config).unwrap();
+
+    // Same price as the plain pricer on the same seed
+    let result = price_autocallable(&underlyings, &correlation, &product, 0.03, &config).unwrap();
+    assert!((attribution.pricing.price - result.pricing.price).abs() < 1e-9);
+    let total: f64 = attribution.components.iter().map(|component| component.price).sum();
+    assert!((total - attribution.pricing.price).abs() < 1e-9);
+
+    let bond = attribution.component(AutocallableComponent::Bond);
+    assert!((bond.price - 1000.0 * (-0.03_f64).exp()).abs() < 1e-9);
+    assert_eq!((bond.delta, bond.vega), (0.0, 0.0));
+
+    // The investor is short the knock-in put: it loses value on falling spots and rising
+    // volatility
+    let put = attribution.component(AutocallableComponent::KnockInPut);
+    assert!(put.price < 0.0);
+    assert!(put.delta > 0.0 && put.vega < 0.0);
+    let coupons = attribution.component(AutocallableComponent::Coupons);
+    assert!(coupons.price > 0.0);
+    // Rising spots call the note earlier
+    let early = attribution.component(AutocallableComponent::EarlyRedemption);
+    assert!(early.price > 0.0 && early.delta > 0.0);
+
+    let delta: f64 = attribution.components.iter().map(|component| component.delta).sum();
+    assert!((delta - attribution.delta).abs() < 1e-9);
+    assert!(attribution.delta > 0.0 && attribution.vega < 0.0);
+}
@@ -0,0 +1,66 @@
+use mcproton::closed_form::black_scholes_barrier_price;
+use mcproton::{
+    price_option_with_config, Barrier, BarrierCorrection, BarrierDirection, KnockType, OptionType,
+    PricingResult, SimulationConfig, Underlying,
+};
+use nalgebra::DMatrix;
+
+const SPOT: f64 = 100.0;
+const STRIKE: f64 = 100.0;
+const BARRIER: f64 = 90.0;
+const VOLATILITY: f64 = 0.60;
+const RATE: f64 = 0.05;
+const DAYS: u32 = 30;
+
+/// Continuously monitored down-and-out call with the barrier below the strike
+fn analytic_down_and_out_call() -> f64 {
+    let barrier = Barrier::single(BARRIER, BarrierDirection::Down, KnockType::Out, false);
+    let time = DAYS as f64 / 365.0;
+    black_scholes_barrier_price(SPOT, STRIKE, &barrier, VOLATILITY, RATE, time, OptionType::Call).unwrap()
+}
+
+fn price(correction: BarrierCorrection) -> PricingResult {
+    let underlyings = vec![Underlying::new("TEST".to_string(), SPOT, VOLATILITY)];
+    let correlation = DMatrix::from_element(1, 1, 1.0);
+    let barrier = Barrier::single(BARRIER, BarrierDirection::Down, KnockType::Out, false);
+    let config = SimulationConfig::new(40_000)
+        .with_seed(11)
+        .with_barrier_correction(correction);
+    price_option_with_config(
+        &underlyings,
+        &correlation,
+        DAYS,
+        STRIKE,
+        OptionType::Call,
+        RATE,
+        Some(&barrier),
+        &config,
+    ).unwrap()
+}
+
+#[test]
+fn test_discrete_monitoring_overprices_knock_out() {
+    let analytic = analytic_down_and_out_call();
+    let result = price(BarrierCorrection::None);
+    assert!(
+        result.price > analytic + 4.0 * result.std_error,
+        "Daily monitored knock-out {} should exceed the continuous price {}",
+        result.price,
+        analytic
+    );
+}
+
+#[test]
+fn test_corrections_match_continuous_monitoring() {
+    let analytic = analytic_down_and_out_call();
+    for correction in [BarrierCorrection::BrownianBridge, BarrierCorrection::ShiftedBarrier] {
+        let result = price(correction);
+        assert!(
+            (result.price - analytic).abs() < 4.0 * result.std_error,
+            "{:?} price {} should match the continuously monitored price {}",
+            correction,
+            result.price,
+            analytic
+        );
+    }
+}
@@ -0,0 +1,53 @@
+use mcproton::closed_form::black_scholes_price;
+use mcproton::{
+    price_option_with_config, Barrier, BarrierDirection, BarrierMonitoring, KnockType, OptionType,
+    SimulationConfig, Underlying,
+};
+use nalgebra::DMatrix;
+
+fn down_and_out_call(monitoring: BarrierMonitoring) -> mcproton::PricingResult {
+    let underlyings = vec![Underlying::new("TEST".to_string(), 100.0, 0.30)];
+    let correlation = DMatrix::from_element(1, 1, 1.0);
+    let barrier = Barrier::single(0.9, BarrierDirection::Down, KnockType::Out, true).with_monitoring(monitoring);
+    let config = SimulationConfig::new(5_000).with_seed(21);
+    price_option_with_config(&underlyings, &correlation, 90, 100.0, OptionType::Call, 0.05, Some(&barrier), &config).unwrap()
+}
+
+#[test]
+fn test_barrier_at_expiry_only_matters_at_expiry() {
+    // Below the strike, a knock-out at expiry removes nothing from the call
+    let at_expiry = down_and_out_call(BarrierMonitoring::AtExpiry);
+    assert_eq!(at_expiry.price, down_and_out_call(BarrierMonitoring::Dates(vec![90])).price);
+    let analytic = black_scholes_price(100.0, 100.0, 0.30, 0.05, 90.0 / 365.0, OptionType::Call);
+    assert!(
+        (at_expiry.price - analytic).abs() < 4.0 * at_expiry.std_error,
+        "Knock-out observed at expiry {} should match the vanilla call {}",
+        at_expiry.price,
+        analytic
+    );
+}
+
+#[test]
+fn test_fewer_observations_knock_out_fewer_paths() {
+    let weekly: Vec<u32> = (1..=12).map(|week| week * 7).collect();
+    let continuous = down_and_out_call(BarrierMonitoring::Continuous).price;
+    let weekly = down_and_out_call(BarrierMonitoring::Dates(weekly)).price;
+    let at_expiry = down_and_out_call(BarrierMonitoring::AtExpiry).price;
+    assert!(
+        continuous < weekly && weekly < at_expiry,
+        "Knock-out prices should rise as observations thin out: daily {}, weekly {}, at expiry {}",
+        continuous,
+        weekly,
+        at_expiry
+    );
+}
+
+#[test]
+fn test_monitoring_days() {
+    let dates = BarrierMonitoring::Dates(vec![30, 60]);
+    assert!(dates.is_monitored(30, 90));
+    assert!(!dates.is_monitored(31, 90));
+    assert!(BarrierMonitoring::AtExpiry.is_monitored(90, 90));
+    assert!(!BarrierMonitoring::AtExpiry.is_monitored(89, 90));
+    assert!(BarrierMonitoring::Continuous.is_monitored(1, 90));
+}
@@ -0,0 +1,107 @@
+use mcproton::closed_form::black_scholes_barrier_price;
+use mcproton::{
+    price_option_with_config, Barrier, BarrierCorrection, BarrierDirection, BarrierMonitoring,
+    BarrierObservation, KnockType, OptionType, PricingResult, SimulationConfig, Underlying,
+};
+use nalgebra::DMatrix;
+
+const SPOT: f64 = 100.0;
+const STRIKE: f64 = 100.0;
+const BARRIER: f64 = 90.0;
+const VOLATILITY: f64 = 0.60;
+const RATE: f64 = 0.05;
+const DAYS: u32 = 30;
+
+fn down_and_out_call() -> Barrier {
+    Barrier::single(BARRIER, BarrierDirection::Down, KnockType::Out, false)
+}
+
+fn price(barrier: &Barrier, correction: BarrierCorrection) -> PricingResult {
+    let underlyings = vec![Underlying::new("TEST".to_string(), SPOT, VOLATILITY)];
+    let correlation = DMatrix::from_element(1, 1, 1.0);
+    let config = SimulationConfig::new(40_000)
+        .with_seed(11)
+        .with_barrier_correction(correction);
+    price_option_with_config(
+        &underlyings,
+        &correlation,
+        DAYS,
+        STRIKE,
+        OptionType::Call,
+        RATE,
+        Some(barrier),
+        &config,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_intraday_observation_matches_continuous_monitoring() {
+    let barrier = down_and_out_call().with_observation(BarrierObservation::Intraday);
+    let time = DAYS as f64 / 365.0;
+    let analytic = black_scholes_barrier_price(
+        SPOT,
+        STRIKE,
+        &barrier,
+        VOLATILITY,
+        RATE,
+        time,
+        OptionType::Call,
+    )
+    .unwrap();
+    let result = price(&barrier, BarrierCorrection::None);
+    assert!(
+        (result.price - analytic).abs() < 4.0 * result.std_error,
+        "Intraday observed knock-out {} should match the continuous price {}",
+        result.price,
+        analytic
+    );
+    // The observation of the barrier overrides the configured correction
+    assert_eq!(
+        price(&barrier, BarrierCorrection::ShiftedBarrier).price,
+        result.price
+    );
+}
+
+#[test]
+fn test_close_observation_ignores_the_configured_correction() {
+    let close = down_and_out_call().with_observation(BarrierObservation::Close);
+    let uncorrected = price(&down_and_out_call(), BarrierCorrection::None);
+    assert_eq!(
+        price(&close, BarrierCorrection::BrownianBridge).price,
+        uncorrected.price
+    );
+    let corrected = price(&down_and_out_call(), BarrierCorrection::BrownianBridge);
+    assert!(
+        uncorrected.price > corrected.price + 4.0 * corrected.std_error,
+        "Close-only knock-out {} should exceed the corrected price {}",
+        uncorrected.price,
+        corrected.price
+    );
+}
+
+#[test]
+fn test_intraday_observation_on_monitoring_dates() {
+    let weekly = down_and_out_call().with_monitoring(BarrierMonitoring::Dates(vec![7, 14, 21, 28]));
+    let close = price(&weekly, BarrierCorrection::BrownianBridge).price;
+    let intraday = price(
+        &weekly
+            .clone()
+            .with_observation(BarrierObservation::Intraday),
+        BarrierCorrection::None,
+    )
+    .price;
+    let continuous = price(
+        &down_and_out_call().with_observation(BarrierObservation::Intraday),
+        BarrierCorrection::None,
+    )
+    .price;
+    assert!(
+        continuous < intraday && intraday < close,
+        "Intraday observation on weekly dates {} should lie between the weekly closes {} and \
+         continuous observation {}",
+        intraday,
+        close,
+        continuous
+    );
+}
@@ -0,0 +1,116 @@
+use mcproton::closed_form::black_scholes_price;
+use mcproton::test_utils::{
+    assert_within_std_errors, deterministic_config, single_stock, two_asset_basket,
+};
+use mcproton::{
+    price_basket_option, price_payoff, Averaging, BasketOption, FixingSchedule, MarketSnapshot,
+    McError, OptionType, Payoff, PricingResult,
+};
+
+const DAYS: u32 = 90;
+
+fn price(market: &MarketSnapshot, option: &BasketOption, num_paths: u64) -> PricingResult {
+    price_basket_option(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        option,
+        market.risk_free_rate.clone(),
+        &deterministic_config(num_paths),
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_terminal_basket_of_one_stock_matches_black_scholes() {
+    let time = DAYS as f64 / 365.0;
+    // All weight on the first stock of the basket
+    let option = BasketOption::new(105.0, OptionType::Call, vec![1.0, 0.0]);
+    let result = price(&two_asset_basket(), &option, 20_000);
+    let expected = black_scholes_price(100.0, 105.0, 0.2, 0.05, time, OptionType::Call);
+    assert_within_std_errors(&result, expected, 4.0);
+
+    // Performances against the initial price scale the payoff
+    let option = BasketOption::new(1.05, OptionType::Call, vec![1.0]).with_normalized_levels();
+    let result = price(&single_stock(), &option, 20_000);
+    assert_within_std_errors(&result, expected / 100.0, 4.0);
+}
+
+#[test]
+fn test_averaging_window_matches_the_asian_payoff() {
+    let market = single_stock();
+    let schedule = FixingSchedule::LastN(5);
+    let option =
+        BasketOption::new(100.0, OptionType::Put, vec![1.0]).with_schedule(schedule.clone());
+    let basket = price(&market, &option, 10_000);
+    let asian = price_payoff(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        &Payoff::AveragePrice {
+            strike_price: 100.0,
+            option_type: OptionType::Put,
+            averaging: Averaging::Arithmetic,
+            schedule,
+        },
+        market.risk_free_rate.clone(),
+        None,
+        &deterministic_config(10_000),
+    )
+    .unwrap();
+    let std_error = basket.std_error.hypot(asian.std_error);
+    assert!(
+        (basket.price - asian.price).abs() < 4.0 * std_error,
+        "basket {} and Asian {} differ",
+        basket.price,
+        asian.price
+    );
+}
+
+#[test]
+fn test_geometric_average_call_is_cheaper_on_the_same_paths() {
+    let market = two_asset_basket();
+    let option = BasketOption::new(100.0, OptionType::Call, vec![0.5, 0.5])
+        .with_schedule(FixingSchedule::LastN(10));
+    let arithmetic = price(&market, &option, 5_000);
+    let geometric = price(
+        &market,
+        &option.clone().with_averaging(Averaging::Geometric),
+        5_000,
+    );
+    assert!(geometric.price < arithmetic.price);
+    assert!(geometric.price > 0.0);
+}
+
+#[test]
+fn test_invalid_weights_and_schedules_are_rejected() {
+    let market = two_asset_basket();
+    let price = |option: BasketOption| {
+        price_basket_option(
+            &market.underlyings,
+            &market.correlation_matrix,
+            DAYS,
+            &option,
+            0.05,
+            &deterministic_config(100),
+        )
+    };
+    for weights in [
+        vec![1.0],
+        vec![1.0, -0.5],
+        vec![0.0, 0.0],
+        vec![f64::NAN, 1.0],
+    ] {
+        assert!(matches!(
+            price(BasketOption::new(100.0, OptionType::Call, weights)),
+            Err(McError::InvalidProduct(_))
+        ));
+    }
+    assert!(matches!(
+        price(
+            BasketOption::new(100.0, OptionType::Call, vec![0.5, 0.5])
+                .with_schedule(FixingSchedule::Dates(vec![DAYS + 1]))
+        ),
+        Err(McError::InvalidSchedule(_))
+    ));
+}
@@ -0,0 +1,163 @@
+use std::time::{Duration, Instant};
+
+use mcproton::test_utils::{deterministic_config, single_stock};
+use mcproton::{
+    price_batch, price_payoff, AccuracyTarget, Barrier, BarrierDirection, BatchTrade, KnockType,
+    McError, PricingWarning, Priority, Product,
+};
+
+fn trades() -> Vec<BatchTrade> {
+    let barrier = Barrier::single(0.8, BarrierDirection::Down, KnockType::Out, true);
+    vec![
+        BatchTrade::new("call".to_string(), Product::call(100.0), 90),
+        BatchTrade::new(
+            "down-and-out put".to_string(),
+            Product::put(100.0).with_barrier(barrier),
+            180,
+        )
+        .with_priority(Priority::Low),
+        BatchTrade::new("put".to_string(), Product::put(95.0), 30),
+    ]
+}
+
+#[test]
+fn test_without_a_deadline_all_trades_get_full_accuracy() {
+    let market = single_stock();
+    let config = deterministic_config(5_000).with_batch_paths(1_000);
+    let batch = price_batch(&market, &trades(), &config, None);
+    assert_eq!(batch.results.len(), 3);
+    assert_eq!(batch.num_reduced(), 0);
+    for (trade, result) in trades().iter().zip(&batch.results) {
+        let expected = price_payoff(
+            &market.underlyings,
+            &market.correlation_matrix,
+            trade.maturity_days,
+            &trade.product.payoff,
+            market.risk_free_rate.clone(),
+            trade.product.barrier.as_ref(),
+            &config,
+        )
+        .unwrap();
+        let result = result.as_ref().unwrap();
+        assert_eq!(result.price, expected.price);
+        assert_eq!(result.num_paths, 5_000);
+    }
+}
+
+#[test]
+fn test_low_priority_trades_are_reduced_past_the_deadline() {
+    let market = single_stock();
+    let config = deterministic_config(20_000).with_batch_paths(1_000);
+    let batch = price_batch(&market, &trades(), &config, Some(Instant::now()));
+
+    // High priority trades keep their paths, the low priority one is priced by its pilot run
+    assert_eq!(batch.num_reduced(), 1);
+    assert_eq!(batch.results[0].as_ref().unwrap().num_paths, 20_000);
+    assert_eq!(batch.results[2].as_ref().unwrap().num_paths, 20_000);
+    let reduced = batch.results[1].as_ref().unwrap();
+    assert_eq!(reduced.num_paths, 1_000);
+    assert!(reduced.warnings.contains(&PricingWarning::ReducedPaths {
+        requested_paths: 20_000
+    }));
+}
+
+#[test]
+fn test_low_priority_trades_get_full_accuracy_before_a_distant_deadline() {
+    let market = single_stock();
+    let config = deterministic_config(2_000).with_batch_paths(500);
+    let deadline = Instant::now() + Duration::from_secs(600);
+    let batch = price_batch(&market, &trades(), &config, Some(deadline));
+    assert_eq!(batch.num_reduced(), 0);
+    assert_eq!(batch.results[1].as_ref().unwrap().num_paths, 2_000);
+    assert!(batch.elapsed_seconds < 600.0);
+}
+
+#[test]
+fn test_invalid_trades_are_rejected_without_aborting_the_run() {
+    let mut trades = trades();
+    trades[1].product = Product::put(-1.0);
+    let batch = price_batch(
+        &single_stock(),
+        &trades,
+        &deterministic_config(1_000),
+        Some(Instant::now()),
+    );
+    assert!(matches!(batch.results[1], Err(McError::InvalidProduct(_))));
+    assert!(batch.results[0].is_ok() && batch.results[2].is_ok());
+}
+
+#[test]
+fn test_trades_with_accuracy_targets_get_the_paths_they_need() {
+    // 3 bps of a notional of 100: a standard error of 0.03
+    let accuracy = AccuracyTarget::new(3.0, 100.0);
+    let barrier = Barrier::single(110.0, BarrierDirection::Up, KnockType::Out, false);
+    let trades = vec![
+        BatchTrade::new("call".to_string(), Product::call(100.0), 90).with_accuracy(accuracy),
+        BatchTrade::new(
+            "up-and-out call".to_string(),
+            Product::call(100.0).with_barrier(barrier),
+            90,
+        )
+        .with_accuracy(accuracy)
+        .with_priority(Priority::Low),
+        BatchTrade::new("put".to_string(), Product::put(95.0), 30),
+    ];
+    let config = deterministic_config(5_000).with_batch_paths(2_000);
+    let batch = price_batch(&single_stock(), &trades, &config, None);
+    let results: Vec<_> = batch.results.iter().map(|r| r.as_ref().unwrap()).collect();
+    for result in &results[..2] {
+        assert!(result.std_error < 0.033, "Standard error {}", result.std_error);
+    }
+    // The capped payoff varies less, so it needs fewer paths than the vanilla; both ignore
+    // the configured paths, which the trade without a target keeps
+    assert!(results[0].num_paths > 5_000);
+    assert!(results[1].num_paths < results[0].num_paths);
+    assert_eq!(results[2].num_paths, 5_000);
+
+    let mut trades = trades;
+    trades[0].accuracy = Some(AccuracyTarget::new(0.0, 100.0));
+    let batch = price_batch(&single_stock(), &trades, &config, None);
+    assert!(matches!(batch.results[0], Err(McError::InvalidProduct(_))));
+    assert!(batch.results[1].is_ok());
+}
+
+#[test]
+fn test_short_positions_net_against_long_ones() {
+    let trades = vec![
+        BatchTrade::new("long call".to_string(), Product::call(100.0), 90).with_quantity(3.0),
+        BatchTrade::new("short call".to_string(), Product::call(100.0), 90).with_quantity(-3.0),
+        BatchTrade::new("short put".to_string(), Product::put(95.0), 90).with_quantity(-2.0),
+    ];
+    let config = deterministic_config(2_000);
+    let batch = price_batch(&single_stock(), &trades, &config, None);
+    let results: Vec<_> = batch.results.iter().map(|r| r.as_ref().unwrap()).collect();
+    let put = price_payoff(
+        &single_stock().underlyings,
+        &single_stock().correlation_matrix,
+        90,
+        &Product::put(95.0).payoff,
+        0.05,
+        None,
+        &config,
+    )
+    .unwrap();
+    assert_eq!(results[2].price, -2.0 * put.price);
+    assert_eq!(results[2].std_error, 2.0 * put.std_error);
+    // The bounds of a short position are the negated bounds of the product
+    let (bounds, put_bounds) = (results[2].bounds.unwrap(), put.bounds.unwrap());
+    assert_eq!(
+        (bounds.lower, bounds.upper),
+        (-2.0 * put_bounds.upper, -2.0 * put_bounds.lower)
+    );
+
+    let total = batch.total().unwrap();
+    assert_eq!(total.price, results[0].price + results[1].price + results[2].price);
+    assert!((total.price + 2.0 * put.price).abs() < 1e-9);
+    let total_error: f64 = results.iter().map(|result| result.std_error).sum();
+    assert_eq!(total.std_error, total_error);
+
+    let mut trades = trades;
+    trades[1].product = Product::call(-1.0);
+    let batch = price_batch(&single_stock(), &trades, &config, None);
+    assert!(matches!(batch.total(), Err(McError::InvalidProduct(_))));
+}
@@ -0,0 +1,28 @@
+use mcproton::{benchmark_cases, run_benchmarks, SimulationConfig};
+
+#[test]
+fn test_benchmarks_pass() {
+    let config = SimulationConfig::new(5_000).with_seed(17).with_antithetic(true);
+    let outcomes = run_benchmarks(&config).unwrap();
+    assert_eq!(outcomes.len(), benchmark_cases().len());
+    for outcome in &outcomes {
+        assert!(
+            outcome.passed,
+            "{} priced {} against a reference of {} (std error {})",
+            outcome.name,
+            outcome.pricing.price,
+            outcome.reference_price,
+            outcome.pricing.std_error
+        );
+    }
+}
+
+#[test]
+fn test_wrong_references_fail() {
+    let config = SimulationConfig::new(5_000).with_seed(17);
+    let mut case = benchmark_cases().remove(0);
+    case.reference_price *= 1.2;
+    let outcome = case.run(&config).unwrap();
+    assert!(!outcome.passed);
+    assert!(outcome.error() < 0.0);
+}
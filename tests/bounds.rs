@@ -0,0 +1,108 @@
+use mcproton::bounds::{american_bounds, no_arbitrage_bounds};
+use mcproton::closed_form::black_scholes_price;
+use mcproton::{
+    price_american, price_option_with_config, Barrier, BarrierDirection, DayCountConvention,
+    KnockType, OptionType, Payoff, PriceBounds, PricingResult, PricingWarning, RateCurve,
+    SimulationConfig, Underlying,
+};
+use nalgebra::DMatrix;
+
+#[test]
+fn test_vanilla_bounds() {
+    let underlying = Underlying::new("TEST".to_string(), 100.0, 0.20);
+    let call = Payoff::Vanilla { strike_price: 90.0, option_type: OptionType::Call };
+    let bounds = no_arbitrage_bounds(&underlying, &call, &RateCurve::flat(0.05), 1.0, false, DayCountConvention::Calendar365);
+    assert!((bounds.lower - (100.0 - 90.0 * (-0.05_f64).exp())).abs() < 1e-12);
+    assert_eq!(bounds.upper, 100.0);
+
+    let put = Payoff::Vanilla { strike_price: 90.0, option_type: OptionType::Put };
+    let bounds = no_arbitrage_bounds(&underlying, &put, &RateCurve::flat(0.05), 1.0, false, DayCountConvention::Calendar365);
+    assert_eq!(bounds.lower, 0.0);
+    assert!((bounds.upper - 90.0 * (-0.05_f64).exp()).abs() < 1e-12);
+}
+
+#[test]
+fn test_barrier_option_bounded_by_vanilla() {
+    let underlying = Underlying::new("TEST".to_string(), 100.0, 0.20);
+    let call = Payoff::Vanilla { strike_price: 100.0, option_type: OptionType::Call };
+    let bounds = no_arbitrage_bounds(&underlying, &call, &RateCurve::flat(0.05), 0.5, true, DayCountConvention::Calendar365);
+    assert_eq!(bounds.lower, 0.0);
+    assert_eq!(bounds.upper, black_scholes_price(100.0, 100.0, 0.20, 0.05, 0.5, OptionType::Call));
+}
+
+#[test]
+fn test_bounds_reported_with_price() {
+    let underlyings = vec![Underlying::new("TEST".to_string(), 100.0, 0.20)];
+    let correlation = DMatrix::from_row_slice(1, 1, &[1.0]);
+    let barrier = Barrier::single(120.0, BarrierDirection::Up, KnockType::Out, false); // absolute
+    let config = SimulationConfig::new(2_000).with_seed(8);
+    let result = price_option_with_config(&underlyings, &correlation, 30, 100.0, OptionType::Call, 0.05, Some(&barrier), &config).unwrap();
+    assert!(result.bounds.is_some(), "Bounds should be reported");
+    // The knock-out is worth little more than its lower bound of zero, so the estimate may
+    // fall below it within the Monte Carlo error
+    assert!(
+        result.is_within_bounds(),
+        "Price {} (std error {}) outside of {:?}",
+        result.price,
+        result.std_error,
+        result.bounds
+    );
+    assert!(
+        !result.warnings.iter().any(|w| matches!(w, PricingWarning::OutsideNoArbitrageBounds { .. })),
+        "Unexpected warnings: {:?}",
+        result.warnings
+    );
+}
+
+#[test]
+fn test_american_bounds_reported_with_price() {
+    let underlyings = vec![Underlying::new("TEST".to_string(), 100.0, 0.20)];
+    let correlation = DMatrix::from_row_slice(1, 1, &[1.0]);
+    let config = SimulationConfig::new(2_000).with_seed(8);
+    let result = price_american(&underlyings, &correlation, 30, 105.0, OptionType::Put, 0.05, &[10, 20], 2, &config).unwrap();
+    let expected = american_bounds(&underlyings[0], 105.0, OptionType::Put, &RateCurve::flat(0.05), 30.0 / 365.0, DayCountConvention::Calendar365);
+    assert_eq!(result.bounds, Some(expected));
+    assert!(
+        !result.warnings.iter().any(|w| matches!(w, PricingWarning::OutsideNoArbitrageBounds { .. })),
+        "Unexpected warnings: {:?}",
+        result.warnings
+    );
+}
+
+#[test]
+fn test_contains_respects_tolerance() {
+    let bounds = PriceBounds { lower: 1.0, upper: 2.0 };
+    assert!(bounds.contains(1.5, 0.0));
+    assert!(!bounds.contains(0.9, 0.0));
+    assert!(bounds.contains(0.9, 0.2));
+    assert!(!bounds.contains(2.5, 0.2));
+}
+
+#[test]
+fn test_estimates_may_miss_bounds_within_their_error() {
+    let mut result = PricingResult {
+        price: -0.01,
+        std_error: 0.005,
+        num_paths: 1_000,
+        bounds: Some(PriceBounds { lower: 0.0, upper: 1.0 }),
+        non_finite_paths: 0,
+        warnings: Vec::new(),
+        audit: Vec::new(),
+    };
+    assert!(result.is_within_bounds());
+    result.price = -0.03;
+    assert!(!result.is_within_bounds());
+    result.bounds = None;
+    assert!(result.is_within_bounds());
+
+    // Estimates without variance may miss their bounds by rounding only
+    result.bounds = Some(PriceBounds {
+        lower: 10.0,
+        upper: 100.0,
+    });
+    result.std_error = 0.0;
+    result.price = 10.0 - 1e-13;
+    assert!(result.is_within_bounds());
+    result.price = 10.0 - 1e-9;
+    assert!(!result.is_within_bounds());
+}
@@ -0,0 +1,253 @@
+use mcproton::test_utils::{deterministic_config, single_stock};
+use mcproton::{
+    price_option_on_dates, price_option_with_config, Autocallable, Barrier, BarrierDirection,
+    BarrierMonitoring, Date, DayCountConvention, Dividend, FixingSchedule, HolidayCalendar,
+    KnockType, McError, OptionType, PricingRequest, PricingSession, Product, Weekday,
+};
+
+fn date(text: &str) -> Date {
+    text.parse().unwrap()
+}
+
+#[test]
+fn test_dates_follow_the_gregorian_calendar() {
+    assert_eq!(date("1970-01-01").weekday(), Weekday::Thursday);
+    assert_eq!(date("2024-03-01").weekday(), Weekday::Friday);
+    assert_eq!(date("2024-02-28").plus_days(1), date("2024-02-29"));
+    assert_eq!(date("2023-02-28").plus_days(1), date("2023-03-01"));
+    assert_eq!(date("2000-01-01").plus_days(-1), date("1999-12-31"));
+    assert_eq!(date("2025-01-01").days_since(date("2024-01-01")), 366);
+    assert!(date("2024-04-30").is_end_of_month());
+    assert_eq!(date("2024-03-05").to_string(), "2024-03-05");
+
+    // Consecutive days have consecutive weekdays across months and years
+    let start = date("1899-12-25");
+    for days in 0..100_000 {
+        let day = start.plus_days(days);
+        assert_eq!(day.days_since(start), days);
+        assert_eq!(day.plus_days(1).weekday(), day.weekday().plus_days(1));
+        assert!(day < day.plus_days(1));
+    }
+
+    for text in [
+        "2023-02-29",
+        "1900-02-29",
+        "2024-13-01",
+        "2024-04-31",
+        "2024/01/01",
+    ] {
+        assert!(
+            matches!(text.parse::<Date>(), Err(McError::InvalidDate(_))),
+            "{}",
+            text
+        );
+    }
+    assert!(Date::new(2000, 2, 29).is_ok());
+}
+
+#[test]
+fn test_day_count_conventions_between_dates() {
+    let calendar = HolidayCalendar::weekends_only();
+    let year_fraction = |convention: DayCountConvention, start: &str, end: &str| {
+        convention
+            .year_fraction_between(date(start), date(end), &calendar)
+            .unwrap()
+    };
+    assert_eq!(
+        year_fraction(DayCountConvention::Calendar365, "2023-01-01", "2024-01-01"),
+        1.0
+    );
+    assert_eq!(
+        year_fraction(DayCountConvention::Actual360, "2024-01-01", "2024-07-01"),
+        182.0 / 360.0
+    );
+    assert_eq!(
+        year_fraction(DayCountConvention::Thirty360, "2024-01-15", "2024-07-15"),
+        0.5
+    );
+
+    let days = |convention: DayCountConvention, start: &str, end: &str| {
+        convention
+            .days_between(date(start), date(end), &calendar)
+            .unwrap()
+    };
+    // Month ends count as the 30th under 30/360
+    assert_eq!(
+        days(DayCountConvention::Thirty360, "2024-01-31", "2024-03-31"),
+        60
+    );
+    assert_eq!(
+        days(DayCountConvention::Thirty360, "2024-02-29", "2024-03-31"),
+        32
+    );
+    assert_eq!(
+        days(DayCountConvention::Thirty360, "2024-02-28", "2024-03-01"),
+        3
+    );
+    // Two weeks hold ten business days
+    assert_eq!(
+        days(DayCountConvention::Trading252, "2024-03-01", "2024-03-15"),
+        10
+    );
+
+    assert!(matches!(
+        DayCountConvention::Calendar365.days_between(
+            date("2024-03-02"),
+            date("2024-03-01"),
+            &calendar
+        ),
+        Err(McError::InvalidDate(_))
+    ));
+    assert_eq!(
+        DayCountConvention::Calendar365
+            .day_offsets(
+                date("2024-03-01"),
+                &[date("2024-03-08"), date("2024-04-01")],
+                &calendar
+            )
+            .unwrap(),
+        [7, 31]
+    );
+}
+
+#[test]
+fn test_holidays_are_not_business_days() {
+    let calendar = HolidayCalendar::new([date("2024-03-06")]);
+    let valuation = date("2024-03-01");
+    let maturity = date("2024-03-11");
+    assert!(!calendar.is_business_day(date("2024-03-06")));
+    assert!(!calendar.is_business_day(date("2024-03-09")));
+    assert_eq!(calendar.following(date("2024-03-09")), date("2024-03-11"));
+    assert_eq!(calendar.business_days_between(valuation, maturity), 5);
+    assert_eq!(
+        calendar
+            .business_days(valuation, maturity, DayCountConvention::Calendar365)
+            .unwrap(),
+        [3, 4, 6, 7, 10]
+    );
+    assert_eq!(
+        calendar
+            .business_days(valuation, maturity, DayCountConvention::Trading252)
+            .unwrap(),
+        [1, 2, 3, 4, 5]
+    );
+}
+
+#[test]
+fn test_barrier_monitored_on_business_days_knocks_out_less_often() {
+    let market = single_stock();
+    let calendar = HolidayCalendar::new([date("2024-04-01"), date("2024-05-27")]);
+    let valuation = date("2024-03-15");
+    let maturity = calendar.following(date("2024-06-15"));
+    let day_count = DayCountConvention::Calendar365;
+    let time_horizon_days = day_count
+        .days_between(valuation, maturity, &calendar)
+        .unwrap();
+    let daily = Barrier::single(90.0, BarrierDirection::Down, KnockType::Out, false);
+    let business = Barrier {
+        monitoring: BarrierMonitoring::Dates(
+            calendar
+                .business_days(valuation, maturity, day_count)
+                .unwrap(),
+        ),
+        ..daily.clone()
+    };
+    let price = |barrier: &Barrier| {
+        price_option_with_config(
+            &market.underlyings,
+            &market.correlation_matrix,
+            time_horizon_days,
+            100.0,
+            OptionType::Call,
+            market.risk_free_rate.clone(),
+            Some(barrier),
+            &deterministic_config(5_000),
+        )
+        .unwrap()
+        .price
+    };
+    assert!(price(&business) > price(&daily));
+}
+
+#[test]
+fn test_dates_convert_once_with_the_configured_convention() {
+    let market = single_stock();
+    let valuation = date("2024-03-01");
+    let maturity = date("2024-06-03");
+    let config = deterministic_config(2_000)
+        .with_day_count(DayCountConvention::Trading252)
+        .with_calendar(HolidayCalendar::new([date("2024-05-27")]));
+    // 66 weekdays, one of them a holiday
+    assert_eq!(config.days_between(valuation, maturity).unwrap(), 65);
+
+    let request = PricingRequest::new()
+        .market(market.clone())
+        .strike(100.0)
+        .config(config.clone());
+    let dated = request
+        .clone()
+        .valuation_date(valuation)
+        .maturity_date(maturity)
+        .price()
+        .unwrap();
+    assert_eq!(
+        dated.price,
+        request.clone().maturity_days(65).price().unwrap().price
+    );
+    let session_price =
+        |session: PricingSession| session.price(&Product::call(100.0)).unwrap().price;
+    assert_eq!(
+        session_price(
+            PricingSession::on_dates(market.clone(), valuation, maturity, &config).unwrap()
+        ),
+        session_price(PricingSession::new(market.clone(), 65, &config).unwrap())
+    );
+
+    assert!(matches!(
+        request.clone().maturity_date(maturity).price(),
+        Err(McError::InvalidDate(_))
+    ));
+    assert!(matches!(
+        price_option_on_dates(
+            &market.underlyings,
+            &market.correlation_matrix,
+            maturity,
+            valuation,
+            100.0,
+            OptionType::Call,
+            0.05,
+            1_000,
+            None
+        ),
+        Err(McError::InvalidDate(_))
+    ));
+
+    let config = config.with_day_count(DayCountConvention::Thirty360);
+    let month_ends = [date("2024-03-31"), date("2024-04-30"), date("2024-05-31")];
+    assert_eq!(
+        FixingSchedule::on_dates(valuation, &month_ends, &config).unwrap(),
+        FixingSchedule::Dates(vec![30, 59, 90])
+    );
+    assert_eq!(
+        Dividend::cash_on(valuation, date("2024-04-15"), 1.5, &config).unwrap(),
+        Dividend::Cash {
+            day: 44,
+            amount: 1.5
+        }
+    );
+    let note = Autocallable::new(1000.0, Vec::new(), 1.0, 0.8, 0.02, false, 0.6)
+        .with_observation_dates(valuation, &month_ends, &config)
+        .unwrap();
+    assert_eq!(note.maturity_days(), 90);
+}
+
+#[test]
+fn test_dates_serialize_as_text() {
+    let json = serde_json::to_string(&date("2024-12-31")).unwrap();
+    assert_eq!(json, "\"2024-12-31\"");
+    assert_eq!(
+        serde_json::from_str::<Date>(&json).unwrap(),
+        date("2024-12-31")
+    );
+    assert!(serde_json::from_str::<Date>("\"2024-02-30\"").is_err());
+}
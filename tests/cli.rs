@@ -0,0 +1,177 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+use mcproton::test_utils::two_asset_basket;
+use mcproton::{
+    Barrier, BarrierDirection, BarrierType, KnockType, PricingSession, Product, SimulationConfig,
+};
+use serde_json::{json, Value};
+
+/// Writes a trade file into the temporary directory, unique to the test
+fn trade_file(name: &str, contents: &Value) -> PathBuf {
+    let path =
+        std::env::temp_dir().join(format!("mcproton-cli-{}-{}.json", std::process::id(), name));
+    fs::write(&path, contents.to_string()).unwrap();
+    path
+}
+
+fn worst_of_barrier() -> Barrier {
+    Barrier::multi(
+        0.8,
+        BarrierDirection::Down,
+        KnockType::In,
+        BarrierType::WorstOf,
+        true,
+        vec![0, 1],
+    )
+    .unwrap()
+}
+
+fn trades() -> Value {
+    json!({
+        "market": two_asset_basket(),
+        "trades": [
+            {
+                "name": "call",
+                "maturity_days": 90,
+                "payoff": { "Vanilla": { "strike_price": 100.0, "option_type": "Call" } }
+            },
+            {
+                "name": "worst-of put, knock-in",
+                "maturity_days": 90,
+                "payoff": { "Vanilla": { "strike_price": 100.0, "option_type": "Put" } },
+                "barrier": worst_of_barrier()
+            }
+        ]
+    })
+}
+
+fn mcproton(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_mcproton"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_json_output_matches_a_pricing_session() {
+    let mut trades = trades();
+    trades["trades"][1]["quantity"] = json!(-2.0);
+    let path = trade_file("json", &trades);
+    let output = mcproton(&[
+        "price",
+        path.to_str().unwrap(),
+        "--paths",
+        "2e3",
+        "--seed",
+        "42",
+        "--greeks",
+        "--output",
+        "json",
+    ]);
+    assert!(output.status.success(), "{:?}", output);
+    let priced: Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    let config = SimulationConfig::new(2_000).with_seed(42);
+    let session = PricingSession::new(two_asset_basket(), 90, &config).unwrap();
+    // The short put is reported as the value of the position
+    let positions = [
+        (1.0, Product::call(100.0)),
+        (-2.0, Product::put(100.0).with_barrier(worst_of_barrier())),
+    ];
+    for (trade, (quantity, product)) in priced.as_array().unwrap().iter().zip(&positions) {
+        let greeks = session.greeks(product).unwrap().scaled(*quantity);
+        // Parsing the printed values may be off in the last digit
+        let assert_close = |value: &Value, expected: f64| {
+            let value = value.as_f64().unwrap();
+            assert!(
+                (value - expected).abs() <= 1e-12 * expected.abs(),
+                "{}",
+                value
+            );
+        };
+        assert_close(&trade["price"], greeks.pricing.price);
+        assert_close(&trade["std_error"], greeks.pricing.std_error);
+        assert_close(&trade["greeks"]["delta"], greeks.delta);
+        assert_close(&trade["greeks"]["vega"], greeks.vega);
+        assert_eq!(trade["num_paths"], json!(2_000));
+    }
+    assert_eq!(priced[1]["name"], json!("worst-of put, knock-in"));
+    assert!(priced[1]["price"].as_f64().unwrap() < 0.0);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_csv_output_is_written_to_a_file() {
+    let path = trade_file("csv", &trades());
+    let out = path.with_extension("csv");
+    let output = mcproton(&[
+        "price",
+        path.to_str().unwrap(),
+        "--paths",
+        "1000",
+        "--seed",
+        "1",
+        "--output",
+        "csv",
+        "--out",
+        out.to_str().unwrap(),
+    ]);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(output.stdout.is_empty());
+
+    let csv = fs::read_to_string(&out).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], "name,price,std_error,num_paths,warnings,error");
+    assert!(lines[1].starts_with("call,"));
+    assert!(lines[2].starts_with("\"worst-of put, knock-in\","));
+    fs::remove_file(&path).unwrap();
+    fs::remove_file(&out).unwrap();
+}
+
+#[test]
+fn test_invalid_inputs_fail_with_a_message() {
+    let mut invalid = trades();
+    invalid["trades"][0]["payoff"] = json!({ "Vanilla": { "strike_price": 100.0 } });
+    let path = trade_file("invalid", &invalid);
+    let output = mcproton(&["price", path.to_str().unwrap(), "--paths", "100"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("invalid trade file"), "{}", stderr);
+    fs::remove_file(&path).unwrap();
+
+    let output = mcproton(&["price", "missing.json", "--paths", "1.5"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("not a whole number"), "{}", stderr);
+}
+
+#[test]
+fn test_rejected_trades_do_not_abort_the_run() {
+    let mut trades = trades();
+    trades["trades"][0]["payoff"]["Vanilla"]["strike_price"] = json!(-100.0);
+    let path = trade_file("rejected", &trades);
+    let output = mcproton(&[
+        "price",
+        path.to_str().unwrap(),
+        "--paths",
+        "1000",
+        "--seed",
+        "1",
+        "--output",
+        "json",
+    ]);
+    // The run fails, but only after the valid trades are priced and written
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("error: trade call:"), "{}", stderr);
+
+    let priced: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(priced[0]["price"], Value::Null);
+    assert!(priced[0]["error"].as_str().unwrap().contains("strike"));
+    assert!(priced[1]["price"].as_f64().unwrap() > 0.0);
+    assert!(priced[1].get("error").is_none());
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,138 @@
+use mcproton::closed_form::black_scholes_price;
+use mcproton::test_utils::{assert_within_std_errors, deterministic_config, single_stock};
+use mcproton::{
+    price_payoff, Averaging, FixingSchedule, McError, OptionType, Payoff, PricingResult,
+    PricingSession, Product, SimulationConfig,
+};
+
+const DAYS: u32 = 90;
+
+fn cliquet(
+    local_floor: Option<f64>,
+    local_cap: Option<f64>,
+    global_floor: Option<f64>,
+    global_cap: Option<f64>,
+    schedule: FixingSchedule,
+) -> Payoff {
+    Payoff::Cliquet {
+        notional: 1_000.0,
+        local_floor,
+        local_cap,
+        global_floor,
+        global_cap,
+        schedule,
+    }
+}
+
+fn price(payoff: &Payoff, config: &SimulationConfig) -> Result<PricingResult, McError> {
+    let market = single_stock();
+    price_payoff(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        payoff,
+        market.risk_free_rate.clone(),
+        None,
+        config,
+    )
+}
+
+#[test]
+fn test_single_floored_period_is_a_call_on_the_return() {
+    let config = deterministic_config(5_000).with_control_variate(false);
+    let payoff = cliquet(
+        Some(0.0),
+        None,
+        None,
+        None,
+        FixingSchedule::Dates(vec![DAYS]),
+    );
+    let result = price(&payoff, &config).unwrap();
+    // An average over the single expiry fixing simulates the same daily paths
+    let call = price(
+        &Payoff::AveragePrice {
+            strike_price: 100.0,
+            option_type: OptionType::Call,
+            averaging: Averaging::Arithmetic,
+            schedule: FixingSchedule::Dates(vec![DAYS]),
+        },
+        &config,
+    )
+    .unwrap();
+    // 1000 * max(S_T / 100 - 1, 0) = 10 * max(S_T - 100, 0) on every path
+    assert!((result.price - 10.0 * call.price).abs() < 1e-9 * result.price);
+}
+
+#[test]
+fn test_locally_bounded_returns_sum_to_forward_start_call_spreads() {
+    let (floor, cap) = (-0.02, 0.03);
+    let payoff = cliquet(Some(floor), Some(cap), None, None, FixingSchedule::Monthly);
+    let result = price(&payoff, &deterministic_config(20_000)).unwrap();
+
+    // Each month returns floor + (R - floor)^+ - (R - cap)^+ on independent lognormal returns
+    let period = 30.0_f64 / 365.0;
+    let growth = (0.05 * period).exp();
+    let call = |strike: f64| {
+        black_scholes_price(1.0, strike, 0.20, 0.05, period, OptionType::Call) * growth
+    };
+    let expected_return = floor + call(1.0 + floor) - call(1.0 + cap);
+    let expected = 1_000.0 * 3.0 * expected_return * (-0.05 * DAYS as f64 / 365.0).exp();
+    assert_within_std_errors(&result, expected, 4.0);
+    let bounds = result.bounds.unwrap();
+    assert!(bounds.lower < result.price && result.price < bounds.upper);
+    assert!((bounds.upper - 1_000.0 * 0.09 * (-0.05 * DAYS as f64 / 365.0).exp()).abs() < 1e-9);
+}
+
+#[test]
+fn test_global_floor_and_cap_bound_the_sum() {
+    let config = deterministic_config(5_000).with_control_variate(false);
+    let price_of = |global_floor, global_cap| {
+        price(
+            &cliquet(
+                Some(-0.05),
+                Some(0.05),
+                global_floor,
+                global_cap,
+                FixingSchedule::Monthly,
+            ),
+            &config,
+        )
+        .unwrap()
+        .price
+    };
+    let unbounded = price_of(None, None);
+    assert!(price_of(Some(0.0), None) > unbounded);
+    assert!(price_of(None, Some(0.02)) < unbounded);
+}
+
+#[test]
+fn test_floors_above_caps_are_rejected() {
+    let config = deterministic_config(100);
+    for payoff in [
+        cliquet(Some(0.05), Some(0.01), None, None, FixingSchedule::Monthly),
+        cliquet(None, None, Some(0.1), Some(0.0), FixingSchedule::Monthly),
+        cliquet(
+            Some(0.0),
+            Some(0.05),
+            Some(0.1),
+            Some(0.0),
+            FixingSchedule::Monthly,
+        ),
+    ] {
+        let result = price(&payoff, &config);
+        assert!(
+            matches!(result, Err(McError::InvalidProduct(_))),
+            "{:?}",
+            result
+        );
+    }
+}
+
+#[test]
+fn test_session_prices_cliquets_from_the_initial_price() {
+    let config = deterministic_config(2_000);
+    let payoff = cliquet(Some(0.0), Some(0.04), None, None, FixingSchedule::Monthly);
+    let session = PricingSession::new(single_stock(), DAYS, &config).unwrap();
+    let result = session.price(&Product::new(payoff.clone())).unwrap();
+    assert_eq!(result.price, price(&payoff, &config).unwrap().price);
+}
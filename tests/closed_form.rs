@@ -0,0 +1,278 @@
+use mcproton::closed_form::{
+    black_scholes_barrier_price, black_scholes_greeks, black_scholes_price,
+    geometric_average_price, norm_cdf, norm_pdf,
+};
+use mcproton::{
+    Barrier, BarrierDirection, BarrierMonitoring, KnockType, OptionType, Rebate, RebateTiming,
+};
+
+#[test]
+fn test_norm_cdf_reference_values() {
+    assert!((norm_cdf(0.0) - 0.5).abs() < 1e-15);
+    assert!((norm_cdf(1.0) - 0.841_344_746_068_543).abs() < 1e-12);
+    assert!((norm_cdf(-1.96) - 0.024_997_895_148_220).abs() < 1e-12);
+    assert!((norm_cdf(8.0) - 1.0).abs() < 1e-14);
+    assert!((norm_pdf(0.0) - 0.398_942_280_401_433).abs() < 1e-15);
+    assert!((norm_pdf(1.0) - norm_pdf(-1.0)).abs() < 1e-15);
+}
+
+#[test]
+fn test_black_scholes_reference_prices() {
+    // Textbook example: S = K = 100, σ = 20%, r = 5%, T = 1 year
+    let call = black_scholes_price(100.0, 100.0, 0.20, 0.05, 1.0, OptionType::Call);
+    let put = black_scholes_price(100.0, 100.0, 0.20, 0.05, 1.0, OptionType::Put);
+    assert!(
+        (call - 10.450_583_572).abs() < 1e-6,
+        "Call price was {}",
+        call
+    );
+    assert!((put - 5.573_526_022).abs() < 1e-6, "Put price was {}", put);
+}
+
+#[test]
+fn test_black_scholes_put_call_parity() {
+    let call = black_scholes_price(100.0, 110.0, 0.30, 0.03, 0.5, OptionType::Call);
+    let put = black_scholes_price(100.0, 110.0, 0.30, 0.03, 0.5, OptionType::Put);
+    let forward_value = 100.0 - 110.0 * (-0.03_f64 * 0.5).exp();
+    assert!((call - put - forward_value).abs() < 1e-10);
+}
+
+#[test]
+fn test_black_scholes_greeks_reference_values() {
+    // Textbook example: S = K = 100, σ = 20%, r = 5%, T = 1 year
+    let call = black_scholes_greeks(100.0, 100.0, 0.20, 0.05, 1.0, OptionType::Call);
+    assert!((call.price - 10.450_583_572).abs() < 1e-6);
+    assert!((call.delta - 0.636_830_651).abs() < 1e-6);
+    assert!((call.gamma - 0.018_762_017).abs() < 1e-6);
+    assert!((call.vega - 37.524_034).abs() < 1e-4);
+    assert!((call.theta + 6.414_027).abs() < 1e-4);
+    assert!((call.rho - 53.232_482).abs() < 1e-4);
+
+    let put = black_scholes_greeks(100.0, 100.0, 0.20, 0.05, 1.0, OptionType::Put);
+    assert!((call.delta - put.delta - 1.0).abs() < 1e-12);
+    assert!((call.gamma - put.gamma).abs() < 1e-12);
+    assert!((call.vega - put.vega).abs() < 1e-12);
+}
+
+#[test]
+fn test_black_scholes_greeks_match_finite_differences() {
+    let (spot, strike, volatility, rate, time) = (95.0, 105.0, 0.35, 0.03, 0.75);
+    for option_type in [OptionType::Call, OptionType::Put] {
+        let greeks = black_scholes_greeks(spot, strike, volatility, rate, time, option_type);
+        let price = |spot: f64, volatility: f64, rate: f64, time: f64| {
+            black_scholes_price(spot, strike, volatility, rate, time, option_type)
+        };
+        let h = 1e-4;
+        let delta = (price(spot + h, volatility, rate, time)
+            - price(spot - h, volatility, rate, time))
+            / (2.0 * h);
+        let gamma = (price(spot + h, volatility, rate, time) - 2.0 * greeks.price
+            + price(spot - h, volatility, rate, time))
+            / (h * h);
+        let vega = (price(spot, volatility + h, rate, time)
+            - price(spot, volatility - h, rate, time))
+            / (2.0 * h);
+        let rho = (price(spot, volatility, rate + h, time)
+            - price(spot, volatility, rate - h, time))
+            / (2.0 * h);
+        let theta = -(price(spot, volatility, rate, time + h)
+            - price(spot, volatility, rate, time - h))
+            / (2.0 * h);
+        assert!((greeks.delta - delta).abs() < 1e-6);
+        assert!((greeks.gamma - gamma).abs() < 1e-4);
+        assert!((greeks.vega - vega).abs() < 1e-5);
+        assert!((greeks.rho - rho).abs() < 1e-5);
+        assert!((greeks.theta - theta).abs() < 1e-5);
+    }
+}
+
+#[test]
+fn test_knock_in_and_knock_out_add_up_to_the_vanilla() {
+    let (spot, volatility, rate, time) = (100.0, 0.25, 0.04, 0.5);
+    for (level, direction) in [
+        (90.0, BarrierDirection::Down),
+        (115.0, BarrierDirection::Up),
+    ] {
+        for strike in [85.0, 100.0, 120.0] {
+            for option_type in [OptionType::Call, OptionType::Put] {
+                let price = |knock_type: KnockType| {
+                    let barrier = Barrier::single(level, direction, knock_type, false);
+                    black_scholes_barrier_price(
+                        spot,
+                        strike,
+                        &barrier,
+                        volatility,
+                        rate,
+                        time,
+                        option_type,
+                    )
+                    .unwrap()
+                };
+                let vanilla =
+                    black_scholes_price(spot, strike, volatility, rate, time, option_type);
+                let (knock_in, knock_out) = (price(KnockType::In), price(KnockType::Out));
+                assert!(knock_in >= 0.0 && knock_out >= 0.0);
+                assert!(
+                    (knock_in + knock_out - vanilla).abs() < 1e-10,
+                    "In {} and out {} should add up to the vanilla {} (level {}, strike {}, {:?})",
+                    knock_in,
+                    knock_out,
+                    vanilla,
+                    level,
+                    strike,
+                    option_type
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_barrier_price_limits() {
+    let (spot, strike, volatility, rate, time) = (100.0, 100.0, 0.25, 0.04, 0.5);
+    let vanilla = black_scholes_price(spot, strike, volatility, rate, time, OptionType::Call);
+    let price = |barrier: &Barrier| {
+        black_scholes_barrier_price(
+            spot,
+            strike,
+            barrier,
+            volatility,
+            rate,
+            time,
+            OptionType::Call,
+        )
+        .unwrap()
+    };
+    // Distant barriers are almost never hit, a knock-in barrier behind the spot already is
+    assert!(
+        (price(&Barrier::single(
+            1.0,
+            BarrierDirection::Down,
+            KnockType::Out,
+            false
+        )) - vanilla)
+            .abs()
+            < 1e-10
+    );
+    assert!(
+        price(&Barrier::single(
+            3.0,
+            BarrierDirection::Up,
+            KnockType::In,
+            true
+        )) < 1e-6
+    );
+    assert_eq!(
+        price(&Barrier::single(
+            0.95,
+            BarrierDirection::Up,
+            KnockType::In,
+            true
+        )),
+        vanilla
+    );
+    // An up-and-out call with the barrier below the strike never pays
+    let up_and_out = Barrier::single(110.0, BarrierDirection::Up, KnockType::Out, false);
+    assert_eq!(
+        black_scholes_barrier_price(
+            spot,
+            120.0,
+            &up_and_out,
+            volatility,
+            rate,
+            time,
+            OptionType::Call
+        ),
+        Some(0.0)
+    );
+
+    let daily = Barrier::single(90.0, BarrierDirection::Down, KnockType::Out, false)
+        .with_monitoring(BarrierMonitoring::AtExpiry);
+    assert!(black_scholes_barrier_price(
+        spot,
+        strike,
+        &daily,
+        volatility,
+        rate,
+        time,
+        OptionType::Call
+    )
+    .is_none());
+    assert!(black_scholes_barrier_price(
+        spot,
+        strike,
+        &Barrier::single(90.0, BarrierDirection::Down, KnockType::Out, false),
+        0.0,
+        rate,
+        time,
+        OptionType::Call
+    )
+    .is_none());
+}
+
+#[test]
+fn test_barrier_rebates() {
+    let (spot, volatility, rate, time) = (100.0, 0.30, 0.05, 90.0 / 365.0);
+    let rebate_price = |knock_type: KnockType, timing: RebateTiming| {
+        // A put with zero strike only pays the rebate
+        let barrier = Barrier::single(90.0, BarrierDirection::Down, knock_type, false)
+            .with_rebate(Rebate::new(10.0, timing));
+        black_scholes_barrier_price(spot, 0.0, &barrier, volatility, rate, time, OptionType::Put)
+            .unwrap()
+    };
+    let discounted = 10.0 * (-rate * time).exp();
+    let knock_in = rebate_price(KnockType::In, RebateTiming::AtExpiry);
+    let at_expiry = rebate_price(KnockType::Out, RebateTiming::AtExpiry);
+    let at_hit = rebate_price(KnockType::Out, RebateTiming::AtHit);
+    assert!((knock_in + at_expiry - discounted).abs() < 1e-12);
+    assert!(
+        at_hit > at_expiry,
+        "Rebates paid at the hit are worth more with positive rates"
+    );
+    assert!(at_hit < 10.0 * (1.0 - knock_in / discounted));
+
+    // Already knocked out: the rebate is paid now
+    let barrier = Barrier::single(105.0, BarrierDirection::Down, KnockType::Out, false)
+        .with_rebate(Rebate::new(10.0, RebateTiming::AtHit));
+    assert_eq!(
+        black_scholes_barrier_price(
+            spot,
+            100.0,
+            &barrier,
+            volatility,
+            rate,
+            time,
+            OptionType::Call
+        ),
+        Some(10.0)
+    );
+}
+
+#[test]
+fn test_geometric_average_price_limits() {
+    // A single fixing at expiry averages the terminal price only
+    for option_type in [OptionType::Call, OptionType::Put] {
+        let asian =
+            geometric_average_price(100.0, 95.0, 0.25, 0.05, &[0.5], 0.5, option_type).unwrap();
+        let vanilla = black_scholes_price(100.0, 95.0, 0.25, 0.05, 0.5, option_type);
+        assert!((asian - vanilla).abs() < 1e-12, "{:?}: {} != {}", option_type, asian, vanilla);
+    }
+
+    // Averaging lowers the variance and with it the value of the call
+    let times = [0.25, 0.5, 0.75, 1.0];
+    let call = geometric_average_price(100.0, 100.0, 0.2, 0.05, &times, 1.0, OptionType::Call)
+        .unwrap();
+    let put = geometric_average_price(100.0, 100.0, 0.2, 0.05, &times, 1.0, OptionType::Put)
+        .unwrap();
+    assert!(call < black_scholes_price(100.0, 100.0, 0.2, 0.05, 1.0, OptionType::Call));
+    // Put-call parity on the expected geometric average
+    let mean_log = 100.0_f64.ln() + (0.05 - 0.02) * times.iter().sum::<f64>() / 4.0;
+    // The fixings covary with the earlier of their times, which sum to 7.5 over all pairs
+    let variance = 0.04 * 7.5 / 16.0;
+    let forward = (mean_log + 0.5 * variance).exp();
+    assert!((call - put - (-0.05_f64).exp() * (forward - 100.0)).abs() < 1e-9);
+    assert_eq!(
+        geometric_average_price(100.0, 100.0, 0.2, 0.05, &[], 1.0, OptionType::Call),
+        None
+    );
+}
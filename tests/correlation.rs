@@ -0,0 +1,304 @@
+use std::sync::Arc;
+
+use mcproton::correlation::{nearest_psd, stress_principal_component};
+use mcproton::test_utils::{
+    deterministic_config, three_asset_basket, two_asset_basket, uniform_correlation, TEST_SEED,
+};
+use mcproton::{
+    price_option_with_config, Barrier, BarrierDirection, CorrelatedNormalGenerator,
+    CorrelationFactor, CorrelationSampling, KnockType, McError, OptionType, PathRecorder,
+    PricingSession, PricingWarning, Product, RandomCorrelation, SimulationConfig,
+};
+use nalgebra::DMatrix;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+/// Correlations of three assets estimated on different windows: the first two move together
+/// with the third, but against each other
+fn inconsistent_correlation() -> DMatrix<f64> {
+    DMatrix::from_row_slice(3, 3, &[1.0, -0.6, 0.8, -0.6, 1.0, 0.8, 0.8, 0.8, 1.0])
+}
+
+#[test]
+fn test_nearest_psd_matches_higham_example() {
+    // Example from Higham (2002), Computing the nearest correlation matrix
+    let matrix = DMatrix::from_row_slice(3, 3, &[1.0, 1.0, 0.0, 1.0, 1.0, 1.0, 0.0, 1.0, 1.0]);
+    let repaired = nearest_psd(&matrix).unwrap();
+    let expected = DMatrix::from_row_slice(
+        3,
+        3,
+        &[
+            1.0, 0.7607, 0.1573, 0.7607, 1.0, 0.7607, 0.1573, 0.7607, 1.0,
+        ],
+    );
+    assert!(
+        (&repaired - &expected).abs().max() < 1e-4,
+        "Repaired matrix {} should match {}",
+        repaired,
+        expected
+    );
+}
+
+#[test]
+fn test_nearest_psd_returns_a_valid_correlation_matrix() {
+    let matrix = inconsistent_correlation();
+    assert!(matrix.clone().cholesky().is_none());
+    let repaired = nearest_psd(&matrix).unwrap();
+    assert!(repaired.clone().cholesky().is_some());
+    assert!(repaired.diagonal().iter().all(|&d| d == 1.0));
+    assert_eq!(repaired, repaired.transpose());
+    // Valid correlation matrices are kept
+    let valid = uniform_correlation(4, 0.3);
+    assert!((nearest_psd(&valid).unwrap() - &valid).abs().max() < 1e-10);
+    assert!(matches!(
+        nearest_psd(&DMatrix::zeros(2, 3)),
+        Err(McError::InvalidCorrelationMatrix(_))
+    ));
+}
+
+#[test]
+fn test_principal_component_stress_keeps_a_valid_matrix() {
+    // A uniform correlation of rho has the leading eigenvalue 1 + (n - 1) rho
+    let stressed = stress_principal_component(&uniform_correlation(4, 0.3), 0.2);
+    assert!((&stressed - uniform_correlation(4, 0.5)).abs().max() < 1e-10);
+    let stressed = stress_principal_component(&uniform_correlation(4, 0.3), -0.2);
+    assert!((&stressed - uniform_correlation(4, 0.1)).abs().max() < 1e-10);
+
+    // Shifts too large to apply pairwise saturate at a single factor
+    let stressed = stress_principal_component(&uniform_correlation(3, 0.5), 0.9);
+    assert!((&stressed - uniform_correlation(3, 1.0)).abs().max() < 1e-10);
+    let stressed = stress_principal_component(&uniform_correlation(3, 0.5), -0.9);
+    assert!((&stressed - DMatrix::identity(3, 3)).abs().max() < 1e-10);
+    let matrix = DMatrix::from_row_slice(3, 3, &[1.0, 0.2, 0.7, 0.2, 1.0, 0.4, 0.7, 0.4, 1.0]);
+    for shift in [-1.0, -0.4, 0.4] {
+        let stressed = stress_principal_component(&matrix, shift);
+        assert!(stressed.diagonal().iter().all(|&d| d == 1.0));
+        assert!((&stressed - stressed.transpose()).abs().max() < 1e-12);
+        let smallest = stressed.symmetric_eigenvalues().min();
+        assert!(smallest > -1e-10, "Eigenvalue {} after a shift of {}", smallest, shift);
+    }
+}
+
+#[test]
+fn test_random_correlations_follow_the_lkj_distribution() {
+    let mut rng = StdRng::seed_from_u64(TEST_SEED);
+    for sampling in [CorrelationSampling::Onion, CorrelationSampling::Vine] {
+        let sampler = RandomCorrelation::new(4).with_sampling(sampling);
+        let draws: Vec<DMatrix<f64>> =
+            (0..4_000).map(|_| sampler.sample(&mut rng).unwrap()).collect();
+        for matrix in &draws {
+            assert!(matrix.diagonal().iter().all(|&d| d == 1.0));
+            assert_eq!(matrix, &matrix.transpose());
+            assert!(CorrelationFactor::new(matrix, false).is_ok());
+        }
+        // Uniform over the correlation matrices, each correlation is 2 Beta(2, 2) - 1, with
+        // mean 0 and variance 1/5
+        let correlations: Vec<f64> = draws.iter().map(|matrix| matrix[(1, 3)]).collect();
+        let mean = correlations.iter().sum::<f64>() / correlations.len() as f64;
+        let variance =
+            correlations.iter().map(|c| c * c).sum::<f64>() / correlations.len() as f64;
+        assert!(mean.abs() < 0.03, "{:?} mean {}", sampling, mean);
+        assert!((variance - 0.2).abs() < 0.015, "{:?} variance {}", sampling, variance);
+    }
+    // Same seed, same draws; a higher concentration draws weaker correlations
+    let sampler = RandomCorrelation::new(5).with_concentration(50.0);
+    let first = sampler.sample(&mut StdRng::seed_from_u64(1)).unwrap();
+    assert_eq!(first, sampler.sample(&mut StdRng::seed_from_u64(1)).unwrap());
+    assert!((&first - DMatrix::identity(5, 5)).abs().max() < 0.5);
+}
+
+#[test]
+fn test_random_correlations_match_the_average() {
+    let mut rng = StdRng::seed_from_u64(TEST_SEED);
+    for average in [-0.15, 0.0, 0.35, 0.9] {
+        let sampler = RandomCorrelation::new(6)
+            .with_sampling(CorrelationSampling::Vine)
+            .with_average_correlation(average);
+        for _ in 0..50 {
+            let matrix = sampler.sample(&mut rng).unwrap();
+            let mean = (matrix.sum() - 6.0) / 30.0;
+            assert!((mean - average).abs() < 1e-12, "Mean {} for {}", mean, average);
+            assert!(CorrelationFactor::new(&matrix, false).is_ok());
+        }
+    }
+    assert_eq!(
+        RandomCorrelation::new(1).with_average_correlation(0.5).sample(&mut rng).unwrap(),
+        DMatrix::identity(1, 1)
+    );
+    for sampler in [
+        RandomCorrelation::new(0),
+        RandomCorrelation::new(3).with_concentration(0.0),
+        RandomCorrelation::new(3).with_average_correlation(1.0),
+        RandomCorrelation::new(3).with_average_correlation(-0.5),
+    ] {
+        assert!(matches!(
+            sampler.sample(&mut rng),
+            Err(McError::InvalidCorrelationMatrix(_))
+        ));
+    }
+}
+
+#[test]
+fn test_pricing_repairs_the_correlation_when_enabled() {
+    let market = three_asset_basket();
+    let correlation = inconsistent_correlation();
+    let price = |config: &SimulationConfig, correlation: &DMatrix<f64>| {
+        price_option_with_config(
+            &market.underlyings,
+            correlation,
+            90,
+            100.0,
+            OptionType::Call,
+            0.05,
+            None,
+            config,
+        )
+    };
+    let config = SimulationConfig::new(1_000).with_seed(5);
+    assert!(matches!(
+        price(&config, &correlation),
+        Err(McError::InvalidCorrelationMatrix(_))
+    ));
+
+    let repaired = price(&config.clone().with_correlation_repair(true), &correlation).unwrap();
+    let expected_adjustment = (nearest_psd(&correlation).unwrap() - &correlation).norm();
+    assert!(repaired.warnings.iter().any(|warning| matches!(
+        warning,
+        PricingWarning::CorrelationRepaired { adjustment } if (adjustment - expected_adjustment).abs() < 1e-12
+    )));
+    // The paths are those of the repaired matrix
+    let direct = price(&config, &nearest_psd(&correlation).unwrap()).unwrap();
+    assert_eq!(repaired.price, direct.price);
+    assert!(!direct
+        .warnings
+        .iter()
+        .any(|warning| matches!(warning, PricingWarning::CorrelationRepaired { .. })));
+}
+
+#[test]
+fn test_session_reports_the_repair_on_every_price() {
+    let market = three_asset_basket();
+    let market = mcproton::MarketSnapshot {
+        correlation_matrix: inconsistent_correlation(),
+        ..market
+    };
+    let config = SimulationConfig::new(1_000).with_correlation_repair(true);
+    let session = PricingSession::new(market, 30, &config).unwrap();
+    for strike_price in [95.0, 105.0] {
+        let result = session.price(&Product::call(strike_price)).unwrap();
+        assert!(result
+            .warnings
+            .iter()
+            .any(|warning| matches!(warning, PricingWarning::CorrelationRepaired { .. })));
+    }
+}
+
+#[test]
+fn test_correlation_factor_validates_and_repairs() {
+    let valid = uniform_correlation(3, 0.4);
+    let factor = CorrelationFactor::new(&valid, false).unwrap();
+    assert_eq!(factor.dimension(), 3);
+    assert_eq!(factor.adjustment(), None);
+    assert!((factor.correlation_matrix() - &valid).abs().max() < 1e-12);
+    assert_eq!(
+        factor.lower().upper_triangle(),
+        DMatrix::from_diagonal(&factor.lower().diagonal())
+    );
+
+    let inconsistent = inconsistent_correlation();
+    assert!(matches!(
+        CorrelationFactor::new(&inconsistent, false),
+        Err(McError::InvalidCorrelationMatrix(_))
+    ));
+    let repaired = CorrelationFactor::new(&inconsistent, true).unwrap();
+    let nearest = nearest_psd(&inconsistent).unwrap();
+    assert!((repaired.correlation_matrix() - &nearest).abs().max() < 1e-12);
+    assert_eq!(
+        repaired.adjustment(),
+        Some((&nearest - &inconsistent).norm())
+    );
+
+    let asymmetric = DMatrix::from_row_slice(2, 2, &[1.0, 0.5, 0.4, 1.0]);
+    for invalid in [
+        asymmetric,
+        DMatrix::zeros(2, 3),
+        DMatrix::identity(2, 2) * 2.0,
+    ] {
+        assert!(matches!(
+            CorrelationFactor::new(&invalid, true),
+            Err(McError::InvalidCorrelationMatrix(_))
+        ));
+    }
+}
+
+#[test]
+fn test_generated_shocks_realize_the_correlation() {
+    let correlation = uniform_correlation(3, 0.4);
+    let factor = CorrelationFactor::new(&correlation, false).unwrap();
+    let mut generator = CorrelatedNormalGenerator::new(factor.clone(), Some(7));
+    let n = 50_000;
+    let mut products = DMatrix::zeros(3, 3);
+    for _ in 0..n {
+        let shocks = nalgebra::DVector::from_vec(generator.next_shocks());
+        products += &shocks * shocks.transpose();
+    }
+    let realized = products / n as f64;
+    assert!((realized - &correlation).abs().max() < 0.02);
+
+    // Further shocks stay independent, and the same seed draws the same shocks
+    let mut shocks = [0.0; 5];
+    CorrelatedNormalGenerator::new(factor.clone(), Some(7)).fill(&mut shocks);
+    let mut independent = [0.0; 5];
+    CorrelatedNormalGenerator::new(
+        CorrelationFactor::new(&DMatrix::identity(3, 3), false).unwrap(),
+        Some(7),
+    )
+    .fill(&mut independent);
+    assert_eq!(shocks[3..], independent[3..]);
+    assert_eq!(shocks[0], independent[0]);
+    assert_ne!(shocks[1], independent[1]);
+}
+
+#[test]
+fn test_generator_draws_the_shocks_of_the_simulation() {
+    let market = two_asset_basket();
+    let recorder = Arc::new(PathRecorder::new(1).with_max_paths(1));
+    let days = 5;
+    price_option_with_config(
+        &market.underlyings,
+        &market.correlation_matrix,
+        days,
+        100.0,
+        OptionType::Call,
+        0.05,
+        // A distant barrier makes the simulation step daily
+        Some(&Barrier::single(
+            10.0,
+            BarrierDirection::Up,
+            KnockType::Out,
+            true,
+        )),
+        &deterministic_config(2).with_path_observer(recorder.clone()),
+    )
+    .unwrap();
+
+    // Recover the shocks of the first path from its log returns
+    let prices = &recorder.paths()[0].prices;
+    let dt = 1.0 / 365.0;
+    let factor = CorrelationFactor::new(&market.correlation_matrix, false).unwrap();
+    let mut generator = CorrelatedNormalGenerator::new(factor, Some(TEST_SEED));
+    for step in 1..=days as usize {
+        let shocks = generator.next_shocks();
+        for (i, underlying) in market.underlyings.iter().enumerate() {
+            let variance = underlying.volatility.powi(2) * dt;
+            let log_return = (prices[step][i] / prices[step - 1][i]).ln();
+            let shock = (log_return - 0.05 * dt + 0.5 * variance) / variance.sqrt();
+            assert!(
+                (shock - shocks[i]).abs() < 1e-9,
+                "{} vs {}",
+                shock,
+                shocks[i]
+            );
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use mcproton::closed_form::black_scholes_price;
+use mcproton::calendar::business_days_between;
+use mcproton::{
+    price_option_with_config, Barrier, BarrierDirection, DayCountConvention, KnockType, OptionType,
+    SimulationConfig, Underlying, VarianceTime, Weekday,
+};
+use nalgebra::DMatrix;
+
+fn single_underlying() -> (Vec<Underlying>, DMatrix<f64>) {
+    (
+        vec![Underlying::new("TEST".to_string(), 100.0, 0.20)],
+        DMatrix::from_row_slice(1, 1, &[1.0]),
+    )
+}
+
+#[test]
+fn test_year_fractions() {
+    assert_eq!(DayCountConvention::default(), DayCountConvention::Calendar365);
+    assert_eq!(DayCountConvention::Calendar365.year_fraction(73), 0.2);
+    assert_eq!(DayCountConvention::Trading252.year_fraction(63), 0.25);
+}
+
+#[test]
+fn test_trading_days_scale_time_to_expiration() {
+    // 63 trading days are a quarter of a year
+    let (underlyings, correlation) = single_underlying();
+    let config = SimulationConfig::new(20_000)
+        .with_seed(3)
+        .with_antithetic(true)
+        .with_day_count(DayCountConvention::Trading252);
+    let result = price_option_with_config(&underlyings, &correlation, 63, 100.0, OptionType::Call, 0.05, None, &config).unwrap();
+    let analytic = black_scholes_price(100.0, 100.0, 0.20, 0.05, 0.25, OptionType::Call);
+    assert!(
+        (result.price - analytic).abs() < 4.0 * result.std_error,
+        "Price {} with 252-day year should match Black-Scholes {}",
+        result.price,
+        analytic
+    );
+}
+
+#[test]
+fn test_trading_days_step_once_per_trading_day() {
+    // A year has 252 instead of 365 monitoring dates, so a knock-out is less likely
+    let (underlyings, correlation) = single_underlying();
+    let barrier = Barrier::single(0.9, BarrierDirection::Down, KnockType::Out, true); // relative
+    let config = SimulationConfig::new(4_000).with_seed(4);
+    let calendar = price_option_with_config(&underlyings, &correlation, 365, 100.0, OptionType::Call, 0.05, Some(&barrier), &config).unwrap();
+    let trading = price_option_with_config(
+        &underlyings, &correlation, 252, 100.0, OptionType::Call, 0.05, Some(&barrier),
+        &config.clone().with_day_count(DayCountConvention::Trading252),
+    ).unwrap();
+    assert!(
+        trading.price >= calendar.price - 4.0 * (calendar.std_error + trading.std_error),
+        "Coarser monitoring {} should not be worth less than daily monitoring {}",
+        trading.price,
+        calendar.price
+    );
+}
+
+#[test]
+fn test_business_days() {
+    assert_eq!(Weekday::Friday.plus_days(1), Weekday::Saturday);
+    assert_eq!(Weekday::Sunday.plus_days(15), Weekday::Monday);
+    assert!(Weekday::Sunday.is_weekend() && !Weekday::Monday.is_weekend());
+    // Friday valuation: Saturday and Sunday are skipped, Monday counts
+    assert_eq!(business_days_between(Weekday::Friday, 0, 3), 1);
+    assert_eq!(business_days_between(Weekday::Monday, 0, 14), 10);
+}
+
+#[test]
+fn test_business_variance_time_skips_weekends() {
+    let calendar = VarianceTime::Calendar;
+    let business = VarianceTime::Business(Weekday::Friday);
+    let day_count = DayCountConvention::Calendar365;
+    assert_eq!(calendar.year_fraction(day_count, 0, 3), 3.0 / 365.0);
+    assert_eq!(business.year_fraction(day_count, 0, 2), 0.0);
+    assert!((business.year_fraction(day_count, 0, 3) - 7.0 / (5.0 * 365.0)).abs() < 1e-15);
+    // Trading days are business days already
+    assert_eq!(
+        business.year_fraction(DayCountConvention::Trading252, 0, 3),
+        calendar.year_fraction(DayCountConvention::Trading252, 0, 3)
+    );
+}
+
+#[test]
+fn test_friday_option_is_cheaper_in_business_time() {
+    // An option expiring on Monday, priced on Friday, only sees one day of variance
+    let (underlyings, correlation) = single_underlying();
+    let config = SimulationConfig::new(20_000).with_seed(5).with_antithetic(true);
+    let calendar = price_option_with_config(&underlyings, &correlation, 3, 100.0, OptionType::Call, 0.05, None, &config).unwrap();
+    let business = price_option_with_config(
+        &underlyings, &correlation, 3, 100.0, OptionType::Call, 0.05, None,
+        &config.clone().with_variance_time(VarianceTime::Business(Weekday::Friday)),
+    ).unwrap();
+    let volatility = 0.20 * (7.0 / 5.0 / 3.0_f64).sqrt();
+    let analytic = black_scholes_price(100.0, 100.0, volatility, 0.05, 3.0 / 365.0, OptionType::Call);
+    assert!(
+        (business.price - analytic).abs() < 4.0 * business.std_error,
+        "Business time price {} should match Black-Scholes {} with one day of variance",
+        business.price,
+        analytic
+    );
+    assert!(business.price < calendar.price, "{} should be below {}", business.price, calendar.price);
+}
@@ -0,0 +1,45 @@
+#![cfg(feature = "deterministic_math")]
+
+use mcproton::closed_form::black_scholes_price;
+use mcproton::test_utils::{assert_within_std_errors, single_stock};
+use mcproton::{
+    price_payoff, Barrier, BarrierDirection, KnockType, OptionType, PricingResult, Product,
+    SimulationConfig,
+};
+
+const DAYS: u32 = 90;
+
+fn price(product: &Product) -> PricingResult {
+    let market = single_stock();
+    price_payoff(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        &product.payoff,
+        market.risk_free_rate.clone(),
+        product.barrier.as_ref(),
+        &SimulationConfig::new(20_000).with_seed(7),
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_seeded_prices_are_bit_identical_across_platforms() {
+    // Recorded once; every platform must reproduce the exact bits
+    let call = price(&Product::call(100.0));
+    assert_eq!(call.price.to_bits(), 0x4012807092923420);
+    let knock_out = Product::call(100.0).with_barrier(Barrier::single(
+        90.0,
+        BarrierDirection::Down,
+        KnockType::Out,
+        false,
+    ));
+    assert_eq!(price(&knock_out).price.to_bits(), 0x4011eb5da3941dd4);
+}
+
+#[test]
+fn test_portable_shocks_price_correctly() {
+    let time = DAYS as f64 / 365.0;
+    let expected = black_scholes_price(100.0, 100.0, 0.2, 0.05, time, OptionType::Call);
+    assert_within_std_errors(&price(&Product::call(100.0)), expected, 4.0);
+}
@@ -0,0 +1,104 @@
+use mcproton::closed_form::{
+    asset_or_nothing_price, black_scholes_barrier_price, cash_or_nothing_price,
+};
+use mcproton::test_utils::{assert_within_std_errors, deterministic_config, single_stock};
+use mcproton::{
+    Barrier, BarrierCorrection, BarrierDirection, KnockType, McError, OptionType, PricingResult,
+    PricingSession, Product, Rebate, RebateTiming, SimulationConfig,
+};
+
+const DAYS: u32 = 90;
+const TIME: f64 = DAYS as f64 / 365.0;
+
+fn price(product: &Product, config: &SimulationConfig) -> PricingResult {
+    PricingSession::new(single_stock(), DAYS, config)
+        .unwrap()
+        .price(product)
+        .unwrap()
+}
+
+#[test]
+fn test_cash_or_nothing_matches_black_scholes() {
+    let config = deterministic_config(20_000);
+    for option_type in [OptionType::Call, OptionType::Put] {
+        let result = price(&Product::cash_or_nothing(105.0, option_type, 10.0), &config);
+        let expected = cash_or_nothing_price(100.0, 105.0, 10.0, 0.20, 0.05, TIME, option_type);
+        assert_within_std_errors(&result, expected, 4.0);
+    }
+
+    // On the same paths, the call and the put pay the amount together
+    let config = config.with_control_variate(false);
+    let call = price(
+        &Product::cash_or_nothing(105.0, OptionType::Call, 10.0),
+        &config,
+    );
+    let put = price(
+        &Product::cash_or_nothing(105.0, OptionType::Put, 10.0),
+        &config,
+    );
+    let discounted = 10.0 * (-0.05 * TIME).exp();
+    assert!((call.price + put.price - discounted).abs() < 1e-9);
+}
+
+#[test]
+fn test_asset_or_nothing_less_cash_or_nothing_is_the_vanilla() {
+    let config = deterministic_config(20_000);
+    let result = price(&Product::asset_or_nothing(95.0, OptionType::Put), &config);
+    let expected = asset_or_nothing_price(100.0, 95.0, 0.20, 0.05, TIME, OptionType::Put);
+    assert_within_std_errors(&result, expected, 4.0);
+
+    // S 1{S > K} - K 1{S > K} = max(S - K, 0) on every path
+    let config = config.with_control_variate(false);
+    let asset = price(&Product::asset_or_nothing(95.0, OptionType::Call), &config);
+    let cash = price(
+        &Product::cash_or_nothing(95.0, OptionType::Call, 95.0),
+        &config,
+    );
+    let vanilla = price(&Product::call(95.0), &config);
+    assert!((asset.price - cash.price - vanilla.price).abs() < 1e-9);
+}
+
+#[test]
+fn test_one_touch_and_no_touch_pay_the_amount_together() {
+    let barrier = Barrier::single(0.9, BarrierDirection::Down, KnockType::In, true);
+    let config = deterministic_config(10_000).with_control_variate(false);
+    let one_touch = price(
+        &Product::one_touch(barrier.clone(), 10.0, RebateTiming::AtExpiry),
+        &config,
+    );
+    let no_touch = price(&Product::no_touch(barrier.clone(), 10.0), &config);
+    let discounted = 10.0 * (-0.05 * TIME).exp();
+    assert!(one_touch.price > 0.0 && no_touch.price > 0.0);
+    assert!((one_touch.price + no_touch.price - discounted).abs() < 1e-9);
+
+    // Paid at the hit, the amount is discounted less
+    let at_hit = price(
+        &Product::one_touch(barrier, 10.0, RebateTiming::AtHit),
+        &config,
+    );
+    assert!(at_hit.price > one_touch.price);
+}
+
+#[test]
+fn test_one_touch_at_hit_matches_the_continuous_rebate_value() {
+    let barrier = Barrier::single(90.0, BarrierDirection::Down, KnockType::Out, false)
+        .with_rebate(Rebate::new(10.0, RebateTiming::AtHit));
+    let config =
+        deterministic_config(20_000).with_barrier_correction(BarrierCorrection::BrownianBridge);
+    let result = price(
+        &Product::one_touch(barrier.clone(), 10.0, RebateTiming::AtHit),
+        &config,
+    );
+    // A put struck at zero pays nothing, so the closed form values the rebate alone
+    let expected =
+        black_scholes_barrier_price(100.0, 0.0, &barrier, 0.20, 0.05, TIME, OptionType::Put)
+            .unwrap();
+    assert_within_std_errors(&result, expected, 4.0);
+}
+
+#[test]
+fn test_negative_digital_amounts_are_rejected() {
+    let session = PricingSession::new(single_stock(), DAYS, &deterministic_config(100)).unwrap();
+    let result = session.price(&Product::cash_or_nothing(100.0, OptionType::Call, -1.0));
+    assert!(matches!(result, Err(McError::InvalidProduct(_))));
+}
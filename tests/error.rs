@@ -0,0 +1,191 @@
+use mcproton::test_utils::single_stock;
+use mcproton::{
+    price_option, price_option_with_config, price_payoff, Averaging, Barrier, BarrierDirection,
+    FixingSchedule, KnockType, McError, OptionType, Payoff, PricingResult, PricingSession, Product,
+    SimulationConfig, Underlying,
+};
+use nalgebra::DMatrix;
+
+fn two_underlyings() -> Vec<Underlying> {
+    vec![
+        Underlying::new("A".to_string(), 100.0, 0.20),
+        Underlying::new("B".to_string(), 100.0, 0.30),
+    ]
+}
+
+fn price_call(
+    underlyings: &[Underlying],
+    correlation: &DMatrix<f64>,
+    barrier: Option<&Barrier>,
+) -> Result<PricingResult, McError> {
+    let config = SimulationConfig::new(100).with_seed(1);
+    price_option_with_config(
+        underlyings,
+        correlation,
+        30,
+        100.0,
+        OptionType::Call,
+        0.05,
+        barrier,
+        &config,
+    )
+}
+
+#[test]
+fn test_invalid_correlation_matrices_are_rejected() {
+    let underlyings = two_underlyings();
+    let is_invalid_correlation = |result: Result<PricingResult, McError>| {
+        matches!(result, Err(McError::InvalidCorrelationMatrix(_)))
+    };
+
+    // Wrong dimensions
+    assert!(is_invalid_correlation(price_call(
+        &underlyings,
+        &DMatrix::identity(3, 3),
+        None
+    )));
+    // Not symmetric
+    let asymmetric = DMatrix::from_row_slice(2, 2, &[1.0, 0.5, 0.4, 1.0]);
+    assert!(is_invalid_correlation(price_call(
+        &underlyings,
+        &asymmetric,
+        None
+    )));
+    // No unit diagonal
+    let diagonal = DMatrix::from_row_slice(2, 2, &[2.0, 0.0, 0.0, 1.0]);
+    assert!(is_invalid_correlation(price_call(
+        &underlyings,
+        &diagonal,
+        None
+    )));
+    // Entries beyond [-1, 1]
+    let out_of_range = DMatrix::from_row_slice(2, 2, &[1.0, 1.5, 1.5, 1.0]);
+    assert!(is_invalid_correlation(price_call(
+        &underlyings,
+        &out_of_range,
+        None
+    )));
+    // Slightly non-positive definite, as from inconsistent pairwise estimates
+    let three = vec![
+        underlyings[0].clone(),
+        underlyings[1].clone(),
+        underlyings[0].clone(),
+    ];
+    let not_psd = DMatrix::from_row_slice(3, 3, &[1.0, 0.9, 0.9, 0.9, 1.0, -0.9, 0.9, -0.9, 1.0]);
+    let error = price_call(&three, &not_psd, None).unwrap_err();
+    assert!(
+        error.to_string().contains("not positive definite"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn test_invalid_market_data_is_rejected() {
+    let correlation = DMatrix::identity(1, 1);
+    let negative_volatility = [Underlying::new("TEST".to_string(), 100.0, -0.2)];
+    assert_eq!(
+        price_call(&negative_volatility, &correlation, None).unwrap_err(),
+        McError::InvalidVolatility {
+            underlying: "TEST".to_string(),
+            volatility: -0.2
+        }
+    );
+    let term_structure = [Underlying::new("TEST".to_string(), 100.0, 0.2)
+        .with_volatility_term_structure(vec![(10, 0.2), (20, f64::NAN)])];
+    assert!(matches!(
+        price_call(&term_structure, &correlation, None),
+        Err(McError::InvalidVolatility { .. })
+    ));
+    let zero_spot = [Underlying::new("TEST".to_string(), 0.0, 0.2)];
+    assert!(matches!(
+        price_call(&zero_spot, &correlation, None),
+        Err(McError::InvalidSpotPrice { .. })
+    ));
+    assert_eq!(
+        price_call(&[], &DMatrix::identity(0, 0), None).unwrap_err(),
+        McError::NoUnderlyings
+    );
+}
+
+#[test]
+fn test_zero_paths_are_rejected() {
+    let underlyings = [Underlying::new("TEST".to_string(), 100.0, 0.2)];
+    let result = price_option(
+        &underlyings,
+        &DMatrix::identity(1, 1),
+        30,
+        100.0,
+        OptionType::Call,
+        0.05,
+        0,
+        None,
+    );
+    assert_eq!(result.unwrap_err(), McError::InvalidPaths(0));
+}
+
+#[test]
+fn test_invalid_products_are_rejected() {
+    let underlyings = two_underlyings();
+    let correlation = DMatrix::identity(2, 2);
+    let mut barrier = Barrier::single(0.9, BarrierDirection::Down, KnockType::Out, true);
+    barrier.underlying_indices = vec![0, 2];
+    assert_eq!(
+        price_call(&underlyings, &correlation, Some(&barrier)).unwrap_err(),
+        McError::BarrierIndexOutOfRange {
+            index: 2,
+            num_underlyings: 2
+        }
+    );
+
+    let config = SimulationConfig::new(100);
+    let negative_strike = Payoff::Vanilla {
+        strike_price: -1.0,
+        option_type: OptionType::Call,
+    };
+    let result = price_payoff(
+        &underlyings,
+        &correlation,
+        30,
+        &negative_strike,
+        0.05,
+        None,
+        &config,
+    );
+    assert!(matches!(result, Err(McError::InvalidProduct(_))));
+
+    let late_fixing = Payoff::AveragePrice {
+        strike_price: 100.0,
+        option_type: OptionType::Call,
+        averaging: Averaging::Arithmetic,
+        schedule: FixingSchedule::Dates(vec![10, 40]),
+    };
+    let result = price_payoff(
+        &underlyings,
+        &correlation,
+        30,
+        &late_fixing,
+        0.05,
+        None,
+        &config,
+    );
+    assert!(matches!(result, Err(McError::InvalidSchedule(_))));
+}
+
+#[test]
+fn test_session_reports_errors_instead_of_panicking() {
+    let mut market = single_stock();
+    market.underlyings[0].volatility = f64::INFINITY;
+    assert!(PricingSession::new(market, 30, &SimulationConfig::new(100)).is_err());
+
+    let session = PricingSession::new(single_stock(), 30, &SimulationConfig::new(100)).unwrap();
+    let mut barrier = Barrier::single(0.9, BarrierDirection::Down, KnockType::Out, true);
+    barrier.underlying_indices = vec![1];
+    let product = Product::call(100.0).with_barrier(barrier);
+    assert!(matches!(
+        session.price(&product),
+        Err(McError::BarrierIndexOutOfRange { index: 1, .. })
+    ));
+    // Spots shifted to zero are not a valid market
+    assert!(session.ladder(&Product::call(100.0), &[-1.0]).is_err());
+}
@@ -0,0 +1,78 @@
+use mcproton::test_utils::{deterministic_config, single_stock};
+use mcproton::{
+    price_payoff, Barrier, BarrierDirection, ErrorTolerance, KnockType, OptionType, Payoff,
+    PricingResult, PricingWarning, SimulationConfig,
+};
+
+const DAYS: u32 = 90;
+
+fn price(payoff: &Payoff, barrier: Option<&Barrier>, config: &SimulationConfig) -> PricingResult {
+    let market = single_stock();
+    price_payoff(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        payoff,
+        market.risk_free_rate.clone(),
+        barrier,
+        config,
+    )
+    .unwrap()
+}
+
+fn call() -> Payoff {
+    Payoff::Vanilla {
+        strike_price: 100.0,
+        option_type: OptionType::Call,
+    }
+}
+
+#[test]
+fn test_simulation_stops_once_the_tolerance_is_met() {
+    let config = deterministic_config(1_000_000)
+        .with_error_tolerance(ErrorTolerance::Relative(0.01))
+        .with_batch_paths(5_000);
+    let result = price(&call(), None, &config);
+    assert!(result.num_paths < 1_000_000);
+    assert_eq!(result.num_paths % 5_000, 0);
+    assert!(result.std_error <= 0.01 * result.price);
+    assert!(result.warnings.is_empty(), "{:?}", result.warnings);
+
+    // The stopped run is the fixed run with the number of paths it used
+    let fixed = price(&call(), None, &deterministic_config(result.num_paths));
+    assert_eq!(fixed.price, result.price);
+    assert_eq!(fixed.std_error, result.std_error);
+}
+
+#[test]
+fn test_barriers_need_more_paths_for_the_same_tolerance() {
+    let config = deterministic_config(1_000_000)
+        .with_antithetic(true)
+        .with_error_tolerance(ErrorTolerance::Relative(0.02))
+        .with_batch_paths(2_000);
+    let vanilla = price(&call(), None, &config);
+    let barrier = Barrier::single(90.0, BarrierDirection::Down, KnockType::In, false);
+    let knock_in = price(&call(), Some(&barrier), &config);
+    assert!(vanilla.std_error <= 0.02 * vanilla.price);
+    assert!(knock_in.std_error <= 0.02 * knock_in.price);
+    assert!(
+        knock_in.num_paths > vanilla.num_paths,
+        "{} <= {}",
+        knock_in.num_paths,
+        vanilla.num_paths
+    );
+}
+
+#[test]
+fn test_maximum_number_of_paths_caps_the_simulation() {
+    let config = deterministic_config(20_000)
+        .with_error_tolerance(ErrorTolerance::Absolute(1e-4))
+        .with_batch_paths(5_000);
+    let result = price(&call(), None, &config);
+    assert_eq!(result.num_paths, 20_000);
+    assert!(result
+        .warnings
+        .contains(&PricingWarning::ErrorToleranceNotMet {
+            target_std_error: 1e-4
+        }));
+}
@@ -0,0 +1,107 @@
+use mcproton::closed_form::black_scholes_price;
+use mcproton::test_utils::{deterministic_config, single_stock};
+use mcproton::{
+    simulate_exposure, Barrier, BarrierDirection, ExposureProfile, KnockType, McError, OptionType,
+    Product,
+};
+
+const DAYS: u32 = 90;
+
+fn exposure(product: &Product, days: &[u32]) -> Result<ExposureProfile, McError> {
+    let market = single_stock();
+    simulate_exposure(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        product,
+        market.risk_free_rate,
+        days,
+        0.95,
+        &deterministic_config(20_000),
+    )
+}
+
+#[test]
+fn test_call_exposure_grows_towards_its_price_at_expiry() {
+    let profile = exposure(&Product::call(100.0), &[90, 30, 60, 30]).unwrap();
+    assert_eq!(profile.days, [30, 60, 90]);
+    assert_eq!(profile.num_paths, 20_000);
+
+    // The discounted intrinsic value at expiry is the payoff the price averages
+    let expected = black_scholes_price(
+        100.0,
+        100.0,
+        0.2,
+        0.05,
+        DAYS as f64 / 365.0,
+        OptionType::Call,
+    );
+    let at_expiry = profile.expected_exposure[2];
+    assert!(
+        (at_expiry - expected).abs() < 0.03 * expected,
+        "Expected exposure at expiry {} should match the price {}",
+        at_expiry,
+        expected
+    );
+    for day in 0..3 {
+        assert!(profile.potential_future_exposure[day] > profile.expected_exposure[day]);
+        if day > 0 {
+            assert!(profile.expected_exposure[day] > profile.expected_exposure[day - 1]);
+        }
+    }
+    assert_eq!(
+        profile.peak_exposure(),
+        profile.potential_future_exposure[2]
+    );
+}
+
+#[test]
+fn test_knocked_out_paths_carry_no_exposure() {
+    let barrier = Barrier::single(110.0, BarrierDirection::Up, KnockType::Out, false);
+    let product = Product::call(100.0).with_barrier(barrier);
+    let profile = exposure(&product, &[30, 60, 90]).unwrap();
+    let vanilla = exposure(&Product::call(100.0), &[30, 60, 90]).unwrap();
+    for day in 0..3 {
+        // Paths alive below the barrier are worth less than 10
+        assert!(profile.potential_future_exposure[day] < 10.0);
+        assert!(profile.expected_exposure[day] < vanilla.expected_exposure[day]);
+    }
+}
+
+#[test]
+fn test_expected_positive_exposure_weighs_the_days() {
+    let profile = ExposureProfile {
+        days: vec![10, 40],
+        expected_exposure: vec![1.0, 2.0],
+        potential_future_exposure: vec![3.0, 5.0],
+        confidence: 0.95,
+        num_paths: 100,
+    };
+    assert_eq!(profile.expected_positive_exposure(), 1.75);
+    assert_eq!(profile.peak_exposure(), 5.0);
+}
+
+#[test]
+fn test_invalid_observation_days_are_rejected() {
+    let product = Product::call(100.0);
+    for days in [&[][..], &[0, 30], &[30, DAYS + 1]] {
+        assert!(matches!(
+            exposure(&product, days),
+            Err(McError::InvalidSchedule(_))
+        ));
+    }
+    let market = single_stock();
+    assert!(matches!(
+        simulate_exposure(
+            &market.underlyings,
+            &market.correlation_matrix,
+            DAYS,
+            &product,
+            0.05,
+            &[30],
+            1.0,
+            &deterministic_config(100),
+        ),
+        Err(McError::InvalidProduct(_))
+    ));
+}
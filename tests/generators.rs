@@ -0,0 +1,99 @@
+use mcproton::generators::{
+    barrier_strategy, correlation_matrix, correlation_strategy, underlyings_strategy,
+    ArbitraryBarrier, ArbitraryCorrelation, ArbitraryUnderlying,
+};
+use mcproton::{price_option_with_config, OptionType, SimulationConfig};
+use nalgebra::DMatrix;
+use proptest::prelude::*;
+use quickcheck::{Arbitrary, QuickCheck};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+fn is_valid_correlation(matrix: &DMatrix<f64>) -> bool {
+    let size = matrix.nrows();
+    *matrix == matrix.transpose()
+        && (0..size).all(|i| matrix[(i, i)] == 1.0)
+        && matrix.iter().all(|c| c.abs() <= 1.0)
+        && matrix.clone().cholesky().is_some()
+}
+
+proptest! {
+    #[test]
+    fn test_generated_correlation_matrices_are_valid(
+        matrix in (1..=6usize).prop_flat_map(correlation_strategy),
+        seed in any::<u64>(),
+    ) {
+        prop_assert!(is_valid_correlation(&matrix));
+        let drawn = correlation_matrix(&mut StdRng::seed_from_u64(seed), matrix.nrows());
+        prop_assert!(is_valid_correlation(&drawn));
+    }
+
+    #[test]
+    fn test_generated_barriers_reference_existing_underlyings(
+        (num_underlyings, generated) in (1..=5usize).prop_flat_map(|n| (Just(n), barrier_strategy(n))),
+    ) {
+        prop_assert!(!generated.underlying_indices.is_empty());
+        prop_assert!(generated.underlying_indices.iter().all(|&i| i < num_underlyings));
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(20))]
+
+    #[test]
+    fn test_fuzzed_put_call_parity_and_strike_monotonicity(
+        (generated, correlation) in (1..=3usize)
+            .prop_flat_map(|n| (underlyings_strategy(n), correlation_strategy(n))),
+        moneyness in 0.8..1.2f64,
+        days in 10..365u32,
+        rate in 0.0..0.08f64,
+        seed in any::<u64>(),
+    ) {
+        let spot = generated[0].spot_price;
+        let strike = spot * moneyness;
+        let config = SimulationConfig::new(4_000).with_seed(seed);
+
+        let call = price_option_with_config(&generated, &correlation, days, strike, OptionType::Call, rate, None, &config).unwrap();
+        let put = price_option_with_config(&generated, &correlation, days, strike, OptionType::Put, rate, None, &config).unwrap();
+        let higher_strike_call =
+            price_option_with_config(&generated, &correlation, days, strike * 1.05, OptionType::Call, rate, None, &config).unwrap();
+
+        // Put-call parity holds up to Monte Carlo error on the forward
+        let forward_value = spot - strike * (-rate * days as f64 / 365.0).exp();
+        let parity_error = call.price - put.price - forward_value;
+        prop_assert!(
+            parity_error.abs() < 5.0 * (call.std_error + put.std_error),
+            "Put-call parity violated by {}",
+            parity_error
+        );
+
+        // With common random numbers, call prices decrease in strike path by path
+        prop_assert!(higher_strike_call.price <= call.price, "Call price must decrease in strike");
+    }
+}
+
+#[test]
+fn test_arbitrary_inputs_and_their_shrinks_are_valid() {
+    fn underlying(generated: ArbitraryUnderlying) -> bool {
+        let valid = |u: &ArbitraryUnderlying| {
+            (10.0..=500.0).contains(&u.0.spot_price) && (0.05..=0.80).contains(&u.0.volatility)
+        };
+        valid(&generated) && generated.shrink().all(|shrunk| valid(&shrunk))
+    }
+    fn correlation(generated: ArbitraryCorrelation) -> bool {
+        is_valid_correlation(&generated.0)
+            && generated
+                .shrink()
+                .all(|shrunk| is_valid_correlation(&shrunk.0))
+    }
+    fn barrier(generated: ArbitraryBarrier) -> bool {
+        let valid = |b: &ArbitraryBarrier| {
+            !b.barrier.underlying_indices.is_empty()
+                && b.barrier.underlying_indices.iter().all(|&i| i < b.num_underlyings)
+        };
+        valid(&generated) && generated.shrink().all(|shrunk| valid(&shrunk))
+    }
+    QuickCheck::new().quickcheck(underlying as fn(ArbitraryUnderlying) -> bool);
+    QuickCheck::new().quickcheck(correlation as fn(ArbitraryCorrelation) -> bool);
+    QuickCheck::new().quickcheck(barrier as fn(ArbitraryBarrier) -> bool);
+}
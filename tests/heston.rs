@@ -0,0 +1,91 @@
+use mcproton::closed_form::black_scholes_price;
+use mcproton::{
+    price_option_with_config, Heston, HestonParameters, McError, OptionType, SimulationConfig,
+    Underlying,
+};
+use nalgebra::DMatrix;
+
+fn heston(vol_of_vol: f64, correlation: f64) -> Heston {
+    Heston::new(vec![HestonParameters {
+        initial_variance: 0.04,
+        mean_reversion: 2.0,
+        long_term_variance: 0.04,
+        vol_of_vol,
+        correlation,
+    }])
+}
+
+fn price(model: Heston, strike: f64, option_type: OptionType, control_variate: bool) -> (f64, f64) {
+    let underlying = Underlying::new("TEST".to_string(), 100.0, 0.20);
+    let correlation = DMatrix::from_element(1, 1, 1.0);
+    let config = SimulationConfig::new(8_000)
+        .with_seed(42)
+        .with_antithetic(true)
+        .with_control_variate(control_variate)
+        .with_model(model);
+    let result = price_option_with_config(
+        &[underlying],
+        &correlation,
+        90,
+        strike,
+        option_type,
+        0.05,
+        None,
+        &config,
+    ).unwrap();
+    (result.price, result.std_error)
+}
+
+#[test]
+fn test_heston_without_vol_of_vol_matches_black_scholes() {
+    let (price, std_error) = price(heston(0.0, 0.0), 100.0, OptionType::Call, false);
+    let analytic = black_scholes_price(100.0, 100.0, 0.20, 0.05, 90.0 / 365.0, OptionType::Call);
+    assert!(
+        (price - analytic).abs() < 4.0 * std_error,
+        "Heston price {} with constant variance should match Black-Scholes {}",
+        price,
+        analytic
+    );
+}
+
+#[test]
+fn test_heston_negative_correlation_creates_skew() {
+    let time = 90.0 / 365.0;
+    // Low strikes gain from the fat left tail, high strikes lose
+    let (put, put_error) = price(heston(0.8, -0.8), 80.0, OptionType::Put, false);
+    let put_analytic = black_scholes_price(100.0, 80.0, 0.20, 0.05, time, OptionType::Put);
+    assert!(
+        put > put_analytic + 4.0 * put_error,
+        "OTM put {} should be worth more than Black-Scholes {} at the same variance",
+        put,
+        put_analytic
+    );
+    let (call, call_error) = price(heston(0.8, -0.8), 120.0, OptionType::Call, false);
+    let call_analytic = black_scholes_price(100.0, 120.0, 0.20, 0.05, time, OptionType::Call);
+    assert!(
+        call < call_analytic - 4.0 * call_error,
+        "OTM call {} should be worth less than Black-Scholes {} at the same variance",
+        call,
+        call_analytic
+    );
+}
+
+#[test]
+fn test_heston_ignores_black_scholes_control_variate() {
+    let without_control = price(heston(0.5, -0.5), 100.0, OptionType::Call, false);
+    let with_control = price(heston(0.5, -0.5), 100.0, OptionType::Call, true);
+    assert_eq!(without_control, with_control);
+}
+
+#[test]
+fn test_heston_requires_parameters_per_underlying() {
+    let underlyings = vec![
+        Underlying::new("A".to_string(), 100.0, 0.20),
+        Underlying::new("B".to_string(), 100.0, 0.20),
+    ];
+    let correlation = DMatrix::identity(2, 2);
+    let config = SimulationConfig::new(100).with_seed(1).with_model(heston(0.5, -0.5));
+    let error = price_option_with_config(&underlyings, &correlation, 30, 100.0, OptionType::Call, 0.05, None, &config).unwrap_err();
+    assert!(matches!(error, McError::InvalidModel(_)));
+    assert!(error.to_string().contains("Heston model needs parameters for each of the 2 underlyings"));
+}
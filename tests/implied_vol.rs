@@ -0,0 +1,129 @@
+use mcproton::closed_form::black_scholes_price;
+use mcproton::rates::{Interpolation, RateCurve};
+use mcproton::{
+    implied_volatilities, implied_volatility, ImpliedVolatility, OptionQuote, OptionType,
+};
+
+#[test]
+fn test_implied_volatility_round_trip() {
+    for &option_type in &[OptionType::Call, OptionType::Put] {
+        for &strike in &[50.0, 80.0, 100.0, 105.127, 130.0, 250.0] {
+            for &volatility in &[0.02, 0.2, 0.7, 3.0] {
+                for &time in &[0.01, 0.5, 5.0] {
+                    let price =
+                        black_scholes_price(100.0, strike, volatility, 0.05, time, option_type);
+                    let quote = OptionQuote::new(strike, time, option_type, price);
+                    // Deep out-of-the-money prices below the tolerance carry no volatility
+                    if price < 1e-10 {
+                        continue;
+                    }
+                    let implied = implied_volatility(100.0, 0.05, &quote)
+                        .volatility()
+                        .unwrap();
+                    let repriced =
+                        black_scholes_price(100.0, strike, implied, 0.05, time, option_type);
+                    assert!(
+                        (repriced - price).abs() < 1e-9 * price.max(1.0),
+                        "{:?} K={} σ={} T={}: implied {} reprices {} instead of {}",
+                        option_type,
+                        strike,
+                        volatility,
+                        time,
+                        implied,
+                        repriced,
+                        price
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_quotes_outside_of_the_no_arbitrage_bounds() {
+    let implied = |option_type, strike, price| {
+        implied_volatility(
+            100.0,
+            0.05,
+            &OptionQuote::new(strike, 1.0, option_type, price),
+        )
+    };
+    let discounted_strike = 120.0 * (-0.05f64).exp();
+    assert_eq!(
+        implied(OptionType::Put, 120.0, discounted_strike - 100.0 - 0.01),
+        ImpliedVolatility::BelowLowerBound
+    );
+    assert_eq!(
+        implied(OptionType::Call, 80.0, 10.0),
+        ImpliedVolatility::BelowLowerBound
+    );
+    assert_eq!(
+        implied(OptionType::Call, 120.0, -0.5),
+        ImpliedVolatility::BelowLowerBound
+    );
+    assert_eq!(
+        implied(OptionType::Call, 120.0, 100.0),
+        ImpliedVolatility::AboveUpperBound
+    );
+    assert_eq!(
+        implied(OptionType::Put, 120.0, 120.0),
+        ImpliedVolatility::AboveUpperBound
+    );
+    // At the lower bound, the option has no time value
+    assert_eq!(
+        implied(OptionType::Put, 120.0, discounted_strike - 100.0),
+        ImpliedVolatility::Solved(0.0)
+    );
+    assert_eq!(
+        implied(OptionType::Call, f64::NAN, 1.0),
+        ImpliedVolatility::InvalidQuote
+    );
+    assert_eq!(
+        implied_volatility(
+            100.0,
+            0.05,
+            &OptionQuote::new(100.0, 0.0, OptionType::Call, 1.0)
+        ),
+        ImpliedVolatility::InvalidQuote
+    );
+    assert_eq!(ImpliedVolatility::AboveUpperBound.volatility(), None);
+}
+
+#[test]
+fn test_chain_inversion_matches_single_quotes() {
+    let rate_curve = RateCurve::new(
+        &[(0.25, 0.03), (1.0, 0.04), (3.0, 0.05)],
+        Interpolation::Linear,
+    );
+    let mut quotes = Vec::new();
+    for maturity in 1..=20 {
+        let time = maturity as f64 * 0.125;
+        let rate = rate_curve.zero_rate(time);
+        for strike in 0..200 {
+            let strike = 50.0 + strike as f64;
+            let volatility = 0.15 + 0.1 * ((strike / 100.0).ln()).powi(2) / time.sqrt();
+            let option_type = if strike < 100.0 {
+                OptionType::Put
+            } else {
+                OptionType::Call
+            };
+            let price = black_scholes_price(100.0, strike, volatility, rate, time, option_type);
+            quotes.push(OptionQuote::new(strike, time, option_type, price));
+        }
+    }
+    // A crossed quote does not fail the chain
+    quotes[1234].price = -1.0;
+
+    let volatilities = implied_volatilities(100.0, &rate_curve, &quotes);
+    assert_eq!(volatilities.len(), quotes.len());
+    assert_eq!(volatilities[1234], ImpliedVolatility::BelowLowerBound);
+    for (quote, volatility) in quotes.iter().zip(&volatilities) {
+        assert_eq!(*volatility, implied_volatility(100.0, &rate_curve, quote));
+    }
+    let solved = volatilities
+        .iter()
+        .filter(|v| v.volatility().is_some())
+        .count();
+    assert!(solved > quotes.len() - 50);
+    assert!(implied_volatilities(100.0, 0.05, &[]).is_empty());
+}
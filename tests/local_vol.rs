@@ -0,0 +1,81 @@
+use mcproton::closed_form::black_scholes_price;
+use mcproton::{
+    price_option_with_config, LocalVolSurface, LocalVolatility, OptionType, SimulationConfig,
+    Underlying,
+};
+use nalgebra::DMatrix;
+
+fn price(surface: LocalVolSurface, strike: f64, option_type: OptionType) -> (f64, f64) {
+    let underlying = Underlying::new("TEST".to_string(), 100.0, 0.20);
+    let correlation = DMatrix::from_element(1, 1, 1.0);
+    let config = SimulationConfig::new(8_000)
+        .with_seed(42)
+        .with_antithetic(true)
+        .with_model(LocalVolatility::new(vec![surface]));
+    let result = price_option_with_config(
+        &[underlying],
+        &correlation,
+        90,
+        strike,
+        option_type,
+        0.05,
+        None,
+        &config,
+    ).unwrap();
+    (result.price, result.std_error)
+}
+
+#[test]
+fn test_surface_interpolates_bilinearly_and_extrapolates_flat() {
+    let surface = LocalVolSurface::new(
+        vec![80.0, 120.0],
+        vec![0.5, 1.0],
+        vec![vec![0.30, 0.20], vec![0.40, 0.30]],
+    );
+    assert!((surface.volatility(100.0, 0.75) - 0.30).abs() < 1e-12);
+    assert!((surface.volatility(90.0, 0.5) - 0.275).abs() < 1e-12);
+    assert!((surface.volatility(50.0, 0.1) - 0.30).abs() < 1e-12);
+    assert!((surface.volatility(200.0, 2.0) - 0.30).abs() < 1e-12);
+}
+
+#[test]
+fn test_flat_surface_matches_black_scholes() {
+    let (price, std_error) = price(LocalVolSurface::flat(0.20), 100.0, OptionType::Call);
+    let analytic = black_scholes_price(100.0, 100.0, 0.20, 0.05, 90.0 / 365.0, OptionType::Call);
+    assert!(
+        (price - analytic).abs() < 4.0 * std_error,
+        "Price {} on a flat surface should match Black-Scholes {}",
+        price,
+        analytic
+    );
+}
+
+#[test]
+fn test_downward_sloping_surface_lifts_low_strikes() {
+    // Volatility rises as the spot falls, fattening the left tail
+    let skew = LocalVolSurface::new(
+        vec![70.0, 100.0, 130.0],
+        vec![1.0],
+        vec![vec![0.45, 0.20, 0.10]],
+    );
+    let (put, std_error) = price(skew, 85.0, OptionType::Put);
+    let analytic = black_scholes_price(100.0, 85.0, 0.20, 0.05, 90.0 / 365.0, OptionType::Put);
+    assert!(
+        put > analytic + 4.0 * std_error,
+        "OTM put {} under the skewed surface should exceed Black-Scholes {} at the ATM volatility",
+        put,
+        analytic
+    );
+}
+
+#[test]
+#[should_panic(expected = "Local volatility strikes must be non-empty and strictly increasing")]
+fn test_surface_rejects_unsorted_strikes() {
+    LocalVolSurface::new(vec![120.0, 80.0], vec![1.0], vec![vec![0.2, 0.2]]);
+}
+
+#[test]
+#[should_panic(expected = "Local volatility grid must have 2 rows of 1 volatilities")]
+fn test_surface_rejects_mismatched_grid() {
+    LocalVolSurface::new(vec![100.0], vec![0.5, 1.0], vec![vec![0.2]]);
+}
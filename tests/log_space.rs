@@ -0,0 +1,90 @@
+use mcproton::{
+    price_option_with_config, price_payoff, Barrier, BarrierDirection, BarrierType, Interpolation,
+    KnockType, OptionType, Payoff, RateCurve, SimulationConfig, Underlying,
+};
+use nalgebra::DMatrix;
+
+fn assert_close(log_space: f64, price_space: f64) {
+    assert!(
+        (log_space - price_space).abs() < 1e-9 * price_space.abs().max(1.0),
+        "Log-space price {} should match the price-space price {} on the same paths",
+        log_space,
+        price_space
+    );
+}
+
+#[test]
+fn test_log_space_barrier_matches_price_space() {
+    let underlyings = vec![
+        Underlying::new("A".to_string(), 100.0, 0.25),
+        Underlying::new("B".to_string(), 50.0, 0.35),
+    ];
+    let correlation = DMatrix::from_row_slice(2, 2, &[1.0, 0.5, 0.5, 1.0]);
+    for barrier_type in [BarrierType::WorstOf, BarrierType::BestOf, BarrierType::Average, BarrierType::Median] {
+        let barrier =
+            Barrier::multi(0.8, BarrierDirection::Down, KnockType::Out, barrier_type, true, vec![0, 1]).unwrap();
+        let price = |log_space: bool| {
+            let config = SimulationConfig::new(2_000).with_seed(5).with_log_space(log_space);
+            price_option_with_config(
+                &underlyings,
+                &correlation,
+                90,
+                100.0,
+                OptionType::Call,
+                0.05,
+                Some(&barrier),
+                &config,
+            ).unwrap()
+            .price
+        };
+        assert_close(price(true), price(false));
+    }
+}
+
+#[test]
+fn test_log_space_path_dependent_payoff_matches_price_space() {
+    let underlyings = vec![Underlying::new("TEST".to_string(), 100.0, 0.30)];
+    let correlation = DMatrix::from_element(1, 1, 1.0);
+    for payoff in [
+        Payoff::FloatingLookback { option_type: OptionType::Put },
+        Payoff::FixedLookback { strike_price: 100.0, option_type: OptionType::Call },
+    ] {
+        let price = |log_space: bool| {
+            let config = SimulationConfig::new(2_000).with_seed(6).with_log_space(log_space);
+            price_payoff(&underlyings, &correlation, 60, &payoff, 0.03, None, &config).unwrap().price
+        };
+        assert_close(price(true), price(false));
+    }
+}
+
+#[test]
+fn test_intermediate_overflow_does_not_stick() {
+    // The forward rate of 1500 over the first half year is undone by -1500 over the second,
+    // so the prices overflow mid-path but end up where they would without the hump
+    let hump = RateCurve::new(&[(0.5, 1500.0), (1.0, 0.0)], Interpolation::LogLinear);
+    let underlyings = vec![Underlying::new("TEST".to_string(), 100.0, 0.20)];
+    let correlation = DMatrix::from_element(1, 1, 1.0);
+    let barrier = Barrier::single(1.0, BarrierDirection::Down, KnockType::Out, false);
+    let price = |rate_curve: RateCurve| {
+        let config = SimulationConfig::new(2_000).with_seed(7).with_log_space(true);
+        price_option_with_config(
+            &underlyings,
+            &correlation,
+            365,
+            100.0,
+            OptionType::Put,
+            rate_curve,
+            Some(&barrier),
+            &config,
+        ).unwrap()
+    };
+    let with_hump = price(hump);
+    let without_hump = price(RateCurve::flat(0.0));
+    assert_eq!(with_hump.non_finite_paths, 0);
+    assert!(
+        (with_hump.price - without_hump.price).abs() < 1e-6,
+        "Price {} with the rate hump should match {} without it",
+        with_hump.price,
+        without_hump.price
+    );
+}
@@ -0,0 +1,61 @@
+use mcproton::closed_form::black_scholes_price;
+use mcproton::{price_payoff, OptionType, Payoff, SimulationConfig, Underlying};
+use nalgebra::DMatrix;
+
+fn single_underlying() -> (Vec<Underlying>, DMatrix<f64>) {
+    (
+        vec![Underlying::new("TEST".to_string(), 100.0, 0.25)],
+        DMatrix::from_row_slice(1, 1, &[1.0]),
+    )
+}
+
+#[test]
+fn test_ladder_without_rungs_is_vanilla() {
+    let (underlyings, correlation) = single_underlying();
+    let ladder = Payoff::Ladder { strike_price: 100.0, option_type: OptionType::Call, rungs: vec![] };
+    let config = SimulationConfig::new(10_000).with_seed(1);
+    let result = price_payoff(&underlyings, &correlation, 60, &ladder, 0.05, None, &config).unwrap();
+    let analytic = black_scholes_price(100.0, 100.0, 0.25, 0.05, 60.0 / 365.0, OptionType::Call);
+    assert!(
+        (result.price - analytic).abs() < 4.0 * result.std_error,
+        "Ladder without rungs {} should price like the vanilla {}",
+        result.price,
+        analytic
+    );
+}
+
+#[test]
+fn test_call_payoffs_ordered_by_locked_in_gains() {
+    // Pathwise: vanilla <= ladder <= fixed-strike lookback on the same simulated paths
+    let (underlyings, correlation) = single_underlying();
+    let config = SimulationConfig::new(3_000).with_seed(2);
+    let price = |payoff: Payoff| price_payoff(&underlyings, &correlation, 90, &payoff, 0.05, None, &config).unwrap().price;
+    let vanilla = price(Payoff::Ladder { strike_price: 100.0, option_type: OptionType::Call, rungs: vec![] });
+    let ladder = price(Payoff::Ladder { strike_price: 100.0, option_type: OptionType::Call, rungs: vec![105.0, 110.0, 120.0] });
+    let lookback = price(Payoff::FixedLookback { strike_price: 100.0, option_type: OptionType::Call });
+    assert!(vanilla < ladder, "Ladder {} should be worth more than vanilla {}", ladder, vanilla);
+    assert!(ladder < lookback, "Lookback {} should be worth more than ladder {}", lookback, ladder);
+}
+
+#[test]
+fn test_put_ladder_locks_in_lower_rungs() {
+    let (underlyings, correlation) = single_underlying();
+    let config = SimulationConfig::new(3_000).with_seed(3);
+    let price = |payoff: Payoff| price_payoff(&underlyings, &correlation, 90, &payoff, 0.05, None, &config).unwrap().price;
+    let vanilla = price(Payoff::Ladder { strike_price: 100.0, option_type: OptionType::Put, rungs: vec![] });
+    let ladder = price(Payoff::Ladder { strike_price: 100.0, option_type: OptionType::Put, rungs: vec![95.0, 90.0] });
+    let lookback = price(Payoff::FixedLookback { strike_price: 100.0, option_type: OptionType::Put });
+    assert!(vanilla < ladder && ladder < lookback);
+}
+
+#[test]
+fn test_floating_lookback_exceeds_at_the_money_option() {
+    let (underlyings, correlation) = single_underlying();
+    let config = SimulationConfig::new(3_000).with_seed(4);
+    let price = |payoff: Payoff| price_payoff(&underlyings, &correlation, 90, &payoff, 0.05, None, &config).unwrap().price;
+    let at_the_money_call = price(Payoff::Ladder { strike_price: 100.0, option_type: OptionType::Call, rungs: vec![] });
+    let at_the_money_put = price(Payoff::Ladder { strike_price: 100.0, option_type: OptionType::Put, rungs: vec![] });
+    // S_T - min(S) >= max(S_T - S_0, 0) and max(S) - S_T >= max(S_0 - S_T, 0) path by path
+    assert!(price(Payoff::FloatingLookback { option_type: OptionType::Call }) >= at_the_money_call);
+    assert!(price(Payoff::FloatingLookback { option_type: OptionType::Put }) >= at_the_money_put);
+}
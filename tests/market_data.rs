@@ -0,0 +1,152 @@
+use mcproton::test_utils::deterministic_config;
+use mcproton::{
+    EstimationSettings, MarketSnapshot, McError, PriceHistory, PricingSession, Product,
+};
+
+/// Returns the annualized sample standard deviation of the log returns of the prices
+fn sample_volatility(prices: &[f64]) -> f64 {
+    let returns: Vec<f64> = prices
+        .windows(2)
+        .map(|pair| (pair[1] / pair[0]).ln())
+        .collect();
+    let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+    let variance =
+        returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (returns.len() - 1) as f64;
+    (variance * 252.0).sqrt()
+}
+
+/// Returns a history of three assets: a wiggling one, its square and its inverse
+fn history() -> PriceHistory {
+    let first: Vec<f64> = (0..60)
+        .map(|day| 100.0 * (1.0 + 0.01 * ((day * day) % 7) as f64))
+        .collect();
+    PriceHistory {
+        names: vec!["A".to_string(), "B".to_string(), "C".to_string()],
+        dates: (0..60).map(|day| format!("day {}", day)).collect(),
+        prices: vec![
+            first.clone(),
+            first.iter().map(|price| price * price / 100.0).collect(),
+            first.iter().map(|price| 1e4 / price).collect(),
+        ],
+    }
+}
+
+#[test]
+fn test_estimates_match_the_sample_statistics() {
+    let history = history();
+    let (underlyings, correlation) = history.estimate(&EstimationSettings::default()).unwrap();
+    let volatility = sample_volatility(&history.prices[0]);
+    assert!((underlyings[0].volatility - volatility).abs() < 1e-12);
+    assert!((underlyings[1].volatility - 2.0 * volatility).abs() < 1e-12);
+    assert!((underlyings[2].volatility - volatility).abs() < 1e-12);
+    assert_eq!(underlyings[1].name, "B");
+    assert_eq!(underlyings[0].spot_price, history.prices[0][59]);
+
+    assert!((correlation[(1, 0)] - 1.0).abs() < 1e-12);
+    assert!((correlation[(2, 0)] + 1.0).abs() < 1e-12);
+    assert_eq!(correlation[(0, 2)], correlation[(2, 0)]);
+    assert_eq!(correlation[(1, 1)], 1.0);
+
+    // Uniform exponential weights change nothing
+    let uniform = history
+        .realized_volatilities(&EstimationSettings::new().with_ewma_decay(1.0))
+        .unwrap();
+    assert!((uniform[0] - volatility).abs() < 1e-12);
+}
+
+#[test]
+fn test_lookback_and_decay_favour_recent_returns() {
+    // Calm for 40 days, then swinging by 5% a day
+    let prices: Vec<f64> = (0..60)
+        .map(|day| match day {
+            0..=39 => 100.0,
+            _ if day % 2 == 0 => 100.0,
+            _ => 105.0,
+        })
+        .collect();
+    let history = PriceHistory {
+        names: vec!["A".to_string()],
+        dates: (0..60).map(|day| day.to_string()).collect(),
+        prices: vec![prices.clone()],
+    };
+    let full = history
+        .realized_volatilities(&EstimationSettings::default())
+        .unwrap()[0];
+    let recent = history
+        .realized_volatilities(&EstimationSettings::new().with_lookback(19))
+        .unwrap()[0];
+    let weighted = history
+        .realized_volatilities(&EstimationSettings::new().with_ewma_decay(0.9))
+        .unwrap()[0];
+    assert!((recent - sample_volatility(&prices[40..])).abs() < 1e-12);
+    assert!(full < weighted && weighted < recent * 1.1);
+    assert!(full < recent);
+}
+
+#[test]
+fn test_csv_with_missing_prices_feeds_the_pricer() {
+    let path = std::env::temp_dir().join(format!("mcproton-{}-history.csv", std::process::id()));
+    let mut csv = String::from("date,\"SPX\",NDX\n");
+    for day in 0..30 {
+        let spx = 4000.0 * (1.0 + 0.01 * ((day * 3) % 5) as f64);
+        let ndx = if day == 29 || day == 10 {
+            String::new()
+        } else {
+            (15000.0 * (1.0 + 0.012 * ((day * 2) % 5) as f64)).to_string()
+        };
+        csv.push_str(&format!("2024-01-{:02},{},{}\n\n", day + 1, spx, ndx));
+    }
+    std::fs::write(&path, csv).unwrap();
+    let history = PriceHistory::from_csv(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(history.names, ["SPX", "NDX"]);
+    assert_eq!(history.dates.len(), 30);
+    assert!(history.prices[1][10].is_nan());
+    let (underlyings, correlation) = history.estimate(&EstimationSettings::default()).unwrap();
+    // The latest available price is the spot
+    assert_eq!(underlyings[1].spot_price, history.prices[1][28]);
+    assert!(underlyings.iter().all(|u| u.volatility > 0.0));
+    assert!(correlation[(0, 1)].abs() <= 1.0);
+
+    let market = MarketSnapshot::new(underlyings, correlation, 0.03);
+    let session = PricingSession::new(market, 30, &deterministic_config(500)).unwrap();
+    assert!(session.price(&Product::call(4000.0)).unwrap().price > 0.0);
+}
+
+#[test]
+fn test_invalid_histories_and_settings_are_rejected() {
+    for csv in [
+        "date\n2024-01-01\n",
+        "date,A\n2024-01-01,100,101\n",
+        "date,A\n2024-01-01,-100\n",
+        "date,A\n2024-01-01,abc\n",
+    ] {
+        assert!(
+            matches!(
+                PriceHistory::parse_csv(csv),
+                Err(McError::InvalidPriceHistory(_))
+            ),
+            "{}",
+            csv
+        );
+    }
+
+    let history = history();
+    for settings in [
+        EstimationSettings::new().with_ewma_decay(1.5),
+        EstimationSettings::new().with_lookback(1),
+        EstimationSettings::new().with_periods_per_year(0.0),
+    ] {
+        assert!(matches!(
+            history.estimate(&settings),
+            Err(McError::InvalidPriceHistory(_))
+        ));
+    }
+
+    let short = PriceHistory::parse_csv("date,A\n1,100\n2,101\n").unwrap();
+    assert!(matches!(
+        short.realized_volatilities(&EstimationSettings::default()),
+        Err(McError::InvalidPriceHistory(_))
+    ));
+}
@@ -0,0 +1,130 @@
+use mcproton::{
+    price_option_with_barriers, price_option_with_config, Barrier, BarrierCombination,
+    BarrierDirection, BarrierQuantifier, KnockType, McError, OptionType, PricingResult,
+    SimulationConfig, Underlying,
+};
+use nalgebra::DMatrix;
+
+fn price_with_barriers(barriers: &[Barrier], combination: BarrierCombination) -> PricingResult {
+    let underlyings = vec![Underlying::new("TEST".to_string(), 100.0, 0.25)];
+    let correlation = DMatrix::identity(1, 1);
+    let config = SimulationConfig::new(20_000).with_seed(17);
+    price_option_with_barriers(
+        &underlyings,
+        &correlation,
+        180,
+        100.0,
+        OptionType::Call,
+        0.05,
+        barriers,
+        combination,
+        &config,
+    )
+    .unwrap()
+}
+
+fn double_barrier(knock_type: KnockType) -> Vec<Barrier> {
+    vec![
+        Barrier::single(120.0, BarrierDirection::Up, knock_type, false),
+        Barrier::single(80.0, BarrierDirection::Down, knock_type, false),
+    ]
+}
+
+#[test]
+fn test_double_knock_out_is_cheaper_than_either_single_knock_out() {
+    let barriers = double_barrier(KnockType::Out);
+    let double = price_with_barriers(&barriers, BarrierCombination::default()).price;
+    let up = price_with_barriers(&barriers[..1], BarrierCombination::default()).price;
+    let down = price_with_barriers(&barriers[1..], BarrierCombination::default()).price;
+    assert!(
+        double < up && double < down,
+        "Double knock-out {} should be below the up-and-out {} and the down-and-out {}",
+        double,
+        up,
+        down
+    );
+}
+
+#[test]
+fn test_any_out_and_any_in_add_up_to_the_vanilla() {
+    // On the same paths, each path either hits one of the barriers or none of them
+    let knock_out = price_with_barriers(
+        &double_barrier(KnockType::Out),
+        BarrierCombination::default(),
+    );
+    let knock_in = price_with_barriers(
+        &double_barrier(KnockType::In),
+        BarrierCombination::default(),
+    );
+    let vanilla = price_with_barriers(&[], BarrierCombination::default());
+    assert!(
+        (knock_out.price + knock_in.price - vanilla.price).abs() < 1e-9,
+        "Knock-out {} and knock-in {} should add up to the vanilla {}",
+        knock_out.price,
+        knock_in.price,
+        vanilla.price
+    );
+}
+
+#[test]
+fn test_all_out_knocks_out_fewer_paths_than_any_out() {
+    let barriers = double_barrier(KnockType::Out);
+    let any = price_with_barriers(&barriers, BarrierCombination::default()).price;
+    let all = price_with_barriers(
+        &barriers,
+        BarrierCombination::new(BarrierQuantifier::All, BarrierQuantifier::Any),
+    )
+    .price;
+    assert!(all > any, "All-out {} should be above any-out {}", all, any);
+}
+
+#[test]
+fn test_single_barrier_matches_the_single_barrier_pricer() {
+    let underlyings = vec![Underlying::new("TEST".to_string(), 100.0, 0.25)];
+    let correlation = DMatrix::identity(1, 1);
+    let barrier = Barrier::single(0.9, BarrierDirection::Down, KnockType::Out, true);
+    let config = SimulationConfig::new(20_000).with_seed(5);
+    let single = price_option_with_config(
+        &underlyings,
+        &correlation,
+        180,
+        100.0,
+        OptionType::Call,
+        0.05,
+        Some(&barrier),
+        &config,
+    )
+    .unwrap();
+    let multi = price_with_barriers(&[barrier], BarrierCombination::default());
+    let tolerance = 4.0 * (single.std_error.powi(2) + multi.std_error.powi(2)).sqrt();
+    assert!(
+        (single.price - multi.price).abs() < tolerance,
+        "Single barrier {} should match the multi-barrier price {}",
+        single.price,
+        multi.price
+    );
+}
+
+#[test]
+fn test_rejects_barriers_on_unknown_underlyings() {
+    let mut barriers = double_barrier(KnockType::Out);
+    barriers[1].underlying_indices = vec![1];
+    let result = price_option_with_barriers(
+        &[Underlying::new("TEST".to_string(), 100.0, 0.25)],
+        &DMatrix::identity(1, 1),
+        180,
+        100.0,
+        OptionType::Call,
+        0.05,
+        &barriers,
+        BarrierCombination::default(),
+        &SimulationConfig::new(100),
+    );
+    assert_eq!(
+        result.unwrap_err(),
+        McError::BarrierIndexOutOfRange {
+            index: 1,
+            num_underlyings: 1
+        }
+    );
+}
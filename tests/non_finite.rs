@@ -0,0 +1,89 @@
+use mcproton::{
+    price_option_with_config, BlackScholes, McError, Model, NonFinitePolicy, OptionType,
+    PricingWarning, SimulationConfig, StepInputs, Underlying,
+};
+use nalgebra::DMatrix;
+
+/// A drift of roughly 710 per year makes about a fifth of the terminal prices overflow to infinity
+fn overflowing_call(policy: NonFinitePolicy) -> Result<mcproton::PricingResult, McError> {
+    let underlyings = vec![Underlying::new("TEST".to_string(), 1.0, 1.0)];
+    let correlation = DMatrix::from_row_slice(1, 1, &[1.0]);
+    let config = SimulationConfig::new(1_000).with_seed(13).with_non_finite_policy(policy);
+    price_option_with_config(&underlyings, &correlation, 365, 1.0, OptionType::Call, 709.5, None, &config)
+}
+
+/// Black-Scholes dynamics whose log prices turn NaN after a shock above 2 standard deviations
+#[derive(Debug)]
+struct NanOnLargeShocks;
+
+impl Model for NanOnLargeShocks {
+    fn evolve_step(&self, inputs: &StepInputs, log_prices: &mut [f64], state: &mut [f64], shocks: &[f64], sign: f64) {
+        BlackScholes.evolve_step(inputs, log_prices, state, shocks, sign);
+        if sign * shocks[0] > 2.0 {
+            log_prices[0] = f64::NAN;
+        }
+    }
+}
+
+/// A put would pay nothing on a NaN terminal price if the payoff were applied unchecked
+fn put_with_nan_paths(policy: NonFinitePolicy) -> Result<mcproton::PricingResult, McError> {
+    let underlyings = vec![Underlying::new("TEST".to_string(), 100.0, 0.20)];
+    let correlation = DMatrix::from_row_slice(1, 1, &[1.0]);
+    let config = SimulationConfig::new(1_000)
+        .with_seed(15)
+        .with_model(NanOnLargeShocks)
+        .with_non_finite_policy(policy);
+    price_option_with_config(&underlyings, &correlation, 30, 100.0, OptionType::Put, 0.05, None, &config)
+}
+
+#[test]
+fn test_finite_prices_are_unaffected() {
+    let underlyings = vec![Underlying::new("TEST".to_string(), 100.0, 0.20)];
+    let correlation = DMatrix::from_row_slice(1, 1, &[1.0]);
+    let config = SimulationConfig::new(1_000).with_seed(14).with_non_finite_policy(NonFinitePolicy::Error);
+    let result = price_option_with_config(&underlyings, &correlation, 30, 100.0, OptionType::Call, 0.05, None, &config).unwrap();
+    assert_eq!(result.non_finite_paths, 0);
+    assert!(!result.warnings.iter().any(|w| matches!(w, PricingWarning::NonFinitePaths { .. })));
+}
+
+#[test]
+fn test_non_finite_paths_dropped_and_counted() {
+    let result = overflowing_call(NonFinitePolicy::Drop).unwrap();
+    assert!(
+        result.non_finite_paths > 100 && result.non_finite_paths < 400,
+        "Unexpected number of non-finite paths: {}",
+        result.non_finite_paths
+    );
+    assert!(result.price.is_finite());
+    assert!(result.warnings.contains(&PricingWarning::NonFinitePaths { count: result.non_finite_paths }));
+}
+
+#[test]
+fn test_non_finite_paths_clamped() {
+    let result = overflowing_call(NonFinitePolicy::Clamp(1e300)).unwrap();
+    assert_eq!(result.non_finite_paths, overflowing_call(NonFinitePolicy::Drop).unwrap().non_finite_paths);
+    assert!(result.price.is_finite());
+}
+
+#[test]
+fn test_non_finite_paths_error_out() {
+    let error = overflowing_call(NonFinitePolicy::Error).unwrap_err();
+    assert!(matches!(error, McError::NonFiniteValue(value) if value.is_infinite()));
+}
+
+#[test]
+fn test_nan_paths_are_handled_by_each_policy() {
+    let dropped = put_with_nan_paths(NonFinitePolicy::Drop).unwrap();
+    assert!(dropped.non_finite_paths > 0, "NaN paths must be counted");
+    assert!(dropped.price.is_finite());
+    assert!(dropped.warnings.contains(&PricingWarning::NonFinitePaths { count: dropped.non_finite_paths }));
+
+    // Clamping pays NaN paths as zero, where dropping leaves them out of the average
+    let clamped = put_with_nan_paths(NonFinitePolicy::Clamp(1e6)).unwrap();
+    assert_eq!(clamped.non_finite_paths, dropped.non_finite_paths);
+    assert!(clamped.price.is_finite());
+    assert!(clamped.price < dropped.price);
+
+    let error = put_with_nan_paths(NonFinitePolicy::Error).unwrap_err();
+    assert!(matches!(error, McError::NonFiniteValue(value) if value.is_nan()));
+}
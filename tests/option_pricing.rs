@@ -1,4 +1,9 @@
-use mcproton::{price_option, Barrier, BarrierType, Underlying};
+use mcproton::closed_form::{black_scholes_barrier_price, black_scholes_price};
+use mcproton::test_utils::{assert_within_std_errors, deterministic_config};
+use mcproton::{
+    price_option, price_option_with_config, Barrier, BarrierCorrection, BarrierDirection,
+    BarrierType, KnockType, OptionType, PricingResult, Product, Underlying,
+};
 use nalgebra::DMatrix;
 
 fn create_correlation_matrix(size: usize) -> DMatrix<f64> {
@@ -14,7 +19,7 @@ fn test_option_pricing_call_positive_payoff() {
     // Deep in-the-money call option should have positive value
     let underlying = Underlying::new("TEST".to_string(), 100.0, 0.20);
     let correlation = create_correlation_matrix(1);
-    let price = price_option(&[underlying], &correlation, 30, 50.0, true, 0.05, 1000, None);
+    let price = price_option(&[underlying], &correlation, 30, 50.0, OptionType::Call, 0.05, 1000, None).unwrap();
     assert!(price > 0.0, "Deep ITM call should have positive value");
 }
 
@@ -23,41 +28,82 @@ fn test_option_pricing_put_positive_payoff() {
     // Deep in-the-money put option should have positive value
     let underlying = Underlying::new("TEST".to_string(), 100.0, 0.20);
     let correlation = create_correlation_matrix(1);
-    let price = price_option(&[underlying], &correlation, 30, 150.0, false, 0.05, 1000, None);
+    let price = price_option(&[underlying], &correlation, 30, 150.0, OptionType::Put, 0.05, 1000, None).unwrap();
     assert!(price > 0.0, "Deep ITM put should have positive value");
 }
 
+#[test]
+fn test_expiring_options_price_at_intrinsic_value() {
+    let underlyings = [Underlying::new("TEST".to_string(), 100.0, 0.20)];
+    let correlation = create_correlation_matrix(1);
+    let price = |strike: f64, option_type: OptionType| {
+        let result = price_option_with_config(&underlyings, &correlation, 0, strike, option_type, 0.05, None, &deterministic_config(1000)).unwrap();
+        assert_eq!(result.non_finite_paths, 0);
+        assert!(result.warnings.is_empty(), "{:?}", result.warnings);
+        result.price
+    };
+    assert!((price(90.0, OptionType::Call) - 10.0).abs() < 1e-9);
+    assert!((price(110.0, OptionType::Put) - 10.0).abs() < 1e-9);
+    assert_eq!(price(110.0, OptionType::Call), 0.0);
+    assert_eq!(price(90.0, OptionType::Put), 0.0);
+}
+
 #[test]
 fn test_option_pricing_at_the_money() {
-    // At-the-money option should have some value due to time value
+    // At-the-money options match the Black-Scholes prices within Monte Carlo error
     let underlying = Underlying::new("TEST".to_string(), 100.0, 0.20);
     let correlation = create_correlation_matrix(1);
-    let call_price = price_option(&[underlying.clone()], &correlation, 30, 100.0, true, 0.05, 1000, None);
-    let put_price = price_option(&[underlying], &correlation, 30, 100.0, false, 0.05, 1000, None);
-    assert!(call_price >= 0.0, "ATM call should have non-negative value");
-    assert!(put_price >= 0.0, "ATM put should have non-negative value");
+    let config = deterministic_config(20_000);
+    for option_type in [OptionType::Call, OptionType::Put] {
+        let result = price_option_with_config(std::slice::from_ref(&underlying), &correlation, 30, 100.0, option_type, 0.05, None, &config).unwrap();
+        let analytic = black_scholes_price(100.0, 100.0, 0.20, 0.05, 30.0 / 365.0, option_type);
+        assert_within_std_errors(&result, analytic, 4.0);
+    }
 }
 
-#[test]
-fn test_barrier_option_put_in_down() {
-    // Put option with barrier (65, in, down) - only has value if price falls below 65
+/// Prices a single-asset barrier option with the daily monitoring corrected towards
+/// continuous monitoring, together with its analytic price
+fn barrier_prices(strike_price: f64, option_type: OptionType, barrier: &Barrier) -> (PricingResult, f64) {
     let underlying = Underlying::new("TEST".to_string(), 100.0, 0.20);
     let correlation = create_correlation_matrix(1);
-    let barrier = Barrier::new(65.0, true, false, false); // in, down, absolute
-    let price = price_option(&[underlying], &correlation, 30, 80.0, false, 0.05, 10000, Some(&barrier));
-    // Should have some positive value since it's likely the barrier will be hit
-    assert!(price >= 0.0, "Barrier put option should have non-negative value");
+    let config = deterministic_config(20_000).with_barrier_correction(BarrierCorrection::BrownianBridge);
+    let result = price_option_with_config(&[underlying], &correlation, 30, strike_price, option_type, 0.05, Some(barrier), &config).unwrap();
+    let analytic = black_scholes_barrier_price(100.0, strike_price, barrier, 0.20, 0.05, 30.0 / 365.0, option_type).unwrap();
+    (result, analytic)
+}
+
+#[test]
+fn test_barrier_option_put_in_down() {
+    // Put option with barrier (90, in, down) - only has value if price falls below 90
+    let barrier = Barrier::single(90.0, BarrierDirection::Down, KnockType::In, false); // absolute
+    let (result, analytic) = barrier_prices(95.0, OptionType::Put, &barrier);
+    assert!(result.price > 0.0, "Barrier put option should have value");
+    assert_within_std_errors(&result, analytic, 4.0);
 }
 
 #[test]
 fn test_barrier_option_out_barrier() {
     // Option with "out" barrier - only has value if barrier is NOT hit
-    let underlying = Underlying::new("TEST".to_string(), 100.0, 0.20);
-    let correlation = create_correlation_matrix(1);
-    let barrier = Barrier::new(150.0, false, true, false); // out, up, absolute - barrier above current price
-    let price = price_option(&[underlying], &correlation, 30, 90.0, true, 0.05, 10000, Some(&barrier));
-    // Should have value since barrier is unlikely to be hit (it's above current price)
-    assert!(price >= 0.0, "Out barrier option should have non-negative value");
+    let barrier = Barrier::single(110.0, BarrierDirection::Up, KnockType::Out, false); // absolute - barrier above current price
+    let (result, analytic) = barrier_prices(95.0, OptionType::Call, &barrier);
+    let vanilla = black_scholes_price(100.0, 95.0, 0.20, 0.05, 30.0 / 365.0, OptionType::Call);
+    assert!(analytic < vanilla, "Knock-out should be worth less than the vanilla");
+    assert_within_std_errors(&result, analytic, 4.0);
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_deprecated_flag_constructors_match_the_enums() {
+    assert_eq!(
+        Barrier::new(0.9, true, false, true),
+        Barrier::single(0.9, BarrierDirection::Down, KnockType::In, true)
+    );
+    assert_eq!(
+        Barrier::new_multi(1.2, false, true, BarrierType::BestOf, true, vec![0, 1]).unwrap(),
+        Barrier::multi(1.2, BarrierDirection::Up, KnockType::Out, BarrierType::BestOf, true, vec![0, 1]).unwrap()
+    );
+    assert_eq!(Product::vanilla(100.0, true), Product::call(100.0));
+    assert_eq!(Product::vanilla(100.0, false), Product::put(100.0));
 }
 
 // ========== Multi-Underlying Barrier Tests ==========
@@ -70,15 +116,15 @@ fn test_barrier_2_underlyings_worstof_in_down() {
         Underlying::new("STOCK2".to_string(), 100.0, 0.25),
     ];
     let correlation = create_correlated_matrix(2, 0.5);
-    let barrier = Barrier::new_multi(
+    let barrier = Barrier::multi(
         0.85, // 85% of initial worst (relative)
-        true, // in
-        false, // down
+        BarrierDirection::Down,
+        KnockType::In,
         BarrierType::WorstOf,
         true, // relative
         vec![0, 1],
     ).unwrap();
-    let price = price_option(&underlyings, &correlation, 30, 90.0, false, 0.05, 10000, Some(&barrier));
+    let price = price_option(&underlyings, &correlation, 30, 90.0, OptionType::Put, 0.05, 10000, Some(&barrier)).unwrap();
     assert!(price >= 0.0, "2-underlying WorstOf in-down barrier should have non-negative value");
 }
 
@@ -89,15 +135,15 @@ fn test_barrier_2_underlyings_bestof_out_up() {
         Underlying::new("STOCK2".to_string(), 100.0, 0.25),
     ];
     let correlation = create_correlated_matrix(2, 0.5);
-    let barrier = Barrier::new_multi(
+    let barrier = Barrier::multi(
         1.20, // 120% of initial best (relative)
-        false, // out
-        true, // up
+        BarrierDirection::Up,
+        KnockType::Out,
         BarrierType::BestOf,
         true, // relative
         vec![0, 1],
     ).unwrap();
-    let price = price_option(&underlyings, &correlation, 30, 90.0, true, 0.05, 10000, Some(&barrier));
+    let price = price_option(&underlyings, &correlation, 30, 90.0, OptionType::Call, 0.05, 10000, Some(&barrier)).unwrap();
     assert!(price >= 0.0, "2-underlying BestOf out-up barrier should have non-negative value");
 }
 
@@ -108,15 +154,15 @@ fn test_barrier_2_underlyings_average_in_down() {
         Underlying::new("STOCK2".to_string(), 100.0, 0.25),
     ];
     let correlation = create_correlated_matrix(2, 0.5);
-    let barrier = Barrier::new_multi(
+    let barrier = Barrier::multi(
         0.90, // 90% of initial average (relative)
-        true, // in
-        false, // down
+        BarrierDirection::Down,
+        KnockType::In,
         BarrierType::Average,
         true, // relative
         vec![0, 1],
     ).unwrap();
-    let price = price_option(&underlyings, &correlation, 30, 95.0, false, 0.05, 10000, Some(&barrier));
+    let price = price_option(&underlyings, &correlation, 30, 95.0, OptionType::Put, 0.05, 10000, Some(&barrier)).unwrap();
     assert!(price >= 0.0, "2-underlying Average in-down barrier should have non-negative value");
 }
 
@@ -127,15 +173,15 @@ fn test_barrier_2_underlyings_median_out_up() {
         Underlying::new("STOCK2".to_string(), 100.0, 0.25),
     ];
     let correlation = create_correlated_matrix(2, 0.5);
-    let barrier = Barrier::new_multi(
+    let barrier = Barrier::multi(
         1.15, // 115% of initial median (relative)
-        false, // out
-        true, // up
+        BarrierDirection::Up,
+        KnockType::Out,
         BarrierType::Median,
         true, // relative
         vec![0, 1],
     ).unwrap();
-    let price = price_option(&underlyings, &correlation, 30, 90.0, true, 0.05, 10000, Some(&barrier));
+    let price = price_option(&underlyings, &correlation, 30, 90.0, OptionType::Call, 0.05, 10000, Some(&barrier)).unwrap();
     assert!(price >= 0.0, "2-underlying Median out-up barrier should have non-negative value");
 }
 
@@ -148,15 +194,15 @@ fn test_barrier_3_underlyings_worstof_in_down() {
         Underlying::new("STOCK3".to_string(), 100.0, 0.30),
     ];
     let correlation = create_correlated_matrix(3, 0.4);
-    let barrier = Barrier::new_multi(
+    let barrier = Barrier::multi(
         0.80, // 80% of initial worst (relative)
-        true, // in
-        false, // down
+        BarrierDirection::Down,
+        KnockType::In,
         BarrierType::WorstOf,
         true, // relative
         vec![0, 1, 2],
     ).unwrap();
-    let price = price_option(&underlyings, &correlation, 30, 90.0, false, 0.05, 10000, Some(&barrier));
+    let price = price_option(&underlyings, &correlation, 30, 90.0, OptionType::Put, 0.05, 10000, Some(&barrier)).unwrap();
     assert!(price >= 0.0, "3-underlying WorstOf in-down barrier should have non-negative value");
 }
 
@@ -168,15 +214,15 @@ fn test_barrier_3_underlyings_bestof_out_up() {
         Underlying::new("STOCK3".to_string(), 100.0, 0.30),
     ];
     let correlation = create_correlated_matrix(3, 0.4);
-    let barrier = Barrier::new_multi(
+    let barrier = Barrier::multi(
         1.25, // 125% of initial best (relative)
-        false, // out
-        true, // up
+        BarrierDirection::Up,
+        KnockType::Out,
         BarrierType::BestOf,
         true, // relative
         vec![0, 1, 2],
     ).unwrap();
-    let price = price_option(&underlyings, &correlation, 30, 90.0, true, 0.05, 10000, Some(&barrier));
+    let price = price_option(&underlyings, &correlation, 30, 90.0, OptionType::Call, 0.05, 10000, Some(&barrier)).unwrap();
     assert!(price >= 0.0, "3-underlying BestOf out-up barrier should have non-negative value");
 }
 
@@ -188,15 +234,15 @@ fn test_barrier_3_underlyings_average_in_down() {
         Underlying::new("STOCK3".to_string(), 100.0, 0.30),
     ];
     let correlation = create_correlated_matrix(3, 0.4);
-    let barrier = Barrier::new_multi(
+    let barrier = Barrier::multi(
         0.88, // 88% of initial average (relative)
-        true, // in
-        false, // down
+        BarrierDirection::Down,
+        KnockType::In,
         BarrierType::Average,
         true, // relative
         vec![0, 1, 2],
     ).unwrap();
-    let price = price_option(&underlyings, &correlation, 30, 95.0, false, 0.05, 10000, Some(&barrier));
+    let price = price_option(&underlyings, &correlation, 30, 95.0, OptionType::Put, 0.05, 10000, Some(&barrier)).unwrap();
     assert!(price >= 0.0, "3-underlying Average in-down barrier should have non-negative value");
 }
 
@@ -208,15 +254,15 @@ fn test_barrier_3_underlyings_median_out_up() {
         Underlying::new("STOCK3".to_string(), 100.0, 0.30),
     ];
     let correlation = create_correlated_matrix(3, 0.4);
-    let barrier = Barrier::new_multi(
+    let barrier = Barrier::multi(
         1.18, // 118% of initial median (relative)
-        false, // out
-        true, // up
+        BarrierDirection::Up,
+        KnockType::Out,
         BarrierType::Median,
         true, // relative
         vec![0, 1, 2],
     ).unwrap();
-    let price = price_option(&underlyings, &correlation, 30, 90.0, true, 0.05, 10000, Some(&barrier));
+    let price = price_option(&underlyings, &correlation, 30, 90.0, OptionType::Call, 0.05, 10000, Some(&barrier)).unwrap();
     assert!(price >= 0.0, "3-underlying Median out-up barrier should have non-negative value");
 }
 
@@ -230,15 +276,15 @@ fn test_barrier_4_underlyings_worstof_in_down() {
         Underlying::new("STOCK4".to_string(), 100.0, 0.22),
     ];
     let correlation = create_correlated_matrix(4, 0.3);
-    let barrier = Barrier::new_multi(
+    let barrier = Barrier::multi(
         0.75, // 75% of initial worst (relative)
-        true, // in
-        false, // down
+        BarrierDirection::Down,
+        KnockType::In,
         BarrierType::WorstOf,
         true, // relative
         vec![0, 1, 2, 3],
     ).unwrap();
-    let price = price_option(&underlyings, &correlation, 30, 90.0, false, 0.05, 10000, Some(&barrier));
+    let price = price_option(&underlyings, &correlation, 30, 90.0, OptionType::Put, 0.05, 10000, Some(&barrier)).unwrap();
     assert!(price >= 0.0, "4-underlying WorstOf in-down barrier should have non-negative value");
 }
 
@@ -251,15 +297,15 @@ fn test_barrier_4_underlyings_bestof_out_up() {
         Underlying::new("STOCK4".to_string(), 100.0, 0.22),
     ];
     let correlation = create_correlated_matrix(4, 0.3);
-    let barrier = Barrier::new_multi(
+    let barrier = Barrier::multi(
         1.30, // 130% of initial best (relative)
-        false, // out
-        true, // up
+        BarrierDirection::Up,
+        KnockType::Out,
         BarrierType::BestOf,
         true, // relative
         vec![0, 1, 2, 3],
     ).unwrap();
-    let price = price_option(&underlyings, &correlation, 30, 90.0, true, 0.05, 10000, Some(&barrier));
+    let price = price_option(&underlyings, &correlation, 30, 90.0, OptionType::Call, 0.05, 10000, Some(&barrier)).unwrap();
     assert!(price >= 0.0, "4-underlying BestOf out-up barrier should have non-negative value");
 }
 
@@ -272,15 +318,15 @@ fn test_barrier_4_underlyings_average_in_down() {
         Underlying::new("STOCK4".to_string(), 100.0, 0.22),
     ];
     let correlation = create_correlated_matrix(4, 0.3);
-    let barrier = Barrier::new_multi(
+    let barrier = Barrier::multi(
         0.85, // 85% of initial average (relative)
-        true, // in
-        false, // down
+        BarrierDirection::Down,
+        KnockType::In,
         BarrierType::Average,
         true, // relative
         vec![0, 1, 2, 3],
     ).unwrap();
-    let price = price_option(&underlyings, &correlation, 30, 95.0, false, 0.05, 10000, Some(&barrier));
+    let price = price_option(&underlyings, &correlation, 30, 95.0, OptionType::Put, 0.05, 10000, Some(&barrier)).unwrap();
     assert!(price >= 0.0, "4-underlying Average in-down barrier should have non-negative value");
 }
 
@@ -293,25 +339,25 @@ fn test_barrier_4_underlyings_median_out_up() {
         Underlying::new("STOCK4".to_string(), 100.0, 0.22),
     ];
     let correlation = create_correlated_matrix(4, 0.3);
-    let barrier = Barrier::new_multi(
+    let barrier = Barrier::multi(
         1.20, // 120% of initial median (relative)
-        false, // out
-        true, // up
+        BarrierDirection::Up,
+        KnockType::Out,
         BarrierType::Median,
         true, // relative
         vec![0, 1, 2, 3],
     ).unwrap();
-    let price = price_option(&underlyings, &correlation, 30, 90.0, true, 0.05, 10000, Some(&barrier));
+    let price = price_option(&underlyings, &correlation, 30, 90.0, OptionType::Call, 0.05, 10000, Some(&barrier)).unwrap();
     assert!(price >= 0.0, "4-underlying Median out-up barrier should have non-negative value");
 }
 
 // Test barrier validation - absolute barrier with multiple underlyings should fail
 #[test]
 fn test_barrier_multi_underlying_absolute_should_fail() {
-    let barrier_result = Barrier::new_multi(
+    let barrier_result = Barrier::multi(
         85.0, // absolute level
-        true, // in
-        false, // down
+        BarrierDirection::Down,
+        KnockType::In,
         BarrierType::WorstOf,
         false, // absolute (not relative)
         vec![0, 1], // multiple underlyings
@@ -0,0 +1,74 @@
+use mcproton::closed_form::black_scholes_price;
+use mcproton::{
+    price_barrier_package, price_option_with_config, Barrier, BarrierDirection, BarrierLeg,
+    Dividend, KnockType, OptionType, SimulationConfig, Underlying, VanillaLeg,
+};
+use nalgebra::DMatrix;
+
+const DAYS: u32 = 90;
+
+fn legs(barrier_quantity: f64) -> (VanillaLeg, BarrierLeg) {
+    let vanilla = VanillaLeg { quantity: 1.0, strike_price: 100.0, option_type: OptionType::Call };
+    let barrier_leg = BarrierLeg {
+        quantity: barrier_quantity,
+        strike_price: 100.0,
+        option_type: OptionType::Call,
+        barrier: Barrier::single(0.9, BarrierDirection::Down, KnockType::In, true),
+    };
+    (vanilla, barrier_leg)
+}
+
+#[test]
+fn test_hybrid_package_cuts_noise_of_the_barrier_leg() {
+    // Long call, short down-and-in call: a down-and-out call by in-out parity
+    let underlyings = vec![Underlying::new("TEST".to_string(), 100.0, 0.30)];
+    let correlation = DMatrix::from_element(1, 1, 1.0);
+    let config = SimulationConfig::new(4_000).with_seed(17);
+    let (vanilla, barrier_leg) = legs(-1.0);
+    let package = price_barrier_package(&underlyings, &correlation, DAYS, &vanilla, &barrier_leg, 0.05, &config).unwrap();
+    assert!(package.is_vanilla_analytic);
+    let analytic = black_scholes_price(100.0, 100.0, 0.30, 0.05, DAYS as f64 / 365.0, OptionType::Call);
+    assert!((package.vanilla_price - analytic).abs() < 1e-12);
+
+    let knock_out = Barrier::single(0.9, BarrierDirection::Down, KnockType::Out, true);
+    let direct =
+        price_option_with_config(&underlyings, &correlation, DAYS, 100.0, OptionType::Call, 0.05, Some(&knock_out), &config).unwrap();
+    let tolerance = 4.0 * (package.pricing.std_error.powi(2) + direct.std_error.powi(2)).sqrt();
+    assert!(
+        (package.pricing.price - direct.price).abs() < tolerance,
+        "Package {} should match the down-and-out call {}",
+        package.pricing.price,
+        direct.price
+    );
+    assert!(
+        package.pricing.std_error < direct.std_error,
+        "Hybrid standard error {} should be below the plain Monte Carlo error {}",
+        package.pricing.std_error,
+        direct.std_error
+    );
+}
+
+#[test]
+fn test_package_without_barrier_leg_is_analytic() {
+    let underlyings = vec![Underlying::new("TEST".to_string(), 100.0, 0.30)];
+    let correlation = DMatrix::from_element(1, 1, 1.0);
+    let config = SimulationConfig::new(500).with_seed(18);
+    let (vanilla, barrier_leg) = legs(0.0);
+    let package = price_barrier_package(&underlyings, &correlation, DAYS, &vanilla, &barrier_leg, 0.05, &config).unwrap();
+    assert_eq!(package.pricing.price, package.vanilla_price);
+    assert_eq!(package.pricing.std_error, 0.0);
+}
+
+#[test]
+fn test_package_falls_back_to_joint_simulation() {
+    // Cash dividends leave the vanilla leg without an analytic price
+    let underlyings = vec![Underlying::new("TEST".to_string(), 100.0, 0.30)
+        .with_dividends(vec![Dividend::Cash { day: 30, amount: 2.0 }])];
+    let correlation = DMatrix::from_element(1, 1, 1.0);
+    let config = SimulationConfig::new(2_000).with_seed(19);
+    let (vanilla, barrier_leg) = legs(-1.0);
+    let package = price_barrier_package(&underlyings, &correlation, DAYS, &vanilla, &barrier_leg, 0.05, &config).unwrap();
+    assert!(!package.is_vanilla_analytic);
+    assert!((package.pricing.price - (package.vanilla_price - package.barrier_price)).abs() < 1e-9);
+    assert!(package.pricing.std_error > 0.0);
+}
@@ -0,0 +1,93 @@
+use mcproton::test_utils::{deterministic_config, single_stock};
+use mcproton::{
+    estimate_paths_needed, price_payoff, Barrier, BarrierDirection, ErrorTolerance, KnockType,
+    PricingResult, Product,
+};
+
+const DAYS: u32 = 90;
+
+#[test]
+fn test_estimated_paths_reach_the_tolerance() {
+    let market = single_stock();
+    let product = Product::call(100.0);
+    let tolerance = ErrorTolerance::Relative(0.005);
+    let estimate = estimate_paths_needed(
+        &market,
+        DAYS,
+        &product,
+        tolerance,
+        &deterministic_config(2_000),
+    )
+    .unwrap();
+    assert_eq!(estimate.pilot.num_paths, 2_000);
+    let paths_needed = estimate.paths_needed.unwrap();
+    assert!(paths_needed > 2_000);
+    assert!((estimate.std_error_at(paths_needed) / estimate.target_std_error - 1.0).abs() < 0.01);
+
+    let result = price_payoff(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        &product.payoff,
+        market.risk_free_rate.clone(),
+        None,
+        &deterministic_config(paths_needed).with_seed(7),
+    )
+    .unwrap();
+    let target = tolerance.target_std_error(result.price);
+    assert!(
+        (result.std_error / target - 1.0).abs() < 0.1,
+        "Standard error {} with {} paths for a target of {}",
+        result.std_error,
+        paths_needed,
+        target
+    );
+}
+
+#[test]
+fn test_estimates_follow_the_variance_of_the_payoff() {
+    let market = single_stock();
+    let tolerance = ErrorTolerance::Absolute(0.01);
+    let config = deterministic_config(4_000).with_antithetic(true);
+    let estimate = |product: &Product| {
+        estimate_paths_needed(&market, DAYS, product, tolerance, &config)
+            .unwrap()
+            .paths_needed
+            .unwrap()
+    };
+    let vanilla = estimate(&Product::call(100.0));
+    let knock_out = estimate(&Product::call(100.0).with_barrier(Barrier::single(
+        110.0,
+        BarrierDirection::Up,
+        KnockType::Out,
+        false,
+    )));
+    // The knock-out caps the payoff, so it varies less; antithetic paths come in pairs
+    assert_eq!(vanilla % 2, 0);
+    assert_eq!(knock_out % 2, 0);
+    assert!(knock_out < vanilla, "{} against {}", knock_out, vanilla);
+}
+
+#[test]
+fn test_paths_needed_without_a_reachable_tolerance() {
+    let mut result = PricingResult {
+        price: 0.0,
+        std_error: 0.0,
+        num_paths: 1_000,
+        bounds: None,
+        non_finite_paths: 0,
+        warnings: Vec::new(),
+        audit: Vec::new(),
+    };
+    assert_eq!(result.paths_needed(ErrorTolerance::Relative(0.01)), Some(1));
+    result.std_error = 0.1;
+    assert_eq!(result.paths_needed(ErrorTolerance::Relative(0.01)), None);
+    assert_eq!(
+        result.paths_needed(ErrorTolerance::Absolute(0.05)),
+        Some(4_000)
+    );
+    assert_eq!(
+        result.paths_needed(ErrorTolerance::Absolute(0.2)),
+        Some(250)
+    );
+}
@@ -0,0 +1,130 @@
+use std::sync::Arc;
+
+use mcproton::test_utils::{deterministic_config, single_stock, two_asset_basket};
+use mcproton::{
+    price_payoff, Averaging, Barrier, BarrierDirection, FixingSchedule, KnockType, OptionType,
+    PathObserver, PathRecorder, Payoff,
+};
+
+const DAYS: u32 = 30;
+
+fn asian_call() -> Payoff {
+    Payoff::AveragePrice {
+        strike_price: 100.0,
+        option_type: OptionType::Call,
+        averaging: Averaging::Arithmetic,
+        schedule: FixingSchedule::Dates(vec![10, 20, 30]),
+    }
+}
+
+#[test]
+fn test_recorder_records_a_subsample_without_changing_the_price() {
+    let market = two_asset_basket();
+    let config = deterministic_config(1_000).with_antithetic(true);
+    let recorder = Arc::new(PathRecorder::new(10).with_max_paths(20));
+    let observed = config.clone().with_path_observer(recorder.clone());
+    let price = |config| {
+        price_payoff(
+            &market.underlyings,
+            &market.correlation_matrix,
+            DAYS,
+            &asian_call(),
+            market.risk_free_rate.clone(),
+            None,
+            config,
+        )
+        .unwrap()
+        .price
+    };
+    assert_eq!(price(&observed), price(&config));
+
+    let paths = recorder.take_paths();
+    let indices: Vec<u64> = paths.iter().map(|path| path.path_index).collect();
+    assert_eq!(indices, (0..20).map(|i| i * 10).collect::<Vec<_>>());
+    for path in &paths {
+        assert_eq!(path.prices.len(), DAYS as usize + 1);
+        assert_eq!(path.prices[0], vec![100.0, 100.0]);
+        assert_eq!(path.prices.last(), Some(&path.final_prices));
+        assert_eq!(path.barrier_hit_probability, 0.0);
+    }
+    assert!(recorder.paths().is_empty());
+}
+
+#[test]
+fn test_recorded_payoffs_and_barrier_hits_match_the_price() {
+    let market = single_stock();
+    let config = deterministic_config(2_000);
+    let recorder = Arc::new(PathRecorder::new(1).with_steps(false));
+    let barrier = Barrier::single(90.0, BarrierDirection::Down, KnockType::Out, false);
+    let result = price_payoff(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        &Payoff::Vanilla {
+            strike_price: 100.0,
+            option_type: OptionType::Put,
+        },
+        market.risk_free_rate.clone(),
+        Some(&barrier),
+        &config.with_path_observer(recorder.clone()),
+    )
+    .unwrap();
+
+    let paths = recorder.paths();
+    assert_eq!(paths.len(), 2_000);
+    let discount_factor = (-0.05 * DAYS as f64 / 365.0).exp();
+    let mean_payoff = paths.iter().map(|path| path.payoff).sum::<f64>() / paths.len() as f64;
+    assert!((mean_payoff * discount_factor - result.price).abs() < 1e-9);
+
+    // Knocked-out paths pay nothing, and some paths knock out
+    let knocked_out = paths
+        .iter()
+        .filter(|path| path.barrier_hit_probability == 1.0)
+        .inspect(|path| assert_eq!(path.payoff, 0.0))
+        .count();
+    assert!(knocked_out > 0 && knocked_out < paths.len());
+    assert!(paths.iter().all(|path| path.prices.is_empty()));
+}
+
+/// Observer accumulating the moments of the daily log returns of two underlyings
+#[derive(Debug, Default)]
+struct ReturnMoments {
+    sums: std::sync::Mutex<(Vec<f64>, [f64; 5])>,
+}
+
+impl PathObserver for ReturnMoments {
+    fn on_step(&self, _path_index: u64, step: usize, prices: &[f64]) {
+        let mut sums = self.sums.lock().unwrap();
+        let (previous, moments) = &mut *sums;
+        if step > 0 {
+            let x = (prices[0] / previous[0]).ln();
+            let y = (prices[1] / previous[1]).ln();
+            for (moment, value) in moments.iter_mut().zip([x, y, x * x, y * y, x * y]) {
+                *moment += value;
+            }
+        }
+        *previous = prices.to_vec();
+    }
+}
+
+#[test]
+fn test_observed_returns_realize_the_correlation() {
+    let market = two_asset_basket();
+    let moments = Arc::new(ReturnMoments::default());
+    price_payoff(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        &asian_call(),
+        market.risk_free_rate.clone(),
+        None,
+        &deterministic_config(2_000).with_path_observer(moments.clone()),
+    )
+    .unwrap();
+
+    let [x, y, xx, yy, xy] = moments.sums.lock().unwrap().1;
+    let n = 2_000.0 * DAYS as f64;
+    let covariance = xy / n - x / n * y / n;
+    let correlation = covariance / ((xx / n - (x / n).powi(2)) * (yy / n - (y / n).powi(2))).sqrt();
+    assert!((correlation - 0.5).abs() < 0.02, "{}", correlation);
+}
@@ -0,0 +1,246 @@
+use mcproton::closed_form::norm_cdf;
+use mcproton::test_utils::{
+    assert_within_std_errors, deterministic_config, single_stock, two_asset_basket,
+};
+use mcproton::{
+    price_path_payoff, price_payoff, price_terminal_payoff, OptionType, PathPayoff, PathStep,
+    Payoff,
+};
+use nalgebra::{DMatrix, DVector};
+
+const DAYS: u32 = 90;
+
+/// Variance swap paying the annualized realized variance of the daily log returns
+struct RealizedVariance;
+
+impl PathPayoff for RealizedVariance {
+    /// Sum of the squared log returns and the time elapsed
+    type State = (f64, f64);
+
+    fn initial_state(&self, _initial_prices: &[f64]) -> (f64, f64) {
+        (0.0, 0.0)
+    }
+
+    fn on_step(&self, state: &mut (f64, f64), step: &PathStep) {
+        state.0 += (step.prices[0] / step.previous_prices[0]).ln().powi(2);
+        state.1 = step.time;
+    }
+
+    fn evaluate(&self, &(sum_squares, time): &(f64, f64), _final_prices: &[f64]) -> f64 {
+        sum_squares / time
+    }
+}
+
+/// Range accrual paying the fraction of the days on which the stock closes above a level
+struct RangeAccrual {
+    level: f64,
+}
+
+impl PathPayoff for RangeAccrual {
+    /// Number of days in the range and number of days
+    type State = (u32, u32);
+
+    fn initial_state(&self, _initial_prices: &[f64]) -> (u32, u32) {
+        (0, 0)
+    }
+
+    fn on_step(&self, state: &mut (u32, u32), step: &PathStep) {
+        state.0 += u32::from(step.prices[0] > self.level);
+        state.1 += 1;
+    }
+
+    fn evaluate(&self, &(in_range, days): &(u32, u32), _final_prices: &[f64]) -> f64 {
+        f64::from(in_range) / f64::from(days)
+    }
+}
+
+/// Fixed-strike lookback call on the running maximum, which the engine also tracks
+struct LookbackCall {
+    strike_price: f64,
+}
+
+impl PathPayoff for LookbackCall {
+    type State = f64;
+
+    fn initial_state(&self, initial_prices: &[f64]) -> f64 {
+        initial_prices[0]
+    }
+
+    fn on_step(&self, running_max: &mut f64, step: &PathStep) {
+        *running_max = running_max.max(step.prices[0]);
+    }
+
+    fn evaluate(&self, &running_max: &f64, _final_prices: &[f64]) -> f64 {
+        (running_max - self.strike_price).max(0.0)
+    }
+}
+
+/// Call on the best performance of all underlyings, which only depends on the prices at expiry
+struct BestOfCall {
+    strike: f64,
+}
+
+impl PathPayoff for BestOfCall {
+    /// Initial prices of the underlyings
+    type State = Vec<f64>;
+
+    fn initial_state(&self, initial_prices: &[f64]) -> Vec<f64> {
+        initial_prices.to_vec()
+    }
+
+    fn on_step(&self, _state: &mut Vec<f64>, _step: &PathStep) {
+        panic!("terminal payoffs are not stepped");
+    }
+
+    fn evaluate(&self, initial_prices: &Vec<f64>, final_prices: &[f64]) -> f64 {
+        let best = final_prices
+            .iter()
+            .zip(initial_prices)
+            .map(|(final_price, initial_price)| final_price / initial_price)
+            .fold(f64::NEG_INFINITY, f64::max);
+        (best - self.strike).max(0.0)
+    }
+
+    fn is_terminal(&self) -> bool {
+        true
+    }
+}
+
+fn discount_factor() -> f64 {
+    (-0.05 * DAYS as f64 / 365.0).exp()
+}
+
+#[test]
+fn test_realized_variance_prices_the_implied_variance() {
+    let market = single_stock();
+    let result = price_path_payoff(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        &RealizedVariance,
+        market.risk_free_rate.clone(),
+        &deterministic_config(10_000),
+    )
+    .unwrap();
+    assert_within_std_errors(&result, 0.20 * 0.20 * discount_factor(), 4.0);
+}
+
+#[test]
+fn test_range_accrual_counts_the_days_in_range() {
+    let market = single_stock();
+    let result = price_path_payoff(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        &RangeAccrual { level: 100.0 },
+        market.risk_free_rate.clone(),
+        &deterministic_config(10_000).with_antithetic(true),
+    )
+    .unwrap();
+
+    // Average probability of closing above the spot over the days
+    let drift = 0.05 - 0.5 * 0.20 * 0.20;
+    let probability_sum: f64 = (1..=DAYS)
+        .map(|day| {
+            let time = day as f64 / 365.0;
+            norm_cdf(drift * time.sqrt() / 0.20)
+        })
+        .sum();
+    let expected = probability_sum / DAYS as f64 * discount_factor();
+    assert_within_std_errors(&result, expected, 4.0);
+    assert_eq!(result.num_paths, 10_000);
+}
+
+#[test]
+fn test_state_matches_the_engine_running_maximum() {
+    let market = single_stock();
+    let config = deterministic_config(2_000).with_control_variate(false);
+    let result = price_path_payoff(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        &LookbackCall {
+            strike_price: 100.0,
+        },
+        market.risk_free_rate.clone(),
+        &config,
+    )
+    .unwrap();
+    let expected = price_payoff(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        &Payoff::FixedLookback {
+            strike_price: 100.0,
+            option_type: OptionType::Call,
+        },
+        market.risk_free_rate.clone(),
+        None,
+        &config,
+    )
+    .unwrap();
+    // Equal up to rounding of the running maximum
+    assert!((result.price - expected.price).abs() < 1e-12 * expected.price);
+    assert!((result.std_error - expected.std_error).abs() < 1e-9 * expected.std_error);
+}
+
+#[test]
+fn test_terminal_payoff_is_simulated_in_a_single_step() {
+    let market = two_asset_basket();
+    let config = deterministic_config(4_000).with_antithetic(true);
+    let result = price_path_payoff(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        &BestOfCall { strike: 1.0 },
+        market.risk_free_rate.clone(),
+        &config,
+    )
+    .unwrap();
+    let best_of_call = |terminal_prices: &DMatrix<f64>| {
+        DVector::from_iterator(
+            terminal_prices.nrows(),
+            terminal_prices
+                .row_iter()
+                .map(|row| (row.max() / 100.0 - 1.0).max(0.0)),
+        )
+    };
+    let expected = price_terminal_payoff(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        &best_of_call,
+        market.risk_free_rate.clone(),
+        &config,
+    )
+    .unwrap();
+    // Same single-step paths as the terminal pricer
+    assert!((result.price - expected.price).abs() < 1e-12);
+    assert_eq!(result.num_paths, 4_000);
+}
+
+#[test]
+fn test_terminal_payoff_uses_moment_matching() {
+    let market = two_asset_basket();
+    let config = deterministic_config(1_000).with_moment_matching(true);
+    let result = price_path_payoff(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        &BestOfCall { strike: 0.0 },
+        market.risk_free_rate.clone(),
+        &config,
+    )
+    .unwrap();
+    let unmatched = price_path_payoff(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        &BestOfCall { strike: 0.0 },
+        market.risk_free_rate.clone(),
+        &config.clone().with_moment_matching(false),
+    )
+    .unwrap();
+    assert!(result.price != unmatched.price);
+    assert!((result.price - unmatched.price).abs() < 4.0 * unmatched.std_error);
+}
@@ -0,0 +1,96 @@
+use mcproton::test_utils::{deterministic_config, single_stock};
+use mcproton::{
+    price_payoff, McError, OptionType, Payoff, PricingResult, PricingSession, Product,
+    SimulationConfig,
+};
+
+const DAYS: u32 = 90;
+
+fn price(config: &SimulationConfig) -> Result<PricingResult, McError> {
+    let market = single_stock();
+    price_payoff(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        &Payoff::Vanilla {
+            strike_price: 100.0,
+            option_type: OptionType::Call,
+        },
+        market.risk_free_rate,
+        None,
+        config,
+    )
+}
+
+#[test]
+fn test_uniform_weights_do_not_change_the_price() {
+    let unweighted = price(&deterministic_config(2_000).with_antithetic(true)).unwrap();
+    let weighted = price(
+        &deterministic_config(2_000)
+            .with_antithetic(true)
+            .with_path_weights(vec![3.0; 2_000]),
+    )
+    .unwrap();
+    assert!((weighted.price - unweighted.price).abs() < 1e-9);
+    assert!((weighted.std_error - unweighted.std_error).abs() < 1e-9);
+}
+
+#[test]
+fn test_paths_of_weight_zero_do_not_count() {
+    let first_half = price(&deterministic_config(1_000)).unwrap();
+    let weights: Vec<f64> = (0..2_000)
+        .map(|path| if path < 1_000 { 0.5 } else { 0.0 })
+        .collect();
+    let weighted = price(&deterministic_config(2_000).with_path_weights(weights)).unwrap();
+    assert!((weighted.price - first_half.price).abs() < 1e-9);
+    assert!((weighted.std_error - first_half.std_error).abs() < 1e-9);
+    assert_eq!(weighted.num_paths, 2_000);
+}
+
+#[test]
+fn test_session_prices_and_percentiles_use_the_weights() {
+    // All weight on the first path: every statistic is the value of that path
+    let mut weights = vec![0.0; 1_000];
+    weights[0] = 1.0;
+    let config = deterministic_config(1_000).with_path_weights(weights);
+    let session = PricingSession::new(single_stock(), DAYS, &config).unwrap();
+    let product = Product::call(100.0);
+    let result = session.price(&product).unwrap();
+    let percentiles = session.percentiles(&product, &[0.0, 0.5, 1.0]).unwrap();
+    for percentile in percentiles {
+        assert!((percentile - result.price).abs() < 1e-12);
+    }
+    assert_eq!(result.num_paths, 1_000);
+}
+
+#[test]
+fn test_unweighted_percentiles_are_ordered() {
+    let session = PricingSession::new(single_stock(), DAYS, &deterministic_config(4_000)).unwrap();
+    let product = Product::call(100.0);
+    let percentiles = session
+        .percentiles(&product, &[0.05, 0.25, 0.5, 0.75, 0.95])
+        .unwrap();
+    assert!(percentiles.windows(2).all(|pair| pair[0] <= pair[1]));
+    // An at-the-money call expires worthless on about half of the paths
+    assert_eq!(percentiles[0], 0.0);
+    assert!(percentiles[4] > session.price(&product).unwrap().price);
+}
+
+#[test]
+fn test_invalid_weights_are_rejected() {
+    for weights in [vec![1.0; 999], vec![-1.0; 1_000], vec![0.0; 1_000]] {
+        let result = price(&deterministic_config(1_000).with_path_weights(weights));
+        assert!(
+            matches!(result, Err(McError::InvalidPathWeights(_))),
+            "{:?}",
+            result
+        );
+    }
+    let config = deterministic_config(1_000)
+        .with_antithetic(true)
+        .with_path_weights(vec![1.0; 999]);
+    assert!(matches!(
+        PricingSession::new(single_stock(), DAYS, &config),
+        Err(McError::InvalidPathWeights(_))
+    ));
+}
@@ -0,0 +1,326 @@
+use mcproton::closed_form::black_scholes_price;
+use mcproton::test_utils::{
+    assert_within_std_errors, deterministic_config, single_stock, two_asset_basket,
+};
+use mcproton::{
+    price_payoff, price_portfolio, price_portfolio_with_maturities, price_term_structure,
+    Averaging, Barrier, BarrierCorrection, BarrierDirection, BarrierMonitoring, FixingSchedule,
+    KnockType, MarketSnapshot, McError, OptionType, Payoff, Product, Rebate, RebateTiming,
+    SimulationConfig,
+};
+
+const DAYS: u32 = 60;
+
+fn asian_call(schedule: FixingSchedule) -> Product {
+    Product::new(Payoff::AveragePrice {
+        strike_price: 100.0,
+        option_type: OptionType::Call,
+        averaging: Averaging::Arithmetic,
+        schedule,
+    })
+}
+
+/// Prices each product on its own with `price_payoff`
+fn separate_prices(
+    market: &MarketSnapshot,
+    products: &[Product],
+    config: &SimulationConfig,
+) -> Vec<f64> {
+    products
+        .iter()
+        .map(|product| {
+            price_payoff(
+                &market.underlyings,
+                &market.correlation_matrix,
+                DAYS,
+                &product.payoff,
+                market.risk_free_rate.clone(),
+                product.barrier.as_ref(),
+                config,
+            )
+            .unwrap()
+            .price
+        })
+        .collect()
+}
+
+#[test]
+fn test_products_price_as_if_priced_separately() {
+    let market = two_asset_basket();
+    let mut knock_out = Barrier::single(0.9, BarrierDirection::Down, KnockType::Out, true);
+    knock_out.rebate = Some(Rebate::new(3.0, RebateTiming::AtHit));
+    let mut knock_in = Barrier::single(110.0, BarrierDirection::Up, KnockType::In, false);
+    knock_in.monitoring = BarrierMonitoring::Dates(vec![20, 40, 60]);
+    let products = [
+        asian_call(FixingSchedule::Dates(vec![20, 40, 60])),
+        asian_call(FixingSchedule::Dates(vec![30, 60])),
+        Product::put(100.0).with_barrier(knock_out),
+        Product::call(95.0).with_barrier(knock_in),
+    ];
+    let config = deterministic_config(2_000)
+        .with_antithetic(true)
+        .with_barrier_correction(BarrierCorrection::BrownianBridge);
+
+    let portfolio = price_portfolio(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        &products,
+        market.risk_free_rate.clone(),
+        &config,
+    )
+    .unwrap();
+    let prices: Vec<f64> = portfolio
+        .results
+        .iter()
+        .map(|result| result.as_ref().unwrap().price)
+        .collect();
+    assert_eq!(prices, separate_prices(&market, &products, &config));
+    assert!(portfolio
+        .results
+        .iter()
+        .map(|result| result.as_ref().unwrap())
+        .all(|result| result.num_paths == 2_000 && result.std_error > 0.0));
+}
+
+#[test]
+fn test_payoff_correlation_of_calls_and_puts() {
+    let market = single_stock();
+    let products = [
+        Product::call(100.0),
+        Product::call(105.0),
+        Product::put(100.0),
+    ];
+    let config = deterministic_config(20_000);
+    let portfolio = price_portfolio(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        &products,
+        market.risk_free_rate.clone(),
+        &config,
+    )
+    .unwrap();
+    let prices: Vec<f64> = portfolio
+        .results
+        .iter()
+        .map(|result| result.as_ref().unwrap().price)
+        .collect();
+    assert_eq!(prices, separate_prices(&market, &products, &config));
+
+    let correlation = &portfolio.payoff_correlation;
+    assert_eq!(correlation.shape(), (3, 3));
+    for i in 0..3 {
+        assert_eq!(correlation[(i, i)], 1.0);
+        for j in 0..3 {
+            assert!((correlation[(i, j)] - correlation[(j, i)]).abs() < 1e-12);
+        }
+    }
+    assert!(correlation[(0, 1)] > 0.9, "{}", correlation);
+    assert!(correlation[(0, 2)] < -0.3, "{}", correlation);
+}
+
+#[test]
+fn test_aggregate_nets_offsetting_positions() {
+    let market = single_stock();
+    let products = [Product::call(100.0), Product::call(105.0), Product::put(1.0)];
+    let portfolio = price_portfolio(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        &products,
+        market.risk_free_rate.clone(),
+        &deterministic_config(20_000),
+    )
+    .unwrap();
+    let results: Vec<_> = portfolio.results.iter().map(|r| r.as_ref().unwrap()).collect();
+
+    // A call spread: the short call hedges most of the error of the long one
+    let spread = portfolio.aggregate(&[1.0, -1.0, 0.0]).unwrap();
+    assert!((spread.price - (results[0].price - results[1].price)).abs() < 1e-12);
+    assert!(spread.std_error < 0.5 * (results[0].std_error + results[1].std_error));
+    // A single position carries the scaled error of its product
+    let flat = portfolio.aggregate(&[2.0, 0.0, 0.0]).unwrap();
+    assert!((flat.std_error - 2.0 * results[0].std_error).abs() < 1e-12);
+    // Rejected products only fail positions held in them
+    let mut rejected = portfolio.clone();
+    rejected.results[1] = Err(McError::InvalidProduct("rejected".to_string()));
+    assert!(rejected.aggregate(&[1.0, 0.0, 3.0]).is_ok());
+    assert!(matches!(
+        rejected.aggregate(&[1.0, -1.0, 0.0]),
+        Err(McError::InvalidProduct(_))
+    ));
+}
+
+#[test]
+fn test_payoffs_without_variance_are_uncorrelated() {
+    let market = single_stock();
+    // A put struck far below the spot never pays over the horizon
+    let products = [Product::call(100.0), Product::put(1.0)];
+    let portfolio = price_portfolio(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        &products,
+        market.risk_free_rate.clone(),
+        &deterministic_config(1_000),
+    )
+    .unwrap();
+    assert_eq!(portfolio.results[1].as_ref().unwrap().price, 0.0);
+    assert_eq!(portfolio.payoff_correlation[(0, 1)], 0.0);
+    assert_eq!(portfolio.payoff_correlation[(1, 1)], 1.0);
+}
+
+#[test]
+fn test_invalid_products_are_rejected_without_aborting_the_run() {
+    let market = two_asset_basket();
+    let products = [
+        Product::call(100.0),
+        Product::put(-5.0),
+        Product::call(100.0).with_barrier(Barrier::single(
+            90.0,
+            BarrierDirection::Down,
+            KnockType::Out,
+            false,
+        )),
+        asian_call(FixingSchedule::Dates(vec![30, 90])),
+        Product::call(100.0).with_barrier(Barrier {
+            underlying_indices: vec![2],
+            ..Barrier::single(90.0, BarrierDirection::Down, KnockType::Out, false)
+        }),
+    ];
+    let config = deterministic_config(2_000);
+    let portfolio = price_portfolio(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        &products,
+        market.risk_free_rate.clone(),
+        &config,
+    )
+    .unwrap();
+
+    assert!(matches!(
+        portfolio.results[1],
+        Err(McError::InvalidProduct(_))
+    ));
+    assert!(matches!(
+        portfolio.results[3],
+        Err(McError::InvalidSchedule(_))
+    ));
+    assert!(matches!(
+        portfolio.results[4],
+        Err(McError::BarrierIndexOutOfRange { .. })
+    ));
+    // The valid products price as if the rejected ones were not in the portfolio
+    assert!(portfolio.results[0].is_ok());
+    assert_eq!(
+        portfolio.results[2].as_ref().unwrap().price,
+        separate_prices(&market, &products[2..3], &config)[0]
+    );
+    let correlation = &portfolio.payoff_correlation;
+    assert_eq!(correlation.row(1).sum(), 1.0);
+    assert!(correlation[(0, 2)] > 0.5);
+}
+
+#[test]
+fn test_products_expire_at_their_own_maturities() {
+    let market = single_stock();
+    let knock_out = Barrier::single(90.0, BarrierDirection::Down, KnockType::Out, false);
+    let products = [
+        Product::call(100.0),
+        Product::put(105.0),
+        Product::call(100.0),
+        asian_call(FixingSchedule::Dates(vec![10, 20, 30])),
+        Product::call(100.0).with_barrier(knock_out),
+    ];
+    let maturities = [30, 90, 180, 30, 45];
+    let config = deterministic_config(40_000).with_antithetic(true);
+    let portfolio = price_portfolio_with_maturities(
+        &market.underlyings,
+        &market.correlation_matrix,
+        &products,
+        &maturities,
+        market.risk_free_rate.clone(),
+        &config,
+    )
+    .unwrap();
+    let results: Vec<_> = portfolio
+        .results
+        .iter()
+        .map(|result| result.as_ref().unwrap())
+        .collect();
+
+    // Vanillas are discounted from their own expiry
+    for (index, option_type, strike) in [
+        (0, OptionType::Call, 100.0),
+        (1, OptionType::Put, 105.0),
+        (2, OptionType::Call, 100.0),
+    ] {
+        let time = maturities[index] as f64 / 365.0;
+        let expected = black_scholes_price(100.0, strike, 0.2, 0.05, time, option_type);
+        assert_within_std_errors(results[index], expected, 4.0);
+    }
+    assert!(results[0].price < results[2].price);
+
+    // Path-dependent products fix and monitor up to their own expiry
+    for index in [3, 4] {
+        let separate = price_payoff(
+            &market.underlyings,
+            &market.correlation_matrix,
+            maturities[index],
+            &products[index].payoff,
+            market.risk_free_rate.clone(),
+            products[index].barrier.as_ref(),
+            &config,
+        )
+        .unwrap();
+        let std_error = results[index].std_error.hypot(separate.std_error);
+        assert!(
+            (results[index].price - separate.price).abs() < 4.0 * std_error,
+            "Product {} prices {} in the portfolio and {} on its own",
+            index,
+            results[index].price,
+            separate.price
+        );
+    }
+}
+
+#[test]
+fn test_term_structure_prices_each_maturity_on_shared_paths() {
+    let market = single_stock();
+    let maturities = [30, 90, 180, 365];
+    let config = deterministic_config(40_000).with_antithetic(true);
+    let prices = price_term_structure(
+        &market.underlyings,
+        &market.correlation_matrix,
+        &Product::call(100.0),
+        &maturities,
+        market.risk_free_rate.clone(),
+        &config,
+    )
+    .unwrap();
+
+    assert_eq!(prices.len(), maturities.len());
+    for (result, &maturity) in prices.iter().zip(&maturities) {
+        let time = maturity as f64 / 365.0;
+        let expected = black_scholes_price(100.0, 100.0, 0.2, 0.05, time, OptionType::Call);
+        assert_within_std_errors(result, expected, 4.0);
+    }
+    // On common random numbers, the call prices increase with the maturity
+    assert!(prices.windows(2).all(|pair| pair[0].price < pair[1].price));
+}
+
+#[test]
+fn test_term_structure_rejects_fixings_beyond_a_maturity() {
+    let market = single_stock();
+    let result = price_term_structure(
+        &market.underlyings,
+        &market.correlation_matrix,
+        &asian_call(FixingSchedule::Dates(vec![10, 60])),
+        &[30, 90],
+        market.risk_free_rate.clone(),
+        &deterministic_config(1_000),
+    );
+    assert!(result.is_err());
+}
@@ -0,0 +1,117 @@
+use mcproton::test_utils::{deterministic_config, three_asset_basket};
+use mcproton::{
+    Barrier, BarrierDirection, BarrierType, BatchTrade, KnockType, PortfolioState, Product,
+    RateCurve,
+};
+
+fn trades() -> Vec<BatchTrade> {
+    let barrier = Barrier::multi(
+        0.8,
+        BarrierDirection::Down,
+        KnockType::Out,
+        BarrierType::WorstOf,
+        true,
+        vec![0, 1],
+    )
+    .unwrap();
+    vec![
+        BatchTrade::new("call".to_string(), Product::call(100.0), 60),
+        BatchTrade::new(
+            "worst-of put".to_string(),
+            Product::put(100.0).with_barrier(barrier),
+            60,
+        ),
+    ]
+}
+
+#[test]
+fn test_unchanged_inputs_are_taken_from_the_cache() {
+    let market = three_asset_basket();
+    let mut state = PortfolioState::new(&deterministic_config(2_000));
+    let first = state.update(&market, &trades());
+    assert_eq!(first.repriced, vec!["call", "worst-of put"]);
+
+    let second = state.update(&market, &trades());
+    assert!(second.repriced.is_empty());
+    for (first, second) in first.results.iter().zip(&second.results) {
+        assert_eq!(
+            first.as_ref().unwrap().price,
+            second.as_ref().unwrap().price
+        );
+    }
+}
+
+#[test]
+fn test_only_trades_on_changed_market_inputs_are_repriced() {
+    let mut market = three_asset_basket();
+    let mut state = PortfolioState::new(&deterministic_config(2_000));
+    state.update(&market, &trades());
+
+    // No trade depends on the third underlying
+    market.underlyings[2].spot_price = 90.0;
+    market.correlation_matrix[(0, 2)] = 0.3;
+    market.correlation_matrix[(2, 0)] = 0.3;
+    assert!(state.update(&market, &trades()).repriced.is_empty());
+
+    // Only the worst-of depends on the second underlying and its correlation
+    market.underlyings[1].volatility = 0.3;
+    assert_eq!(
+        state.update(&market, &trades()).repriced,
+        vec!["worst-of put"]
+    );
+    market.correlation_matrix[(0, 1)] = 0.6;
+    market.correlation_matrix[(1, 0)] = 0.6;
+    assert_eq!(
+        state.update(&market, &trades()).repriced,
+        vec!["worst-of put"]
+    );
+
+    // All trades depend on the first underlying and on the rates
+    market.underlyings[0].spot_price = 101.0;
+    assert_eq!(state.update(&market, &trades()).repriced.len(), 2);
+    market.risk_free_rate = RateCurve::flat(0.04);
+    let update = state.update(&market, &trades());
+    assert_eq!(update.repriced.len(), 2);
+    assert_eq!(state.market(), Some(&market));
+}
+
+#[test]
+fn test_changed_and_new_trades_are_repriced() {
+    let market = three_asset_basket();
+    let mut state = PortfolioState::new(&deterministic_config(2_000));
+    state.update(&market, &trades());
+
+    let mut trades = trades();
+    trades[0].maturity_days = 90;
+    trades.push(BatchTrade::new("put".to_string(), Product::put(95.0), 30));
+    let update = state.update(&market, &trades);
+    assert_eq!(update.repriced, vec!["call", "put"]);
+    assert_eq!(update.results.len(), 3);
+
+    // A removed trade is dropped from the cache and priced again when it returns
+    state.update(&market, &trades[..2]);
+    assert_eq!(state.update(&market, &trades).repriced, vec!["put"]);
+}
+
+#[test]
+fn test_changed_quantities_rescale_the_cached_prices() {
+    let market = three_asset_basket();
+    let mut state = PortfolioState::new(&deterministic_config(2_000));
+    let first = state.update(&market, &trades());
+
+    let mut trades = trades();
+    trades[1].quantity = -4.0;
+    let second = state.update(&market, &trades);
+    assert!(second.repriced.is_empty());
+    let (long, short) = (
+        first.results[1].as_ref().unwrap(),
+        second.results[1].as_ref().unwrap(),
+    );
+    assert_eq!(short.price, -4.0 * long.price);
+    assert_eq!(short.std_error, 4.0 * long.std_error);
+    let total = second.total().unwrap();
+    assert_eq!(
+        total.price,
+        second.results[0].as_ref().unwrap().price + short.price
+    );
+}
@@ -0,0 +1,142 @@
+use mcproton::test_utils::{single_stock, three_asset_basket};
+use mcproton::{
+    Averaging, Barrier, BarrierDirection, BarrierMonitoring, BarrierType, FixingSchedule,
+    KnockType, McError, OptionType, Payoff, PricingSession, Product, Rebate, RebateTiming,
+    SimulationConfig,
+};
+
+/// Returns a down-and-out barrier of the given type; barriers on several underlyings can only
+/// be created with relative levels, so absolute ones are set afterwards
+fn barrier(
+    level: f64,
+    barrier_type: BarrierType,
+    relative: bool,
+    underlying_indices: Vec<usize>,
+) -> Barrier {
+    Barrier {
+        barrier_level: level,
+        barrier_type,
+        relative,
+        underlying_indices,
+        ..Barrier::single(level, BarrierDirection::Down, KnockType::Out, relative)
+    }
+}
+
+fn worst_of(level: f64, relative: bool, indices: Vec<usize>) -> Barrier {
+    barrier(level, BarrierType::WorstOf, relative, indices)
+}
+
+#[test]
+fn test_equivalent_representations_share_an_id() {
+    let spots = [100.0, 80.0, 120.0];
+    let relative = Product::call(100.0).with_barrier(worst_of(0.75, true, vec![2, 1, 1]));
+    let absolute = Product::call(100.0).with_barrier(worst_of(60.0, false, vec![1, 2]));
+    assert_ne!(relative, absolute);
+    assert_eq!(
+        relative.canonicalize(&spots).unwrap(),
+        absolute.canonicalize(&spots).unwrap()
+    );
+    assert_eq!(relative.id(&spots).unwrap(), absolute.id(&spots).unwrap());
+    assert_eq!(relative.id(&spots).unwrap().to_string().len(), 16);
+
+    // An average over a repeated underlying weighs it more and is not de-duplicated
+    let average = |indices| {
+        Product::call(100.0).with_barrier(barrier(72.0, BarrierType::Average, false, indices))
+    };
+    assert_ne!(
+        average(vec![1, 1, 2]).id(&spots).unwrap(),
+        average(vec![1, 2]).id(&spots).unwrap()
+    );
+    assert_eq!(
+        average(vec![2, 1, 1]).id(&spots).unwrap(),
+        average(vec![1, 1, 2]).id(&spots).unwrap()
+    );
+    // All barrier types agree on a single underlying
+    assert_eq!(
+        average(vec![1, 1]).id(&spots).unwrap(),
+        Product::call(100.0)
+            .with_barrier(worst_of(72.0, false, vec![1]))
+            .id(&spots)
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_schedules_rebates_and_signed_zeros_are_normalized() {
+    let spots = [100.0];
+    let asian = |days| {
+        Product::new(Payoff::AveragePrice {
+            strike_price: -0.0,
+            option_type: OptionType::Call,
+            averaging: Averaging::Arithmetic,
+            schedule: FixingSchedule::Dates(days),
+        })
+    };
+    let monitored = |days, rebate| Barrier {
+        monitoring: BarrierMonitoring::Dates(days),
+        rebate,
+        ..Barrier::single(90.0, BarrierDirection::Down, KnockType::Out, false)
+    };
+    let first = asian(vec![30, 10, 20, 10]).with_barrier(monitored(
+        vec![20, 5],
+        Some(Rebate::new(0.0, RebateTiming::AtHit)),
+    ));
+    let second = Product::new(Payoff::AveragePrice {
+        strike_price: 0.0,
+        option_type: OptionType::Call,
+        averaging: Averaging::Arithmetic,
+        schedule: FixingSchedule::Dates(vec![10, 20, 30]),
+    })
+    .with_barrier(monitored(vec![5, 20], None));
+    assert_eq!(first.id(&spots).unwrap(), second.id(&spots).unwrap());
+
+    let rebated = asian(vec![10, 20, 30]).with_barrier(monitored(
+        vec![5, 20],
+        Some(Rebate::new(1.0, RebateTiming::AtHit)),
+    ));
+    assert_ne!(first.id(&spots).unwrap(), rebated.id(&spots).unwrap());
+    assert_ne!(
+        Product::call(100.0).id(&spots).unwrap(),
+        Product::call(101.0).id(&spots).unwrap()
+    );
+}
+
+#[test]
+fn test_barriers_on_missing_underlyings_are_rejected() {
+    let product = Product::call(100.0).with_barrier(worst_of(0.9, true, vec![0, 3]));
+    assert!(matches!(
+        product.id(&[100.0, 100.0, 100.0]),
+        Err(McError::BarrierIndexOutOfRange { index: 3, .. })
+    ));
+}
+
+#[test]
+fn test_session_prices_equivalent_products_once() {
+    let session = PricingSession::new(single_stock(), 60, &SimulationConfig::new(1_000)).unwrap();
+    let relative = Product::call(100.0).with_barrier(Barrier::single(
+        0.9,
+        BarrierDirection::Down,
+        KnockType::Out,
+        true,
+    ));
+    let absolute = Product::call(100.0).with_barrier(Barrier::single(
+        90.0,
+        BarrierDirection::Down,
+        KnockType::Out,
+        false,
+    ));
+    let first = session.price(&relative).unwrap();
+    let second = session.price(&absolute).unwrap();
+    assert_eq!(first.price, second.price);
+    assert_eq!(session.num_memoized_results(), 1);
+
+    let basket =
+        PricingSession::new(three_asset_basket(), 60, &SimulationConfig::new(1_000)).unwrap();
+    basket
+        .price(&Product::call(100.0).with_barrier(worst_of(0.8, true, vec![0, 2])))
+        .unwrap();
+    basket
+        .price(&Product::call(100.0).with_barrier(worst_of(0.8, true, vec![2, 0, 0])))
+        .unwrap();
+    assert_eq!(basket.num_memoized_results(), 1);
+}
@@ -0,0 +1,63 @@
+#![cfg(feature = "python")]
+
+use std::ffi::CStr;
+
+use mcproton::python::python_module;
+use mcproton::McError;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use pyo3::wrap_pymodule;
+
+/// Runs Python code with the extension module imported as `mcproton`
+fn run(code: &CStr) -> PyResult<()> {
+    Python::initialize();
+    Python::attach(|py| {
+        let globals = PyDict::new(py);
+        globals.set_item("mcproton", wrap_pymodule!(python_module)(py))?;
+        py.run(code, Some(&globals), None)
+    })
+}
+
+#[test]
+fn test_products_are_built_from_python() {
+    run(c"
+underlying = mcproton.Underlying('ACME', 100.0, 0.2, dividend_yield=0.01)
+underlying.spot_price = 105.0
+assert (underlying.spot_price, underlying.dividend_yield) == (105.0, 0.01)
+
+barrier = mcproton.Barrier.single(90.0, 'down', 'out')
+assert barrier.underlying_indices == [0]
+product = mcproton.Product.call(100.0).with_barrier(barrier)
+assert 'Down' in repr(product)
+
+multi = mcproton.Barrier.multi(0.8, 'down', 'in', 'worst_of', [0, 1])
+assert multi.barrier_level == 0.8
+
+config = mcproton.SimulationConfig(1000, seed=7, antithetic=True)
+assert (config.num_paths, config.seed) == (1000, 7)
+")
+    .unwrap();
+}
+
+#[test]
+fn test_invalid_inputs_raise_value_errors() {
+    run(c"
+for build in [
+    lambda: mcproton.Barrier.single(90.0, 'sideways', 'out'),
+    lambda: mcproton.Barrier.multi(90.0, 'down', 'out', 'worst_of', [0, 1], relative=False),
+    lambda: mcproton.Product.cash_or_nothing(100.0, 'straddle', 1.0),
+]:
+    try:
+        build()
+    except ValueError:
+        pass
+    else:
+        raise AssertionError('expected a ValueError')
+")
+    .unwrap();
+
+    Python::attach(|py| {
+        assert!(PyErr::from(McError::NoUnderlyings).is_instance_of::<PyValueError>(py));
+    });
+}
@@ -0,0 +1,214 @@
+use mcproton::closed_form::black_scholes_price;
+use mcproton::test_utils::{single_stock, two_asset_basket};
+use mcproton::{
+    price_payoff, Barrier, BarrierDirection, ErrorTolerance, KnockType, McError, OptionType,
+    Payoff, PricingResult, PricingWarning, Sampling, SimulationConfig,
+};
+
+const DAYS: u32 = 90;
+
+fn call() -> Payoff {
+    Payoff::Vanilla {
+        strike_price: 105.0,
+        option_type: OptionType::Call,
+    }
+}
+
+fn price(config: &SimulationConfig) -> PricingResult {
+    let market = single_stock();
+    price_payoff(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        &call(),
+        market.risk_free_rate.clone(),
+        None,
+        config,
+    )
+    .unwrap()
+}
+
+fn analytic() -> f64 {
+    let market = single_stock();
+    let underlying = &market.underlyings[0];
+    black_scholes_price(
+        underlying.spot_price,
+        105.0,
+        underlying.volatility,
+        0.05,
+        DAYS as f64 / 365.0,
+        OptionType::Call,
+    )
+}
+
+#[test]
+fn test_replicated_quasi_random_samplings_match_black_scholes() {
+    let pseudo_random = price(&SimulationConfig::new(16_384).with_seed(1));
+    for sampling in [Sampling::Sobol, Sampling::ScrambledSobol, Sampling::Halton] {
+        let config = SimulationConfig::new(16_384)
+            .with_seed(1)
+            .with_sampling(sampling)
+            .with_qmc_replications(16);
+        let result = price(&config);
+        assert_eq!(result.num_paths, 16_384);
+        assert!(
+            (result.price - analytic()).abs() < 4.0 * result.std_error.max(1e-4),
+            "{:?} price {} should match Black-Scholes {}",
+            sampling,
+            result.price,
+            analytic()
+        );
+        assert!(
+            result.std_error < 0.2 * pseudo_random.std_error,
+            "{:?} error {} should be far below the pseudo-random error {}",
+            sampling,
+            result.std_error,
+            pseudo_random.std_error
+        );
+    }
+}
+
+#[test]
+fn test_quasi_random_samplings_price_path_dependent_payoffs() {
+    // Daily monitoring needs more dimensions than the sequences have
+    let market = two_asset_basket();
+    let barrier = Barrier::single(0.9, BarrierDirection::Down, KnockType::Out, true);
+    let price = |config: &SimulationConfig| {
+        price_payoff(
+            &market.underlyings,
+            &market.correlation_matrix,
+            60,
+            &call(),
+            market.risk_free_rate.clone(),
+            Some(&barrier),
+            config,
+        )
+        .unwrap()
+    };
+    let reference = price(&SimulationConfig::new(40_000).with_seed(5));
+    for sampling in [Sampling::ScrambledSobol, Sampling::Halton] {
+        let config = SimulationConfig::new(8_192)
+            .with_seed(6)
+            .with_sampling(sampling)
+            .with_qmc_replications(16);
+        let result = price(&config);
+        let tolerance = 4.0 * (result.std_error.powi(2) + reference.std_error.powi(2)).sqrt();
+        assert!(
+            (result.price - reference.price).abs() < tolerance,
+            "{:?} price {} should match the Monte Carlo price {}",
+            sampling,
+            result.price,
+            reference.price
+        );
+    }
+}
+
+#[test]
+fn test_replicated_error_estimates_cover_the_price() {
+    // The spread of the runs is an unbiased error estimate, so the price is within three
+    // standard errors for almost all seeds
+    for sampling in [Sampling::ScrambledSobol, Sampling::Halton] {
+        let covered = (0..20)
+            .filter(|&seed| {
+                let config = SimulationConfig::new(2_048)
+                    .with_seed(seed)
+                    .with_sampling(sampling)
+                    .with_qmc_replications(8);
+                let result = price(&config);
+                (result.price - analytic()).abs() < 3.0 * result.std_error
+            })
+            .count();
+        assert!(covered >= 17, "{:?} covered {} of 20", sampling, covered);
+    }
+}
+
+#[test]
+fn test_error_tolerance_stops_adding_replications() {
+    let config = SimulationConfig::new(64 * 1_024)
+        .with_seed(3)
+        .with_sampling(Sampling::ScrambledSobol)
+        .with_qmc_replications(64)
+        .with_error_tolerance(ErrorTolerance::Relative(0.01));
+    let result = price(&config);
+    assert!(result.num_paths >= 2 * 1_024 && result.num_paths < 64 * 1_024);
+    assert_eq!(result.num_paths % 1_024, 0);
+    assert!(result.std_error <= 0.01 * result.price);
+    assert!(!result
+        .warnings
+        .iter()
+        .any(|warning| matches!(warning, PricingWarning::ErrorToleranceNotMet { .. })));
+}
+
+#[test]
+fn test_paths_beyond_the_sequence_dimensions_report_the_padding() {
+    let market = two_asset_basket();
+    let padding = |sampling, days, payoff: &Payoff| {
+        let config = SimulationConfig::new(1_000)
+            .with_seed(5)
+            .with_sampling(sampling);
+        price_payoff(
+            &market.underlyings,
+            &market.correlation_matrix,
+            days,
+            payoff,
+            market.risk_free_rate.clone(),
+            None,
+            &config,
+        )
+        .unwrap()
+        .warnings
+        .into_iter()
+        .find(|warning| matches!(warning, PricingWarning::QuasiRandomPadding { .. }))
+    };
+    let lookback = Payoff::FloatingLookback {
+        option_type: OptionType::Call,
+    };
+
+    // Whole daily steps of both underlyings are quasi-random: 10 of 30 for Sobol, 16 for
+    // Halton
+    assert_eq!(
+        padding(Sampling::Sobol, 30, &lookback),
+        Some(PricingWarning::QuasiRandomPadding {
+            quasi_random_dimensions: 20,
+            total_dimensions: 60,
+        })
+    );
+    assert_eq!(
+        padding(Sampling::Halton, 30, &lookback),
+        Some(PricingWarning::QuasiRandomPadding {
+            quasi_random_dimensions: 32,
+            total_dimensions: 60,
+        })
+    );
+    // Paths within the dimensions of the sequence, and pseudo-random paths, are not padded
+    assert_eq!(padding(Sampling::Sobol, 10, &lookback), None);
+    assert_eq!(padding(Sampling::Sobol, DAYS, &call()), None);
+    assert_eq!(padding(Sampling::PseudoRandom, 30, &lookback), None);
+}
+
+#[test]
+fn test_sobol_sampling_rejects_more_paths_than_points() {
+    let market = single_stock();
+    let num_paths = 1 << 32;
+    for sampling in [Sampling::Sobol, Sampling::ScrambledSobol] {
+        for replications in [1, 16] {
+            let config = SimulationConfig::new(num_paths)
+                .with_sampling(sampling)
+                .with_qmc_replications(replications);
+            let result = price_payoff(
+                &market.underlyings,
+                &market.correlation_matrix,
+                DAYS,
+                &call(),
+                market.risk_free_rate.clone(),
+                None,
+                &config,
+            );
+            assert!(
+                matches!(result, Err(McError::InvalidPaths(paths)) if paths == num_paths),
+                "{:?}",
+                result
+            );
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use mcproton::closed_form::{black_scholes_price, inverse_norm_cdf, norm_cdf};
+use mcproton::{
+    price_payoff, quick_quote, Barrier, BarrierDirection, KnockType, OptionType, Payoff, Sampling,
+    SimulationConfig, Underlying,
+};
+use nalgebra::DMatrix;
+
+#[test]
+fn test_inverse_norm_cdf_inverts_norm_cdf() {
+    for &x in &[-6.0, -2.5, -1.0, -0.1, 0.0, 0.3, 1.7, 4.0] {
+        assert!((inverse_norm_cdf(norm_cdf(x)) - x).abs() < 1e-7, "Round trip failed at {}", x);
+    }
+    assert_eq!(inverse_norm_cdf(0.0), f64::NEG_INFINITY);
+    assert_eq!(inverse_norm_cdf(1.0), f64::INFINITY);
+}
+
+#[test]
+fn test_quick_quote_vanilla_is_accurate() {
+    let underlyings = vec![Underlying::new("TEST".to_string(), 100.0, 0.25)];
+    let correlation = DMatrix::from_element(1, 1, 1.0);
+    let payoff = Payoff::Vanilla { strike_price: 105.0, option_type: OptionType::Call };
+    let config = SimulationConfig::quick_quote().with_seed(3);
+    let quote = quick_quote(&underlyings, &correlation, 180, &payoff, 0.05, None, &config).unwrap();
+    let analytic = black_scholes_price(100.0, 105.0, 0.25, 0.05, 180.0 / 365.0, OptionType::Call);
+    assert!(
+        (quote.price - analytic).abs() < 4.0 * quote.std_error.max(1e-4),
+        "Quick quote {} should match Black-Scholes {}",
+        quote.price,
+        analytic
+    );
+
+    // Quasi-random numbers beat pseudo-random ones at the same path count
+    let pseudo_random = price_payoff(
+        &underlyings,
+        &correlation,
+        180,
+        &payoff,
+        0.05,
+        None,
+        &SimulationConfig::new(config.num_paths).with_seed(3),
+    ).unwrap();
+    assert!(
+        quote.std_error < 0.2 * pseudo_random.std_error,
+        "Quick quote error {} should be far below the pseudo-random error {}",
+        quote.std_error,
+        pseudo_random.std_error
+    );
+}
+
+#[test]
+fn test_sobol_sampling_prices_barriers() {
+    let underlyings = vec![
+        Underlying::new("A".to_string(), 100.0, 0.25),
+        Underlying::new("B".to_string(), 100.0, 0.30),
+    ];
+    let correlation = DMatrix::from_row_slice(2, 2, &[1.0, 0.4, 0.4, 1.0]);
+    let payoff = Payoff::Vanilla { strike_price: 100.0, option_type: OptionType::Put };
+    let barrier = Barrier::single(0.8, BarrierDirection::Down, KnockType::In, true);
+    let quote = quick_quote(
+        &underlyings,
+        &correlation,
+        60,
+        &payoff,
+        0.02,
+        Some(&barrier),
+        &SimulationConfig::quick_quote().with_seed(4),
+    ).unwrap();
+    let reference = price_payoff(
+        &underlyings,
+        &correlation,
+        60,
+        &payoff,
+        0.02,
+        Some(&barrier),
+        &SimulationConfig::new(20_000).with_seed(5).with_sampling(Sampling::PseudoRandom),
+    ).unwrap();
+    let tolerance = 4.0 * (quote.std_error.powi(2) + reference.std_error.powi(2)).sqrt();
+    assert!(
+        (quote.price - reference.price).abs() < tolerance,
+        "Quick quote {} should match the Monte Carlo price {}",
+        quote.price,
+        reference.price
+    );
+    assert_eq!(quote.num_paths, SimulationConfig::quick_quote().num_paths);
+}
@@ -0,0 +1,121 @@
+use mcproton::test_utils::{deterministic_config, single_stock};
+use mcproton::{
+    DeltaUnit, GreekConvention, PricingRequest, PricingResult, PricingSession, Product,
+    QuotationConvention, QuoteUnit, RhoUnit, Rounding, VegaUnit,
+};
+
+fn result(price: f64, std_error: f64) -> PricingResult {
+    PricingResult {
+        price,
+        std_error,
+        num_paths: 1_000,
+        bounds: None,
+        non_finite_paths: 0,
+        warnings: Vec::new(),
+        audit: Vec::new(),
+    }
+}
+
+#[test]
+fn test_quote_units() {
+    let result = result(4.5678, 0.01);
+    let per_unit = result.quoted(&QuotationConvention::per_unit());
+    assert_eq!(per_unit.price, 4.5678);
+    assert_eq!(per_unit.std_error, 0.01);
+
+    let per_contract = result.quoted(&QuotationConvention::per_contract(100.0));
+    assert!((per_contract.price - 456.78).abs() < 1e-10);
+    assert!((per_contract.std_error - 1.0).abs() < 1e-12);
+    assert_eq!(
+        per_contract.convention.unit(),
+        QuoteUnit::PerContract(100.0)
+    );
+
+    let percent = result.quoted(&QuotationConvention::percent_of_notional(50.0));
+    assert!((percent.price - 9.1356).abs() < 1e-12);
+    assert!((percent.std_error - 0.02).abs() < 1e-12);
+}
+
+#[test]
+fn test_rounding_to_the_tick_size() {
+    let convention = QuotationConvention::per_unit().with_tick_size(0.05);
+    assert_eq!(convention.quote(10.4499), 10.45);
+    assert_eq!(convention.quote(10.426), 10.45);
+    assert_eq!(convention.quote(10.424), 10.40);
+    assert_eq!(convention.quote(-0.026), -0.05);
+    let down = convention.with_rounding(Rounding::Down);
+    let up = convention.with_rounding(Rounding::Up);
+    assert_eq!(down.quote(10.449), 10.40);
+    assert_eq!(up.quote(10.401), 10.45);
+    // Prices on a tick stay there, whatever the direction
+    assert_eq!(down.quote(10.45), 10.45);
+    assert_eq!(up.quote(10.45), 10.45);
+
+    // Ticks that are no decimal fraction of a unit
+    let thirds = QuotationConvention::per_unit().with_tick_size(1.0 / 3.0);
+    assert!((thirds.quote(1.1) - 1.0).abs() < 1e-12);
+    assert!((thirds.quote(1.2) - 4.0 / 3.0).abs() < 1e-12);
+
+    // The tick size applies in the unit of the convention
+    let percent = QuotationConvention::percent_of_notional(100.0).with_tick_size(0.01);
+    assert_eq!(percent.quote(3.45678), 3.46);
+    assert_eq!(result(3.45678, 0.1).quoted(&percent).std_error, 0.1);
+}
+
+#[test]
+fn test_quoting_a_priced_product() {
+    let market = single_stock();
+    let spot = market.underlyings[0].spot_price;
+    let result = PricingRequest::new()
+        .market(market)
+        .maturity_days(90)
+        .strike(100.0)
+        .config(deterministic_config(10_000))
+        .price()
+        .unwrap();
+    let quote = result.quoted(&QuotationConvention::percent_of_notional(spot).with_tick_size(0.01));
+    assert!((quote.price - result.price * 100.0 / spot).abs() <= 0.005 + 1e-12);
+    assert_eq!((quote.price * 100.0).round() / 100.0, quote.price);
+}
+
+#[test]
+#[should_panic(expected = "Tick size must be positive")]
+fn test_non_positive_tick_size_panics() {
+    QuotationConvention::per_unit().with_tick_size(0.0);
+}
+
+#[test]
+fn test_greeks_in_market_conventions() {
+    let market = single_stock();
+    let spot = market.underlyings[0].spot_price;
+    let session = PricingSession::new(market, 90, &deterministic_config(5_000)).unwrap();
+    let greeks = session.greeks(&Product::call(100.0)).unwrap();
+    assert_eq!(greeks.convention, GreekConvention::derivatives());
+    assert_eq!(greeks.spot_price, spot);
+
+    let market_greeks = greeks.in_convention(&GreekConvention::market());
+    assert_eq!(market_greeks.convention, GreekConvention::market());
+    assert!((market_greeks.delta - greeks.delta * spot).abs() < 1e-9);
+    assert!((market_greeks.gamma - greeks.gamma * spot * spot / 100.0).abs() < 1e-9);
+    assert!((market_greeks.vega - greeks.vega / 100.0).abs() < 1e-12);
+    assert!((market_greeks.rho - greeks.rho / 10_000.0).abs() < 1e-12);
+    assert_eq!(market_greeks.pricing.price, greeks.pricing.price);
+
+    let per_percent = market_greeks.in_convention(
+        &GreekConvention::derivatives()
+            .with_delta(DeltaUnit::PerPercent)
+            .with_rho(RhoUnit::PerPercent),
+    );
+    assert!((per_percent.delta - greeks.delta * spot / 100.0).abs() < 1e-9);
+    assert!((per_percent.gamma - greeks.gamma * spot * spot / 10_000.0).abs() < 1e-9);
+    assert!((per_percent.vega - greeks.vega).abs() < 1e-9);
+    assert!((per_percent.rho - greeks.rho / 100.0).abs() < 1e-9);
+
+    // Converting back gives the derivatives
+    let derivatives = per_percent.in_convention(&GreekConvention::default());
+    assert!((derivatives.delta - greeks.delta).abs() < 1e-12);
+    assert!((derivatives.rho - greeks.rho).abs() < 1e-9);
+    let vol_points =
+        greeks.in_convention(&GreekConvention::derivatives().with_vega(VegaUnit::PerVolPoint));
+    assert!((vol_points.vega - greeks.vega / 100.0).abs() < 1e-12);
+}
@@ -0,0 +1,84 @@
+use mcproton::closed_form::black_scholes_price;
+use mcproton::{
+    price_option_with_config, Barrier, BarrierDirection, Interpolation, KnockType, OptionType,
+    RateCurve, SimulationConfig, Underlying,
+};
+use nalgebra::DMatrix;
+
+fn upward_curve(interpolation: Interpolation) -> RateCurve {
+    RateCurve::new(&[(0.25, 0.02), (1.0, 0.04), (2.0, 0.05)], interpolation)
+}
+
+#[test]
+fn test_flat_curve() {
+    let curve = RateCurve::from(0.05);
+    for time in [0.1, 1.0, 10.0] {
+        assert_eq!(curve.zero_rate(time), 0.05);
+        assert!((curve.discount_factor(time) - (-0.05 * time).exp()).abs() < 1e-15);
+        assert!((curve.forward_rate(time, time + 0.5) - 0.05).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn test_interpolation_between_pillars() {
+    let linear = upward_curve(Interpolation::Linear);
+    assert_eq!(linear.zero_rate(0.1), 0.02); // Flat before the first pillar
+    assert_eq!(linear.zero_rate(5.0), 0.05); // Flat after the last pillar
+    assert!((linear.zero_rate(0.625) - 0.03).abs() < 1e-12);
+
+    // Log-linear interpolation keeps the forward rate constant between pillars
+    let log_linear = upward_curve(Interpolation::LogLinear);
+    let forward = (0.04 * 1.0 - 0.02 * 0.25) / 0.75;
+    assert!((log_linear.forward_rate(0.25, 0.5) - forward).abs() < 1e-12);
+    assert!((log_linear.forward_rate(0.75, 1.0) - forward).abs() < 1e-12);
+    assert!((log_linear.zero_rate(1.0) - 0.04).abs() < 1e-12);
+}
+
+#[test]
+fn test_rolled_curve_realizes_the_forward_rates() {
+    let curve = upward_curve(Interpolation::LogLinear);
+    let rolled = curve.rolled(0.5);
+    assert_eq!(rolled.tenors(), &[0.5, 1.5]);
+    assert!((rolled.zero_rate(0.5) - curve.forward_rate(0.5, 1.0)).abs() < 1e-12);
+    assert!((rolled.zero_rate(1.5) - curve.forward_rate(0.5, 2.0)).abs() < 1e-12);
+
+    // Beyond the last pillar, the flat extrapolation is its own forward rate
+    for time in [0.1, 1.0, 10.0] {
+        assert!((curve.rolled(3.0).zero_rate(time) - 0.05).abs() < 1e-12);
+        assert!((RateCurve::flat(0.03).rolled(0.25).zero_rate(time) - 0.03).abs() < 1e-12);
+    }
+}
+
+#[test]
+#[should_panic(expected = "strictly increasing")]
+fn test_unsorted_pillars_rejected() {
+    RateCurve::new(&[(1.0, 0.02), (0.5, 0.03)], Interpolation::Linear);
+}
+
+#[test]
+fn test_flat_rate_and_flat_curve_price_identically() {
+    let underlyings = vec![Underlying::new("TEST".to_string(), 100.0, 0.20)];
+    let correlation = DMatrix::from_row_slice(1, 1, &[1.0]);
+    let barrier = Barrier::single(0.9, BarrierDirection::Down, KnockType::Out, true); // relative
+    let config = SimulationConfig::new(1_000).with_seed(9);
+    let with_rate = price_option_with_config(&underlyings, &correlation, 60, 100.0, OptionType::Call, 0.05, Some(&barrier), &config).unwrap();
+    let curve = RateCurve::flat(0.05);
+    let with_curve = price_option_with_config(&underlyings, &correlation, 60, 100.0, OptionType::Call, &curve, Some(&barrier), &config).unwrap();
+    assert!((with_rate.price - with_curve.price).abs() < 1e-9);
+}
+
+#[test]
+fn test_vanilla_with_curve_matches_black_scholes_at_zero_rate() {
+    let underlyings = vec![Underlying::new("TEST".to_string(), 100.0, 0.20)];
+    let correlation = DMatrix::from_row_slice(1, 1, &[1.0]);
+    let curve = upward_curve(Interpolation::LogLinear);
+    let config = SimulationConfig::new(20_000).with_seed(10).with_antithetic(true);
+    let result = price_option_with_config(&underlyings, &correlation, 365, 100.0, OptionType::Put, &curve, None, &config).unwrap();
+    let analytic = black_scholes_price(100.0, 100.0, 0.20, curve.zero_rate(1.0), 1.0, OptionType::Put);
+    assert!(
+        (result.price - analytic).abs() < 4.0 * result.std_error,
+        "Price {} should match Black-Scholes {} at the 1y zero rate",
+        result.price,
+        analytic
+    );
+}
@@ -0,0 +1,151 @@
+use mcproton::closed_form::norm_cdf;
+use mcproton::{
+    price_option_with_config, Barrier, BarrierCorrection, BarrierDirection, KnockType, OptionType,
+    PricingResult, PricingWarning, Rebate, RebateTiming, SimulationConfig, Underlying,
+};
+use nalgebra::DMatrix;
+
+const SPOT: f64 = 100.0;
+const BARRIER: f64 = 90.0;
+const VOLATILITY: f64 = 0.30;
+const RATE: f64 = 0.05;
+const DAYS: u32 = 90;
+const REBATE: f64 = 10.0;
+
+/// Prices a put with zero strike, which only pays the rebate, on a down barrier
+fn price_rebate(
+    knock_type: KnockType,
+    rebate: Rebate,
+    rate: f64,
+    config: &SimulationConfig,
+) -> PricingResult {
+    let underlyings = vec![Underlying::new("TEST".to_string(), SPOT, VOLATILITY)];
+    let correlation = DMatrix::identity(1, 1);
+    let barrier =
+        Barrier::single(BARRIER, BarrierDirection::Down, knock_type, false).with_rebate(rebate);
+    price_option_with_config(
+        &underlyings,
+        &correlation,
+        DAYS,
+        0.0,
+        OptionType::Put,
+        rate,
+        Some(&barrier),
+        config,
+    )
+    .unwrap()
+}
+
+/// Probability that a continuously monitored geometric Brownian motion falls to the barrier
+fn analytic_hit_probability() -> f64 {
+    let time = DAYS as f64 / 365.0;
+    let std_dev = VOLATILITY * time.sqrt();
+    let drift = (RATE - 0.5 * VOLATILITY * VOLATILITY) * time;
+    let log_barrier = (BARRIER / SPOT).ln();
+    norm_cdf((log_barrier - drift) / std_dev)
+        + (2.0 * drift * log_barrier / (std_dev * std_dev)).exp()
+            * norm_cdf((log_barrier + drift) / std_dev)
+}
+
+#[test]
+fn test_knock_out_and_knock_in_rebates_add_up_to_the_discounted_amount() {
+    // On the same paths, the knock-out rebate is paid on the hit paths and the knock-in
+    // rebate on all others
+    let config = SimulationConfig::new(10_000).with_seed(3);
+    let rebate = Rebate::new(REBATE, RebateTiming::AtExpiry);
+    let knock_out = price_rebate(KnockType::Out, rebate, RATE, &config);
+    let knock_in = price_rebate(KnockType::In, rebate, RATE, &config);
+    let discounted = REBATE * (-RATE * DAYS as f64 / 365.0).exp();
+    assert!(
+        (knock_out.price + knock_in.price - discounted).abs() < 1e-9,
+        "Knock-out rebate {} and knock-in rebate {} should add up to {}",
+        knock_out.price,
+        knock_in.price,
+        discounted
+    );
+}
+
+#[test]
+fn test_rebate_at_expiry_matches_the_continuous_hit_probability() {
+    let config = SimulationConfig::new(20_000)
+        .with_seed(5)
+        .with_barrier_correction(BarrierCorrection::BrownianBridge);
+    let result = price_rebate(
+        KnockType::Out,
+        Rebate::new(REBATE, RebateTiming::AtExpiry),
+        RATE,
+        &config,
+    );
+    let expected = REBATE * (-RATE * DAYS as f64 / 365.0).exp() * analytic_hit_probability();
+    assert!(
+        (result.price - expected).abs() < 4.0 * result.std_error,
+        "Rebate {} +/- {} should match the analytic value {}",
+        result.price,
+        result.std_error,
+        expected
+    );
+}
+
+#[test]
+fn test_rebate_at_hit_is_worth_more_with_positive_rates() {
+    let config = SimulationConfig::new(10_000).with_seed(7);
+    let at_hit = price_rebate(
+        KnockType::Out,
+        Rebate::new(REBATE, RebateTiming::AtHit),
+        RATE,
+        &config,
+    );
+    let at_expiry = price_rebate(
+        KnockType::Out,
+        Rebate::new(REBATE, RebateTiming::AtExpiry),
+        RATE,
+        &config,
+    );
+    assert!(
+        at_hit.price > at_expiry.price,
+        "Rebate at hit {} should be above the rebate at expiry {}",
+        at_hit.price,
+        at_expiry.price
+    );
+
+    let at_hit = price_rebate(
+        KnockType::Out,
+        Rebate::new(REBATE, RebateTiming::AtHit),
+        0.0,
+        &config,
+    );
+    let at_expiry = price_rebate(
+        KnockType::Out,
+        Rebate::new(REBATE, RebateTiming::AtExpiry),
+        0.0,
+        &config,
+    );
+    assert!(
+        (at_hit.price - at_expiry.price).abs() < 1e-9,
+        "Without rates, the timing should not matter: {} vs {}",
+        at_hit.price,
+        at_expiry.price
+    );
+}
+
+#[test]
+fn test_rebate_stays_within_the_bounds() {
+    let config = SimulationConfig::new(10_000).with_seed(9);
+    let result = price_rebate(
+        KnockType::Out,
+        Rebate::new(REBATE, RebateTiming::AtHit),
+        RATE,
+        &config,
+    );
+    let bounds = result.bounds.expect("Barrier options have bounds");
+    assert!(bounds.upper >= REBATE * (-RATE * DAYS as f64 / 365.0).exp());
+    assert!(
+        !result
+            .warnings
+            .iter()
+            .any(|warning| matches!(warning, PricingWarning::OutsideNoArbitrageBounds { .. })),
+        "Rebate {} should lie within the bounds {:?}",
+        result.price,
+        bounds
+    );
+}
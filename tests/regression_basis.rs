@@ -0,0 +1,241 @@
+use mcproton::closed_form::black_scholes_price;
+use mcproton::test_utils::{deterministic_config, three_asset_basket};
+use mcproton::{
+    price_american, price_american_with_basis, price_american_with_policy,
+    price_american_with_upper_bound, AmericanResult, DualSettings, ExercisePolicy, McError,
+    OptionType, RegressionBasis, SimulationConfig, Underlying,
+};
+use nalgebra::DMatrix;
+
+/// Longstaff-Schwartz reference put: S = 36, K = 40, σ = 20%, r = 6%, T = 1 year, weekly
+/// exercise, worth about 4.48
+fn reference_put(basis: &RegressionBasis) -> Result<AmericanResult, McError> {
+    let underlyings = vec![Underlying::new("TEST".to_string(), 36.0, 0.20)];
+    let correlation = DMatrix::from_element(1, 1, 1.0);
+    let exercise_dates: Vec<u32> = (1..=365).step_by(7).collect();
+    price_american_with_basis(
+        &underlyings,
+        &correlation,
+        365,
+        40.0,
+        OptionType::Put,
+        0.06,
+        &exercise_dates,
+        basis,
+        &SimulationConfig::new(4_000).with_seed(7),
+    )
+}
+
+#[test]
+fn test_polynomial_basis_matches_price_american() {
+    let result = reference_put(&RegressionBasis::polynomial(2)).unwrap();
+    let underlyings = vec![Underlying::new("TEST".to_string(), 36.0, 0.20)];
+    let exercise_dates: Vec<u32> = (1..=365).step_by(7).collect();
+    let price = price_american(
+        &underlyings,
+        &DMatrix::from_element(1, 1, 1.0),
+        365,
+        40.0,
+        OptionType::Put,
+        0.06,
+        &exercise_dates,
+        2,
+        &SimulationConfig::new(4_000).with_seed(7),
+    )
+    .unwrap()
+    .price;
+    assert_eq!(result.pricing.price, price);
+    // Without control variate, the price is the in-sample value
+    assert_eq!(result.in_sample_value, price);
+}
+
+#[test]
+fn test_laguerre_basis_fits_the_continuation_value() {
+    let basis = RegressionBasis::laguerre(3).with_exercise_value();
+    let result = reference_put(&basis).unwrap();
+    assert!(
+        (result.pricing.price - 4.48).abs() < 0.2,
+        "American put {} should be close to 4.48",
+        result.pricing.price
+    );
+
+    // One regression per weekly exercise date before expiry, in order
+    assert_eq!(result.regressions.len(), 52);
+    assert!(result
+        .regressions
+        .windows(2)
+        .all(|fits| fits[0].day < fits[1].day));
+    for fit in &result.regressions {
+        assert!(fit.num_paths > 0);
+        assert!((0.0..=1.0).contains(&fit.r_squared), "{:?}", fit);
+    }
+
+    // A good fit values the policy out of sample close to in sample
+    let std_error = result
+        .pricing
+        .std_error
+        .hypot(result.out_of_sample_std_error);
+    assert!(
+        result.value_gap().abs() < 4.0 * std_error,
+        "In-sample value {} and out-of-sample value {} differ",
+        result.in_sample_value,
+        result.out_of_sample_value
+    );
+}
+
+#[test]
+fn test_basis_sizes() {
+    assert_eq!(RegressionBasis::polynomial(2).num_functions(3), 7);
+    assert_eq!(
+        RegressionBasis::laguerre(2)
+            .with_exercise_value()
+            .with_cross_terms()
+            .num_functions(3),
+        12
+    );
+    assert!(matches!(
+        reference_put(&RegressionBasis::laguerre(0)),
+        Err(McError::InvalidBasisOrder)
+    ));
+}
+
+#[test]
+fn test_cross_terms_on_several_underlyings() {
+    let market = three_asset_basket();
+    let basis = RegressionBasis::polynomial(2)
+        .with_exercise_value()
+        .with_cross_terms();
+    let result = price_american_with_basis(
+        &market.underlyings,
+        &market.correlation_matrix,
+        60,
+        100.0,
+        OptionType::Put,
+        market.risk_free_rate,
+        &[20, 40],
+        &basis,
+        &deterministic_config(2_000),
+    )
+    .unwrap();
+    assert!(result.pricing.price > 0.0);
+    assert_eq!(result.regressions.len(), 2);
+    assert!(result.out_of_sample_value.is_finite());
+}
+
+#[test]
+fn test_dual_upper_bound_brackets_the_price() {
+    let underlyings = vec![Underlying::new("TEST".to_string(), 36.0, 0.20)];
+    let correlation = DMatrix::from_element(1, 1, 1.0);
+    let price = |dual: &DualSettings| {
+        price_american_with_upper_bound(
+            &underlyings,
+            &correlation,
+            90,
+            40.0,
+            OptionType::Put,
+            0.06,
+            &[18, 36, 54, 72],
+            &RegressionBasis::laguerre(3).with_exercise_value(),
+            dual,
+            &SimulationConfig::new(10_000).with_seed(5),
+        )
+    };
+    let result = price(&DualSettings::new(200, 300)).unwrap();
+    let upper_bound = result.upper_bound.unwrap();
+    assert_eq!(upper_bound.settings, DualSettings::new(200, 300));
+    let european = black_scholes_price(36.0, 40.0, 0.20, 0.06, 90.0 / 365.0, OptionType::Put);
+    let std_error = upper_bound.std_error.hypot(result.out_of_sample_std_error);
+    let gap = result.duality_gap().unwrap();
+    assert!(
+        gap > -4.0 * std_error && gap < 0.1,
+        "Upper bound {} should lie just above the lower bound {}",
+        upper_bound.value,
+        result.out_of_sample_value
+    );
+    assert!(upper_bound.value > european);
+
+    assert!(price_american_with_basis(
+        &underlyings,
+        &correlation,
+        90,
+        40.0,
+        OptionType::Put,
+        0.06,
+        &[45],
+        &RegressionBasis::polynomial(2),
+        &SimulationConfig::new(100),
+    )
+    .unwrap()
+    .upper_bound
+    .is_none());
+    assert!(matches!(
+        price(&DualSettings::new(100, 0)),
+        Err(McError::InvalidPaths(_))
+    ));
+}
+
+fn price_with_policy(spot: f64, policy: &ExercisePolicy, seed: u64) -> Result<f64, McError> {
+    price_american_with_policy(
+        &[Underlying::new("TEST".to_string(), spot, 0.20)],
+        &DMatrix::from_element(1, 1, 1.0),
+        365,
+        40.0,
+        OptionType::Put,
+        0.06,
+        policy,
+        &SimulationConfig::new(4_000).with_seed(seed),
+    )
+    .map(|result| result.price)
+}
+
+#[test]
+fn test_frozen_policy_reprices_out_of_sample() {
+    let result = reference_put(&RegressionBasis::laguerre(3)).unwrap();
+    let policy = &result.exercise_policy;
+    assert_eq!(policy.reference_prices, vec![36.0]);
+    assert_eq!(policy.exercise_days.len(), policy.coefficients.len());
+
+    let price = price_with_policy(36.0, policy, 11).unwrap();
+    assert_eq!(price_with_policy(36.0, policy, 11).unwrap(), price);
+    assert!(
+        (price - result.out_of_sample_value).abs()
+            < 4.0 * result.out_of_sample_std_error * 2f64.sqrt(),
+        "Frozen policy price {} should match the out-of-sample value {}",
+        price,
+        result.out_of_sample_value
+    );
+
+    // With common random numbers, the frozen policy gives a smooth delta
+    let delta =
+        price_with_policy(36.5, policy, 11).unwrap() - price_with_policy(35.5, policy, 11).unwrap();
+    assert!(
+        delta > -1.0 && delta < -0.4,
+        "Put delta {} out of range",
+        delta
+    );
+}
+
+#[test]
+fn test_invalid_exercise_policy() {
+    let policy = reference_put(&RegressionBasis::polynomial(2))
+        .unwrap()
+        .exercise_policy;
+    let two_assets = ExercisePolicy {
+        reference_prices: vec![36.0, 36.0],
+        ..policy.clone()
+    };
+    let beyond_expiry = ExercisePolicy {
+        exercise_days: policy.exercise_days.iter().map(|day| day + 200).collect(),
+        ..policy.clone()
+    };
+    let missing_coefficients = ExercisePolicy {
+        coefficients: policy.coefficients[1..].to_vec(),
+        ..policy
+    };
+    for policy in [two_assets, beyond_expiry, missing_coefficients] {
+        assert!(matches!(
+            price_with_policy(36.0, &policy, 1),
+            Err(McError::InvalidExercisePolicy(_))
+        ));
+    }
+}
@@ -0,0 +1,125 @@
+use mcproton::closed_form::{black_scholes_price, cash_or_nothing_price};
+use mcproton::test_utils::{assert_within_std_errors, deterministic_config, single_stock};
+use mcproton::{
+    replicate_product, Averaging, FixingSchedule, McError, OptionType, Payoff, Product,
+    ReplicatingInstrument,
+};
+
+const DAYS: u32 = 180;
+
+fn strike_grid(low: f64, high: f64, step: f64) -> Vec<f64> {
+    let count = ((high - low) / step).round() as usize;
+    (0..=count).map(|i| low + i as f64 * step).collect()
+}
+
+#[test]
+fn test_vanillas_replicate_with_a_single_call() {
+    let time = DAYS as f64 / 365.0;
+    let strikes = strike_grid(60.0, 140.0, 10.0);
+    let config = deterministic_config(20_000);
+    let call = replicate_product(
+        &single_stock(),
+        DAYS,
+        &Product::call(100.0),
+        &strikes,
+        &config,
+    )
+    .unwrap();
+    assert!(call.is_static);
+    assert_eq!(call.legs.len(), 1);
+    assert_eq!(call.legs[0].instrument, ReplicatingInstrument::Call(100.0));
+    assert_eq!(call.legs[0].quantity, 1.0);
+    let expected = black_scholes_price(100.0, 100.0, 0.2, 0.05, time, OptionType::Call);
+    assert!((call.value() - expected).abs() < 1e-9);
+    assert_within_std_errors(&call.pricing, call.value(), 4.0);
+
+    // A put is a bond, short the forward and long the call (put-call parity)
+    let put = replicate_product(
+        &single_stock(),
+        DAYS,
+        &Product::put(100.0),
+        &strikes,
+        &config,
+    )
+    .unwrap();
+    let instruments: Vec<_> = put.legs.iter().map(|leg| leg.instrument).collect();
+    assert_eq!(
+        instruments,
+        [
+            ReplicatingInstrument::Bond,
+            ReplicatingInstrument::Forward,
+            ReplicatingInstrument::Call(100.0)
+        ]
+    );
+    let expected = black_scholes_price(100.0, 100.0, 0.2, 0.05, time, OptionType::Put);
+    assert!((put.value() - expected).abs() < 1e-9);
+}
+
+#[test]
+fn test_digitals_replicate_with_call_spreads() {
+    let product = Product::new(Payoff::CashOrNothing {
+        strike_price: 100.0,
+        option_type: OptionType::Call,
+        amount: 10.0,
+    });
+    let strikes = strike_grid(50.0, 150.0, 0.5);
+    let replication = replicate_product(
+        &single_stock(),
+        DAYS,
+        &product,
+        &strikes,
+        &deterministic_config(1_000),
+    )
+    .unwrap();
+    assert!(replication.is_static);
+    // Long the call at the strike, short the call half a unit above
+    assert_eq!(replication.legs.len(), 2);
+    assert_eq!(replication.legs[0].quantity, 20.0);
+    assert_eq!(replication.legs[1].quantity, -20.0);
+    let time = DAYS as f64 / 365.0;
+    let expected = cash_or_nothing_price(100.0, 100.0, 10.0, 0.2, 0.05, time, OptionType::Call);
+    assert!((replication.value() - expected).abs() < 0.1);
+}
+
+#[test]
+fn test_path_dependent_products_replicate_their_expected_payoff() {
+    let product = Product::new(Payoff::AveragePrice {
+        strike_price: 100.0,
+        option_type: OptionType::Call,
+        averaging: Averaging::Arithmetic,
+        schedule: FixingSchedule::Monthly,
+    });
+    let strikes = strike_grid(50.0, 160.0, 5.0);
+    let replication = replicate_product(
+        &single_stock(),
+        DAYS,
+        &product,
+        &strikes,
+        &deterministic_config(40_000),
+    )
+    .unwrap();
+    assert!(!replication.is_static);
+    assert!(replication.legs.len() > 2);
+    // The strip on the terminal price explains most of the price
+    assert!(
+        replication.residual().abs() < 0.05 * replication.pricing.price,
+        "Strip worth {} for a price of {}",
+        replication.value(),
+        replication.pricing.price
+    );
+}
+
+#[test]
+fn test_strikes_must_increase() {
+    let config = deterministic_config(1_000);
+    for strikes in [vec![100.0], vec![0.0, 100.0], vec![100.0, 90.0]] {
+        let result = replicate_product(
+            &single_stock(),
+            DAYS,
+            &Product::call(100.0),
+            &strikes,
+            &config,
+        );
+        assert!(matches!(result, Err(McError::InvalidProduct(_))));
+    }
+}
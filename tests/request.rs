@@ -0,0 +1,121 @@
+use mcproton::test_utils::two_asset_basket;
+use mcproton::{
+    price_option, price_option_with_config, price_payoff, Averaging, Barrier, BarrierDirection,
+    FixingSchedule, KnockType, McError, OptionType, Payoff, PricingRequest, SimulationConfig,
+    Underlying,
+};
+use nalgebra::DMatrix;
+
+fn stock() -> Underlying {
+    Underlying::new("AAPL".to_string(), 150.0, 0.25)
+}
+
+#[test]
+fn test_request_matches_the_positional_api() {
+    let barrier = Barrier::single(0.9, BarrierDirection::Down, KnockType::Out, true);
+    let result = PricingRequest::new()
+        .underlying(stock())
+        .correlation(DMatrix::identity(1, 1))
+        .maturity_days(30)
+        .strike(155.0)
+        .put()
+        .barrier(barrier.clone())
+        .rate(0.05)
+        .paths(5_000)
+        .seed(42)
+        .price()
+        .unwrap();
+    let expected = price_option_with_config(
+        &[stock()],
+        &DMatrix::identity(1, 1),
+        30,
+        155.0,
+        OptionType::Put,
+        0.05,
+        Some(&barrier),
+        &SimulationConfig::new(5_000).with_seed(42),
+    )
+    .unwrap();
+    assert_eq!(result.price, expected.price);
+    assert_eq!(result.std_error, expected.std_error);
+}
+
+#[test]
+fn test_request_defaults() {
+    // Uncorrelated underlyings, zero rate, a Call and the default path count
+    let market = two_asset_basket();
+    let result = PricingRequest::new()
+        .underlyings(market.underlyings.clone())
+        .maturity_days(60)
+        .strike(100.0)
+        .seed(7)
+        .price()
+        .unwrap();
+    let expected = price_option_with_config(
+        &market.underlyings,
+        &DMatrix::identity(2, 2),
+        60,
+        100.0,
+        OptionType::Call,
+        0.0,
+        None,
+        &SimulationConfig::default().with_seed(7),
+    )
+    .unwrap();
+    assert_eq!(result.price, expected.price);
+    assert_eq!(result.num_paths, SimulationConfig::default().num_paths);
+
+    // The positional wrapper prices through a request
+    let price = price_option(
+        &[stock()],
+        &DMatrix::identity(1, 1),
+        30,
+        155.0,
+        OptionType::Call,
+        0.05,
+        0,
+        None,
+    );
+    assert_eq!(price.unwrap_err(), McError::InvalidPaths(0));
+}
+
+#[test]
+fn test_request_prices_payoffs_on_a_market() {
+    let market = two_asset_basket();
+    let asian = Payoff::AveragePrice {
+        strike_price: 100.0,
+        option_type: OptionType::Call,
+        averaging: Averaging::Arithmetic,
+        schedule: FixingSchedule::Monthly,
+    };
+    let config = SimulationConfig::new(2_000)
+        .with_seed(3)
+        .with_antithetic(true);
+    let result = PricingRequest::new()
+        .market(market.clone())
+        .maturity_days(90)
+        .payoff(asian.clone())
+        .config(config.clone())
+        .price()
+        .unwrap();
+    let expected = price_payoff(
+        &market.underlyings,
+        &market.correlation_matrix,
+        90,
+        &asian,
+        &market.risk_free_rate,
+        None,
+        &config,
+    )
+    .unwrap();
+    assert_eq!(result.price, expected.price);
+}
+
+#[test]
+fn test_request_without_strike_or_payoff_is_rejected() {
+    let result = PricingRequest::new()
+        .underlying(stock())
+        .maturity_days(30)
+        .price();
+    assert!(matches!(result, Err(McError::InvalidProduct(_))));
+}
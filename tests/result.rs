@@ -0,0 +1,123 @@
+use std::fs;
+use std::path::PathBuf;
+
+use mcproton::{
+    load_results, save_results, AuditEntry, McError, PriceBounds, PricingResult, PricingWarning,
+};
+
+/// Returns a result file path in the temporary directory, unique to the test
+fn result_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("mcproton-{}-{}.results", std::process::id(), name))
+}
+
+fn results() -> Vec<PricingResult> {
+    vec![
+        PricingResult {
+            price: 10.45,
+            std_error: 0.02,
+            num_paths: 100_000,
+            bounds: Some(PriceBounds {
+                lower: 4.88,
+                upper: 100.0,
+            }),
+            non_finite_paths: 3,
+            warnings: vec![
+                PricingWarning::NonFinitePaths { count: 3 },
+                PricingWarning::PutCallParity {
+                    residual: 0.5,
+                    tolerance: 0.1,
+                },
+                PricingWarning::CorrelationRepaired { adjustment: 0.25 },
+                PricingWarning::ErrorToleranceNotMet {
+                    target_std_error: 0.01,
+                },
+                PricingWarning::ReducedPaths {
+                    requested_paths: 1_000_000,
+                },
+            ],
+            audit: vec![
+                AuditEntry::new("day_count", "Calendar365", true),
+                AuditEntry::new("seed", "42", false),
+                AuditEntry::new("barrier_shift", "0 step standard deviations", true),
+            ],
+        },
+        PricingResult {
+            price: 0.0,
+            std_error: 0.0,
+            num_paths: 10,
+            bounds: None,
+            non_finite_paths: 0,
+            warnings: Vec::new(),
+            audit: Vec::new(),
+        },
+    ]
+}
+
+fn assert_same_results(loaded: &[PricingResult], expected: &[PricingResult]) {
+    assert_eq!(loaded.len(), expected.len());
+    for (loaded, expected) in loaded.iter().zip(expected) {
+        assert_eq!(loaded.price, expected.price);
+        assert_eq!(loaded.std_error, expected.std_error);
+        assert_eq!(loaded.num_paths, expected.num_paths);
+        assert_eq!(loaded.bounds, expected.bounds);
+        assert_eq!(loaded.non_finite_paths, expected.non_finite_paths);
+        assert_eq!(loaded.warnings, expected.warnings);
+        assert_eq!(loaded.audit, expected.audit);
+    }
+}
+
+#[test]
+fn test_results_round_trip() {
+    let path = result_path("round-trip");
+    save_results(&path, &results()).unwrap();
+    assert_same_results(&load_results(&path).unwrap(), &results());
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_fields_and_warnings_of_later_versions_are_ignored() {
+    let path = result_path("later");
+    let expected = &results()[1..];
+    save_results(&path, expected).unwrap();
+
+    // A later version writes a warning of a new kind and appends a field to the only result
+    // record, which ends with the number of warnings and the number of audit entries
+    let mut contents = fs::read(&path).unwrap();
+    let len = u64::from_le_bytes(contents[32..40].try_into().unwrap());
+    contents[32..40].copy_from_slice(&(len + 4 * 8).to_le_bytes());
+    contents.truncate(contents.len() - 16);
+    for word in [1_u64, 99, 8, 42, 0, 7] {
+        contents.extend(word.to_le_bytes());
+    }
+    fs::write(&path, &contents).unwrap();
+    assert_same_results(&load_results(&path).unwrap(), expected);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_results_of_earlier_versions_have_no_audit_trail() {
+    let path = result_path("earlier");
+    save_results(&path, &results()[1..]).unwrap();
+
+    // Results written before the audit trail end with the number of warnings
+    let mut contents = fs::read(&path).unwrap();
+    let len = u64::from_le_bytes(contents[32..40].try_into().unwrap());
+    contents[32..40].copy_from_slice(&(len - 8).to_le_bytes());
+    contents.truncate(contents.len() - 8);
+    fs::write(&path, &contents).unwrap();
+    let loaded = load_results(&path).unwrap();
+    assert_same_results(&loaded, &results()[1..]);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_other_files_are_rejected() {
+    let path = result_path("other");
+    fs::write(&path, b"MCPSCEN\0").unwrap();
+    assert!(matches!(load_results(&path), Err(McError::InvalidFile(_))));
+    fs::remove_file(&path).unwrap();
+    assert!(matches!(
+        load_results(result_path("missing")),
+        Err(McError::Io(_))
+    ));
+}
@@ -0,0 +1,67 @@
+use mcproton::closed_form::black_scholes_price;
+use mcproton::test_utils::{assert_within_std_errors, deterministic_config, single_stock};
+use mcproton::{roll_down_report, Averaging, FixingSchedule, McError, OptionType, Payoff, Product};
+
+const DAYS: u32 = 180;
+
+#[test]
+fn test_call_rolls_down_to_black_scholes_on_the_remaining_life() {
+    let roll_days = [30, 90, 150];
+    let report = roll_down_report(
+        &single_stock(),
+        DAYS,
+        &Product::call(100.0),
+        &roll_days,
+        &deterministic_config(40_000).with_antithetic(true),
+    )
+    .unwrap();
+    assert_eq!(report.points.len(), roll_days.len());
+    let expected = black_scholes_price(100.0, 100.0, 0.2, 0.05, 180.0 / 365.0, OptionType::Call);
+    assert_within_std_errors(&report.base, expected, 4.0);
+
+    for point in &report.points {
+        let remaining = (DAYS - point.day) as f64 / 365.0;
+        let unchanged = black_scholes_price(100.0, 100.0, 0.2, 0.05, remaining, OptionType::Call);
+        assert_within_std_errors(&point.unchanged, unchanged, 4.0);
+        // The spot rolls to its forward
+        let forward_spot = 100.0 * (0.05 * point.day as f64 / 365.0).exp();
+        let forward =
+            black_scholes_price(forward_spot, 100.0, 0.2, 0.05, remaining, OptionType::Call);
+        assert_within_std_errors(&point.forward, forward, 4.0);
+    }
+
+    // The time value decays under unchanged markets, the drift offsets part of it
+    for (_, unchanged, forward) in report.price_changes() {
+        assert!(unchanged < 0.0);
+        assert!(forward > unchanged);
+    }
+}
+
+#[test]
+fn test_past_fixing_dates_drop_out() {
+    let product = Product::new(Payoff::AveragePrice {
+        strike_price: 100.0,
+        option_type: OptionType::Call,
+        averaging: Averaging::Arithmetic,
+        schedule: FixingSchedule::Dates(vec![60, 120, 180]),
+    });
+    let config = deterministic_config(5_000);
+    let report = roll_down_report(&single_stock(), DAYS, &product, &[90], &config).unwrap();
+    assert!(report.points[0].unchanged.price > 0.0);
+
+    // No fixing date remains after the last one
+    let result = roll_down_report(&single_stock(), 200, &product, &[180], &config);
+    assert!(matches!(result, Err(McError::InvalidSchedule(_))));
+}
+
+#[test]
+fn test_roll_days_must_be_before_expiry() {
+    let result = roll_down_report(
+        &single_stock(),
+        DAYS,
+        &Product::call(100.0),
+        &[30, DAYS],
+        &deterministic_config(1_000),
+    );
+    assert!(matches!(result, Err(McError::InvalidSchedule(_))));
+}
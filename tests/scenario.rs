@@ -0,0 +1,209 @@
+use std::fs;
+use std::path::PathBuf;
+
+use mcproton::test_utils::{single_stock, two_asset_basket};
+use mcproton::{
+    Averaging, Barrier, BarrierDirection, BarrierType, FixingSchedule, KnockType, McError,
+    OptionType, Payoff, PricingSession, Product, SimulationConfig,
+};
+
+const DAYS: u32 = 60;
+
+/// Returns a scenario file path in the temporary directory, unique to the test
+fn scenario_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "mcproton-{}-{}.scenarios",
+        std::process::id(),
+        name
+    ))
+}
+
+fn worst_of_barrier() -> Barrier {
+    let mut barrier = Barrier::single(0.85, BarrierDirection::Down, KnockType::Out, true);
+    barrier.barrier_type = BarrierType::WorstOf;
+    barrier.underlying_indices = vec![0, 1];
+    barrier
+}
+
+fn asian_call() -> Product {
+    Product::new(Payoff::AveragePrice {
+        strike_price: 100.0,
+        option_type: OptionType::Call,
+        averaging: Averaging::Arithmetic,
+        schedule: FixingSchedule::Dates(vec![20, 40, 60]),
+    })
+}
+
+#[test]
+fn test_loaded_scenarios_reprice_without_simulating() {
+    let config = SimulationConfig::new(2_000).with_seed(17);
+    let products = [
+        Product::put(95.0),
+        asian_call().with_barrier(worst_of_barrier()),
+    ];
+    let session = PricingSession::new(two_asset_basket(), DAYS, &config).unwrap();
+    let prices: Vec<f64> = products
+        .iter()
+        .map(|product| session.price(product).unwrap().price)
+        .collect();
+    let greeks = session.greeks(&products[0]).unwrap();
+    let path = scenario_path("reprice");
+    session.save_scenarios(&path).unwrap();
+
+    let loaded = PricingSession::new(two_asset_basket(), DAYS, &config).unwrap();
+    loaded.load_scenarios(&path).unwrap();
+    for (product, &price) in products.iter().zip(&prices) {
+        assert_eq!(loaded.price(product).unwrap().price, price);
+    }
+    let loaded_greeks = loaded.greeks(&products[0]).unwrap();
+    assert_eq!(loaded_greeks.delta, greeks.delta);
+    assert_eq!(loaded_greeks.vega, greeks.vega);
+    assert_eq!(loaded_greeks.rho, greeks.rho);
+    assert_eq!(loaded.num_simulations(), 0);
+
+    // Other strikes and levels reuse the loaded paths as well
+    let barrier = Barrier {
+        barrier_level: 0.9,
+        ..worst_of_barrier()
+    };
+    loaded.price(&asian_call().with_barrier(barrier)).unwrap();
+    assert_eq!(loaded.num_simulations(), 0);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_saving_an_unused_session_records_its_paths() {
+    let config = SimulationConfig::new(1_000).with_seed(3);
+    let session = PricingSession::new(two_asset_basket(), DAYS, &config).unwrap();
+    let path = scenario_path("unused");
+    session.save_scenarios(&path).unwrap();
+    assert_eq!(session.num_simulations(), 1);
+
+    let loaded = PricingSession::new(two_asset_basket(), DAYS, &config).unwrap();
+    loaded.load_scenarios(&path).unwrap();
+    let call = Product::call(100.0);
+    assert_eq!(
+        loaded.price(&call).unwrap().price,
+        session.price(&call).unwrap().price
+    );
+    assert_eq!(loaded.num_simulations(), 0);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_scenarios_of_another_session_are_rejected() {
+    let config = SimulationConfig::new(1_000).with_seed(3);
+    let session = PricingSession::new(two_asset_basket(), DAYS, &config).unwrap();
+    let path = scenario_path("other");
+    session.save_scenarios(&path).unwrap();
+
+    let other_seed = SimulationConfig::new(1_000).with_seed(4);
+    let other = PricingSession::new(two_asset_basket(), DAYS, &other_seed).unwrap();
+    assert!(matches!(
+        other.load_scenarios(&path),
+        Err(McError::InvalidFile(_))
+    ));
+    let other = PricingSession::new(two_asset_basket(), DAYS + 1, &config).unwrap();
+    assert!(matches!(
+        other.load_scenarios(&path),
+        Err(McError::InvalidFile(_))
+    ));
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_invalid_scenario_files_are_rejected() {
+    let session =
+        PricingSession::new(two_asset_basket(), DAYS, &SimulationConfig::new(100)).unwrap();
+    assert!(matches!(
+        session.load_scenarios(scenario_path("missing")),
+        Err(McError::Io(_))
+    ));
+
+    let path = scenario_path("invalid");
+    fs::write(&path, b"not a scenario file").unwrap();
+    assert!(matches!(
+        session.load_scenarios(&path),
+        Err(McError::InvalidFile(_))
+    ));
+
+    session.save_scenarios(&path).unwrap();
+    let contents = fs::read(&path).unwrap();
+    fs::write(&path, &contents[..contents.len() - 8]).unwrap();
+    assert!(matches!(
+        session.load_scenarios(&path),
+        Err(McError::InvalidFile(_))
+    ));
+    fs::remove_file(&path).unwrap();
+}
+
+/// Encodes words of a scenario file
+fn words(values: &[u64]) -> Vec<u8> {
+    values.iter().flat_map(|word| word.to_le_bytes()).collect()
+}
+
+#[test]
+fn test_version_1_scenario_files_remain_readable() {
+    // Two paths of a single stock without fixings or barrier reference, in the layout of
+    // version 1: header and path sets one after the other
+    let (seed, num_paths) = (7, 2);
+    let mut contents = b"MCPSCEN\0".to_vec();
+    contents.extend(words(&[1, u64::from(DAYS), seed, num_paths, 0, 1]));
+    contents.extend(words(&[
+        100.0_f64.to_bits(),
+        1,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+        num_paths,
+    ]));
+    for column in [
+        [90.0, 120.0],
+        [101.0, 125.0],
+        [85.0, 99.0],
+        [f64::INFINITY; 2],
+        [f64::NEG_INFINITY; 2],
+    ] {
+        contents.extend(words(&column.map(f64::to_bits)));
+    }
+    let path = scenario_path("version-1");
+    fs::write(&path, contents).unwrap();
+
+    let config = SimulationConfig::new(num_paths).with_seed(seed);
+    let session = PricingSession::new(single_stock(), DAYS, &config).unwrap();
+    session.load_scenarios(&path).unwrap();
+    let result = session.price(&Product::call(100.0)).unwrap();
+    let expected = 10.0 * (-0.05 * DAYS as f64 / 365.0).exp();
+    assert!((result.price - expected).abs() < 1e-12);
+    assert_eq!(session.num_simulations(), 0);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_later_versions_are_read_if_compatible() {
+    let config = SimulationConfig::new(1_000).with_seed(3);
+    let session = PricingSession::new(two_asset_basket(), DAYS, &config).unwrap();
+    let call = Product::call(100.0);
+    let price = session.price(&call).unwrap().price;
+    let path = scenario_path("later");
+    session.save_scenarios(&path).unwrap();
+
+    // A later version adds a record of a new kind
+    let mut contents = fs::read(&path).unwrap();
+    contents.extend(words(&[99, 16, 1, 2]));
+    fs::write(&path, &contents).unwrap();
+    let loaded = PricingSession::new(two_asset_basket(), DAYS, &config).unwrap();
+    loaded.load_scenarios(&path).unwrap();
+    assert_eq!(loaded.price(&call).unwrap().price, price);
+
+    // A later version that cannot be read by this one
+    contents[16..24].copy_from_slice(&u64::MAX.to_le_bytes());
+    fs::write(&path, &contents).unwrap();
+    let error = loaded.load_scenarios(&path).unwrap_err();
+    assert!(matches!(error, McError::InvalidFile(_)));
+    assert!(error.to_string().contains("not supported"), "{}", error);
+    fs::remove_file(&path).unwrap();
+}
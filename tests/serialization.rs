@@ -0,0 +1,148 @@
+use mcproton::test_utils::{deterministic_config, two_asset_basket};
+use mcproton::{
+    Averaging, Barrier, BarrierDirection, BarrierMonitoring, FixingSchedule, Interpolation,
+    KnockType, MarketSnapshot, OptionType, Payoff, PriceBounds, PricingRequest, PricingResult,
+    PricingWarning, Product, RateCurve, Rebate, RebateTiming,
+};
+use serde_json::json;
+
+fn barrier_asian_call() -> Product {
+    let mut barrier = Barrier::single(0.8, BarrierDirection::Down, KnockType::Out, true);
+    barrier.monitoring = BarrierMonitoring::Dates(vec![30, 60]);
+    barrier.rebate = Some(Rebate::new(2.0, RebateTiming::AtExpiry));
+    Product::new(Payoff::AveragePrice {
+        strike_price: 100.0,
+        option_type: OptionType::Call,
+        averaging: Averaging::Geometric,
+        schedule: FixingSchedule::Dates(vec![20, 40, 60]),
+    })
+    .with_barrier(barrier)
+}
+
+#[test]
+fn test_products_round_trip_through_json() {
+    let products = [
+        Product::put(95.0),
+        barrier_asian_call(),
+        Product::new(Payoff::Ladder {
+            strike_price: 100.0,
+            option_type: OptionType::Call,
+            rungs: vec![110.0, 120.0],
+        }),
+    ];
+    for product in products {
+        let json = serde_json::to_string(&product).unwrap();
+        assert_eq!(serde_json::from_str::<Product>(&json).unwrap(), product);
+    }
+}
+
+#[test]
+fn test_trades_are_read_from_hand_written_json() {
+    let product: Product = serde_json::from_value(json!({
+        "payoff": { "Vanilla": { "strike_price": 100.0, "option_type": "Call" } },
+        "barrier": {
+            "barrier_level": 120.0,
+            "direction": "Up",
+            "knock_type": "Out",
+            "barrier_type": "WorstOf",
+            "relative": false,
+            "underlying_indices": [0]
+        }
+    }))
+    .unwrap();
+    let barrier = Barrier::single(120.0, BarrierDirection::Up, KnockType::Out, false);
+    assert_eq!(product, Product::call(100.0).with_barrier(barrier));
+
+    let unknown_option_type = json!({
+        "payoff": { "Vanilla": { "strike_price": 100.0, "option_type": "Straddle" } }
+    });
+    assert!(serde_json::from_value::<Product>(unknown_option_type).is_err());
+}
+
+#[test]
+fn test_market_serializes_the_correlation_matrix_as_rows() {
+    let value = serde_json::to_value(two_asset_basket()).unwrap();
+    assert_eq!(value["correlation_matrix"], json!([[1.0, 0.5], [0.5, 1.0]]));
+    assert_eq!(value["risk_free_rate"], json!(0.05));
+
+    // Optional underlying fields may be omitted
+    let market: MarketSnapshot = serde_json::from_value(json!({
+        "underlyings": [
+            { "name": "STOCK1", "spot_price": 100.0, "volatility": 0.20 },
+            { "name": "STOCK2", "spot_price": 100.0, "volatility": 0.25 }
+        ],
+        "correlation_matrix": [[1.0, 0.5], [0.5, 1.0]],
+        "risk_free_rate": 0.05
+    }))
+    .unwrap();
+    assert_eq!(serde_json::to_value(&market).unwrap(), value);
+    assert_eq!(
+        market.correlation_matrix,
+        two_asset_basket().correlation_matrix
+    );
+
+    let mut ragged = value;
+    ragged["correlation_matrix"] = json!([[1.0, 0.5], [0.5]]);
+    assert!(serde_json::from_value::<MarketSnapshot>(ragged).is_err());
+}
+
+#[test]
+fn test_rate_curves_serialize_as_pillars() {
+    let curve = RateCurve::new(&[(0.5, 0.03), (2.0, 0.04)], Interpolation::LogLinear);
+    let value = serde_json::to_value(&curve).unwrap();
+    assert_eq!(
+        value,
+        json!({ "pillars": [[0.5, 0.03], [2.0, 0.04]], "interpolation": "LogLinear" })
+    );
+    assert_eq!(serde_json::from_value::<RateCurve>(value).unwrap(), curve);
+    assert_eq!(
+        serde_json::from_value::<RateCurve>(json!(0.05)).unwrap(),
+        RateCurve::flat(0.05)
+    );
+
+    let decreasing = json!({ "pillars": [[2.0, 0.04], [0.5, 0.03]] });
+    assert!(serde_json::from_value::<RateCurve>(decreasing).is_err());
+    assert!(serde_json::from_value::<RateCurve>(json!({ "pillars": [] })).is_err());
+}
+
+#[test]
+fn test_deserialized_requests_price_like_the_original() {
+    let request = PricingRequest::new()
+        .market(two_asset_basket())
+        .maturity_days(60)
+        .payoff(barrier_asian_call().payoff)
+        .barrier(barrier_asian_call().barrier.unwrap());
+    let json = serde_json::to_string(&request).unwrap();
+    let loaded: PricingRequest = serde_json::from_str(&json).unwrap();
+
+    let config = deterministic_config(2_000);
+    let price = |request: PricingRequest| request.config(config.clone()).price().unwrap().price;
+    assert_eq!(price(loaded), price(request));
+}
+
+#[test]
+fn test_results_round_trip_with_infinite_bounds() {
+    let result = PricingResult {
+        price: 10.45,
+        std_error: 0.02,
+        num_paths: 100_000,
+        bounds: Some(PriceBounds {
+            lower: 4.9,
+            upper: f64::INFINITY,
+        }),
+        non_finite_paths: 0,
+        warnings: vec![PricingWarning::HighStandardError {
+            relative_error: 0.015,
+        }],
+        audit: Vec::new(),
+    };
+    let value = serde_json::to_value(&result).unwrap();
+    assert_eq!(value["bounds"]["upper"], json!("inf"));
+
+    let loaded: PricingResult = serde_json::from_value(value).unwrap();
+    assert_eq!(loaded.price, result.price);
+    assert_eq!(loaded.std_error, result.std_error);
+    assert_eq!(loaded.num_paths, result.num_paths);
+    assert_eq!(loaded.bounds, result.bounds);
+    assert_eq!(loaded.warnings, result.warnings);
+}
@@ -0,0 +1,416 @@
+use mcproton::closed_form::{black_scholes_price, black_scholes_price_with_dividends};
+use mcproton::test_utils::{single_stock, two_asset_basket};
+use mcproton::{
+    price_option_with_config, AssetClass, Averaging, Barrier, BarrierCorrection, BarrierDirection,
+    BarrierType, DayCountConvention, ErrorTolerance, FixingSchedule, KnockType, MarketSnapshot,
+    OptionType, Payoff, PricingSession, Product, Quanto, RateCurve, Rebate, RebateTiming,
+    SimulationConfig, Underlying,
+};
+use nalgebra::DMatrix;
+
+const DAYS: u32 = 60;
+
+#[test]
+fn test_barrier_price_matches_a_full_simulation_on_the_same_seed() {
+    let market = single_stock();
+    let config = SimulationConfig::new(5_000).with_seed(21);
+    let barrier = Barrier::single(0.9, BarrierDirection::Down, KnockType::Out, true);
+    let session = PricingSession::new(market.clone(), DAYS, &config).unwrap();
+    let cached = session
+        .price(&Product::call(100.0).with_barrier(barrier.clone()))
+        .unwrap();
+    let full = price_option_with_config(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        100.0,
+        OptionType::Call,
+        &market.risk_free_rate,
+        Some(&barrier),
+        &config,
+    )
+    .unwrap();
+    assert!(
+        (cached.price - full.price).abs() < 1e-9,
+        "Session price {} should match the full simulation {}",
+        cached.price,
+        full.price
+    );
+}
+
+#[test]
+fn test_strike_and_barrier_level_changes_reuse_the_paths() {
+    let session = PricingSession::new(single_stock(), DAYS, &SimulationConfig::new(5_000)).unwrap();
+    let mut previous = f64::INFINITY;
+    for strike_price in [90.0, 100.0, 110.0] {
+        let price = session.price(&Product::call(strike_price)).unwrap().price;
+        assert!(price < previous, "Call prices should fall with the strike");
+        previous = price;
+    }
+    let mut previous = 0.0;
+    for level in [0.80, 0.85, 0.90] {
+        let barrier = Barrier::single(level, BarrierDirection::Down, KnockType::In, true);
+        let price = session
+            .price(&Product::call(100.0).with_barrier(barrier))
+            .unwrap()
+            .price;
+        assert!(
+            price >= previous,
+            "Down-and-in prices should rise with the level"
+        );
+        previous = price;
+    }
+    // Vanilla and in/out barrier options on the same paths add up exactly
+    let barrier = Barrier::single(0.9, BarrierDirection::Down, KnockType::In, true);
+    let knock_in = session
+        .price(&Product::call(100.0).with_barrier(barrier.clone()))
+        .unwrap()
+        .price;
+    let knock_out = session
+        .price(&Product::call(100.0).with_barrier(Barrier {
+            knock_type: KnockType::Out,
+            ..barrier
+        }))
+        .unwrap()
+        .price;
+    let vanilla = session.price(&Product::call(100.0)).unwrap().price;
+    assert!((knock_in + knock_out - vanilla).abs() < 1e-9);
+    // The barrier reference is recorded once the first barrier option is priced
+    assert_eq!(session.num_simulations(), 2);
+}
+
+#[test]
+fn test_new_observables_simulate_the_same_paths_again() {
+    let session =
+        PricingSession::new(two_asset_basket(), DAYS, &SimulationConfig::new(2_000)).unwrap();
+    let vanilla = session.price(&Product::call(100.0)).unwrap().price;
+    let asian = Payoff::AveragePrice {
+        strike_price: 100.0,
+        option_type: OptionType::Call,
+        averaging: Averaging::Arithmetic,
+        schedule: FixingSchedule::Monthly,
+    };
+    session.price(&asian.clone().into()).unwrap();
+    let worst_of = Barrier::multi(
+        0.8,
+        BarrierDirection::Down,
+        KnockType::Out,
+        BarrierType::WorstOf,
+        true,
+        vec![0, 1],
+    )
+    .expect("Relative barriers are valid");
+    session
+        .price(&Product::call(100.0).with_barrier(worst_of.clone()))
+        .unwrap();
+    assert_eq!(session.num_simulations(), 3);
+
+    // The previous observables are still recorded
+    session
+        .price(&Product::new(asian).with_barrier(worst_of))
+        .unwrap();
+    let repriced = session.price(&Product::call(100.0)).unwrap().price;
+    assert_eq!(session.num_simulations(), 3);
+    assert!((repriced - vanilla).abs() < 1e-9);
+}
+
+#[test]
+fn test_corrected_barriers_fall_back_to_a_full_simulation() {
+    let config = SimulationConfig::new(2_000)
+        .with_seed(4)
+        .with_barrier_correction(BarrierCorrection::BrownianBridge);
+    let session = PricingSession::new(single_stock(), DAYS, &config).unwrap();
+    let barrier = Barrier::single(0.9, BarrierDirection::Down, KnockType::Out, true);
+    session
+        .price(&Product::call(100.0).with_barrier(barrier.clone()))
+        .unwrap();
+    session
+        .price(&Product::call(105.0).with_barrier(barrier))
+        .unwrap();
+    assert_eq!(session.num_simulations(), 2);
+}
+
+#[test]
+fn test_greeks_of_a_call_match_black_scholes() {
+    let config = SimulationConfig::new(20_000).with_seed(8);
+    let session = PricingSession::new(single_stock(), DAYS, &config).unwrap();
+    let greeks = session.greeks(&Product::call(100.0)).unwrap();
+
+    let time = DAYS as f64 / 365.0;
+    let analytic = |spot: f64, volatility: f64, rate: f64| {
+        black_scholes_price(spot, 100.0, volatility, rate, time, OptionType::Call)
+    };
+    let delta = (analytic(100.01, 0.2, 0.05) - analytic(99.99, 0.2, 0.05)) / 0.02;
+    let vega = (analytic(100.0, 0.2001, 0.05) - analytic(100.0, 0.1999, 0.05)) / 0.0002;
+    let rho = (analytic(100.0, 0.2, 0.0501) - analytic(100.0, 0.2, 0.0499)) / 0.0002;
+    assert!(
+        (greeks.delta - delta).abs() < 0.02,
+        "Delta {} vs {}",
+        greeks.delta,
+        delta
+    );
+    assert!(
+        greeks.gamma > 0.0,
+        "Gamma {} should be positive",
+        greeks.gamma
+    );
+    assert!(
+        (greeks.vega - vega).abs() < 0.05 * vega,
+        "Vega {} vs {}",
+        greeks.vega,
+        vega
+    );
+    assert!(
+        (greeks.rho - rho).abs() < 0.05 * rho,
+        "Rho {} vs {}",
+        greeks.rho,
+        rho
+    );
+
+    // The bumped paths are recorded for further products
+    let simulations = session.num_simulations();
+    session.greeks(&Product::call(110.0)).unwrap();
+    assert_eq!(session.num_simulations(), simulations);
+}
+
+#[test]
+fn test_fx_greeks_of_an_fx_option_match_garman_kohlhagen() {
+    let fx = |spot: f64, volatility: f64| {
+        Underlying::new("EURUSD".to_string(), spot, volatility)
+            .with_asset_class(AssetClass::Fx { foreign_rate: 0.03 })
+    };
+    let market = MarketSnapshot::new(vec![fx(1.10, 0.10)], DMatrix::identity(1, 1), 0.05);
+    let config = SimulationConfig::new(20_000).with_seed(8);
+    let session = PricingSession::new(market, DAYS, &config).unwrap();
+    let greeks = session.greeks(&Product::call(1.12)).unwrap();
+
+    let time = DAYS as f64 / 365.0;
+    let analytic = |spot: f64, volatility: f64| {
+        let rate_curve = RateCurve::flat(0.05);
+        black_scholes_price_with_dividends(
+            &fx(spot, volatility),
+            1.12,
+            &rate_curve,
+            time,
+            OptionType::Call,
+            DayCountConvention::Calendar365,
+        )
+        .unwrap()
+    };
+    let fx_delta = (analytic(1.1001, 0.10) - analytic(1.0999, 0.10)) / 0.0002;
+    let fx_vega = (analytic(1.10, 0.1001) - analytic(1.10, 0.0999)) / 0.0002;
+    assert_eq!(greeks.fx_spot_price, Some(1.10));
+    assert!(
+        (greeks.fx_delta - fx_delta).abs() < 0.02,
+        "FX delta {} vs {}",
+        greeks.fx_delta,
+        fx_delta
+    );
+    assert!(
+        (greeks.fx_vega - fx_vega).abs() < 0.05 * fx_vega,
+        "FX vega {} vs {}",
+        greeks.fx_vega,
+        fx_vega
+    );
+    // The exchange rate is the only underlying, so its bumps are those of the spot and volatility
+    assert!((greeks.fx_delta - greeks.delta).abs() < 1e-9);
+    assert!((greeks.fx_vega - greeks.vega).abs() < 1e-9);
+}
+
+#[test]
+fn test_fx_vega_of_a_quanto_call_matches_black_scholes() {
+    let nikkei = |fx_volatility: f64| {
+        Underlying::new("NIKKEI".to_string(), 100.0, 0.25).with_quanto(Quanto::new(
+            0.001,
+            fx_volatility,
+            -0.4,
+        ))
+    };
+    let market = MarketSnapshot::new(vec![nikkei(0.12)], DMatrix::identity(1, 1), 0.05);
+    let config = SimulationConfig::new(20_000).with_seed(8);
+    let session = PricingSession::new(market, DAYS, &config).unwrap();
+    let greeks = session.greeks(&Product::call(100.0)).unwrap();
+
+    let time = DAYS as f64 / 365.0;
+    let analytic = |fx_volatility: f64| {
+        let rate_curve = RateCurve::flat(0.05);
+        black_scholes_price_with_dividends(
+            &nikkei(fx_volatility),
+            100.0,
+            &rate_curve,
+            time,
+            OptionType::Call,
+            DayCountConvention::Calendar365,
+        )
+        .unwrap()
+    };
+    let fx_vega = (analytic(0.1201) - analytic(0.1199)) / 0.0002;
+    // The payoff is paid at a fixed exchange rate, so only the FX volatility matters
+    assert_eq!(greeks.fx_spot_price, None);
+    assert_eq!(greeks.fx_delta, 0.0);
+    assert!(fx_vega > 0.0);
+    assert!(
+        (greeks.fx_vega - fx_vega).abs() < 0.05 * fx_vega,
+        "FX vega {} vs {}",
+        greeks.fx_vega,
+        fx_vega
+    );
+
+    // Without exposure to exchange rates, the FX Greeks vanish
+    let session = PricingSession::new(single_stock(), DAYS, &config).unwrap();
+    let greeks = session.greeks(&Product::call(100.0)).unwrap();
+    assert_eq!((greeks.fx_delta, greeks.fx_vega), (0.0, 0.0));
+}
+
+#[test]
+fn test_ladder_keeps_relative_barriers_at_the_session_spot() {
+    let session = PricingSession::new(single_stock(), DAYS, &SimulationConfig::new(5_000)).unwrap();
+    let down_and_out = Product::call(100.0).with_barrier(Barrier::single(
+        0.9,
+        BarrierDirection::Down,
+        KnockType::Out,
+        true,
+    ));
+    let ladder = session
+        .ladder(&down_and_out, &[-0.2, -0.05, 0.0, 0.05])
+        .unwrap();
+    // At 20% down, the spot starts below the barrier at 90 and the option is knocked out
+    assert_eq!(ladder[0].price, 0.0);
+    assert!(ladder.windows(2).all(|pair| pair[0].price < pair[1].price));
+    assert_eq!(ladder[2].price, session.price(&down_and_out).unwrap().price);
+}
+
+#[test]
+fn test_concurrent_requests_share_the_recorded_paths() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<PricingSession>();
+
+    let session = PricingSession::new(
+        two_asset_basket(),
+        DAYS,
+        &SimulationConfig::new(2_000).with_seed(12),
+    )
+    .unwrap();
+    let strikes = [90.0, 95.0, 100.0, 105.0, 110.0];
+    let sequential: Vec<f64> = strikes
+        .iter()
+        .map(|&strike_price| session.price(&Product::call(strike_price)).unwrap().price)
+        .collect();
+
+    let concurrent: Vec<f64> = std::thread::scope(|scope| {
+        let handles: Vec<_> = strikes
+            .iter()
+            .map(|&strike_price| {
+                let session = &session;
+                scope.spawn(move || session.price(&Product::call(strike_price)).unwrap().price)
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    });
+    assert_eq!(concurrent, sequential);
+    assert_eq!(session.num_simulations(), 1);
+}
+
+#[test]
+fn test_portfolio_greeks_add_up_across_cached_and_simulated_products() {
+    // The error tolerance would let full simulations stop at another path count
+    let config = SimulationConfig::new(5_000)
+        .with_seed(13)
+        .with_error_tolerance(ErrorTolerance::Relative(0.5))
+        .with_batch_paths(1_000);
+    let session = PricingSession::new(single_stock(), DAYS, &config).unwrap();
+    let knock_in = Product::call(100.0).with_barrier(Barrier::single(
+        0.9,
+        BarrierDirection::Down,
+        KnockType::In,
+        true,
+    ));
+    // A knock-out rebate paid at the hit is priced by a full simulation
+    let knock_out = Product::call(100.0).with_barrier(
+        Barrier::single(0.9, BarrierDirection::Down, KnockType::Out, true)
+            .with_rebate(Rebate::new(0.0, RebateTiming::AtHit)),
+    );
+    let portfolio = session
+        .portfolio_greeks(&[
+            (2.0, knock_in),
+            (2.0, knock_out),
+            (-2.0, Product::call(100.0)),
+        ])
+        .unwrap();
+
+    // In and out options make up the vanilla option on every path and bump
+    let total = &portfolio.total;
+    for value in [
+        total.pricing.price,
+        total.delta,
+        total.gamma,
+        total.vega,
+        total.rho,
+    ] {
+        assert!(value.abs() < 1e-9, "{:?}", total);
+    }
+    assert_eq!(total.pricing.num_paths, 5_000);
+    let delta: f64 = portfolio
+        .positions
+        .iter()
+        .zip([2.0, 2.0, -2.0])
+        .map(|(greeks, quantity)| quantity * greeks.delta)
+        .sum();
+    assert_eq!(total.delta, delta);
+}
+
+#[test]
+fn test_identical_products_are_priced_once() {
+    let config = SimulationConfig::new(2_000)
+        .with_seed(8)
+        .with_barrier_correction(BarrierCorrection::BrownianBridge);
+    let session = PricingSession::new(single_stock(), DAYS, &config).unwrap();
+    let barrier = Barrier::single(0.9, BarrierDirection::Down, KnockType::Out, true);
+    let first = session
+        .price(&Product::call(100.0).with_barrier(barrier.clone()))
+        .unwrap();
+    let second = session
+        .price(&Product::call(100.0).with_barrier(barrier.clone()))
+        .unwrap();
+    assert_eq!(session.num_simulations(), 1);
+    assert_eq!(session.num_memoized_results(), 1);
+    assert_eq!(first.price, second.price);
+    assert_eq!(first.std_error, second.std_error);
+
+    // The Greeks reuse the memoized price on the session market
+    session
+        .greeks(&Product::call(100.0).with_barrier(barrier.clone()))
+        .unwrap();
+    assert_eq!(session.num_memoized_results(), 7);
+
+    session.clear_memoized_results();
+    session
+        .price(&Product::call(100.0).with_barrier(barrier))
+        .unwrap();
+    assert_eq!(session.num_memoized_results(), 1);
+}
+
+#[test]
+fn test_fingerprints_identify_products_and_markets() {
+    let barrier = Barrier::single(0.9, BarrierDirection::Down, KnockType::Out, true);
+    let product = Product::call(100.0).with_barrier(barrier.clone());
+    assert_eq!(
+        product.fingerprint(),
+        Product::call(100.0).with_barrier(barrier).fingerprint()
+    );
+    assert_ne!(product.fingerprint(), Product::call(100.5).fingerprint());
+    assert_ne!(
+        Product::call(100.0).fingerprint(),
+        Product::put(100.0).fingerprint()
+    );
+
+    let market = single_stock();
+    assert_eq!(market.fingerprint(), market.clone().fingerprint());
+    let mut bumped = market.clone();
+    bumped.underlyings[0].spot_price += 1e-9;
+    assert_ne!(market.fingerprint(), bumped.fingerprint());
+    assert_ne!(market.fingerprint(), two_asset_basket().fingerprint());
+}
@@ -0,0 +1,114 @@
+use mcproton::closed_form::black_scholes_price;
+use mcproton::test_utils::{assert_within_std_errors, deterministic_config, single_stock};
+use mcproton::{
+    ErrorTolerance, McError, OptionType, Payoff, PricingResult, PricingWarning, Simulation,
+    SimulationConfig,
+};
+
+const DAYS: u32 = 90;
+
+fn call(strike_price: f64) -> Payoff {
+    Payoff::Vanilla {
+        strike_price,
+        option_type: OptionType::Call,
+    }
+}
+
+fn simulation(payoff: &Payoff, config: &SimulationConfig) -> Result<Simulation, McError> {
+    let market = single_stock();
+    Simulation::new(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        payoff,
+        market.risk_free_rate,
+        None,
+        config,
+    )
+}
+
+fn simulation_result(config: &SimulationConfig) -> PricingResult {
+    simulation(&call(105.0), config)
+        .unwrap()
+        .run()
+        .unwrap()
+        .unwrap()
+}
+
+#[test]
+fn test_polling_reports_progress_until_the_price_is_final() {
+    let config = deterministic_config(20_000).with_batch_paths(5_000);
+    let mut simulation = simulation(&call(105.0), &config).unwrap();
+    assert!(simulation.progress().estimate.is_none());
+    assert!(simulation.result().is_none());
+
+    let mut completed = Vec::new();
+    while !simulation.is_finished() {
+        let progress = simulation.poll().unwrap();
+        assert!(progress.estimate.is_some());
+        completed.push(progress.paths_completed);
+    }
+    assert_eq!(completed, [5_000, 10_000, 15_000, 20_000]);
+    assert_eq!(simulation.progress().fraction(), 1.0);
+    // Polling a finished simulation simulates nothing
+    assert_eq!(simulation.poll().unwrap().paths_completed, 20_000);
+
+    let result = simulation.result().unwrap();
+    let expected = black_scholes_price(
+        100.0,
+        105.0,
+        0.2,
+        0.05,
+        DAYS as f64 / 365.0,
+        OptionType::Call,
+    );
+    assert_within_std_errors(&result, expected, 4.0);
+    assert_eq!(result.num_paths, 20_000);
+    assert!(result.bounds.is_some());
+
+    // The same seed reproduces the result
+    let rerun = simulation_result(&config);
+    assert_eq!(rerun.price, result.price);
+}
+
+#[test]
+fn test_cancelled_simulation_keeps_its_estimate_without_a_result() {
+    let config = deterministic_config(100_000).with_batch_paths(1_000);
+    let mut simulation = simulation(&call(100.0), &config).unwrap();
+    simulation.poll().unwrap();
+    simulation.cancel();
+    assert!(simulation.is_finished() && simulation.is_cancelled());
+    let progress = simulation.poll().unwrap();
+    assert_eq!(progress.paths_completed, 1_000);
+    assert!((progress.fraction() - 0.01).abs() < 1e-12);
+    assert!(progress.estimate.unwrap().price > 0.0);
+    assert!(simulation.result().is_none());
+    assert!(simulation.run().unwrap().is_none());
+}
+
+#[test]
+fn test_error_tolerance_finishes_early() {
+    let config = deterministic_config(1_000_000)
+        .with_batch_paths(2_000)
+        .with_error_tolerance(ErrorTolerance::Absolute(0.2));
+    let result = simulation_result(&config);
+    assert!(result.std_error <= 0.2);
+    assert!(result.num_paths < 100_000);
+    assert!(!result
+        .warnings
+        .iter()
+        .any(|warning| matches!(warning, PricingWarning::ErrorToleranceNotMet { .. })));
+}
+
+#[test]
+fn test_invalid_inputs_are_rejected_before_simulating() {
+    let config = deterministic_config(1_000);
+    assert!(matches!(
+        simulation(&call(-1.0), &config),
+        Err(McError::InvalidProduct(_))
+    ));
+    assert!(matches!(
+        simulation(&call(100.0), &config.with_path_weights(vec![1.0; 1_000])),
+        Err(McError::InvalidPathWeights(_))
+    ));
+}
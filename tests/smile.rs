@@ -0,0 +1,188 @@
+use mcproton::closed_form::black_scholes_price;
+use mcproton::test_utils::{assert_within_std_errors, deterministic_config};
+use mcproton::{
+    price_option_with_config, Barrier, BarrierDirection, KnockType, LocalVolSurface,
+    LocalVolatility, McError, OptionType, PricingResult, SimulationConfig, Underlying,
+    VolatilitySmile,
+};
+use nalgebra::DMatrix;
+
+const DAYS: u32 = 90;
+const TIME: f64 = DAYS as f64 / 365.0;
+
+const SKEW: VolatilitySmile = VolatilitySmile::Quadratic {
+    atm_volatility: 0.20,
+    skew: -0.30,
+    curvature: 0.50,
+};
+
+fn price(
+    underlying: Underlying,
+    strike: f64,
+    option_type: OptionType,
+    rate: f64,
+    barrier: Option<&Barrier>,
+    config: &SimulationConfig,
+) -> PricingResult {
+    price_option_with_config(
+        &[underlying],
+        &DMatrix::identity(1, 1),
+        DAYS,
+        strike,
+        option_type,
+        rate,
+        barrier,
+        config,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_smiles_are_evaluated_in_log_moneyness() {
+    assert!((SKEW.implied_volatility(0.0) - 0.20).abs() < 1e-15);
+    assert!((SKEW.implied_volatility(-0.2) - (0.20 + 0.06 + 0.02)).abs() < 1e-15);
+
+    let svi = VolatilitySmile::Svi {
+        expiry: 0.5,
+        a: 0.01,
+        b: 0.1,
+        rho: -0.5,
+        m: 0.0,
+        sigma: 0.2,
+    };
+    assert!((svi.implied_volatility(0.0) - (0.03_f64 / 0.5).sqrt()).abs() < 1e-15);
+    assert!(svi.implied_volatility(-0.3) > svi.implied_volatility(0.3));
+
+    let underlying = Underlying::new("TEST".to_string(), 100.0, 0.25).with_smile(SKEW);
+    assert_eq!(underlying.smile_volatility(105.0, 105.0), Some(0.20));
+    assert_eq!(
+        Underlying::new("TEST".to_string(), 100.0, 0.25).smile_volatility(90.0, 100.0),
+        None
+    );
+}
+
+#[test]
+fn test_vanillas_are_priced_at_the_smile_volatility_of_their_strike() {
+    let config = deterministic_config(20_000);
+    let underlying = Underlying::new("TEST".to_string(), 100.0, 0.25)
+        .with_volatility_term_structure(vec![(30, 0.40)])
+        .with_smile(SKEW);
+    let forward = 100.0 * (0.05 * TIME).exp();
+    for strike in [85.0, 100.0, 115.0] {
+        let volatility = SKEW.implied_volatility((strike / forward).ln());
+        let result = price(
+            underlying.clone(),
+            strike,
+            OptionType::Put,
+            0.05,
+            None,
+            &config,
+        );
+        let expected = black_scholes_price(100.0, strike, volatility, 0.05, TIME, OptionType::Put);
+        assert_within_std_errors(&result, expected, 4.0);
+    }
+
+    // Barrier options are simulated with the volatility and term structure
+    let barrier = Barrier::single(0.5, BarrierDirection::Down, KnockType::Out, true);
+    let without_smile = Underlying {
+        smile: None,
+        ..underlying.clone()
+    };
+    let price_of = |underlying| {
+        price(
+            underlying,
+            85.0,
+            OptionType::Put,
+            0.05,
+            Some(&barrier),
+            &config,
+        )
+        .price
+    };
+    assert_eq!(price_of(underlying), price_of(without_smile));
+}
+
+#[test]
+fn test_local_volatilities_of_a_smile_reprice_its_vanillas() {
+    let smile = VolatilitySmile::Svi {
+        expiry: TIME,
+        a: 0.006,
+        b: 0.04,
+        rho: -0.6,
+        m: 0.02,
+        sigma: 0.15,
+    };
+    // A flat smile has flat local volatilities
+    let flat = VolatilitySmile::Quadratic {
+        atm_volatility: 0.2,
+        skew: 0.0,
+        curvature: 0.0,
+    };
+    assert!((flat.local_volatility(0.3, 0.5) - 0.2).abs() < 1e-6);
+    // Local skews are steeper than implied ones
+    assert!(smile.local_volatility(-0.2, TIME) > smile.implied_volatility(-0.2));
+
+    let strikes = (0..=80).map(|i| 50.0 + 2.0 * i as f64).collect();
+    let maturities = (1..=10).map(|i| TIME * i as f64 / 10.0).collect();
+    let surface = LocalVolSurface::from_smile(&smile, 100.0, strikes, maturities);
+    let config = deterministic_config(20_000).with_model(LocalVolatility::new(vec![surface]));
+    let underlying = Underlying::new("TEST".to_string(), 100.0, 0.20);
+    for strike in [85.0, 100.0, 110.0] {
+        let result = price(
+            underlying.clone(),
+            strike,
+            OptionType::Put,
+            0.0,
+            None,
+            &config,
+        );
+        let volatility = smile.implied_volatility((strike / 100.0_f64).ln());
+        let expected = black_scholes_price(100.0, strike, volatility, 0.0, TIME, OptionType::Put);
+        assert_within_std_errors(&result, expected, 4.0);
+    }
+}
+
+#[test]
+fn test_invalid_smiles_are_rejected() {
+    let config = deterministic_config(100);
+    for smile in [
+        VolatilitySmile::Svi {
+            expiry: 0.5,
+            a: 0.01,
+            b: 0.1,
+            rho: -1.2,
+            m: 0.0,
+            sigma: 0.2,
+        },
+        VolatilitySmile::Svi {
+            expiry: 0.5,
+            a: -0.1,
+            b: 0.1,
+            rho: 0.0,
+            m: 0.0,
+            sigma: 0.2,
+        },
+        VolatilitySmile::Quadratic {
+            atm_volatility: f64::NAN,
+            skew: 0.0,
+            curvature: 0.0,
+        },
+    ] {
+        let underlying = Underlying::new("TEST".to_string(), 100.0, 0.2).with_smile(smile);
+        let result = price_option_with_config(
+            &[underlying],
+            &DMatrix::identity(1, 1),
+            DAYS,
+            100.0,
+            OptionType::Call,
+            0.05,
+            None,
+            &config,
+        );
+        assert!(
+            matches!(result, Err(McError::InvalidSmile { .. })),
+            "{:?}",
+            result
+        );
+    }
+}
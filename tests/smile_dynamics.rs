@@ -0,0 +1,113 @@
+use mcproton::closed_form::black_scholes_price;
+use mcproton::test_utils::single_stock;
+use mcproton::{
+    stress_test, MarketSnapshot, OptionType, PricingSession, Product, SimulationConfig,
+    SmileDynamics, StressGrid, VolatilitySmile,
+};
+
+const DAYS: u32 = 60;
+
+const SKEW: VolatilitySmile = VolatilitySmile::Quadratic {
+    atm_volatility: 0.20,
+    skew: -0.30,
+    curvature: 0.50,
+};
+
+fn skewed_market(smile_dynamics: SmileDynamics) -> MarketSnapshot {
+    let mut market = single_stock();
+    market.underlyings[0] = market.underlyings[0]
+        .clone()
+        .with_smile(SKEW)
+        .with_smile_dynamics(smile_dynamics);
+    market
+}
+
+#[test]
+fn test_shifted_smiles_move_along_the_log_moneyness() {
+    let svi = VolatilitySmile::Svi {
+        expiry: 0.5,
+        a: 0.02,
+        b: 0.1,
+        rho: -0.4,
+        m: 0.05,
+        sigma: 0.2,
+    };
+    for smile in [SKEW, svi] {
+        let shifted = smile.shifted(0.07);
+        for &k in &[-0.3, -0.05, 0.0, 0.2] {
+            assert!(
+                (shifted.implied_volatility(k) - smile.implied_volatility(k + 0.07)).abs() < 1e-12
+            );
+        }
+    }
+}
+
+#[test]
+fn test_sticky_strike_keeps_the_volatility_of_the_strike() {
+    let time = DAYS as f64 / 365.0;
+    let forward = 100.0 * (0.05 * time).exp();
+    let strike_volatility = SKEW.implied_volatility((100.0 / forward).ln());
+    let analytic = |spot: f64, volatility: f64| {
+        black_scholes_price(spot, 100.0, volatility, 0.05, time, OptionType::Call)
+    };
+    let config = SimulationConfig::new(20_000).with_seed(8);
+    let delta = |smile_dynamics| {
+        let session = PricingSession::new(skewed_market(smile_dynamics), DAYS, &config).unwrap();
+        session.greeks(&Product::call(100.0)).unwrap().delta
+    };
+    let sticky_strike = delta(SmileDynamics::StickyStrike);
+    let sticky_delta = delta(SmileDynamics::StickyDelta);
+
+    // Sticky strike: the Black-Scholes delta at the volatility of the strike
+    let black_scholes_delta =
+        (analytic(100.01, strike_volatility) - analytic(99.99, strike_volatility)) / 0.02;
+    assert!(
+        (sticky_strike - black_scholes_delta).abs() < 0.02,
+        "Sticky strike delta {} vs {}",
+        sticky_strike,
+        black_scholes_delta
+    );
+    // Sticky delta: the volatility of the strike rises with the spot on a negative skew,
+    // adding vega times its slope
+    let vega = (analytic(100.0, strike_volatility + 1e-4)
+        - analytic(100.0, strike_volatility - 1e-4))
+        / 2e-4;
+    let smile_slope = (SKEW.implied_volatility((100.0 / (forward * 1.01)).ln())
+        - SKEW.implied_volatility((100.0 / (forward * 0.99)).ln()))
+        / 2.0;
+    let expected_difference = vega * smile_slope;
+    assert!(expected_difference > 0.03);
+    assert!(
+        (sticky_delta - sticky_strike - expected_difference).abs() < 0.2 * expected_difference,
+        "Delta difference {} vs {}",
+        sticky_delta - sticky_strike,
+        expected_difference
+    );
+}
+
+#[test]
+fn test_stress_scenarios_remark_the_smile() {
+    let config = SimulationConfig::new(5_000).with_seed(3);
+    let grid = StressGrid::new().with_spot_shifts(vec![-0.1, 0.0, 0.1]);
+    let prices = |smile_dynamics| {
+        stress_test(
+            &skewed_market(smile_dynamics),
+            DAYS,
+            &Product::put(90.0),
+            &grid,
+            &config,
+        )
+        .unwrap()
+        .results
+        .iter()
+        .map(|result| result.price)
+        .collect::<Vec<_>>()
+    };
+    let sticky_strike = prices(SmileDynamics::StickyStrike);
+    let sticky_delta = prices(SmileDynamics::StickyDelta);
+    assert_eq!(sticky_strike[1], sticky_delta[1]);
+    // Sticky delta moves the smile with the spot: as the spot falls, the low strike moves
+    // towards the money and down the skew, while sticky strike keeps its volatility
+    assert!(sticky_delta[0] < sticky_strike[0]);
+    assert!(sticky_delta[2] > sticky_strike[2]);
+}
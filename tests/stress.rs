@@ -0,0 +1,110 @@
+use mcproton::test_utils::{
+    deterministic_config, single_stock, three_asset_basket, two_asset_basket,
+};
+use mcproton::{
+    stress_test, Barrier, BarrierDirection, BarrierType, CorrelationStress, KnockType, McError,
+    Product, StressGrid, StressScenario,
+};
+
+const DAYS: u32 = 60;
+
+#[test]
+fn test_spot_and_volatility_ladder() {
+    let grid = StressGrid::new()
+        .with_spot_shifts(vec![-0.1, 0.0, 0.1])
+        .with_volatility_shifts(vec![0.0, 0.05]);
+    let result = stress_test(
+        &single_stock(),
+        DAYS,
+        &Product::call(100.0),
+        &grid,
+        &deterministic_config(5_000),
+    )
+    .unwrap();
+    assert_eq!(result.results.len(), 6);
+    assert_eq!(
+        result.scenarios[3],
+        StressScenario {
+            spot_shift: 0.0,
+            volatility_shift: 0.05,
+            rate_shift: 0.0,
+            correlation_shift: 0.0,
+        }
+    );
+    // The unshocked scenario is the base, and all scenarios share their random numbers
+    assert_eq!(result.get(1, 0, 0, 0).price, result.base.price);
+    let pnl = result.pnl_matrix(0, 0);
+    assert_eq!(pnl.shape(), (3, 2));
+    assert_eq!(pnl[(1, 0)], 0.0);
+    for volatility_index in 0..2 {
+        assert!(pnl[(0, volatility_index)] < pnl[(1, volatility_index)]);
+        assert!(pnl[(1, volatility_index)] < pnl[(2, volatility_index)]);
+    }
+    for spot_index in 0..3 {
+        assert!(pnl[(spot_index, 0)] < pnl[(spot_index, 1)]);
+    }
+}
+
+#[test]
+fn test_correlation_stress_of_a_worst_of_barrier() {
+    let mut barrier = Barrier::single(0.85, BarrierDirection::Down, KnockType::Out, true);
+    barrier.barrier_type = BarrierType::WorstOf;
+    barrier.underlying_indices = vec![0, 1];
+    let product = Product::call(100.0).with_barrier(barrier);
+    let grid = StressGrid::new()
+        .with_spot_shifts(vec![0.0, -0.1])
+        .with_correlation_shifts(vec![-0.3, 0.0, 0.3]);
+    let result = stress_test(
+        &two_asset_basket(),
+        DAYS,
+        &product,
+        &grid,
+        &deterministic_config(5_000),
+    )
+    .unwrap();
+
+    // Higher correlation makes the worst performer less likely to knock the option out
+    let prices: Vec<f64> = (0..3).map(|i| result.get(0, 0, 0, i).price).collect();
+    assert!(
+        prices[0] < prices[1] && prices[1] < prices[2],
+        "{:?}",
+        prices
+    );
+    // The barrier stays at 85% of the unshocked spots, so a spot drop knocks out more paths
+    assert!(result.get(1, 0, 0, 1).price < result.base.price);
+}
+
+#[test]
+fn test_invalid_stressed_markets_are_rejected() {
+    // Pairwise correlations of -0.6 between three underlyings are not positive semi-definite
+    let grid = StressGrid::new().with_correlation_shifts(vec![-1.0]);
+    let result = stress_test(
+        &three_asset_basket(),
+        DAYS,
+        &Product::call(100.0),
+        &grid,
+        &deterministic_config(1_000),
+    );
+    assert!(matches!(result, Err(McError::InvalidCorrelationMatrix(_))));
+    // Stressing the principal component instead saturates at independent underlyings
+    let grid = grid.with_correlation_stress(CorrelationStress::PrincipalComponent);
+    let result = stress_test(
+        &three_asset_basket(),
+        DAYS,
+        &Product::call(100.0),
+        &grid,
+        &deterministic_config(1_000),
+    );
+    let result = result.unwrap();
+    assert_eq!(result.scenarios[0].correlation_shift, -1.0);
+
+    let grid = StressGrid::new().with_volatility_shifts(vec![-0.5]);
+    let result = stress_test(
+        &single_stock(),
+        DAYS,
+        &Product::call(100.0),
+        &grid,
+        &deterministic_config(1_000),
+    );
+    assert!(matches!(result, Err(McError::InvalidVolatility { .. })));
+}
@@ -0,0 +1,175 @@
+use mcproton::closed_form::{black_scholes_price, norm_cdf};
+use mcproton::test_utils::{
+    assert_within_std_errors, deterministic_config, single_stock, two_asset_basket,
+};
+use mcproton::{
+    price_payoff, price_terminal_payoff, McError, OptionType, Payoff, SimulationConfig,
+    VolatilitySmile,
+};
+use nalgebra::{DMatrix, DVector};
+
+const DAYS: u32 = 90;
+
+#[test]
+fn test_vectorized_call_matches_the_path_by_path_price() {
+    let market = single_stock();
+    let config = deterministic_config(5_000)
+        .with_antithetic(true)
+        .with_control_variate(false)
+        .with_batch_paths(300);
+    let call = |prices: &DMatrix<f64>| prices.column(0).map(|price| (price - 100.0).max(0.0));
+    let result = price_terminal_payoff(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        &call,
+        market.risk_free_rate.clone(),
+        &config,
+    )
+    .unwrap();
+
+    let expected = price_payoff(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        &Payoff::Vanilla {
+            strike_price: 100.0,
+            option_type: OptionType::Call,
+        },
+        market.risk_free_rate.clone(),
+        None,
+        &config,
+    )
+    .unwrap();
+    assert_eq!(result.price, expected.price);
+    assert_eq!(result.std_error, expected.std_error);
+    assert_eq!(result.num_paths, 5_000);
+}
+
+#[test]
+fn test_exchange_option_matches_margrabe() {
+    let market = two_asset_basket();
+    // Option to exchange the second stock for the first
+    let exchange = |prices: &DMatrix<f64>| {
+        DVector::from_iterator(
+            prices.nrows(),
+            prices.row_iter().map(|row| (row[0] - row[1]).max(0.0)),
+        )
+    };
+    let result = price_terminal_payoff(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        &exchange,
+        market.risk_free_rate.clone(),
+        &deterministic_config(100_000),
+    )
+    .unwrap();
+
+    let time = DAYS as f64 / 365.0;
+    let volatility = (0.20_f64.powi(2) + 0.25_f64.powi(2) - 2.0 * 0.5 * 0.20 * 0.25).sqrt();
+    let d1 = 0.5 * volatility * time.sqrt();
+    let d2 = d1 - volatility * time.sqrt();
+    let margrabe = 100.0 * norm_cdf(d1) - 100.0 * norm_cdf(d2);
+    assert_within_std_errors(&result, margrabe, 4.0);
+}
+
+#[test]
+fn test_payoffs_of_the_wrong_length_are_rejected() {
+    let market = single_stock();
+    let short = |prices: &DMatrix<f64>| DVector::zeros(prices.nrows() - 1);
+    let result = price_terminal_payoff(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        &short,
+        market.risk_free_rate.clone(),
+        &deterministic_config(1_000),
+    );
+    assert!(matches!(result, Err(McError::InvalidProduct(_))));
+}
+
+#[test]
+fn test_moment_matching_fixes_the_forward_and_the_volatility_of_every_batch() {
+    let mut market = two_asset_basket();
+    market.underlyings[1] = market.underlyings[1]
+        .clone()
+        .with_smile(VolatilitySmile::Quadratic {
+            atm_volatility: 0.30,
+            skew: -0.2,
+            curvature: 0.0,
+        });
+    let config = deterministic_config(2_000)
+        .with_batch_paths(500)
+        .with_moment_matching(true);
+    let time = DAYS as f64 / 365.0;
+    let discount_factor = (-0.05 * time).exp();
+    let price = |payoff: &dyn Fn(&DMatrix<f64>) -> DVector<f64>| {
+        price_terminal_payoff(
+            &market.underlyings,
+            &market.correlation_matrix,
+            DAYS,
+            &payoff,
+            market.risk_free_rate.clone(),
+            &config,
+        )
+        .unwrap()
+        .price
+    };
+
+    // The discounted mean of each batch is the spot
+    let first = |prices: &DMatrix<f64>| prices.column(0).clone_owned();
+    assert!((price(&first) - 100.0).abs() < 1e-9);
+    // Every batch has the target standard deviation of its log returns
+    let log_std_dev = |column: usize| {
+        move |prices: &DMatrix<f64>| {
+            let logs = prices.column(column).map(f64::ln);
+            let mean = logs.mean();
+            let variance = logs.map(|log| (log - mean).powi(2)).mean();
+            DVector::from_element(prices.nrows(), variance.sqrt())
+        }
+    };
+    assert!((price(&log_std_dev(0)) - 0.20 * time.sqrt() * discount_factor).abs() < 1e-9);
+    // The smile sets the ATM volatility
+    assert!((price(&log_std_dev(1)) - 0.30 * time.sqrt() * discount_factor).abs() < 1e-9);
+}
+
+#[test]
+fn test_moment_matching_reduces_the_error_of_small_samples() {
+    let market = single_stock();
+    let call = |prices: &DMatrix<f64>| prices.column(0).map(|price| (price - 100.0).max(0.0));
+    let price = |config: &SimulationConfig| {
+        price_terminal_payoff(
+            &market.underlyings,
+            &market.correlation_matrix,
+            DAYS,
+            &call,
+            market.risk_free_rate.clone(),
+            config,
+        )
+        .unwrap()
+    };
+    let analytic = black_scholes_price(
+        100.0,
+        100.0,
+        0.20,
+        0.05,
+        DAYS as f64 / 365.0,
+        OptionType::Call,
+    );
+    let matched = price(&deterministic_config(1_000).with_moment_matching(true));
+    assert_within_std_errors(&matched, analytic, 4.0);
+
+    // Over many small runs, the matched prices scatter less around the analytic price
+    let squared_error = |moment_matching: bool| {
+        (0..50)
+            .map(|seed| {
+                let config = SimulationConfig::new(200)
+                    .with_seed(seed)
+                    .with_moment_matching(moment_matching);
+                (price(&config).price - analytic).powi(2)
+            })
+            .sum::<f64>()
+    };
+    assert!(squared_error(true) < 0.5 * squared_error(false));
+}
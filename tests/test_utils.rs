@@ -0,0 +1,39 @@
+use mcproton::closed_form::black_scholes_price;
+use mcproton::{price_option_with_config, OptionType};
+use mcproton::test_utils::{
+    assert_within_std_errors, deterministic_config, is_within_std_errors, single_stock, three_asset_basket,
+};
+
+#[test]
+fn test_deterministic_config_is_reproducible() {
+    let market = single_stock();
+    let config = deterministic_config(2_000);
+    let first = price_option_with_config(
+        &market.underlyings, &market.correlation_matrix, 30, 100.0, OptionType::Call, &market.risk_free_rate, None, &config,
+    ).unwrap();
+    let second = price_option_with_config(
+        &market.underlyings, &market.correlation_matrix, 30, 100.0, OptionType::Call, &market.risk_free_rate, None, &config,
+    ).unwrap();
+    assert_eq!(first.price, second.price);
+    assert_eq!(first.std_error, second.std_error);
+}
+
+#[test]
+fn test_vanilla_within_std_errors_of_black_scholes() {
+    let market = single_stock();
+    let result = price_option_with_config(
+        &market.underlyings, &market.correlation_matrix, 90, 95.0, OptionType::Put, &market.risk_free_rate, None,
+        &deterministic_config(20_000),
+    ).unwrap();
+    let analytic = black_scholes_price(100.0, 95.0, 0.20, 0.05, 90.0 / 365.0, OptionType::Put);
+    assert_within_std_errors(&result, analytic, 4.0);
+    assert!(!is_within_std_errors(&result, analytic + 1.0, 4.0));
+}
+
+#[test]
+fn test_canned_snapshots_are_consistent() {
+    let market = three_asset_basket();
+    assert_eq!(market.underlyings.len(), 3);
+    assert_eq!(market.correlation_matrix.nrows(), 3);
+    assert!(market.correlation_matrix.clone().cholesky().is_some());
+}
@@ -0,0 +1,78 @@
+use mcproton::test_utils::{deterministic_config, single_stock, two_asset_basket};
+use mcproton::{
+    tornado_report, Barrier, BarrierDirection, BarrierType, KnockType, McError, Product,
+    TornadoBumps, TornadoInput,
+};
+
+const DAYS: u32 = 90;
+
+#[test]
+fn test_call_is_driven_by_the_spot() {
+    let report = tornado_report(
+        &single_stock(),
+        DAYS,
+        &Product::call(100.0),
+        &TornadoBumps::default(),
+        &deterministic_config(5_000),
+    )
+    .unwrap();
+    let inputs: Vec<TornadoInput> = report.bars.iter().map(|bar| bar.input).collect();
+    assert_eq!(inputs.len(), 4);
+    assert_eq!(inputs[0], TornadoInput::Spot);
+    assert!(report.bar(TornadoInput::Correlation).is_none());
+    assert!(report.bar(TornadoInput::BarrierLevel).is_none());
+    assert!(report
+        .bars
+        .windows(2)
+        .all(|pair| pair[0].swing() >= pair[1].swing()));
+
+    // A call gains with the spot, volatility and rates, and loses with the dividend yield
+    for (input, down, up) in report.impacts() {
+        match input {
+            TornadoInput::DividendYield => assert!(down > 0.0 && up < 0.0, "{:?}", input),
+            _ => assert!(down < 0.0 && up > 0.0, "{:?}", input),
+        }
+    }
+}
+
+#[test]
+fn test_worst_of_barrier_perturbs_correlation_and_barrier_level() {
+    let barrier = Barrier::multi(
+        0.8,
+        BarrierDirection::Down,
+        KnockType::Out,
+        BarrierType::WorstOf,
+        true,
+        vec![0, 1],
+    )
+    .unwrap();
+    let product = Product::call(100.0).with_barrier(barrier);
+    let report = tornado_report(
+        &two_asset_basket(),
+        DAYS,
+        &product,
+        &TornadoBumps::new().with_correlation(0.2),
+        &deterministic_config(5_000),
+    )
+    .unwrap();
+    assert_eq!(report.bars.len(), 6);
+    let correlation = report.bar(TornadoInput::Correlation).unwrap();
+    assert_eq!(correlation.bump, 0.2);
+    // Higher correlation makes a knock-out by either underlying less likely
+    assert!(correlation.up.price > correlation.down.price);
+    // A higher knock-out level knocks out more paths
+    let barrier_level = report.bar(TornadoInput::BarrierLevel).unwrap();
+    assert!(barrier_level.up.price < barrier_level.down.price);
+}
+
+#[test]
+fn test_volatility_bump_below_zero_is_rejected() {
+    let result = tornado_report(
+        &single_stock(),
+        DAYS,
+        &Product::call(100.0),
+        &TornadoBumps::new().with_volatility(0.3),
+        &deterministic_config(100),
+    );
+    assert!(matches!(result, Err(McError::InvalidVolatility { .. })));
+}
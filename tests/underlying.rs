@@ -1,4 +1,9 @@
-use mcproton::Underlying;
+use mcproton::closed_form::{black_scholes_price, black_scholes_price_with_dividends, norm_cdf};
+use mcproton::{
+    price_option_with_config, AssetClass, Barrier, BarrierDirection, DayCountConvention, Dividend, KnockType,
+    McError, OptionType, Quanto, RateCurve, SimulationConfig, Underlying, VarianceTime, VolatilityEvent,
+};
+use nalgebra::DMatrix;
 
 #[test]
 fn test_underlying_creation() {
@@ -8,3 +13,346 @@ fn test_underlying_creation() {
     assert_eq!(underlying.volatility, 0.20);
 }
 
+
+#[test]
+fn test_underlying_without_dividends_has_spot_as_prepaid_forward() {
+    let underlying = Underlying::new("TEST".to_string(), 100.0, 0.20);
+    assert_eq!(underlying.dividend_yield, 0.0);
+    assert!(underlying.dividends.is_empty());
+    assert_eq!(underlying.prepaid_forward(&RateCurve::flat(0.05), 1.0, DayCountConvention::Calendar365), 100.0);
+}
+
+#[test]
+fn test_prepaid_forward_with_dividends() {
+    let underlying = Underlying::new("TEST".to_string(), 100.0, 0.20)
+        .with_dividend_yield(0.01)
+        .with_dividends(vec![
+            Dividend::Proportional { day: 146, ratio: 0.02 },
+            Dividend::Cash { day: 73, amount: 1.5 },
+            Dividend::Cash { day: 500, amount: 1.5 }, // After expiry
+        ]);
+    assert_eq!(underlying.dividends[0].day(), 73);
+    assert!(underlying.has_cash_dividends());
+
+    let expected = (100.0 * (-0.01 * 0.2_f64).exp() - 1.5 * (-0.05 * 0.2_f64).exp())
+        * 0.98
+        * (-0.01 * 0.8_f64).exp();
+    let prepaid_forward = underlying.prepaid_forward(&RateCurve::flat(0.05), 1.0, DayCountConvention::Calendar365);
+    assert!((prepaid_forward - expected).abs() < 1e-12, "{} != {}", prepaid_forward, expected);
+}
+
+#[test]
+fn test_dividends_lower_call_prices() {
+    let correlation = DMatrix::from_row_slice(1, 1, &[1.0]);
+    let config = SimulationConfig::new(20_000).with_seed(5).with_antithetic(true);
+    let price = |underlying: Underlying| {
+        price_option_with_config(&[underlying], &correlation, 365, 100.0, OptionType::Call, 0.05, None, &config).unwrap()
+    };
+
+    // Continuous yield: matches Black-Scholes on the prepaid forward
+    let with_yield = Underlying::new("TEST".to_string(), 100.0, 0.20).with_dividend_yield(0.03);
+    let analytic = black_scholes_price_with_dividends(&with_yield, 100.0, &RateCurve::flat(0.05), 1.0, OptionType::Call, DayCountConvention::Calendar365)
+        .expect("Yields keep prices lognormal");
+    let result = price(with_yield);
+    assert!(
+        (result.price - analytic).abs() < 4.0 * result.std_error,
+        "Price {} with dividend yield should match Black-Scholes {}",
+        result.price,
+        analytic
+    );
+
+    // Cash dividend: the price drops on the ex-day, so the call loses value
+    let no_dividends = price(Underlying::new("TEST".to_string(), 100.0, 0.20));
+    let with_cash = price(
+        Underlying::new("TEST".to_string(), 100.0, 0.20).with_dividends(vec![Dividend::Cash { day: 180, amount: 5.0 }]),
+    );
+    assert!(
+        with_cash.price < no_dividends.price - 4.0 * (with_cash.std_error + no_dividends.std_error),
+        "Cash dividend {} should lower the call price {}",
+        with_cash.price,
+        no_dividends.price
+    );
+}
+
+#[test]
+fn test_volatility_term_structure() {
+    let underlying = Underlying::new("TEST".to_string(), 100.0, 0.20)
+        .with_volatility_term_structure(vec![(30, 0.40), (90, 0.25)]);
+    assert_eq!(underlying.volatility_on(1), 0.40);
+    assert_eq!(underlying.volatility_on(30), 0.40);
+    assert_eq!(underlying.volatility_on(31), 0.25);
+    assert_eq!(underlying.volatility_on(365), 0.25); // Last volatility extends flat
+
+    let calendar = VarianceTime::Calendar;
+    let day_count = DayCountConvention::Calendar365;
+    let expected = (0.40 * 0.40 * 30.0 + 0.25 * 0.25 * 150.0) / 365.0;
+    let variance = underlying.integrated_variance(0, 180, day_count, calendar);
+    assert!((variance - expected).abs() < 1e-15, "{} != {}", variance, expected);
+    assert_eq!(underlying.integrated_variance(40, 40, day_count, calendar), 0.0);
+
+    // Without a term structure, the flat volatility applies
+    let flat = Underlying::new("TEST".to_string(), 100.0, 0.20);
+    assert!((flat.integrated_variance(10, 83, day_count, calendar) - 0.04 * 0.2).abs() < 1e-15);
+}
+
+#[test]
+fn test_term_structure_vanilla_matches_black_scholes_at_average_volatility() {
+    let correlation = DMatrix::from_row_slice(1, 1, &[1.0]);
+    let underlying = Underlying::new("TEST".to_string(), 100.0, 0.20)
+        .with_volatility_term_structure(vec![(30, 0.40), (90, 0.25)]);
+    // Daily steps through the buckets, enforced by an unreachable barrier
+    let barrier = Barrier::single(10.0, BarrierDirection::Up, KnockType::Out, true); // relative
+    let config = SimulationConfig::new(10_000).with_seed(6).with_antithetic(true);
+    let result = price_option_with_config(&[underlying], &correlation, 90, 100.0, OptionType::Call, 0.05, Some(&barrier), &config).unwrap();
+
+    let volatility = ((0.40 * 0.40 * 30.0 + 0.25 * 0.25 * 60.0) / 90.0_f64).sqrt();
+    let analytic = black_scholes_price(100.0, 100.0, volatility, 0.05, 90.0 / 365.0, OptionType::Call);
+    assert!(
+        (result.price - analytic).abs() < 4.0 * result.std_error,
+        "Price {} should match Black-Scholes {} at the average volatility",
+        result.price,
+        analytic
+    );
+}
+
+#[test]
+fn test_start_values_override_spot_prices() {
+    let correlation = DMatrix::from_row_slice(1, 1, &[1.0]);
+    let barrier = Barrier::single(0.9, BarrierDirection::Down, KnockType::Out, true);
+    let price = |spot: f64, config: &SimulationConfig| {
+        let underlying = Underlying::new("TEST".to_string(), spot, 0.20);
+        price_option_with_config(&[underlying], &correlation, 60, 100.0, OptionType::Call, 0.05, Some(&barrier), config).unwrap()
+    };
+    let config = SimulationConfig::new(2_000).with_seed(3);
+    let shocked = price(100.0, &config.clone().with_start_values(vec![110.0]));
+    let respotted = price(110.0, &config);
+    assert_eq!(shocked.price, respotted.price);
+    assert_eq!(shocked.bounds, respotted.bounds);
+}
+
+#[test]
+fn test_start_values_must_match_underlyings() {
+    let underlying = Underlying::new("TEST".to_string(), 100.0, 0.20);
+    let correlation = DMatrix::from_row_slice(1, 1, &[1.0]);
+    let config = SimulationConfig::new(100).with_start_values(vec![100.0, 50.0]);
+    let result = price_option_with_config(&[underlying], &correlation, 30, 100.0, OptionType::Call, 0.05, None, &config);
+    assert_eq!(result.unwrap_err(), McError::InvalidStartValues { expected: 1, actual: 2 });
+}
+
+#[test]
+fn test_marked_forward_interpolates_log_linearly() {
+    let underlying = Underlying::new("TEST".to_string(), 100.0, 0.20)
+        .with_forward_curve(vec![(73, 101.0), (365, 99.0)]);
+    let day_count = DayCountConvention::Calendar365;
+    let forward = |time: f64| underlying.marked_forward(time, day_count).unwrap();
+    assert!((forward(0.0) - 100.0).abs() < 1e-9);
+    assert!((forward(0.1) - 100.0 * 1.01_f64.powf(0.5)).abs() < 1e-9);
+    assert!((forward(1.0) - 99.0).abs() < 1e-9);
+    // The carry of the last segment continues beyond the last mark
+    let last_carry = (99.0_f64 / 101.0).ln() / 0.8;
+    assert!((forward(1.5) - 99.0 * (0.5 * last_carry).exp()).abs() < 1e-9);
+    assert!(Underlying::new("TEST".to_string(), 100.0, 0.20).marked_forward(1.0, day_count).is_none());
+}
+
+#[test]
+fn test_forward_curve_drives_the_drift() {
+    // Dividends are embedded in the marked forwards and must not be deducted again
+    let underlying = Underlying::new("TEST".to_string(), 100.0, 0.20)
+        .with_dividends(vec![Dividend::Cash { day: 100, amount: 5.0 }])
+        .with_forward_curve(vec![(182, 98.0), (365, 97.0)]);
+    let rate_curve = RateCurve::flat(0.05);
+    let day_count = DayCountConvention::Calendar365;
+    assert!(!underlying.has_cash_dividends());
+    assert!((underlying.prepaid_forward(&rate_curve, 1.0, day_count) - 97.0 * (-0.05_f64).exp()).abs() < 1e-9);
+
+    let correlation = DMatrix::from_row_slice(1, 1, &[1.0]);
+    let barrier = Barrier::single(1.0, BarrierDirection::Down, KnockType::Out, false); // Never hit, forces daily steps
+    let config = SimulationConfig::new(6_000).with_seed(9).with_antithetic(true);
+    let result =
+        price_option_with_config(std::slice::from_ref(&underlying), &correlation, 365, 100.0, OptionType::Call, 0.05, Some(&barrier), &config).unwrap();
+    let analytic = black_scholes_price_with_dividends(&underlying, 100.0, &rate_curve, 1.0, OptionType::Call, day_count)
+        .expect("Marked forwards keep prices lognormal");
+    assert!(
+        (result.price - analytic).abs() < 4.0 * result.std_error,
+        "Price {} should match Black-Scholes {} on the marked forward",
+        result.price,
+        analytic
+    );
+}
+
+#[test]
+#[should_panic(expected = "Forward curve delivery days must be positive and strictly increasing")]
+fn test_forward_curve_rejects_unsorted_days() {
+    Underlying::new("TEST".to_string(), 100.0, 0.20).with_forward_curve(vec![(365, 99.0), (182, 98.0)]);
+}
+
+#[test]
+fn test_futures_price_with_black_76() {
+    // Dividends do not apply to a futures price, which does not drift
+    let future = Underlying::new("FUTURE".to_string(), 80.0, 0.30)
+        .with_dividends(vec![Dividend::Cash { day: 100, amount: 5.0 }])
+        .with_asset_class(AssetClass::Future);
+    let rate_curve = RateCurve::flat(0.05);
+    let day_count = DayCountConvention::Calendar365;
+    assert!(!future.has_cash_dividends());
+    assert_eq!(future.carry_rate(0.05), 0.0);
+
+    let (strike, time) = (85.0, 182.0_f64 / 365.0);
+    let discount_factor = (-0.05 * time).exp();
+    let d1 = ((80.0_f64 / strike).ln() + 0.5 * 0.30 * 0.30 * time) / (0.30 * time.sqrt());
+    let d2 = d1 - 0.30 * time.sqrt();
+    let black_76 = discount_factor * (80.0 * norm_cdf(d1) - strike * norm_cdf(d2));
+    let analytic = black_scholes_price_with_dividends(&future, strike, &rate_curve, time, OptionType::Call, day_count).unwrap();
+    assert!((analytic - black_76).abs() < 1e-10);
+
+    let correlation = DMatrix::from_row_slice(1, 1, &[1.0]);
+    let config = SimulationConfig::new(40_000).with_seed(5).with_control_variate(false);
+    let result =
+        price_option_with_config(std::slice::from_ref(&future), &correlation, 182, strike, OptionType::Call, 0.05, None, &config).unwrap();
+    assert!(
+        (result.price - black_76).abs() < 4.0 * result.std_error,
+        "Price {} should match Black-76 {}",
+        result.price,
+        black_76
+    );
+}
+
+#[test]
+fn test_commodities_grow_at_the_cost_of_carry() {
+    let commodity = Underlying::new("OIL".to_string(), 70.0, 0.35)
+        .with_dividend_yield(0.5) // Ignored for commodities
+        .with_asset_class(AssetClass::Commodity { convenience_yield: 0.08 });
+    let rate_curve = RateCurve::flat(0.05);
+    let day_count = DayCountConvention::Calendar365;
+    assert!((commodity.carry_rate(0.05) + 0.03).abs() < 1e-15);
+    let prepaid_forward = commodity.prepaid_forward(&rate_curve, 1.0, day_count);
+    assert!((prepaid_forward - 70.0 * (-0.08_f64).exp()).abs() < 1e-9);
+
+    let correlation = DMatrix::from_row_slice(1, 1, &[1.0]);
+    let barrier = Barrier::single(1.0, BarrierDirection::Down, KnockType::Out, false); // Never hit, forces daily steps
+    let config = SimulationConfig::new(6_000).with_seed(9).with_antithetic(true);
+    let result =
+        price_option_with_config(std::slice::from_ref(&commodity), &correlation, 365, 70.0, OptionType::Put, 0.05, Some(&barrier), &config).unwrap();
+    let analytic = black_scholes_price_with_dividends(&commodity, 70.0, &rate_curve, 1.0, OptionType::Put, day_count).unwrap();
+    assert!(
+        (result.price - analytic).abs() < 4.0 * result.std_error,
+        "Price {} should match Black-Scholes {} on the cost of carry",
+        result.price,
+        analytic
+    );
+}
+
+#[test]
+fn test_fx_options_price_with_garman_kohlhagen() {
+    // EUR/USD quoted in USD per EUR, priced in USD
+    let fx = Underlying::new("EURUSD".to_string(), 1.10, 0.10).with_asset_class(AssetClass::Fx { foreign_rate: 0.03 });
+    assert!((fx.carry_rate(0.05) - 0.02).abs() < 1e-15);
+
+    let (strike, time) = (1.12, 0.5);
+    let forward = 1.10 * (0.02_f64 * time).exp();
+    let d1 = ((forward / strike).ln() + 0.5 * 0.10 * 0.10 * time) / (0.10 * time.sqrt());
+    let d2 = d1 - 0.10 * time.sqrt();
+    let garman_kohlhagen = (-0.05 * time).exp() * (forward * norm_cdf(d1) - strike * norm_cdf(d2));
+    let analytic = black_scholes_price_with_dividends(&fx, strike, &RateCurve::flat(0.05), time, OptionType::Call, DayCountConvention::Calendar365).unwrap();
+    assert!((analytic - garman_kohlhagen).abs() < 1e-12);
+
+    let correlation = DMatrix::from_row_slice(1, 1, &[1.0]);
+    let config = SimulationConfig::new(40_000).with_seed(3).with_control_variate(false);
+    let result =
+        price_option_with_config(std::slice::from_ref(&fx), &correlation, 182, strike, OptionType::Call, 0.05, None, &config).unwrap();
+    let garman_kohlhagen = black_scholes_price_with_dividends(&fx, strike, &RateCurve::flat(0.05), 182.0 / 365.0, OptionType::Call, DayCountConvention::Calendar365).unwrap();
+    assert!(
+        (result.price - garman_kohlhagen).abs() < 4.0 * result.std_error,
+        "Price {} should match Garman-Kohlhagen {}",
+        result.price,
+        garman_kohlhagen
+    );
+}
+
+#[test]
+fn test_quanto_underlyings_drift_at_the_adjusted_foreign_rate() {
+    // Nikkei paid in USD: grows at the JPY rate, adjusted for its correlation with USD/JPY
+    let nikkei = Underlying::new("NIKKEI".to_string(), 100.0, 0.25)
+        .with_dividend_yield(0.01)
+        .with_quanto(Quanto::new(0.001, 0.12, -0.4));
+    assert!((nikkei.carry_rate(0.05) + 0.009).abs() < 1e-15);
+    assert!((Quanto::new(0.001, 0.12, -0.4).drift_adjustment(0.25) - 0.012).abs() < 1e-15);
+
+    let rate_curve = RateCurve::flat(0.05);
+    let day_count = DayCountConvention::Calendar365;
+    let prepaid_forward = nikkei.prepaid_forward(&rate_curve, 1.0, day_count);
+    let expected = 100.0 * ((0.001 - 0.01 + 0.4 * 0.12 * 0.25) - 0.05_f64).exp();
+    assert!((prepaid_forward - expected).abs() < 1e-10, "{} vs {}", prepaid_forward, expected);
+
+    let correlation = DMatrix::from_row_slice(1, 1, &[1.0]);
+    let config = SimulationConfig::new(20_000).with_seed(11).with_antithetic(true);
+    let analytic = black_scholes_price_with_dividends(&nikkei, 100.0, &rate_curve, 1.0, OptionType::Call, day_count).unwrap();
+    // Without marked forwards, the quanto and the domestic price differ
+    let domestic = Underlying::new("NIKKEI".to_string(), 100.0, 0.25).with_dividend_yield(0.01);
+    let domestic_price = black_scholes_price_with_dividends(&domestic, 100.0, &rate_curve, 1.0, OptionType::Call, day_count).unwrap();
+    assert!(analytic < domestic_price - 1.0);
+    // One step for the vanilla, daily steps for the never hit barrier
+    let barrier = Barrier::single(1.0, BarrierDirection::Down, KnockType::Out, false);
+    for barrier in [None, Some(&barrier)] {
+        let result =
+            price_option_with_config(std::slice::from_ref(&nikkei), &correlation, 365, 100.0, OptionType::Call, 0.05, barrier, &config).unwrap();
+        assert!(
+            (result.price - analytic).abs() < 4.0 * result.std_error,
+            "Price {} should match the quanto Black-Scholes price {}",
+            result.price,
+            analytic
+        );
+    }
+}
+
+#[test]
+fn test_invalid_quanto_terms_are_rejected() {
+    let correlation = DMatrix::from_row_slice(1, 1, &[1.0]);
+    let config = SimulationConfig::new(100);
+    for quanto in [Quanto::new(0.01, -0.1, 0.0), Quanto::new(0.01, 0.1, 1.5)] {
+        let underlying = Underlying::new("QUANTO".to_string(), 100.0, 0.2).with_quanto(quanto);
+        let result = price_option_with_config(&[underlying], &correlation, 30, 100.0, OptionType::Call, 0.05, None, &config);
+        assert!(matches!(result, Err(McError::InvalidQuanto { .. })), "{:?}", result);
+    }
+}
+
+#[test]
+fn test_volatility_events_add_variance_on_their_day() {
+    let underlying = Underlying::new("TEST".to_string(), 100.0, 0.20)
+        .with_volatility_events(vec![VolatilityEvent::new(200, 0.05), VolatilityEvent::new(20, 0.08)]);
+    assert_eq!(underlying.volatility_events[0].day, 20);
+    let day_count = DayCountConvention::Calendar365;
+    let calendar = VarianceTime::Calendar;
+    let base = 0.04 * 30.0 / 365.0;
+    assert!((underlying.integrated_variance(0, 30, day_count, calendar) - base - 0.0064).abs() < 1e-15);
+    // An event on the start day has already moved the price
+    assert!((underlying.integrated_variance(20, 50, day_count, calendar) - base).abs() < 1e-15);
+    assert!((underlying.integrated_variance(19, 20, day_count, calendar) - 0.04 / 365.0 - 0.0064).abs() < 1e-15);
+}
+
+#[test]
+fn test_earnings_before_expiry_raise_vanilla_prices() {
+    let correlation = DMatrix::from_row_slice(1, 1, &[1.0]);
+    let config = SimulationConfig::new(20_000).with_seed(12).with_antithetic(true);
+    let price = |events: Vec<VolatilityEvent>| {
+        let underlying = Underlying::new("TEST".to_string(), 100.0, 0.20).with_volatility_events(events);
+        price_option_with_config(&[underlying], &correlation, 30, 100.0, OptionType::Call, 0.05, None, &config).unwrap()
+    };
+
+    let result = price(vec![VolatilityEvent::new(10, 0.06)]);
+    let volatility = ((0.04 * 30.0 / 365.0 + 0.0036) / (30.0 / 365.0_f64)).sqrt();
+    let analytic = black_scholes_price(100.0, 100.0, volatility, 0.05, 30.0 / 365.0, OptionType::Call);
+    assert!(
+        (result.price - analytic).abs() < 4.0 * result.std_error,
+        "Price {} should match Black-Scholes {} including the event variance",
+        result.price,
+        analytic
+    );
+    assert!(result.price > price(Vec::new()).price + 1.0);
+    // Events after expiry do not move the option
+    assert_eq!(price(vec![VolatilityEvent::new(45, 0.06)]).price, price(Vec::new()).price);
+
+    let underlying = Underlying::new("TEST".to_string(), 100.0, 0.20).with_volatility_events(vec![VolatilityEvent::new(10, -0.06)]);
+    let result = price_option_with_config(&[underlying], &correlation, 30, 100.0, OptionType::Call, 0.05, None, &config);
+    assert!(matches!(result, Err(McError::InvalidVolatility { .. })), "{:?}", result);
+}
@@ -0,0 +1,105 @@
+use mcproton::{
+    price_option_with_config, price_payoff, Averaging, Barrier, BarrierDirection, FixingSchedule,
+    KnockType, OptionType, Payoff, PricingResult, PricingWarning, SimulationConfig, Underlying,
+};
+use nalgebra::DMatrix;
+
+fn single_underlying() -> (Vec<Underlying>, DMatrix<f64>) {
+    (
+        vec![Underlying::new("TEST".to_string(), 100.0, 0.20)],
+        DMatrix::from_row_slice(1, 1, &[1.0]),
+    )
+}
+
+/// Warnings raised by the sanity checks, ignoring the path-count dependent standard error
+fn check_warnings(result: &PricingResult) -> Vec<&PricingWarning> {
+    result
+        .warnings
+        .iter()
+        .filter(|w| !matches!(w, PricingWarning::HighStandardError { .. }))
+        .collect()
+}
+
+#[test]
+fn test_validation_passes_for_vanilla_options() {
+    let (underlyings, correlation) = single_underlying();
+    let config = SimulationConfig::new(5_000).with_validation(true);
+    for option_type in [OptionType::Call, OptionType::Put] {
+        let result = price_option_with_config(&underlyings, &correlation, 60, 100.0, option_type, 0.05, None, &config).unwrap();
+        assert!(check_warnings(&result).is_empty(), "Unexpected warnings: {:?}", result.warnings);
+    }
+}
+
+#[test]
+fn test_validation_passes_with_control_variate() {
+    // The control variate makes vanilla prices exact, parity must still hold
+    let (underlyings, correlation) = single_underlying();
+    let config = SimulationConfig::new(1_000).with_control_variate(true).with_validation(true);
+    let result = price_option_with_config(&underlyings, &correlation, 30, 95.0, OptionType::Put, 0.05, None, &config).unwrap();
+    assert!(check_warnings(&result).is_empty(), "Unexpected warnings: {:?}", result.warnings);
+}
+
+#[test]
+fn test_validation_passes_for_barrier_and_asian_options() {
+    let (underlyings, correlation) = single_underlying();
+    let config = SimulationConfig::new(2_000).with_seed(17).with_validation(true);
+    let barrier = Barrier::single(0.9, BarrierDirection::Down, KnockType::In, true); // relative
+    let knock_in = price_option_with_config(&underlyings, &correlation, 30, 100.0, OptionType::Put, 0.05, Some(&barrier), &config).unwrap();
+    assert!(check_warnings(&knock_in).is_empty(), "Unexpected warnings: {:?}", knock_in.warnings);
+
+    let asian = Payoff::AveragePrice {
+        strike_price: 100.0,
+        option_type: OptionType::Call,
+        averaging: Averaging::Arithmetic,
+        schedule: FixingSchedule::Daily,
+    };
+    let result = price_payoff(&underlyings, &correlation, 30, &asian, 0.05, None, &config).unwrap();
+    assert!(check_warnings(&result).is_empty(), "Unexpected warnings: {:?}", result.warnings);
+}
+
+#[test]
+fn test_validation_does_not_change_seeded_price() {
+    let (underlyings, correlation) = single_underlying();
+    let config = SimulationConfig::new(2_000).with_seed(23);
+    let plain = price_option_with_config(&underlyings, &correlation, 30, 100.0, OptionType::Call, 0.05, None, &config).unwrap();
+    let validated = price_option_with_config(
+        &underlyings, &correlation, 30, 100.0, OptionType::Call, 0.05, None, &config.clone().with_validation(true),
+    ).unwrap();
+    assert_eq!(plain.price, validated.price);
+}
+
+#[test]
+fn test_warning_display() {
+    let warning = PricingWarning::OutsideNoArbitrageBounds { lower: 1.0, upper: 2.0 };
+    assert_eq!(
+        warning.to_string(),
+        "Price lies outside of the no-arbitrage bounds [1.000000, 2.000000]"
+    );
+}
+
+#[test]
+fn test_high_standard_error_is_flagged() {
+    let (underlyings, correlation) = single_underlying();
+    let config = SimulationConfig::new(100).with_seed(5);
+    let result = price_option_with_config(&underlyings, &correlation, 30, 100.0, OptionType::Call, 0.05, None, &config).unwrap();
+    assert!(
+        result.warnings.iter().any(|w| matches!(w, PricingWarning::HighStandardError { .. })),
+        "Expected a standard error warning, got {:?}",
+        result.warnings
+    );
+}
+
+#[test]
+fn test_barrier_near_spot_is_flagged() {
+    let (underlyings, correlation) = single_underlying();
+    let config = SimulationConfig::new(500).with_seed(6);
+    let is_flagged = |level: f64| {
+        let barrier = Barrier::single(level, BarrierDirection::Down, KnockType::Out, true); // relative
+        price_option_with_config(&underlyings, &correlation, 30, 100.0, OptionType::Call, 0.05, Some(&barrier), &config).unwrap()
+            .warnings
+            .iter()
+            .any(|w| matches!(w, PricingWarning::BarrierNearSpot { .. }))
+    };
+    assert!(is_flagged(0.99), "Barrier 1% below the spot should be flagged");
+    assert!(!is_flagged(0.80), "Barrier 20% below the spot should not be flagged");
+}
@@ -0,0 +1,98 @@
+use mcproton::closed_form::black_scholes_price;
+use mcproton::{
+    price_option_with_config, Barrier, BarrierDirection, KnockType, OptionType, SimulationConfig,
+    Underlying,
+};
+use nalgebra::DMatrix;
+
+fn single_underlying() -> (Vec<Underlying>, DMatrix<f64>) {
+    (
+        vec![Underlying::new("TEST".to_string(), 100.0, 0.20)],
+        DMatrix::from_row_slice(1, 1, &[1.0]),
+    )
+}
+
+#[test]
+fn test_plain_config_reports_standard_error() {
+    let (underlyings, correlation) = single_underlying();
+    let config = SimulationConfig::new(20_000);
+    let result = price_option_with_config(&underlyings, &correlation, 30, 100.0, OptionType::Call, 0.05, None, &config).unwrap();
+    let analytic = black_scholes_price(100.0, 100.0, 0.20, 0.05, 30.0 / 365.0, OptionType::Call);
+    assert_eq!(result.num_paths, 20_000);
+    assert!(result.std_error > 0.0, "Standard error should be positive");
+    assert!(
+        (result.price - analytic).abs() < 4.0 * result.std_error,
+        "MC price {} should be within 4 standard errors of {}",
+        result.price,
+        analytic
+    );
+}
+
+#[test]
+fn test_antithetic_reduces_standard_error() {
+    let (underlyings, correlation) = single_underlying();
+    let plain = SimulationConfig::new(20_000);
+    let antithetic = SimulationConfig::new(20_000).with_antithetic(true);
+    let plain_result = price_option_with_config(&underlyings, &correlation, 30, 90.0, OptionType::Call, 0.05, None, &plain).unwrap();
+    let antithetic_result =
+        price_option_with_config(&underlyings, &correlation, 30, 90.0, OptionType::Call, 0.05, None, &antithetic).unwrap();
+    assert_eq!(antithetic_result.num_paths, 20_000);
+    assert!(
+        antithetic_result.std_error < plain_result.std_error,
+        "Antithetic std error {} should be below plain std error {}",
+        antithetic_result.std_error,
+        plain_result.std_error
+    );
+}
+
+#[test]
+fn test_control_variate_recovers_vanilla_price() {
+    // For a vanilla option the control is the payoff itself, so the estimate is exact
+    let (underlyings, correlation) = single_underlying();
+    let config = SimulationConfig::new(1_000).with_control_variate(true);
+    let result = price_option_with_config(&underlyings, &correlation, 30, 105.0, OptionType::Put, 0.05, None, &config).unwrap();
+    let analytic = black_scholes_price(100.0, 105.0, 0.20, 0.05, 30.0 / 365.0, OptionType::Put);
+    assert!((result.price - analytic).abs() < 1e-9);
+    assert!(result.std_error < 1e-9);
+}
+
+#[test]
+fn test_control_variate_reduces_barrier_standard_error() {
+    let (underlyings, correlation) = single_underlying();
+    let barrier = Barrier::single(130.0, BarrierDirection::Up, KnockType::Out, false); // absolute
+    let plain = SimulationConfig::new(5_000);
+    let controlled = SimulationConfig::new(5_000).with_control_variate(true);
+    let plain_result =
+        price_option_with_config(&underlyings, &correlation, 30, 100.0, OptionType::Call, 0.05, Some(&barrier), &plain).unwrap();
+    let controlled_result =
+        price_option_with_config(&underlyings, &correlation, 30, 100.0, OptionType::Call, 0.05, Some(&barrier), &controlled).unwrap();
+    assert!(
+        controlled_result.std_error < 0.5 * plain_result.std_error,
+        "Control variate std error {} should be well below plain std error {}",
+        controlled_result.std_error,
+        plain_result.std_error
+    );
+}
+
+#[test]
+fn test_statistics_streamed_across_chunks() {
+    // More samples than one accumulation chunk, with and without the control variate
+    let (underlyings, correlation) = single_underlying();
+    let analytic = black_scholes_price(100.0, 100.0, 0.20, 0.05, 30.0 / 365.0, OptionType::Call);
+    for control_variate in [false, true] {
+        let config = SimulationConfig::new(150_000).with_seed(12).with_control_variate(control_variate);
+        let result = price_option_with_config(&underlyings, &correlation, 30, 100.0, OptionType::Call, 0.05, None, &config).unwrap();
+        assert_eq!(result.num_paths, 150_000);
+        assert!(
+            (result.price - analytic).abs() <= 4.0 * result.std_error + 1e-9,
+            "Price {} should match Black-Scholes {}",
+            result.price,
+            analytic
+        );
+        if !control_variate {
+            // The vanilla payoff has a standard deviation of roughly 1.3 times its price
+            let expected_error = 1.3 * analytic / (150_000.0_f64).sqrt();
+            assert!((result.std_error / expected_error - 1.0).abs() < 0.2, "Standard error {}", result.std_error);
+        }
+    }
+}
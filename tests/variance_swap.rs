@@ -0,0 +1,95 @@
+use mcproton::test_utils::{assert_within_std_errors, deterministic_config, single_stock};
+use mcproton::{
+    fair_swap_strikes, FixingSchedule, McError, PricingSession, Product, VolatilityEvent,
+};
+
+const DAYS: u32 = 90;
+
+#[test]
+fn test_fair_variance_strike_is_the_black_scholes_variance() {
+    let strikes = fair_swap_strikes(
+        &single_stock(),
+        DAYS,
+        365.0,
+        FixingSchedule::Daily,
+        &deterministic_config(5_000),
+    )
+    .unwrap();
+    // Daily log returns have variance σ² dt and a drift of (r - σ²/2) dt
+    let expected = (0.04_f64 + 0.03 * 0.03 / 365.0).sqrt();
+    assert!(
+        (strikes.variance_strike - expected).abs() < 4.0 * strikes.variance_std_error,
+        "{:?}",
+        strikes
+    );
+    // Jensen: the expected volatility lies below the root of the expected variance
+    assert!(strikes.volatility_strike < strikes.variance_strike);
+    assert!(strikes.volatility_strike > 0.19);
+}
+
+#[test]
+fn test_swaps_at_the_fair_strikes_are_worth_nothing() {
+    let config = deterministic_config(2_000);
+    let market = single_stock();
+    let strikes = fair_swap_strikes(&market, DAYS, 365.0, FixingSchedule::Daily, &config).unwrap();
+    let session = PricingSession::new(market, DAYS, &config).unwrap();
+    let variance_swap = session
+        .price(&Product::variance_swap(
+            1_000.0,
+            strikes.variance_strike,
+            365.0,
+            FixingSchedule::Daily,
+        ))
+        .unwrap();
+    assert!(variance_swap.price.abs() < 1e-9, "{:?}", variance_swap);
+    assert!(variance_swap.bounds.unwrap().lower < 0.0);
+    let volatility_swap = session
+        .price(&Product::volatility_swap(
+            1_000.0,
+            strikes.volatility_strike,
+            365.0,
+            FixingSchedule::Daily,
+        ))
+        .unwrap();
+    assert!(volatility_swap.price.abs() < 1e-9, "{:?}", volatility_swap);
+}
+
+#[test]
+fn test_realized_variance_includes_earnings_moves() {
+    let config = deterministic_config(5_000);
+    let mut market = single_stock();
+    market.underlyings[0] = market.underlyings[0]
+        .clone()
+        .with_volatility_events(vec![VolatilityEvent::new(45, 0.10)]);
+    // Sampled monthly, the three returns carry the base variance and the event
+    let session = PricingSession::new(market, DAYS, &config).unwrap();
+    let result = session
+        .price(&Product::variance_swap(
+            1.0,
+            0.0,
+            365.0 / 30.0,
+            FixingSchedule::Monthly,
+        ))
+        .unwrap();
+    let discount_factor = (-0.05 * DAYS as f64 / 365.0).exp();
+    let drift = (0.05 - 0.02) * 30.0 / 365.0;
+    let expected = (0.04 + (0.01 + 3.0 * drift * drift) * 365.0 / 30.0 / 3.0) * discount_factor;
+    assert_within_std_errors(&result, expected, 4.0);
+}
+
+#[test]
+fn test_invalid_swap_terms_are_rejected() {
+    let session = PricingSession::new(single_stock(), DAYS, &deterministic_config(100)).unwrap();
+    for product in [
+        Product::variance_swap(1.0, 0.2, 0.0, FixingSchedule::Daily),
+        Product::volatility_swap(-1.0, 0.2, 365.0, FixingSchedule::Daily),
+        Product::volatility_swap(1.0, f64::NAN, 365.0, FixingSchedule::Daily),
+    ] {
+        let result = session.price(&product);
+        assert!(
+            matches!(result, Err(McError::InvalidProduct(_))),
+            "{:?}",
+            result
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use mcproton::closed_form::{black_scholes_barrier_price, black_scholes_price};
+use mcproton::test_utils::{
+    assert_within_std_errors, deterministic_config, single_stock, two_asset_basket,
+};
+use mcproton::{
+    price_payoff, price_vectorized, Averaging, Barrier, BarrierCorrection, BarrierDirection,
+    BarrierType, FixingSchedule, Heston, HestonParameters, KnockType, McError, OptionType, Payoff,
+    PricingResult,
+};
+
+const DAYS: u32 = 90;
+
+fn call(strike_price: f64) -> Payoff {
+    Payoff::Vanilla {
+        strike_price,
+        option_type: OptionType::Call,
+    }
+}
+
+fn price(payoff: &Payoff, barrier: Option<&Barrier>, num_paths: u64) -> PricingResult {
+    let market = single_stock();
+    price_vectorized(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        payoff,
+        market.risk_free_rate,
+        barrier,
+        &deterministic_config(num_paths).with_barrier_correction(BarrierCorrection::BrownianBridge),
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_vanilla_call_matches_black_scholes() {
+    let result = price(&call(105.0), None, 20_000);
+    let expected = black_scholes_price(
+        100.0,
+        105.0,
+        0.2,
+        0.05,
+        DAYS as f64 / 365.0,
+        OptionType::Call,
+    );
+    assert_within_std_errors(&result, expected, 4.0);
+    assert_eq!(result.num_paths, 20_000);
+}
+
+#[test]
+fn test_bridged_knock_out_matches_continuous_monitoring() {
+    let barrier = Barrier::single(90.0, BarrierDirection::Down, KnockType::Out, false);
+    let result = price(&call(100.0), Some(&barrier), 20_000);
+    let time = DAYS as f64 / 365.0;
+    let expected =
+        black_scholes_barrier_price(100.0, 100.0, &barrier, 0.2, 0.05, time, OptionType::Call)
+            .unwrap();
+    assert_within_std_errors(&result, expected, 4.0);
+}
+
+#[test]
+fn test_path_dependent_prices_agree_with_the_path_engine() {
+    let market = single_stock();
+    let config = deterministic_config(10_000);
+    let payoffs = [
+        Payoff::AveragePrice {
+            strike_price: 100.0,
+            option_type: OptionType::Call,
+            averaging: Averaging::Arithmetic,
+            schedule: FixingSchedule::Monthly,
+        },
+        Payoff::FixedLookback {
+            strike_price: 100.0,
+            option_type: OptionType::Put,
+        },
+    ];
+    for payoff in &payoffs {
+        let vectorized = price(payoff, None, 10_000);
+        let reference = price_payoff(
+            &market.underlyings,
+            &market.correlation_matrix,
+            DAYS,
+            payoff,
+            market.risk_free_rate.clone(),
+            None,
+            &config,
+        )
+        .unwrap();
+        let std_error = vectorized.std_error.hypot(reference.std_error);
+        assert!(
+            (vectorized.price - reference.price).abs() < 4.0 * std_error,
+            "{:?}: vectorized {} and path engine {} differ",
+            payoff,
+            vectorized.price,
+            reference.price
+        );
+    }
+}
+
+#[test]
+fn test_unsupported_models_and_barriers_are_rejected() {
+    let market = two_asset_basket();
+    let heston = Heston::new(vec![
+        HestonParameters {
+            initial_variance: 0.04,
+            mean_reversion: 2.0,
+            long_term_variance: 0.04,
+            vol_of_vol: 0.5,
+            correlation: -0.5,
+        };
+        2
+    ]);
+    let result = price_vectorized(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        &call(100.0),
+        0.05,
+        None,
+        &deterministic_config(100).with_model(heston),
+    );
+    assert!(matches!(result, Err(McError::InvalidModel(_))));
+
+    let barrier = Barrier::multi(
+        80.0,
+        BarrierDirection::Down,
+        KnockType::Out,
+        BarrierType::WorstOf,
+        false,
+        vec![1],
+    )
+    .unwrap();
+    let result = price_vectorized(
+        &market.underlyings,
+        &market.correlation_matrix,
+        DAYS,
+        &call(100.0),
+        0.05,
+        Some(&barrier),
+        &deterministic_config(100),
+    );
+    assert!(matches!(result, Err(McError::InvalidBarrier(_))));
+}
@@ -0,0 +1,109 @@
+use mcproton::{ArbitrageKind, ImpliedVolSurface, LocalVolSurface};
+
+fn log_moneyness() -> Vec<f64> {
+    (-4..=4).map(|i| i as f64 * 0.1).collect()
+}
+
+/// Surface with a skew of the same total variance shape at every expiry
+fn skewed_surface() -> ImpliedVolSurface {
+    let expiries = vec![0.25, 0.5, 1.0, 2.0];
+    let volatilities = expiries
+        .iter()
+        .map(|_| log_moneyness().iter().map(|k| 0.2 - 0.1 * k).collect())
+        .collect();
+    ImpliedVolSurface::new(log_moneyness(), expiries, volatilities)
+}
+
+#[test]
+fn test_arbitrage_free_surface_is_left_unchanged() {
+    let surface = skewed_surface();
+    assert!(surface.arbitrage_violations().is_empty());
+    let (repaired, adjustments) = surface.repaired();
+    assert_eq!(repaired, surface);
+    assert!(adjustments.is_empty());
+
+    assert!((surface.implied_volatility(0.0, 0.75) - 0.2).abs() < 1e-12);
+    let variance = 0.5 * (0.21f64.powi(2) + 0.2f64.powi(2));
+    assert!((surface.implied_volatility(-0.05, 1.0) - variance.sqrt()).abs() < 1e-12);
+    assert!((surface.implied_volatility(1.0, 5.0) - 0.16).abs() < 1e-12);
+}
+
+#[test]
+fn test_calendar_arbitrage_is_detected_and_repaired() {
+    let mut volatilities = skewed_surface().volatilities().to_vec();
+    // Total variance at the money falls from 0.02 to 0.0144 at the third expiry
+    volatilities[2][4] = 0.12;
+    let surface = ImpliedVolSurface::new(log_moneyness(), vec![0.25, 0.5, 1.0, 2.0], volatilities);
+    let violations = surface.arbitrage_violations();
+    assert!(violations
+        .iter()
+        .any(|violation| violation.kind == ArbitrageKind::Calendar
+            && violation.expiry_index == 2
+            && violation.strike_index == 4));
+
+    let (repaired, adjustments) = surface.repaired();
+    assert!(repaired.arbitrage_violations().is_empty());
+    let adjustment = adjustments
+        .iter()
+        .find(|adjustment| adjustment.expiry_index == 2 && adjustment.strike_index == 4)
+        .unwrap();
+    assert_eq!(adjustment.original, 0.12);
+    assert!(adjustment.repaired >= 0.2 / 2f64.sqrt() - 1e-9);
+    assert!(adjustment.repaired < 0.2);
+}
+
+#[test]
+fn test_butterfly_arbitrage_is_detected_and_repaired() {
+    let mut volatilities = skewed_surface().volatilities().to_vec();
+    volatilities[1][5] = 0.45;
+    let surface = ImpliedVolSurface::new(log_moneyness(), vec![0.25, 0.5, 1.0, 2.0], volatilities);
+    let violations = surface.arbitrage_violations();
+    assert!(violations
+        .iter()
+        .any(|violation| violation.kind == ArbitrageKind::Butterfly
+            && violation.expiry_index == 1
+            && violation.amount > 0.0));
+
+    let (repaired, adjustments) = surface.repaired();
+    assert!(repaired.arbitrage_violations().is_empty());
+    assert!(!adjustments.is_empty());
+    // The spike is lowered, the grid points far from it keep their volatilities
+    let spike = adjustments
+        .iter()
+        .find(|adjustment| adjustment.expiry_index == 1 && adjustment.strike_index == 5)
+        .unwrap();
+    assert!(spike.repaired < spike.original);
+    assert_eq!(repaired.volatilities()[1][0], surface.volatilities()[1][0]);
+    assert_eq!(repaired.volatilities()[0], surface.volatilities()[0]);
+}
+
+#[test]
+fn test_local_volatilities_of_an_implied_surface() {
+    let flat = ImpliedVolSurface::new(log_moneyness(), vec![0.5, 1.0, 2.0], vec![vec![0.25; 9]; 3]);
+    let local = LocalVolSurface::from_implied_surface(&flat, 100.0);
+    for &spot in &[70.0, 100.0, 140.0] {
+        for &time in &[0.5, 1.0, 2.0] {
+            assert!((local.volatility(spot, time) - 0.25).abs() < 1e-12);
+        }
+    }
+
+    let mut volatilities = skewed_surface().volatilities().to_vec();
+    volatilities[1][5] = 0.45;
+    volatilities[2][4] = 0.12;
+    // The local volatilities of the repaired grid are finite and positive
+    let (repaired, _) =
+        ImpliedVolSurface::new(log_moneyness(), vec![0.25, 0.5, 1.0, 2.0], volatilities).repaired();
+    let local = LocalVolSurface::from_implied_surface(&repaired, 100.0);
+    for spot in (60..=160).step_by(5) {
+        for &time in &[0.25, 0.5, 1.0, 2.0] {
+            let volatility = local.volatility(spot as f64, time);
+            assert!(volatility.is_finite() && volatility > 0.0);
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "expiries must be positive")]
+fn test_surface_needs_positive_expiries() {
+    ImpliedVolSurface::new(vec![0.0], vec![0.0], vec![vec![0.2]]);
+}